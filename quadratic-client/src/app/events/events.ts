@@ -4,6 +4,7 @@ import { CursorMode } from '@/app/gridGL/HTMLGrid/inlineEditor/inlineEditorKeybo
 import { CodeCell } from '@/app/gridGL/types/codeCell';
 import { SheetPosTS } from '@/app/gridGL/types/size';
 import {
+  A1Selection,
   JsBordersSheet,
   JsCodeCell,
   JsHtmlOutput,
@@ -15,6 +16,7 @@ import {
   JsValidationWarning,
   SheetBounds,
   SheetInfo,
+  Table,
   Validation,
 } from '@/app/quadratic-core-types';
 import type { CodeRun } from '@/app/web-workers/CodeRun';
@@ -69,6 +71,7 @@ interface EventTypes {
   javascriptInit: (version: string) => void;
   javascriptState: (state: LanguageState, current?: CodeRun, awaitingExecution?: CodeRun[]) => void;
   connectionState: (state: LanguageState, current?: CodeRun, awaitingExecution?: CodeRun[]) => void;
+  restartLanguageWorker: (language: 'Python' | 'Javascript') => void;
 
   updateCodeCell: (options: {
     sheetId: string;
@@ -89,6 +92,8 @@ interface EventTypes {
   multiplayerState: (state: MultiplayerState) => void;
   multiplayerCellEdit: (cellEdit: CellEdit, player: MultiplayerUser) => void;
   multiplayerFollow: () => void;
+  multiplayerPresenter: (presenter?: string) => void;
+  multiplayerLocks: (locks: Map<string, A1Selection>) => void;
   multiplayerCodeRunning: (multiplayerUser: MultiplayerUser) => void;
   multiplayerSynced: () => void;
 
@@ -107,6 +112,7 @@ interface EventTypes {
   insertCodeEditorText: (text: string) => void;
 
   sheetValidations: (sheetId: string, validations: Validation[]) => void;
+  sheetTables: (sheetId: string, tables: Table[]) => void;
   renderValidationWarnings: (
     sheetId: string,
     hashX: number | undefined,
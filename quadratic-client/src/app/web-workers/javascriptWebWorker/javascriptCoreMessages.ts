@@ -1,4 +1,4 @@
-import { JsCodeResult, JsGetCellResponse } from '@/app/quadratic-core-types';
+import { JsCodeResult } from '@/app/quadratic-core-types';
 
 export interface CoreJavascriptRun {
   type: 'coreJavascriptRun';
@@ -13,18 +13,17 @@ export interface JavascriptCoreResults {
   type: 'javascriptCoreResults';
   transactionId: string;
   results: JsCodeResult;
+
+  // the results' output_array as an Arrow IPC stream (see outputArrayToArrow)
+  output?: Uint8Array;
 }
 
 export interface CoreJavascriptGetCellsA1 {
   type: 'coreJavascriptGetCellsA1';
   id: number;
-  cells?: JsGetCellResponse[];
-  // returned by GetCellsA1
-  x?: number;
-  y?: number;
-  w?: number;
-  h?: number;
-  two_dimensional?: boolean;
+
+  // the cells as an Arrow IPC stream (see arrowToCellsA1)
+  arrow?: Uint8Array;
 }
 
 export interface JavascriptCoreGetCellsA1 {
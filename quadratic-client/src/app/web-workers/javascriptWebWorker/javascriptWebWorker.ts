@@ -10,6 +10,10 @@ class JavascriptWebWorker {
 
   private worker?: Worker;
 
+  constructor() {
+    events.on('restartLanguageWorker', this.handleRestartLanguageWorker);
+  }
+
   private send(message: ClientJavascriptMessage, port?: MessagePort) {
     if (!this.worker) throw new Error('Expected worker to be defined in javascript.ts');
     if (port) {
@@ -55,13 +59,23 @@ class JavascriptWebWorker {
 
   cancelExecution = () => {
     mixpanel.track('[JavascriptWebWorker].restartFromUser');
+    this.restart();
+    quadraticCore.sendCancelExecution('Javascript');
+  };
 
+  // core already timed out the code run, so it doesn't need to be cancelled
+  private handleRestartLanguageWorker = (language: 'Python' | 'Javascript') => {
+    if (language !== 'Javascript' || !this.worker) return;
+    mixpanel.track('[JavascriptWebWorker].restartFromTimeout');
+    this.restart();
+  };
+
+  private restart() {
     if (!this.worker) throw new Error('Expected worker to be defined in Javascript.ts');
     this.worker.terminate();
-    quadraticCore.sendCancelExecution('Javascript');
     this.init();
     events.emit('javascriptState', 'ready');
-  };
+  }
 }
 
 export const javascriptWebWorker = new JavascriptWebWorker();
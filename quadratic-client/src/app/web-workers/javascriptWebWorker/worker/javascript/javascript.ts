@@ -6,6 +6,7 @@ import type { LanguageState } from '@/app/web-workers/languageTypes';
 import * as esbuild from 'esbuild-wasm';
 import { CoreJavascriptRun } from '../../javascriptCoreMessages';
 import { javascriptClient } from '../javascriptClient';
+import { javascriptCore } from '../javascriptCore';
 import { JavascriptAPI } from './javascriptAPI';
 import { javascriptFindSyntaxError, prepareJavascriptCode, transformCode } from './javascriptCompile';
 import { javascriptErrorResult, javascriptResults } from './javascriptResults';
@@ -41,6 +42,7 @@ export class Javascript {
       // think this is necessary but it's an option.
       worker: false,
     });
+    await javascriptCore.loadRustClient();
 
     this.state = 'ready';
     this.next();
@@ -1,5 +1,6 @@
 import { debugWebWorkers, debugWebWorkersMessages } from '@/app/debugFlags';
-import { JsCodeResult, JsGetCellResponse } from '@/app/quadratic-core-types';
+import { CellA1Response, JsCodeResult, JsGetCellResponse } from '@/app/quadratic-core-types';
+import init, { arrowToCellsA1, outputArrayToArrow } from '@/app/quadratic-rust-client/quadratic_rust_client';
 import {
   CoreJavascriptGetCellsA1,
   CoreJavascriptMessage,
@@ -19,6 +20,11 @@ class JavascriptCore {
     if (debugWebWorkers) console.log('[javascriptCore] initialized');
   }
 
+  // the rust client decodes and encodes the Arrow IPC streams sent to and from core
+  async loadRustClient() {
+    await init();
+  }
+
   private send(message: JavascriptCoreMessage, transfer?: Transferable) {
     if (!this.coreMessagePort) throw new Error('coreMessagePort not initialized');
     if (transfer) {
@@ -52,13 +58,25 @@ class JavascriptCore {
   };
 
   sendJavascriptResults(transactionId: string, results: JsCodeResult, transfer?: Transferable) {
+    let output: Uint8Array | undefined;
+    if (results.output_array) {
+      try {
+        output = outputArrayToArrow(JSON.stringify(results.output_array));
+      } catch (e) {
+        console.warn('[javascriptCore] outputArrayToArrow error', e);
+      }
+      if (output) {
+        results = { ...results, output_array: null };
+      }
+    }
     this.send(
       {
         type: 'javascriptCoreResults',
         transactionId,
         results,
+        output,
       },
-      transfer
+      transfer ?? output?.buffer
     );
   }
 
@@ -72,22 +90,22 @@ class JavascriptCore {
     return new Promise((resolve) => {
       const id = this.id++;
       this.waitingForResponse[id] = (message: CoreJavascriptGetCellsA1) => {
-        if (
-          message.cells !== undefined &&
-          message.x !== undefined &&
-          message.y !== undefined &&
-          message.w !== undefined &&
-          message.h !== undefined
-        ) {
+        if (!message.arrow) {
+          resolve(undefined);
+          return;
+        }
+        try {
+          const cells = JSON.parse(arrowToCellsA1(message.arrow)) as CellA1Response;
           resolve({
-            cells: message.cells,
-            x: message.x,
-            y: message.y,
-            w: message.w,
-            h: message.h,
-            two_dimensional: message.two_dimensional ?? false,
+            cells: cells.cells,
+            x: Number(cells.x),
+            y: Number(cells.y),
+            w: Number(cells.w),
+            h: Number(cells.h),
+            two_dimensional: cells.two_dimensional,
           });
-        } else {
+        } catch (e) {
+          console.warn('[javascriptCore] getCellsA1 error', e);
           resolve(undefined);
         }
       };
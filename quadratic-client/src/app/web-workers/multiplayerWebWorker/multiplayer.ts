@@ -6,6 +6,7 @@ import { MULTIPLAYER_COLORS, MULTIPLAYER_COLORS_TINT } from '@/app/gridGL/HTMLGr
 import { pixiApp } from '@/app/gridGL/pixiApp/PixiApp';
 import { pixiAppSettings } from '@/app/gridGL/pixiApp/PixiAppSettings';
 import { SheetPosTS } from '@/app/gridGL/types/size';
+import { A1Selection } from '@/app/quadratic-core-types';
 import { JsSelection } from '@/app/quadratic-rust-client/quadratic_rust_client';
 import type { CodeRun } from '@/app/web-workers/CodeRun';
 import { LanguageState } from '@/app/web-workers/languageTypes';
@@ -18,6 +19,7 @@ import { quadraticCore } from '../quadraticCore/quadraticCore';
 import {
   ClientMultiplayerMessage,
   MultiplayerClientMessage,
+  MultiplayerClientPresentation,
   MultiplayerClientUserUpdate,
   MultiplayerState,
 } from './multiplayerClientMessages';
@@ -45,6 +47,12 @@ export class Multiplayer {
   // users currently logged in to the room
   users: Map<string, MultiplayerUser> = new Map();
 
+  // session_id of the user presenting to the room, if a presentation is active
+  presenter?: string;
+
+  // ranges locked by users in the room, by session_id
+  locks: Map<string, A1Selection> = new Map();
+
   constructor() {
     this.sessionId = uuid();
 
@@ -118,6 +126,14 @@ export class Multiplayer {
         this.receiveUsersInRoom(e.data.room);
         break;
 
+      case 'multiplayerClientPresentation':
+        this.receivePresentation(e.data);
+        break;
+
+      case 'multiplayerClientRangeLocked':
+        this.receiveRangeLocked(e.data.sessionId);
+        break;
+
       case 'multiplayerClientReload':
         events.emit('needRefresh', 'force');
         break;
@@ -279,6 +295,23 @@ export class Multiplayer {
     this.send({ type: 'clientMultiplayerFollow', follow });
   }
 
+  startPresenting() {
+    this.send({ type: 'clientMultiplayerStartPresenting' });
+  }
+
+  stopPresenting() {
+    this.send({ type: 'clientMultiplayerStopPresenting' });
+  }
+
+  // prevents other users from changing the cells in the selection
+  claimLock(selection: A1Selection) {
+    this.send({ type: 'clientMultiplayerLock', lock: { type: 'Claim', selection } });
+  }
+
+  releaseLock() {
+    this.send({ type: 'clientMultiplayerLock', lock: { type: 'Release' } });
+  }
+
   private clearAllUsers() {
     if (debugShowMultiplayer) console.log('[Multiplayer] Clearing all users.');
     this.users.clear();
@@ -361,6 +394,41 @@ export class Multiplayer {
       player.follow = update.follow;
       events.emit('multiplayerFollow');
     }
+
+    if (update.lock) {
+      if (update.lock.type === 'Claim') {
+        this.locks.set(player.session_id, update.lock.selection);
+      } else {
+        this.locks.delete(player.session_id);
+      }
+      events.emit('multiplayerLocks', this.locks);
+    }
+  }
+
+  // the server rejected our change because another user locked the range
+  private receiveRangeLocked(sessionId: string) {
+    const player = this.users.get(sessionId);
+    const name = player ? displayName(player, false) : 'Another user';
+    pixiAppSettings.snackbar(`${name} has locked those cells, so your change was undone.`, { severity: 'warning' });
+  }
+
+  // everyone else in the room follows the presenter's sheet and viewport
+  private receivePresentation(presentation: MultiplayerClientPresentation) {
+    if (presentation.sessionId !== this.presenter) return;
+    this.receiveUserUpdate({
+      type: 'multiplayerClientUserUpdate',
+      sessionId: presentation.sessionId,
+      fileId: presentation.fileId,
+      userUpdate: presentation.update,
+    });
+
+    const { sheet_id, viewport } = presentation.update;
+    if (sheet_id && sheet_id !== sheets.current && sheets.getById(sheet_id)) {
+      sheets.current = sheet_id;
+    }
+    if (viewport && pixiAppSettings.editorInteractionState.follow !== presentation.sessionId) {
+      pixiApp.viewport.loadMultiplayerViewport(JSON.parse(viewport));
+    }
   }
 
   // updates the React hook to populate the Avatar list
@@ -419,6 +487,13 @@ export class Multiplayer {
       if (debugShowMultiplayer) console.log(`[Multiplayer] Player ${this.users.get(sessionId)?.first_name} left room.`);
       this.users.delete(sessionId);
     });
+    const presenter = room.presenter ?? undefined;
+    if (presenter !== this.presenter) {
+      this.presenter = presenter;
+      events.emit('multiplayerPresenter', presenter);
+    }
+    this.locks = new Map(Object.entries(room.locks));
+    events.emit('multiplayerLocks', this.locks);
     events.emit('multiplayerUpdate', this.getUsers());
     pixiApp.multiplayerCursor.dirty = true;
   }
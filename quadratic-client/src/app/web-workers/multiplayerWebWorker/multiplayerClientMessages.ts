@@ -1,5 +1,5 @@
 import { User } from '@/auth/auth';
-import { CellEdit, ReceiveRoom, UserUpdate } from './multiplayerTypes';
+import { CellEdit, LockUpdate, PresentationUpdate, ReceiveRoom, UserUpdate } from './multiplayerTypes';
 
 export type MultiplayerState =
   | 'startup'
@@ -75,6 +75,32 @@ export interface ClientMultiplayerFollow {
   follow: string;
 }
 
+export interface ClientMultiplayerStartPresenting {
+  type: 'clientMultiplayerStartPresenting';
+}
+
+export interface ClientMultiplayerStopPresenting {
+  type: 'clientMultiplayerStopPresenting';
+}
+
+export interface ClientMultiplayerLock {
+  type: 'clientMultiplayerLock';
+  lock: LockUpdate;
+}
+
+export interface MultiplayerClientRangeLocked {
+  type: 'multiplayerClientRangeLocked';
+  // session_id of the user holding the lock
+  sessionId: string;
+}
+
+export interface MultiplayerClientPresentation {
+  type: 'multiplayerClientPresentation';
+  sessionId: string;
+  fileId: string;
+  update: PresentationUpdate;
+}
+
 export interface MultiplayerClientUsersInRoom {
   type: 'multiplayerClientUsersInRoom';
   room: ReceiveRoom;
@@ -108,7 +134,9 @@ export type MultiplayerClientMessage =
   | MultiplayerClientUsersInRoom
   | MultiplayerClientUserUpdate
   | MultiplayerClientReload
-  | MultiplayerClientRefreshJwt;
+  | MultiplayerClientRefreshJwt
+  | MultiplayerClientPresentation
+  | MultiplayerClientRangeLocked;
 
 export type ClientMultiplayerMessage =
   | ClientMultiplayerInit
@@ -119,6 +147,9 @@ export type ClientMultiplayerMessage =
   | ClientMultiplayerViewport
   | ClientMultiplayerCodeRunning
   | ClientMultiplayerFollow
+  | ClientMultiplayerStartPresenting
+  | ClientMultiplayerStopPresenting
+  | ClientMultiplayerLock
   | ClientMultiplayerRefreshJwt
   | ClientMultiplayerOnline
   | ClientMultiplayerOffline;
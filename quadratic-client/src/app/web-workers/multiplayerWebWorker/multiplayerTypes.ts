@@ -1,4 +1,5 @@
 import { SheetPosTS } from '@/app/gridGL/types/size';
+import { A1Selection } from '@/app/quadratic-core-types';
 import { JsSelection } from '@/app/quadratic-rust-client/quadratic_rust_client';
 
 export interface CellEdit {
@@ -50,8 +51,15 @@ export interface ReceiveRoom {
   type: 'UsersInRoom';
   users: MultiplayerUser[];
   min_version: Version;
+  // session_id of the user presenting to the room, if a presentation is active
+  presenter: string | null;
+  // ranges locked by users, by session_id
+  locks: Record<string, A1Selection>;
 }
 
+// claims or releases the user's lock on a range
+export type LockUpdate = { type: 'Claim'; selection: A1Selection } | { type: 'Release' };
+
 export interface UserUpdate {
   cell_edit?: CellEdit;
   selection?: string;
@@ -62,6 +70,7 @@ export interface UserUpdate {
   viewport?: string;
   code_running?: string;
   follow?: string;
+  lock?: LockUpdate;
 }
 
 export interface MessageUserUpdate {
@@ -71,6 +80,39 @@ export interface MessageUserUpdate {
   update: UserUpdate;
 }
 
+// changes a presenter pushes to everyone else in the room
+export interface PresentationUpdate {
+  sheet_id?: string;
+  selection?: string;
+  viewport?: string;
+}
+
+export interface SendStartPresenting {
+  type: 'StartPresenting';
+  session_id: string;
+  file_id: string;
+}
+
+export interface SendStopPresenting {
+  type: 'StopPresenting';
+  session_id: string;
+  file_id: string;
+}
+
+export interface SendPresent {
+  type: 'Present';
+  session_id: string;
+  file_id: string;
+  update: PresentationUpdate;
+}
+
+export interface ReceivePresentation {
+  type: 'Presentation';
+  session_id: string;
+  file_id: string;
+  update: PresentationUpdate;
+}
+
 export interface SendEnterRoom extends MultiplayerUserServer {
   type: 'EnterRoom';
 }
@@ -133,6 +175,7 @@ export interface ReceiveCurrentTransaction {
 
 export interface ReceiveError {
   type: 'Error';
+  // RangeLocked is [transaction_id, session_id of the user holding the lock]
   error: string | Record<string, string[]>;
   error_level: string;
 }
@@ -145,6 +188,13 @@ export type ReceiveMessages =
   | ReceiveTransactions
   | ReceiveEnterRoom
   | ReceiveError
-  | ReceiveCurrentTransaction;
-
-export type MultiplayerServerMessage = SendTransaction | SendEnterRoom | SendGetTransactions;
+  | ReceiveCurrentTransaction
+  | ReceivePresentation;
+
+export type MultiplayerServerMessage =
+  | SendTransaction
+  | SendEnterRoom
+  | SendGetTransactions
+  | SendStartPresenting
+  | SendStopPresenting
+  | SendPresent;
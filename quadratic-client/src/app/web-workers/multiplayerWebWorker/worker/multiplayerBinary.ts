@@ -0,0 +1,90 @@
+/**
+ * Binary websocket frames for transactions (see quadratic-multiplayer's
+ * message/binary.rs). Used when the server accepts the `quadratic-binary-v1`
+ * subprotocol, so operations aren't base64 encoded in JSON.
+ *
+ * [version: u8][header length: u32 big-endian][JSON header][operations]
+ */
+
+import { Buffer } from 'buffer';
+import { ReceiveTransaction, ReceiveTransactions, SendGetTransactions, SendTransaction } from '../multiplayerTypes';
+
+export const BINARY_PROTOCOL = 'quadratic-binary-v1';
+const BINARY_VERSION = 1;
+
+// sizes of the version and header length fields
+const PREFIX_LENGTH = 5;
+
+interface TransactionHeader {
+  id: string;
+  file_id: string;
+  sequence_num: number;
+  operations_len: number;
+}
+
+type ResponseHeader =
+  | { type: 'Transaction'; id: string; file_id: string; sequence_num: number }
+  | { type: 'Transactions'; transactions: TransactionHeader[] };
+
+const frame = (header: object, operations?: Uint8Array): ArrayBuffer => {
+  const json = new TextEncoder().encode(JSON.stringify(header));
+  const operationsLength = operations?.byteLength ?? 0;
+  const buffer = new ArrayBuffer(PREFIX_LENGTH + json.byteLength + operationsLength);
+  const view = new DataView(buffer);
+  view.setUint8(0, BINARY_VERSION);
+  view.setUint32(1, json.byteLength);
+
+  const bytes = new Uint8Array(buffer);
+  bytes.set(json, PREFIX_LENGTH);
+  if (operations) {
+    bytes.set(operations, PREFIX_LENGTH + json.byteLength);
+  }
+  return buffer;
+};
+
+export const encodeTransaction = (
+  message: Omit<SendTransaction, 'operations'>,
+  operations: ArrayBuffer
+): ArrayBuffer => {
+  const { id, session_id, file_id } = message;
+  return frame({ type: 'Transaction', id, session_id, file_id }, new Uint8Array(operations));
+};
+
+export const encodeGetTransactions = (message: SendGetTransactions): ArrayBuffer => {
+  const { file_id, session_id, min_sequence_num } = message;
+  return frame({ type: 'GetTransactions', file_id, session_id, min_sequence_num });
+};
+
+// Decodes a binary frame sent by the server. Operations are returned as
+// Buffers, which core accepts in place of base64 strings.
+export const decodeResponse = (data: ArrayBuffer): ReceiveTransaction | ReceiveTransactions => {
+  const view = new DataView(data);
+  if (data.byteLength < PREFIX_LENGTH) throw new Error('Invalid binary frame: too short');
+
+  const version = view.getUint8(0);
+  if (version !== BINARY_VERSION) throw new Error(`Invalid binary frame: unsupported version ${version}`);
+
+  const headerLength = view.getUint32(1);
+  if (data.byteLength < PREFIX_LENGTH + headerLength) {
+    throw new Error('Invalid binary frame: header is longer than the frame');
+  }
+
+  const json = new TextDecoder().decode(new Uint8Array(data, PREFIX_LENGTH, headerLength));
+  const header: ResponseHeader = JSON.parse(json);
+  let offset = PREFIX_LENGTH + headerLength;
+
+  switch (header.type) {
+    case 'Transaction':
+      return { ...header, operations: Buffer.from(data, offset) };
+
+    case 'Transactions':
+      return {
+        type: 'Transactions',
+        transactions: header.transactions.map(({ operations_len, ...transaction }) => {
+          const operations = Buffer.from(data, offset, operations_len);
+          offset += operations_len;
+          return { type: 'Transaction', ...transaction, operations };
+        }),
+      };
+  }
+};
@@ -4,7 +4,7 @@
 
 import { debugWebWorkersMessages } from '@/app/debugFlags';
 import { ClientMultiplayerMessage, MultiplayerClientMessage, MultiplayerState } from '../multiplayerClientMessages';
-import { MessageUserUpdate, ReceiveRoom } from '../multiplayerTypes';
+import { MessageUserUpdate, ReceivePresentation, ReceiveRoom } from '../multiplayerTypes';
 import { multiplayerCore } from './multiplayerCore';
 import { cellEditDefault, multiplayerServer } from './multiplayerServer';
 
@@ -71,6 +71,18 @@ class MultiplayerClient {
         multiplayerServer.userUpdate.follow = e.data.follow;
         break;
 
+      case 'clientMultiplayerLock':
+        multiplayerServer.userUpdate.lock = e.data.lock;
+        break;
+
+      case 'clientMultiplayerStartPresenting':
+        multiplayerServer.startPresenting();
+        break;
+
+      case 'clientMultiplayerStopPresenting':
+        multiplayerServer.stopPresenting();
+        break;
+
       case 'clientMultiplayerRefreshJwt':
         if (e.data.id in this.waitingForConnection) {
           this.waitingForConnection[e.data.id]();
@@ -101,6 +113,22 @@ class MultiplayerClient {
     });
   }
 
+  sendPresentation(data: ReceivePresentation) {
+    this.send({
+      type: 'multiplayerClientPresentation',
+      sessionId: data.session_id,
+      fileId: data.file_id,
+      update: data.update,
+    });
+  }
+
+  sendRangeLocked(sessionId: string) {
+    this.send({
+      type: 'multiplayerClientRangeLocked',
+      sessionId,
+    });
+  }
+
   sendState(data: MultiplayerState) {
     this.send({
       type: 'multiplayerClientState',
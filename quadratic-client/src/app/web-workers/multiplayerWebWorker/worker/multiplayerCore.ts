@@ -57,6 +57,14 @@ class MultiplayerCore {
       transactions: receive_transactions.transactions,
     });
   }
+
+  // the server rejected one of our transactions (eg, it touched a locked range)
+  rejectTransaction(transactionId: string) {
+    this.send({
+      type: 'multiplayerCoreRejectTransaction',
+      transactionId,
+    });
+  }
 }
 
 export const multiplayerCore = new MultiplayerCore();
@@ -18,10 +18,14 @@ import {
   ReceiveRoom,
   SendEnterRoom,
   SendGetTransactions,
+  SendPresent,
+  SendStartPresenting,
+  SendStopPresenting,
   SendTransaction,
   UserUpdate,
   Version,
 } from '../multiplayerTypes';
+import { BINARY_PROTOCOL, decodeResponse, encodeGetTransactions, encodeTransaction } from './multiplayerBinary';
 import { multiplayerClient } from './multiplayerClient';
 import { multiplayerCore } from './multiplayerCore';
 
@@ -73,6 +77,12 @@ export class MultiplayerServer {
   private lastHeartbeat = 0;
   private updateId?: number;
 
+  // whether this session is presenting to the room
+  private presenting = false;
+
+  // whether the server accepted binary transactions for this connection
+  private binary = false;
+
   init = (message: ClientMultiplayerInit) => {
     this.sessionId = message.sessionId;
     this.fileId = message.fileId;
@@ -124,7 +134,10 @@ export class MultiplayerServer {
       await multiplayerClient.sendRefreshJwt();
     }
 
-    this.websocket = new WebSocket(import.meta.env.VITE_QUADRATIC_MULTIPLAYER_URL);
+    // offer binary transactions; servers that don't support them ignore the
+    // subprotocol and we fall back to JSON
+    this.websocket = new WebSocket(import.meta.env.VITE_QUADRATIC_MULTIPLAYER_URL, [BINARY_PROTOCOL]);
+    this.websocket.binaryType = 'arraybuffer';
     this.websocket.addEventListener('message', this.handleMessage);
 
     this.websocket.addEventListener('close', () => {
@@ -139,6 +152,7 @@ export class MultiplayerServer {
     });
     this.websocket.addEventListener('open', () => {
       if (debugShow) console.log('[Multiplayer] websocket connected.');
+      this.binary = this.websocket?.protocol === BINARY_PROTOCOL;
       this.state = 'connected';
       this.enterFileRoom();
       this.waitingForConnection.forEach((resolve) => resolve(0));
@@ -203,6 +217,24 @@ export class MultiplayerServer {
     if (!this.fileId) throw new Error('Expected fileId to be defined in update');
 
     const now = performance.now();
+
+    // while presenting, the sheet, selection and viewport are pushed to the
+    // rest of the room instead of being sent as a user update
+    if (this.presenting) {
+      const { sheet_id, selection, viewport, ...userUpdate } = this.userUpdate;
+      if (sheet_id !== undefined || selection !== undefined || viewport !== undefined) {
+        const message: SendPresent = {
+          type: 'Present',
+          session_id: this.sessionId,
+          file_id: this.fileId,
+          update: { sheet_id, selection, viewport },
+        };
+        this.send(message);
+        this.userUpdate = userUpdate;
+        this.lastHeartbeat = now;
+      }
+    }
+
     if (Object.keys(this.userUpdate).length > 0) {
       const message: MessageUserUpdate = {
         type: 'UserUpdate',
@@ -232,8 +264,8 @@ export class MultiplayerServer {
    * Receive Messages from Multiplayer Server *
    ********************************************/
 
-  private handleMessage = (e: MessageEvent<string>) => {
-    const data: ReceiveMessages = JSON.parse(e.data);
+  private handleMessage = (e: MessageEvent<string | ArrayBuffer>) => {
+    const data: ReceiveMessages = typeof e.data === 'string' ? JSON.parse(e.data) : decodeResponse(e.data);
     switch (data.type) {
       case 'UsersInRoom':
         this.receiveUsersInRoom(data);
@@ -260,7 +292,20 @@ export class MultiplayerServer {
         multiplayerCore.receiveCurrentTransaction(data.sequence_num);
         break;
 
+      case 'Presentation':
+        multiplayerClient.sendPresentation(data);
+        break;
+
       case 'Error':
+        // the server rejected one of our transactions because it changed
+        // cells locked by another user, so we undo it locally
+        if (typeof data.error !== 'string' && 'RangeLocked' in data.error) {
+          const [transactionId, sessionId] = data.error.RangeLocked;
+          multiplayerCore.rejectTransaction(transactionId);
+          multiplayerClient.sendRangeLocked(sessionId);
+          break;
+        }
+
         if (data.error_level === 'Error') {
           // If the server is missing transactions, reload the page
           if (typeof data.error != 'string' && 'MissingTransactions' in data.error) {
@@ -289,6 +334,7 @@ export class MultiplayerServer {
   };
 
   private receiveUsersInRoom(room: ReceiveRoom) {
+    this.presenting = room.presenter === this.sessionId;
     multiplayerClient.sendUsersInRoom(room);
   }
 
@@ -304,16 +350,53 @@ export class MultiplayerServer {
     }
 
     multiplayerClient.sendState('syncing');
-    const message: SendTransaction = {
-      type: 'Transaction',
+    const header = {
+      type: 'Transaction' as const,
       id: transactionMessage.transaction_id,
       session_id: this.sessionId!,
       file_id: this.fileId!,
+    };
+    if (this.binary) {
+      this.sendBinary(encodeTransaction(header, transactionMessage.operations));
+      return;
+    }
+    const message: SendTransaction = {
+      ...header,
       operations: Buffer.from(transactionMessage.operations).toString('base64'),
     };
     this.send(message);
   }
 
+  private sendBinary(frame: ArrayBuffer) {
+    if (!this.websocket) throw new Error('Expected websocket to be defined in sendBinary');
+    this.websocket.send(frame);
+  }
+
+  startPresenting() {
+    if (this.state !== 'connected' && this.state !== 'syncing') return;
+    if (!this.sessionId) throw new Error('Expected sessionId to be defined in startPresenting');
+    if (!this.fileId) throw new Error('Expected fileId to be defined in startPresenting');
+    const message: SendStartPresenting = {
+      type: 'StartPresenting',
+      session_id: this.sessionId,
+      file_id: this.fileId,
+    };
+    this.send(message);
+  }
+
+  stopPresenting() {
+    if (this.state !== 'connected' && this.state !== 'syncing') return;
+    if (!this.sessionId) throw new Error('Expected sessionId to be defined in stopPresenting');
+    if (!this.fileId) throw new Error('Expected fileId to be defined in stopPresenting');
+    this.presenting = false;
+    const message: SendStopPresenting = {
+      type: 'StopPresenting',
+      session_id: this.sessionId,
+      file_id: this.fileId,
+    };
+    this.send(message);
+  }
+
   requestTransactions(sequenceNum: number) {
     if (!this.sessionId) throw new Error('Expected sessionId to be defined in requestTransactions');
     if (!this.fileId) throw new Error('Expected fileId to be defined in requestTransactions');
@@ -324,7 +407,11 @@ export class MultiplayerServer {
       file_id: this.fileId,
       min_sequence_num: sequenceNum,
     };
-    this.send(message);
+    if (this.binary) {
+      this.sendBinary(encodeGetTransactions(message));
+    } else {
+      this.send(message);
+    }
   }
 }
 
@@ -13,6 +13,9 @@ export interface PythonCoreResults {
   type: 'pythonCoreResults';
   transactionId: string;
   results: PythonRun;
+
+  // the results' array_output as an Arrow IPC stream (see outputArrayToArrow)
+  output?: Uint8Array;
 }
 
 export interface PythonCoreGetCellsA1Length {
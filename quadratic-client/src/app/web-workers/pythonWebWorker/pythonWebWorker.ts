@@ -10,6 +10,10 @@ class PythonWebWorker {
 
   private worker?: Worker;
 
+  constructor() {
+    events.on('restartLanguageWorker', this.handleRestartLanguageWorker);
+  }
+
   private send(message: ClientPythonMessage, port?: MessagePort) {
     if (!this.worker) throw new Error('Expected worker to be defined in python.ts');
     if (port) {
@@ -55,13 +59,23 @@ class PythonWebWorker {
 
   cancelExecution = () => {
     mixpanel.track('[PythonWebWorker].restartFromUser');
+    this.restart();
+    quadraticCore.sendCancelExecution('Python');
+  };
 
+  // core already timed out the code run, so it doesn't need to be cancelled
+  private handleRestartLanguageWorker = (language: 'Python' | 'Javascript') => {
+    if (language !== 'Python' || !this.worker) return;
+    mixpanel.track('[PythonWebWorker].restartFromTimeout');
+    this.restart();
+  };
+
+  private restart() {
     if (!this.worker) throw new Error('Expected worker to be defined in python.ts');
     this.worker.terminate();
-    quadraticCore.sendCancelExecution('Python');
     this.init();
     events.emit('pythonState', 'loading');
-  };
+  }
 }
 
 export const pythonWebWorker = new PythonWebWorker();
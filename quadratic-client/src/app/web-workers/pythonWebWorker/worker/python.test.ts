@@ -17,6 +17,7 @@ let pythonResults: any;
 vi.mock('./pythonCore.ts', () => {
   return {
     pythonCore: {
+      loadRustClient: async () => {},
       sendPythonResults: (_: string, results: any) => {
         pythonResults = results;
       },
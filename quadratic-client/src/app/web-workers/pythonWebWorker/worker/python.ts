@@ -113,6 +113,7 @@ class Python {
       ],
     });
 
+    await pythonCore.loadRustClient();
     this.pyodide.registerJsModule('getCellsA1', this.getCellsA1);
 
     // patch requests https://github.com/koenvo/pyodide-http
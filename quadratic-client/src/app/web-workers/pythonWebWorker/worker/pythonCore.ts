@@ -1,5 +1,6 @@
 import { debugWebWorkers, debugWebWorkersMessages } from '@/app/debugFlags';
 import { CellA1Response, JsGetCellResponse } from '@/app/quadratic-core-types';
+import init, { arrowToCellsA1, outputArrayToArrow } from '@/app/quadratic-rust-client/quadratic_rust_client';
 import type { CorePythonMessage, PythonCoreMessage } from '../pythonCoreMessages';
 import type { PythonRun } from '../pythonTypes';
 import { python } from './python';
@@ -13,9 +14,18 @@ export class PythonCore {
     if (debugWebWorkers) console.log('[pythonCore] initialized');
   }
 
-  private send(message: PythonCoreMessage) {
+  // the rust client decodes and encodes the Arrow IPC streams sent to and from core
+  async loadRustClient() {
+    await init();
+  }
+
+  private send(message: PythonCoreMessage, transfer?: Transferable) {
     if (!this.coreMessagePort) throw new Error('coreMessagePort not initialized');
-    this.coreMessagePort.postMessage(message);
+    if (transfer) {
+      this.coreMessagePort.postMessage(message, [transfer]);
+    } else {
+      this.coreMessagePort.postMessage(message);
+    }
   }
 
   private handleMessage = async (e: MessageEvent<CorePythonMessage>) => {
@@ -29,11 +39,30 @@ export class PythonCore {
   };
 
   sendPythonResults(transactionId: string, results: PythonRun) {
-    this.send({
-      type: 'pythonCoreResults',
-      transactionId,
-      results,
-    });
+    let output: Uint8Array | undefined;
+    if (results.array_output) {
+      // A 1d list is converted to a 2d array by changing each entry into an array.
+      const outputArray = Array.isArray(results.array_output[0]?.[0])
+        ? results.array_output
+        : results.array_output.map((row) => [row]);
+      try {
+        output = outputArrayToArrow(JSON.stringify(outputArray));
+      } catch (e) {
+        console.warn('[pythonCore] outputArrayToArrow error', e);
+      }
+      if (output) {
+        results = { ...results, array_output: [] };
+      }
+    }
+    this.send(
+      {
+        type: 'pythonCoreResults',
+        transactionId,
+        results,
+        output,
+      },
+      output?.buffer
+    );
   }
 
   sendGetCellsA1(
@@ -46,7 +75,7 @@ export class PythonCore {
       // The first 4 bytes are used to signal the python core that the data is ready
       // The second 4 bytes are used to signal the length of the data
       // The third 4 bytes are used to signal the id of the data
-      // Length of the cells string is unknown at this point
+      // Length of the cells' Arrow IPC stream is unknown at this point
       let sharedBuffer: SharedArrayBuffer | undefined = new SharedArrayBuffer(4 + 4 + 4);
       let int32View: Int32Array | undefined = new Int32Array(sharedBuffer, 0, 3);
       Atomics.store(int32View, 0, 0);
@@ -58,7 +87,7 @@ export class PythonCore {
 
       const id = int32View[2];
 
-      // New shared buffer, which is sized to hold the cells string
+      // New shared buffer, which is sized to hold the cells' Arrow IPC stream
       sharedBuffer = new SharedArrayBuffer(4 + length);
       int32View = new Int32Array(sharedBuffer, 0, 1);
       Atomics.store(int32View, 0, 0);
@@ -77,9 +106,7 @@ export class PythonCore {
       int32View = undefined;
       uint8View = undefined;
 
-      const decoder = new TextDecoder();
-      const cellsStringified = decoder.decode(nonSharedView);
-      const cells = JSON.parse(cellsStringified) as CellA1Response;
+      const cells = JSON.parse(arrowToCellsA1(nonSharedView)) as CellA1Response;
       return { cells: cells.cells, x: Number(cells.x), y: Number(cells.y), w: Number(cells.w), h: Number(cells.h) };
     } catch (e) {
       console.warn('[pythonCore] getCellsA1 error', e);
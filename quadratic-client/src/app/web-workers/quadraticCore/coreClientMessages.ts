@@ -26,6 +26,7 @@ import {
   SheetInfo,
   SheetPos,
   SheetRect,
+  Table,
   TransactionName,
   Validation,
 } from '@/app/quadratic-core-types';
@@ -944,6 +945,12 @@ export interface CoreClientSheetValidations {
   validations: Validation[];
 }
 
+export interface CoreClientSheetTables {
+  type: 'coreClientSheetTables';
+  sheetId: string;
+  tables: Table[];
+}
+
 export interface CoreClientGetValidationFromPos {
   type: 'coreClientGetValidationFromPos';
   id: number;
@@ -1101,6 +1108,12 @@ export interface CoreClientClientMessage {
   error: boolean;
 }
 
+// sent when a code run timed out, since its worker may be stuck
+export interface CoreClientRestartLanguageWorker {
+  type: 'coreClientRestartLanguageWorker';
+  language: 'Python' | 'Javascript';
+}
+
 export interface ClientCoreFiniteRectFromSelection {
   type: 'clientCoreFiniteRectFromSelection';
   id: number;
@@ -1251,6 +1264,7 @@ export type CoreClientMessage =
   | CoreClientOfflineTransactionsApplied
   | CoreClientGetValidations
   | CoreClientSheetValidations
+  | CoreClientSheetTables
   | CoreClientGetValidationFromPos
   | CoreClientGetValidationList
   | CoreClientGetDisplayCell
@@ -1262,6 +1276,7 @@ export type CoreClientMessage =
   | CoreClientBordersSheet
   | CoreClientGetCellValue
   | CoreClientClientMessage
+  | CoreClientRestartLanguageWorker
   | CoreClientGetAIContextRectsInSelections
   | CoreClientGetErroredCodeCellsInSelections
   | CoreClientFindNextColumnForRect
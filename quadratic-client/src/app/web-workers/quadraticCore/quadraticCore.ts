@@ -184,6 +184,9 @@ class QuadraticCore {
     } else if (e.data.type === 'coreClientSheetValidations') {
       events.emit('sheetValidations', e.data.sheetId, e.data.validations);
       return;
+    } else if (e.data.type === 'coreClientSheetTables') {
+      events.emit('sheetTables', e.data.sheetId, e.data.tables);
+      return;
     } else if (e.data.type === 'coreClientRenderValidationWarnings') {
       events.emit('renderValidationWarnings', e.data.sheetId, e.data.hashX, e.data.hashY, e.data.validationWarnings);
       return;
@@ -200,6 +203,9 @@ class QuadraticCore {
       // after importing from dashboard. This can be removed in the future.
       this.receivedClientMessage = true;
 
+      return;
+    } else if (e.data.type === 'coreClientRestartLanguageWorker') {
+      events.emit('restartLanguageWorker', e.data.language);
       return;
     }
 
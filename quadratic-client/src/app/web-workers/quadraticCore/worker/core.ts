@@ -7,6 +7,7 @@
 
 import { bigIntReplacer } from '@/app/bigint';
 import { debugWebWorkers } from '@/app/debugFlags';
+import { getLanguage } from '@/app/helpers/codeCellLanguage';
 import {
   BorderSelection,
   BorderStyle,
@@ -15,6 +16,7 @@ import {
   CellVerticalAlign,
   CellWrap,
   CodeCellLanguage,
+  ConnectionTiming,
   Format,
   JsCellValue,
   JsCellValuePosAIContext,
@@ -48,6 +50,7 @@ import {
   ClientCoreSummarizeSelection,
 } from '@/app/web-workers/quadraticCore/coreClientMessages';
 import { coreClient } from '@/app/web-workers/quadraticCore/worker/coreClient';
+import { coreConnection } from '@/app/web-workers/quadraticCore/worker/coreConnection';
 import { coreRender } from '@/app/web-workers/quadraticCore/worker/coreRender';
 import { offline } from '@/app/web-workers/quadraticCore/worker/offline';
 import {
@@ -60,6 +63,9 @@ import * as Sentry from '@sentry/react';
 import { Buffer } from 'buffer';
 import { Rectangle } from 'pixi.js';
 
+// how often to check for code runs that have timed out
+const ASYNC_TIMEOUT_CHECK_INTERVAL = 5000;
+
 class Core {
   gridController?: GridController;
 
@@ -81,6 +87,7 @@ class Core {
 
   constructor() {
     this.next();
+    setInterval(this.checkAsyncTimeouts, ASYNC_TIMEOUT_CHECK_INTERVAL);
   }
 
   private allowEventLoop() {
@@ -295,6 +302,23 @@ class Core {
     });
   }
 
+  // Undoes a transaction the server rejected and removes it from the offline queue.
+  rejectTransaction(transactionId: string) {
+    return new Promise((resolve) => {
+      this.clientQueue.push(async () => {
+        if (!this.gridController) throw new Error('Expected gridController to be defined');
+        this.gridController.rejectTransaction(transactionId);
+        offline.markTransactionSent(transactionId);
+        if (await offline.unsentTransactionsCount()) {
+          coreClient.sendMultiplayerState('syncing');
+        } else {
+          coreClient.sendMultiplayerState('connected');
+        }
+        resolve(undefined);
+      });
+    });
+  }
+
   receiveTransactions(receive_transactions: MultiplayerCoreReceiveTransactions) {
     return new Promise((resolve) => {
       this.clientQueue.push(async () => {
@@ -918,9 +942,34 @@ class Core {
     this.gridController.calculationComplete(JSON.stringify(results));
   }
 
-  connectionComplete(transactionId: string, data: ArrayBuffer, std_out?: string, std_err?: string, extra?: string) {
+  // the output is the results' output_array as an Arrow IPC stream
+  calculationCompleteArrow(results: JsCodeResult, output: Uint8Array) {
     if (!this.gridController) throw new Error('Expected gridController to be defined');
-    this.gridController.connectionComplete(transactionId, new Uint8Array(data), std_out, std_err, extra);
+    this.gridController.calculationCompleteArrow(JSON.stringify(results), output);
+  }
+
+  connectionComplete(
+    transactionId: string,
+    data: ArrayBuffer,
+    std_out?: string,
+    std_err?: string,
+    extra?: string,
+    connectionTiming?: ConnectionTiming
+  ) {
+    if (!this.gridController) throw new Error('Expected gridController to be defined');
+    this.gridController.connectionComplete(
+      transactionId,
+      new Uint8Array(data),
+      std_out,
+      std_err,
+      extra,
+      connectionTiming ? JSON.stringify(connectionTiming, bigIntReplacer) : undefined
+    );
+  }
+
+  connectionCancelled(transactionId: string, timedOut: boolean) {
+    if (!this.gridController) throw new Error('Expected gridController to be defined');
+    this.gridController.connectionCancelled(transactionId, timedOut);
   }
 
   // Returns true if the transaction was applied successfully.
@@ -955,20 +1004,33 @@ class Core {
 
   cancelExecution(transactionId: string) {
     if (!this.gridController) throw new Error('Expected gridController to be defined');
-    const codeResult: JsCodeResult = {
-      transaction_id: transactionId,
-      success: false,
-      std_err: 'Execution cancelled by user',
-      std_out: null,
-      output_value: null,
-      output_array: null,
-      line_number: null,
-      output_display_type: null,
-      cancel_compute: true,
-    };
-    this.gridController.calculationComplete(JSON.stringify(codeResult));
+    try {
+      this.gridController.cancelTransaction(transactionId);
+    } catch (e) {
+      // the transaction may have completed (or timed out) before the cancel was received
+      if (debugWebWorkers) console.log('[core] cancelExecution: transaction not found', e);
+    }
   }
 
+  // Times out code runs that have been waiting too long (eg, the worker died),
+  // and restarts their workers so later code runs aren't stuck behind them
+  private checkAsyncTimeouts = () => {
+    this.clientQueue.push(() => {
+      if (!this.gridController) return;
+      const languages: CodeCellLanguage[] = this.gridController.checkAsyncTimeouts();
+      if (!languages.length) return;
+
+      coreClient.sendClientMessage('Code run exceeded the maximum allowed time', true);
+      new Set(languages.map(getLanguage)).forEach((language) => {
+        if (language === 'Connection') {
+          coreConnection.stopTimedOutQuery();
+        } else if (language === 'Python' || language === 'Javascript') {
+          coreClient.sendRestartLanguageWorker(language);
+        }
+      });
+    });
+  };
+
   changeDecimalPlaces(selection: string, decimals: number, cursor?: string) {
     this.clientQueue.push(() => {
       if (!this.gridController) throw new Error('Expected gridController to be defined');
@@ -1157,9 +1219,10 @@ class Core {
     });
   }
 
-  getCellsA1(transactionId: string, a1: string, lineNumber?: number): string {
+  // returns the cells as an Arrow IPC stream, which the language workers decode
+  getCellsA1Arrow(transactionId: string, a1: string, lineNumber?: number): Uint8Array {
     if (!this.gridController) throw new Error('Expected gridController to be defined');
-    return this.gridController.calculationGetCellsA1(transactionId, a1, lineNumber);
+    return this.gridController.calculationGetCellsA1Arrow(transactionId, a1, lineNumber);
   }
 
   finiteRectFromSelection(selection: string): Rectangle | undefined {
@@ -19,6 +19,7 @@ import {
   JsValidationWarning,
   SheetBounds,
   SheetInfo,
+  Table,
   TransactionName,
   Validation,
 } from '@/app/quadratic-core-types';
@@ -78,6 +79,7 @@ declare var self: WorkerGlobalScope &
     sendUndoRedo: (undo: boolean, redo: boolean) => void;
     sendImage: (sheetId: string, x: number, y: number, image?: string, w?: string, h?: string) => void;
     sendSheetValidations: (sheetId: string, validations: Validation[]) => void;
+    sendSheetTables: (sheetId: string, tables: Table[]) => void;
     sendRenderValidationWarnings: (
       sheetId: string,
       hashX: number,
@@ -118,6 +120,7 @@ class CoreClient {
     self.sendUndoRedo = coreClient.sendUndoRedo;
     self.sendImage = coreClient.sendImage;
     self.sendSheetValidations = coreClient.sendSheetValidations;
+    self.sendSheetTables = coreClient.sendSheetTables;
     self.sendRenderValidationWarnings = coreClient.sendRenderValidationWarnings;
     self.sendMultiplayerSynced = coreClient.sendMultiplayerSynced;
     self.sendClientMessage = coreClient.sendClientMessage;
@@ -791,6 +794,10 @@ class CoreClient {
     this.send({ type: 'coreClientSheetValidations', sheetId, validations });
   };
 
+  sendSheetTables = (sheetId: string, tables: Table[]) => {
+    this.send({ type: 'coreClientSheetTables', sheetId, tables });
+  };
+
   sendRenderValidationWarnings = (
     sheetId: string,
     hashX: number | undefined,
@@ -807,6 +814,10 @@ class CoreClient {
   sendClientMessage = (message: string, error: boolean) => {
     this.send({ type: 'coreClientClientMessage', message, error });
   };
+
+  sendRestartLanguageWorker = (language: 'Python' | 'Javascript') => {
+    this.send({ type: 'coreClientRestartLanguageWorker', language });
+  };
 }
 
 export const coreClient = new CoreClient();
@@ -1,5 +1,5 @@
 import { debugWebWorkers } from '@/app/debugFlags';
-import { ConnectionKind } from '@/app/quadratic-core-types';
+import { ConnectionKind, ConnectionTiming } from '@/app/quadratic-core-types';
 import { LanguageState } from '@/app/web-workers/languageTypes';
 import { core } from '@/app/web-workers/quadraticCore/worker/core';
 import { coreClient } from '@/app/web-workers/quadraticCore/worker/coreClient';
@@ -18,9 +18,16 @@ declare var self: WorkerGlobalScope &
     ) => void;
   };
 
+// status codes returned by the connection service when a query is stopped
+const QUERY_TIMEOUT_STATUS = 408;
+const QUERY_CANCELLED_STATUS = 499;
+
 class CoreConnection {
   controller: AbortController = new AbortController();
 
+  // transaction id of the running query, used to cancel it in the connection service
+  private runningQuery?: string;
+
   start() {
     self.sendConnection = this.sendConnection;
 
@@ -56,12 +63,14 @@ class CoreConnection {
     const body = {
       connection_id,
       query: code,
+      query_id: transactionId,
     };
 
     let buffer = new ArrayBuffer(0);
     let std_out = undefined;
     let std_err = undefined;
     let extra = undefined;
+    let connectionTiming: ConnectionTiming | undefined = undefined;
     let codeRun: CodeRun = {
       transactionId,
       sheetPos: { x, y, sheetId },
@@ -71,6 +80,7 @@ class CoreConnection {
 
     try {
       this.sendConnectionState('running', { current: codeRun });
+      this.runningQuery = transactionId;
 
       const response = await fetch(url, {
         signal,
@@ -82,6 +92,14 @@ class CoreConnection {
         body: JSON.stringify(body),
       });
 
+      this.runningQuery = undefined;
+
+      if (response.status === QUERY_TIMEOUT_STATUS || response.status === QUERY_CANCELLED_STATUS) {
+        core.connectionCancelled(transactionId, response.status === QUERY_TIMEOUT_STATUS);
+        this.sendConnectionState('ready');
+        return;
+      }
+
       if (!response.ok) {
         std_err = (await response.text()) + `\n\nQuery: ${codeRun.code}`;
         console.warn(std_err);
@@ -92,10 +110,22 @@ class CoreConnection {
         const isOverTheLimit = headers.get('over-the-limit') === 'true';
         std_out = isOverTheLimit ? 'Exceeded maximum allowed bytes, not all available records returned.' : '';
         extra = ` in ${headers.get('elapsed-total-ms')}ms`;
+        connectionTiming = {
+          connect_ms: BigInt(headers.get('elapsed-database-connection-ms') ?? 0),
+          query_ms: BigInt(headers.get('elapsed-database-query-ms') ?? 0),
+          total_ms: BigInt(headers.get('elapsed-total-ms') ?? 0),
+        };
       }
 
       // send the parquet bytes to core
-      core.connectionComplete(transactionId, buffer, std_out, std_err?.replace(/\\/g, '').replace(/"/g, ''), extra);
+      core.connectionComplete(
+        transactionId,
+        buffer,
+        std_out,
+        std_err?.replace(/\\/g, '').replace(/"/g, ''),
+        extra,
+        connectionTiming
+      );
       this.sendConnectionState('ready');
     } catch (e) {
       console.error(`Error fetching ${url}`, e);
@@ -103,6 +133,29 @@ class CoreConnection {
   };
 
   cancelExecution() {
+    const runningQuery = this.stopRunningQuery();
+    if (runningQuery) {
+      core.connectionCancelled(runningQuery, false);
+    }
+    this.sendConnectionState('ready');
+  }
+
+  // Stops the running query after core timed it out. Core has already
+  // finished the transaction, so it isn't told about the cancel.
+  stopTimedOutQuery() {
+    this.stopRunningQuery();
+    this.sendConnectionState('ready');
+  }
+
+  // stops the running query and aborts its fetch; returns the query's transaction id
+  private stopRunningQuery(): string | undefined {
+    const runningQuery = this.runningQuery;
+    this.runningQuery = undefined;
+
+    if (runningQuery) {
+      this.cancelQuery(runningQuery);
+    }
+
     try {
       this.controller.abort();
     } catch (error: any) {
@@ -113,8 +166,21 @@ class CoreConnection {
     }
 
     this.controller = new AbortController();
-    this.sendConnectionState('ready');
+    return runningQuery;
   }
+
+  // ask the connection service to stop the query in the database
+  private cancelQuery = async (transactionId: string) => {
+    const base = coreClient.env.VITE_QUADRATIC_CONNECTION_URL;
+    const url = `${base}/query/${transactionId}/cancel`;
+
+    try {
+      const jwt = await coreClient.getJwt();
+      await fetch(url, { method: 'POST', headers: { Authorization: `Bearer ${jwt}` } });
+    } catch (e) {
+      console.warn(`Error cancelling query ${transactionId}`, e);
+    }
+  };
 }
 
 export const coreConnection = new CoreConnection();
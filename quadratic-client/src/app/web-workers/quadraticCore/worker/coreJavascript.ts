@@ -1,5 +1,4 @@
 import { debugWebWorkers } from '@/app/debugFlags';
-import { CellA1Response } from '@/app/quadratic-core-types';
 import {
   CoreJavascriptMessage,
   JavascriptCoreMessage,
@@ -30,7 +29,11 @@ class CoreJavascript {
         if (this.lastTransactionId === e.data.transactionId) {
           this.lastTransactionId = undefined;
         }
-        core.calculationComplete(e.data.results);
+        if (e.data.output) {
+          core.calculationCompleteArrow(e.data.results, e.data.output);
+        } else {
+          core.calculationComplete(e.data.results);
+        }
         break;
 
       case 'javascriptCoreGetCellsA1':
@@ -42,34 +45,26 @@ class CoreJavascript {
     }
   };
 
-  private send(message: CoreJavascriptMessage) {
+  private send(message: CoreJavascriptMessage, transfer?: Transferable) {
     if (!this.coreJavascriptPort) {
       console.warn('Expected coreJavascriptPort to be defined in CoreJavascript.send');
       return;
     }
-    this.coreJavascriptPort.postMessage(message);
+    if (transfer) {
+      this.coreJavascriptPort.postMessage(message, [transfer]);
+    } else {
+      this.coreJavascriptPort.postMessage(message);
+    }
   }
 
   private handleGetCellsA1Response = (id: number, transactionId: string, a1: string, lineNumber?: number) => {
-    let cells: CellA1Response | undefined;
+    let arrow: Uint8Array | undefined;
     try {
-      const cellsString = core.getCellsA1(transactionId, a1, lineNumber);
-      if (cellsString) {
-        cells = JSON.parse(cellsString) as CellA1Response;
-      }
+      arrow = core.getCellsA1Arrow(transactionId, a1, lineNumber);
     } catch (_e) {
       // core threw and handled the error
     }
-    this.send({
-      type: 'coreJavascriptGetCellsA1',
-      id,
-      cells: cells?.cells,
-      x: cells ? Number(cells.x) : undefined,
-      y: cells ? Number(cells.y) : undefined,
-      w: cells ? Number(cells.w) : undefined,
-      h: cells ? Number(cells.h) : undefined,
-      two_dimensional: cells ? cells.two_dimensional : false,
-    });
+    this.send({ type: 'coreJavascriptGetCellsA1', id, arrow }, arrow?.buffer);
   };
 
   sendRunJavascript = (transactionId: string, x: number, y: number, sheetId: string, code: string) => {
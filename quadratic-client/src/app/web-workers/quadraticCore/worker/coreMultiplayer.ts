@@ -43,6 +43,10 @@ class CoreMultiplayer {
         core.receiveTransactions(e.data);
         break;
 
+      case 'multiplayerCoreRejectTransaction':
+        core.rejectTransaction(e.data.transactionId);
+        break;
+
       default:
         console.warn('[coreMultiplayer] Unhandled message type', e.data);
     }
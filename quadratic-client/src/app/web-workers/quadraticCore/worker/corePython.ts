@@ -11,7 +11,7 @@ declare var self: WorkerGlobalScope &
 class CorePython {
   private corePythonPort?: MessagePort;
   private id = 0;
-  private getCellsResponses: Record<number, string> = {};
+  private getCellsResponses: Record<number, Uint8Array> = {};
 
   // last running transaction (used to cancel execution)
   lastTransactionId?: string;
@@ -56,7 +56,11 @@ class CorePython {
           cancel_compute: false,
         };
 
-        core.calculationComplete(codeResult);
+        if (e.data.output) {
+          core.calculationCompleteArrow({ ...codeResult, output_array: null }, e.data.output);
+        } else {
+          core.calculationComplete(codeResult);
+        }
         break;
 
       case 'pythonCoreGetCellsA1Length':
@@ -88,15 +92,13 @@ class CorePython {
   ) {
     const int32View = new Int32Array(sharedBuffer, 0, 3);
     try {
-      const cellsString = core.getCellsA1(transactionId, a1, lineNumber);
-
-      // need to get the bytes of the string (which covers unicode characters)
-      const length = new Blob([cellsString]).size;
+      const cells = core.getCellsA1Arrow(transactionId, a1, lineNumber);
+      const length = cells.length;
 
       Atomics.store(int32View, 1, length);
       if (length !== 0) {
         const id = this.id++;
-        this.getCellsResponses[id] = cellsString;
+        this.getCellsResponses[id] = cells;
         Atomics.store(int32View, 2, id);
       }
       Atomics.store(int32View, 0, 1);
@@ -107,16 +109,14 @@ class CorePython {
   }
 
   private sendGetCellsA1Data(id: number, sharedBuffer: SharedArrayBuffer) {
-    const cellsString = this.getCellsResponses[id];
+    const cells = this.getCellsResponses[id];
     delete this.getCellsResponses[id];
     const int32View = new Int32Array(sharedBuffer, 0, 1);
-    if (cellsString === undefined) {
+    if (cells === undefined) {
       console.warn('[corePython] No cells found for id:', id);
     } else {
-      const encoder = new TextEncoder();
-      const encodedCells = encoder.encode(cellsString);
-      const uint8View = new Uint8Array(sharedBuffer, 4, encodedCells.length);
-      uint8View.set(encodedCells);
+      const uint8View = new Uint8Array(sharedBuffer, 4, cells.length);
+      uint8View.set(cells);
     }
     Atomics.store(int32View, 0, 1);
     Atomics.notify(int32View, 0, 1);
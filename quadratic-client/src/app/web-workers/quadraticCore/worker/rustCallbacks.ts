@@ -13,6 +13,7 @@ import {
   JsValidationWarning,
   SheetBounds,
   SheetInfo,
+  Table,
   TransactionName,
   Validation,
 } from '@/app/quadratic-core-types';
@@ -77,6 +78,7 @@ declare var self: WorkerGlobalScope &
     ) => void;
     sendImage: (sheetId: string, x: number, y: number, image?: string, w?: string, h?: string) => void;
     sendSheetValidations: (sheetId: string, validations: Validation[]) => void;
+    sendSheetTables: (sheetId: string, tables: Table[]) => void;
     sendRequestRowHeights: (transactionId: string, sheetId: string, rows: string) => void;
     sendRenderValidationWarnings: (
       sheetId: string,
@@ -262,6 +264,11 @@ export const jsSheetValidations = (sheetId: string, validations: string) => {
   self.sendSheetValidations(sheetId, validationsParsed);
 };
 
+export const jsSheetTables = (sheetId: string, tables: string) => {
+  const tablesParsed = JSON.parse(tables) as Table[];
+  self.sendSheetTables(sheetId, tablesParsed);
+};
+
 export const jsRequestRowHeights = (transactionId: string, sheetId: string, rows: string) => {
   self.sendRequestRowHeights(transactionId, sheetId, rows);
 };
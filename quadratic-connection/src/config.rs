@@ -21,6 +21,13 @@ pub(crate) struct Config {
     pub(crate) quadratic_api_uri: String,
     pub(crate) m2m_auth_token: String,
     pub(crate) max_response_bytes: u64,
+    pub(crate) query_timeout_default_s: u64,
+    pub(crate) query_timeout_max_s: u64,
+    pub(crate) pool_idle_timeout_s: u64,
+    pub(crate) pool_max_idle_per_connection: usize,
+    pub(crate) schema_cache_ttl_s: u64,
+    pub(crate) max_write_bytes: usize,
+    pub(crate) write_batch_size: usize,
     pub(crate) static_ips: Vec<String>,
 }
 
@@ -9,7 +9,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
-use quadratic_rust_shared::{clean_errors, SharedError};
+use quadratic_rust_shared::{clean_errors, sql::error::Sql, SharedError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -38,6 +38,18 @@ pub enum ConnectionError {
     #[error("Query error: {0}")]
     Query(String),
 
+    #[error("Query {0} was cancelled")]
+    QueryCancelled(String),
+
+    #[error("Query rejected on a read-only connection: {0}")]
+    QueryRejected(String),
+
+    #[error("Query not found: {0}")]
+    QueryNotFound(String),
+
+    #[error("Query exceeded the timeout of {0} seconds")]
+    QueryTimeout(u64),
+
     #[error("Error requesting data: {0}")]
     Request(String),
 
@@ -52,10 +64,17 @@ pub(crate) fn proxy_error(e: impl ToString) -> ConnectionError {
     ConnectionError::Proxy(e.to_string())
 }
 
+/// Non-standard "Client Closed Request" status, returned when a query is
+/// cancelled so that clients can tell it apart from a failed query.
+pub(crate) fn query_cancelled_status() -> StatusCode {
+    StatusCode::from_u16(499).unwrap_or(StatusCode::BAD_REQUEST)
+}
+
 impl From<SharedError> for ConnectionError {
     fn from(error: SharedError) -> Self {
         match error {
             SharedError::Auth(error) => ConnectionError::Authentication(error.to_string()),
+            SharedError::Sql(Sql::ReadOnly(error)) => ConnectionError::QueryRejected(error),
             SharedError::Sql(error) => ConnectionError::Query(error.to_string()),
             SharedError::QuadraticApi(error) => ConnectionError::Connection(error.to_string()),
             _ => ConnectionError::Unknown(error.to_string()),
@@ -99,6 +118,10 @@ impl IntoResponse for ConnectionError {
                 (StatusCode::UNAUTHORIZED, clean_errors(error))
             }
             ConnectionError::Query(error) => (StatusCode::BAD_REQUEST, clean_errors(error)),
+            ConnectionError::QueryCancelled(_) => (query_cancelled_status(), self.to_string()),
+            ConnectionError::QueryNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+            ConnectionError::QueryRejected(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            ConnectionError::QueryTimeout(_) => (StatusCode::REQUEST_TIMEOUT, self.to_string()),
             ConnectionError::Connection(error) => (StatusCode::NOT_FOUND, clean_errors(error)),
             ConnectionError::Proxy(error) => (StatusCode::BAD_REQUEST, clean_errors(error)),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, "Unknown".into()),
@@ -4,9 +4,13 @@
 //! to be shared across all requests and threads.  Adds tracing/logging.
 
 use axum::{
-    http::{header::AUTHORIZATION, Method},
+    extract::DefaultBodyLimit,
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        Method,
+    },
     middleware::map_response,
-    response::Response,
+    response::{IntoResponse, Response},
     routing::{any, get, post},
     Extension, Json, Router,
 };
@@ -15,6 +19,7 @@ use http::{
     HeaderName, HeaderValue,
 };
 use quadratic_rust_shared::auth::jwt::get_jwks;
+use quadratic_rust_shared::metrics::METRICS_CONTENT_TYPE;
 use quadratic_rust_shared::sql::Connection;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -33,12 +38,23 @@ use crate::{
     error::{ConnectionError, Result},
     proxy::proxy,
     sql::{
-        mssql::{query as query_mssql, schema as schema_mssql, test as test_mssql},
-        mysql::{query as query_mysql, schema as schema_mysql, test as test_mysql},
-        postgres::{query as query_postgres, schema as schema_postgres, test as test_postgres},
-        snowflake::{query as query_snowflake, schema as schema_snowflake, test as test_snowflake},
+        cancel as cancel_query,
+        mssql::{
+            query as query_mssql, schema as schema_mssql, test as test_mssql, write as write_mssql,
+        },
+        mysql::{
+            query as query_mysql, schema as schema_mysql, test as test_mysql, write as write_mysql,
+        },
+        postgres::{
+            query as query_postgres, schema as schema_postgres, test as test_postgres,
+            write as write_postgres,
+        },
+        snowflake::{
+            query as query_snowflake, schema as schema_snowflake, test as test_snowflake,
+            write as write_snowflake,
+        },
     },
-    state::State,
+    state::{stats::StatsResponse, State},
 };
 
 const HEALTHCHECK_INTERVAL_S: u64 = 5;
@@ -47,6 +63,12 @@ const HEALTHCHECK_INTERVAL_S: u64 = 5;
 pub(crate) struct SqlQuery {
     pub(crate) query: String,
     pub(crate) connection_id: Uuid,
+    /// Id to register the query under, so the client can cancel it
+    #[serde(default)]
+    pub(crate) query_id: Option<Uuid>,
+    /// Requested timeout, capped at the configured maximum
+    #[serde(default)]
+    pub(crate) timeout_s: Option<u64>,
 }
 
 #[derive(Serialize, PartialEq, Debug)]
@@ -91,6 +113,9 @@ pub(crate) fn app(state: State) -> Result<Router> {
     // get the auth middleware
     let auth = get_middleware(state.clone());
 
+    // write-back payloads are larger than axum's default body limit
+    let write_limit = DefaultBodyLimit::max(state.settings.max_write_bytes);
+
     // sensitive headers, that are excluded from tracing logs
     let sensitive_headers = [
         AUTHORIZATION,
@@ -106,18 +131,31 @@ pub(crate) fn app(state: State) -> Result<Router> {
         .route("/postgres/test", post(test_postgres))
         .route("/postgres/query", post(query_postgres))
         .route("/postgres/schema/:id", get(schema_postgres))
+        .route(
+            "/postgres/write/:id",
+            post(write_postgres).layer(write_limit.clone()),
+        )
         // mysql
         .route("/mysql/test", post(test_mysql))
         .route("/mysql/query", post(query_mysql))
         .route("/mysql/schema/:id", get(schema_mysql))
+        .route(
+            "/mysql/write/:id",
+            post(write_mysql).layer(write_limit.clone()),
+        )
         // mssql
         .route("/mssql/test", post(test_mssql))
         .route("/mssql/query", post(query_mssql))
         .route("/mssql/schema/:id", get(schema_mssql))
+        .route("/mssql/write/:id", post(write_mssql).layer(write_limit))
         // snowflake
         .route("/snowflake/test", post(test_snowflake))
         .route("/snowflake/query", post(query_snowflake))
         .route("/snowflake/schema/:id", get(schema_snowflake))
+        .route("/snowflake/write/:id", post(write_snowflake))
+        //
+        // cancel a running query
+        .route("/query/:id/cancel", post(cancel_query))
         //
         // proxy
         .route("/proxy", any(proxy))
@@ -131,6 +169,14 @@ pub(crate) fn app(state: State) -> Result<Router> {
         // state, required
         .with_state(state.clone())
         //
+        // unprotected routes with state
+        //
+        // stats
+        .route("/stats", get(stats))
+        //
+        // prometheus metrics
+        .route("/metrics", get(metrics))
+        //
         // state, repeated, but required
         .layer(Extension(state))
         //
@@ -197,11 +243,24 @@ pub(crate) async fn serve() -> Result<()> {
             loop {
                 interval.tick().await;
 
+                // drop idle connections and expired schemas
+                let evicted = state
+                    .pool
+                    .lock()
+                    .await
+                    .evict_idle(state.settings.pool_idle_timeout);
+                state.schema_cache.lock().await.evict_expired();
+
+                if evicted > 0 {
+                    tracing::info!("Evicted {evicted} idle connections from the pool");
+                }
+
                 let stats = state.stats.lock().await;
+                let running_queries = state.queries.lock().await.len();
 
                 // push stats to the logs if there are files to process
                 if stats.last_query_time.is_some() {
-                    tracing::info!("Stats: {}", stats);
+                    tracing::info!("Stats: {}, running queries: {}", stats, running_queries);
                 }
             }
         }
@@ -233,6 +292,28 @@ pub(crate) async fn healthcheck() -> Json<HealthResponse> {
     .into()
 }
 
+pub(crate) async fn stats(state: Extension<State>) -> Json<StatsResponse> {
+    let stats = state.stats.lock().await.to_owned();
+
+    StatsResponse::from(&stats).into()
+}
+
+pub(crate) async fn metrics(state: Extension<State>) -> impl IntoResponse {
+    let running_queries = state.queries.lock().await.len();
+
+    state.metrics.set_gauge(
+        "quadratic_connection_running_queries",
+        "Queries currently running",
+        &[],
+        running_queries as f64,
+    );
+
+    (
+        [(CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        state.metrics.render(),
+    )
+}
+
 pub(crate) async fn static_ips() -> Result<Json<StaticIpsResponse>> {
     let static_ips = config()?.static_ips.to_vec();
     let response = StaticIpsResponse { static_ips };
@@ -305,4 +386,62 @@ pub(crate) mod tests {
 
         assert_eq!(expected, body);
     }
+
+    #[tokio::test]
+    async fn gets_stats() {
+        let state = new_state().await;
+        state.stats.lock().await.schema_cache_hits = 1;
+        let app = app(state).unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/stats")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response_json::<StatsResponse>(response).await;
+        assert_eq!(body.schema_cache_hits, 1);
+        assert_eq!(body.pool_hits, 0);
+    }
+
+    #[tokio::test]
+    async fn gets_metrics() {
+        let state = new_state().await;
+        let labels = &[("kind", "postgres")];
+        state.metrics.inc_counter(
+            "quadratic_connection_queries_total",
+            "Queries run",
+            labels,
+            1.0,
+        );
+        let app = app(state).unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], METRICS_CONTENT_TYPE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"quadratic_connection_queries_total{kind="postgres"} 1"#));
+        assert!(body.contains("quadratic_connection_running_queries 0"));
+    }
 }
@@ -1,14 +1,22 @@
-use axum::{http::HeaderMap, response::IntoResponse, Extension, Json};
-use quadratic_rust_shared::sql::{schema::SchemaTable, Connection};
-use serde::Serialize;
-use tokio::time::Instant;
+use axum::{extract::Path, http::HeaderMap, response::IntoResponse, Extension, Json};
+use bytes::Bytes;
+use quadratic_rust_shared::sql::{
+    read_only::validate_read_only,
+    schema::{DatabaseSchema, SchemaFilter, SchemaTable},
+    write::{WriteData, WriteMode, WriteOptions},
+    Connection,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{sleep, Instant};
 use uuid::Uuid;
 
 use crate::{
-    error::Result,
+    auth::Claims,
+    error::{ConnectionError, Result},
     header::{number_header, time_header},
     server::SqlQuery,
-    state::State,
+    state::{pool::PoolKey, queries::RunningQueryGuard, State},
 };
 
 pub(crate) mod mssql;
@@ -25,25 +33,246 @@ pub struct Schema {
     pub tables: Vec<SchemaTable>,
 }
 
-/// Query the database and return the results as a parquet file.
-pub(crate) async fn query_generic<T: Connection>(
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SchemaQuery {
+    /// Skip the schema cache and introspect the database
+    #[serde(default)]
+    pub(crate) force_cache_refresh: bool,
+    /// Only introspect this schema
+    pub(crate) schema: Option<String>,
+    /// Only introspect this table
+    pub(crate) table: Option<String>,
+}
+
+impl SchemaQuery {
+    pub(crate) fn filter(&self) -> SchemaFilter {
+        SchemaFilter {
+            schema: self.schema.to_owned(),
+            table: self.table.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct WriteQuery {
+    pub(crate) schema: Option<String>,
+    pub(crate) table: String,
+    #[serde(default)]
+    pub(crate) mode: WriteMode,
+    /// Comma separated columns that identify a row, required for upserts
+    pub(crate) key_columns: Option<String>,
+    /// Rows per statement, defaults to the configured batch size
+    pub(crate) batch_size: Option<usize>,
+    /// Validate the data against the table without writing it
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+}
+
+impl WriteQuery {
+    pub(crate) fn options(&self, default_batch_size: usize) -> WriteOptions {
+        let key_columns = self
+            .key_columns
+            .iter()
+            .flat_map(|columns| columns.split(','))
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        WriteOptions {
+            schema: self.schema.to_owned(),
+            table: self.table.to_owned(),
+            mode: self.mode,
+            key_columns,
+            batch_size: self.batch_size.unwrap_or(default_batch_size),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct WriteResponse {
+    pub(crate) rows: usize,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct CancelResponse {
+    pub(crate) cancelled: bool,
+}
+
+/// Take an idle connection from the pool, or connect if there isn't one.
+///
+/// Pooled connections are pinged first, since the database may have closed
+/// them while they were idle.
+async fn checkout_connection<T>(state: &State, key: &PoolKey, connection: &T) -> Result<T::Conn>
+where
+    T: Connection,
+    T::Conn: Send + 'static,
+{
+    let pooled = state.pool.lock().await.checkout::<T::Conn>(key);
+
+    if let Some(mut pool) = pooled {
+        match connection.ping(&mut pool).await {
+            Ok(()) => {
+                state.stats.lock().await.pool_hits += 1;
+                return Ok(pool);
+            }
+            Err(e) => tracing::info!("Discarding pooled {} connection: {e}", connection.kind()),
+        }
+    }
+
+    state.stats.lock().await.pool_misses += 1;
+    Ok(connection.connect().await?)
+}
+
+/// Return a connection that ran a user's query to the pool.
+///
+/// The query may have changed the session, e.g. its settings, temporary
+/// tables or an open transaction, so the session is reset first so none of
+/// that leaks into the next query.  Sessions that can't be reset are dropped.
+async fn checkin_session<T>(state: &State, key: PoolKey, connection: &T, mut pool: T::Conn)
+where
+    T: Connection,
+    T::Conn: Send + 'static,
+{
+    match connection.reset(&mut pool).await {
+        Ok(()) => state.pool.lock().await.checkin(key, pool),
+        Err(e) => tracing::debug!("Discarding {} connection: {e}", connection.kind()),
+    }
+}
+
+/// Query the database and return the results as a parquet file, recording
+/// query counts, latency, bytes and errors by the kind of database.
+pub(crate) async fn query_generic<T>(
     connection: T,
     state: Extension<State>,
+    claims: &Claims,
     sql_query: Json<SqlQuery>,
-) -> Result<impl IntoResponse> {
+) -> Result<impl IntoResponse>
+where
+    T: Connection + Serialize,
+    T::Conn: Send + 'static,
+{
+    let labels: &[(&str, &str)] = &[("kind", connection.kind())];
+    let metrics = Arc::clone(&state.metrics);
+    let start = Instant::now();
+    let result = run_query(connection, state, claims, sql_query).await;
+
+    metrics.observe_duration(
+        "quadratic_connection_query_seconds",
+        "Time to connect, query and serialize results",
+        labels,
+        start.elapsed(),
+    );
+
+    match &result {
+        Ok((_, parquet)) => {
+            metrics.inc_counter(
+                "quadratic_connection_queries_total",
+                "Queries run",
+                labels,
+                1.0,
+            );
+            metrics.inc_counter(
+                "quadratic_connection_query_bytes_total",
+                "Parquet bytes returned from queries",
+                labels,
+                parquet.len() as f64,
+            );
+        }
+        Err(_) => metrics.inc_counter(
+            "quadratic_connection_query_errors_total",
+            "Queries that failed",
+            labels,
+            1.0,
+        ),
+    }
+
+    result
+}
+
+/// Run a query, returning the results as a parquet file.
+///
+/// The query is registered under `sql_query.query_id` (or a generated id) while
+/// it runs, so that it can be cancelled.  It's stopped in the database if it's
+/// cancelled or exceeds its timeout.
+///
+/// Read-only connections reject any statement that doesn't read data before
+/// connecting.
+async fn run_query<T>(
+    connection: T,
+    state: Extension<State>,
+    claims: &Claims,
+    sql_query: Json<SqlQuery>,
+) -> Result<(HeaderMap, Bytes)>
+where
+    T: Connection + Serialize,
+    T::Conn: Send + 'static,
+{
     let mut headers = HeaderMap::new();
     let start = Instant::now();
     let max_response_bytes = Some(state.settings.max_response_bytes);
+    let timeout = state.settings.query_timeout(sql_query.timeout_s);
+    let query_id = sql_query.query_id.unwrap_or_else(Uuid::new_v4);
+
+    if connection.read_only() {
+        validate_read_only(&sql_query.query)?;
+    }
+
+    let cancel =
+        state
+            .queries
+            .lock()
+            .await
+            .register(query_id, sql_query.connection_id, &claims.sub)?;
+    let _guard = RunningQueryGuard::new(Arc::clone(&state.queries), query_id);
 
+    headers.insert("QUERY-ID", number_header(query_id));
+
+    let key = PoolKey::new(sql_query.connection_id, &connection);
     let start_connect = Instant::now();
-    let mut pool = connection.connect().await?;
+    let mut pool = checkout_connection(&state, &key, &connection).await?;
+    let session_id = connection.session_id(&mut pool).await?;
 
     headers.insert("ELAPSED-DATABASE-CONNECTION-MS", time_header(start_connect));
 
     let start_query = Instant::now();
-    let (parquet, over_the_limit, num_records) = connection
-        .query(&mut pool, &sql_query.query, max_response_bytes)
-        .await?;
+    let result = tokio::select! {
+        result = connection.query(&mut pool, &sql_query.query, max_response_bytes) => {
+            result.map_err(ConnectionError::from)
+        }
+        _ = cancel.notified() => Err(ConnectionError::QueryCancelled(query_id.to_string())),
+        _ = sleep(timeout) => Err(ConnectionError::QueryTimeout(timeout.as_secs())),
+    };
+
+    if let Err(error @ (ConnectionError::QueryCancelled(_) | ConnectionError::QueryTimeout(_))) =
+        &result
+    {
+        // the query is no longer awaited, but is still running in the database;
+        // close its connection first so that databases without a session id to
+        // cancel abort it on disconnect
+        drop(pool);
+
+        if let Some(session_id) = session_id {
+            if let Err(e) = connection.cancel(&session_id).await {
+                tracing::warn!("Error cancelling query {query_id} in the database: {e}");
+            }
+        }
+
+        let mut stats = state.stats.lock().await;
+
+        match error {
+            ConnectionError::QueryTimeout(_) => stats.num_timed_out_queries += 1,
+            _ => stats.num_cancelled_queries += 1,
+        }
+    }
+
+    let (parquet, over_the_limit, num_records) = result?;
+
+    // a partially read result can leave rows on the wire, so don't reuse it
+    if !over_the_limit {
+        checkin_session(&state, key, &connection, pool).await;
+    }
 
     headers.insert("RECORD-COUNT", number_header(num_records));
     headers.insert("ELAPSED-DATABASE-QUERY-MS", time_header(start_query));
@@ -54,3 +283,223 @@ pub(crate) async fn query_generic<T: Connection>(
 
     Ok((headers, parquet))
 }
+
+/// Get the schema of the database, from the cache unless it has expired or a
+/// refresh is forced.
+pub(crate) async fn schema_generic<T>(
+    connection: &T,
+    state: &State,
+    connection_id: Uuid,
+    params: &SchemaQuery,
+) -> Result<DatabaseSchema>
+where
+    T: Connection + Serialize,
+    T::Conn: Send + 'static,
+{
+    let key = PoolKey::new(connection_id, connection);
+    let filter = params.filter();
+    let cached = match params.force_cache_refresh {
+        true => None,
+        false => state.schema_cache.lock().await.get(&key, &filter),
+    };
+
+    if let Some(schema) = cached {
+        state.stats.lock().await.schema_cache_hits += 1;
+        return Ok(schema);
+    }
+
+    state.stats.lock().await.schema_cache_misses += 1;
+
+    let mut pool = checkout_connection(state, &key, connection).await?;
+    let schema = connection.schema(&mut pool, &filter).await?;
+
+    state.pool.lock().await.checkin(key, pool);
+    state
+        .schema_cache
+        .lock()
+        .await
+        .insert(key, filter, schema.clone());
+
+    Ok(schema)
+}
+
+/// Write a Parquet file or Arrow IPC stream into a table.
+///
+/// The data is always validated against the table's current schema first, so
+/// a dry run reports the rows that would be written without touching the
+/// table.
+pub(crate) async fn write_generic<T>(
+    connection: &T,
+    state: &State,
+    connection_id: Uuid,
+    params: WriteQuery,
+    body: Bytes,
+) -> Result<Json<WriteResponse>>
+where
+    T: Connection + Serialize,
+    T::Conn: Send + 'static,
+{
+    // checked before the dry run too, so a dry run never suggests that a
+    // write would succeed
+    if connection.read_only() || !connection.allow_write() {
+        return Err(ConnectionError::QueryRejected(
+            "writing to tables is not allowed on this connection".into(),
+        ));
+    }
+
+    let mut options = params.options(state.settings.write_batch_size);
+    let data = WriteData::from_bytes(body)?;
+
+    let schema_query = SchemaQuery {
+        force_cache_refresh: true,
+        schema: options.schema.to_owned(),
+        table: Some(options.table.to_owned()),
+    };
+    let schema = schema_generic(connection, state, connection_id, &schema_query).await?;
+    let table = schema
+        .tables
+        .values()
+        .find(|table| {
+            table.name == options.table
+                && options
+                    .schema
+                    .as_ref()
+                    .map_or(true, |schema| &table.schema == schema)
+        })
+        .ok_or_else(|| ConnectionError::Query(format!("Table {} not found", options.table)))?;
+
+    data.validate(table, &options)?;
+
+    // write to the table that was validated, rather than letting the
+    // database resolve an unqualified name to a table in another schema
+    options.schema = Some(table.schema.to_owned());
+
+    if params.dry_run {
+        return Ok(Json(WriteResponse {
+            rows: data.rows.len(),
+            dry_run: true,
+        }));
+    }
+
+    let key = PoolKey::new(connection_id, connection);
+    let mut pool = checkout_connection(state, &key, connection).await?;
+    let rows = connection.write(&mut pool, &data, &options).await?;
+
+    state.pool.lock().await.checkin(key, pool);
+
+    tracing::info!(
+        "Wrote {rows} rows to {} on connection {connection_id}",
+        options.table
+    );
+
+    Ok(Json(WriteResponse {
+        rows,
+        dry_run: false,
+    }))
+}
+
+/// Cancel a running query that was started by the current user.
+pub(crate) async fn cancel(
+    Path(id): Path<Uuid>,
+    state: Extension<State>,
+    claims: Claims,
+) -> Result<Json<CancelResponse>> {
+    state.queries.lock().await.cancel(&id, &claims.sub)?;
+
+    Ok(Json(CancelResponse { cancelled: true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{_new_postgres_connection, get_claims, new_state};
+
+    #[tokio::test]
+    async fn cancels_a_running_query() {
+        let state = Extension(new_state().await);
+        let claims = get_claims();
+        let query_id = Uuid::new_v4();
+        let cancelled = state
+            .queries
+            .lock()
+            .await
+            .register(query_id, Uuid::new_v4(), &claims.sub)
+            .unwrap();
+
+        let response = cancel(Path(query_id), state.clone(), claims.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.0, CancelResponse { cancelled: true });
+        cancelled.notified().await;
+
+        let unknown_id = Uuid::new_v4();
+        let response = cancel(Path(unknown_id), state, claims).await;
+        assert_eq!(
+            response.unwrap_err(),
+            ConnectionError::QueryNotFound(unknown_id.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn read_only_connections_reject_writes() {
+        let state = Extension(new_state().await);
+        let mut connection = _new_postgres_connection();
+        connection.read_only = true;
+
+        let sql_query = SqlQuery {
+            query: "drop table users".into(),
+            connection_id: Uuid::new_v4(),
+            query_id: None,
+            timeout_s: None,
+        };
+        let response = query_generic(connection, state.clone(), &get_claims(), Json(sql_query))
+            .await
+            .err();
+        assert_eq!(
+            response,
+            Some(ConnectionError::QueryRejected(
+                "DROP statements are not allowed".into()
+            ))
+        );
+
+        let mut connection = _new_postgres_connection();
+        connection.read_only = true;
+        let params = WriteQuery {
+            schema: None,
+            table: "users".into(),
+            mode: WriteMode::Insert,
+            key_columns: None,
+            batch_size: None,
+            dry_run: true,
+        };
+        let response = write_generic(&connection, &state, Uuid::new_v4(), params, Bytes::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(response, ConnectionError::QueryRejected(_)));
+    }
+
+    #[tokio::test]
+    async fn writes_must_be_allowed() {
+        let state = Extension(new_state().await);
+        let mut connection = _new_postgres_connection();
+        connection.allow_write = false;
+
+        let params = WriteQuery {
+            schema: None,
+            table: "users".into(),
+            mode: WriteMode::Insert,
+            key_columns: None,
+            batch_size: None,
+            dry_run: true,
+        };
+        let response = write_generic(&connection, &state, Uuid::new_v4(), params, Bytes::new())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            response,
+            ConnectionError::QueryRejected(
+                "writing to tables is not allowed on this connection".into()
+            )
+        );
+    }
+}
@@ -1,7 +1,11 @@
-use axum::{extract::Path, response::IntoResponse, Extension, Json};
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+    Extension, Json,
+};
+use bytes::Bytes;
 use quadratic_rust_shared::{
-    quadratic_api::Connection as ApiConnection,
-    sql::{mssql_connection::MsSqlConnection, Connection},
+    quadratic_api::Connection as ApiConnection, sql::mssql_connection::MsSqlConnection,
 };
 use uuid::Uuid;
 
@@ -13,7 +17,9 @@ use crate::{
     state::State,
 };
 
-use super::{query_generic, Schema};
+use super::{
+    query_generic, schema_generic, write_generic, Schema, SchemaQuery, WriteQuery, WriteResponse,
+};
 
 /// Test the connection to the database.
 pub(crate) async fn test(Json(connection): Json<MsSqlConnection>) -> Json<TestResponse> {
@@ -41,6 +47,8 @@ async fn get_connection(
                 username: Some("sa".into()),
                 password: Some("yourStrong(!)Password".into()),
                 database: "AllTypes".into(),
+                read_only: false,
+                allow_write: true,
             },
         }
     };
@@ -51,6 +59,8 @@ async fn get_connection(
         connection.type_details.host.to_owned(),
         connection.type_details.port.to_owned(),
         connection.type_details.database.to_owned(),
+        connection.type_details.read_only,
+        connection.type_details.allow_write,
     );
 
     Ok((mssql_connection, connection))
@@ -65,18 +75,18 @@ pub(crate) async fn query(
     let connection = get_connection(&state, &claims, &sql_query.connection_id)
         .await?
         .0;
-    query_generic::<MsSqlConnection>(connection, state, sql_query).await
+    query_generic::<MsSqlConnection>(connection, state, &claims, sql_query).await
 }
 
 /// Get the schema of the database
 pub(crate) async fn schema(
     Path(id): Path<Uuid>,
+    Query(params): Query<SchemaQuery>,
     state: Extension<State>,
     claims: Claims,
 ) -> Result<Json<Schema>> {
     let (connection, api_connection) = get_connection(&state, &claims, &id).await?;
-    let mut pool = connection.connect().await?;
-    let database_schema = connection.schema(&mut pool).await?;
+    let database_schema = schema_generic(&connection, &state, id, &params).await?;
     let schema = Schema {
         id: api_connection.uuid,
         name: api_connection.name,
@@ -88,6 +98,18 @@ pub(crate) async fn schema(
     Ok(Json(schema))
 }
 
+/// Insert or upsert a Parquet file or Arrow IPC stream into a table
+pub(crate) async fn write(
+    Path(id): Path<Uuid>,
+    Query(params): Query<WriteQuery>,
+    state: Extension<State>,
+    claims: Claims,
+    body: Bytes,
+) -> Result<Json<WriteResponse>> {
+    let connection = get_connection(&state, &claims, &id).await?.0;
+    write_generic(&connection, &state, id, params, body).await
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -103,7 +125,7 @@ mod tests {
     use bytes::Bytes;
     use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
     use http::StatusCode;
-    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable};
+    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable, TableKind};
     use tracing_test::traced_test;
     use uuid::Uuid;
 
@@ -118,9 +140,19 @@ mod tests {
     async fn mssql_schema() {
         let connection_id = Uuid::new_v4();
         let state = Extension(new_state().await);
-        let response = schema(Path(connection_id), state, get_claims())
-            .await
-            .unwrap();
+        let mut response = schema(
+            Path(connection_id),
+            Query(SchemaQuery::default()),
+            state,
+            get_claims(),
+        )
+        .await
+        .unwrap();
+
+        // row counts are estimates that change as the database is analyzed
+        for table in response.0.tables.iter_mut() {
+            table.row_count = None;
+        }
 
         let expected = Schema {
             id: response.0.id,
@@ -130,173 +162,212 @@ mod tests {
             tables: vec![SchemaTable {
                 name: "all_native_data_types".into(),
                 schema: "dbo".into(),
+                kind: TableKind::Table,
                 columns: vec![
                     SchemaColumn {
                         name: "id".into(),
                         r#type: "int".into(),
                         is_nullable: false,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "tinyint_col".into(),
                         r#type: "tinyint".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "smallint_col".into(),
                         r#type: "smallint".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "int_col".into(),
                         r#type: "int".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "bigint_col".into(),
                         r#type: "bigint".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "bit_col".into(),
                         r#type: "bit".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "decimal_col".into(),
                         r#type: "decimal".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "numeric_col".into(),
                         r#type: "numeric".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "money_col".into(),
                         r#type: "money".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "smallmoney_col".into(),
                         r#type: "smallmoney".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "float_col".into(),
                         r#type: "float".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "real_col".into(),
                         r#type: "real".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "date_col".into(),
                         r#type: "date".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "time_col".into(),
                         r#type: "time".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "datetime2_col".into(),
                         r#type: "datetime2".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "datetimeoffset_col".into(),
                         r#type: "datetimeoffset".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "datetime_col".into(),
                         r#type: "datetime".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "smalldatetime_col".into(),
                         r#type: "smalldatetime".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "char_col".into(),
                         r#type: "char".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "varchar_col".into(),
                         r#type: "varchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "text_col".into(),
                         r#type: "text".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "nchar_col".into(),
                         r#type: "nchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "nvarchar_col".into(),
                         r#type: "nvarchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "ntext_col".into(),
                         r#type: "ntext".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "binary_col".into(),
                         r#type: "binary".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "varbinary_col".into(),
                         r#type: "varbinary".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "image_col".into(),
                         r#type: "image".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "json_col".into(),
                         r#type: "nvarchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "uniqueidentifier_col".into(),
                         r#type: "uniqueidentifier".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "xml_col".into(),
                         r#type: "xml".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "varchar_max_col".into(),
                         r#type: "varchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "nvarchar_max_col".into(),
                         r#type: "nvarchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "varbinary_max_col".into(),
                         r#type: "varbinary".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                 ],
+                primary_key: vec!["id".into()],
+                foreign_keys: vec![],
+                indexes: vec![],
+                comment: None,
+                row_count: None,
             }],
         };
 
@@ -310,6 +381,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "SELECT TOP 1 * FROM [dbo].[all_native_data_types] ORDER BY id".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let state = Extension(new_state().await);
         let data = query(state, get_claims(), Json(sql_query)).await.unwrap();
@@ -406,6 +479,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "SELECT TOP 1 * FROM [dbo].[all_native_data_types] ORDER BY id".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let mut state = Extension(new_state().await);
         state.settings.max_response_bytes = 0;
@@ -1,7 +1,11 @@
-use axum::{extract::Path, response::IntoResponse, Extension, Json};
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+    Extension, Json,
+};
+use bytes::Bytes;
 use quadratic_rust_shared::{
-    quadratic_api::Connection as ApiConnection,
-    sql::{postgres_connection::PostgresConnection, Connection},
+    quadratic_api::Connection as ApiConnection, sql::postgres_connection::PostgresConnection,
 };
 use uuid::Uuid;
 
@@ -13,7 +17,9 @@ use crate::{
     state::State,
 };
 
-use super::{query_generic, Schema};
+use super::{
+    query_generic, schema_generic, write_generic, Schema, SchemaQuery, WriteQuery, WriteResponse,
+};
 
 /// Test the connection to the database.
 pub(crate) async fn test(Json(connection): Json<PostgresConnection>) -> Json<TestResponse> {
@@ -41,6 +47,8 @@ async fn get_connection(
                 username: Some("user".into()),
                 password: Some("password".into()),
                 database: "postgres-connection".into(),
+                read_only: false,
+                allow_write: true,
             },
         }
     };
@@ -51,6 +59,8 @@ async fn get_connection(
         connection.type_details.host.to_owned(),
         connection.type_details.port.to_owned(),
         connection.type_details.database.to_owned(),
+        connection.type_details.read_only,
+        connection.type_details.allow_write,
     );
 
     Ok((pg_connection, connection))
@@ -65,18 +75,18 @@ pub(crate) async fn query(
     let connection = get_connection(&state, &claims, &sql_query.connection_id)
         .await?
         .0;
-    query_generic::<PostgresConnection>(connection, state, sql_query).await
+    query_generic::<PostgresConnection>(connection, state, &claims, sql_query).await
 }
 
 /// Get the schema of the database
 pub(crate) async fn schema(
     Path(id): Path<Uuid>,
+    Query(params): Query<SchemaQuery>,
     state: Extension<State>,
     claims: Claims,
 ) -> Result<Json<Schema>> {
     let (connection, api_connection) = get_connection(&state, &claims, &id).await?;
-    let mut pool = connection.connect().await?;
-    let database_schema = connection.schema(&mut pool).await?;
+    let database_schema = schema_generic(&connection, &state, id, &params).await?;
     let schema = Schema {
         id: api_connection.uuid,
         name: api_connection.name,
@@ -88,6 +98,18 @@ pub(crate) async fn schema(
     Ok(Json(schema))
 }
 
+/// Insert or upsert a Parquet file or Arrow IPC stream into a table
+pub(crate) async fn write(
+    Path(id): Path<Uuid>,
+    Query(params): Query<WriteQuery>,
+    state: Extension<State>,
+    claims: Claims,
+    body: Bytes,
+) -> Result<Json<WriteResponse>> {
+    let connection = get_connection(&state, &claims, &id).await?.0;
+    write_generic(&connection, &state, id, params, body).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,7 +122,7 @@ mod tests {
     use bytes::Bytes;
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
     use http::StatusCode;
-    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable};
+    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable, TableKind};
     use tracing_test::traced_test;
     use uuid::Uuid;
 
@@ -115,9 +137,19 @@ mod tests {
     async fn postgres_schema() {
         let connection_id = Uuid::new_v4();
         let state = Extension(new_state().await);
-        let response = schema(Path(connection_id), state, get_claims())
-            .await
-            .unwrap();
+        let mut response = schema(
+            Path(connection_id),
+            Query(SchemaQuery::default()),
+            state,
+            get_claims(),
+        )
+        .await
+        .unwrap();
+
+        // row counts are estimates that change as the database is analyzed
+        for table in response.0.tables.iter_mut() {
+            table.row_count = None;
+        }
 
         let expected = Schema {
             id: response.0.id,
@@ -127,198 +159,242 @@ mod tests {
             tables: vec![SchemaTable {
                 name: "all_native_data_types".into(),
                 schema: "public".into(),
+                kind: TableKind::Table,
                 columns: vec![
                     SchemaColumn {
                         name: "id".into(),
                         r#type: "int4".into(),
                         is_nullable: false,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "smallint_col".into(),
                         r#type: "int2".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "integer_col".into(),
                         r#type: "int4".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "bigint_col".into(),
                         r#type: "int8".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "decimal_col".into(),
                         r#type: "numeric".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "numeric_col".into(),
                         r#type: "numeric".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "real_col".into(),
                         r#type: "float4".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "double_col".into(),
                         r#type: "float8".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "serial_col".into(),
                         r#type: "int4".into(),
                         is_nullable: false,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "bigserial_col".into(),
                         r#type: "int8".into(),
                         is_nullable: false,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "money_col".into(),
                         r#type: "money".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "char_col".into(),
                         r#type: "bpchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "varchar_col".into(),
                         r#type: "varchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "text_col".into(),
                         r#type: "text".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "bytea_col".into(),
                         r#type: "bytea".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "timestamp_col".into(),
                         r#type: "timestamp".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "timestamptz_col".into(),
                         r#type: "timestamptz".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "date_col".into(),
                         r#type: "date".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "time_col".into(),
                         r#type: "time".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "timetz_col".into(),
                         r#type: "timetz".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "interval_col".into(),
                         r#type: "interval".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "boolean_col".into(),
                         r#type: "bool".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "enum_col".into(),
                         r#type: "varchar".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "point_col".into(),
                         r#type: "point".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "line_col".into(),
                         r#type: "line".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "lseg_col".into(),
                         r#type: "lseg".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "box_col".into(),
                         r#type: "box".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "path_col".into(),
                         r#type: "path".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "polygon_col".into(),
                         r#type: "polygon".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "circle_col".into(),
                         r#type: "circle".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "cidr_col".into(),
                         r#type: "cidr".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "inet_col".into(),
                         r#type: "inet".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "macaddr_col".into(),
                         r#type: "macaddr".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "json_col".into(),
                         r#type: "json".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "jsonb_col".into(),
                         r#type: "jsonb".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "uuid_col".into(),
                         r#type: "uuid".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "xml_col".into(),
                         r#type: "xml".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "array_col".into(),
                         r#type: "_int4".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                 ],
+                primary_key: vec!["id".into()],
+                foreign_keys: vec![],
+                indexes: vec![],
+                comment: None,
+                row_count: None,
             }],
         };
         assert_eq!(response.0, expected)
@@ -331,6 +407,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "select * from all_native_data_types order by id limit 1".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let state = Extension(new_state().await);
         let data = query(state, get_claims(), Json(sql_query)).await.unwrap();
@@ -423,6 +501,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "select * from all_native_data_types order by id limit 1".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let mut state = Extension(new_state().await);
         state.settings.max_response_bytes = 0;
@@ -434,4 +514,74 @@ mod tests {
         let body = response_bytes(response).await;
         assert_eq!(body, Bytes::new());
     }
+
+    fn write_parquet(column: &str) -> Bytes {
+        let schema = arrow_schema::Schema::new(vec![
+            arrow_schema::Field::new(column, DataType::Utf8, true),
+            arrow_schema::Field::new("integer_col", DataType::Int32, true),
+        ]);
+        let batch = arrow::array::RecordBatch::try_new(
+            std::sync::Arc::new(schema),
+            vec![
+                std::sync::Arc::new(arrow::array::StringArray::from(vec!["a", "b"])),
+                std::sync::Arc::new(arrow::array::Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+        let mut writer =
+            parquet::arrow::ArrowWriter::try_new(vec![], batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+
+        writer.into_inner().unwrap().into()
+    }
+
+    fn write_query(dry_run: bool) -> WriteQuery {
+        WriteQuery {
+            schema: Some("public".into()),
+            table: "all_native_data_types".into(),
+            mode: Default::default(),
+            key_columns: None,
+            batch_size: None,
+            dry_run,
+        }
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn postgres_write_dry_run() {
+        let state = Extension(new_state().await);
+        let response = write(
+            Path(Uuid::new_v4()),
+            Query(write_query(true)),
+            state,
+            get_claims(),
+            write_parquet("text_col"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.0,
+            WriteResponse {
+                rows: 2,
+                dry_run: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn postgres_write_unknown_column() {
+        let state = Extension(new_state().await);
+        let response = write(
+            Path(Uuid::new_v4()),
+            Query(write_query(false)),
+            state,
+            get_claims(),
+            write_parquet("not_a_column"),
+        )
+        .await;
+
+        assert!(response.is_err());
+    }
 }
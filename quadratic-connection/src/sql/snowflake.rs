@@ -1,4 +1,8 @@
-use axum::{extract::Path, response::IntoResponse, Extension, Json};
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+    Extension, Json,
+};
 use quadratic_rust_shared::{
     quadratic_api::Connection as ApiConnection,
     sql::{snowflake_connection::SnowflakeConnection, Connection},
@@ -8,23 +12,24 @@ use uuid::Uuid;
 use crate::{
     auth::Claims,
     connection::get_api_connection,
-    error::Result,
+    error::{ConnectionError, Result},
     server::{SqlQuery, TestResponse},
     state::State,
 };
 
-use super::{query_generic, Schema};
+use super::{query_generic, schema_generic, Schema, SchemaQuery, WriteResponse};
 
 /// Test the connection to the database.
-pub(crate) async fn test(
-    state: Extension<State>,
-    Json(connection): Json<SnowflakeConnection>,
-) -> Json<TestResponse> {
-    let sql_query = SqlQuery {
-        query: "SELECT 1".into(),
-        connection_id: Uuid::new_v4(), // This is not used
-    };
-    let response = query_generic::<SnowflakeConnection>(connection, state, sql_query.into()).await;
+///
+/// Snowflake only reports bad credentials once a query runs, so this runs one
+/// directly on a new session.  The connection isn't saved yet, so the session
+/// isn't pooled and the query isn't registered for cancellation.
+pub(crate) async fn test(Json(connection): Json<SnowflakeConnection>) -> Json<TestResponse> {
+    let response = async {
+        let mut pool = connection.connect().await?;
+        connection.query(&mut pool, "SELECT 1", None).await
+    }
+    .await;
     let message = match response {
         Ok(_) => None,
         Err(e) => Some(e.to_string()),
@@ -56,6 +61,7 @@ async fn get_connection(
                 warehouse: None,
                 schema: None,
                 role: None,
+                read_only: false,
             },
         }
     };
@@ -68,6 +74,7 @@ async fn get_connection(
         connection.type_details.database.to_owned(),
         None,
         None,
+        connection.type_details.read_only,
     );
 
     Ok((snowflake_connection, connection))
@@ -82,18 +89,18 @@ pub(crate) async fn query(
     let connection = get_connection(&state, &claims, &sql_query.connection_id)
         .await?
         .0;
-    query_generic::<SnowflakeConnection>(connection, state, sql_query).await
+    query_generic::<SnowflakeConnection>(connection, state, &claims, sql_query).await
 }
 
 /// Get the schema of the database
 pub(crate) async fn schema(
     Path(id): Path<Uuid>,
+    Query(params): Query<SchemaQuery>,
     state: Extension<State>,
     claims: Claims,
 ) -> Result<Json<Schema>> {
     let (connection, api_connection) = get_connection(&state, &claims, &id).await?;
-    let mut pool = connection.connect().await?;
-    let database_schema = connection.schema(&mut pool).await?;
+    let database_schema = schema_generic(&connection, &state, id, &params).await?;
     let schema = Schema {
         id: api_connection.uuid,
         name: api_connection.name,
@@ -105,6 +112,14 @@ pub(crate) async fn schema(
     Ok(Json(schema))
 }
 
+/// Writing to Snowflake tables is not supported yet, so reject it explicitly
+/// rather than leaving the route unmatched.
+pub(crate) async fn write(Path(_id): Path<Uuid>) -> Result<Json<WriteResponse>> {
+    Err(ConnectionError::Query(
+        "Writing to Snowflake tables is not supported".into(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -113,7 +128,7 @@ mod tests {
     use bytes::Bytes;
     use http::StatusCode;
     use quadratic_rust_shared::parquet::utils::compare_parquet_file_with_bytes;
-    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable};
+    use quadratic_rust_shared::sql::schema::{SchemaColumn, SchemaTable, TableKind};
     use quadratic_rust_shared::test::get_snowflake_parquet_path;
     use tracing_test::traced_test;
     use uuid::Uuid;
@@ -128,7 +143,7 @@ mod tests {
     //     let (snowflake_connection, _) = get_connection(&state, &claims, &connection_id)
     //         .await
     //         .unwrap();
-    //     let response = test(state, axum::Json(snowflake_connection)).await;
+    //     let response = test(axum::Json(snowflake_connection)).await;
 
     //     assert!(response.0.connected);
     // }
@@ -138,9 +153,14 @@ mod tests {
     async fn snowflake_schema() {
         let connection_id = Uuid::new_v4();
         let state = Extension(new_state().await);
-        let response = schema(Path(connection_id), state, get_claims())
-            .await
-            .unwrap();
+        let response = schema(
+            Path(connection_id),
+            Query(SchemaQuery::default()),
+            state,
+            get_claims(),
+        )
+        .await
+        .unwrap();
 
         let expected = Schema {
             id: response.0.id,
@@ -150,98 +170,122 @@ mod tests {
             tables: vec![SchemaTable {
                 name: "ALL_NATIVE_DATA_TYPES".into(),
                 schema: "PUBLIC".into(),
+                kind: TableKind::Table,
                 columns: vec![
                     SchemaColumn {
                         name: "INTEGER_COL".into(),
                         r#type: "NUMBER".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "FLOAT_COL".into(),
                         r#type: "FLOAT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "NUMBER_COL".into(),
                         r#type: "NUMBER".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "DECIMAL_COL".into(),
                         r#type: "NUMBER".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "BOOLEAN_COL".into(),
                         r#type: "BOOLEAN".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "VARCHAR_COL".into(),
                         r#type: "TEXT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "CHAR_COL".into(),
                         r#type: "TEXT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "STRING_COL".into(),
                         r#type: "TEXT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "BINARY_COL".into(),
                         r#type: "BINARY".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "DATE_COL".into(),
                         r#type: "DATE".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "TIME_COL".into(),
                         r#type: "TIME".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "TIMESTAMP_NTZ_COL".into(),
                         r#type: "TIMESTAMP_NTZ".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "TIMESTAMP_LTZ_COL".into(),
                         r#type: "TIMESTAMP_LTZ".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "TIMESTAMP_TZ_COL".into(),
                         r#type: "TIMESTAMP_TZ".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "VARIANT_COL".into(),
                         r#type: "VARIANT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "OBJECT_COL".into(),
                         r#type: "OBJECT".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "ARRAY_COL".into(),
                         r#type: "ARRAY".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                     SchemaColumn {
                         name: "GEOGRAPHY_COL".into(),
                         r#type: "GEOGRAPHY".into(),
                         is_nullable: true,
+                        comment: None,
                     },
                 ],
+                primary_key: vec![],
+                foreign_keys: vec![],
+                indexes: vec![],
+                comment: None,
+                row_count: None,
             }],
         };
 
@@ -255,6 +299,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "select * from all_native_data_types;".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let state = Extension(new_state().await);
         let data = query(state, get_claims(), Json(sql_query)).await.unwrap();
@@ -274,6 +320,8 @@ mod tests {
         let sql_query = SqlQuery {
             query: "SELECT TOP 1 * FROM [dbo].[all_native_data_types] ORDER BY id".into(),
             connection_id,
+            query_id: None,
+            timeout_s: None,
         };
         let mut state = Extension(new_state().await);
         state.settings.max_response_bytes = 0;
@@ -285,4 +333,12 @@ mod tests {
         let body = response_bytes(response).await;
         assert_eq!(body, Bytes::new());
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn snowflake_write_is_unsupported() {
+        let response = write(Path(Uuid::new_v4())).await;
+
+        assert!(matches!(response, Err(ConnectionError::Query(_))));
+    }
 }
@@ -3,12 +3,17 @@
 //! Store information about the state of the application in a send + sync
 //! struct.  All access and mutations to state should be performed here.
 
+pub mod pool;
+pub mod queries;
+pub mod schema_cache;
 pub mod settings;
 pub mod stats;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use jsonwebtoken::jwk::JwkSet;
+use quadratic_rust_shared::metrics::Metrics;
 use reqwest::redirect::Policy;
 use reqwest::Client;
 use tokio::sync::Mutex;
@@ -17,6 +22,9 @@ use crate::config::Config;
 use crate::error::{proxy_error, Result};
 use crate::state::settings::Settings;
 
+use self::pool::ConnectionPool;
+use self::queries::Queries;
+use self::schema_cache::SchemaCache;
 use self::stats::Stats;
 
 #[derive(Debug, Clone)]
@@ -24,6 +32,10 @@ pub(crate) struct State {
     pub(crate) settings: Settings,
     pub(crate) client: Client,
     pub(crate) stats: Arc<Mutex<Stats>>,
+    pub(crate) queries: Arc<Mutex<Queries>>,
+    pub(crate) pool: Arc<Mutex<ConnectionPool>>,
+    pub(crate) schema_cache: Arc<Mutex<SchemaCache>>,
+    pub(crate) metrics: Arc<Metrics>,
 }
 
 impl State {
@@ -36,6 +48,14 @@ impl State {
                 .build()
                 .map_err(proxy_error)?,
             stats: Arc::new(Mutex::new(Stats::new())),
+            queries: Arc::new(Mutex::new(Queries::new())),
+            pool: Arc::new(Mutex::new(ConnectionPool::new(
+                config.pool_max_idle_per_connection,
+            ))),
+            schema_cache: Arc::new(Mutex::new(SchemaCache::new(Duration::from_secs(
+                config.schema_cache_ttl_s,
+            )))),
+            metrics: Arc::new(Metrics::new()),
         })
     }
 }
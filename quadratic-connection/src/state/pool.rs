@@ -0,0 +1,170 @@
+//! Connection Pool
+//!
+//! Keep idle database connections between requests so that queries don't pay
+//! for a new TCP/TLS/auth handshake each time.  Connections are keyed by the
+//! connection id and a hash of the credentials, so updated credentials never
+//! reuse a stale connection.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::Debug,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct PoolKey {
+    pub(crate) connection_id: Uuid,
+    pub(crate) credentials_hash: u64,
+}
+
+impl PoolKey {
+    pub(crate) fn new(connection_id: Uuid, connection: &impl Serialize) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        // all connection details are serializable, so use them as the hash input
+        serde_json::to_string(connection)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        PoolKey {
+            connection_id,
+            credentials_hash: hasher.finish(),
+        }
+    }
+}
+
+struct IdleConnection {
+    connection: Box<dyn Any + Send>,
+    idle_since: Instant,
+}
+
+pub(crate) struct ConnectionPool {
+    idle: HashMap<PoolKey, Vec<IdleConnection>>,
+    max_idle_per_key: usize,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(max_idle_per_key: usize) -> Self {
+        ConnectionPool {
+            idle: HashMap::new(),
+            max_idle_per_key,
+        }
+    }
+
+    /// Take the most recently used idle connection for the key, if any.
+    pub(crate) fn checkout<C: Send + 'static>(&mut self, key: &PoolKey) -> Option<C> {
+        let idle = self.idle.get_mut(key)?;
+        let connection = idle.pop()?.connection.downcast::<C>().ok()?;
+
+        if idle.is_empty() {
+            self.idle.remove(key);
+        }
+
+        Some(*connection)
+    }
+
+    /// Return a healthy connection to the pool.  The oldest idle connection
+    /// is dropped if the key is already at capacity.
+    pub(crate) fn checkin<C: Send + 'static>(&mut self, key: PoolKey, connection: C) {
+        let idle = self.idle.entry(key).or_default();
+
+        idle.push(IdleConnection {
+            connection: Box::new(connection),
+            idle_since: Instant::now(),
+        });
+
+        if idle.len() > self.max_idle_per_key {
+            idle.remove(0);
+        }
+    }
+
+    /// Drop connections that have been idle for longer than `idle_timeout`.
+    ///
+    /// Returns the number of connections that were dropped.
+    pub(crate) fn evict_idle(&mut self, idle_timeout: Duration) -> usize {
+        let before = self.len();
+
+        self.idle.retain(|_, idle| {
+            idle.retain(|connection| connection.idle_since.elapsed() < idle_timeout);
+            !idle.is_empty()
+        });
+
+        before - self.len()
+    }
+
+    /// The number of idle connections across all keys.
+    pub(crate) fn len(&self) -> usize {
+        self.idle.values().map(Vec::len).sum()
+    }
+}
+
+impl Debug for ConnectionPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("keys", &self.idle.len())
+            .field("idle", &self.len())
+            .field("max_idle_per_key", &self.max_idle_per_key)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Credentials {
+        password: &'static str,
+    }
+
+    #[test]
+    fn keys_include_credentials() {
+        let connection_id = Uuid::new_v4();
+        let key = PoolKey::new(connection_id, &Credentials { password: "a" });
+
+        assert_eq!(
+            key,
+            PoolKey::new(connection_id, &Credentials { password: "a" })
+        );
+        assert_ne!(
+            key,
+            PoolKey::new(connection_id, &Credentials { password: "b" })
+        );
+    }
+
+    #[test]
+    fn checks_connections_in_and_out() {
+        let mut pool = ConnectionPool::new(2);
+        let key = PoolKey::new(Uuid::new_v4(), &Credentials { password: "a" });
+
+        assert_eq!(pool.checkout::<String>(&key), None);
+
+        pool.checkin(key, "one".to_string());
+        pool.checkin(key, "two".to_string());
+        pool.checkin(key, "three".to_string());
+
+        // the oldest connection is dropped when over capacity
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.checkout::<String>(&key), Some("three".into()));
+        assert_eq!(pool.checkout::<String>(&key), Some("two".into()));
+        assert_eq!(pool.checkout::<String>(&key), None);
+    }
+
+    #[test]
+    fn evicts_idle_connections() {
+        let mut pool = ConnectionPool::new(2);
+        let key = PoolKey::new(Uuid::new_v4(), &Credentials { password: "a" });
+
+        pool.checkin(key, "one".to_string());
+
+        assert_eq!(pool.evict_idle(Duration::from_secs(60)), 0);
+        assert_eq!(pool.evict_idle(Duration::ZERO), 1);
+        assert_eq!(pool.len(), 0);
+    }
+}
@@ -0,0 +1,153 @@
+//! Running Queries
+//!
+//! Track queries while they are executing so that they can be cancelled by id.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{Mutex, Notify};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::error::{ConnectionError, Result};
+
+#[derive(Debug)]
+pub(crate) struct RunningQuery {
+    pub(crate) connection_id: Uuid,
+    pub(crate) user_id: String,
+    pub(crate) started: Instant,
+    pub(crate) cancel: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Queries {
+    running: HashMap<Uuid, RunningQuery>,
+}
+
+impl Queries {
+    pub(crate) fn new() -> Self {
+        Queries::default()
+    }
+
+    /// Register a query as running.  The returned `Notify` is signaled when
+    /// the query is cancelled.
+    pub(crate) fn register(
+        &mut self,
+        id: Uuid,
+        connection_id: Uuid,
+        user_id: &str,
+    ) -> Result<Arc<Notify>> {
+        if self.running.contains_key(&id) {
+            return Err(ConnectionError::Query(format!(
+                "Query {id} is already running"
+            )));
+        }
+
+        let cancel = Arc::new(Notify::new());
+        let query = RunningQuery {
+            connection_id,
+            user_id: user_id.to_owned(),
+            started: Instant::now(),
+            cancel: Arc::clone(&cancel),
+        };
+        self.running.insert(id, query);
+
+        Ok(cancel)
+    }
+
+    pub(crate) fn remove(&mut self, id: &Uuid) -> Option<RunningQuery> {
+        self.running.remove(id)
+    }
+
+    /// Signal a running query to stop.  Only the user that started the query
+    /// can cancel it.
+    pub(crate) fn cancel(&mut self, id: &Uuid, user_id: &str) -> Result<()> {
+        let query = self
+            .running
+            .get(id)
+            .filter(|query| query.user_id == user_id)
+            .ok_or_else(|| ConnectionError::QueryNotFound(id.to_string()))?;
+
+        tracing::info!(
+            "Cancelling query {id} on connection {} after {:?}",
+            query.connection_id,
+            query.started.elapsed()
+        );
+
+        // stores a permit if the query isn't awaiting yet, so it's never missed
+        query.cancel.notify_one();
+
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.running.len()
+    }
+}
+
+/// Removes a query from the running queries when dropped, which also covers
+/// requests that are dropped because the client went away.
+pub(crate) struct RunningQueryGuard {
+    queries: Arc<Mutex<Queries>>,
+    id: Uuid,
+}
+
+impl RunningQueryGuard {
+    pub(crate) fn new(queries: Arc<Mutex<Queries>>, id: Uuid) -> Self {
+        RunningQueryGuard { queries, id }
+    }
+}
+
+impl Drop for RunningQueryGuard {
+    fn drop(&mut self) {
+        let queries = Arc::clone(&self.queries);
+        let id = self.id;
+
+        tokio::spawn(async move {
+            queries.lock().await.remove(&id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn registers_and_cancels_a_query() {
+        let mut queries = Queries::new();
+        let id = Uuid::new_v4();
+        let cancel = queries.register(id, Uuid::new_v4(), "user").unwrap();
+
+        // ids must be unique while running
+        assert!(queries.register(id, Uuid::new_v4(), "user").is_err());
+
+        // only the owner can cancel
+        assert_eq!(
+            queries.cancel(&id, "other"),
+            Err(ConnectionError::QueryNotFound(id.to_string()))
+        );
+
+        queries.cancel(&id, "user").unwrap();
+        cancel.notified().await;
+
+        queries.remove(&id);
+        assert_eq!(queries.len(), 0);
+        assert!(queries.cancel(&id, "user").is_err());
+    }
+
+    #[tokio::test]
+    async fn guard_removes_the_query() {
+        let queries = Arc::new(Mutex::new(Queries::new()));
+        let id = Uuid::new_v4();
+        queries
+            .lock()
+            .await
+            .register(id, Uuid::new_v4(), "user")
+            .unwrap();
+
+        drop(RunningQueryGuard::new(Arc::clone(&queries), id));
+        tokio::task::yield_now().await;
+
+        assert_eq!(queries.lock().await.len(), 0);
+    }
+}
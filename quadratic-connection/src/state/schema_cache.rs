@@ -0,0 +1,97 @@
+//! Schema Cache
+//!
+//! Introspecting a database schema can be slow on large warehouses, so keep
+//! the results for a configurable TTL.  Entries share the connection pool's
+//! key, so changing credentials bypasses the cache, and filtered schemas are
+//! cached separately from the full schema.
+
+use std::{collections::HashMap, time::Duration};
+
+use quadratic_rust_shared::sql::schema::{DatabaseSchema, SchemaFilter};
+use tokio::time::Instant;
+
+use super::pool::PoolKey;
+
+#[derive(Debug)]
+pub(crate) struct SchemaCache {
+    entries: HashMap<(PoolKey, SchemaFilter), (DatabaseSchema, Instant)>,
+    ttl: Duration,
+}
+
+impl SchemaCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        SchemaCache {
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// Get a copy of the schema if it was cached within the TTL.
+    pub(crate) fn get(&self, key: &PoolKey, filter: &SchemaFilter) -> Option<DatabaseSchema> {
+        self.entries
+            .get(&(*key, filter.to_owned()))
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            .map(|(schema, _)| schema.to_owned())
+    }
+
+    pub(crate) fn insert(&mut self, key: PoolKey, filter: SchemaFilter, schema: DatabaseSchema) {
+        self.entries.insert((key, filter), (schema, Instant::now()));
+    }
+
+    /// Remove entries that are older than the TTL.
+    pub(crate) fn evict_expired(&mut self) {
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn schema() -> DatabaseSchema {
+        DatabaseSchema {
+            database: "test".into(),
+            tables: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn caches_schemas_within_the_ttl() {
+        let key = PoolKey::new(Uuid::new_v4(), &"credentials");
+        let mut cache = SchemaCache::new(Duration::from_secs(60));
+
+        let filter = SchemaFilter::default();
+        assert_eq!(cache.get(&key, &filter), None);
+
+        cache.insert(key, filter.clone(), schema());
+        assert_eq!(cache.get(&key, &filter), Some(schema()));
+
+        // filtered schemas are cached separately
+        let table_filter = SchemaFilter {
+            schema: None,
+            table: Some("users".into()),
+        };
+        assert_eq!(cache.get(&key, &table_filter), None);
+
+        cache.evict_expired();
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    #[test]
+    fn expires_schemas_after_the_ttl() {
+        let key = PoolKey::new(Uuid::new_v4(), &"credentials");
+        let mut cache = SchemaCache::new(Duration::ZERO);
+
+        cache.insert(key, SchemaFilter::default(), schema());
+        assert_eq!(cache.get(&key, &SchemaFilter::default()), None);
+
+        cache.evict_expired();
+        assert_eq!(cache.entries.len(), 0);
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use jsonwebtoken::jwk::JwkSet;
 
 use crate::config::Config;
@@ -8,6 +10,11 @@ pub(crate) struct Settings {
     pub(crate) _m2m_auth_token: String,
     pub(crate) jwks: Option<JwkSet>,
     pub(crate) max_response_bytes: u64,
+    pub(crate) query_timeout_default: Duration,
+    pub(crate) query_timeout_max: Duration,
+    pub(crate) pool_idle_timeout: Duration,
+    pub(crate) max_write_bytes: usize,
+    pub(crate) write_batch_size: usize,
 }
 
 impl Settings {
@@ -17,6 +24,36 @@ impl Settings {
             _m2m_auth_token: config.m2m_auth_token.to_owned(),
             jwks,
             max_response_bytes: config.max_response_bytes,
+            query_timeout_default: Duration::from_secs(config.query_timeout_default_s),
+            query_timeout_max: Duration::from_secs(config.query_timeout_max_s),
+            pool_idle_timeout: Duration::from_secs(config.pool_idle_timeout_s),
+            max_write_bytes: config.max_write_bytes,
+            write_batch_size: config.write_batch_size,
         }
     }
+
+    /// The timeout for a query, using the default if one isn't requested and
+    /// never exceeding the maximum.
+    pub(crate) fn query_timeout(&self, requested_s: Option<u64>) -> Duration {
+        requested_s
+            .map(Duration::from_secs)
+            .unwrap_or(self.query_timeout_default)
+            .min(self.query_timeout_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config;
+
+    #[test]
+    fn caps_the_query_timeout() {
+        let settings = Settings::new(&config().unwrap(), None);
+        let max = settings.query_timeout_max;
+
+        assert_eq!(settings.query_timeout(None), settings.query_timeout_default);
+        assert_eq!(settings.query_timeout(Some(1)), Duration::from_secs(1));
+        assert_eq!(settings.query_timeout(Some(max.as_secs() + 1)), max);
+    }
 }
@@ -1,27 +1,51 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use tokio::time::Instant;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Stats {
     pub(crate) last_query_time: Option<Instant>,
+    pub(crate) num_cancelled_queries: u64,
+    pub(crate) num_timed_out_queries: u64,
+    pub(crate) pool_hits: u64,
+    pub(crate) pool_misses: u64,
+    pub(crate) schema_cache_hits: u64,
+    pub(crate) schema_cache_misses: u64,
 }
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub(crate) struct StatsResponse {
     pub(crate) last_processed_query: String,
+    pub(crate) num_cancelled_queries: u64,
+    pub(crate) num_timed_out_queries: u64,
+    pub(crate) pool_hits: u64,
+    pub(crate) pool_misses: u64,
+    pub(crate) schema_cache_hits: u64,
+    pub(crate) schema_cache_misses: u64,
 }
 
-impl Display for Stats {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let last_processed_query = match self.last_query_time {
+impl From<&Stats> for StatsResponse {
+    fn from(stats: &Stats) -> Self {
+        let last_processed_query = match stats.last_query_time {
             Some(time) => format!("{:?} seconds ago", time.elapsed().as_secs()),
             None => "No queries processed yet".to_string(),
         };
 
-        let stats = StatsResponse {
+        StatsResponse {
             last_processed_query,
-        };
+            num_cancelled_queries: stats.num_cancelled_queries,
+            num_timed_out_queries: stats.num_timed_out_queries,
+            pool_hits: stats.pool_hits,
+            pool_misses: stats.pool_misses,
+            schema_cache_hits: stats.schema_cache_hits,
+            schema_cache_misses: stats.schema_cache_misses,
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = StatsResponse::from(self);
 
         write!(
             f,
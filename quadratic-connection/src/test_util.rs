@@ -63,6 +63,8 @@ pub(crate) fn _new_postgres_connection() -> PostgresConnection {
         "0.0.0.0".into(),
         Some("5432".into()),
         "postgres".into(),
+        false,
+        true,
     )
 }
 
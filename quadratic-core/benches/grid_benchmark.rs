@@ -1,9 +1,10 @@
 use criterion::{criterion_group, criterion_main, Bencher, Criterion};
+use quadratic_core::arrow_ipc::{arrow_ipc_to_vec, vec_to_arrow_ipc};
 use quadratic_core::controller::operations::clipboard::PasteSpecial;
 use quadratic_core::controller::GridController;
 use quadratic_core::grid::js_types::JsClipboard;
-use quadratic_core::grid::{CellAlign, Grid};
-use quadratic_core::{A1Selection, Pos, Rect, SheetRect};
+use quadratic_core::grid::{CellAlign, CellsAccessed, CodeRun, CodeRunResult, Grid};
+use quadratic_core::{A1Selection, Array, CellValue, Pos, Rect, SheetPos, SheetRect, Value};
 use std::time::Duration;
 
 criterion_group!(benches, criterion_benchmark);
@@ -311,6 +312,168 @@ fn criterion_benchmark(c: &mut Criterion) {
         )
     });
 
+    let dependency_inputs = vec![
+        ("1000_code_cells", grid_with_dependencies(1000)),
+        ("10000_code_cells", grid_with_dependencies(10000)),
+    ];
+
+    benchmark_grids(
+        c,
+        &dependency_inputs,
+        "get_dependent_code_cells",
+        |b, grid| {
+            let gc = GridController::from_grid(grid.clone(), 0);
+            let sheet_id = gc.sheet_ids()[0];
+            let sheet_rect = SheetRect::single_pos(Pos { x: 1, y: 500 }, sheet_id);
+            b.iter(|| gc.get_dependent_code_cells(&sheet_rect));
+        },
+    );
+
+    benchmark_grids(
+        c,
+        &dependency_inputs,
+        "get_dependent_code_cells_in_order",
+        |b, grid| {
+            let gc = GridController::from_grid(grid.clone(), 0);
+            let sheet_id = gc.sheet_ids()[0];
+            let sheet_rect = SheetRect::single_pos(Pos { x: 1, y: 500 }, sheet_id);
+            b.iter(|| gc.get_dependent_code_cells_in_order(&sheet_rect, None));
+        },
+    );
+
+    benchmark_grids(
+        c,
+        &dependency_inputs,
+        "set_cell_value_near_dependents",
+        |b, grid| {
+            let mut gc = GridController::from_grid(grid.clone(), 0);
+            let sheet_id = gc.sheet_ids()[0];
+            b.iter(|| {
+                gc.set_cell_value(
+                    SheetPos {
+                        x: 3,
+                        y: 500,
+                        sheet_id,
+                    },
+                    "1".to_string(),
+                    None,
+                )
+            });
+        },
+    );
+
+    // a 1M-cell code result (100 columns x 10,000 rows), sent to core as
+    // strings or as an Arrow IPC stream
+    let mut group = c.benchmark_group("code_result_1m_cells");
+    group.measurement_time(Duration::new(10, 0));
+    group.sample_size(10);
+    let columns = code_result_columns(100, 10_000);
+    let string_list = (0..10_000)
+        .map(|y| {
+            columns
+                .iter()
+                .map(|column| vec![column[y].to_string(), "number".to_string()])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let headers = (0..100).map(|x| format!("column {x}")).collect::<Vec<_>>();
+    let ipc = vec_to_arrow_ipc(headers.clone(), columns.clone(), Default::default()).unwrap();
+    group.bench_function("string_list", |b| {
+        let mut grid = Grid::new();
+        b.iter(|| {
+            Array::from_string_list(
+                Pos { x: 1, y: 1 },
+                grid.first_sheet_mut(),
+                string_list.clone(),
+            )
+        });
+    });
+    group.bench_function("arrow_ipc", |b| {
+        b.iter(|| Array::from(arrow_ipc_to_vec(&ipc).unwrap()));
+    });
+
+    // the same result with numbers, decimals, text and logicals mixed in each
+    // column, which is sent as a union of the types
+    let mixed_columns = mixed_code_result_columns(100, 10_000);
+    let mixed_string_list = (0..10_000)
+        .map(|y| {
+            mixed_columns
+                .iter()
+                .map(|column| {
+                    let type_name = match column[y] {
+                        CellValue::Number(_) => "number",
+                        CellValue::Logical(_) => "logical",
+                        _ => "text",
+                    };
+                    vec![column[y].to_string(), type_name.to_string()]
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mixed_ipc =
+        vec_to_arrow_ipc(headers.clone(), mixed_columns.clone(), Default::default()).unwrap();
+    group.bench_function("string_list_mixed", |b| {
+        let mut grid = Grid::new();
+        b.iter(|| {
+            Array::from_string_list(
+                Pos { x: 1, y: 1 },
+                grid.first_sheet_mut(),
+                mixed_string_list.clone(),
+            )
+        });
+    });
+    group.bench_function("arrow_ipc_mixed", |b| {
+        b.iter(|| Array::from(arrow_ipc_to_vec(&mixed_ipc).unwrap()));
+    });
+    group.bench_function("arrow_ipc_mixed_encode", |b| {
+        b.iter(|| {
+            vec_to_arrow_ipc(headers.clone(), mixed_columns.clone(), Default::default()).unwrap()
+        });
+    });
+    group.finish();
+
+    // reading 1M cells from a sheet for code, as strings or as an Arrow IPC
+    // stream
+    let mut group = c.benchmark_group("get_cells_1m_cells");
+    group.measurement_time(Duration::new(10, 0));
+    group.sample_size(10);
+    let mut grid = Grid::new();
+    let sheet = grid.first_sheet_mut();
+    for (x, column) in columns.iter().enumerate() {
+        for (y, value) in column.iter().enumerate() {
+            sheet.set_cell_value(
+                Pos {
+                    x: x as i64 + 1,
+                    y: y as i64 + 1,
+                },
+                value.clone(),
+            );
+        }
+    }
+    let rect = Rect::new(1, 1, 100, 10_000);
+    group.bench_function("string_list", |b| {
+        b.iter(|| serde_json::to_string(&grid.first_sheet().get_cells_response(rect)).unwrap());
+    });
+    group.bench_function("arrow_ipc", |b| {
+        b.iter(|| {
+            let sheet = grid.first_sheet();
+            let columns = rect
+                .x_range()
+                .map(|x| {
+                    rect.y_range()
+                        .map(|y| {
+                            sheet
+                                .display_value(Pos { x, y })
+                                .unwrap_or(CellValue::Blank)
+                        })
+                        .collect()
+                })
+                .collect();
+            vec_to_arrow_ipc(headers.clone(), columns, Default::default()).unwrap()
+        });
+    });
+    group.finish();
+
     benchmark_grids(c, &inputs, "import_small_csv", |b, grid| {
         const SIMPLE_CSV: &str = r#"city,region,country,population
         Southborough,MA,United States,9686
@@ -348,6 +511,72 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
+/// Columns of numbers for a code result.
+fn code_result_columns(width: usize, height: usize) -> Vec<Vec<CellValue>> {
+    (0..width)
+        .map(|x| {
+            (0..height)
+                .map(|y| CellValue::Number(((x * height + y) as i64).into()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Columns that mix integers, decimals, text and logicals for a code result.
+fn mixed_code_result_columns(width: usize, height: usize) -> Vec<Vec<CellValue>> {
+    (0..width)
+        .map(|x| {
+            (0..height)
+                .map(|y| {
+                    let index = (x * height + y) as i64;
+                    match y % 4 {
+                        0 => CellValue::Number(index.into()),
+                        1 => CellValue::Number(bigdecimal::BigDecimal::new(index.into(), 3)),
+                        2 => CellValue::Text(format!("text {index}")),
+                        _ => CellValue::Logical(index % 2 == 0),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A grid where each of `count` code cells in column B accesses the cell to
+/// its left, and every tenth code cell also accesses all of column A.
+fn grid_with_dependencies(count: i64) -> Grid {
+    let mut grid = Grid::new();
+    let sheet = grid.first_sheet_mut();
+    let sheet_id = sheet.id;
+
+    for y in 1..=count {
+        let mut cells_accessed = CellsAccessed::default();
+        cells_accessed.add_sheet_pos(SheetPos { x: 1, y, sheet_id });
+        if y % 10 == 0 {
+            cells_accessed.add(sheet_id, "A".parse().unwrap());
+        }
+
+        sheet.set_code_run(
+            Pos { x: 2, y },
+            Some(CodeRun {
+                formatted_code_string: None,
+                std_out: None,
+                std_err: None,
+                cells_accessed,
+                result: CodeRunResult::Ok(Value::Single(CellValue::Number(y.into()))),
+                return_type: None,
+                spill_error: false,
+                line_number: None,
+                output_type: None,
+                last_modified: chrono::Utc::now(),
+                stale: false,
+                timing: None,
+            }),
+        );
+    }
+
+    grid
+}
+
 fn benchmark_grids(
     c: &mut Criterion,
     inputs: &[(&str, Grid)],
@@ -11,6 +11,9 @@ impl A1Selection {
                     CellRefRange::Sheet { range } => {
                         range.end = RefRangeBounds::ALL.end;
                     }
+                    CellRefRange::Table { .. } => {
+                        self.ranges.push(CellRefRange::ALL);
+                    }
                 }
             }
         } else {
@@ -27,7 +30,7 @@ impl A1Selection {
             let mut ranges = vec![];
             self.ranges.iter().for_each(|range| {
                 if !range.has_column_range(col) {
-                    ranges.push(*range);
+                    ranges.push(range.clone());
                 } else {
                     match range {
                         CellRefRange::Sheet { mut range } => {
@@ -77,6 +80,7 @@ impl A1Selection {
                                 ranges.push(second);
                             };
                         }
+                        CellRefRange::Table { .. } => ranges.push(range.clone()),
                     }
                 }
             });
@@ -120,6 +124,11 @@ impl A1Selection {
                         self.cursor.y = range.start.row();
                     }
                 }
+                CellRefRange::Table { .. } => {
+                    self.ranges.push(CellRefRange::new_relative_column(col));
+                    self.cursor.x = col;
+                    self.cursor.y = top;
+                }
             }
         } else {
             self.ranges.push(CellRefRange::new_relative_column(col));
@@ -136,7 +145,7 @@ impl A1Selection {
             let mut ranges = vec![];
             self.ranges.iter().for_each(|range| {
                 if !range.has_row_range(row) {
-                    ranges.push(*range);
+                    ranges.push(range.clone());
                 } else {
                     match range {
                         CellRefRange::Sheet { mut range } => {
@@ -181,6 +190,7 @@ impl A1Selection {
                                 ranges.push(second);
                             };
                         }
+                        CellRefRange::Table { .. } => ranges.push(range.clone()),
                     }
                 }
             });
@@ -273,6 +283,11 @@ impl A1Selection {
                         row: CellRefCoord::new_rel(row),
                     };
                 }
+                CellRefRange::Table { .. } => {
+                    self.ranges.push(CellRefRange::new_relative_row(row));
+                    self.cursor.x = left;
+                    self.cursor.y = row;
+                }
             }
         } else {
             self.ranges.push(CellRefRange::new_relative_row(row));
@@ -365,6 +380,18 @@ impl A1Selection {
                         self.cursor.x = column;
                     }
                 }
+                // a table can't be extended, so it's replaced by the range
+                // from the cursor
+                CellRefRange::Table { .. } => {
+                    *last = CellRefRange::Sheet {
+                        range: RefRangeBounds::new_relative(
+                            self.cursor.x,
+                            self.cursor.y,
+                            column,
+                            row,
+                        ),
+                    };
+                }
             }
         }
         if !append {
@@ -380,6 +407,7 @@ impl A1Selection {
         };
         let last = match last {
             CellRefRange::Sheet { range } => *range,
+            CellRefRange::Table { .. } => return,
         };
         self.ranges.clear();
         self.ranges.push(CellRefRange::Sheet {
@@ -398,6 +426,7 @@ impl A1Selection {
         };
         let last = match last {
             CellRefRange::Sheet { range } => *range,
+            CellRefRange::Table { .. } => return,
         };
         self.ranges.clear();
         self.ranges.push(CellRefRange::Sheet {
@@ -4,7 +4,7 @@ use std::ops::RangeInclusive;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use super::{CellRefRange, SheetCellRefRange};
+use super::{CellRefRange, RefRangeBounds, SheetCellRefRange};
 use crate::{
     grid::SheetId, selection::OldSelection, A1Error, Pos, SheetNameIdMap, SheetPos, SheetRect,
 };
@@ -19,9 +19,16 @@ pub mod a1_selection_select;
 pub struct A1Selection {
     /// Current sheet.
     ///
-    /// Selections can only span a single sheet.
+    /// Selections span a single sheet, except for 3D references (see
+    /// `last_sheet_id`).
     #[cfg_attr(test, proptest(value = "SheetId::test()"))]
     pub sheet_id: SheetId,
+    /// Last sheet of a 3D reference such as `Jan:Dec!B5`, which covers
+    /// `sheet_id`, `last_sheet_id` and every sheet between them in sheet
+    /// order. `None` for a selection on a single sheet.
+    #[serde(default)]
+    #[cfg_attr(test, proptest(value = "None"))]
+    pub last_sheet_id: Option<SheetId>,
     /// Cursor position, which is moved using the arrow keys (while not holding
     /// shift).
     ///
@@ -79,6 +86,7 @@ impl From<OldSelection> for A1Selection {
 
         Self {
             sheet_id,
+            last_sheet_id: None,
             cursor: Pos { x, y },
             ranges,
         }
@@ -90,6 +98,7 @@ impl A1Selection {
     pub fn from_range(range: CellRefRange, sheet: SheetId) -> Self {
         Self {
             sheet_id: sheet,
+            last_sheet_id: None,
             cursor: cursor_pos_from_last_range(&range),
             ranges: vec![range],
         }
@@ -153,6 +162,7 @@ impl A1Selection {
         let cursor = cursor_pos_from_last_range(last_range);
         Self {
             sheet_id: sheet,
+            last_sheet_id: None,
             cursor,
             ranges,
         }
@@ -175,18 +185,35 @@ impl A1Selection {
     /// Parses a selection from a comma-separated list of ranges.
     ///
     /// Returns an error if ranges refer to different sheets. Ranges without an
-    /// explicit sheet use `default_sheet_id`.
+    /// explicit sheet use `default_sheet_id`. A 3D reference such as
+    /// `Jan:Dec!B5` sets `last_sheet_id`.
+    ///
+    /// Ranges may also use absolute R1C1 notation such as `R5C2`. Relative
+    /// R1C1 references such as `R[-1]C[2]` need a base position; see
+    /// [`Self::from_str_relative_to()`].
     pub fn from_str(
         a1: &str,
         default_sheet_id: &SheetId,
         sheet_map: &SheetNameIdMap,
     ) -> Result<Self, A1Error> {
-        let mut sheet = None;
+        Self::from_str_relative_to(a1, default_sheet_id, sheet_map, None)
+    }
+
+    /// Parses a selection like [`Self::from_str()`], resolving relative R1C1
+    /// references such as `R[-1]C[2]` from `base`.
+    pub fn from_str_relative_to(
+        a1: &str,
+        default_sheet_id: &SheetId,
+        sheet_map: &SheetNameIdMap,
+        base: Option<Pos>,
+    ) -> Result<Self, A1Error> {
+        let mut sheets = None;
         let mut ranges = vec![];
 
         let mut segments = Vec::new();
         let mut current_segment = String::new();
         let mut in_quotes = false;
+        let mut bracket_depth = 0;
 
         for (i, c) in a1.trim().chars().enumerate() {
             match c {
@@ -197,7 +224,17 @@ impl A1Selection {
                     in_quotes = !in_quotes;
                     current_segment.push(c);
                 }
-                ',' if !in_quotes => {
+                // commas inside a table reference, such as
+                // `Sales[[#Headers],[Amount]]`, don't separate ranges
+                '[' if !in_quotes => {
+                    bracket_depth += 1;
+                    current_segment.push(c);
+                }
+                ']' if !in_quotes => {
+                    bracket_depth -= 1;
+                    current_segment.push(c);
+                }
+                ',' if !in_quotes && bracket_depth == 0 => {
                     if !current_segment.is_empty() {
                         segments.push(current_segment);
                         current_segment = String::new();
@@ -212,22 +249,30 @@ impl A1Selection {
         }
 
         for segment in segments {
-            let range = SheetCellRefRange::from_str(segment.trim(), default_sheet_id, sheet_map)?;
-            if *sheet.get_or_insert(range.sheet) != range.sheet {
+            let (sheet, last_sheet, cells) = super::parse_optional_sheet_span_to_ids(
+                segment.trim(),
+                default_sheet_id,
+                sheet_map,
+            )?;
+            if *sheets.get_or_insert((sheet, last_sheet)) != (sheet, last_sheet) {
                 return Err(A1Error::TooManySheets(a1.to_string()));
             }
 
-            ranges.push(range.cells);
+            ranges.push(match RefRangeBounds::from_r1c1_str(cells, base)? {
+                Some(range) => CellRefRange::Sheet { range },
+                None => cells.parse::<CellRefRange>()?,
+            });
         }
 
         let last_range = ranges
             .last()
-            .copied()
             .ok_or_else(|| A1Error::InvalidRange(a1.to_string()))?;
+        let (sheet_id, last_sheet_id) = sheets.unwrap_or((default_sheet_id.to_owned(), None));
 
         Ok(Self {
-            sheet_id: sheet.unwrap_or(default_sheet_id.to_owned()),
-            cursor: cursor_pos_from_last_range(&last_range),
+            sheet_id,
+            last_sheet_id,
+            cursor: cursor_pos_from_last_range(last_range),
             ranges,
         })
     }
@@ -236,16 +281,38 @@ impl A1Selection {
     /// included in the output only if `default_sheet_id` is `None` or differs
     /// from the ID of the sheet containing the range.
     ///
-    /// The cursor position has no effect on the output.
+    /// The cursor position has no effect on the output. A 3D reference always
+    /// includes its sheet names.
     pub fn to_string(
         &self,
         default_sheet_id: Option<SheetId>,
         sheet_map: &SheetNameIdMap,
     ) -> String {
         let sheet = self.sheet_id;
+        if let Some(last_sheet) = self.last_sheet_id {
+            let sheet_name = |sheet_id: SheetId| {
+                sheet_map
+                    .iter()
+                    .find(|(_, id)| **id == sheet_id)
+                    .map_or(super::UNKNOWN_SHEET_NAME, |(name, _)| name.as_str())
+            };
+            let sheet_span = super::quote_sheet_span(sheet_name(sheet), sheet_name(last_sheet));
+            return self
+                .ranges
+                .iter()
+                .map(|cells| format!("{sheet_span}!{cells}"))
+                .collect::<Vec<_>>()
+                .join(",");
+        }
         self.ranges
             .iter()
-            .map(|&cells| SheetCellRefRange { sheet, cells }.to_string(default_sheet_id, sheet_map))
+            .map(|cells| {
+                SheetCellRefRange {
+                    sheet,
+                    cells: cells.clone(),
+                }
+                .to_string(default_sheet_id, sheet_map)
+            })
             .collect::<Vec<_>>()
             .join(",")
     }
@@ -278,14 +345,17 @@ impl A1Selection {
                                 });
                             }
                         }
+                        CellRefRange::Table { .. } => (),
                     });
             }
+            CellRefRange::Table { .. } => (),
         });
         if ranges.is_empty() {
             None
         } else {
             let mut result = Self {
                 sheet_id: self.sheet_id,
+                last_sheet_id: self.last_sheet_id,
                 cursor: self.cursor,
                 ranges,
             };
@@ -325,8 +395,14 @@ impl A1Selection {
                     CellRefRange::Sheet { range: other_range } => {
                         range.intersection(other_range).is_some()
                     }
+                    CellRefRange::Table { .. } => false,
                 })
             }
+            // unresolved table references only overlap the same reference
+            CellRefRange::Table { range } => other
+                .ranges
+                .iter()
+                .any(|other_range| other_range.as_table_ref() == Some(range)),
         })
     }
 
@@ -376,6 +452,8 @@ fn cursor_pos_from_last_range(last_range: &CellRefRange) -> Pos {
             let y = range.start.row();
             Pos { x, y }
         }
+        // the cursor is moved to the table once the reference is resolved
+        CellRefRange::Table { .. } => pos![A1],
     }
 }
 
@@ -576,6 +654,7 @@ mod tests {
     fn test_to_a1_pos() {
         let selection = A1Selection {
             sheet_id: SheetId::test(),
+            last_sheet_id: None,
             cursor: pos![A1],
             ranges: vec![CellRefRange::new_relative_rect(Rect::new(1, 1, 1, 1))],
         };
@@ -589,6 +668,7 @@ mod tests {
     fn test_to_a1() {
         let selection = A1Selection {
             sheet_id: SheetId::test(),
+            last_sheet_id: None,
             cursor: Pos { x: 10, y: 11 }, // this should be ignored
             ranges: vec![
                 CellRefRange::new_relative_column_range(1, 5),
@@ -609,6 +689,7 @@ mod tests {
     fn test_a1_with_one_sized_rect() {
         let selection = A1Selection {
             sheet_id: SheetId::test(),
+            last_sheet_id: None,
             cursor: Pos { x: 1, y: 1 },
             ranges: vec![CellRefRange::test_a1("A1:A1")],
         };
@@ -663,6 +744,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_3d_reference() {
+        let jan = SheetId::test();
+        let dec = SheetId::new();
+        let map = HashMap::from([("Jan".to_string(), jan), ("Dec".to_string(), dec)]);
+        let selection = A1Selection::from_str("Jan:Dec!B5,Jan:Dec!C6", &jan, &map).unwrap();
+        assert_eq!(selection.sheet_id, jan);
+        assert_eq!(selection.last_sheet_id, Some(dec));
+        assert_eq!(
+            selection.ranges,
+            vec![CellRefRange::test_a1("B5"), CellRefRange::test_a1("C6")]
+        );
+        assert_eq!(
+            selection.to_string(Some(jan), &map),
+            "Jan:Dec!B5,Jan:Dec!C6"
+        );
+
+        assert_eq!(
+            A1Selection::from_str("Jan:Dec!B5,C6", &jan, &map),
+            Err(A1Error::TooManySheets("Jan:Dec!B5,C6".to_string())),
+        );
+    }
+
+    #[test]
+    fn test_r1c1() {
+        let sheet_id = SheetId::test();
+        let map = HashMap::from([("Sheet1".to_string(), sheet_id)]);
+
+        let selection = A1Selection::from_str("Sheet1!R5C2:R6C3,R1C1", &sheet_id, &map).unwrap();
+        assert_eq!(selection.to_string(Some(sheet_id), &map), "$B$5:$C$6,$A$1");
+
+        let selection = A1Selection::from_str_relative_to(
+            "R[-1]C[2],RC[-1]:R[1]C",
+            &sheet_id,
+            &map,
+            Some(pos![B5]),
+        )
+        .unwrap();
+        assert_eq!(selection.to_string(Some(sheet_id), &map), "D4,A5:B6");
+        assert_eq!(selection.cursor, pos![A5]);
+
+        assert_eq!(
+            A1Selection::from_str("R[-1]C[2]", &sheet_id, &map),
+            Err(A1Error::InvalidCellReference("R[-1]C[2]".to_string())),
+        );
+    }
+
     #[test]
     fn test_cursor_a1_string() {
         // Test basic cursor position
@@ -46,19 +46,50 @@ pub(crate) fn parse_optional_sheet_name_to_id<'a>(
     let (sheet_name, rest) = parse_optional_sheet_name(a1)?;
     let sheet_id = match sheet_name {
         Some(sheet_name) => {
-            let folded_name = sheet_name.to_lowercase();
-            **sheet_map
-                .iter()
-                .map(|(k, v)| (k.to_lowercase(), v))
-                .collect::<HashMap<_, _>>()
-                .get(&folded_name)
-                .ok_or(A1Error::InvalidSheetName(sheet_name))?
+            sheet_name_to_id(&sheet_name, sheet_map).ok_or(A1Error::InvalidSheetName(sheet_name))?
         }
         None => default_sheet_id.to_owned(),
     };
     Ok((sheet_id, rest))
 }
 
+/// Parses the sheet name from an A1 string, which may be a 3D reference to a
+/// span of sheets such as `Jan:Dec!B5`. Returns the IDs of the first and last
+/// sheets (the last is `None` unless the string is a 3D reference), and the
+/// rest of the string.
+///
+/// Sheet names may contain `:`, so an exact sheet name takes precedence.
+pub(crate) fn parse_optional_sheet_span_to_ids<'a>(
+    a1: &'a str,
+    default_sheet_id: &SheetId,
+    sheet_map: &SheetNameIdMap,
+) -> Result<(SheetId, Option<SheetId>, &'a str), A1Error> {
+    let (sheet_name, rest) = parse_optional_sheet_name(a1)?;
+    let Some(sheet_name) = sheet_name else {
+        return Ok((default_sheet_id.to_owned(), None, rest));
+    };
+    if let Some(sheet_id) = sheet_name_to_id(&sheet_name, sheet_map) {
+        return Ok((sheet_id, None, rest));
+    }
+    sheet_name
+        .match_indices(':')
+        .find_map(|(i, _)| {
+            let first = sheet_name_to_id(&sheet_name[..i], sheet_map)?;
+            let last = sheet_name_to_id(&sheet_name[i + 1..], sheet_map)?;
+            Some((first, (first != last).then_some(last), rest))
+        })
+        .ok_or(A1Error::InvalidSheetName(sheet_name))
+}
+
+/// Finds a sheet ID by case-insensitive sheet name.
+fn sheet_name_to_id(sheet_name: &str, sheet_map: &SheetNameIdMap) -> Option<SheetId> {
+    let folded_name = sheet_name.to_lowercase();
+    sheet_map
+        .iter()
+        .find(|(name, _)| name.to_lowercase() == folded_name)
+        .map(|(_, id)| *id)
+}
+
 /// Returns a sheet name, quoted if necessary.
 pub(crate) fn quote_sheet_name(sheet_name: &str) -> String {
     if sheet_name_must_be_quoted(sheet_name) {
@@ -68,6 +99,16 @@ pub(crate) fn quote_sheet_name(sheet_name: &str) -> String {
     }
 }
 
+/// Returns a 3D reference to a span of sheets, such as `Jan:Dec`, quoted if
+/// either sheet name must be quoted.
+pub(crate) fn quote_sheet_span(first: &str, last: &str) -> String {
+    if sheet_name_must_be_quoted(first) || sheet_name_must_be_quoted(last) {
+        format!("'{}:{}'", first.replace("'", "''"), last.replace("'", "''"))
+    } else {
+        format!("{first}:{last}")
+    }
+}
+
 /// Returns whether a sheet name must be quoted.
 fn sheet_name_must_be_quoted(sheet_name: &str) -> bool {
     sheet_name.starts_with(|c: char| c.is_ascii_digit())
@@ -178,4 +219,42 @@ mod tests {
             Ok((sheet.id, "A1:B2"))
         );
     }
+
+    #[test]
+    fn test_parse_optional_sheet_span_to_ids() {
+        let jan = SheetId::new();
+        let dec = SheetId::new();
+        let span = SheetId::new();
+        let map = HashMap::from([
+            ("Jan".to_string(), jan),
+            ("Dec 2024".to_string(), dec),
+            ("Jan:Feb".to_string(), span),
+        ]);
+        assert_eq!(
+            parse_optional_sheet_span_to_ids("'jan:Dec 2024'!B5", &jan, &map),
+            Ok((jan, Some(dec), "B5"))
+        );
+        assert_eq!(
+            parse_optional_sheet_span_to_ids("Jan:Feb!B5", &jan, &map),
+            Ok((span, None, "B5"))
+        );
+        assert_eq!(
+            parse_optional_sheet_span_to_ids("Jan:Jan!B5", &dec, &map),
+            Ok((jan, None, "B5"))
+        );
+        assert_eq!(
+            parse_optional_sheet_span_to_ids("B5", &dec, &map),
+            Ok((dec, None, "B5"))
+        );
+        assert_eq!(
+            parse_optional_sheet_span_to_ids("Jan:Mar!B5", &jan, &map),
+            Err(A1Error::InvalidSheetName("Jan:Mar".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_quote_sheet_span() {
+        assert_eq!(quote_sheet_span("Jan", "Dec"), "Jan:Dec");
+        assert_eq!(quote_sheet_span("Jan", "Dec 2024"), "'Jan:Dec 2024'");
+    }
 }
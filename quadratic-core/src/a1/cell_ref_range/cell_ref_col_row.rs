@@ -27,6 +27,7 @@ impl CellRefRange {
                     }
                 }
             }
+            Self::Table { .. } => (),
         }
         changed
     }
@@ -56,6 +57,7 @@ impl CellRefRange {
                     }
                 }
             }
+            Self::Table { .. } => (),
         }
         changed
     }
@@ -77,6 +79,7 @@ impl CellRefRange {
                     changed = true;
                 }
             }
+            Self::Table { .. } => (),
         }
         changed
     }
@@ -98,6 +101,7 @@ impl CellRefRange {
                     changed = true;
                 }
             }
+            Self::Table { .. } => (),
         }
         changed
     }
@@ -5,22 +5,35 @@ use ts_rs::TS;
 
 use crate::{Pos, Rect, RefRangeBounds};
 
-use super::{A1Error, UNBOUNDED};
+use super::{A1Error, TableRef, UNBOUNDED};
 
 pub mod cell_ref_col_row;
 pub mod cell_ref_query;
 
-#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, TS)]
+/// A range of cells, either in sheet coordinates or as a structured reference
+/// to a table.
+///
+/// Table references have no position of their own: they are resolved against
+/// the sheet's tables (see [`crate::grid::Sheet::resolve_cell_ref_range`]).
+/// Until then, they are treated as an empty range.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, TS)]
 #[cfg_attr(test, derive(proptest_derive::Arbitrary))]
 #[serde(untagged)]
 pub enum CellRefRange {
-    Sheet { range: RefRangeBounds },
+    Sheet {
+        range: RefRangeBounds,
+    },
+    #[cfg_attr(test, proptest(skip))]
+    Table {
+        range: TableRef,
+    },
 }
 
 impl fmt::Debug for CellRefRange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Sheet { range } => write!(f, "CellRefRange::Sheet({})", range),
+            Self::Table { range } => write!(f, "CellRefRange::Table({})", range),
         }
     }
 }
@@ -29,6 +42,7 @@ impl fmt::Display for CellRefRange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Sheet { range } => fmt::Display::fmt(range, f),
+            Self::Table { range } => fmt::Display::fmt(range, f),
         }
     }
 }
@@ -37,6 +51,11 @@ impl FromStr for CellRefRange {
     type Err = A1Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if TableRef::is_table_ref(s) {
+            return Ok(Self::Table {
+                range: TableRef::from_str(s)?,
+            });
+        }
         Ok(Self::Sheet {
             range: RefRangeBounds::from_str(s)?,
         })
@@ -110,87 +129,119 @@ impl CellRefRange {
         }
     }
 
-    pub fn might_intersect_rect(self, rect: Rect) -> bool {
+    /// Returns the sheet range, or None for a table reference.
+    pub fn as_sheet_range(&self) -> Option<&RefRangeBounds> {
+        match self {
+            Self::Sheet { range } => Some(range),
+            Self::Table { .. } => None,
+        }
+    }
+
+    /// Returns the table reference, or None for a sheet range.
+    pub fn as_table_ref(&self) -> Option<&TableRef> {
+        match self {
+            Self::Sheet { .. } => None,
+            Self::Table { range } => Some(range),
+        }
+    }
+
+    pub fn might_intersect_rect(&self, rect: Rect) -> bool {
         match self {
             Self::Sheet { range } => range.might_intersect_rect(rect),
+            Self::Table { .. } => false,
         }
     }
 
-    pub fn might_contain_pos(self, pos: Pos) -> bool {
+    pub fn might_contain_pos(&self, pos: Pos) -> bool {
         match self {
             Self::Sheet { range } => range.might_contain_pos(pos),
+            Self::Table { .. } => false,
         }
     }
 
-    pub fn contains_pos(self, pos: Pos) -> bool {
+    pub fn contains_pos(&self, pos: Pos) -> bool {
         match self {
             Self::Sheet { range } => range.contains_pos(pos),
+            Self::Table { .. } => false,
         }
     }
 
     pub fn is_column_range(&self) -> bool {
         match self {
             Self::Sheet { range } => range.is_column_range(),
+            Self::Table { .. } => false,
         }
     }
 
     pub fn has_column_range(&self, col: i64) -> bool {
         match self {
             Self::Sheet { range } => range.has_column_range(col),
+            Self::Table { .. } => false,
         }
     }
 
     pub fn is_row_range(&self) -> bool {
         match self {
             Self::Sheet { range } => range.is_row_range(),
+            Self::Table { .. } => false,
         }
     }
 
     pub fn has_row_range(&self, row: i64) -> bool {
         match self {
             Self::Sheet { range } => range.has_row_range(row),
+            Self::Table { .. } => false,
         }
     }
 
     pub fn is_finite(&self) -> bool {
         match self {
             Self::Sheet { range } => range.is_finite(),
+            Self::Table { .. } => true,
         }
     }
 
     pub fn to_rect(&self) -> Option<Rect> {
         match self {
             Self::Sheet { range } => range.to_rect(),
+            Self::Table { .. } => None,
         }
     }
 
     pub fn selected_columns_finite(&self) -> Vec<i64> {
         match self {
             Self::Sheet { range } => range.selected_columns_finite(),
+            Self::Table { .. } => vec![],
         }
     }
 
     pub fn selected_columns(&self, from: i64, to: i64) -> Vec<i64> {
         match self {
             Self::Sheet { range } => range.selected_columns(from, to),
+            Self::Table { .. } => vec![],
         }
     }
 
     pub fn selected_rows_finite(&self) -> Vec<i64> {
         match self {
             Self::Sheet { range } => range.selected_rows_finite(),
+            Self::Table { .. } => vec![],
         }
     }
 
     pub fn selected_rows(&self, from: i64, to: i64) -> Vec<i64> {
         match self {
             Self::Sheet { range } => range.selected_rows(from, to),
+            Self::Table { .. } => vec![],
         }
     }
 
+    /// Translates a sheet range. Table references follow their table, so
+    /// they are not translated.
     pub fn translate_in_place(&mut self, x: i64, y: i64) {
         match self {
             Self::Sheet { range } => range.translate_in_place(x, y),
+            Self::Table { .. } => (),
         }
     }
 
@@ -199,6 +250,7 @@ impl CellRefRange {
             Self::Sheet { range } => Self::Sheet {
                 range: range.translate(x, y),
             },
+            Self::Table { .. } => self.clone(),
         }
     }
 
@@ -210,6 +262,7 @@ impl CellRefRange {
     ) {
         match self {
             Self::Sheet { range } => range.adjust_column_row_in_place(column, row, delta),
+            Self::Table { .. } => (),
         }
     }
 
@@ -218,18 +271,21 @@ impl CellRefRange {
             Self::Sheet { range } => Self::Sheet {
                 range: range.adjust_column_row(column, row, delta),
             },
+            Self::Table { .. } => self.clone(),
         }
     }
 
     pub fn try_to_pos(&self) -> Option<Pos> {
         match self {
             Self::Sheet { range } => range.try_to_pos(),
+            Self::Table { .. } => None,
         }
     }
 
     pub fn is_single_cell(&self) -> bool {
         match self {
             Self::Sheet { range } => range.is_single_cell(),
+            Self::Table { .. } => false,
         }
     }
 
@@ -237,9 +293,7 @@ impl CellRefRange {
     pub fn test_a1(a1: &str) -> Self {
         use std::str::FromStr;
 
-        Self::Sheet {
-            range: RefRangeBounds::from_str(a1).unwrap(),
-        }
+        Self::from_str(a1).unwrap()
     }
 }
 
@@ -571,4 +625,31 @@ mod tests {
         range.adjust_column_row_in_place(None, Some(1), -1);
         assert_eq!(range.to_string(), "B2");
     }
+
+    #[test]
+    fn test_table_ref() {
+        let range = CellRefRange::test_a1("Sales[Amount]");
+        assert_eq!(
+            range.as_table_ref(),
+            Some(&"Sales[Amount]".parse::<TableRef>().unwrap())
+        );
+        assert_eq!(range.to_string(), "Sales[Amount]");
+        assert_eq!(range.to_rect(), None);
+        assert!(!range.contains_pos(Pos { x: 1, y: 1 }));
+        assert_eq!(range.translate(1, 1), range);
+
+        assert!(CellRefRange::from_str("Sales[Amount").is_err());
+        assert!(CellRefRange::test_a1("A1").as_table_ref().is_none());
+    }
+
+    #[test]
+    fn test_serialize_table_ref() {
+        let range = CellRefRange::test_a1("Sales[[#Headers],[Amount]]");
+        let json = serde_json::to_string(&range).unwrap();
+        assert_eq!(serde_json::from_str::<CellRefRange>(&json).unwrap(), range);
+
+        let range = CellRefRange::test_a1("A1:B2");
+        let json = serde_json::to_string(&range).unwrap();
+        assert_eq!(serde_json::from_str::<CellRefRange>(&json).unwrap(), range);
+    }
 }
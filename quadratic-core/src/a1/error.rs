@@ -18,6 +18,8 @@ pub enum A1Error {
     InvalidExclusion(String),
     TranslateInvalid(String),
     SheetNotFound,
+    InvalidTableRef(String),
+    TableNotFound(String),
 }
 
 impl From<A1Error> for String {
@@ -46,6 +48,8 @@ impl std::fmt::Display for A1Error {
             A1Error::InvalidExclusion(msg) => write!(f, "Invalid Exclusion: {msg}"),
             A1Error::TranslateInvalid(msg) => write!(f, "Translate Invalid: {msg}"),
             A1Error::SheetNotFound => write!(f, "Sheet Not Found"),
+            A1Error::InvalidTableRef(msg) => write!(f, "Invalid Table Reference: {msg}"),
+            A1Error::TableNotFound(msg) => write!(f, "Table Not Found: {msg}"),
         }
     }
 }
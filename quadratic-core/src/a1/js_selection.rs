@@ -378,3 +378,15 @@ pub fn rect_to_a1(rect: JsValue) -> Result<String, String> {
     let rect = serde_wasm_bindgen::from_value::<Rect>(rect).map_err(|e| e.to_string())?;
     Ok(rect.a1_string())
 }
+
+/// Converts the cell references in a formula at (x, y) to R1C1 notation.
+#[wasm_bindgen(js_name = "formulaToR1C1")]
+pub fn formula_to_r1c1(formula: &str, x: i32, y: i32) -> String {
+    crate::formulas::a1_to_r1c1(formula, Pos::new(x as i64, y as i64))
+}
+
+/// Converts the cell references in a formula at (x, y) to A1 notation.
+#[wasm_bindgen(js_name = "formulaToA1")]
+pub fn formula_to_a1(formula: &str, x: i32, y: i32) -> String {
+    crate::formulas::r1c1_to_a1(formula, Pos::new(x as i64, y as i64))
+}
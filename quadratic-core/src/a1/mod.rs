@@ -6,8 +6,10 @@ mod cell_ref_range;
 mod column_names;
 mod error;
 mod js_selection;
+mod r1c1;
 mod ref_range_bounds;
 mod sheet_cell_ref_range;
+mod table_ref;
 
 pub use a1_selection::*;
 pub use a1_sheet_name::*;
@@ -17,8 +19,10 @@ pub use cell_ref_range::*;
 pub use column_names::*;
 pub use error::*;
 pub use js_selection::*;
+pub(crate) use r1c1::*;
 pub use ref_range_bounds::*;
 pub use sheet_cell_ref_range::*;
+pub use table_ref::*;
 
 /// Name to use when a sheet ID has no corresponding name.
 ///
@@ -0,0 +1,231 @@
+//! R1C1 reference notation, such as `R5C2` or `R[-1]C[2]`.
+//!
+//! Absolute R1C1 coordinates such as `R5C2` are the same as `$B$5`. Relative
+//! coordinates such as `R[-1]C[2]` are offsets from a base position, and an
+//! omitted number such as the row in `RC[2]` is an offset of zero. Relative
+//! coordinates are resolved when parsing, so `R[-1]C[2]` from `B5` is `D4`.
+
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+use super::{A1Error, CellRefCoord, CellRefRangeEnd, RefRangeBounds};
+use crate::Pos;
+
+/// R1C1 reference, such as `R5C2`, `R[-1]C[2]` or `RC[1]`. `RC` and `RC5` are
+/// excluded since they are A1 references.
+///
+/// R(\d+|\[-?\d+\])C(\d+|\[-?\d+\])?|RC(\[-?\d+\])
+/// R(\d+|\[-?\d+\])                                 row
+///                 C(\d+|\[-?\d+\])?                optional column
+///                                  |               OR
+///                                   RC(\[-?\d+\])  current row, column offset
+pub(crate) const R1C1_PATTERN: &str = r"R(\d+|\[-?\d+\])C(\d+|\[-?\d+\])?|RC(\[-?\d+\])";
+
+lazy_static! {
+    /// Regex that matches a full R1C1 reference, ignoring case.
+    pub(crate) static ref R1C1_REGEX: Regex =
+        RegexBuilder::new(&format!("^({R1C1_PATTERN})$"))
+            .case_insensitive(true)
+            .build()
+            .expect("bad regex");
+}
+
+/// Splits an R1C1 reference into its row and column. An empty row or column
+/// is the current one. Returns `None` if `s` is not an R1C1 reference.
+pub(crate) fn split_r1c1(s: &str) -> Option<(&str, &str)> {
+    let captures = R1C1_REGEX.captures(s.trim())?;
+    let row = captures.get(2).map_or("", |m| m.as_str());
+    let col = captures
+        .get(3)
+        .or_else(|| captures.get(4))
+        .map_or("", |m| m.as_str());
+    Some((row, col))
+}
+
+/// Formats an R1C1 reference from its row and column. An empty row is written
+/// as `[0]` unless the column is an offset, since `RC` and `RC5` would be A1
+/// references.
+pub(crate) fn format_r1c1(row: &str, col: &str) -> String {
+    let row = if row.is_empty() && !col.starts_with('[') {
+        "[0]"
+    } else {
+        row
+    };
+    format!("R{row}C{col}")
+}
+
+impl CellRefCoord {
+    /// Parses the row or column number of an R1C1 reference, resolving a
+    /// relative number from `base`.
+    fn parse_r1c1(s: &str, base: Option<i64>, r1c1: &str) -> Result<Self, A1Error> {
+        let invalid = || A1Error::InvalidCellReference(r1c1.to_owned());
+        let coord = match s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(delta) => {
+                let delta = delta.parse::<i64>().map_err(|_| invalid())?;
+                let base = base.ok_or_else(invalid)?;
+                CellRefCoord::new_rel(base.checked_add(delta).ok_or_else(invalid)?)
+            }
+            None if s.is_empty() => CellRefCoord::new_rel(base.ok_or_else(invalid)?),
+            None => CellRefCoord::new_abs(s.parse().map_err(|_| invalid())?),
+        };
+        Ok(coord)
+    }
+
+    /// Returns the row or column number of an R1C1 reference relative to
+    /// `base`.
+    fn r1c1_string(self, base: i64) -> String {
+        match (self.is_absolute, self.coord.saturating_sub(base)) {
+            (true, _) => self.coord.to_string(),
+            (false, 0) => String::new(),
+            (false, delta) => format!("[{delta}]"),
+        }
+    }
+}
+
+impl CellRefRangeEnd {
+    /// Parses a cell reference in R1C1 notation, such as `R5C2` or
+    /// `R[-1]C[2]`. Relative coordinates are resolved from `base`, and are an
+    /// error if there is no base.
+    ///
+    /// Returns `Ok(None)` if `s` is not in R1C1 notation. `RC` and `RC5` are
+    /// not R1C1 references, since they are also valid A1 references.
+    pub fn parse_r1c1(s: &str, base: Option<Pos>) -> Result<Option<Self>, A1Error> {
+        let Some((row, col)) = split_r1c1(s) else {
+            return Ok(None);
+        };
+
+        let col = CellRefCoord::parse_r1c1(col, base.map(|pos| pos.x), s)?;
+        let row = CellRefCoord::parse_r1c1(row, base.map(|pos| pos.y), s)?;
+        if col.coord < 1 {
+            return Err(A1Error::InvalidColumn(s.to_owned()));
+        }
+        if row.coord < 1 {
+            return Err(A1Error::InvalidRow(s.to_owned()));
+        }
+        Ok(Some(Self { col, row }))
+    }
+
+    /// Returns the cell reference in R1C1 notation, with relative coordinates
+    /// relative to `base`.
+    pub fn to_r1c1_string(self, base: Pos) -> String {
+        format_r1c1(&self.row.r1c1_string(base.y), &self.col.r1c1_string(base.x))
+    }
+}
+
+impl RefRangeBounds {
+    /// Parses a cell or range of cells in R1C1 notation, such as `R5C2` or
+    /// `R[-1]C:R[1]C[2]`. Relative coordinates are resolved from `base`.
+    ///
+    /// Returns `Ok(None)` if `s` is not in R1C1 notation.
+    pub fn from_r1c1_str(s: &str, base: Option<Pos>) -> Result<Option<Self>, A1Error> {
+        let (start, end) = s.split_once(':').unwrap_or((s, s));
+        let start = CellRefRangeEnd::parse_r1c1(start, base)?;
+        let end = CellRefRangeEnd::parse_r1c1(end, base)?;
+        Ok(start.zip(end).map(|(start, end)| Self { start, end }))
+    }
+
+    /// Returns the range in R1C1 notation, with relative coordinates relative
+    /// to `base`. Whole rows and columns have no R1C1 form here, so they are
+    /// returned in A1 notation.
+    pub fn to_r1c1_string(&self, base: Pos) -> String {
+        if self.start.is_multi_range() || self.end.is_multi_range() {
+            return self.to_string();
+        }
+        let start = self.start.to_r1c1_string(base);
+        if self.start == self.end {
+            start
+        } else {
+            format!("{start}:{}", self.end.to_r1c1_string(base))
+        }
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_r1c1() {
+        let parse = |s: &str| CellRefRangeEnd::parse_r1c1(s, Some(pos![B5]));
+
+        assert_eq!(
+            parse("R5C2"),
+            Ok(Some(CellRefRangeEnd {
+                col: CellRefCoord::new_abs(2),
+                row: CellRefCoord::new_abs(5),
+            }))
+        );
+        assert_eq!(
+            parse("R[-1]C[2]"),
+            Ok(Some(CellRefRangeEnd::new_relative_pos(pos![D4])))
+        );
+        assert_eq!(
+            parse("rc[1]"),
+            Ok(Some(CellRefRangeEnd::new_relative_pos(pos![C5])))
+        );
+        assert_eq!(
+            parse("R1C"),
+            Ok(Some(CellRefRangeEnd {
+                col: CellRefCoord::new_rel(2),
+                row: CellRefCoord::new_abs(1),
+            }))
+        );
+
+        // A1 references
+        assert_eq!(parse("RC"), Ok(None));
+        assert_eq!(parse("RC5"), Ok(None));
+        assert_eq!(parse("R5"), Ok(None));
+        assert_eq!(parse("B5"), Ok(None));
+
+        assert_eq!(
+            parse("R[-5]C"),
+            Err(A1Error::InvalidRow("R[-5]C".to_string()))
+        );
+        assert_eq!(
+            CellRefRangeEnd::parse_r1c1("R[1]C", None),
+            Err(A1Error::InvalidCellReference("R[1]C".to_string()))
+        );
+        assert!(CellRefRangeEnd::parse_r1c1("R5C2", None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_split_and_format_r1c1() {
+        assert_eq!(split_r1c1("R5C2"), Some(("5", "2")));
+        assert_eq!(split_r1c1("r[-1]c"), Some(("[-1]", "")));
+        assert_eq!(split_r1c1("RC[1]"), Some(("", "[1]")));
+        assert_eq!(split_r1c1("RC"), None);
+        assert_eq!(split_r1c1("RC5"), None);
+
+        assert_eq!(format_r1c1("5", "2"), "R5C2");
+        assert_eq!(format_r1c1("", "[1]"), "RC[1]");
+        assert_eq!(format_r1c1("", "2"), "R[0]C2");
+        assert_eq!(format_r1c1("", ""), "R[0]C");
+    }
+
+    #[test]
+    fn test_r1c1_range() {
+        let base = pos![B5];
+        let range = RefRangeBounds::from_r1c1_str("R[-1]C:R10C[1]", Some(base))
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.to_string(), "B4:C$10");
+        assert_eq!(range.to_r1c1_string(base), "R[-1]C:R10C[1]");
+        assert_eq!(range.to_r1c1_string(pos![A1]), "R[3]C[1]:R10C[2]");
+
+        let range = RefRangeBounds::test_a1("$B$5");
+        assert_eq!(range.to_r1c1_string(pos![Z99]), "R5C2");
+
+        let range = RefRangeBounds::test_a1("$B5");
+        assert_eq!(range.to_r1c1_string(base), "R[0]C2");
+        assert_eq!(
+            RefRangeBounds::from_r1c1_str("R[0]C2", Some(base)),
+            Ok(Some(range))
+        );
+
+        let range = RefRangeBounds::test_a1("B:C");
+        assert_eq!(range.to_r1c1_string(base), "B:C");
+
+        assert_eq!(RefRangeBounds::from_r1c1_str("A1:B2", Some(base)), Ok(None));
+    }
+}
@@ -4,7 +4,7 @@ use crate::grid::SheetId;
 
 use super::{A1Error, CellRefRange, SheetNameIdMap};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SheetCellRefRange {
     pub sheet: SheetId,
     pub cells: CellRefRange,
@@ -29,11 +29,14 @@ impl SheetCellRefRange {
     /// included in the output only if `default_sheet_id` is `None` or differs
     /// from the ID of the sheet containing the range.
     pub fn to_string(
-        self,
+        &self,
         default_sheet_id: Option<SheetId>,
         sheet_map: &SheetNameIdMap,
     ) -> String {
-        if default_sheet_id.is_some_and(|it| it != self.sheet) {
+        // table names are unique across sheets, so they never need a sheet name
+        if self.cells.as_table_ref().is_none()
+            && default_sheet_id.is_some_and(|it| it != self.sheet)
+        {
             let sheet_name = sheet_map
                 .iter()
                 .find(|(_, id)| **id == self.sheet)
@@ -0,0 +1,491 @@
+//! Structured references to table objects, such as `Sales[Amount]`,
+//! `Sales[#Headers]` or `Sales[@Amount]`.
+//!
+//! Table names are unique across the file, so a structured reference never
+//! includes a sheet name. A table reference must always include brackets
+//! (`Sales[]` rather than `Sales`) so that it can't be confused with a column
+//! reference.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::A1Error;
+
+/// Rows of a table that are included in a structured reference.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash, TS)]
+pub enum TableRows {
+    /// `#Data`: the rows between the header and the totals rows.
+    #[default]
+    Data,
+    /// `#Headers`: the header row.
+    Headers,
+    /// `#Totals`: the totals row.
+    Totals,
+    /// `#All`: the headers, data and totals rows.
+    All,
+    /// `@` or `#This Row`: the data row of the cell containing the reference.
+    ThisRow,
+}
+
+impl TableRows {
+    fn from_special_item(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "#data" => Some(Self::Data),
+            "#headers" => Some(Self::Headers),
+            "#totals" => Some(Self::Totals),
+            "#all" => Some(Self::All),
+            "#this row" => Some(Self::ThisRow),
+            _ => None,
+        }
+    }
+
+    fn special_item(self) -> &'static str {
+        match self {
+            Self::Data => "#Data",
+            Self::Headers => "#Headers",
+            Self::Totals => "#Totals",
+            Self::All => "#All",
+            Self::ThisRow => "#This Row",
+        }
+    }
+}
+
+/// Columns of a table that are included in a structured reference.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, TS)]
+pub enum TableColumns {
+    #[default]
+    All,
+    Column(String),
+    ColumnRange {
+        start: String,
+        end: String,
+    },
+}
+
+impl TableColumns {
+    /// Parses `[Amount]`, `[Price]:[Amount]` or an unbracketed `Amount`.
+    fn parse(s: &str) -> Result<Self, A1Error> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Self::All);
+        }
+        if !s.starts_with('[') {
+            return Ok(Self::Column(parse_column_name(s)?));
+        }
+
+        let (start, rest) = split_bracketed(s)?;
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(Self::Column(parse_column_name(start)?));
+        }
+        let Some(rest) = rest.strip_prefix(':') else {
+            return Err(A1Error::InvalidTableRef(s.to_string()));
+        };
+        let (end, rest) = split_bracketed(rest.trim())?;
+        if !rest.trim().is_empty() {
+            return Err(A1Error::InvalidTableRef(s.to_string()));
+        }
+        Ok(Self::ColumnRange {
+            start: parse_column_name(start)?,
+            end: parse_column_name(end)?,
+        })
+    }
+
+    /// Returns true if the column is named in the reference.
+    pub fn has_column(&self, name: &str) -> bool {
+        match self {
+            Self::All => false,
+            Self::Column(column) => column.eq_ignore_ascii_case(name),
+            Self::ColumnRange { start, end } => {
+                start.eq_ignore_ascii_case(name) || end.eq_ignore_ascii_case(name)
+            }
+        }
+    }
+
+    /// Renames a column named in the reference.
+    pub fn rename_column(&mut self, old_name: &str, new_name: &str) {
+        let rename = |column: &mut String| {
+            if column.eq_ignore_ascii_case(old_name) {
+                *column = new_name.to_string();
+            }
+        };
+        match self {
+            Self::All => (),
+            Self::Column(column) => rename(column),
+            Self::ColumnRange { start, end } => {
+                rename(start);
+                rename(end);
+            }
+        }
+    }
+
+    /// Returns the bracketed form of the columns, or None for all columns.
+    fn bracketed(&self) -> Option<String> {
+        match self {
+            Self::All => None,
+            Self::Column(column) => Some(format!("[{column}]")),
+            Self::ColumnRange { start, end } => Some(format!("[{start}]:[{end}]")),
+        }
+    }
+}
+
+/// A structured reference to part of a table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, TS)]
+pub struct TableRef {
+    pub table_name: String,
+    pub rows: TableRows,
+    pub columns: TableColumns,
+}
+
+impl TableRef {
+    pub fn new(table_name: &str) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+            rows: TableRows::default(),
+            columns: TableColumns::default(),
+        }
+    }
+
+    /// Returns true if the string looks like a structured reference (ie, a
+    /// name followed by brackets). This does not check whether the reference
+    /// is valid.
+    pub fn is_table_ref(s: &str) -> bool {
+        s.split_once('[')
+            .is_some_and(|(name, _)| is_valid_table_name(name.trim()))
+    }
+
+    /// Returns true if the reference is to the table with the given name.
+    pub fn is_table(&self, table_name: &str) -> bool {
+        self.table_name.eq_ignore_ascii_case(table_name)
+    }
+}
+
+impl FromStr for TableRef {
+    type Err = A1Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || A1Error::InvalidTableRef(s.to_string());
+
+        let bracket = s.find('[').ok_or_else(invalid)?;
+        let table_name = s[..bracket].trim();
+        if !is_valid_table_name(table_name) {
+            return Err(invalid());
+        }
+        let (inner, rest) = split_bracketed(&s[bracket..])?;
+        if !rest.trim().is_empty() {
+            return Err(invalid());
+        }
+        let inner = inner.trim();
+
+        // Sales[@Amount] or Sales[@[Unit Price]]
+        if let Some(columns) = inner.strip_prefix('@') {
+            return Ok(Self {
+                table_name: table_name.to_string(),
+                rows: TableRows::ThisRow,
+                columns: TableColumns::parse(columns)?,
+            });
+        }
+
+        // Sales[#Headers]
+        if inner.starts_with('#') {
+            return Ok(Self {
+                table_name: table_name.to_string(),
+                rows: TableRows::from_special_item(inner).ok_or_else(invalid)?,
+                columns: TableColumns::All,
+            });
+        }
+
+        // Sales[[#Headers],[Amount]] or Sales[[Price]:[Amount]]
+        if inner.starts_with('[') {
+            let mut rows = None;
+            let mut columns = None;
+            for item in split_top_level(inner, ',') {
+                let item = item.trim();
+                let special = item
+                    .strip_prefix('[')
+                    .and_then(|item| item.strip_suffix(']'))
+                    .filter(|item| item.trim_start().starts_with(['#', '@']));
+                match special {
+                    Some(special) => {
+                        if rows.is_some() {
+                            return Err(invalid());
+                        }
+                        let special = special.trim();
+                        rows = Some(if special == "@" {
+                            TableRows::ThisRow
+                        } else {
+                            TableRows::from_special_item(special).ok_or_else(invalid)?
+                        });
+                    }
+                    None => {
+                        if columns.is_some() {
+                            return Err(invalid());
+                        }
+                        columns = Some(TableColumns::parse(item)?);
+                    }
+                }
+            }
+            return Ok(Self {
+                table_name: table_name.to_string(),
+                rows: rows.unwrap_or_default(),
+                columns: columns.unwrap_or_default(),
+            });
+        }
+
+        // Sales[Amount] or Sales[]
+        Ok(Self {
+            table_name: table_name.to_string(),
+            rows: TableRows::Data,
+            columns: TableColumns::parse(inner)?,
+        })
+    }
+}
+
+impl fmt::Display for TableRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.table_name;
+        match (self.rows, &self.columns) {
+            (TableRows::Data, TableColumns::All) => write!(f, "{name}[]"),
+            (TableRows::Data, TableColumns::Column(column)) => write!(f, "{name}[{column}]"),
+            (TableRows::ThisRow, TableColumns::Column(column))
+                if !column.contains(char::is_whitespace) =>
+            {
+                write!(f, "{name}[@{column}]")
+            }
+            (TableRows::ThisRow, columns) if columns != &TableColumns::All => {
+                write!(f, "{name}[@{}]", columns.bracketed().unwrap_or_default())
+            }
+            (TableRows::Data, columns) => {
+                write!(f, "{name}[{}]", columns.bracketed().unwrap_or_default())
+            }
+            (rows, TableColumns::All) => write!(f, "{name}[{}]", rows.special_item()),
+            (rows, columns) => write!(
+                f,
+                "{name}[[{}],{}]",
+                rows.special_item(),
+                columns.bracketed().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Returns true if the name can be used for a table. Names start with a letter
+/// or underscore, may contain letters, digits, underscores and periods, and
+/// may not be `R` or `C` (which would be confused with R1C1 references).
+pub fn is_valid_table_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        && !name.eq_ignore_ascii_case("r")
+        && !name.eq_ignore_ascii_case("c")
+}
+
+/// Returns true if the name can be used for a table column. Column names may
+/// not be empty, contain brackets, or start with `#` or `@`.
+pub fn is_valid_table_column_name(name: &str) -> bool {
+    let trimmed = name.trim();
+    !trimmed.is_empty()
+        && trimmed == name
+        && !name.contains(['[', ']'])
+        && !name.starts_with(['#', '@'])
+}
+
+fn parse_column_name(s: &str) -> Result<String, A1Error> {
+    let s = s.trim();
+    if is_valid_table_column_name(s) {
+        Ok(s.to_string())
+    } else {
+        Err(A1Error::InvalidTableRef(s.to_string()))
+    }
+}
+
+/// Splits `[inner]rest` into `inner` and `rest`, allowing nested brackets.
+fn split_bracketed(s: &str) -> Result<(&str, &str), A1Error> {
+    let invalid = || A1Error::InvalidTableRef(s.to_string());
+    if !s.starts_with('[') {
+        return Err(invalid());
+    }
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((&s[1..i], &s[i + 1..]));
+                }
+            }
+            _ => (),
+        }
+    }
+    Err(invalid())
+}
+
+/// Splits a string on a separator that is not inside brackets.
+pub(crate) fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+
+    fn table_ref(table_name: &str, rows: TableRows, columns: TableColumns) -> TableRef {
+        TableRef {
+            table_name: table_name.to_string(),
+            rows,
+            columns,
+        }
+    }
+
+    #[test]
+    fn test_parse_table_ref() {
+        assert_eq!(
+            "Sales[Amount]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::Data,
+                TableColumns::Column("Amount".into())
+            ))
+        );
+        assert_eq!("Sales[]".parse::<TableRef>(), Ok(TableRef::new("Sales")));
+        assert_eq!(
+            "Sales[#headers]".parse::<TableRef>(),
+            Ok(table_ref("Sales", TableRows::Headers, TableColumns::All))
+        );
+        assert_eq!(
+            "Sales[@Amount]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::ThisRow,
+                TableColumns::Column("Amount".into())
+            ))
+        );
+        assert_eq!(
+            "Sales[@[Unit Price]]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::ThisRow,
+                TableColumns::Column("Unit Price".into())
+            ))
+        );
+        assert_eq!(
+            "Sales[[#Totals],[Price]:[Amount]]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::Totals,
+                TableColumns::ColumnRange {
+                    start: "Price".into(),
+                    end: "Amount".into()
+                }
+            ))
+        );
+        assert_eq!(
+            "Sales[[Price]:[Amount]]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::Data,
+                TableColumns::ColumnRange {
+                    start: "Price".into(),
+                    end: "Amount".into()
+                }
+            ))
+        );
+        assert_eq!(
+            "Sales[[#This Row],[Amount]]".parse::<TableRef>(),
+            Ok(table_ref(
+                "Sales",
+                TableRows::ThisRow,
+                TableColumns::Column("Amount".into())
+            ))
+        );
+
+        assert!("Sales".parse::<TableRef>().is_err());
+        assert!("Sales[Amount".parse::<TableRef>().is_err());
+        assert!("Sales[Amount]B".parse::<TableRef>().is_err());
+        assert!("Sales[#Bad]".parse::<TableRef>().is_err());
+        assert!("Sales[[#Headers],[#Totals]]".parse::<TableRef>().is_err());
+        assert!("1Sales[Amount]".parse::<TableRef>().is_err());
+        assert!("R[1]".parse::<TableRef>().is_err());
+    }
+
+    #[test]
+    fn test_table_ref_to_string() {
+        for s in [
+            "Sales[]",
+            "Sales[Amount]",
+            "Sales[Unit Price]",
+            "Sales[[Price]:[Amount]]",
+            "Sales[#Headers]",
+            "Sales[#All]",
+            "Sales[#This Row]",
+            "Sales[@Amount]",
+            "Sales[@[Unit Price]]",
+            "Sales[@[Price]:[Amount]]",
+            "Sales[[#Totals],[Amount]]",
+        ] {
+            assert_eq!(s.parse::<TableRef>().unwrap().to_string(), s);
+        }
+        assert_eq!(
+            "Sales[#Data]".parse::<TableRef>().unwrap().to_string(),
+            "Sales[]"
+        );
+    }
+
+    #[test]
+    fn test_rename_column() {
+        let mut columns = TableColumns::ColumnRange {
+            start: "Price".into(),
+            end: "Amount".into(),
+        };
+        assert!(columns.has_column("amount"));
+        columns.rename_column("amount", "Total");
+        assert_eq!(
+            columns,
+            TableColumns::ColumnRange {
+                start: "Price".into(),
+                end: "Total".into()
+            }
+        );
+        assert!(!columns.has_column("Amount"));
+    }
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_table_name("Sales"));
+        assert!(is_valid_table_name("_Sales.2024"));
+        assert!(!is_valid_table_name(""));
+        assert!(!is_valid_table_name("2024"));
+        assert!(!is_valid_table_name("Sales Data"));
+        assert!(!is_valid_table_name("r"));
+
+        assert!(is_valid_table_column_name("Unit Price"));
+        assert!(!is_valid_table_column_name(""));
+        assert!(!is_valid_table_column_name(" Price"));
+        assert!(!is_valid_table_column_name("#Price"));
+        assert!(!is_valid_table_column_name("Price[1]"));
+    }
+}
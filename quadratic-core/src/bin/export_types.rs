@@ -1,6 +1,7 @@
 use std::fs::create_dir_all;
 
 use quadratic_core::color::Rgba;
+use quadratic_core::controller::active_transactions::async_timeouts::AsyncTimeouts;
 use quadratic_core::controller::active_transactions::transaction_name::TransactionName;
 use quadratic_core::controller::execution::run_code::get_cells::CellA1Response;
 use quadratic_core::controller::execution::run_code::get_cells::JsGetCellResponse;
@@ -9,9 +10,9 @@ use quadratic_core::controller::transaction_types::JsCodeResult;
 use quadratic_core::grid::formats::Format;
 use quadratic_core::grid::js_types::{
     CellFormatSummary, JsCellValue, JsCellValuePos, JsCellValuePosAIContext, JsClipboard,
-    JsCodeCell, JsHtmlOutput, JsNumber, JsOffset, JsRenderCell, JsRenderCellSpecial,
-    JsRenderCodeCell, JsRenderCodeCellState, JsRenderFill, JsReturnInfo, JsRowHeight, JsSheetFill,
-    JsSummarizeSelectionResult, JsValidationWarning,
+    JsCodeCell, JsCodeRunTiming, JsGoalSeekResult, JsHtmlOutput, JsNumber, JsOffset, JsRenderCell,
+    JsRenderCellSpecial, JsRenderCodeCell, JsRenderCodeCellState, JsRenderFill, JsReturnInfo,
+    JsRowHeight, JsSheetFill, JsSummarizeSelectionResult, JsValidationWarning,
 };
 use quadratic_core::grid::sheet::borders::BorderSelection;
 use quadratic_core::grid::sheet::borders::BorderSide;
@@ -24,6 +25,7 @@ use quadratic_core::grid::sheet::borders::JsBorderVertical;
 use quadratic_core::grid::sheet::borders::JsBordersSheet;
 use quadratic_core::grid::sheet::jump_cursor::JumpDirection;
 use quadratic_core::grid::sheet::search::SearchOptions;
+use quadratic_core::grid::sheet::tables::{Table, TableColumn};
 use quadratic_core::grid::sheet::validations::validation::{
     Validation, ValidationError, ValidationMessage, ValidationStyle,
 };
@@ -41,10 +43,12 @@ use quadratic_core::grid::sheet::validations::validation_rules::validation_text:
     TextCase, TextMatch, ValidationText,
 };
 use quadratic_core::grid::sheet::validations::validation_rules::ValidationRule;
+use quadratic_core::grid::{
+    CalculationMode, CodeCellLanguage, CodeRunTiming, ConnectionKind, ConnectionTiming,
+};
 use quadratic_core::grid::{
     CellAlign, CellVerticalAlign, CellWrap, GridBounds, NumericFormat, NumericFormatKind, SheetId,
 };
-use quadratic_core::grid::{CodeCellLanguage, ConnectionKind};
 use quadratic_core::grid::{JsCellsAccessed, RenderSize};
 use quadratic_core::sheet_offsets::resize_transient::TransientResize;
 use quadratic_core::sheet_offsets::sheet_offsets_wasm::ColumnRow;
@@ -58,6 +62,7 @@ use quadratic_core::{
     ArraySize, Axis, CellRefRange, JsCoordinate, Pos, Rect, RunError, RunErrorMsg, SheetPos,
     SheetRect, Span,
 };
+use quadratic_core::{TableColumns, TableRef, TableRows};
 use ts_rs::TS;
 
 macro_rules! generate_type_declarations {
@@ -77,12 +82,14 @@ fn main() {
     s += &generate_type_declarations!(
         A1Selection,
         ArraySize,
+        AsyncTimeouts,
         Axis,
         BorderSelection,
         BorderSide,
         BorderStyle,
         BorderStyleCell,
         BorderStyleTimestamp,
+        CalculationMode,
         CellA1Response,
         CellAlign,
         CellBorderLine,
@@ -93,8 +100,10 @@ fn main() {
         CellVerticalAlign,
         CellWrap,
         CodeCellLanguage,
+        CodeRunTiming,
         ColumnRow,
         ConnectionKind,
+        ConnectionTiming,
         DateTimeRange,
         Format,
         GridBounds,
@@ -108,8 +117,10 @@ fn main() {
         JsClipboard,
         JsCodeCell,
         JsCodeResult,
+        JsCodeRunTiming,
         JsCoordinate,
         JsGetCellResponse,
+        JsGoalSeekResult,
         JsHtmlOutput,
         JsNumber,
         JsOffset,
@@ -143,6 +154,11 @@ fn main() {
         SheetRect,
         SmallTimestamp,
         Span,
+        Table,
+        TableColumn,
+        TableColumns,
+        TableRef,
+        TableRows,
         TextCase,
         TextMatch,
         TransactionName,
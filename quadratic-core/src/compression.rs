@@ -94,6 +94,28 @@ pub fn decompress_zlib(data: &[u8]) -> Result<Vec<u8>> {
     Ok(decoder.finish()?)
 }
 
+/// Decompress `data`, or return None if it decompresses to more than
+/// `max_bytes`.  Decompression stops at the limit, so a small payload can't
+/// expand into an unbounded allocation.
+pub fn decompress_with_limit(
+    compression_format: &CompressionFormat,
+    data: &[u8],
+    max_bytes: usize,
+) -> Result<Option<Vec<u8>>> {
+    let decompressed = match compression_format {
+        CompressionFormat::None => data.to_vec(),
+        CompressionFormat::Zlib => {
+            let mut decompressed = Vec::new();
+            flate2::read::ZlibDecoder::new(data)
+                .take(max_bytes as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            decompressed
+        }
+    };
+
+    Ok((decompressed.len() <= max_bytes).then_some(decompressed))
+}
+
 // HEADER
 
 pub fn add_header(header: Vec<u8>, data: Vec<u8>) -> Result<Vec<u8>> {
@@ -138,6 +160,21 @@ mod test {
         assert_eq!(data, decompressed);
     }
 
+    #[test]
+    fn test_decompress_with_limit() {
+        let data = vec![0; 1000];
+        let compressed = compress(&CompressionFormat::Zlib, data.clone()).unwrap();
+
+        assert_eq!(
+            decompress_with_limit(&CompressionFormat::Zlib, &compressed, 1000).unwrap(),
+            Some(data)
+        );
+        assert_eq!(
+            decompress_with_limit(&CompressionFormat::Zlib, &compressed, 999).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn roundtrip_compression_json() {
         let compression_format = CompressionFormat::Zlib;
@@ -0,0 +1,66 @@
+//! How long an async transaction waits for a code run before giving up.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::grid::CodeCellLanguage;
+
+/// Ten minutes
+const DEFAULT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// Maximum time to wait for each async language.  `None` waits forever.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, TS)]
+pub struct AsyncTimeouts {
+    pub python_ms: Option<u64>,
+    pub javascript_ms: Option<u64>,
+    pub connection_ms: Option<u64>,
+}
+
+impl Default for AsyncTimeouts {
+    fn default() -> Self {
+        AsyncTimeouts {
+            python_ms: Some(DEFAULT_TIMEOUT_MS),
+            javascript_ms: Some(DEFAULT_TIMEOUT_MS),
+            connection_ms: Some(DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+impl AsyncTimeouts {
+    /// Returns the timeout for a language, or None if it doesn't time out.
+    /// Formulas run synchronously and never time out.
+    pub fn timeout_ms(&self, language: &CodeCellLanguage) -> Option<u64> {
+        match language {
+            CodeCellLanguage::Python => self.python_ms,
+            CodeCellLanguage::Javascript => self.javascript_ms,
+            CodeCellLanguage::Connection { .. } => self.connection_ms,
+            CodeCellLanguage::Formula => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::grid::ConnectionKind;
+
+    #[test]
+    fn test_timeout_ms() {
+        let timeouts = AsyncTimeouts {
+            python_ms: Some(1),
+            javascript_ms: None,
+            connection_ms: Some(3),
+        };
+        assert_eq!(timeouts.timeout_ms(&CodeCellLanguage::Python), Some(1));
+        assert_eq!(timeouts.timeout_ms(&CodeCellLanguage::Javascript), None);
+        assert_eq!(
+            timeouts.timeout_ms(&CodeCellLanguage::Connection {
+                kind: ConnectionKind::Postgres,
+                id: "id".into()
+            }),
+            Some(3)
+        );
+        assert_eq!(timeouts.timeout_ms(&CodeCellLanguage::Formula), None);
+    }
+}
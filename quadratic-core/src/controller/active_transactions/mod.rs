@@ -5,12 +5,16 @@
 //! * tracking the state of pending async transactions
 //! * tracking the state of pending multiplayer transactions (both sent and received)
 
-use self::{pending_transaction::PendingTransaction, unsaved_transactions::UnsavedTransactions};
+use self::{
+    async_timeouts::AsyncTimeouts, pending_transaction::PendingTransaction,
+    unsaved_transactions::UnsavedTransactions,
+};
 use super::transaction::Transaction;
 use crate::error_core::{CoreError, Result};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+pub mod async_timeouts;
 pub mod pending_transaction;
 pub mod transaction_name;
 pub mod unsaved_transactions;
@@ -31,6 +35,9 @@ pub struct ActiveTransactions {
 
     // The last sequence_num we applied locally.
     pub last_sequence_num: u64,
+
+    // How long async transactions wait for a code run before timing out.
+    pub async_timeouts: AsyncTimeouts,
 }
 
 impl ActiveTransactions {
@@ -6,6 +6,7 @@
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::{
@@ -14,10 +15,10 @@ use crate::{
     },
     grid::{
         js_types::JsValidationWarning, sheet::validations::validation::Validation, CellsAccessed,
-        CodeCellLanguage, CodeRun, Sheet, SheetId,
+        CodeCellLanguage, CodeRun, CodeRunResult, CodeRunTiming, ConnectionTiming, Sheet, SheetId,
     },
     renderer_constants::{CELL_SHEET_HEIGHT, CELL_SHEET_WIDTH},
-    A1Selection, Pos, SheetPos, SheetRect,
+    A1Selection, Pos, SheetPos, SheetRect, Value,
 };
 
 use super::transaction_name::TransactionName;
@@ -57,6 +58,16 @@ pub struct PendingTransaction {
     /// used by Code Cell execution to track dependencies
     pub cells_accessed: CellsAccessed,
 
+    /// used by Code Cell execution to count the cells read
+    pub cells_read: u64,
+
+    /// when the current code cell started running
+    pub code_run_started: Option<DateTime<Utc>>,
+
+    /// when the current code cell was sent to its worker (or the connection
+    /// service); async timeouts are measured from here
+    pub async_dispatched: Option<DateTime<Utc>>,
+
     /// save code_cell info for async calls
     pub current_sheet_pos: Option<SheetPos>,
 
@@ -78,6 +89,9 @@ pub struct PendingTransaction {
     /// sheets w/updated validations warnings
     pub validations_warnings: HashMap<SheetId, SheetValidationsWarnings>,
 
+    /// sheets w/updated tables
+    pub tables: HashSet<SheetId>,
+
     /// sheets w/updated rows to resize
     pub resize_rows: HashMap<SheetId, HashSet<i64>>,
 
@@ -118,6 +132,9 @@ impl Default for PendingTransaction {
             forward_operations: Vec::new(),
             has_async: 0,
             cells_accessed: Default::default(),
+            cells_read: 0,
+            code_run_started: None,
+            async_dispatched: None,
             current_sheet_pos: None,
             waiting_for_async: None,
             complete: false,
@@ -125,6 +142,7 @@ impl Default for PendingTransaction {
             cursor_undo_redo: None,
             validations: HashSet::new(),
             validations_warnings: HashMap::new(),
+            tables: HashSet::new(),
             resize_rows: HashMap::new(),
             dirty_hashes: HashMap::new(),
             sheet_borders: HashSet::new(),
@@ -298,6 +316,31 @@ impl PendingTransaction {
         }
     }
 
+    /// Returns the timing for the code run that just completed, and resets
+    /// the cells read for the next code run.
+    pub fn code_run_timing(
+        &mut self,
+        result: &CodeRunResult,
+        connection: Option<ConnectionTiming>,
+    ) -> Option<CodeRunTiming> {
+        let cells_read = std::mem::take(&mut self.cells_read);
+        let started = self.code_run_started.take()?;
+        let duration_ms = (Utc::now() - started).num_milliseconds().max(0) as u64;
+        let (output_w, output_h) = match result {
+            CodeRunResult::Ok(Value::Array(array)) => (array.width(), array.height()),
+            CodeRunResult::Ok(Value::Single(_)) => (1, 1),
+            CodeRunResult::Ok(Value::Tuple(_)) | CodeRunResult::Err(_) => (0, 0),
+        };
+
+        Some(CodeRunTiming {
+            duration_ms,
+            cells_read,
+            output_w,
+            output_h,
+            connection,
+        })
+    }
+
     /// Adds a code cell, html cell and image cell to the transaction from a CodeRun
     pub fn add_from_code_run(&mut self, sheet_id: SheetId, pos: Pos, code_run: &Option<CodeRun>) {
         if let Some(code_run) = &code_run {
@@ -411,6 +454,7 @@ impl PendingTransaction {
         self.generate_thumbnail |= transaction.generate_thumbnail;
 
         self.validations.extend(transaction.validations);
+        self.tables.extend(transaction.tables);
 
         for (sheet_id, dirty_hashes) in transaction.dirty_hashes {
             self.dirty_hashes
@@ -594,6 +638,8 @@ mod tests {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         transaction.add_from_code_run(sheet_id, pos, &Some(code_run));
         assert_eq!(transaction.code_cells.len(), 1);
@@ -611,6 +657,8 @@ mod tests {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         transaction.add_from_code_run(sheet_id, pos, &Some(code_run));
         assert_eq!(transaction.code_cells.len(), 1);
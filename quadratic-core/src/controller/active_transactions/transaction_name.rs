@@ -22,4 +22,6 @@ pub enum TransactionName {
     MoveCells,
     Validation,
     ManipulateColumnRow,
+    SetCalculationMode,
+    Table,
 }
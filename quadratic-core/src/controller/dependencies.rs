@@ -1,23 +1,31 @@
-use std::{self};
-
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{SheetPos, SheetRect};
 
 use super::GridController;
 
+/// Code cells that depend on each other.  A group is either a single code
+/// cell, or the code cells in a circular reference.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodeCellGroup {
+    pub(crate) cells: Vec<SheetPos>,
+    pub(crate) circular: bool,
+}
+
 impl GridController {
-    /// Searches all code_runs in all sheets for cells that are dependent on the given sheet_rect.
+    /// Finds the code cells in all sheets that depend on the given sheet_rect.
     pub fn get_dependent_code_cells(&self, sheet_rect: &SheetRect) -> Option<HashSet<SheetPos>> {
-        let mut dependent_cells = HashSet::new();
-
-        self.grid.sheets().iter().for_each(|sheet| {
-            sheet.code_runs.iter().for_each(|(pos, code_run)| {
-                if code_run.cells_accessed.intersects(sheet_rect) {
-                    dependent_cells.insert(pos.to_sheet_pos(sheet.id));
-                }
-            });
-        });
+        let dependent_cells = self
+            .grid
+            .sheets()
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .dependent_code_runs(sheet_rect)
+                    .into_iter()
+                    .map(|pos| pos.to_sheet_pos(sheet.id))
+            })
+            .collect::<HashSet<_>>();
 
         if dependent_cells.is_empty() {
             None
@@ -25,6 +33,178 @@ impl GridController {
             Some(dependent_cells)
         }
     }
+
+    /// Finds the code cells that depend on the given sheet_rect, directly or
+    /// through the output of other code cells, in the order they should be
+    /// computed so that each one runs after the code cells it depends on.
+    /// `skip` (usually the code cell that changed the sheet_rect) is never
+    /// included.  Code cells in a circular reference are grouped together.
+    pub fn get_dependent_code_cells_in_order(
+        &self,
+        sheet_rect: &SheetRect,
+        skip: Option<SheetPos>,
+    ) -> Vec<SheetPos> {
+        self.dependent_code_cell_groups(sheet_rect)
+            .into_iter()
+            .flat_map(|group| group.cells)
+            .filter(|sheet_pos| Some(*sheet_pos) != skip)
+            .collect()
+    }
+
+    /// Groups the code cells that depend on the given sheet_rect, directly or
+    /// through the output of other code cells, into strongly connected
+    /// components (Tarjan's algorithm), in the order they should be computed.
+    pub(crate) fn dependent_code_cell_groups(&self, sheet_rect: &SheetRect) -> Vec<CodeCellGroup> {
+        struct Node {
+            index: usize,
+            low_link: usize,
+            on_stack: bool,
+        }
+
+        let mut nodes: HashMap<SheetPos, Node> = HashMap::new();
+        let mut self_references = HashSet::new();
+        let mut stack = vec![];
+        let mut groups = vec![];
+
+        for root in self.sorted_dependents(sheet_rect) {
+            if nodes.contains_key(&root) {
+                continue;
+            }
+
+            // (code cell, its dependents, index of the next dependent to visit)
+            let mut call_stack = vec![];
+            let visit = |sheet_pos: SheetPos,
+                         nodes: &mut HashMap<SheetPos, Node>,
+                         stack: &mut Vec<SheetPos>| {
+                let index = nodes.len();
+                nodes.insert(
+                    sheet_pos,
+                    Node {
+                        index,
+                        low_link: index,
+                        on_stack: true,
+                    },
+                );
+                stack.push(sheet_pos);
+                (sheet_pos, self.code_cell_dependents(sheet_pos), 0)
+            };
+            call_stack.push(visit(root, &mut nodes, &mut stack));
+
+            while let Some((sheet_pos, dependents, next)) = call_stack.last_mut() {
+                let sheet_pos = *sheet_pos;
+
+                if let Some(dependent) = dependents.get(*next).copied() {
+                    *next += 1;
+
+                    if dependent == sheet_pos {
+                        self_references.insert(sheet_pos);
+                    }
+
+                    match nodes.get(&dependent) {
+                        None => call_stack.push(visit(dependent, &mut nodes, &mut stack)),
+                        Some(node) if node.on_stack => {
+                            let index = node.index;
+                            if let Some(node) = nodes.get_mut(&sheet_pos) {
+                                node.low_link = node.low_link.min(index);
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                let Some((index, low_link)) = nodes
+                    .get(&sheet_pos)
+                    .map(|node| (node.index, node.low_link))
+                else {
+                    continue;
+                };
+
+                if let Some((parent, _, _)) = call_stack.last() {
+                    if let Some(parent) = nodes.get_mut(parent) {
+                        parent.low_link = parent.low_link.min(low_link);
+                    }
+                }
+
+                // sheet_pos is the root of a group
+                if low_link == index {
+                    let mut cells = vec![];
+                    while let Some(member) = stack.pop() {
+                        if let Some(node) = nodes.get_mut(&member) {
+                            node.on_stack = false;
+                        }
+                        cells.push(member);
+                        if member == sheet_pos {
+                            break;
+                        }
+                    }
+                    cells.reverse();
+
+                    let circular = cells.len() > 1 || self_references.contains(&sheet_pos);
+                    groups.push(CodeCellGroup { cells, circular });
+                }
+            }
+        }
+
+        // groups are found after all of their dependents
+        groups.reverse();
+        groups
+    }
+
+    /// Finds a circular reference through the code cell at sheet_pos, in any
+    /// language.  Returns the code cells in the cycle, starting with
+    /// sheet_pos, where each one depends on the output of the previous one
+    /// and sheet_pos depends on the output of the last one.
+    pub fn get_circular_reference(&self, sheet_pos: SheetPos) -> Option<Vec<SheetPos>> {
+        let mut visited = HashSet::from([sheet_pos]);
+        let mut path = vec![sheet_pos];
+        let mut stack = vec![(self.code_cell_dependents(sheet_pos), 0)];
+
+        while let Some((dependents, next)) = stack.last_mut() {
+            match dependents.get(*next).copied() {
+                Some(dependent) => {
+                    *next += 1;
+
+                    if dependent == sheet_pos {
+                        return Some(path);
+                    }
+
+                    if visited.insert(dependent) {
+                        path.push(dependent);
+                        stack.push((self.code_cell_dependents(dependent), 0));
+                    }
+                }
+                None => {
+                    stack.pop();
+                    path.pop();
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Dependents of a rect, sorted so the recompute order is deterministic.
+    fn sorted_dependents(&self, sheet_rect: &SheetRect) -> Vec<SheetPos> {
+        let mut dependents = self
+            .get_dependent_code_cells(sheet_rect)
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<_>>();
+        dependents.sort_by_cached_key(|sheet_pos| {
+            (sheet_pos.sheet_id.to_string(), sheet_pos.y, sheet_pos.x)
+        });
+        dependents
+    }
+
+    /// Code cells that depend on the output of the code cell at sheet_pos.
+    fn code_cell_dependents(&self, sheet_pos: SheetPos) -> Vec<SheetPos> {
+        self.try_sheet(sheet_pos.sheet_id)
+            .and_then(|sheet| sheet.code_run(sheet_pos.into()))
+            .map(|code_run| self.sorted_dependents(&code_run.output_sheet_rect(sheet_pos, false)))
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -33,9 +213,9 @@ mod test {
     use chrono::Utc;
 
     use crate::{
-        controller::GridController,
+        controller::{operations::operation::Operation, GridController},
         grid::{CellsAccessed, CodeCellLanguage, CodeRun, CodeRunResult},
-        CellValue, Pos, SheetPos, SheetRect, Value,
+        CellValue, Pos, RunError, RunErrorMsg, SheetPos, SheetRect, Value,
     };
 
     #[test]
@@ -75,6 +255,8 @@ mod test {
                 line_number: None,
                 output_type: None,
                 cells_accessed: cells_accessed.clone(),
+                stale: false,
+                timing: None,
             }),
         );
         let sheet_pos_02 = SheetPos {
@@ -146,4 +328,213 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn test_dependencies_in_order() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = |x, y| SheetPos { x, y, sheet_id };
+
+        gc.set_cell_value(sheet_pos(1, 1), "1".to_string(), None);
+
+        // C1 depends on A1 directly and through B1, D1 depends on C1
+        gc.set_code_cell(
+            sheet_pos(4, 1),
+            CodeCellLanguage::Formula,
+            "C1 * 2".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            sheet_pos(3, 1),
+            CodeCellLanguage::Formula,
+            "A1 + B1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            sheet_pos(2, 1),
+            CodeCellLanguage::Formula,
+            "A1 + 1".to_string(),
+            None,
+        );
+
+        assert_eq!(
+            gc.get_dependent_code_cells_in_order(&sheet_pos(1, 1).into(), None),
+            vec![sheet_pos(2, 1), sheet_pos(3, 1), sheet_pos(4, 1)]
+        );
+        assert_eq!(
+            gc.get_dependent_code_cells_in_order(&sheet_pos(2, 1).into(), Some(sheet_pos(2, 1))),
+            vec![sheet_pos(3, 1), sheet_pos(4, 1)]
+        );
+
+        // each dependent is computed once, after the cells it depends on
+        gc.set_cell_value(sheet_pos(1, 1), "2".to_string(), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(Pos { x: 4, y: 1 }),
+            Some(CellValue::Number(10.into()))
+        );
+        let computed = gc
+            .last_transaction()
+            .unwrap()
+            .operations
+            .iter()
+            .filter(|op| matches!(op, Operation::SetCodeRunVersion { .. }))
+            .count();
+        assert_eq!(computed, 3);
+    }
+
+    #[test]
+    fn test_dependencies_after_removing_code_run() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = SheetPos {
+            x: 2,
+            y: 1,
+            sheet_id,
+        };
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Formula, "A1".to_string(), None);
+        assert!(gc
+            .get_dependent_code_cells(&SheetRect::single_pos(Pos { x: 1, y: 1 }, sheet_id))
+            .is_some());
+
+        gc.sheet_mut(sheet_id).set_code_run(sheet_pos.into(), None);
+        assert!(gc
+            .get_dependent_code_cells(&SheetRect::single_pos(Pos { x: 1, y: 1 }, sheet_id))
+            .is_none());
+    }
+
+    #[test]
+    fn test_circular_reference() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = |x, y| SheetPos { x, y, sheet_id };
+        let is_circular = |gc: &GridController, x, y| {
+            matches!(
+                gc.sheet(sheet_id)
+                    .code_run(Pos { x, y })
+                    .map(|run| &run.result),
+                Some(CodeRunResult::Err(RunError {
+                    msg: RunErrorMsg::CircularReference,
+                    ..
+                }))
+            )
+        };
+
+        // A1 -> B1 -> C1 -> A1, and D1 depends on the cycle
+        gc.set_code_cell(
+            sheet_pos(4, 1),
+            CodeCellLanguage::Formula,
+            "C1 + 1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            sheet_pos(2, 1),
+            CodeCellLanguage::Formula,
+            "A1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            sheet_pos(3, 1),
+            CodeCellLanguage::Formula,
+            "B1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            sheet_pos(1, 1),
+            CodeCellLanguage::Formula,
+            "C1".to_string(),
+            None,
+        );
+
+        assert!(is_circular(&gc, 1, 1));
+        assert!(is_circular(&gc, 2, 1));
+        assert!(is_circular(&gc, 3, 1));
+        assert_eq!(
+            gc.sheet(sheet_id)
+                .code_run(Pos { x: 2, y: 1 })
+                .and_then(|run| run.std_err.clone()),
+            Some("Circular reference: B1 -> C1 -> A1 -> B1".to_string())
+        );
+
+        assert_eq!(
+            gc.get_circular_reference(sheet_pos(1, 1)),
+            Some(vec![sheet_pos(1, 1), sheet_pos(2, 1), sheet_pos(3, 1)])
+        );
+        assert_eq!(
+            gc.get_circular_reference(sheet_pos(3, 1)),
+            Some(vec![sheet_pos(3, 1), sheet_pos(1, 1), sheet_pos(2, 1)])
+        );
+        assert_eq!(gc.get_circular_reference(sheet_pos(4, 1)), None);
+
+        let groups = gc.dependent_code_cell_groups(&sheet_pos(1, 1).into());
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].circular);
+        assert_eq!(groups[0].cells.len(), 3);
+        assert_eq!(groups[1].cells, vec![sheet_pos(4, 1)]);
+        assert!(!groups[1].circular);
+
+        // breaking the cycle computes the code cells again
+        gc.set_code_cell(
+            sheet_pos(1, 1),
+            CodeCellLanguage::Formula,
+            "5".to_string(),
+            None,
+        );
+        assert_eq!(gc.get_circular_reference(sheet_pos(1, 1)), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(Pos { x: 3, y: 1 }),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 4, y: 1 }),
+            Some(CellValue::Number(6.into()))
+        );
+    }
+
+    #[test]
+    fn test_circular_reference_across_sheets() {
+        let mut gc = GridController::test();
+        let sheet_1 = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_2 = gc.sheet_ids()[1];
+        let sheet_2_name = gc.sheet(sheet_2).name.clone();
+
+        gc.set_code_cell(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id: sheet_2,
+            },
+            CodeCellLanguage::Formula,
+            "'Sheet 1'!A1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id: sheet_1,
+            },
+            CodeCellLanguage::Formula,
+            format!("'{sheet_2_name}'!A1"),
+            None,
+        );
+
+        let code_run = gc.sheet(sheet_1).code_run(Pos { x: 1, y: 1 }).unwrap();
+        assert_eq!(
+            code_run.result,
+            CodeRunResult::Err(RunError {
+                span: None,
+                msg: RunErrorMsg::CircularReference,
+            })
+        );
+        assert!(gc
+            .get_circular_reference(SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id: sheet_1,
+            })
+            .is_some_and(|cycle| cycle.len() == 2));
+    }
 }
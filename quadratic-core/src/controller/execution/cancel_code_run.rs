@@ -0,0 +1,338 @@
+//! Cancels code runs that async transactions are waiting on, either when the
+//! user asks or when a run takes longer than its language's timeout (eg, the
+//! worker died).  The code cell keeps the result of its previous run.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::GridController;
+use crate::controller::active_transactions::async_timeouts::AsyncTimeouts;
+use crate::controller::active_transactions::pending_transaction::PendingTransaction;
+use crate::controller::operations::operation::Operation;
+use crate::error_core::Result;
+use crate::grid::{CodeCellLanguage, CodeRun};
+use crate::{RunErrorMsg, SheetPos};
+
+impl GridController {
+    pub fn async_timeouts(&self) -> AsyncTimeouts {
+        self.transactions.async_timeouts
+    }
+
+    pub fn set_async_timeouts(&mut self, async_timeouts: AsyncTimeouts) {
+        self.transactions.async_timeouts = async_timeouts;
+    }
+
+    /// Cancels the code run that an async transaction is waiting on. Returns
+    /// the language of the cancelled code run so its worker can be stopped.
+    pub fn cancel_async_transaction(
+        &mut self,
+        transaction_id: String,
+    ) -> Result<Option<CodeCellLanguage>> {
+        let transaction_id = Uuid::parse_str(&transaction_id)?;
+        self.cancel_awaiting_async(transaction_id, false)
+    }
+
+    /// Cancels the code run at sheet_pos, if an async transaction is waiting
+    /// on it. Returns the language of the cancelled code run.
+    pub fn cancel_code_run(&mut self, sheet_pos: SheetPos) -> Option<CodeCellLanguage> {
+        let transaction_id = self
+            .transactions
+            .async_transactions()
+            .iter()
+            .find(|transaction| {
+                transaction.waiting_for_async.is_some()
+                    && transaction.current_sheet_pos == Some(sheet_pos)
+            })
+            .map(|transaction| transaction.id)?;
+
+        self.cancel_awaiting_async(transaction_id, false)
+            .ok()
+            .flatten()
+    }
+
+    /// Cancels every code run that an async transaction is waiting on.
+    /// Returns the languages of the cancelled code runs.
+    pub fn cancel_all_code_runs(&mut self) -> Vec<CodeCellLanguage> {
+        let transaction_ids = self.awaiting_async_transactions(|_| true);
+        self.cancel_all_awaiting_async(transaction_ids, false)
+    }
+
+    /// Times out the code runs that have been waiting longer than the
+    /// timeout for their language. Returns the languages of the code runs
+    /// that timed out.
+    pub fn check_async_timeouts(&mut self) -> Vec<CodeCellLanguage> {
+        let now = Utc::now();
+        let async_timeouts = self.transactions.async_timeouts;
+        let transaction_ids = self.awaiting_async_transactions(|transaction| {
+            let (Some(language), Some(dispatched)) =
+                (&transaction.waiting_for_async, transaction.async_dispatched)
+            else {
+                return false;
+            };
+            async_timeouts
+                .timeout_ms(language)
+                .is_some_and(|timeout_ms| {
+                    (now - dispatched).num_milliseconds() >= timeout_ms as i64
+                })
+        });
+        self.cancel_all_awaiting_async(transaction_ids, true)
+    }
+
+    /// Returns the ids of async transactions waiting on a code run that
+    /// match the filter.
+    fn awaiting_async_transactions(
+        &self,
+        filter: impl Fn(&PendingTransaction) -> bool,
+    ) -> Vec<Uuid> {
+        self.transactions
+            .async_transactions()
+            .iter()
+            .filter(|transaction| transaction.waiting_for_async.is_some() && filter(transaction))
+            .map(|transaction| transaction.id)
+            .collect()
+    }
+
+    fn cancel_all_awaiting_async(
+        &mut self,
+        transaction_ids: Vec<Uuid>,
+        timed_out: bool,
+    ) -> Vec<CodeCellLanguage> {
+        transaction_ids
+            .into_iter()
+            .filter_map(|transaction_id| {
+                self.cancel_awaiting_async(transaction_id, timed_out)
+                    .ok()
+                    .flatten()
+            })
+            .collect()
+    }
+
+    /// Stops waiting on the transaction's code run and finishes the
+    /// transaction. A cancelled transaction skips its remaining code runs,
+    /// while a timed out transaction reports the timeout and continues.
+    fn cancel_awaiting_async(
+        &mut self,
+        transaction_id: Uuid,
+        timed_out: bool,
+    ) -> Result<Option<CodeCellLanguage>> {
+        let mut transaction = self.transactions.remove_awaiting_async(transaction_id)?;
+        let language = transaction.waiting_for_async.take();
+        transaction.cells_accessed.clear();
+        transaction.cells_read = 0;
+        transaction.code_run_started = None;
+        transaction.async_dispatched = None;
+
+        if timed_out {
+            self.code_run_timeout_error(&mut transaction)?;
+        } else {
+            transaction
+                .operations
+                .retain(|op| !matches!(op, Operation::ComputeCode { .. }));
+        }
+
+        self.start_transaction(&mut transaction);
+        self.finalize_transaction(transaction);
+
+        Ok(language)
+    }
+
+    /// Reports a timeout for the transaction's code run. A previous result is
+    /// kept with the timeout in its std_err; otherwise the code cell is set to
+    /// the timeout error.
+    fn code_run_timeout_error(&mut self, transaction: &mut PendingTransaction) -> Result<()> {
+        let Some(sheet_pos) = transaction.current_sheet_pos else {
+            return Ok(());
+        };
+        let error = RunErrorMsg::CodeRunTimeout.without_span();
+        let old_code_run = self
+            .try_sheet(sheet_pos.sheet_id)
+            .and_then(|sheet| sheet.code_run(sheet_pos.into()))
+            .cloned();
+
+        match old_code_run {
+            Some(old_code_run) => {
+                let new_code_run = CodeRun {
+                    std_out: None,
+                    std_err: Some(error.msg.to_string()),
+                    last_modified: Utc::now(),
+                    ..old_code_run
+                };
+                self.finalize_code_run(transaction, sheet_pos, Some(new_code_run), None);
+                Ok(())
+            }
+            None => self.code_cell_sheet_error(transaction, &error),
+        }
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::controller::transaction_types::JsCodeResult;
+    use crate::grid::CodeRunResult;
+    use crate::{CellValue, Pos};
+
+    /// Runs python at sheet_pos and completes it with a number.
+    fn run_python(gc: &mut GridController, sheet_pos: SheetPos, code: &str, output: &str) {
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Python, code.into(), None);
+        let transaction_id = gc.async_transactions()[0].id;
+        gc.calculation_complete(JsCodeResult::new(
+            transaction_id.to_string(),
+            true,
+            None,
+            None,
+            Some(vec![output.into(), "number".into()]),
+            None,
+            None,
+            None,
+            None,
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cancel_code_run_keeps_previous_result() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = SheetPos::new(sheet_id, 1, 1);
+        run_python(&mut gc, sheet_pos, "1", "1");
+
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Python, "2".into(), None);
+        assert_eq!(gc.async_transactions().len(), 1);
+
+        assert_eq!(
+            gc.cancel_code_run(SheetPos::new(sheet_id, 2, 2)),
+            None,
+            "no code run is waiting at B2"
+        );
+        assert_eq!(
+            gc.cancel_code_run(sheet_pos),
+            Some(CodeCellLanguage::Python)
+        );
+        assert!(gc.async_transactions().is_empty());
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Number(1.into()))
+        );
+
+        // there is nothing left to cancel
+        assert_eq!(gc.cancel_code_run(sheet_pos), None);
+    }
+
+    #[test]
+    fn test_cancel_async_transaction() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = SheetPos::new(sheet_id, 1, 1);
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Javascript, "1".into(), None);
+        let transaction_id = gc.async_transactions()[0].id.to_string();
+
+        assert_eq!(
+            gc.cancel_async_transaction(transaction_id.clone()).unwrap(),
+            Some(CodeCellLanguage::Javascript)
+        );
+        assert!(gc.async_transactions().is_empty());
+        assert!(gc.sheet(sheet_id).code_run(sheet_pos.into()).is_none());
+
+        assert!(gc.cancel_async_transaction(transaction_id).is_err());
+        assert!(gc.cancel_async_transaction("bad id".into()).is_err());
+    }
+
+    #[test]
+    fn test_cancel_all_code_runs() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 1),
+            CodeCellLanguage::Python,
+            "1".into(),
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 2, 1),
+            CodeCellLanguage::Javascript,
+            "1".into(),
+            None,
+        );
+        assert_eq!(gc.async_transactions().len(), 2);
+
+        assert_eq!(
+            gc.cancel_all_code_runs(),
+            vec![CodeCellLanguage::Python, CodeCellLanguage::Javascript]
+        );
+        assert!(gc.async_transactions().is_empty());
+        assert!(gc.cancel_all_code_runs().is_empty());
+    }
+
+    #[test]
+    fn test_check_async_timeouts() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = SheetPos::new(sheet_id, 1, 1);
+        run_python(&mut gc, sheet_pos, "1", "1");
+
+        // nothing times out without a timeout
+        gc.set_async_timeouts(AsyncTimeouts {
+            python_ms: None,
+            javascript_ms: None,
+            connection_ms: None,
+        });
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Python, "2".into(), None);
+        assert!(gc.check_async_timeouts().is_empty());
+        assert_eq!(gc.async_transactions().len(), 1);
+
+        // a timed out code run keeps its previous result
+        gc.set_async_timeouts(AsyncTimeouts {
+            python_ms: Some(0),
+            ..gc.async_timeouts()
+        });
+        assert_eq!(gc.check_async_timeouts(), vec![CodeCellLanguage::Python]);
+        assert!(gc.async_transactions().is_empty());
+        let code_run = gc.sheet(sheet_id).code_run(sheet_pos.into()).unwrap();
+        assert_eq!(
+            code_run.result,
+            CodeRunResult::Ok(crate::Value::Single(CellValue::Number(1.into())))
+        );
+        assert_eq!(
+            code_run.std_err,
+            Some(RunErrorMsg::CodeRunTimeout.to_string())
+        );
+
+        // a timed out code run without a previous result is an error
+        let sheet_pos = SheetPos::new(sheet_id, 2, 1);
+        gc.set_code_cell(sheet_pos, CodeCellLanguage::Python, "3".into(), None);
+        assert_eq!(gc.check_async_timeouts(), vec![CodeCellLanguage::Python]);
+        let code_run = gc.sheet(sheet_id).code_run(sheet_pos.into()).unwrap();
+        assert_eq!(
+            code_run.result,
+            CodeRunResult::Err(RunErrorMsg::CodeRunTimeout.without_span())
+        );
+    }
+
+    #[test]
+    fn test_check_async_timeouts_from_dispatch() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_async_timeouts(AsyncTimeouts {
+            python_ms: Some(60_000),
+            ..gc.async_timeouts()
+        });
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 1),
+            CodeCellLanguage::Python,
+            "1".into(),
+            None,
+        );
+        let started = Utc::now() - chrono::Duration::minutes(2);
+
+        // the clock starts when the code run is sent to the worker
+        let transaction = &mut gc.transactions.async_transactions_mut()[0];
+        transaction.code_run_started = Some(started);
+        assert!(gc.check_async_timeouts().is_empty());
+
+        let transaction = &mut gc.transactions.async_transactions_mut()[0];
+        transaction.async_dispatched = Some(started);
+        assert_eq!(gc.check_async_timeouts(), vec![CodeCellLanguage::Python]);
+    }
+}
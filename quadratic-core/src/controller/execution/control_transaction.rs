@@ -9,7 +9,7 @@ use crate::controller::transaction::Transaction;
 use crate::controller::transaction_types::JsCodeResult;
 use crate::error_core::Result;
 use crate::grid::js_types::JsHtmlOutput;
-use crate::grid::{CodeRun, CodeRunResult};
+use crate::grid::{CodeRun, CodeRunResult, ConnectionTiming};
 use crate::parquet::parquet_to_vec;
 use crate::renderer_constants::{CELL_SHEET_HEIGHT, CELL_SHEET_WIDTH};
 use crate::{Pos, RunError, RunErrorMsg, Value};
@@ -149,6 +149,12 @@ impl GridController {
                 }
             });
 
+            transaction.tables.iter().for_each(|sheet_id| {
+                if let Some(sheet) = self.try_sheet(*sheet_id) {
+                    sheet.send_all_tables();
+                }
+            });
+
             transaction
                 .validations_warnings
                 .iter()
@@ -273,6 +279,7 @@ impl GridController {
         std_out: Option<String>,
         std_err: Option<String>,
         extra: Option<String>,
+        connection_timing: Option<ConnectionTiming>,
     ) -> Result<()> {
         let transaction_id = Uuid::parse_str(&transaction_id)?;
         let mut transaction = self.transactions.remove_awaiting_async(transaction_id)?;
@@ -296,6 +303,7 @@ impl GridController {
             } else {
                 CodeRunResult::Ok(Value::Array(array.into()))
             };
+            let timing = transaction.code_run_timing(&result, connection_timing);
 
             let code_run = CodeRun {
                 formatted_code_string: None,
@@ -308,6 +316,8 @@ impl GridController {
                 spill_error: false,
                 last_modified: Utc::now(),
                 cells_accessed: transaction.cells_accessed.clone(),
+                stale: false,
+                timing,
             };
 
             transaction.cells_accessed.clear();
@@ -319,6 +329,27 @@ impl GridController {
 
         Ok(())
     }
+
+    /// Externally called when an async connection is cancelled by the user or
+    /// times out in the connection service
+    pub fn connection_cancelled(&mut self, transaction_id: String, timed_out: bool) -> Result<()> {
+        let transaction_id = Uuid::parse_str(&transaction_id)?;
+        let mut transaction = self.transactions.remove_awaiting_async(transaction_id)?;
+
+        if transaction.current_sheet_pos.is_some() {
+            let msg = if timed_out {
+                RunErrorMsg::QueryTimeout
+            } else {
+                RunErrorMsg::QueryCancelled
+            };
+
+            self.code_cell_sheet_error(&mut transaction, &msg.without_span())?;
+            self.start_transaction(&mut transaction);
+            self.finalize_transaction(transaction);
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -530,8 +561,60 @@ mod tests {
             None,
             Some("error".into()),
             None,
+            Some(ConnectionTiming {
+                connect_ms: 5,
+                query_ms: 20,
+                total_ms: 30,
+            }),
         );
 
         assert!(result.is_ok());
+
+        let code_run = gc.sheet(sheet_id).code_run(Pos { x: 0, y: 0 }).unwrap();
+        let timing = code_run.timing.clone().unwrap();
+        assert_eq!(
+            timing.connection,
+            Some(ConnectionTiming {
+                connect_ms: 5,
+                query_ms: 20,
+                total_ms: 30,
+            })
+        );
+        assert_eq!((timing.output_w, timing.output_h), (0, 0));
+    }
+
+    #[test]
+    #[parallel]
+    fn test_connection_cancelled() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = SheetPos {
+            x: 0,
+            y: 0,
+            sheet_id,
+        };
+        gc.set_code_cell(
+            sheet_pos,
+            CodeCellLanguage::Connection {
+                kind: ConnectionKind::Postgres,
+                id: Uuid::new_v4().to_string(),
+            },
+            "select * from table".into(),
+            None,
+        );
+
+        let transaction_id = gc.last_transaction().unwrap().id;
+        let result = gc.connection_cancelled(transaction_id.to_string(), true);
+        assert!(result.is_ok());
+
+        let code_run = gc.sheet(sheet_id).code_run(sheet_pos.into()).unwrap();
+        assert_eq!(
+            code_run.result,
+            CodeRunResult::Err(RunErrorMsg::QueryTimeout.without_span())
+        );
+
+        // the transaction is no longer awaiting the connection
+        let result = gc.connection_cancelled(transaction_id.to_string(), false);
+        assert!(result.is_err());
     }
 }
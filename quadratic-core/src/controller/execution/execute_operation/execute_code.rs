@@ -1,3 +1,5 @@
+use chrono::Utc;
+
 use crate::{
     controller::{
         active_transactions::pending_transaction::PendingTransaction,
@@ -8,34 +10,87 @@ use crate::{
 };
 
 impl GridController {
-    /// Adds operations to compute cells that are dependents within a SheetRect
+    /// Adds operations to compute cells that are dependents within a
+    /// SheetRect, including the dependents of those cells, in topological
+    /// order so that each dependent is computed once per transaction.  Code
+    /// cells in a circular reference are set to an error instead, and code
+    /// cells that the calculation mode doesn't compute are marked stale.
     pub fn add_compute_operations(
         &mut self,
         transaction: &mut PendingTransaction,
         output: &SheetRect,
         skip_compute: Option<SheetPos>,
     ) {
-        self.get_dependent_code_cells(output)
-            .iter()
-            .for_each(|sheet_positions| {
-                sheet_positions.iter().for_each(|code_cell_sheet_pos| {
-                    if !skip_compute
-                        .is_some_and(|skip_compute| skip_compute == *code_cell_sheet_pos)
-                    {
-                        // only add a compute operation if there isn't already one pending
-                        if !transaction.operations.iter().any(|op| match op {
-                            Operation::ComputeCode { sheet_pos } => {
-                                code_cell_sheet_pos == sheet_pos
-                            }
-                            _ => false,
-                        }) {
-                            transaction.operations.push_back(Operation::ComputeCode {
-                                sheet_pos: *code_cell_sheet_pos,
-                            });
-                        }
-                    }
-                });
+        let calculation_mode = self.grid.calculation_mode();
+
+        for group in self.dependent_code_cell_groups(output) {
+            if group.circular {
+                self.set_circular_reference_errors(transaction, &group.cells);
+                continue;
+            }
+
+            for code_cell_sheet_pos in group.cells {
+                if skip_compute == Some(code_cell_sheet_pos) {
+                    continue;
+                }
+
+                let computes = self
+                    .code_cell_language(code_cell_sheet_pos)
+                    .is_some_and(|language| calculation_mode.computes(&language));
+                if !computes {
+                    self.set_code_run_stale(transaction, code_cell_sheet_pos);
+                    continue;
+                }
+
+                // only add a compute operation if there isn't already one pending
+                if !transaction.operations.iter().any(|op| match op {
+                    Operation::ComputeCode { sheet_pos } => code_cell_sheet_pos == *sheet_pos,
+                    _ => false,
+                }) {
+                    transaction.operations.push_back(Operation::ComputeCode {
+                        sheet_pos: code_cell_sheet_pos,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns the language of the code cell at sheet_pos.
+    fn code_cell_language(&self, sheet_pos: SheetPos) -> Option<CodeCellLanguage> {
+        let sheet = self.try_sheet(sheet_pos.sheet_id)?;
+        match sheet.cell_value_ref(sheet_pos.into()) {
+            Some(CellValue::Code(code_cell)) => Some(code_cell.language.clone()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn execute_set_calculation_mode(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        op: Operation,
+    ) {
+        unwrap_op!(let SetCalculationMode { calculation_mode } = op);
+
+        let old_calculation_mode = self.grid.calculation_mode();
+        self.grid.set_calculation_mode(calculation_mode);
+
+        transaction
+            .forward_operations
+            .push(Operation::SetCalculationMode { calculation_mode });
+        transaction
+            .reverse_operations
+            .push(Operation::SetCalculationMode {
+                calculation_mode: old_calculation_mode,
             });
+
+        // compute the stale code cells that the new mode no longer pauses
+        if transaction.is_user_undo_redo() {
+            let ops = self.recalculate_operations(|sheet_id, pos| {
+                self.code_cell_language(pos.to_sheet_pos(sheet_id))
+                    .is_some_and(|language| calculation_mode.computes(&language))
+            });
+            transaction.operations.extend(ops);
+        }
     }
 
     // delete any code runs within the sheet_rect.
@@ -142,6 +197,9 @@ impl GridController {
                 _ => return,
             };
 
+            transaction.cells_read = 0;
+            transaction.code_run_started = Some(Utc::now());
+
             match language {
                 CodeCellLanguage::Python => {
                     self.run_python(transaction, sheet_pos, code);
@@ -98,7 +98,9 @@ impl GridController {
                     };
 
                     for cells_range in cells_ranges.iter() {
-                        let cells_rect = sheet.cell_ref_range_to_rect(*cells_range);
+                        let Some(cells_rect) = sheet.cell_ref_range_to_rect(cells_range) else {
+                            continue;
+                        };
 
                         if cells_rect.max.x < column.unwrap_or(UNBOUNDED)
                             && cells_rect.max.y < row.unwrap_or(UNBOUNDED)
@@ -454,6 +456,8 @@ mod tests {
             last_modified: Utc::now(),
             cells_accessed,
             formatted_code_string: None,
+            stale: false,
+            timing: None,
         };
         let transaction = &mut PendingTransaction::default();
         gc.finalize_code_run(transaction, sheet_pos, Some(code_run), None);
@@ -530,6 +534,8 @@ mod tests {
             last_modified: Utc::now(),
             cells_accessed,
             formatted_code_string: None,
+            stale: false,
+            timing: None,
         };
         let transaction = &mut PendingTransaction::default();
         gc.finalize_code_run(transaction, sheet_pos, Some(code_run), None);
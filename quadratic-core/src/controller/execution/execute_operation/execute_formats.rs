@@ -98,6 +98,8 @@ mod test {
                 return_type: None,
                 line_number: None,
                 last_modified: Utc::now(),
+                stale: false,
+                timing: None,
             }),
         );
 
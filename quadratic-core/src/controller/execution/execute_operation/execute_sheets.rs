@@ -3,11 +3,99 @@ use crate::{
         active_transactions::pending_transaction::PendingTransaction,
         operations::operation::Operation, GridController,
     },
-    grid::{file::sheet_schema::export_sheet, Sheet, SheetId},
+    formulas::{find_cell_references, replace_sheet_names, RangeRef},
+    grid::{file::sheet_schema::export_sheet, CodeCellLanguage, Sheet, SheetId},
+    CellValue, Pos,
 };
 use lexicon_fractional_index::key_between;
 
 impl GridController {
+    /// Returns whether a formula contains a 3D reference, such as
+    /// `Jan:Dec!B5`.
+    fn has_sheet_span_reference(&self, code: &str, pos: Pos) -> bool {
+        find_cell_references(code, pos)
+            .into_iter()
+            .any(|range_ref| {
+                let sheet = match range_ref.inner {
+                    RangeRef::RowRange { sheet, .. } | RangeRef::ColRange { sheet, .. } => sheet,
+                    RangeRef::CellRange { start, .. } => start.sheet,
+                    RangeRef::Cell { pos } => pos.sheet,
+                };
+                sheet.is_some_and(|name| {
+                    name.contains(':') && self.grid.try_sheet_from_name(name).is_none()
+                })
+            })
+    }
+
+    /// Returns the new name of a 3D reference when `deleted_sheet_id` is
+    /// deleted. If the deleted sheet is an endpoint, the reference ends at the
+    /// adjacent sheet inside the span instead.
+    fn sheet_span_name_without(&self, name: &str, deleted_sheet_id: SheetId) -> Option<String> {
+        if self.grid.try_sheet_from_name(name.to_string()).is_some() {
+            return None;
+        }
+        let (first, last) = self.grid.try_sheet_span_endpoints(name)?;
+        if first != deleted_sheet_id && last != deleted_sheet_id {
+            return None;
+        }
+        let mut sheet_ids = self.grid.sheet_ids_between(first, last)?;
+        sheet_ids.retain(|id| *id != deleted_sheet_id);
+        if self.grid.sheet_id_to_index(first) > self.grid.sheet_id_to_index(last) {
+            sheet_ids.reverse();
+        }
+        let first = &self.grid.try_sheet(*sheet_ids.first()?)?.name;
+        let last = &self.grid.try_sheet(*sheet_ids.last()?)?.name;
+        if first == last {
+            Some(first.clone())
+        } else {
+            Some(format!("{first}:{last}"))
+        }
+    }
+
+    /// Reruns formulas with 3D references, since the sheets they cover change
+    /// when sheets are added, moved or deleted. For user actions, 3D
+    /// references that end at `deleted_sheet_id` are updated to end at the
+    /// adjacent sheet. This must be called before the sheet is removed.
+    fn update_sheet_span_formulas(
+        &self,
+        transaction: &mut PendingTransaction,
+        deleted_sheet_id: Option<SheetId>,
+    ) {
+        for sheet in self.grid.sheets().iter() {
+            if Some(sheet.id) == deleted_sheet_id {
+                continue;
+            }
+            for pos in sheet.code_runs.keys() {
+                let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) else {
+                    continue;
+                };
+                if code.language != CodeCellLanguage::Formula
+                    || !self.has_sheet_span_reference(&code.code, *pos)
+                {
+                    continue;
+                }
+                let sheet_pos = pos.to_sheet_pos(sheet.id);
+                if let Some(deleted_sheet_id) = deleted_sheet_id.filter(|_| transaction.is_user()) {
+                    let new_code = replace_sheet_names(&code.code, *pos, |name| {
+                        self.sheet_span_name_without(name, deleted_sheet_id)
+                            .unwrap_or_else(|| name.to_string())
+                    });
+                    if new_code != code.code {
+                        let mut new_code_cell = code.clone();
+                        new_code_cell.code = new_code;
+                        transaction.operations.push_back(Operation::SetCellValues {
+                            sheet_pos,
+                            values: CellValue::Code(new_code_cell).into(),
+                        });
+                    }
+                }
+                transaction
+                    .operations
+                    .push_back(Operation::ComputeCode { sheet_pos });
+            }
+        }
+    }
+
     pub(crate) fn execute_add_sheet(
         &mut self,
         transaction: &mut PendingTransaction,
@@ -23,6 +111,10 @@ impl GridController {
 
             self.send_add_sheet(sheet_id, transaction);
 
+            if transaction.is_user_undo_redo() {
+                self.update_sheet_span_formulas(transaction, None);
+            }
+
             transaction
                 .forward_operations
                 .push(Operation::AddSheetSchema {
@@ -51,6 +143,10 @@ impl GridController {
                 self.send_add_sheet(sheet_id, transaction);
                 self.send_all_fills(sheet_id);
 
+                if transaction.is_user_undo_redo() {
+                    self.update_sheet_span_formulas(transaction, None);
+                }
+
                 transaction
                     .forward_operations
                     .push(Operation::AddSheetSchema { schema });
@@ -72,6 +168,10 @@ impl GridController {
             // get code run operations for the sheet
             let code_run_ops = self.rerun_sheet_code_cells_operations(sheet_id);
 
+            if transaction.is_user_undo_redo() && self.grid.try_sheet(sheet_id).is_some() {
+                self.update_sheet_span_formulas(transaction, Some(sheet_id));
+            }
+
             let Some(deleted_sheet) = self.grid.remove_sheet(sheet_id) else {
                 // sheet was already deleted
                 return;
@@ -142,6 +242,10 @@ impl GridController {
                 });
 
             transaction.sheet_info.insert(target);
+
+            if transaction.is_user_undo_redo() {
+                self.update_sheet_span_formulas(transaction, None);
+            }
         }
     }
 
@@ -231,6 +335,10 @@ impl GridController {
 
             self.send_add_sheet(new_sheet_id, transaction);
 
+            if transaction.is_user_undo_redo() {
+                self.update_sheet_span_formulas(transaction, None);
+            }
+
             transaction
                 .forward_operations
                 .push(Operation::DuplicateSheet {
@@ -574,4 +682,56 @@ mod tests {
             true,
         );
     }
+
+    #[test]
+    #[serial]
+    fn test_sheet_span_formula_sheet_changes() {
+        let mut gc = GridController::test();
+        let summary_id = gc.sheet_ids()[0];
+        for name in ["Jan", "Feb", "Mar"] {
+            gc.add_sheet_with_name(name.to_string(), None);
+        }
+        let ids = gc.sheet_ids();
+        for (i, sheet_id) in ids[1..].iter().enumerate() {
+            gc.set_cell_value(SheetPos::new(*sheet_id, 2, 5), (i + 1).to_string(), None);
+        }
+        let formula_pos = SheetPos::new(summary_id, 1, 1);
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "SUM(Jan:Mar!B5)".into(),
+            None,
+        );
+        let value = |gc: &GridController| gc.sheet(summary_id).display_value(formula_pos.into());
+        assert_eq!(value(&gc), Some(CellValue::Number(6.into())));
+
+        // changing a cell on any covered sheet reruns the formula
+        gc.set_cell_value(SheetPos::new(ids[2], 2, 5), "20".into(), None);
+        assert_eq!(value(&gc), Some(CellValue::Number(24.into())));
+
+        // a sheet inserted between the endpoints is included
+        gc.duplicate_sheet(ids[2], None);
+        assert_eq!(value(&gc), Some(CellValue::Number(44.into())));
+
+        // a sheet moved outside the endpoints is excluded
+        gc.move_sheet(ids[2], None, None);
+        assert_eq!(value(&gc), Some(CellValue::Number(24.into())));
+
+        // deleting an endpoint moves the reference to the adjacent sheet
+        gc.delete_sheet(ids[1], None);
+        let Some(CellValue::Code(code)) = gc.sheet(summary_id).cell_value(formula_pos.into())
+        else {
+            panic!("expected code cell");
+        };
+        assert!(code.code.contains("\"Feb Copy:Mar\"!"));
+        assert_eq!(value(&gc), Some(CellValue::Number(23.into())));
+
+        gc.undo(None);
+        let Some(CellValue::Code(code)) = gc.sheet(summary_id).cell_value(formula_pos.into())
+        else {
+            panic!("expected code cell");
+        };
+        assert!(code.code.contains("\"Jan:Mar\"!"));
+        assert_eq!(value(&gc), Some(CellValue::Number(24.into())));
+    }
 }
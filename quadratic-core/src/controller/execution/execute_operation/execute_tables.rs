@@ -0,0 +1,319 @@
+use crate::controller::active_transactions::pending_transaction::PendingTransaction;
+use crate::controller::operations::operation::Operation;
+use crate::controller::GridController;
+use crate::grid::sheet::tables::{Table, TableColumn};
+use crate::grid::{CodeCellValue, SheetId};
+use crate::{CellValue, Pos, Rect, SheetNameIdMap};
+
+impl GridController {
+    /// Rewrites the code of all code cells in the file using `update_fn`. Code
+    /// cells that change are set and rerun.
+    fn update_code_cells_table_references(
+        &self,
+        transaction: &mut PendingTransaction,
+        update_fn: impl Fn(&mut CodeCellValue, &SheetId, &SheetNameIdMap),
+    ) {
+        let sheet_map = self.grid.sheet_name_id_map();
+        for sheet in self.grid.sheets().iter() {
+            for pos in sheet.code_runs.keys() {
+                if let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) {
+                    let mut new_code = code.clone();
+                    update_fn(&mut new_code, &sheet.id, &sheet_map);
+                    if new_code.code != code.code {
+                        let sheet_pos = pos.to_sheet_pos(sheet.id);
+                        transaction.operations.push_back(Operation::SetCellValues {
+                            sheet_pos,
+                            values: CellValue::Code(new_code).into(),
+                        });
+                        transaction
+                            .operations
+                            .push_back(Operation::ComputeCode { sheet_pos });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Updates references in code cells when a table or its columns are
+    /// renamed.
+    fn rename_table_references(
+        &self,
+        transaction: &mut PendingTransaction,
+        old_table: &Table,
+        new_table: &Table,
+    ) {
+        if old_table.name != new_table.name {
+            self.update_code_cells_table_references(transaction, |code, sheet_id, sheet_map| {
+                code.rename_table(&old_table.name, &new_table.name, sheet_id, sheet_map);
+            });
+        }
+
+        // columns are only renamed (rather than inserted or removed) if the
+        // number of columns is unchanged
+        if old_table.columns.len() == new_table.columns.len() {
+            for (old_column, new_column) in old_table.columns.iter().zip(&new_table.columns) {
+                if old_column.name != new_column.name {
+                    self.update_code_cells_table_references(
+                        transaction,
+                        |code, sheet_id, sheet_map| {
+                            code.rename_table_column(
+                                &new_table.name,
+                                &old_column.name,
+                                &new_column.name,
+                                sheet_id,
+                                sheet_map,
+                            );
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Expands tables on the sheet to include values set in the row directly
+    /// below them (if there is no totals row) or the column directly to their
+    /// right.
+    pub(crate) fn expand_tables(
+        &self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        rect: Rect,
+    ) {
+        let Some(sheet) = self.try_sheet(sheet_id) else {
+            return;
+        };
+        for table in sheet.tables.iter() {
+            let bounds = table.bounds;
+            let mut new_bounds = bounds;
+            if !table.show_totals
+                && rect.min.y == bounds.max.y + 1
+                && rect.min.x <= bounds.max.x
+                && rect.max.x >= bounds.min.x
+            {
+                new_bounds.max.y = rect.max.y;
+            }
+            if rect.min.x == bounds.max.x + 1
+                && rect.min.y <= bounds.max.y
+                && rect.max.y >= bounds.min.y
+            {
+                new_bounds.max.x = rect.max.x;
+            }
+            if new_bounds == bounds
+                || sheet
+                    .tables
+                    .tables_in_rect(new_bounds)
+                    .any(|other| other.id != table.id)
+            {
+                continue;
+            }
+
+            let mut new_table = table.clone();
+            new_table.bounds = new_bounds;
+            for x in bounds.max.x + 1..=new_bounds.max.x {
+                let name = table
+                    .header_row()
+                    .and_then(|y| sheet.display_value(Pos { x, y }))
+                    .map(|value| value.to_display().trim().to_string())
+                    .filter(|name| {
+                        crate::is_valid_table_column_name(name)
+                            && new_table.column_index(name).is_none()
+                    })
+                    .unwrap_or_else(|| new_table.default_column_name());
+                new_table.columns.push(TableColumn { name });
+            }
+            transaction.operations.push_back(Operation::SetTable {
+                sheet_id,
+                table: new_table,
+            });
+        }
+    }
+
+    pub(crate) fn execute_set_table(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        op: Operation,
+    ) {
+        if let Operation::SetTable { sheet_id, table } = op {
+            let Some(sheet) = self.grid.try_sheet_mut(sheet_id) else {
+                return;
+            };
+            let old_table = sheet.tables.table(table.id).cloned();
+
+            transaction.forward_operations.push(Operation::SetTable {
+                sheet_id,
+                table: table.clone(),
+            });
+            transaction
+                .reverse_operations
+                .extend(sheet.tables.set(sheet_id, table.clone()));
+            transaction.tables.insert(sheet_id);
+
+            let mut rect = table.bounds;
+            if let Some(old_table) = &old_table {
+                rect.union_in_place(&old_table.bounds);
+            }
+            let sheet_rect = rect.to_sheet_rect(sheet_id);
+
+            if !transaction.is_server() {
+                transaction.add_dirty_hashes_from_sheet_rect(sheet_rect);
+                self.send_updated_bounds(sheet_id);
+            }
+
+            if transaction.is_user() {
+                if let Some(old_table) = &old_table {
+                    self.rename_table_references(transaction, old_table, &table);
+                }
+                self.add_compute_operations(transaction, &sheet_rect, None);
+            }
+        }
+    }
+
+    pub(crate) fn execute_remove_table(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        op: Operation,
+    ) {
+        if let Operation::RemoveTable { sheet_id, table_id } = op {
+            let Some(sheet) = self.grid.try_sheet_mut(sheet_id) else {
+                return;
+            };
+            let Some(bounds) = sheet.tables.table(table_id).map(|table| table.bounds) else {
+                return;
+            };
+
+            transaction
+                .forward_operations
+                .push(Operation::RemoveTable { sheet_id, table_id });
+            transaction
+                .reverse_operations
+                .extend(sheet.tables.remove(sheet_id, table_id));
+            transaction.tables.insert(sheet_id);
+
+            let sheet_rect = bounds.to_sheet_rect(sheet_id);
+
+            if !transaction.is_server() {
+                transaction.add_dirty_hashes_from_sheet_rect(sheet_rect);
+                self.send_updated_bounds(sheet_id);
+            }
+
+            if transaction.is_user() {
+                self.add_compute_operations(transaction, &sheet_rect, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use crate::controller::GridController;
+    use crate::grid::sheet::tables::Table;
+    use crate::grid::CodeCellLanguage;
+    use crate::{CellValue, Pos, Rect, SheetPos};
+
+    fn test_table(gc: &mut GridController) -> Table {
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 1), "Item".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 1), "Amount".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 2), "10".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 3), "20".into(), None);
+        gc.add_table(
+            Rect::test_a1("A1:B3").to_sheet_rect(sheet_id),
+            Some("Sales".into()),
+            true,
+            None,
+        )
+        .unwrap();
+        gc.sheet(sheet_id)
+            .tables
+            .table_by_name("Sales")
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    #[parallel]
+    fn test_expand_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        test_table(&mut gc);
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 5), "30".into(), None);
+        let table = gc.sheet(sheet_id).tables.table_by_name("Sales").unwrap();
+        assert_eq!(table.bounds, Rect::test_a1("A1:B3"));
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 4), "30".into(), None);
+        let table = gc.sheet(sheet_id).tables.table_by_name("Sales").unwrap();
+        assert_eq!(table.bounds, Rect::test_a1("A1:B4"));
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 3, 1), "Price".into(), None);
+        let table = gc.sheet(sheet_id).tables.table_by_name("Sales").unwrap();
+        assert_eq!(table.bounds, Rect::test_a1("A1:C4"));
+        assert_eq!(table.columns[2].name, "Price");
+
+        gc.undo(None);
+        let table = gc.sheet(sheet_id).tables.table_by_name("Sales").unwrap();
+        assert_eq!(table.bounds, Rect::test_a1("A1:B4"));
+        assert_eq!(gc.sheet(sheet_id).display_value(Pos { x: 3, y: 1 }), None);
+    }
+
+    #[test]
+    #[parallel]
+    fn test_expand_table_updates_formula() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        test_table(&mut gc);
+
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 5, 1),
+            CodeCellLanguage::Formula,
+            "SUM(Sales[Amount])".into(),
+            None,
+        );
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(Pos { x: 5, y: 1 }),
+            Some(CellValue::Number(30.into()))
+        );
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 4), "5".into(), None);
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(Pos { x: 5, y: 1 }),
+            Some(CellValue::Number(35.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn test_rename_table_updates_code() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let table = test_table(&mut gc);
+
+        let formula_pos = SheetPos::new(sheet_id, 5, 1);
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "SUM(Sales[Amount])".into(),
+            None,
+        );
+
+        gc.rename_table(sheet_id, table.id, "Revenue".into(), None)
+            .unwrap();
+        gc.rename_table_column(sheet_id, table.id, 1, "Total".into(), None)
+            .unwrap();
+
+        let Some(CellValue::Code(code)) = gc.sheet(sheet_id).cell_value(formula_pos.into()) else {
+            panic!("expected code cell");
+        };
+        assert_eq!(code.code, "SUM(Revenue[Total])");
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(Pos { x: 2, y: 1 }),
+            Some(CellValue::Text("Total".into()))
+        );
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(Pos { x: 5, y: 1 }),
+            Some(CellValue::Number(30.into()))
+        );
+    }
+}
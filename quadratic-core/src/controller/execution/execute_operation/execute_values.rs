@@ -24,6 +24,10 @@ impl GridController {
                         return;
                     }
 
+                    let has_content = values
+                        .into_iter()
+                        .any(|(_, _, value)| !value.is_blank_or_empty_string());
+
                     if cfg!(target_family = "wasm")
                         && !transaction.is_server()
                         && values.into_iter().any(|(_, _, value)| value.is_html())
@@ -50,6 +54,13 @@ impl GridController {
                             .push(Operation::SetCellValues { sheet_pos, values });
 
                         if transaction.is_user() {
+                            if has_content {
+                                self.expand_tables(
+                                    transaction,
+                                    sheet_rect.sheet_id,
+                                    sheet_rect.into(),
+                                );
+                            }
                             self.check_deleted_code_runs(transaction, &sheet_rect);
                             self.add_compute_operations(transaction, &sheet_rect, None);
                             self.check_all_spills(transaction, sheet_rect.sheet_id, true);
@@ -34,6 +34,7 @@ mod execute_formats_old;
 mod execute_move_cells;
 mod execute_offsets;
 mod execute_sheets;
+mod execute_tables;
 mod execute_validation;
 mod execute_values;
 
@@ -92,10 +93,17 @@ impl GridController {
                 self.execute_set_validation_warning(transaction, op);
             }
 
+            Operation::SetTable { .. } => self.execute_set_table(transaction, op),
+            Operation::RemoveTable { .. } => self.execute_remove_table(transaction, op),
+
             Operation::DeleteColumn { .. } => self.execute_delete_column(transaction, op),
             Operation::DeleteRow { .. } => self.execute_delete_row(transaction, op),
             Operation::InsertColumn { .. } => self.execute_insert_column(transaction, op),
             Operation::InsertRow { .. } => self.execute_insert_row(transaction, op),
+
+            Operation::SetCalculationMode { .. } => {
+                self.execute_set_calculation_mode(transaction, op);
+            }
         }
     }
 }
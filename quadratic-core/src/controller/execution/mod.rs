@@ -1,4 +1,5 @@
 pub mod auto_resize_row_heights;
+pub mod cancel_code_run;
 pub mod control_transaction;
 pub mod execute_operation;
 pub mod receive_multiplayer;
@@ -262,6 +262,29 @@ impl GridController {
         self.finalize_transaction(results);
     }
 
+    /// The server rejected one of our transactions (eg, it changed cells locked
+    /// by another user). We roll back the unsaved transactions, drop the
+    /// rejected one (including its undo/redo entries), and reapply the rest.
+    pub fn reject_transaction(&mut self, transaction_id: Uuid) {
+        let Some(index) = self
+            .transactions
+            .unsaved_transactions
+            .find_index(transaction_id)
+        else {
+            return;
+        };
+        let mut transaction = PendingTransaction {
+            source: TransactionSource::Multiplayer,
+            ..Default::default()
+        };
+        self.rollback_unsaved_transactions(&mut transaction);
+        self.transactions.unsaved_transactions.remove(index);
+        self.undo_stack.retain(|t| t.id != transaction_id);
+        self.redo_stack.retain(|t| t.id != transaction_id);
+        self.reapply_unsaved_transactions(&mut transaction);
+        self.finalize_transaction(transaction);
+    }
+
     /// Called by TS for each offline transaction it has in its offline queue.
     pub fn apply_offline_unsaved_transaction(
         &mut self,
@@ -1172,4 +1195,30 @@ mod tests {
             Some(CellValue::Number(BigDecimal::from(3)))
         );
     }
+
+    #[test]
+    #[parallel]
+    fn test_reject_transaction() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(pos![A1].to_sheet_pos(sheet_id), "1".to_string(), None);
+        let rejected_id = gc.last_transaction().unwrap().id;
+        gc.set_cell_value(pos![A2].to_sheet_pos(sheet_id), "2".to_string(), None);
+        assert_eq!(gc.transactions.unsaved_transactions.len(), 2);
+
+        gc.reject_transaction(rejected_id);
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(sheet.display_value(pos![A1]), None);
+        assert_eq!(
+            sheet.display_value(pos![A2]),
+            Some(CellValue::Number(BigDecimal::from(2)))
+        );
+        assert_eq!(gc.transactions.unsaved_transactions.len(), 1);
+        assert!(gc.undo_stack.iter().all(|t| t.id != rejected_id));
+
+        // rejecting an unknown transaction is a no-op
+        gc.reject_transaction(Uuid::new_v4());
+        assert_eq!(gc.transactions.unsaved_transactions.len(), 1);
+    }
 }
@@ -1,8 +1,15 @@
+use std::collections::HashMap;
+
 use ts_rs::TS;
 use uuid::Uuid;
 
 use crate::{
-    controller::GridController, error_core::CoreError, CellRefRange, RunError, RunErrorMsg,
+    a1::column_name,
+    arrow_ipc::{arrow_ipc_to_vec_with_metadata, vec_to_arrow_ipc, HEADERS_METADATA_KEY},
+    controller::GridController,
+    error_core::CoreError,
+    grid::SheetId,
+    CellRefRange, CellValue, Pos, Rect, RunError, RunErrorMsg,
 };
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +31,47 @@ pub struct JsGetCellResponse {
     pub type_name: String,
 }
 
+impl CellA1Response {
+    /// Decodes the Arrow IPC stream from `calculation_get_cells_a1_arrow`.
+    /// This is used by the language workers so that core only has to encode
+    /// the cells.
+    pub fn from_arrow_ipc(bytes: &[u8]) -> anyhow::Result<Self> {
+        let (rows, metadata) = arrow_ipc_to_vec_with_metadata(bytes)?;
+        let number = |key: &str| -> anyhow::Result<i64> {
+            Ok(metadata
+                .get(key)
+                .ok_or_else(|| anyhow::anyhow!("Missing {key} in getCells metadata"))?
+                .parse()?)
+        };
+        let (x, y) = (number("x")?, number("y")?);
+        let cells = rows
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row_index, row)| {
+                row.into_iter()
+                    .enumerate()
+                    .map(move |(col_index, value)| JsGetCellResponse {
+                        x: x + col_index as i64,
+                        y: y + row_index as i64,
+                        value: value.to_get_cells(),
+                        type_name: value.type_name().into(),
+                    })
+            })
+            .collect();
+
+        Ok(CellA1Response {
+            cells,
+            x,
+            y,
+            w: number("w")?,
+            h: number("h")?,
+            two_dimensional: metadata
+                .get("two_dimensional")
+                .is_some_and(|two_dimensional| two_dimensional == "true"),
+        })
+    }
+}
+
 impl GridController {
     /// This is used to get cells during an async calculation.
     pub fn calculation_get_cells_a1(
@@ -32,6 +80,88 @@ impl GridController {
         a1: String,
         line_number: Option<u32>,
     ) -> Result<CellA1Response, CoreError> {
+        let (sheet_id, rect, two_dimensional) =
+            self.calculation_get_cells_a1_rect(transaction_id, a1, line_number)?;
+
+        let response = match (self.try_sheet(sheet_id), rect) {
+            (Some(sheet), Some(rect)) => CellA1Response {
+                cells: sheet.get_cells_response(rect),
+                x: rect.min.x,
+                y: rect.min.y,
+                w: rect.width() as i64,
+                h: rect.height() as i64,
+                two_dimensional,
+            },
+            _ => CellA1Response {
+                cells: vec![],
+                x: 1,
+                y: 1,
+                w: 0,
+                h: 0,
+                two_dimensional: false,
+            },
+        };
+
+        Ok(response)
+    }
+
+    /// This is used to get cells during an async calculation as an Arrow IPC
+    /// stream with one typed column per sheet column.  The position and size
+    /// of the cells are in the schema's metadata (`x`, `y`, `w`, `h` and
+    /// `two_dimensional`), matching [`CellA1Response`].
+    pub fn calculation_get_cells_a1_arrow(
+        &mut self,
+        transaction_id: String,
+        a1: String,
+        line_number: Option<u32>,
+    ) -> Result<Vec<u8>, CoreError> {
+        let (sheet_id, rect, two_dimensional) =
+            self.calculation_get_cells_a1_rect(transaction_id, a1, line_number)?;
+
+        let (x, y, w, h) = rect.map_or((1, 1, 0, 0), |rect| {
+            (rect.min.x, rect.min.y, rect.width(), rect.height())
+        });
+        let (headers, columns) = match (self.try_sheet(sheet_id), rect) {
+            (Some(sheet), Some(rect)) => rect
+                .x_range()
+                .map(|x| {
+                    let column = rect
+                        .y_range()
+                        .map(|y| {
+                            sheet
+                                .display_value(Pos { x, y })
+                                .unwrap_or(CellValue::Blank)
+                        })
+                        .collect();
+                    (column_name(x), column)
+                })
+                .unzip(),
+            _ => (vec![], vec![]),
+        };
+        let metadata = HashMap::from([
+            (HEADERS_METADATA_KEY.to_string(), "false".to_string()),
+            ("x".to_string(), x.to_string()),
+            ("y".to_string(), y.to_string()),
+            ("w".to_string(), w.to_string()),
+            ("h".to_string(), h.to_string()),
+            (
+                "two_dimensional".to_string(),
+                (two_dimensional && rect.is_some()).to_string(),
+            ),
+        ]);
+
+        Ok(vec_to_arrow_ipc(headers, columns, metadata)?)
+    }
+
+    /// Finds the cells for a getCells call during an async calculation, and
+    /// adds them to the transaction's cells_accessed.  Returns the sheet, the
+    /// rect of cells (if any), and whether the result is two-dimensional.
+    fn calculation_get_cells_a1_rect(
+        &mut self,
+        transaction_id: String,
+        a1: String,
+        line_number: Option<u32>,
+    ) -> Result<(SheetId, Option<Rect>, bool), CoreError> {
         let transaction_id = Uuid::parse_str(&transaction_id)
             .map_err(|_| CoreError::TransactionNotFound("Transaction Id is invalid".into()))?;
 
@@ -66,7 +196,11 @@ impl GridController {
             }
         };
 
-        let selection = match self.a1_selection_from_string(&a1, &current_sheet_pos.sheet_id) {
+        let selection = match self.a1_selection_from_string(
+            &a1,
+            &current_sheet_pos.sheet_id,
+            Some(current_sheet_pos.into()),
+        ) {
             Ok(selection) => selection,
             Err(e) => {
                 // unable to parse A1 string
@@ -82,6 +216,35 @@ impl GridController {
             }
         };
 
+        if let Some(last_sheet_id) = selection.last_sheet_id {
+            // 3D references return one range per sheet, which only formulas
+            // support
+            let sheet_name = |sheet_id| {
+                self.try_sheet(sheet_id)
+                    .map_or(crate::a1::UNKNOWN_SHEET_NAME, |sheet| sheet.name.as_str())
+            };
+            let msg = format!(
+                "{a1} covers every sheet from {} to {}, but references to multiple sheets are only supported in formulas",
+                sheet_name(selection.sheet_id),
+                sheet_name(last_sheet_id),
+            );
+            let run_error = get_run_error(&msg);
+            let error = match self.code_cell_sheet_error(&mut transaction, &run_error) {
+                Ok(_) => CoreError::A1Error(msg),
+                Err(err) => err,
+            };
+            self.start_transaction(&mut transaction);
+            self.finalize_transaction(transaction);
+            return Err(error);
+        }
+
+        // table references are resolved to the cells they refer to, using the
+        // code cell's row for `@` references
+        let selection = match self.try_sheet(selection.sheet_id) {
+            Some(sheet) => sheet.resolve_selection(&selection, Some(current_sheet_pos.y)),
+            None => selection,
+        };
+
         if selection.sheet_id == current_sheet_pos.sheet_id
             && selection.might_contain_pos(current_sheet_pos.into())
         {
@@ -128,49 +291,35 @@ impl GridController {
         }
 
         selection.ranges.iter().for_each(|range| {
-            transaction.cells_accessed.add(sheet.id, *range);
+            transaction.cells_accessed.add(sheet.id, range.clone());
         });
 
-        let response = if let Some(rect) = rects.first() {
-            // Tracks whether to force the get_cells call to return a 2D array.
-            // The use case is where the rect is currently one-dimensional, but
-            // the selection may change to two-dimensional based on data bounds.
-            // For example, "2:" or "B5:".
-            let two_dimensional = if let Some(range) = selection.ranges.first() {
-                match range {
-                    CellRefRange::Sheet { range } => {
-                        (range.end.col.is_unbounded() && range.end.row.is_unbounded())
-                            || !(range.start.row.coord == range.end.row.coord
-                                || range.start.col.coord == range.end.col.coord)
-                    }
+        // Tracks whether to force the get_cells call to return a 2D array.
+        // The use case is where the rect is currently one-dimensional, but
+        // the selection may change to two-dimensional based on data bounds.
+        // For example, "2:" or "B5:".
+        let two_dimensional = if let Some(range) = selection.ranges.first() {
+            match range {
+                CellRefRange::Sheet { range } => {
+                    (range.end.col.is_unbounded() && range.end.row.is_unbounded())
+                        || !(range.start.row.coord == range.end.row.coord
+                            || range.start.col.coord == range.end.col.coord)
                 }
-            } else {
-                false
-            };
-
-            let cells = sheet.get_cells_response(*rect);
-            CellA1Response {
-                cells,
-                x: rect.min.x,
-                y: rect.min.y,
-                w: rect.width() as i64,
-                h: rect.height() as i64,
-                two_dimensional,
+                // a table reference that couldn't be resolved
+                CellRefRange::Table { .. } => false,
             }
         } else {
-            CellA1Response {
-                cells: vec![],
-                x: 1,
-                y: 1,
-                w: 0,
-                h: 0,
-                two_dimensional: false,
-            }
+            false
         };
+        let sheet_id = sheet.id;
+        let rect = rects.first().copied();
+        if let Some(rect) = rect {
+            transaction.cells_read += rect.count() as u64;
+        }
 
         self.transactions.add_async_transaction(&mut transaction);
 
-        Ok(response)
+        Ok((sheet_id, rect, two_dimensional))
     }
 }
 
@@ -252,6 +401,40 @@ mod test {
         assert!(error.contains("Invalid Sheet Name: bad sheet name"));
     }
 
+    #[test]
+    fn test_calculation_get_cells_3d_reference() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_sheet_with_name("Other".to_string(), None);
+        gc.set_code_cell(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            CodeCellLanguage::Python,
+            "".to_string(),
+            None,
+        );
+        let transaction_id = gc.last_transaction().unwrap().id;
+
+        let result = gc.calculation_get_cells_a1(
+            transaction_id.to_string(),
+            "'Sheet 1:Other'!B5".to_string(),
+            None,
+        );
+        assert!(result.is_err());
+        let error = gc
+            .sheet(sheet_id)
+            .code_run(Pos { x: 1, y: 1 })
+            .unwrap()
+            .clone()
+            .std_err
+            .unwrap();
+        assert!(error.contains("every sheet from Sheet 1 to Other"));
+        assert!(error.contains("only supported in formulas"));
+    }
+
     // This was previously disallowed. It is now allowed to unlock appending results.
     // Leaving in some commented out code in case we want to revert this behavior.
     #[test]
@@ -346,6 +529,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_calculation_get_cells_r1c1() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            "test".to_string(),
+            None,
+        );
+
+        gc.set_code_cell(
+            SheetPos {
+                x: 2,
+                y: 2,
+                sheet_id,
+            },
+            CodeCellLanguage::Python,
+            "".to_string(),
+            None,
+        );
+        let transaction_id = gc.last_transaction().unwrap().id;
+
+        // relative references are resolved from the code cell
+        let result = gc
+            .calculation_get_cells_a1(transaction_id.to_string(), "R[-1]C[-1]".to_string(), None)
+            .unwrap();
+        assert_eq!((result.x, result.y), (1, 1));
+        assert_eq!(result.cells[0].value, "test");
+    }
+
     #[test]
     fn calculation_get_cells_with_no_y1() {
         let mut gc = GridController::test();
@@ -541,4 +759,71 @@ mod test {
             .unwrap();
         assert!(result.two_dimensional);
     }
+
+    #[test]
+    fn test_calculation_get_cells_arrow() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = |x, y| SheetPos { x, y, sheet_id };
+
+        gc.set_cell_value(sheet_pos(1, 1), "1".to_string(), None);
+        gc.set_cell_value(sheet_pos(1, 2), "2".to_string(), None);
+        gc.set_cell_value(sheet_pos(2, 1), "test".to_string(), None);
+        gc.set_code_cell(
+            sheet_pos(3, 1),
+            CodeCellLanguage::Python,
+            "".to_string(),
+            None,
+        );
+        let transaction_id = gc.last_transaction().unwrap().id;
+
+        let ipc = gc
+            .calculation_get_cells_a1_arrow(transaction_id.to_string(), "A1:B2".to_string(), None)
+            .unwrap();
+
+        let reader =
+            arrow_ipc::reader::StreamReader::try_new(std::io::Cursor::new(&ipc), None).unwrap();
+        let metadata = reader.schema().metadata().clone();
+        assert_eq!(metadata["x"], "1");
+        assert_eq!(metadata["y"], "1");
+        assert_eq!(metadata["w"], "2");
+        assert_eq!(metadata["h"], "2");
+        assert_eq!(metadata["two_dimensional"], "true");
+
+        assert_eq!(
+            crate::arrow_ipc::arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![
+                vec![CellValue::Number(1.into()), CellValue::Text("test".into())],
+                vec![CellValue::Number(2.into()), CellValue::Blank],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_a1_response_from_arrow_ipc() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet_pos = |x, y| SheetPos { x, y, sheet_id };
+
+        gc.set_cell_value(sheet_pos(2, 3), "1.5".to_string(), None);
+        gc.set_cell_value(sheet_pos(3, 4), "test".to_string(), None);
+        gc.set_code_cell(
+            sheet_pos(5, 5),
+            CodeCellLanguage::Python,
+            "".to_string(),
+            None,
+        );
+        let transaction_id = gc.last_transaction().unwrap().id;
+
+        let ipc = gc
+            .calculation_get_cells_a1_arrow(transaction_id.to_string(), "B3:C4".to_string(), None)
+            .unwrap();
+        let from_arrow = CellA1Response::from_arrow_ipc(&ipc).unwrap();
+
+        // the json and arrow paths return the same response
+        let from_json = gc
+            .calculation_get_cells_a1(transaction_id.to_string(), "B3:C4".to_string(), None)
+            .unwrap();
+        assert_eq!(from_arrow, from_json);
+    }
 }
@@ -1,5 +1,6 @@
 use chrono::Utc;
 
+use crate::arrow_ipc::arrow_ipc_to_vec;
 use crate::controller::active_transactions::pending_transaction::PendingTransaction;
 use crate::controller::operations::operation::Operation;
 use crate::controller::transaction_types::JsCodeResult;
@@ -52,6 +53,7 @@ impl GridController {
         } else {
             sheet.code_runs.shift_remove(&pos)
         };
+        sheet.update_dependency_index(pos);
 
         if old_code_run == new_code_run {
             return;
@@ -200,6 +202,7 @@ impl GridController {
         };
 
         let result = CodeRunResult::Err(error.clone());
+        let timing = transaction.code_run_timing(&result, None);
 
         let new_code_run = match sheet.code_run(pos) {
             Some(old_code_run) => {
@@ -216,6 +219,8 @@ impl GridController {
 
                     // keep the old cells_accessed to better rerun after an error
                     cells_accessed: old_code_run.cells_accessed.clone(),
+                    stale: false,
+                    timing,
                 }
             }
             None => CodeRun {
@@ -231,6 +236,8 @@ impl GridController {
                 spill_error: false,
                 last_modified: Utc::now(),
                 cells_accessed: transaction.cells_accessed.clone(),
+                stale: false,
+                timing,
             },
         };
         transaction.cells_accessed.clear();
@@ -240,6 +247,105 @@ impl GridController {
         Ok(())
     }
 
+    /// Sets a circular reference error on each code cell in a cycle.  The
+    /// code cells keep their cells_accessed so that the cycle is found again
+    /// the next time one of their dependencies changes.  Code cells that
+    /// already have the error are left alone, which stops the cycle from
+    /// being recomputed.
+    pub(crate) fn set_circular_reference_errors(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        cycle: &[SheetPos],
+    ) {
+        for sheet_pos in cycle {
+            let Some(old_code_run) = self
+                .try_sheet(sheet_pos.sheet_id)
+                .and_then(|sheet| sheet.code_run((*sheet_pos).into()))
+            else {
+                continue;
+            };
+
+            if matches!(
+                &old_code_run.result,
+                CodeRunResult::Err(RunError {
+                    msg: RunErrorMsg::CircularReference,
+                    ..
+                })
+            ) {
+                continue;
+            }
+
+            let path = self
+                .get_circular_reference(*sheet_pos)
+                .unwrap_or_else(|| cycle.to_vec());
+            let new_code_run = CodeRun {
+                result: CodeRunResult::Err(RunError {
+                    span: None,
+                    msg: RunErrorMsg::CircularReference,
+                }),
+                return_type: None,
+                std_out: None,
+                std_err: Some(format!(
+                    "Circular reference: {}",
+                    self.circular_reference_path(&path)
+                )),
+                spill_error: false,
+                last_modified: Utc::now(),
+                ..old_code_run.clone()
+            };
+            self.finalize_code_run(transaction, *sheet_pos, Some(new_code_run), None);
+        }
+    }
+
+    /// Marks the code run at sheet_pos as stale because its inputs changed
+    /// while the calculation mode paused it.  Its dependents are then marked
+    /// stale (or computed) by finalize_code_run.
+    pub(crate) fn set_code_run_stale(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_pos: SheetPos,
+    ) {
+        let Some(code_run) = self
+            .try_sheet(sheet_pos.sheet_id)
+            .and_then(|sheet| sheet.code_run(sheet_pos.into()))
+        else {
+            return;
+        };
+
+        if code_run.stale {
+            return;
+        }
+
+        let new_code_run = CodeRun {
+            stale: true,
+            ..code_run.clone()
+        };
+        self.finalize_code_run(transaction, sheet_pos, Some(new_code_run), None);
+    }
+
+    /// Describes a cycle as a path of A1 references, e.g. `A1 -> B1 -> A1`.
+    /// References to another sheet include the sheet name.
+    fn circular_reference_path(&self, cycle: &[SheetPos]) -> String {
+        let Some(first) = cycle.first() else {
+            return String::new();
+        };
+
+        cycle
+            .iter()
+            .chain(std::iter::once(first))
+            .map(|sheet_pos| {
+                let a1 = Pos::from(*sheet_pos).a1_string();
+                match self.try_sheet(sheet_pos.sheet_id) {
+                    Some(sheet) if sheet_pos.sheet_id != first.sheet_id => {
+                        format!("'{}'!{a1}", sheet.name)
+                    }
+                    _ => a1,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
     // Returns a CodeCellValue from a JsCodeResult.
     pub(super) fn js_code_result_to_code_cell_value(
         &mut self,
@@ -266,10 +372,23 @@ impl GridController {
                 spill_error: false,
                 last_modified: Utc::now(),
                 cells_accessed: transaction.cells_accessed.clone(),
+                stale: false,
+                timing: None,
             };
         };
         let result = if js_code_result.success {
-            let result = if let Some(array_output) = js_code_result.output_array {
+            let result = if let Some(arrow_output) = js_code_result.output_arrow {
+                match arrow_ipc_to_vec(&arrow_output) {
+                    Ok(rows) if rows.first().is_some_and(|row| !row.is_empty()) => {
+                        Value::Array(rows.into())
+                    }
+                    Ok(_) => Value::Single("".into()),
+                    Err(e) => {
+                        dbgjs!(format!("Cannot parse arrow output: {}", e));
+                        Value::Single(CellValue::Blank)
+                    }
+                }
+            } else if let Some(array_output) = js_code_result.output_array {
                 let (array, ops) = Array::from_string_list(start.into(), sheet, array_output);
                 transaction.reverse_operations.extend(ops);
                 if let Some(array) = array {
@@ -310,6 +429,7 @@ impl GridController {
             CodeRunResult::Err(_) => None,
         };
 
+        let timing = transaction.code_run_timing(&result, None);
         let code_run = CodeRun {
             formatted_code_string: None,
             result,
@@ -321,6 +441,8 @@ impl GridController {
             spill_error: false,
             last_modified: Utc::now(),
             cells_accessed: transaction.cells_accessed.clone(),
+            stale: false,
+            timing,
         };
         transaction.cells_accessed.clear();
         code_run
@@ -372,6 +494,8 @@ mod test {
             last_modified: Utc::now(),
             cells_accessed: Default::default(),
             spill_error: false,
+            stale: false,
+            timing: None,
         };
         gc.finalize_code_run(transaction, sheet_pos, Some(new_code_run.clone()), None);
         assert_eq!(transaction.forward_operations.len(), 1);
@@ -401,6 +525,8 @@ mod test {
             last_modified: Utc::now(),
             cells_accessed: Default::default(),
             spill_error: false,
+            stale: false,
+            timing: None,
         };
         gc.finalize_code_run(transaction, sheet_pos, Some(new_code_run.clone()), None);
         assert_eq!(transaction.forward_operations.len(), 1);
@@ -456,6 +582,7 @@ mod test {
             output_array: None,
             output_display_type: None,
             cancel_compute: None,
+            output_arrow: None,
         };
         gc.calculation_complete(result).unwrap();
         expect_js_call_count("jsSendImage", 1, true);
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::Utc;
 use regex::Regex;
 
 use crate::{
@@ -109,6 +110,7 @@ impl GridController {
         // stop the computation cycle until async returns
         transaction.current_sheet_pos = Some(sheet_pos);
         transaction.waiting_for_async = Some(CodeCellLanguage::Connection { kind, id });
+        transaction.async_dispatched = Some(Utc::now());
         self.transactions.add_async_transaction(transaction);
     }
 }
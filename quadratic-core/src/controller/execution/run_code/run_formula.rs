@@ -24,6 +24,9 @@ impl GridController {
                 let errors = output.inner.errors();
 
                 transaction.cells_accessed = ctx.cells_accessed;
+                transaction.cells_read = ctx.cells_read;
+                let result = CodeRunResult::Ok(output.inner);
+                let timing = transaction.code_run_timing(&result, None);
                 let new_code_run = CodeRun {
                     std_out: None,
                     std_err: (!errors.is_empty())
@@ -32,10 +35,12 @@ impl GridController {
                     spill_error: false,
                     last_modified: Utc::now(),
                     cells_accessed: transaction.cells_accessed.clone(),
-                    result: CodeRunResult::Ok(output.inner),
+                    result,
                     return_type: None,
                     line_number: None,
                     output_type: None,
+                    stale: false,
+                    timing,
                 };
                 transaction.cells_accessed.clear();
                 self.finalize_code_run(transaction, sheet_pos, Some(new_code_run), None);
@@ -221,6 +226,79 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_data_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(pos![A1].to_sheet_pos(sheet_id), "1".into(), None);
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 * 2 + 1".into(),
+            None,
+        );
+        gc.set_code_cell(
+            pos![C1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "DATATABLE(B1, A1, {1; 2; 3})".into(),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(CellValue::Number(3.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C2]),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C3]),
+            Some(CellValue::Number(7.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Number(1.into()))
+        );
+
+        // changing the result formula recomputes the table
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 * 10".into(),
+            None,
+        );
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(CellValue::Number(10.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C3]),
+            Some(CellValue::Number(30.into()))
+        );
+
+        // so does changing a cell the result formula reads
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 * D1".into(),
+            None,
+        );
+        gc.set_cell_value(pos![D1].to_sheet_pos(sheet_id), "5".into(), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C3]),
+            Some(CellValue::Number(15.into()))
+        );
+    }
+
     #[test]
     fn test_js_code_result_to_code_cell_value_single() {
         let mut gc = GridController::test();
@@ -258,6 +336,8 @@ mod test {
                 output_type: None,
                 cells_accessed: Default::default(),
                 spill_error: false,
+                stale: false,
+                timing: None,
             },
         );
     }
@@ -322,6 +402,8 @@ mod test {
                 cells_accessed: Default::default(),
                 spill_error: false,
                 last_modified: result.last_modified,
+                stale: false,
+                timing: None,
             }
         );
     }
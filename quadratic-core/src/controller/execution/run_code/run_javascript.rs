@@ -1,3 +1,5 @@
+use chrono::Utc;
+
 use crate::{
     controller::{active_transactions::pending_transaction::PendingTransaction, GridController},
     grid::CodeCellLanguage,
@@ -23,6 +25,7 @@ impl GridController {
         // stop the computation cycle until async returns
         transaction.current_sheet_pos = Some(sheet_pos);
         transaction.waiting_for_async = Some(CodeCellLanguage::Javascript);
+        transaction.async_dispatched = Some(Utc::now());
         self.transactions.add_async_transaction(transaction);
     }
 }
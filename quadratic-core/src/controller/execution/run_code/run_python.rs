@@ -1,3 +1,5 @@
+use chrono::Utc;
+
 use crate::{
     controller::{active_transactions::pending_transaction::PendingTransaction, GridController},
     grid::CodeCellLanguage,
@@ -23,6 +25,7 @@ impl GridController {
         // stop the computation cycle until async returns
         transaction.current_sheet_pos = Some(sheet_pos);
         transaction.waiting_for_async = Some(CodeCellLanguage::Python);
+        transaction.async_dispatched = Some(Utc::now());
         self.transactions.add_async_transaction(transaction);
     }
 }
@@ -189,6 +192,11 @@ mod tests {
             Some(CellValue::Number(BigDecimal::from(10)))
         );
 
+        // the run's timing counts the cells read by get_cells
+        let timing = sheet.code_run(pos![A2]).unwrap().timing.clone().unwrap();
+        assert_eq!(timing.cells_read, 1);
+        assert_eq!((timing.output_w, timing.output_h), (1, 1));
+
         // transaction should be completed
         let async_transaction = gc.transactions.get_async_transaction(transaction_id);
         assert!(async_transaction.is_err());
@@ -588,4 +596,60 @@ mod tests {
         let async_transaction = gc.transactions.get_async_transaction(transaction_id);
         assert!(async_transaction.is_err());
     }
+
+    #[test]
+    #[parallel]
+    fn test_python_arrow_output() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_code_cell(
+            pos![A1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Python,
+            "create a dataframe".into(),
+            None,
+        );
+        let transaction_id = gc.async_transactions()[0].id;
+
+        let output = crate::arrow_ipc::vec_to_arrow_ipc(
+            vec!["number".into(), "text".into()],
+            vec![
+                vec![CellValue::Number(1.into()), CellValue::Number(2.into())],
+                vec![CellValue::Text("a".into()), CellValue::Blank],
+            ],
+            Default::default(),
+        )
+        .unwrap();
+        let mut result = JsCodeResult::new(
+            transaction_id.to_string(),
+            true,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        result.output_arrow = Some(output);
+        assert!(gc.calculation_complete(result).is_ok());
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Text("number".into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![A2]),
+            Some(CellValue::Number(1.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![B2]),
+            Some(CellValue::Text("a".into()))
+        );
+        assert_eq!(
+            sheet.code_run(pos![A1]).unwrap().output_size(),
+            ArraySize::new(2, 3).unwrap()
+        );
+    }
 }
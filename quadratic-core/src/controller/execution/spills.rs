@@ -437,6 +437,8 @@ mod tests {
             last_modified: Utc::now(),
             cells_accessed: Default::default(),
             formatted_code_string: None,
+            stale: false,
+            timing: None,
         };
         let pos = Pos { x: 0, y: 0 };
         let sheet = gc.sheet_mut(sheet_id);
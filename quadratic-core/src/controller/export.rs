@@ -3,7 +3,7 @@ use csv::Writer;
 use itertools::PeekingNext;
 
 use super::GridController;
-use crate::{A1Selection, Pos};
+use crate::{values::parquet::vec_to_parquet, A1Selection, CellValue, Pos};
 
 impl GridController {
     /// exports a CSV string from a selection on the grid.
@@ -37,6 +37,60 @@ impl GridController {
         let output = String::from_utf8(writer.into_inner()?)?;
         Ok(output)
     }
+
+    /// exports a Parquet file from a selection on the grid, using the first
+    /// row as the column names.  Columns are typed from their values so the
+    /// file can be written back to a database.
+    ///
+    /// Returns the Parquet bytes.
+    pub fn export_parquet_selection(&self, selection: &A1Selection) -> Result<Vec<u8>> {
+        let sheet = self
+            .try_sheet(selection.sheet_id)
+            .context("Sheet not found")?;
+        let bounds = sheet.selection_bounds(selection).context("No values")?;
+        let values = sheet.selection_sorted_vec(selection, false);
+        let mut iter = values.iter();
+        let mut rows = vec![];
+        for y in bounds.min.y..=bounds.max.y {
+            let mut row = vec![];
+            for x in bounds.min.x..=bounds.max.x {
+                // we need to ignore unselected columns or rows
+                if selection.might_contain_pos(Pos { x, y }) {
+                    if let Some((_, value)) = iter.peeking_next(|(pos, _)| pos.x == x && pos.y == y)
+                    {
+                        row.push((*value).to_owned());
+                    } else {
+                        row.push(CellValue::Blank);
+                    }
+                }
+            }
+            if !row.is_empty() {
+                rows.push(row);
+            }
+        }
+
+        let mut rows = rows.into_iter();
+        let headers = rows
+            .next()
+            .context("No values")?
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| match value.to_string() {
+                header if header.is_empty() => format!("Column {}", index + 1),
+                header => header,
+            })
+            .collect::<Vec<_>>();
+
+        // transpose rows into columns
+        let mut columns = vec![vec![]; headers.len()];
+        for row in rows {
+            for (index, value) in row.into_iter().enumerate() {
+                columns[index].push(value);
+            }
+        }
+
+        vec_to_parquet(headers, columns)
+    }
 }
 
 #[cfg(test)]
@@ -68,4 +122,29 @@ mod tests {
 
         assert_eq!(&result, expected);
     }
+
+    #[test]
+    fn exports_a_parquet_file() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        let selected = A1Selection::test_a1("A1:B3");
+        let vals = vec![vec!["name", ""], vec!["a", "1"], vec!["b", "2"]];
+
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.set_cell_values(crate::Rect::new(1, 1, 2, 3), &Array::from(vals));
+
+        let result = gc.export_parquet_selection(&selected).unwrap();
+        let values = crate::values::parquet::parquet_to_vec(result).unwrap();
+
+        assert_eq!(
+            values[0],
+            vec![
+                CellValue::Text("name".into()),
+                CellValue::Text("Column 2".into())
+            ]
+        );
+        assert_eq!(values[2][0], CellValue::Text("b".into()));
+        assert_eq!(values.len(), 3);
+    }
 }
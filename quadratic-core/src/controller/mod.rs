@@ -7,6 +7,7 @@ pub mod execution;
 pub mod export;
 pub mod formula;
 pub mod operations;
+pub mod profiling;
 pub mod send_render;
 pub mod sheet_offsets;
 pub mod sheets;
@@ -310,6 +310,10 @@ impl GridController {
 
     /// Creates border operations to clear the selection of any borders.
     pub fn clear_borders_a1_operations(&self, selection: &A1Selection) -> Vec<Operation> {
+        let selection = match self.try_sheet(selection.sheet_id) {
+            Some(sheet) => sheet.resolve_selection(selection, None),
+            None => selection.clone(),
+        };
         let mut borders: BordersUpdates = BordersUpdates::default();
         selection.ranges.iter().for_each(|range| match range {
             CellRefRange::Sheet { range } => {
@@ -343,6 +347,7 @@ impl GridController {
                     Some(ClearOption::Clear),
                 );
             }
+            CellRefRange::Table { .. } => (),
         });
         vec![Operation::SetBordersA1 {
             sheet_id: selection.sheet_id,
@@ -359,6 +364,7 @@ impl GridController {
         clear_neighbors: bool,
     ) -> Option<Vec<Operation>> {
         let sheet = self.try_sheet(selection.sheet_id)?;
+        let selection = sheet.resolve_selection(&selection, None);
 
         // Mutable so we can clear it if the style is toggled.
         let mut style = style;
@@ -374,6 +380,7 @@ impl GridController {
                 CellRefRange::Sheet { range } => {
                     self.a1_border_style_range(border_selection, style, range, &mut borders, false);
                 }
+                CellRefRange::Table { .. } => (),
             });
             if sheet.borders.is_toggle_borders(&borders) {
                 style = None;
@@ -391,6 +398,7 @@ impl GridController {
                     clear_neighbors,
                 );
             }
+            CellRefRange::Table { .. } => (),
         });
 
         if !borders.is_empty() {
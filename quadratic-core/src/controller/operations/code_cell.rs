@@ -3,8 +3,8 @@ use crate::{
     cell_values::CellValues,
     controller::GridController,
     formulas::replace_a1_notation,
-    grid::{CodeCellLanguage, CodeCellValue, CodeRun, SheetId},
-    CellValue, SheetPos,
+    grid::{CalculationMode, CodeCellLanguage, CodeCellValue, CodeRun, SheetId},
+    A1Selection, CellValue, Pos, SheetPos,
 };
 
 impl GridController {
@@ -129,6 +129,59 @@ impl GridController {
     pub fn rerun_code_cell_operations(&self, sheet_pos: SheetPos) -> Vec<Operation> {
         vec![Operation::ComputeCode { sheet_pos }]
     }
+
+    /// Computes the stale code cells that match the filter.
+    pub(crate) fn recalculate_operations(
+        &self,
+        filter: impl Fn(SheetId, Pos) -> bool,
+    ) -> Vec<Operation> {
+        let mut code_cell_positions = self
+            .grid()
+            .sheets()
+            .iter()
+            .flat_map(|sheet| {
+                sheet
+                    .code_runs
+                    .iter()
+                    .filter(|(pos, code_run)| code_run.stale && filter(sheet.id, **pos))
+                    .map(|(pos, code_run)| (pos.to_sheet_pos(sheet.id), code_run))
+            })
+            .collect::<Vec<_>>();
+
+        self.order_code_cells(&mut code_cell_positions);
+
+        code_cell_positions
+            .iter()
+            .map(|(sheet_pos, _)| Operation::ComputeCode {
+                sheet_pos: *sheet_pos,
+            })
+            .collect()
+    }
+
+    /// Computes all stale code cells.
+    pub fn recalculate_all_operations(&self) -> Vec<Operation> {
+        self.recalculate_operations(|_, _| true)
+    }
+
+    /// Computes the stale code cells in a Sheet.
+    pub fn recalculate_sheet_operations(&self, sheet_id: SheetId) -> Vec<Operation> {
+        self.recalculate_operations(|id, _| id == sheet_id)
+    }
+
+    /// Computes the stale code cells in a selection.
+    pub fn recalculate_selection_operations(&self, selection: &A1Selection) -> Vec<Operation> {
+        self.recalculate_operations(|sheet_id, pos| {
+            sheet_id == selection.sheet_id && selection.contains_pos(pos)
+        })
+    }
+
+    /// Sets the calculation mode.
+    pub fn set_calculation_mode_operations(
+        &self,
+        calculation_mode: CalculationMode,
+    ) -> Vec<Operation> {
+        vec![Operation::SetCalculationMode { calculation_mode }]
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,420 @@
+//! Goal seek varies the value of one input cell until a formula cell reaches a
+//! target value.
+//!
+//! The formula is re-evaluated with the input value substituted (see
+//! [`Ctx::set_substitutions`]), so the grid is not modified while searching.
+//! The secant method is tried first, since it converges quickly for smooth
+//! formulas. If it fails, the search looks for values on either side of the
+//! target and bisects between them.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use bigdecimal::ToPrimitive;
+
+use super::operation::Operation;
+use crate::{
+    cell_values::CellValues,
+    controller::GridController,
+    formulas::Ctx,
+    grid::{js_types::JsGoalSeekResult, CodeCellLanguage},
+    util::round,
+    CellValue, RunError, SheetPos,
+};
+
+/// Maximum number of times the formula is evaluated while searching.
+const MAX_ITERATIONS: u32 = 200;
+
+/// Maximum number of secant steps before falling back to bisection.
+const MAX_SECANT_ITERATIONS: u32 = 50;
+
+/// Maximum number of times the search range is doubled when looking for values
+/// on either side of the target.
+const MAX_BRACKET_EXPANSIONS: u32 = 60;
+
+/// Tolerance relative to the magnitude of the target.
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+/// Maximum number of decimal places tried when rounding the solution.
+const MAX_ROUNDING_DECIMALS: i64 = 9;
+
+struct GoalSeek<'a> {
+    ctx: Ctx<'a>,
+    formula_pos: SheetPos,
+    input_pos: SheetPos,
+    target: f64,
+    tolerance: f64,
+    iterations: u32,
+    /// Input value with the smallest error so far, and the formula result for
+    /// it.
+    best: Option<(f64, f64)>,
+    /// Error that stops the search, such as a Python cell between the input
+    /// and the formula.
+    fatal_error: Option<RunError>,
+}
+impl GoalSeek<'_> {
+    /// Returns the formula result with `input` in the input cell.
+    fn result(&mut self, input: f64) -> Option<f64> {
+        let substitutions = HashMap::from([(self.input_pos, CellValue::from(input))]);
+        self.ctx.set_substitutions(substitutions);
+        match self.ctx.eval_formula_cell(self.formula_pos) {
+            Ok(CellValue::Number(n)) => n.to_f64().filter(|n| n.is_finite()),
+            Ok(_) => None,
+            Err(e) => {
+                self.fatal_error.get_or_insert(e);
+                None
+            }
+        }
+    }
+
+    /// Returns whether the search must stop.
+    fn is_stopped(&self) -> bool {
+        self.iterations >= MAX_ITERATIONS || self.fatal_error.is_some()
+    }
+
+    /// Returns the difference between the formula result and the target with
+    /// `input` in the input cell.
+    fn error(&mut self, input: f64) -> Option<f64> {
+        if !input.is_finite() || self.is_stopped() {
+            return None;
+        }
+        self.iterations += 1;
+        let result = self.result(input)?;
+        let error = result - self.target;
+        if !matches!(self.best, Some((_, best)) if (best - self.target).abs() <= error.abs()) {
+            self.best = Some((input, result));
+        }
+        Some(error)
+    }
+
+    fn is_solution(&self, error: f64) -> bool {
+        error.abs() <= self.tolerance
+    }
+
+    /// Searches from `x0` using the secant method.
+    fn secant(&mut self, mut x0: f64) -> Option<f64> {
+        let mut x1 = if x0 == 0.0 { 0.01 } else { x0 * 1.01 };
+        let mut f0 = self.error(x0)?;
+        if self.is_solution(f0) {
+            return Some(x0);
+        }
+        for _ in 0..MAX_SECANT_ITERATIONS {
+            let f1 = self.error(x1)?;
+            if self.is_solution(f1) {
+                return Some(x1);
+            }
+            if f1 == f0 {
+                return None;
+            }
+            let x2 = x1 - f1 * (x1 - x0) / (f1 - f0);
+            (x0, f0, x1) = (x1, f1, x2);
+        }
+        None
+    }
+
+    /// Searches for values on either side of the target around `x0`, then
+    /// bisects between them.
+    fn bisection(&mut self, x0: f64) -> Option<f64> {
+        let f0 = self.error(x0)?;
+        let mut step = x0.abs().max(1.0) * 0.1;
+        let (mut lo, mut hi, mut f_lo) = 'bracket: {
+            for _ in 0..MAX_BRACKET_EXPANSIONS {
+                for x in [x0 - step, x0 + step] {
+                    // Errors are allowed here, since the formula may not be
+                    // defined everywhere (such as `SQRT` of negative numbers).
+                    let Some(f) = self.error(x) else {
+                        if self.is_stopped() {
+                            return None;
+                        }
+                        continue;
+                    };
+                    if self.is_solution(f) {
+                        return Some(x);
+                    }
+                    if f.signum() != f0.signum() {
+                        break 'bracket (x0.min(x), x0.max(x), if x < x0 { f } else { f0 });
+                    }
+                }
+                step *= 2.0;
+            }
+            return None;
+        };
+
+        while hi - lo > f64::EPSILON * lo.abs().max(hi.abs()) {
+            let mid = lo + (hi - lo) / 2.0;
+            let f_mid = self.error(mid)?;
+            if self.is_solution(f_mid) {
+                return Some(mid);
+            }
+            if f_mid.signum() == f_lo.signum() {
+                (lo, f_lo) = (mid, f_mid);
+            } else {
+                hi = mid;
+            }
+        }
+        None
+    }
+
+    /// Returns the solution rounded to as few decimal places as possible while
+    /// still reaching the target.
+    fn round_solution(&mut self, input: f64) -> f64 {
+        for decimals in 0..=MAX_ROUNDING_DECIMALS {
+            let rounded = round(input, decimals);
+            if self
+                .result(rounded)
+                .is_some_and(|result| self.is_solution(result - self.target))
+            {
+                return rounded;
+            }
+        }
+        input
+    }
+}
+
+impl GridController {
+    /// Searches for a value of the cell at `input_pos` that makes the formula
+    /// at `formula_pos` evaluate to `target`. The grid is not modified; if a
+    /// solution is found, the returned operations set the input cell to it.
+    pub fn goal_seek_operations(
+        &self,
+        formula_pos: SheetPos,
+        target: f64,
+        input_pos: SheetPos,
+    ) -> Result<(JsGoalSeekResult, Vec<Operation>)> {
+        if !target.is_finite() {
+            bail!("Target value must be a number");
+        }
+        if formula_pos == input_pos {
+            bail!("Input cell must be different from the formula cell");
+        }
+        let Some(sheet) = self.try_sheet(formula_pos.sheet_id) else {
+            bail!("Sheet not found");
+        };
+        match sheet.cell_value_ref(formula_pos.into()) {
+            Some(CellValue::Code(code)) if code.language == CodeCellLanguage::Formula => (),
+            _ => bail!("Goal seek requires a formula cell"),
+        }
+        let Some(sheet) = self.try_sheet(input_pos.sheet_id) else {
+            bail!("Sheet not found");
+        };
+        let start = match sheet.cell_value_ref(input_pos.into()) {
+            None | Some(CellValue::Blank) => 0.0,
+            Some(CellValue::Number(n)) => n.to_f64().unwrap_or_default(),
+            _ => bail!("Input cell must be empty or contain a number"),
+        };
+
+        let mut goal_seek = GoalSeek {
+            ctx: Ctx::new(self.grid(), formula_pos),
+            formula_pos,
+            input_pos,
+            target,
+            tolerance: RELATIVE_TOLERANCE * target.abs().max(1.0),
+            iterations: 0,
+            best: None,
+            fatal_error: None,
+        };
+        let solution = goal_seek
+            .secant(start)
+            .or_else(|| goal_seek.bisection(start))
+            .map(|input| goal_seek.round_solution(input));
+        if let Some(error) = &goal_seek.fatal_error {
+            bail!("{}", error.msg);
+        }
+
+        let result = match solution {
+            Some(input) => JsGoalSeekResult {
+                found: true,
+                input_value: input,
+                result_value: goal_seek.result(input),
+                iterations: goal_seek.iterations,
+            },
+            None => JsGoalSeekResult {
+                found: false,
+                input_value: goal_seek.best.map_or(start, |(input, _)| input),
+                result_value: goal_seek.best.map(|(_, result)| result),
+                iterations: goal_seek.iterations,
+            },
+        };
+
+        let ops = solution
+            .map(|input| Operation::SetCellValues {
+                sheet_pos: input_pos,
+                values: CellValues::from(CellValue::from(input)),
+            })
+            .into_iter()
+            .collect();
+        Ok((result, ops))
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        grid::{CellsAccessed, CodeCellValue, CodeRun, CodeRunResult},
+        Value,
+    };
+
+    #[test]
+    fn test_goal_seek_operations() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let input_pos = pos![A1].to_sheet_pos(sheet_id);
+        let formula_pos = pos![B1].to_sheet_pos(sheet_id);
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "A1 * A1".into(),
+            None,
+        );
+
+        let (result, ops) = gc
+            .goal_seek_operations(formula_pos, 2.0, input_pos)
+            .unwrap();
+        assert!(result.found);
+        crate::util::assert_f64_approx_eq(
+            std::f64::consts::SQRT_2,
+            result.input_value,
+            "wrong goal seek input",
+        );
+        assert_eq!(ops.len(), 1);
+
+        // the grid is not modified
+        assert_eq!(gc.sheet(sheet_id).cell_value(pos![A1]), None);
+
+        // no input value reaches a negative square
+        let (result, ops) = gc
+            .goal_seek_operations(formula_pos, -1.0, input_pos)
+            .unwrap();
+        assert!(!result.found);
+        assert!(ops.is_empty());
+
+        assert!(gc
+            .goal_seek_operations(formula_pos, 1.0, formula_pos)
+            .is_err());
+        assert!(gc
+            .goal_seek_operations(input_pos, 1.0, formula_pos)
+            .is_err());
+        assert!(gc
+            .goal_seek_operations(formula_pos, f64::NAN, input_pos)
+            .is_err());
+    }
+
+    #[test]
+    fn test_goal_seek_formula_chain() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let input_pos = pos![A1].to_sheet_pos(sheet_id);
+
+        // B1 = A1 + 1, B2 = B1 + 1, ..., B200 = B199 + 1, which is deeper than
+        // formulas may be evaluated inside each other
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 + 1".into(),
+            None,
+        );
+        for y in 2..=200 {
+            gc.set_code_cell(
+                SheetPos { x: 2, y, sheet_id },
+                CodeCellLanguage::Formula,
+                format!("B{} + 1", y - 1),
+                None,
+            );
+        }
+        let formula_pos = SheetPos {
+            x: 2,
+            y: 200,
+            sheet_id,
+        };
+
+        let (result, _) = gc
+            .goal_seek_operations(formula_pos, 250.0, input_pos)
+            .unwrap();
+        assert!(result.found);
+        assert_eq!(result.input_value, 50.0);
+    }
+
+    #[test]
+    fn test_goal_seek_stale_code_cells() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let input_pos = pos![A1].to_sheet_pos(sheet_id);
+
+        // a Python cell reads the input, and a formula reads the Python cell
+        let mut cells_accessed = CellsAccessed::default();
+        cells_accessed.add_sheet_pos(input_pos);
+        let sheet = gc.sheet_mut(sheet_id);
+        let _ = sheet.set_cell_value(
+            pos![C1],
+            CellValue::Code(CodeCellValue {
+                language: CodeCellLanguage::Python,
+                code: "q.cells('A1') * 2".into(),
+            }),
+        );
+        sheet.set_code_run(
+            pos![C1],
+            Some(CodeRun {
+                formatted_code_string: None,
+                std_out: None,
+                std_err: None,
+                cells_accessed,
+                result: CodeRunResult::Ok(Value::Single(CellValue::Number(0.into()))),
+                return_type: Some("number".into()),
+                spill_error: false,
+                line_number: None,
+                output_type: None,
+                last_modified: Utc::now(),
+                stale: false,
+                timing: None,
+            }),
+        );
+        let formula_pos = pos![B1].to_sheet_pos(sheet_id);
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "C1 + 1".into(),
+            None,
+        );
+        let error = gc
+            .goal_seek_operations(formula_pos, 5.0, input_pos)
+            .unwrap_err();
+        assert!(error.to_string().contains("Python"));
+
+        // a formula reads a cell from the output of a formula that reads the
+        // input, other than its first cell
+        gc.set_code_cell(
+            pos![D1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "{1; 2; 3} * A1".into(),
+            None,
+        );
+        let formula_pos = pos![E1].to_sheet_pos(sheet_id);
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "D2 + 1".into(),
+            None,
+        );
+        let error = gc
+            .goal_seek_operations(formula_pos, 5.0, input_pos)
+            .unwrap_err();
+        assert!(error.to_string().contains("first cell"));
+
+        // reading the first cell is fine
+        gc.set_code_cell(
+            formula_pos,
+            CodeCellLanguage::Formula,
+            "D1 + 1".into(),
+            None,
+        );
+        let (result, _) = gc
+            .goal_seek_operations(formula_pos, 5.0, input_pos)
+            .unwrap();
+        assert!(result.found);
+        assert_eq!(result.input_value, 4.0);
+    }
+}
@@ -9,6 +9,8 @@ pub mod clipboard;
 pub mod code_cell;
 pub mod formats;
 pub mod formatting;
+pub mod goal_seek;
 pub mod import;
 pub mod operation;
 pub mod sheets;
+pub mod tables;
@@ -14,9 +14,10 @@ use crate::{
                 borders_old::{BorderStyleCellUpdates, SheetBorders},
                 BordersUpdates,
             },
+            tables::Table,
             validations::validation::Validation,
         },
-        CodeRun, CodeRunOld, Sheet, SheetId,
+        CalculationMode, CodeRun, CodeRunOld, Sheet, SheetId,
     },
     selection::OldSelection,
     A1Selection, CopyFormats, SheetPos, SheetRect,
@@ -180,6 +181,15 @@ pub enum Operation {
         row: i64,
         copy_formats: CopyFormats,
     },
+
+    /// Sets whether dependent code cells are computed when their inputs
+    /// change.
+    SetCalculationMode { calculation_mode: CalculationMode },
+
+    /// Creates or updates a table.
+    SetTable { sheet_id: SheetId, table: Table },
+    /// Deletes a table (but not its cells).
+    RemoveTable { sheet_id: SheetId, table_id: Uuid },
 }
 
 // TODO: either remove this or add a comment explaining why it's better than the
@@ -365,6 +375,21 @@ impl fmt::Display for Operation {
                     "InsertRow {{ sheet_id: {sheet_id}, row: {row}, copy_formats: {copy_formats:?} }}"
                 )
             }
+            Operation::SetCalculationMode { calculation_mode } => {
+                write!(
+                    fmt,
+                    "SetCalculationMode {{ calculation_mode: {calculation_mode:?} }}"
+                )
+            }
+            Operation::SetTable { sheet_id, table } => {
+                write!(fmt, "SetTable {{ sheet_id: {sheet_id}, table: {table:?} }}")
+            }
+            Operation::RemoveTable { sheet_id, table_id } => {
+                write!(
+                    fmt,
+                    "RemoveTable {{ sheet_id: {sheet_id}, table_id: {table_id} }}"
+                )
+            }
         }
     }
 }
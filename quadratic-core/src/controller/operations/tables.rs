@@ -0,0 +1,177 @@
+use anyhow::{bail, Result};
+use uuid::Uuid;
+
+use crate::{
+    controller::GridController,
+    grid::{sheet::tables::Table, SheetId},
+    is_valid_table_column_name, is_valid_table_name, CellValue, Pos, SheetPos, SheetRect,
+};
+
+use super::operation::Operation;
+
+impl GridController {
+    /// Returns the first `TableN` name that is not used in the file.
+    fn default_table_name(&self) -> String {
+        let names = self.grid.table_names();
+        (1..)
+            .map(|n| format!("Table{n}"))
+            .find(|name| !names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+            .unwrap_or_default()
+    }
+
+    /// Checks that a table name is valid and not used by another table.
+    fn validate_table_name(&self, name: &str, table_id: Option<Uuid>) -> Result<()> {
+        if !is_valid_table_name(name) {
+            bail!("Invalid table name: {name}");
+        }
+        if let Some((_, table)) = self.grid.try_table_from_name(name) {
+            if Some(table.id) != table_id {
+                bail!("Table name already exists: {name}");
+            }
+        }
+        Ok(())
+    }
+
+    fn try_table(&self, sheet_id: SheetId, table_id: Uuid) -> Result<&Table> {
+        let Some(sheet) = self.try_sheet(sheet_id) else {
+            bail!("Sheet not found");
+        };
+        let Some(table) = sheet.tables.table(table_id) else {
+            bail!("Table not found");
+        };
+        Ok(table)
+    }
+
+    /// Creates a table over `sheet_rect`. If `show_header` is true, the column
+    /// names are taken from the first row of the range.
+    pub fn add_table_operations(
+        &self,
+        sheet_rect: SheetRect,
+        name: Option<String>,
+        show_header: bool,
+    ) -> Result<Vec<Operation>> {
+        let Some(sheet) = self.try_sheet(sheet_rect.sheet_id) else {
+            bail!("Sheet not found");
+        };
+        let rect = sheet_rect.into();
+        if sheet.tables.tables_in_rect(rect).next().is_some() {
+            bail!("Tables cannot overlap");
+        }
+        let name = match name {
+            Some(name) => {
+                let name = name.trim().to_string();
+                self.validate_table_name(&name, None)?;
+                name
+            }
+            None => self.default_table_name(),
+        };
+        let column_names = if show_header {
+            sheet_rect
+                .x_range()
+                .map(|x| {
+                    sheet
+                        .display_value(Pos {
+                            x,
+                            y: sheet_rect.min.y,
+                        })
+                        .map(|value| value.to_display())
+                        .unwrap_or_default()
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let table = Table::new(&name, rect, show_header, column_names);
+        Ok(vec![Operation::SetTable {
+            sheet_id: sheet_rect.sheet_id,
+            table,
+        }])
+    }
+
+    pub fn remove_table_operations(&self, sheet_id: SheetId, table_id: Uuid) -> Vec<Operation> {
+        vec![Operation::RemoveTable { sheet_id, table_id }]
+    }
+
+    pub fn rename_table_operations(
+        &self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        name: String,
+    ) -> Result<Vec<Operation>> {
+        let name = name.trim().to_string();
+        self.validate_table_name(&name, Some(table_id))?;
+        let mut table = self.try_table(sheet_id, table_id)?.clone();
+        table.name = name;
+        Ok(vec![Operation::SetTable { sheet_id, table }])
+    }
+
+    /// Renames a column of a table. If the table's header is shown, the header
+    /// cell is also updated.
+    pub fn rename_table_column_operations(
+        &self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        column: usize,
+        name: String,
+    ) -> Result<Vec<Operation>> {
+        let name = name.trim().to_string();
+        if !is_valid_table_column_name(&name) {
+            bail!("Invalid column name: {name}");
+        }
+        let mut table = self.try_table(sheet_id, table_id)?.clone();
+        if column >= table.columns.len() {
+            bail!("Column not found");
+        }
+        if table
+            .column_index(&name)
+            .is_some_and(|index| index != column)
+        {
+            bail!("Column name already exists: {name}");
+        }
+        table.columns[column].name.clone_from(&name);
+
+        let header_pos = table
+            .header_row()
+            .map(|y| SheetPos::new(sheet_id, table.bounds.min.x + column as i64, y));
+        let mut ops = vec![Operation::SetTable { sheet_id, table }];
+        if let Some(sheet_pos) = header_pos {
+            ops.push(Operation::SetCellValues {
+                sheet_pos,
+                values: CellValue::Text(name).into(),
+            });
+        }
+        Ok(ops)
+    }
+
+    /// Shows or hides the totals row of a table. The totals row is added below
+    /// the table's data, or removed from the end of the table.
+    pub fn set_table_totals_operations(
+        &self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        show_totals: bool,
+    ) -> Result<Vec<Operation>> {
+        let mut table = self.try_table(sheet_id, table_id)?.clone();
+        if table.show_totals == show_totals {
+            return Ok(vec![]);
+        }
+        if show_totals {
+            table.bounds.max.y += 1;
+            let sheet = self.sheet(sheet_id);
+            if sheet
+                .tables
+                .tables_in_rect(table.bounds)
+                .any(|other| other.id != table_id)
+            {
+                bail!("Tables cannot overlap");
+            }
+        } else {
+            if table.data_rows().is_none() {
+                bail!("Table must have a data row");
+            }
+            table.bounds.max.y -= 1;
+        }
+        table.show_totals = show_totals;
+        Ok(vec![Operation::SetTable { sheet_id, table }])
+    }
+}
@@ -0,0 +1,136 @@
+use crate::grid::js_types::JsCodeRunTiming;
+use crate::CellValue;
+
+use super::GridController;
+
+impl GridController {
+    /// Returns the code cells in all sheets whose last run took the longest,
+    /// slowest first.  Code runs without timing (for example, runs saved
+    /// before timing was recorded) are skipped.
+    pub fn slowest_code_cells(&self, limit: usize) -> Vec<JsCodeRunTiming> {
+        let mut timings = self
+            .grid
+            .sheets()
+            .iter()
+            .flat_map(|sheet| {
+                sheet.code_runs.iter().filter_map(move |(pos, code_run)| {
+                    let timing = code_run.timing.clone()?;
+                    let Some(CellValue::Code(code_cell)) = sheet.cell_value_ref(*pos) else {
+                        return None;
+                    };
+                    Some(JsCodeRunTiming {
+                        sheet_id: sheet.id.to_string(),
+                        x: pos.x,
+                        y: pos.y,
+                        language: code_cell.language.clone(),
+                        timing,
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // stable sort, so ties stay in sheet order
+        timings.sort_by(|a, b| b.timing.duration_ms.cmp(&a.timing.duration_ms));
+        timings.truncate(limit);
+        timings
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::grid::{CodeCellLanguage, CodeRunTiming};
+    use crate::{Pos, SheetPos};
+
+    #[test]
+    fn test_formula_timing() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_values(
+            SheetPos::new(sheet_id, 1, 1),
+            vec![vec!["1", "2"], vec!["3", "4"]],
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 4, 1),
+            CodeCellLanguage::Formula,
+            "SUM(A1:B2)".into(),
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 5, 1),
+            CodeCellLanguage::Formula,
+            "A1:A2".into(),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        let timing = sheet.code_run(Pos { x: 4, y: 1 }).unwrap().timing.clone();
+        let timing = timing.unwrap();
+        assert_eq!(timing.cells_read, 4);
+        assert_eq!((timing.output_w, timing.output_h), (1, 1));
+        assert_eq!(timing.connection, None);
+
+        let timing = sheet.code_run(Pos { x: 5, y: 1 }).unwrap().timing.clone();
+        let timing = timing.unwrap();
+        assert_eq!(timing.cells_read, 2);
+        assert_eq!((timing.output_w, timing.output_h), (1, 2));
+    }
+
+    #[test]
+    fn test_slowest_code_cells() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_id_2 = gc.sheet_ids()[1];
+
+        let code_cells = [
+            (SheetPos::new(sheet_id, 1, 1), 10),
+            (SheetPos::new(sheet_id, 2, 1), 500),
+            (SheetPos::new(sheet_id_2, 1, 1), 200),
+            (SheetPos::new(sheet_id_2, 2, 1), 10),
+        ];
+        for (sheet_pos, duration_ms) in code_cells {
+            gc.set_code_cell(sheet_pos, CodeCellLanguage::Formula, "1".into(), None);
+            gc.sheet_mut(sheet_pos.sheet_id)
+                .code_runs
+                .get_mut(&Pos::from(sheet_pos))
+                .unwrap()
+                .timing = Some(CodeRunTiming {
+                duration_ms,
+                ..Default::default()
+            });
+        }
+
+        // a code run without timing is skipped
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 3, 1),
+            CodeCellLanguage::Formula,
+            "1".into(),
+            None,
+        );
+        gc.sheet_mut(sheet_id)
+            .code_runs
+            .get_mut(&Pos { x: 3, y: 1 })
+            .unwrap()
+            .timing = None;
+
+        let slowest = gc.slowest_code_cells(10);
+        assert_eq!(
+            slowest
+                .iter()
+                .map(|timing| (timing.sheet_id.clone(), timing.x, timing.timing.duration_ms))
+                .collect::<Vec<_>>(),
+            vec![
+                (sheet_id.to_string(), 2, 500),
+                (sheet_id_2.to_string(), 1, 200),
+                (sheet_id.to_string(), 1, 10),
+                (sheet_id_2.to_string(), 2, 10),
+            ]
+        );
+        assert_eq!(slowest[0].language, CodeCellLanguage::Formula);
+
+        assert_eq!(gc.slowest_code_cells(2).len(), 2);
+    }
+}
@@ -465,6 +465,7 @@ mod test {
             line_number: None,
             output_display_type: None,
             cancel_compute: None,
+            output_arrow: None,
         });
 
         expect_js_call(
@@ -505,6 +506,7 @@ mod test {
             line_number: None,
             output_display_type: None,
             cancel_compute: None,
+            output_arrow: None,
         })
         .unwrap();
 
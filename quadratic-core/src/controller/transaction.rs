@@ -8,8 +8,8 @@ use super::execution::TransactionSource;
 use super::operations::operation::Operation;
 use super::GridController;
 use crate::compression::{
-    add_header, decompress_and_deserialize, deserialize, remove_header, serialize,
-    serialize_and_compress, CompressionFormat, SerializationFormat,
+    add_header, decompress_and_deserialize, decompress_with_limit, deserialize, remove_header,
+    serialize, serialize_and_compress, CompressionFormat, SerializationFormat,
 };
 
 pub static SERIALIZATION_FORMAT: SerializationFormat = SerializationFormat::Json;
@@ -74,6 +74,24 @@ impl Transaction {
 
         decompress_and_deserialize::<T>(&SERIALIZATION_FORMAT, &COMPRESSION_FORMAT, data)
     }
+
+    /// Like [`Self::decompress_and_deserialize`], but returns None if the
+    /// operations decompress to more than `max_bytes`.
+    pub fn decompress_and_deserialize_with_limit<T: DeserializeOwned>(
+        operations: &[u8],
+        max_bytes: usize,
+    ) -> Result<Option<T>> {
+        let (header, data) = remove_header(operations)?;
+        let _version = deserialize::<TransactionVersion>(&HEADER_SERIALIZATION_FORMAT, header)?;
+
+        match decompress_with_limit(&COMPRESSION_FORMAT, data, max_bytes)? {
+            Some(decompressed) => Ok(Some(deserialize::<T>(
+                &SERIALIZATION_FORMAT,
+                &decompressed,
+            )?)),
+            None => Ok(None),
+        }
+    }
 }
 
 // Transaction received from Server
@@ -13,6 +13,11 @@ pub struct JsCodeResult {
     pub output_array: Option<Vec<Vec<Vec<String>>>>,
     pub output_display_type: Option<String>,
     pub cancel_compute: Option<bool>,
+
+    /// Output as an Arrow IPC stream, used instead of output_array.  This is
+    /// passed separately from the JSON result (see calculationCompleteArrow).
+    #[serde(skip)]
+    pub output_arrow: Option<Vec<u8>>,
 }
 
 impl JsCodeResult {
@@ -39,6 +44,7 @@ impl JsCodeResult {
             line_number,
             output_display_type,
             cancel_compute,
+            output_arrow: None,
         }
     }
 }
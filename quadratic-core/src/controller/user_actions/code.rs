@@ -1,7 +1,7 @@
 use crate::{
     controller::{active_transactions::transaction_name::TransactionName, GridController},
-    grid::{CodeCellLanguage, SheetId},
-    SheetPos,
+    grid::{CalculationMode, CodeCellLanguage, SheetId},
+    A1Selection, SheetPos,
 };
 
 impl GridController {
@@ -34,6 +34,35 @@ impl GridController {
         let ops = self.rerun_code_cell_operations(sheet_pos);
         self.start_user_transaction(ops, cursor, TransactionName::RunCode);
     }
+
+    /// Recalculates all stale code cells.
+    pub fn recalculate_all(&mut self, cursor: Option<String>) {
+        let ops = self.recalculate_all_operations();
+        self.start_user_transaction(ops, cursor, TransactionName::RunCode);
+    }
+
+    /// Recalculates the stale code cells in a sheet.
+    pub fn recalculate_sheet(&mut self, sheet_id: SheetId, cursor: Option<String>) {
+        let ops = self.recalculate_sheet_operations(sheet_id);
+        self.start_user_transaction(ops, cursor, TransactionName::RunCode);
+    }
+
+    /// Recalculates the stale code cells in a selection.
+    pub fn recalculate_selection(&mut self, selection: &A1Selection, cursor: Option<String>) {
+        let ops = self.recalculate_selection_operations(selection);
+        self.start_user_transaction(ops, cursor, TransactionName::RunCode);
+    }
+
+    /// Sets whether dependent code cells are computed when their inputs
+    /// change.
+    pub fn set_calculation_mode(
+        &mut self,
+        calculation_mode: CalculationMode,
+        cursor: Option<String>,
+    ) {
+        let ops = self.set_calculation_mode_operations(calculation_mode);
+        self.start_user_transaction(ops, cursor, TransactionName::SetCalculationMode);
+    }
 }
 
 #[cfg(test)]
@@ -78,4 +107,117 @@ mod tests {
         assert!(matches!(get_cell(pos![C1]), crate::CellValue::Text(_)));
         assert!(matches!(get_cell(pos![C2]), crate::CellValue::Blank));
     }
+
+    fn is_stale(gc: &GridController, sheet_id: SheetId, pos: crate::Pos) -> bool {
+        gc.sheet(sheet_id)
+            .code_run(pos)
+            .is_some_and(|code_run| code_run.stale)
+    }
+
+    fn manual_grid() -> (GridController, SheetId) {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(pos![A1].to_sheet_pos(sheet_id), "1".to_string(), None);
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 + 1".to_string(),
+            None,
+        );
+        gc.set_code_cell(
+            pos![C1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "B1 * 2".to_string(),
+            None,
+        );
+        gc.set_calculation_mode(CalculationMode::Manual, None);
+        gc.set_cell_value(pos![A1].to_sheet_pos(sheet_id), "5".to_string(), None);
+        (gc, sheet_id)
+    }
+
+    #[test]
+    fn test_manual_calculation_mode() {
+        let (mut gc, sheet_id) = manual_grid();
+        assert_eq!(gc.grid().calculation_mode(), CalculationMode::Manual);
+
+        // dependents keep their values and are marked stale
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![B1]),
+            Some(crate::CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(crate::CellValue::Number(4.into()))
+        );
+        assert!(is_stale(&gc, sheet_id, pos![B1]));
+        assert!(is_stale(&gc, sheet_id, pos![C1]));
+        assert!(sheet.get_render_code_cell(pos![B1]).unwrap().stale);
+
+        gc.recalculate_sheet(sheet_id, None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![B1]),
+            Some(crate::CellValue::Number(6.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(crate::CellValue::Number(12.into()))
+        );
+        assert!(!is_stale(&gc, sheet_id, pos![B1]));
+        assert!(!is_stale(&gc, sheet_id, pos![C1]));
+    }
+
+    #[test]
+    fn test_recalculate_selection() {
+        let (mut gc, sheet_id) = manual_grid();
+
+        gc.recalculate_selection(&A1Selection::test_a1("B1"), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![B1]),
+            Some(crate::CellValue::Number(6.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(crate::CellValue::Number(4.into()))
+        );
+        assert!(!is_stale(&gc, sheet_id, pos![B1]));
+        assert!(is_stale(&gc, sheet_id, pos![C1]));
+
+        gc.recalculate_all(None);
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(pos![C1]),
+            Some(crate::CellValue::Number(12.into()))
+        );
+        assert!(!is_stale(&gc, sheet_id, pos![C1]));
+    }
+
+    #[test]
+    fn test_set_calculation_mode_computes_stale_code_cells() {
+        let (mut gc, sheet_id) = manual_grid();
+
+        gc.set_calculation_mode(CalculationMode::Automatic, None);
+        assert_eq!(
+            gc.sheet(sheet_id).display_value(pos![C1]),
+            Some(crate::CellValue::Number(12.into()))
+        );
+        assert!(!is_stale(&gc, sheet_id, pos![B1]));
+
+        gc.undo(None);
+        assert_eq!(gc.grid().calculation_mode(), CalculationMode::Manual);
+    }
+
+    #[test]
+    fn test_calculation_mode_saved_in_file() {
+        let (gc, sheet_id) = manual_grid();
+
+        let file = crate::grid::file::export(gc.grid().clone()).unwrap();
+        let grid = crate::grid::file::import(file).unwrap();
+        assert_eq!(grid.calculation_mode(), CalculationMode::Manual);
+        assert!(grid
+            .try_sheet(sheet_id)
+            .and_then(|sheet| sheet.code_run(pos![B1]))
+            .is_some_and(|code_run| code_run.stale));
+    }
 }
@@ -0,0 +1,122 @@
+use anyhow::Result;
+
+use crate::{
+    controller::{active_transactions::transaction_name::TransactionName, GridController},
+    grid::js_types::JsGoalSeekResult,
+    SheetPos,
+};
+
+impl GridController {
+    /// Varies the cell at `input_pos` until the formula at `formula_pos`
+    /// evaluates to `target`, then sets the input cell to the solution. The
+    /// input cell is unchanged if no solution is found.
+    pub fn goal_seek(
+        &mut self,
+        formula_pos: SheetPos,
+        target: f64,
+        input_pos: SheetPos,
+        cursor: Option<String>,
+    ) -> Result<JsGoalSeekResult> {
+        let (result, ops) = self.goal_seek_operations(formula_pos, target, input_pos)?;
+        if !ops.is_empty() {
+            self.start_user_transaction(ops, cursor, TransactionName::SetCells);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::{grid::CodeCellLanguage, CellValue};
+
+    #[test]
+    fn test_goal_seek() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let input_pos = pos![A1].to_sheet_pos(sheet_id);
+        gc.set_cell_value(input_pos, "1".into(), None);
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 * 2 + 1".into(),
+            None,
+        );
+        gc.set_code_cell(
+            pos![C1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "B1 * 10".into(),
+            None,
+        );
+
+        let result = gc
+            .goal_seek(pos![B1].to_sheet_pos(sheet_id), 11.0, input_pos, None)
+            .unwrap();
+        assert!(result.found);
+        assert_eq!(result.input_value, 5.0);
+        assert_eq!(result.result_value, Some(11.0));
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![B1]),
+            Some(CellValue::Number(11.into()))
+        );
+
+        // the formula may depend on the input through other formulas
+        let result = gc
+            .goal_seek(pos![C1].to_sheet_pos(sheet_id), 210.0, input_pos, None)
+            .unwrap();
+        assert!(result.found);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Number(10.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(CellValue::Number(210.into()))
+        );
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(pos![C1]),
+            Some(CellValue::Number(110.into()))
+        );
+
+        // the input cell is unchanged if there is no solution
+        gc.set_code_cell(
+            pos![B1].to_sheet_pos(sheet_id),
+            CodeCellLanguage::Formula,
+            "A1 * A1".into(),
+            None,
+        );
+        let result = gc
+            .goal_seek(pos![B1].to_sheet_pos(sheet_id), -1.0, input_pos, None)
+            .unwrap();
+        assert!(!result.found);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(pos![A1]),
+            Some(CellValue::Number(5.into()))
+        );
+
+        // the input cell must not contain code
+        assert!(gc
+            .goal_seek(
+                pos![C1].to_sheet_pos(sheet_id),
+                1.0,
+                pos![B1].to_sheet_pos(sheet_id),
+                None,
+            )
+            .is_err());
+    }
+}
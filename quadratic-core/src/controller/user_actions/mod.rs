@@ -7,7 +7,9 @@ pub mod clipboard;
 pub mod code;
 pub mod col_row;
 pub mod formats;
+pub mod goal_seek;
 pub mod import;
 pub mod sheets;
+pub mod tables;
 pub mod undo;
 pub mod validations;
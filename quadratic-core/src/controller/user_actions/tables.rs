@@ -0,0 +1,231 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::{
+    controller::{active_transactions::transaction_name::TransactionName, GridController},
+    grid::{sheet::tables::Table, SheetId},
+    SheetRect,
+};
+
+impl GridController {
+    /// Gets the tables for a sheet.
+    pub fn tables(&self, sheet_id: SheetId) -> Vec<&Table> {
+        self.try_sheet(sheet_id)
+            .map(|sheet| sheet.tables.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Creates a table over `sheet_rect`. A `TableN` name is used if `name` is
+    /// None.
+    pub fn add_table(
+        &mut self,
+        sheet_rect: SheetRect,
+        name: Option<String>,
+        show_header: bool,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        let ops = self.add_table_operations(sheet_rect, name, show_header)?;
+        self.start_user_transaction(ops, cursor, TransactionName::Table);
+        Ok(())
+    }
+
+    /// Removes a table. The cells of the table are not changed.
+    pub fn remove_table(&mut self, sheet_id: SheetId, table_id: Uuid, cursor: Option<String>) {
+        let ops = self.remove_table_operations(sheet_id, table_id);
+        self.start_user_transaction(ops, cursor, TransactionName::Table);
+    }
+
+    /// Renames a table and updates references to it in code cells.
+    pub fn rename_table(
+        &mut self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        name: String,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        let ops = self.rename_table_operations(sheet_id, table_id, name)?;
+        self.start_user_transaction(ops, cursor, TransactionName::Table);
+        Ok(())
+    }
+
+    /// Renames a column of a table and updates references to it in code cells.
+    pub fn rename_table_column(
+        &mut self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        column: usize,
+        name: String,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        let ops = self.rename_table_column_operations(sheet_id, table_id, column, name)?;
+        self.start_user_transaction(ops, cursor, TransactionName::Table);
+        Ok(())
+    }
+
+    /// Shows or hides the totals row of a table.
+    pub fn set_table_totals(
+        &mut self,
+        sheet_id: SheetId,
+        table_id: Uuid,
+        show_totals: bool,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        let ops = self.set_table_totals_operations(sheet_id, table_id, show_totals)?;
+        if !ops.is_empty() {
+            self.start_user_transaction(ops, cursor, TransactionName::Table);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::{Rect, SheetPos};
+
+    #[test]
+    fn test_add_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 1), "Item".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 2, 1), "Item".into(), None);
+
+        let rect = Rect::test_a1("A1:C4");
+        gc.add_table(rect.to_sheet_rect(sheet_id), None, true, None)
+            .unwrap();
+        let tables = gc.tables(sheet_id);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "Table1");
+        assert_eq!(tables[0].bounds, rect);
+        let names = tables[0]
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Item", "Column1", "Column2"]);
+
+        // overlapping tables and duplicate names are rejected
+        assert!(gc
+            .add_table(
+                Rect::test_a1("B2:D5").to_sheet_rect(sheet_id),
+                None,
+                true,
+                None
+            )
+            .is_err());
+        assert!(gc
+            .add_table(
+                Rect::test_a1("E1:F2").to_sheet_rect(sheet_id),
+                Some("table1".into()),
+                true,
+                None
+            )
+            .is_err());
+        assert!(gc
+            .add_table(
+                Rect::test_a1("E1:F2").to_sheet_rect(sheet_id),
+                Some("1Table".into()),
+                true,
+                None
+            )
+            .is_err());
+
+        gc.add_table(
+            Rect::test_a1("E1:F2").to_sheet_rect(sheet_id),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(gc.tables(sheet_id)[1].name, "Table2");
+
+        gc.undo(None);
+        assert_eq!(gc.tables(sheet_id).len(), 1);
+    }
+
+    #[test]
+    fn test_remove_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_table(
+            Rect::test_a1("A1:B3").to_sheet_rect(sheet_id),
+            Some("Sales".into()),
+            true,
+            None,
+        )
+        .unwrap();
+        let table_id = gc.tables(sheet_id)[0].id;
+
+        gc.remove_table(sheet_id, table_id, None);
+        assert!(gc.tables(sheet_id).is_empty());
+
+        gc.undo(None);
+        assert_eq!(gc.tables(sheet_id)[0].name, "Sales");
+    }
+
+    #[test]
+    fn test_rename_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_table(
+            Rect::test_a1("A1:B3").to_sheet_rect(sheet_id),
+            Some("Sales".into()),
+            true,
+            None,
+        )
+        .unwrap();
+        gc.add_table(
+            Rect::test_a1("D1:E3").to_sheet_rect(sheet_id),
+            Some("Other".into()),
+            true,
+            None,
+        )
+        .unwrap();
+        let table_id = gc.tables(sheet_id)[0].id;
+
+        assert!(gc
+            .rename_table(sheet_id, table_id, "other".into(), None)
+            .is_err());
+        gc.rename_table(sheet_id, table_id, "Revenue".into(), None)
+            .unwrap();
+        assert_eq!(gc.tables(sheet_id)[0].name, "Revenue");
+
+        assert!(gc
+            .rename_table_column(sheet_id, table_id, 0, "Column2".into(), None)
+            .is_err());
+        gc.rename_table_column(sheet_id, table_id, 0, "Item".into(), None)
+            .unwrap();
+        assert_eq!(gc.tables(sheet_id)[0].columns[0].name, "Item");
+        assert_eq!(
+            gc.sheet(sheet_id).display_value((1, 1).into()),
+            Some(crate::CellValue::Text("Item".into()))
+        );
+    }
+
+    #[test]
+    fn test_set_table_totals() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_table(
+            Rect::test_a1("A1:B3").to_sheet_rect(sheet_id),
+            Some("Sales".into()),
+            true,
+            None,
+        )
+        .unwrap();
+        let table_id = gc.tables(sheet_id)[0].id;
+
+        gc.set_table_totals(sheet_id, table_id, true, None).unwrap();
+        let table = gc.tables(sheet_id)[0];
+        assert!(table.show_totals);
+        assert_eq!(table.bounds, Rect::test_a1("A1:B4"));
+        assert_eq!(table.totals_row(), Some(4));
+
+        gc.set_table_totals(sheet_id, table_id, false, None)
+            .unwrap();
+        let table = gc.tables(sheet_id)[0];
+        assert!(!table.show_totals);
+        assert_eq!(table.bounds, Rect::test_a1("A1:B3"));
+    }
+}
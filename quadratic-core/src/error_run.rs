@@ -108,6 +108,13 @@ pub enum RunErrorMsg {
     IndexOutOfBounds,
     NoMatch,
     InvalidArgument,
+
+    // Connection errors
+    QueryCancelled,
+    QueryTimeout,
+
+    // Async errors
+    CodeRunTimeout,
 }
 
 impl fmt::Display for RunErrorMsg {
@@ -257,6 +264,16 @@ impl fmt::Display for RunErrorMsg {
             Self::InvalidArgument => {
                 write!(f, "Invalid argument")
             }
+
+            Self::QueryCancelled => {
+                write!(f, "Query was cancelled")
+            }
+            Self::QueryTimeout => {
+                write!(f, "Query exceeded the maximum allowed time")
+            }
+            Self::CodeRunTimeout => {
+                write!(f, "Code run exceeded the maximum allowed time")
+            }
         }
     }
 }
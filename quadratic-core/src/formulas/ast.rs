@@ -4,8 +4,8 @@ use smallvec::smallvec;
 
 use super::*;
 use crate::{
-    Array, ArraySize, CellValue, CodeResult, CodeResultExt, CoerceInto, RunErrorMsg, SheetRect,
-    Span, Spanned, Value,
+    a1::TableRef, Array, ArraySize, CellValue, CodeResult, CodeResultExt, CoerceInto, Pos,
+    RunErrorMsg, SheetPos, SheetRect, Span, Spanned, Value,
 };
 
 /// Abstract syntax tree of a formula expression.
@@ -26,6 +26,7 @@ pub enum AstNodeContents {
     Paren(Vec<AstNode>),
     Array(Vec<Vec<AstNode>>),
     CellRef(CellRef),
+    TableRef(TableRef),
     String(String),
     Number(f64),
     Bool(bool),
@@ -46,6 +47,7 @@ impl AstNodeContents {
             },
             AstNodeContents::Array(_) => "array literal",
             AstNodeContents::CellRef(_) => "cell reference",
+            AstNodeContents::TableRef(_) => "table reference",
             AstNodeContents::String(_) => "string literal",
             AstNodeContents::Number(_) => "numeric literal",
             AstNodeContents::Bool(_) => "boolean literal",
@@ -70,10 +72,13 @@ impl AstNode {
 
             AstNodeContents::FunctionCall { func, .. } if func.inner == ":" => {
                 let range = self.to_range_ref(ctx)?;
-                let rect = ctx.resolve_range_ref(&range.inner, self.span)?;
-                let array = ctx.get_cell_array(rect.inner, self.span)?;
+                eval_range_ref(ctx, &range.inner, self.span)?
+            }
 
-                Value::Array(array.inner)
+            AstNodeContents::FunctionCall { func, args }
+                if func.inner.eq_ignore_ascii_case("DATATABLE") =>
+            {
+                eval_data_table(ctx, args, self.span)?
             }
 
             // Other operator/function
@@ -135,12 +140,27 @@ impl AstNode {
                 Array::new_row_major(size, flat_array)?.into()
             }
 
+            // 3D references return one array for each sheet.
+            AstNodeContents::CellRef(cell_ref)
+                if is_sheet_span(ctx, cell_ref) || range_to_sheet(ctx, cell_ref).is_some() =>
+            {
+                let range = self.to_range_ref(ctx)?;
+                eval_range_ref(ctx, &range.inner, self.span)?
+            }
+
             // Single cell references return 1x1 arrays for Excel compatibility.
             AstNodeContents::CellRef(cell_ref) => {
                 let pos = ctx.resolve_ref(cell_ref, self.span)?.inner;
                 Array::from(ctx.get_cell(pos, self.span, true).inner).into()
             }
 
+            AstNodeContents::TableRef(table_ref) => {
+                let rect = ctx.resolve_table_ref(table_ref, self.span)?;
+                let array = ctx.get_cell_array(rect.inner, self.span)?;
+
+                Value::Array(array.inner)
+            }
+
             AstNodeContents::String(s) => Value::from(s.to_string()),
             AstNodeContents::Number(n) => Value::from(*n),
             AstNodeContents::Bool(b) => Value::from(*b),
@@ -181,10 +201,29 @@ impl AstNode {
             AstNodeContents::Paren(contents) if contents.len() == 1 => {
                 contents[0].to_range_ref(ctx)
             }
-            AstNodeContents::CellRef(cell_ref) => Ok(RangeRef::Cell {
-                pos: cell_ref.clone(),
-            })
+            AstNodeContents::CellRef(cell_ref) => Ok(range_to_sheet(ctx, cell_ref).unwrap_or_else(
+                || RangeRef::Cell {
+                    pos: cell_ref.clone(),
+                },
+            ))
             .with_span(self.span),
+            AstNodeContents::TableRef(table_ref) => {
+                let rect = ctx.resolve_table_ref(table_ref, self.span)?.inner;
+                let sheet = ctx
+                    .grid
+                    .try_sheet(rect.sheet_id)
+                    .ok_or(RunErrorMsg::BadCellReference.with_span(self.span))?;
+                let cell_ref = |pos: Pos| CellRef {
+                    sheet: Some(sheet.name.clone()),
+                    x: CellRefCoord::Absolute(pos.x),
+                    y: CellRefCoord::Absolute(pos.y),
+                };
+                Ok(RangeRef::CellRange {
+                    start: cell_ref(rect.min),
+                    end: cell_ref(rect.max),
+                })
+                .with_span(self.span)
+            }
             _ => Err(RunErrorMsg::Expected {
                 expected: "cell range reference".into(),
                 got: Some(self.inner.type_string().into()),
@@ -282,6 +321,153 @@ impl AstNode {
     }
 }
 
+/// Evaluates `DATATABLE`, which takes cell references that are not evaluated
+/// as values, so it can't be implemented as a regular function.
+///
+/// The result cell is evaluated once for each input value (or pair of input
+/// values) by substituting them for the input cells. The sheet is never
+/// modified.
+fn eval_data_table(ctx: &mut Ctx<'_>, args: &[AstNode], span: Span) -> CodeResult<Value> {
+    const FUNC_NAME: &str = "DATATABLE";
+    fn resolve_cell(ctx: &mut Ctx<'_>, node: &AstNode) -> CodeResult<SheetPos> {
+        let cell_ref = node.to_cell_ref(ctx)?;
+        Ok(ctx.resolve_ref(&cell_ref, node.span)?.inner)
+    }
+
+    let arg = |i: usize, arg_name: &'static str| {
+        args.get(i).ok_or(
+            RunErrorMsg::MissingRequiredArgument {
+                func_name: FUNC_NAME.into(),
+                arg_name: arg_name.into(),
+            }
+            .with_span(span),
+        )
+    };
+    if args.len() > 5 {
+        return Err(RunErrorMsg::TooManyArguments {
+            func_name: FUNC_NAME.into(),
+            max_arg_count: 5,
+        }
+        .with_span(span));
+    }
+
+    let result_pos = resolve_cell(ctx, arg(0, "result_cell")?)?;
+    let row_input = resolve_cell(ctx, arg(1, "input_cell")?)?;
+    let row_values = arg(2, "values")?.eval(ctx)?.into_array()?.inner;
+    let column = match args.get(3) {
+        Some(column_input) => {
+            let column_input = resolve_cell(ctx, column_input)?;
+            let column_values = arg(4, "column_values")?.eval(ctx)?.into_array()?.inner;
+            Some((column_input, column_values))
+        }
+        None => None,
+    };
+
+    if ctx.skip_computation {
+        return Ok(CellValue::Blank.into());
+    }
+
+    // The result depends on the result cell's formula, and on anything it
+    // reads, which is recorded while evaluating it.
+    ctx.cells_accessed.add_sheet_pos(result_pos);
+
+    let (size, substitutions) = match &column {
+        // Each value of a one-variable table replaces the input cell.
+        None => (
+            row_values.size(),
+            row_values
+                .cell_values_slice()
+                .iter()
+                .map(|value| vec![(row_input, value.clone())])
+                .collect_vec(),
+        ),
+        // Row values go across the top and column values go down the side.
+        Some((column_input, column_values)) => {
+            let row_values = row_values.cell_values_slice();
+            let column_values = column_values.cell_values_slice();
+            let size = ArraySize::new_or_err(row_values.len() as u32, column_values.len() as u32)?;
+            let substitutions = column_values
+                .iter()
+                .cartesian_product(row_values)
+                .map(|(column_value, row_value)| {
+                    vec![
+                        (row_input, row_value.clone()),
+                        (*column_input, column_value.clone()),
+                    ]
+                })
+                .collect_vec();
+            (size, substitutions)
+        }
+    };
+
+    let mut results = smallvec![];
+    for substitution in substitutions {
+        let mut substitutions = ctx.substitutions().clone();
+        substitutions.extend(substitution);
+        let previous = ctx.set_substitutions(substitutions);
+        let value = ctx.get_cell(result_pos, span, false).inner;
+        // an error from a cell that can't be recomputed is reported even if
+        // the formula would otherwise hide it, such as with `IFERROR`
+        let value = match ctx.substitution_error() {
+            Some(e) => CellValue::Error(Box::new(e.clone())),
+            None => value,
+        };
+        ctx.set_substitutions(previous);
+        results.push(value);
+    }
+
+    Ok(Array::new_row_major(size, results)?.into())
+}
+
+/// Returns whether a cell reference is a 3D reference to a span of sheets,
+/// such as `Jan:Dec!B5`.
+fn is_sheet_span(ctx: &Ctx<'_>, cell_ref: &CellRef) -> bool {
+    cell_ref.sheet.as_ref().is_some_and(|sheet_name| {
+        ctx.grid.try_sheet_from_name(sheet_name.clone()).is_none()
+            && ctx.grid.try_sheet_span_endpoints(sheet_name).is_some()
+    })
+}
+
+/// Returns the range for a cell reference such as `A1:Sheet2!B5`, which is
+/// lexed the same way as a 3D reference. It is only a 3D reference if both
+/// `A1` and `Sheet2` are existing sheets; otherwise it is a range from `A1` to
+/// `Sheet2!B5`.
+fn range_to_sheet(ctx: &Ctx<'_>, cell_ref: &CellRef) -> Option<RangeRef> {
+    let sheet_name = cell_ref.sheet.as_ref()?;
+    if ctx.grid.try_sheet_span_from_name(sheet_name).is_some() {
+        return None;
+    }
+    let (start, end_sheet) = sheet_name.split_once(':')?;
+    if !start.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(RangeRef::CellRange {
+        start: CellRef::parse_a1(start, ctx.sheet_pos.into())?,
+        end: CellRef {
+            sheet: Some(end_sheet.to_string()),
+            ..cell_ref.clone()
+        },
+    })
+}
+
+/// Fetches the cells of a range reference. A 3D reference returns a tuple with
+/// one array for each sheet it covers.
+fn eval_range_ref(ctx: &mut Ctx<'_>, range: &RangeRef, span: Span) -> CodeResult<Value> {
+    let mut arrays: Vec<Array> = ctx
+        .resolve_range_ref_sheets(range, span)?
+        .into_iter()
+        .map(|rect| CodeResult::Ok(ctx.get_cell_array(rect.inner, span)?.inner))
+        .try_collect()?;
+    if arrays.len() == 1 {
+        Ok(arrays
+            .pop()
+            .ok_or(RunErrorMsg::InternalError("empty range".into()))?
+            .into())
+    } else {
+        Ok(Value::Tuple(arrays))
+    }
+}
+
 fn eval_cell_range_op(
     ctx: &mut Ctx<'_>,
     args: &[AstNode],
@@ -74,6 +74,35 @@ impl RangeRef {
             RangeRef::Cell { pos } => pos.a1_string(base),
         }
     }
+
+    /// Returns the string representing this range reference in R1C1
+    /// notation, such as `R5C2` or `R[-1]C:R[1]C[2]`.
+    pub fn r1c1_string(self) -> String {
+        match self {
+            RangeRef::RowRange { start, end, .. } => {
+                format!("R{}:R{}", start.r1c1_string(), end.r1c1_string())
+            }
+            RangeRef::ColRange { start, end, .. } => {
+                format!("C{}:C{}", start.r1c1_string(), end.r1c1_string())
+            }
+            RangeRef::CellRange { start, end } => {
+                format!("{}:{}", start.r1c1_string(), end.r1c1_string())
+            }
+            RangeRef::Cell { pos } => pos.r1c1_string(),
+        }
+    }
+
+    /// Returns whether the range reference is missing a row or column, such
+    /// as `A:A` or `2:3`.
+    pub fn is_unbounded(&self, base: Pos) -> bool {
+        match self {
+            RangeRef::RowRange { .. } | RangeRef::ColRange { .. } => true,
+            RangeRef::CellRange { start, end } => {
+                start.is_unbounded(base) || end.is_unbounded(base)
+            }
+            RangeRef::Cell { pos } => pos.is_unbounded(base),
+        }
+    }
 }
 
 /// A reference to a single cell.
@@ -154,6 +183,44 @@ impl CellRef {
         format!("{sheet_str}{col}{row}")
     }
 
+    /// Returns the string representing this cell reference in R1C1 notation,
+    /// such as `R5C2` or `R[-1]C[2]`.
+    pub fn r1c1_string(&self) -> String {
+        let sheet_str = match &self.sheet {
+            Some(sheet_name) => format!("{}!", escape_string(sheet_name)),
+            None => String::new(),
+        };
+        let r1c1 = crate::a1::format_r1c1(&self.y.r1c1_string(), &self.x.r1c1_string());
+
+        format!("{sheet_str}{r1c1}")
+    }
+
+    /// Returns whether the cell reference is missing a row or column.
+    fn is_unbounded(&self, base: Pos) -> bool {
+        self.x.resolve_from(base.x) == UNBOUNDED || self.y.resolve_from(base.y) == UNBOUNDED
+    }
+
+    /// Parses an R1C1-style cell reference, such as `R5C2` or `R[-1]C[2]`. An
+    /// omitted row or column number is relative to the current row or column.
+    /// `RC` and `RC5` are A1-style references, so they return `None`.
+    pub fn parse_r1c1(s: &str) -> Option<CellRef> {
+        let (sheet, rest) = parse_sheet_name(s);
+
+        let (row, col) = crate::a1::split_r1c1(rest)?;
+
+        let parse_coord = |s: &str| match s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(delta) => Some(CellRefCoord::Relative(delta.parse().ok()?)),
+            None if s.is_empty() => Some(CellRefCoord::Relative(0)),
+            None => Some(CellRefCoord::Absolute(s.parse().ok()?)),
+        };
+
+        Some(CellRef {
+            sheet,
+            x: parse_coord(col)?,
+            y: parse_coord(row)?,
+        })
+    }
+
     /// Parses an A1-style cell reference relative to a given location.
     pub fn parse_a1(s: &str, mut base: Pos) -> Option<CellRef> {
         let (sheet, rest) = parse_sheet_name(s);
@@ -290,6 +357,15 @@ impl CellRefCoord {
 
         format!("{}{row}", self.prefix())
     }
+    /// Returns the row or column number of an R1C1-style reference. A
+    /// relative offset of zero is omitted.
+    fn r1c1_string(self) -> String {
+        match self {
+            CellRefCoord::Relative(0) => String::new(),
+            CellRefCoord::Relative(delta) => format!("[{delta}]"),
+            CellRefCoord::Absolute(coord) => coord.to_string(),
+        }
+    }
     pub fn get_value(self, base: i64) -> i64 {
         match self {
             CellRefCoord::Relative(delta) => delta.saturating_add(base),
@@ -1,13 +1,20 @@
+use std::collections::{HashMap, HashSet};
+
 use itertools::Itertools;
 use smallvec::{smallvec, SmallVec};
 
 use super::*;
 use crate::{
-    grid::{CellsAccessed, Grid},
-    Array, CellValue, CodeResult, CodeResultExt, Pos, RunErrorMsg, SheetPos, SheetRect, Span,
-    Spanned, Value, UNBOUNDED,
+    a1::TableRef,
+    grid::{CellsAccessed, CodeCellLanguage, Grid, SheetId},
+    Array, CellValue, CodeResult, CodeResultExt, Pos, Rect, RunError, RunErrorMsg, SheetPos,
+    SheetRect, Span, Spanned, Value, UNBOUNDED,
 };
 
+/// Maximum number of formula cells that may be re-evaluated inside each other
+/// while there are substitutions.
+const MAX_SUBSTITUTED_FORMULA_DEPTH: usize = 64;
+
 /// Formula execution context.
 #[derive(Debug)]
 pub struct Ctx<'ctx> {
@@ -17,9 +24,28 @@ pub struct Ctx<'ctx> {
     pub sheet_pos: SheetPos,
     /// Cells that have been accessed in evaluating the formula.
     pub cells_accessed: CellsAccessed,
+    /// Number of cells read in evaluating the formula.
+    pub cells_read: u64,
 
     /// Whether to only parse, skipping expensive computations.
     pub skip_computation: bool,
+
+    /// Values to use in place of the contents of cells, without modifying the
+    /// grid. While there are substitutions, formula cells that depend on them
+    /// are re-evaluated when they are read so that they see the substituted
+    /// values.
+    substitutions: HashMap<SheetPos, CellValue>,
+    /// Results of formula cells re-evaluated with the current substitutions.
+    substituted_formula_values: HashMap<SheetPos, CellValue>,
+    /// Code cells that depend on the substituted cells, directly or through
+    /// the output of other code cells.
+    substituted_dependents: HashSet<SheetPos>,
+    /// First error from a cell that can't be recomputed with the current
+    /// substitutions.
+    substitution_error: Option<RunError>,
+    /// Positions of the formulas whose evaluation is in progress, outside of
+    /// the one at `sheet_pos`.
+    formula_stack: Vec<SheetPos>,
 }
 impl<'ctx> Ctx<'ctx> {
     /// Constructs a context for evaluating a formula at `pos` in `grid`.
@@ -28,7 +54,13 @@ impl<'ctx> Ctx<'ctx> {
             grid,
             sheet_pos,
             cells_accessed: Default::default(),
+            cells_read: 0,
             skip_computation: false,
+            substitutions: HashMap::new(),
+            substituted_formula_values: HashMap::new(),
+            substituted_dependents: HashSet::new(),
+            substitution_error: None,
+            formula_stack: vec![],
         }
     }
 
@@ -40,7 +72,13 @@ impl<'ctx> Ctx<'ctx> {
             grid,
             sheet_pos: Pos::ORIGIN.to_sheet_pos(grid.sheets()[0].id),
             cells_accessed: Default::default(),
+            cells_read: 0,
             skip_computation: true,
+            substitutions: HashMap::new(),
+            substituted_formula_values: HashMap::new(),
+            substituted_dependents: HashSet::new(),
+            substitution_error: None,
+            formula_stack: vec![],
         }
     }
 
@@ -89,6 +127,71 @@ impl<'ctx> Ctx<'ctx> {
         }
     }
 
+    /// Resolves the sheet name of a reference to the sheets it covers. A 3D
+    /// reference such as `Jan:Dec` covers both sheets and every sheet between
+    /// them in sheet order.
+    pub fn resolve_sheet_span(
+        &self,
+        sheet_name: &Option<String>,
+        span: Span,
+    ) -> CodeResult<Vec<SheetId>> {
+        match sheet_name {
+            Some(sheet_name) => self
+                .grid
+                .try_sheet_span_from_name(sheet_name)
+                .ok_or(RunErrorMsg::BadCellReference.with_span(span)),
+            None => Ok(vec![self.sheet_pos.sheet_id]),
+        }
+    }
+
+    /// Resolves a cell range reference relative to `self.sheet_pos`, returning
+    /// one rectangle for each sheet covered by the reference.
+    pub fn resolve_range_ref_sheets(
+        &self,
+        range: &RangeRef,
+        span: Span,
+    ) -> CodeResult<Vec<Spanned<SheetRect>>> {
+        let base = self.sheet_pos.into();
+        let (sheet_name, rect) = match range {
+            RangeRef::CellRange { start, end } => (
+                &start.sheet,
+                Rect::new_span(start.resolve_from(base), end.resolve_from(base)),
+            ),
+            RangeRef::Cell { pos } => (&pos.sheet, Rect::single_pos(pos.resolve_from(base))),
+            _ => return Ok(vec![self.resolve_range_ref(range, span)?]),
+        };
+        Ok(self
+            .resolve_sheet_span(sheet_name, span)?
+            .into_iter()
+            .map(|sheet_id| Spanned {
+                span,
+                inner: rect.to_sheet_rect(sheet_id),
+            })
+            .collect())
+    }
+
+    /// Resolves a structured table reference relative to `self.sheet_pos`.
+    /// `#This Row` references use the row of the formula.
+    pub fn resolve_table_ref(
+        &self,
+        table_ref: &TableRef,
+        span: Span,
+    ) -> CodeResult<Spanned<SheetRect>> {
+        if self.skip_computation {
+            // Tables may not exist in the grid used for syntax checks.
+            return Ok(SheetRect::single_sheet_pos(self.sheet_pos)).with_span(span);
+        }
+
+        let (sheet, table) = self
+            .grid
+            .try_table_from_name(&table_ref.table_name)
+            .ok_or(RunErrorMsg::BadCellReference.with_span(span))?;
+        let rect = table
+            .resolve(table_ref, Some(self.sheet_pos.y))
+            .ok_or(RunErrorMsg::BadCellReference.with_span(span))?;
+        Ok(rect.to_sheet_rect(sheet.id)).with_span(span)
+    }
+
     /// Fetches the contents of the cell at `pos` evaluated at `self.sheet_pos`,
     /// or returns an error in the case of a circular reference. If
     /// add_cells_accessed is true, it will add the cell reference to
@@ -112,18 +215,266 @@ impl<'ctx> Ctx<'ctx> {
         let Some(sheet) = self.grid.try_sheet(pos.sheet_id) else {
             return error_value(RunErrorMsg::BadCellReference);
         };
-        if pos == self.sheet_pos {
+        if pos == self.sheet_pos || self.formula_stack.contains(&pos) {
             return error_value(RunErrorMsg::CircularReference);
         }
+        if let Some(value) = self.substitutions.get(&pos) {
+            let value = value.clone();
+            return Spanned { inner: value, span };
+        }
 
         if add_cells_accessed {
             self.cells_accessed.add_sheet_pos(pos);
         }
+        self.cells_read += 1;
+
+        if !self.substitutions.is_empty() {
+            if let Err(e) = self.check_substituted_output(pos) {
+                self.substitution_error
+                    .get_or_insert(e.clone().without_span());
+                return error_value(e);
+            }
+            if self.substituted_dependents.contains(&pos) {
+                if let Some(value) = self.try_eval_formula_cell(pos) {
+                    return Spanned { inner: value, span };
+                }
+            }
+        }
 
         let value = sheet.get_cell_for_formula(pos.into());
         Spanned { inner: value, span }
     }
 
+    /// Returns the values substituted for cells.
+    pub fn substitutions(&self) -> &HashMap<SheetPos, CellValue> {
+        &self.substitutions
+    }
+
+    /// Returns the first error from a cell that can't be recomputed with the
+    /// current substitutions, such as a Python cell that reads a substituted
+    /// cell.
+    pub fn substitution_error(&self) -> Option<&RunError> {
+        self.substitution_error.as_ref()
+    }
+
+    /// Replaces the values substituted for cells, returning the previous
+    /// substitutions.
+    pub fn set_substitutions(
+        &mut self,
+        substitutions: HashMap<SheetPos, CellValue>,
+    ) -> HashMap<SheetPos, CellValue> {
+        self.substituted_formula_values.clear();
+        self.substitution_error = None;
+        let same_cells = substitutions.len() == self.substitutions.len()
+            && substitutions
+                .keys()
+                .all(|pos| self.substitutions.contains_key(pos));
+        if !same_cells {
+            self.substituted_dependents = self.dependents_of(substitutions.keys().copied());
+        }
+        std::mem::replace(&mut self.substitutions, substitutions)
+    }
+
+    /// Returns the code cells that depend on `positions`, directly or through
+    /// the output of other code cells.
+    fn dependents_of(&self, positions: impl IntoIterator<Item = SheetPos>) -> HashSet<SheetPos> {
+        let mut dependents = HashSet::new();
+        let mut changed = positions
+            .into_iter()
+            .map(SheetRect::single_sheet_pos)
+            .collect_vec();
+        while let Some(sheet_rect) = changed.pop() {
+            for sheet in self.grid.sheets() {
+                for pos in sheet.dependent_code_runs(&sheet_rect) {
+                    let sheet_pos = pos.to_sheet_pos(sheet.id);
+                    if dependents.insert(sheet_pos) {
+                        if let Some(code_run) = sheet.code_run(pos) {
+                            changed.push(code_run.output_sheet_rect(sheet_pos, false));
+                        }
+                    }
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Returns an error if the cell at `pos` is in the output of a code cell
+    /// that depends on the substituted cells but isn't re-evaluated with them:
+    /// a Python, JavaScript or connection cell, or a formula cell other than
+    /// the one at the top left of its output.
+    fn check_substituted_output(&self, pos: SheetPos) -> Result<(), RunErrorMsg> {
+        let Some(sheet) = self.grid.try_sheet(pos.sheet_id) else {
+            return Ok(());
+        };
+        for &code_pos in &self.substituted_dependents {
+            if code_pos.sheet_id != pos.sheet_id {
+                continue;
+            }
+            let in_output = sheet
+                .code_run(code_pos.into())
+                .is_some_and(|code_run| code_run.output_sheet_rect(code_pos, false).contains(pos));
+            if !in_output {
+                continue;
+            }
+            let Some(CellValue::Code(code)) = sheet.cell_value_ref(code_pos.into()) else {
+                continue;
+            };
+            let language = match code.language {
+                CodeCellLanguage::Formula if code_pos == pos => continue,
+                CodeCellLanguage::Formula => {
+                    return Err(RunErrorMsg::CodeRunError(
+                        "Only the first cell of a formula's output can be recomputed".into(),
+                    ));
+                }
+                CodeCellLanguage::Python => "Python",
+                CodeCellLanguage::Javascript => "JavaScript",
+                CodeCellLanguage::Connection { .. } => "Connection",
+            };
+            return Err(RunErrorMsg::CodeRunError(
+                format!("{language} cells can't be recomputed with substituted values").into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Evaluates the formula in the code cell at `pos` using the current
+    /// substitutions, returning the value at the top left of its output.
+    ///
+    /// Returns an error if the cell at `pos` does not contain a formula, or if
+    /// the formula reads a cell that can't be recomputed with the current
+    /// substitutions.
+    pub fn eval_formula_cell(&mut self, pos: SheetPos) -> CodeResult<CellValue> {
+        let value = self
+            .try_eval_formula_cell(pos)
+            .ok_or(RunErrorMsg::Expected {
+                expected: "formula cell".into(),
+                got: None,
+            })?;
+        match self.substitution_error() {
+            Some(e) => Err(e.clone()),
+            None => Ok(value),
+        }
+    }
+
+    /// Evaluates the formula in the code cell at `pos` using the current
+    /// substitutions. Returns `None` if the cell at `pos` does not contain a
+    /// formula.
+    fn try_eval_formula_cell(&mut self, pos: SheetPos) -> Option<CellValue> {
+        if let Some(value) = self.substituted_formula_values.get(&pos) {
+            return Some(value.clone());
+        }
+        let code = self.formula_code(pos)?;
+        if self.formula_stack.is_empty() && self.substituted_dependents.contains(&pos) {
+            self.eval_substituted_dependencies(pos);
+        }
+        Some(self.eval_substituted_formula(pos, &code))
+    }
+
+    /// Returns the code of the formula cell at `pos`.
+    fn formula_code(&self, pos: SheetPos) -> Option<String> {
+        let sheet = self.grid.try_sheet(pos.sheet_id)?;
+        match sheet.cell_value_ref(pos.into()) {
+            Some(CellValue::Code(code)) if code.language == CodeCellLanguage::Formula => {
+                Some(code.code.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates the formula cells that the code cell at `pos` reads, directly
+    /// or through other code cells, and that depend on the substituted cells.
+    /// They are evaluated in topological order, so each formula only reads
+    /// results that are already cached instead of evaluating them inside
+    /// itself.
+    fn eval_substituted_dependencies(&mut self, pos: SheetPos) {
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        // (code cell, whether its dependencies have been visited)
+        let mut stack = vec![(pos, false)];
+        while let Some((sheet_pos, dependencies_visited)) = stack.pop() {
+            if dependencies_visited {
+                order.push(sheet_pos);
+            } else if visited.insert(sheet_pos) {
+                stack.push((sheet_pos, true));
+                for dependency in self.substituted_dependencies(sheet_pos) {
+                    if !visited.contains(&dependency) {
+                        stack.push((dependency, false));
+                    }
+                }
+            }
+        }
+
+        for sheet_pos in order {
+            if sheet_pos == pos
+                || sheet_pos == self.sheet_pos
+                || self.substituted_formula_values.contains_key(&sheet_pos)
+            {
+                continue;
+            }
+            if let Some(code) = self.formula_code(sheet_pos) {
+                self.eval_substituted_formula(sheet_pos, &code);
+            }
+        }
+    }
+
+    /// Returns the code cells that depend on the substituted cells and whose
+    /// output is read by the code cell at `pos`.
+    fn substituted_dependencies(&self, pos: SheetPos) -> Vec<SheetPos> {
+        let Some(code_run) = self
+            .grid
+            .try_sheet(pos.sheet_id)
+            .and_then(|sheet| sheet.code_run(pos.into()))
+        else {
+            return vec![];
+        };
+        self.substituted_dependents
+            .iter()
+            .filter(|&&dependency| dependency != pos)
+            .filter(|&&dependency| {
+                self.grid
+                    .try_sheet(dependency.sheet_id)
+                    .and_then(|sheet| sheet.code_run(dependency.into()))
+                    .is_some_and(|dependency_run| {
+                        let output = dependency_run.output_sheet_rect(dependency, false);
+                        code_run.cells_accessed.intersects(&output)
+                    })
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Evaluates the formula `code` of the cell at `pos` using the current
+    /// substitutions, and caches the value at the top left of its output.
+    fn eval_substituted_formula(&mut self, pos: SheetPos, code: &str) -> CellValue {
+        // formulas that weren't evaluated in order are evaluated inside the
+        // formula that reads them, so limit how deep that can go
+        if self.formula_stack.len() >= MAX_SUBSTITUTED_FORMULA_DEPTH {
+            let e = RunErrorMsg::CodeRunError("Too many formulas to recompute".into());
+            self.substitution_error
+                .get_or_insert(e.clone().without_span());
+            return CellValue::Error(Box::new(e.without_span()));
+        }
+
+        let value = match parse_formula(code, pos.into()) {
+            Ok(parsed) => {
+                self.formula_stack.push(self.sheet_pos);
+                let outer_sheet_pos = std::mem::replace(&mut self.sheet_pos, pos);
+                let value = parsed.eval(self).into_non_tuple().inner;
+                self.sheet_pos = outer_sheet_pos;
+                self.formula_stack.pop();
+
+                match value.get(0, 0) {
+                    Ok(value) => value.clone(),
+                    Err(e) => CellValue::Error(Box::new(e.without_span())),
+                }
+            }
+            Err(e) => CellValue::Error(Box::new(e)),
+        };
+
+        self.substituted_formula_values.insert(pos, value.clone());
+        value
+    }
+
     /// Fetches the contents of the cell array at `rect`, or returns an error in
     /// the case of a circular reference.
     pub fn get_cell_array(&mut self, rect: SheetRect, span: Span) -> CodeResult<Spanned<Array>> {
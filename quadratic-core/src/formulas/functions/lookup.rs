@@ -377,9 +377,35 @@ fn get_functions() -> Vec<FormulaFunction> {
                     .inner
             }
         ),
+        // `DATATABLE` takes cell references rather than values, so it is
+        // evaluated by the AST; this entry only provides its documentation.
+        FormulaFunction {
+            name: "DATATABLE",
+            arg_completion: Some(
+                "${1:result_cell}, ${2:input_cell}, ${3:values}\
+                 ${4:, ${5:[column_input_cell]}${6:, ${7:[column_values]}}}",
+            ),
+            usage: "result_cell, input_cell, values, [column_input_cell], [column_values]",
+            examples: &[
+                "DATATABLE(B1, A1, {1; 2; 3; 4})",
+                "DATATABLE(C1, A1, D2:H2, B1, C3:C10)",
+            ],
+            doc: "Evaluates the formula in `result_cell` once for each value in \
+                  `values`, with that value in place of `input_cell`, and returns \
+                  the results in an array the same shape as `values`. The sheet \
+                  is not modified.\n\n\
+                  If `column_input_cell` and `column_values` are given, returns a \
+                  two-variable table with one column for each value in `values` \
+                  and one row for each value in `column_values`.",
+            eval: eval_data_table_placeholder,
+        },
     ]
 }
 
+fn eval_data_table_placeholder(_ctx: &mut Ctx<'_>, args: FormulaFnArgs) -> CodeResult<Value> {
+    Err(RunErrorMsg::InternalError("DATATABLE is evaluated by the AST".into()).with_span(args.span))
+}
+
 /// Arguments to the `INDEX` function.
 #[derive(Debug, Copy, Clone)]
 pub struct IndexFunctionArgs {
@@ -4,7 +4,10 @@ use lazy_static::lazy_static;
 use regex::{Regex, RegexBuilder};
 use strum_macros::Display;
 
-use crate::{Span, Spanned};
+use crate::{
+    a1::{R1C1_PATTERN, R1C1_REGEX},
+    Span, Spanned,
+};
 
 pub fn tokenize(input_str: &str) -> impl '_ + Iterator<Item = Spanned<Token>> {
     let mut token_start = 0;
@@ -40,6 +43,15 @@ const FUNCTION_CALL_PATTERN: &str = r"[A-Za-z_](\.?[A-Za-z_\d])*\(";
 const A1_CELL_REFERENCE_PATTERN: &str = r"\$?n?([a-zA-Z]+\$?n?\d*|\d+)";
 const INTERNAL_CELL_REFERENCE_PATTERN: &str = r"R([\[|\{]-?\d+[\]|\}])C([\[|\{]-?\d+[\]|\}])";
 
+/// Structured reference to a table, such as `Sales[Amount]` or
+/// `Sales[[#Headers],[Amount]]`.
+///
+/// [A-Za-z_][A-Za-z0-9_\.]*\[(\[[^\[\]]*\]|[^\[\]])*\]
+/// [A-Za-z_][A-Za-z0-9_\.]*                               table name
+///                         \[                         \]  outer brackets
+///                           (\[[^\[\]]*\]|[^\[\]])*      nested specifiers
+const TABLE_REFERENCE_PATTERN: &str = r"[A-Za-z_][A-Za-z0-9_\.]*\[(\[[^\[\]]*\]|[^\[\]])*\]";
+
 /// Floating-point or integer number, without leading sign.
 ///
 /// (\d+(\.\d*)?|\.\d+)([eE][+-]?\d+)?
@@ -59,10 +71,14 @@ const SINGLE_QUOTE_STRING_LITERAL_PATTERN: &str = r"'([^'\\]|\\[\s\S])*'";
 /// Double-quoted string. Note that like Rust strings, this can span multiple
 /// lines.
 const DOUBLE_QUOTE_STRING_LITERAL_PATTERN: &str = r#""([^"\\]|\\[\s\S])*""#;
-/// Unquoted sheet reference, such as `Sheet1!`. A quoted sheet reference such
-/// as `'Sheet1'!` is parsed as a string followed by a sheet reference operator
-/// `!`.
-const UNQUOTED_SHEET_REFERENCE_PATTERN: &str = r"[A-Za-z_][A-Za-z0-9_\.]*\s*!";
+/// Unquoted sheet reference, such as `Sheet1!`, or 3D reference to a span of
+/// sheets, such as `Jan:Dec!`. A quoted sheet reference such as `'Sheet1'!` is
+/// parsed as a string followed by a sheet reference operator `!`.
+///
+/// `A1:Sheet2!` also matches, so whether it is a 3D reference or the end of a
+/// range is decided when the formula is evaluated and the sheets are known.
+const UNQUOTED_SHEET_REFERENCE_PATTERN: &str =
+    r"[A-Za-z_][A-Za-z0-9_\.]*(\s*:\s*[A-Za-z_][A-Za-z0-9_\.]*)?\s*!";
 /// Unterminated string literal.
 const UNTERMINATED_STRING_LITERAL_PATTERN: &str = r#"["']"#;
 
@@ -90,6 +106,10 @@ const TOKEN_PATTERNS: &[&str] = &[
     r#"false|true"#,
     // Internal cell reference.
     INTERNAL_CELL_REFERENCE_PATTERN,
+    // R1C1-style cell reference.
+    R1C1_PATTERN,
+    // Structured reference to a table.
+    TABLE_REFERENCE_PATTERN,
     // Reference to a cell.
     A1_CELL_REFERENCE_PATTERN,
     // Whitespace.
@@ -108,7 +128,8 @@ lazy_static! {
     pub static ref FUNCTION_CALL_REGEX: Regex =
         new_fullmatch_regex(FUNCTION_CALL_PATTERN);
 
-    /// Regex that matches an unquoted sheet reference, such as `Sheet1!`.
+    /// Regex that matches an unquoted sheet reference, such as `Sheet1!` or
+    /// `Jan:Dec!`.
     pub static ref UNQUOTED_SHEET_REFERENCE: Regex =
         new_fullmatch_regex(UNQUOTED_SHEET_REFERENCE_PATTERN);
 
@@ -120,6 +141,10 @@ lazy_static! {
     pub static ref INTERNAL_CELL_REFERENCE_REGEX: Regex =
         new_fullmatch_regex(INTERNAL_CELL_REFERENCE_PATTERN);
 
+    /// Regex that matches a structured table reference.
+    pub static ref TABLE_REFERENCE_REGEX: Regex =
+        new_fullmatch_regex(TABLE_REFERENCE_PATTERN);
+
     /// Regex that matches all valid numeric literals and some invalid ones.
     pub static ref NUMERIC_LITERAL_REGEX: Regex =
         new_fullmatch_regex(NUMERIC_LITERAL_PATTERN);
@@ -225,6 +250,10 @@ pub enum Token {
     CellRef,
     #[strum(to_string = "internal cell reference")]
     InternalCellRef,
+    #[strum(to_string = "R1C1 cell reference")]
+    R1C1CellRef,
+    #[strum(to_string = "table reference")]
+    TableRef,
     #[strum(to_string = "whitespace")]
     Whitespace,
     #[strum(to_string = "unknown symbol")]
@@ -299,7 +328,16 @@ impl Token {
 
             // Match anything else.
             s if FUNCTION_CALL_REGEX.is_match(s) => Self::FunctionCall,
-            s if UNQUOTED_SHEET_REFERENCE.is_match(s) => Self::UnquotedSheetReference,
+            s if UNQUOTED_SHEET_REFERENCE.is_match(s) => {
+                // In `Sheet1!A1:Sheet1!B2`, `A1:Sheet1!` is the end of one
+                // cell reference and the start of another, not a 3D reference.
+                match s.split_once(':') {
+                    Some((first, _)) if input_str[..start].trim_end().ends_with('!') => {
+                        return Self::consume_from_input(&input_str[..start + first.len()], start);
+                    }
+                    _ => Self::UnquotedSheetReference,
+                }
+            }
             s if STRING_LITERAL_REGEX.is_match(s) => Self::StringLiteral,
             s if UNTERMINATED_STRING_LITERAL_REGEX.is_match(s) => Self::UnterminatedStringLiteral,
             s if s.eq_ignore_ascii_case("false") => Self::False,
@@ -311,6 +349,8 @@ impl Token {
                 Self::NumericLiteral
             }
             s if INTERNAL_CELL_REFERENCE_REGEX.is_match(s) => Self::InternalCellRef,
+            s if R1C1_REGEX.is_match(s) => Self::R1C1CellRef,
+            s if TABLE_REFERENCE_REGEX.is_match(s) => Self::TableRef,
             s if A1_CELL_REFERENCE_REGEX.is_match(s) => Self::CellRef,
             s if s.trim().is_empty() => Self::Whitespace,
 
@@ -361,6 +401,67 @@ mod tests {
         test_block_comment(false, "/* /*");
         test_block_comment(false, "/*/");
     }
+
+    #[test]
+    #[parallel]
+    fn test_lex_sheet_span_reference() {
+        fn tokens(s: &str) -> Vec<(Token, &str)> {
+            tokenize(s)
+                .filter(|t| !t.inner.is_skip())
+                .map(|t| (t.inner, t.span.of_str(s)))
+                .collect()
+        }
+        assert_eq!(
+            tokens("Jan:Dec!B5"),
+            [
+                (Token::UnquotedSheetReference, "Jan:Dec!"),
+                (Token::CellRef, "B5"),
+            ],
+        );
+        assert_eq!(
+            tokens("Sheet1!A1:Sheet1!B2"),
+            [
+                (Token::UnquotedSheetReference, "Sheet1!"),
+                (Token::CellRef, "A1"),
+                (Token::CellRangeOp, ":"),
+                (Token::UnquotedSheetReference, "Sheet1!"),
+                (Token::CellRef, "B2"),
+            ],
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn test_lex_r1c1_reference() {
+        fn tokens(s: &str) -> Vec<(Token, &str)> {
+            tokenize(s)
+                .filter(|t| !t.inner.is_skip())
+                .map(|t| (t.inner, t.span.of_str(s)))
+                .collect()
+        }
+        assert_eq!(
+            tokens("R5C2+r[-1]C[2]+RC[1]+R[1]C"),
+            [
+                (Token::R1C1CellRef, "R5C2"),
+                (Token::Plus, "+"),
+                (Token::R1C1CellRef, "r[-1]C[2]"),
+                (Token::Plus, "+"),
+                (Token::R1C1CellRef, "RC[1]"),
+                (Token::Plus, "+"),
+                (Token::R1C1CellRef, "R[1]C"),
+            ],
+        );
+        // A1-style references
+        assert_eq!(
+            tokens("RC5+R5"),
+            [
+                (Token::CellRef, "RC5"),
+                (Token::Plus, "+"),
+                (Token::CellRef, "R5"),
+            ],
+        );
+    }
+
     fn test_block_comment(expected_to_end: bool, s: &str) {
         let tokens = tokenize(s).collect_vec();
         if expected_to_end {
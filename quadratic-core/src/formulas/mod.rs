@@ -22,8 +22,9 @@ pub use ctx::Ctx;
 use functions::FormulaFnArgs;
 use params::{Param, ParamKind};
 pub use parser::{
-    find_cell_references, parse_and_check_formula, parse_formula, replace_a1_notation,
-    replace_cell_references_with, replace_internal_cell_references,
+    a1_to_r1c1, find_cell_references, parse_and_check_formula, parse_formula, r1c1_to_a1,
+    replace_a1_notation, replace_cell_references_with, replace_internal_cell_references,
+    replace_sheet_names, replace_table_references,
 };
 use wildcards::wildcard_pattern_to_regex;
 
@@ -1,6 +1,7 @@
 //! Parser that turns a flat list of tokens directly into an AST.
 
 use std::ops::Range;
+use std::str::FromStr;
 
 use itertools::Itertools;
 
@@ -12,7 +13,9 @@ use lexer::Token;
 use rules::SyntaxRule;
 
 use super::*;
-use crate::{grid::Grid, CodeResult, CoerceInto, Pos, RunError, RunErrorMsg, Span, Spanned};
+use crate::{
+    a1::TableRef, grid::Grid, CodeResult, CoerceInto, Pos, RunError, RunErrorMsg, Span, Spanned,
+};
 
 pub fn parse_formula(source: &str, pos: Pos) -> CodeResult<ast::Formula> {
     Ok(Formula {
@@ -94,6 +97,41 @@ pub fn replace_a1_notation(source: &str, pos: Pos) -> String {
     replace_cell_range_references(source, pos, replace_fn)
 }
 
+/// Replace all cell references in a formula with R1C1 notation, such as
+/// `R5C2` or `R[-1]C[2]`. Row and column ranges such as `A:A` are kept in A1
+/// notation.
+///
+/// # Example
+/// ```rust
+/// use quadratic_core::{formulas::a1_to_r1c1, Pos};
+///
+/// let pos = Pos { x: 2, y: 5 };
+/// let replaced = a1_to_r1c1("SUM(A1:B4, $B$5)", pos);
+/// assert_eq!(replaced, "SUM(R[-4]C[-1]:R[-1]C, R5C2)");
+/// ```
+pub fn a1_to_r1c1(source: &str, pos: Pos) -> String {
+    let replace_fn = |range_ref: RangeRef| match range_ref.is_unbounded(pos) {
+        true => range_ref.a1_string(pos),
+        false => range_ref.r1c1_string(),
+    };
+    replace_cell_range_references(source, pos, replace_fn)
+}
+
+/// Replace all cell references in a formula, including R1C1 notation, with A1
+/// notation.
+///
+/// # Example
+/// ```rust
+/// use quadratic_core::{formulas::r1c1_to_a1, Pos};
+///
+/// let pos = Pos { x: 2, y: 5 };
+/// let replaced = r1c1_to_a1("SUM(R[-4]C[-1]:R[-1]C, R5C2)", pos);
+/// assert_eq!(replaced, "SUM(A1:B4, $B$5)");
+/// ```
+pub fn r1c1_to_a1(source: &str, pos: Pos) -> String {
+    replace_internal_cell_references(source, pos)
+}
+
 /// Replace all cell references with internal cell references (RC notation) by
 /// applying the function `replace_x_fn` to X coordinates and `replace_y_fn` to
 /// Y coordinates.
@@ -133,6 +171,39 @@ pub fn replace_cell_references_with(
     })
 }
 
+/// Replace the sheet names of all cell references (RC notation) by applying
+/// the function `replace_fn` to each sheet name.
+pub fn replace_sheet_names(source: &str, pos: Pos, replace_fn: impl Fn(&str) -> String) -> String {
+    let replace_sheet = |sheet: Option<String>| sheet.map(|sheet| replace_fn(&sheet));
+    let replace_cell_ref = |cell_ref: CellRef| CellRef {
+        sheet: replace_sheet(cell_ref.sheet),
+        ..cell_ref
+    };
+
+    replace_cell_range_references(source, pos, |range_ref| {
+        match range_ref {
+            RangeRef::RowRange { start, end, sheet } => RangeRef::RowRange {
+                start,
+                end,
+                sheet: replace_sheet(sheet),
+            },
+            RangeRef::ColRange { start, end, sheet } => RangeRef::ColRange {
+                start,
+                end,
+                sheet: replace_sheet(sheet),
+            },
+            RangeRef::CellRange { start, end } => RangeRef::CellRange {
+                start: replace_cell_ref(start),
+                end: replace_cell_ref(end),
+            },
+            RangeRef::Cell { pos } => RangeRef::Cell {
+                pos: replace_cell_ref(pos),
+            },
+        }
+        .to_string()
+    })
+}
+
 fn replace_cell_range_references(
     source: &str,
     pos: Pos,
@@ -154,6 +225,28 @@ fn replace_cell_range_references(
     replaced
 }
 
+/// Replace all structured table references in a formula by applying the
+/// function `replace_fn` to each reference that can be parsed.
+pub fn replace_table_references(source: &str, replace_fn: impl Fn(TableRef) -> TableRef) -> String {
+    let mut replaced = source.to_string();
+
+    // replace in reverse order to preserve previous span references
+    lexer::tokenize(source)
+        .filter(|t| t.inner == Token::TableRef)
+        .collect_vec()
+        .into_iter()
+        .rev()
+        .for_each(|Spanned { span, .. }| {
+            let range: Range<usize> = span.into();
+            if let Ok(table_ref) = TableRef::from_str(&source[range.clone()]) {
+                let new_str = replace_fn(table_ref).to_string();
+                replaced.replace_range(range, &new_str);
+            }
+        });
+
+    replaced
+}
+
 /// Token parser used to assemble an AST.
 #[derive(Debug, Copy, Clone)]
 pub struct Parser<'a> {
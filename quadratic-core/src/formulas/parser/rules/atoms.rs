@@ -1,4 +1,5 @@
 use super::*;
+use crate::a1::TableRef;
 use std::str::FromStr;
 
 /// Matches a string literal.
@@ -74,7 +75,11 @@ impl SyntaxRule for SheetRefPrefix {
                 let name_without_bang = p.token_str().strip_suffix('!').ok_or_else(|| {
                     RunErrorMsg::InternalError("expected '!' in unquoted sheet reference".into())
                 })?;
-                Ok(name_without_bang.trim().to_string())
+                match name_without_bang.split_once(':') {
+                    // 3D reference, such as `Jan:Dec!`
+                    Some((first, last)) => Ok(format!("{}:{}", first.trim(), last.trim())),
+                    None => Ok(name_without_bang.trim().to_string()),
+                }
             }
             _ => p.expected(self),
         }
@@ -90,7 +95,12 @@ impl SyntaxRule for CellReference {
 
     fn prefix_matches(&self, mut p: Parser<'_>) -> bool {
         match p.next() {
-            Some(Token::CellRef | Token::InternalCellRef | Token::UnquotedSheetReference) => true,
+            Some(
+                Token::CellRef
+                | Token::InternalCellRef
+                | Token::R1C1CellRef
+                | Token::UnquotedSheetReference,
+            ) => true,
             Some(Token::StringLiteral) => p.peek_next() == Some(Token::SheetRefOp),
             _ => false,
         }
@@ -103,7 +113,8 @@ impl SyntaxRule for CellReference {
         p.next();
 
         let cell_ref = CellRef::parse_a1(p.token_str(), p.pos)
-            .or_else(|| CellRef::from_str(p.token_str()).ok());
+            .or_else(|| CellRef::from_str(p.token_str()).ok())
+            .or_else(|| CellRef::parse_r1c1(p.token_str()));
 
         cell_ref.map_or_else(
             || Err(RunErrorMsg::BadCellReference.with_span(p.span())),
@@ -118,6 +129,29 @@ impl SyntaxRule for CellReference {
     }
 }
 
+/// Matches a structured table reference.
+#[derive(Debug, Copy, Clone)]
+pub struct TableReference;
+impl_display!(for TableReference, "table reference such as 'Sales[Amount]'");
+impl SyntaxRule for TableReference {
+    type Output = Spanned<TableRef>;
+
+    fn prefix_matches(&self, mut p: Parser<'_>) -> bool {
+        p.next() == Some(Token::TableRef)
+    }
+    fn consume_match(&self, p: &mut Parser<'_>) -> CodeResult<Self::Output> {
+        if p.next() != Some(Token::TableRef) {
+            return p.expected(self);
+        }
+        let table_ref = TableRef::from_str(p.token_str())
+            .map_err(|_| RunErrorMsg::BadCellReference.with_span(p.span()))?;
+        Ok(Spanned {
+            span: p.span(),
+            inner: table_ref,
+        })
+    }
+}
+
 /// Matches a single cell reference or a cell range reference on its own, not as
 /// part of an expression.
 #[derive(Debug, Copy, Clone)]
@@ -99,9 +99,12 @@ impl SyntaxRule for TupleExpression {
     }
     fn consume_match(&self, p: &mut Parser<'_>) -> CodeResult<Self::Output> {
         let mut tmp_p = p.clone();
-        if tmp_p.next() == Some(Token::LParen) { // (
-            if tmp_p.parse(TupleExpression).is_ok() { // expression
-                if tmp_p.next() == Some(Token::ArgSep) { // ,
+        if tmp_p.next() == Some(Token::LParen) {
+            // (
+            if tmp_p.parse(TupleExpression).is_ok() {
+                // expression
+                if tmp_p.next() == Some(Token::ArgSep) {
+                    // ,
                     return p.parse(
                         List {
                             // In Excel, tuples can only contain cell ranges and tuples.
@@ -201,7 +204,9 @@ impl SyntaxRule for ExpressionWithPrecedence {
                 | Token::UnterminatedStringLiteral
                 | Token::NumericLiteral
                 | Token::CellRef
-                | Token::InternalCellRef => true,
+                | Token::InternalCellRef
+                | Token::R1C1CellRef
+                | Token::TableRef => true,
 
                 Token::Whitespace => false,
                 Token::Unknown => false,
@@ -219,6 +224,7 @@ impl SyntaxRule for ExpressionWithPrecedence {
                 [
                     FunctionCall.map(Some),
                     CellReferenceExpression.map(Some),
+                    TableReferenceExpression.map(Some),
                     StringLiteralExpression.map(Some),
                     NumericLiteral.map(Some),
                     ArrayLiteral.map(Some),
@@ -414,6 +420,21 @@ impl SyntaxRule for CellReferenceExpression {
     }
 }
 
+/// Matches a structured table reference.
+#[derive(Debug, Copy, Clone)]
+pub struct TableReferenceExpression;
+impl_display!(for TableReferenceExpression, "table reference such as 'Sales[Amount]'");
+impl SyntaxRule for TableReferenceExpression {
+    type Output = AstNode;
+
+    fn prefix_matches(&self, p: Parser<'_>) -> bool {
+        TableReference.prefix_matches(p)
+    }
+    fn consume_match(&self, p: &mut Parser<'_>) -> CodeResult<Self::Output> {
+        Ok(p.parse(TableReference)?.map(ast::AstNodeContents::TableRef))
+    }
+}
+
 /// Matches a pair of parentheses containing an expression.
 #[derive(Debug, Copy, Clone)]
 pub struct ParenExpression;
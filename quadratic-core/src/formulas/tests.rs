@@ -424,6 +424,98 @@ fn test_sheet_references() {
     );
 }
 
+#[test]
+#[parallel]
+fn test_3d_references() {
+    let mut g = Grid::new();
+    g.sheets_mut()[0].name = "Summary".into();
+    for (i, name) in ["Jan", "Feb", "Mar"].into_iter().enumerate() {
+        let sheet_id = g.add_sheet(None);
+        let sheet = g.try_sheet_mut(sheet_id).unwrap();
+        sheet.name = name.into();
+        let _ = sheet.set_cell_value(pos![B5], i as i64 + 1);
+        let _ = sheet.set_cell_value(pos![B6], 10);
+    }
+    let summary_id = g.sheets()[0].id;
+    let pos = Pos::ORIGIN.to_sheet_pos(summary_id);
+
+    assert_eq!("6", eval_to_string_at(&g, pos, "SUM(Jan:Mar!B5)"));
+    assert_eq!("5", eval_to_string_at(&g, pos, "SUM(Feb : Mar!B5)"));
+    assert_eq!("36", eval_to_string_at(&g, pos, "SUM(Jan:Mar!B5:B6)"));
+    assert_eq!("32", eval_to_string_at(&g, pos, "SUM(Mar:Jan!B6, Feb!B5)"));
+    assert_eq!("3", eval_to_string_at(&g, pos, "COUNT('Jan:Mar'!B5)"));
+    assert_eq!(
+        RunErrorMsg::BadCellReference,
+        eval_to_err(&g, "SUM(Jan:Apr!B5)").msg,
+    );
+
+    // only a 3D reference if both ends are existing sheets
+    let summary = g.try_sheet_mut(summary_id).unwrap();
+    let _ = summary.set_cell_value(pos![A1], 100);
+    let _ = summary.set_cell_value(pos![B5], 1000);
+    assert_eq!("1100", eval_to_string_at(&g, pos, "SUM(A1:Jan!B5)"));
+    assert_eq!("1100", eval_to_string_at(&g, pos, "SUM(A1 : Mar!B5)"));
+    let summary = g.try_sheet_mut(summary_id).unwrap();
+    let _ = summary.set_cell_value(pos![A1], CellValue::Blank);
+    let _ = summary.set_cell_value(pos![B5], CellValue::Blank);
+
+    // every covered sheet is tracked
+    let mut ctx = Ctx::new(&g, pos);
+    parse_formula("SUM(Jan:Mar!B5)", Pos::ORIGIN)
+        .unwrap()
+        .eval(&mut ctx);
+    for sheet in &g.sheets()[1..] {
+        let sheet_pos = pos![B5].to_sheet_pos(sheet.id);
+        assert!(ctx.cells_accessed.contains(sheet_pos));
+    }
+    assert!(!ctx
+        .cells_accessed
+        .contains(pos![B5].to_sheet_pos(summary_id)));
+
+    let replaced = replace_a1_notation("SUM(Jan:Mar!B5)", Pos::ORIGIN);
+    assert_eq!("SUM(\"Jan:Mar\"!R[5]C[1])", replaced);
+    let replaced = replace_sheet_names(&replaced, Pos::ORIGIN, |name| name.replace("Jan", "Feb"));
+    assert_eq!(
+        "SUM(\"Feb:Mar\"!B5)",
+        replace_internal_cell_references(&replaced, Pos::ORIGIN)
+    );
+}
+
+#[test]
+#[parallel]
+fn test_r1c1_references() {
+    let mut g = Grid::new();
+    let sheet = &mut g.sheets_mut()[0];
+    let _ = sheet.set_cell_value(pos![B5], 2);
+    let _ = sheet.set_cell_value(pos![C6], 3);
+
+    // formulas are evaluated at `Pos::ORIGIN`
+    assert_eq!("2", eval_to_string(&g, "R5C2"));
+    assert_eq!("5", eval_to_string(&g, "r[5]c[1] + R6C3"));
+    assert_eq!("5", eval_to_string(&g, "SUM(R5C2:R[6]C[2])"));
+    assert_eq!("5", eval_to_string(&g, "SUM(R5C[1]:R6C3)"));
+
+    let pos = pos![B5];
+    let a1 = "SUM(A1:B4, $B$5, 'Sheet 2'!C6, A:A)";
+    let r1c1 = "SUM(R[-4]C[-1]:R[-1]C, R5C2, \"Sheet 2\"!R[1]C[1], A:A)";
+    assert_eq!(r1c1, a1_to_r1c1(a1, pos));
+    assert_eq!(
+        "SUM(A1:B4, $B$5, \"Sheet 2\"!C6, A:A)",
+        r1c1_to_a1(r1c1, pos)
+    );
+
+    // `RC2` is an A1-style reference
+    assert_eq!("R[0]C2+R[0]C", a1_to_r1c1("$B5+B5", pos));
+    assert_eq!("$B5+B5", r1c1_to_a1("R[0]C2+R[0]C", pos));
+
+    // R1C1 is position-independent, so copies of a formula look the same
+    assert_eq!(a1_to_r1c1("A1+B1", pos![C1]), a1_to_r1c1("A2+B2", pos![C2]));
+    assert_eq!(
+        "R{5}C{2}+R[-1]C[2]",
+        replace_a1_notation("R5C2+R[-1]C[2]", Pos::ORIGIN)
+    );
+}
+
 #[test]
 fn test_cell_range_op_errors() {
     let g = Grid::new();
@@ -469,3 +561,48 @@ fn test_syntax_check_ok() {
     assert_check_syntax_succeeds(&g, "XLOOKUP(\"zebra\", A1:Z1, A4:Z6)");
     assert_check_syntax_succeeds(&g, "ABS(({1, 2; 3, 4}, A1:C10))");
 }
+
+#[test]
+#[parallel]
+fn test_table_references() {
+    use crate::{a1::TableColumns, grid::sheet::tables::Table, Rect};
+
+    let mut g = Grid::new();
+    let sheet_id = g.sheets()[0].id;
+    let sheet = g.try_sheet_mut(sheet_id).unwrap();
+    let _ = sheet.set_cell_value(pos![A1], "Item");
+    let _ = sheet.set_cell_value(pos![B1], "Amount");
+    let _ = sheet.set_cell_value(pos![B2], 10);
+    let _ = sheet.set_cell_value(pos![B3], 20);
+    let _ = sheet.set_cell_value(pos![B4], 30);
+    let table = Table::new(
+        "Sales",
+        Rect::test_a1("A1:B4"),
+        true,
+        vec!["Item".into(), "Amount".into()],
+    );
+    sheet.tables.set(sheet_id, table);
+
+    let pos = pos![D3].to_sheet_pos(sheet_id);
+    assert_eq!("60", eval_to_string_at(&g, pos, "SUM(Sales[Amount])"));
+    assert_eq!("20", eval_to_string_at(&g, pos, "Sales[@Amount]"));
+    assert_eq!(
+        "Amount",
+        eval_to_string_at(&g, pos, "Sales[[#Headers],[Amount]]")
+    );
+    assert_eq!("3", eval_to_string_at(&g, pos, "COUNT(Sales[Amount])"));
+    assert_eq!(
+        RunErrorMsg::BadCellReference,
+        eval_to_err(&g, "SUM(Missing[Amount])").msg,
+    );
+    assert_check_syntax_succeeds(&g, "SUM(Missing[Amount])");
+
+    let replaced = replace_table_references("SUM(Sales[Amount]) + A1", |mut table_ref| {
+        table_ref.table_name = "Revenue".into();
+        if let TableColumns::Column(column) = &mut table_ref.columns {
+            *column = "Total".into();
+        }
+        table_ref
+    });
+    assert_eq!("SUM(Revenue[Total]) + A1", replaced);
+}
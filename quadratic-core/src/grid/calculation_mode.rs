@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use super::{CodeCellLanguage, Grid};
+
+/// Controls whether code cells are computed when the cells they depend on
+/// change.  Code cells that aren't computed are marked stale until they're
+/// recalculated.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "js", derive(ts_rs::TS))]
+pub enum CalculationMode {
+    /// Dependent code cells are always computed.
+    #[default]
+    Automatic,
+
+    /// Dependent code cells are computed, except for connections.
+    AutomaticExceptConnections,
+
+    /// Dependent code cells are only computed when recalculated.
+    Manual,
+}
+
+impl CalculationMode {
+    /// Whether a dependent code cell in the language is computed when the
+    /// cells it depends on change.
+    pub fn computes(&self, language: &CodeCellLanguage) -> bool {
+        match self {
+            CalculationMode::Automatic => true,
+            CalculationMode::AutomaticExceptConnections => {
+                !matches!(language, CodeCellLanguage::Connection { .. })
+            }
+            CalculationMode::Manual => false,
+        }
+    }
+}
+
+impl Grid {
+    pub fn calculation_mode(&self) -> CalculationMode {
+        self.calculation_mode
+    }
+
+    pub fn set_calculation_mode(&mut self, calculation_mode: CalculationMode) {
+        self.calculation_mode = calculation_mode;
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::grid::ConnectionKind;
+
+    #[test]
+    fn test_computes() {
+        let connection = CodeCellLanguage::Connection {
+            kind: ConnectionKind::Postgres,
+            id: "id".into(),
+        };
+
+        assert!(CalculationMode::Automatic.computes(&CodeCellLanguage::Python));
+        assert!(CalculationMode::Automatic.computes(&connection));
+        assert!(CalculationMode::AutomaticExceptConnections.computes(&CodeCellLanguage::Formula));
+        assert!(!CalculationMode::AutomaticExceptConnections.computes(&connection));
+        assert!(!CalculationMode::Manual.computes(&CodeCellLanguage::Javascript));
+    }
+}
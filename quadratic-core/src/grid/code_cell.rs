@@ -2,6 +2,8 @@ use fancy_regex::Regex;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
+use crate::a1::{CellRefRange, TableRef};
+use crate::formulas;
 use crate::grid::CodeCellLanguage;
 use crate::{A1Selection, SheetNameIdMap};
 
@@ -103,6 +105,84 @@ impl CodeCellValue {
             })
             .to_string();
     }
+
+    /// Renames a table in the structured references of the code.
+    pub fn rename_table(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        default_sheet_id: &SheetId,
+        sheet_map: &SheetNameIdMap,
+    ) {
+        self.update_table_references(default_sheet_id, sheet_map, |table_ref| {
+            if table_ref.is_table(old_name) {
+                table_ref.table_name = new_name.to_string();
+            }
+        });
+    }
+
+    /// Renames a column of a table in the structured references of the code.
+    pub fn rename_table_column(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+        default_sheet_id: &SheetId,
+        sheet_map: &SheetNameIdMap,
+    ) {
+        self.update_table_references(default_sheet_id, sheet_map, |table_ref| {
+            if table_ref.is_table(table_name) {
+                table_ref.columns.rename_column(old_name, new_name);
+            }
+        });
+    }
+
+    /// Applies `update_fn` to all structured table references in the code.
+    /// References that are unchanged are left as written.
+    fn update_table_references(
+        &mut self,
+        default_sheet_id: &SheetId,
+        sheet_map: &SheetNameIdMap,
+        update_fn: impl Fn(&mut TableRef),
+    ) {
+        match self.language {
+            CodeCellLanguage::Formula => {
+                self.code = formulas::replace_table_references(&self.code, |mut table_ref| {
+                    update_fn(&mut table_ref);
+                    table_ref
+                });
+            }
+            CodeCellLanguage::Python | CodeCellLanguage::Javascript => {
+                self.code = Q_CELLS_A1_REGEX_COMPILED
+                    .replace_all(&self.code, |caps: &fancy_regex::Captures<'_>| {
+                        let full_match = &caps[0]; // Capture the entire match
+                        let a1_str = &caps[2]; // Capture the first argument which is inside quotes
+
+                        let Ok(mut a1_selection) =
+                            A1Selection::from_str(a1_str, default_sheet_id, sheet_map)
+                        else {
+                            return full_match.to_string();
+                        };
+                        let mut changed = false;
+                        for range in a1_selection.ranges.iter_mut() {
+                            if let CellRefRange::Table { range } = range {
+                                let old = range.clone();
+                                update_fn(range);
+                                changed |= *range != old;
+                            }
+                        }
+                        if !changed {
+                            return full_match.to_string();
+                        }
+                        let a1_str = a1_selection.to_string(Some(*default_sheet_id), sheet_map);
+                        // Replace only the first argument, keep the rest unchanged
+                        format!(r#"q.cells("{0}""#, a1_str)
+                    })
+                    .to_string();
+            }
+            _ => (),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -306,4 +386,54 @@ mod tests {
             "first_row_header=True failed"
         );
     }
+
+    #[test]
+    fn test_rename_table() {
+        let sheet_id = SheetId::new();
+        let sheet_map = SheetNameIdMap::new();
+
+        let mut code = CodeCellValue {
+            language: CodeCellLanguage::Python,
+            code: r#"q.cells('Sales[Amount]') + q.cells('A1:B2')"#.to_string(),
+        };
+        code.rename_table("sales", "Revenue", &sheet_id, &sheet_map);
+        assert_eq!(
+            code.code,
+            r#"q.cells("Revenue[Amount]") + q.cells('A1:B2')"#
+        );
+
+        let mut code = CodeCellValue {
+            language: CodeCellLanguage::Javascript,
+            code: r#"q.cells("Other[Amount]")"#.to_string(),
+        };
+        code.rename_table("Sales", "Revenue", &sheet_id, &sheet_map);
+        assert_eq!(code.code, r#"q.cells("Other[Amount]")"#);
+
+        let mut code = CodeCellValue {
+            language: CodeCellLanguage::Formula,
+            code: "SUM(Sales[Amount]) + Sales[@Price]".to_string(),
+        };
+        code.rename_table("Sales", "Revenue", &sheet_id, &sheet_map);
+        assert_eq!(code.code, "SUM(Revenue[Amount]) + Revenue[@Price]");
+    }
+
+    #[test]
+    fn test_rename_table_column() {
+        let sheet_id = SheetId::new();
+        let sheet_map = SheetNameIdMap::new();
+
+        let mut code = CodeCellValue {
+            language: CodeCellLanguage::Python,
+            code: r#"q.cells("Sales[[#Totals],[Amount]]")"#.to_string(),
+        };
+        code.rename_table_column("Sales", "amount", "Total", &sheet_id, &sheet_map);
+        assert_eq!(code.code, r#"q.cells("Sales[[#Totals],[Total]]")"#);
+
+        let mut code = CodeCellValue {
+            language: CodeCellLanguage::Formula,
+            code: "SUM(Sales[Amount], Other[Amount])".to_string(),
+        };
+        code.rename_table_column("Sales", "Amount", "Total", &sheet_id, &sheet_map);
+        assert_eq!(code.code, "SUM(Sales[Total], Other[Amount])");
+    }
 }
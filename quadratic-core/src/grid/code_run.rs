@@ -52,6 +52,8 @@ impl From<CodeRunOld> for CodeRun {
             line_number: old.line_number,
             output_type: old.output_type,
             last_modified: old.last_modified,
+            stale: false,
+            timing: None,
         }
     }
 }
@@ -83,6 +85,43 @@ pub struct CodeRun {
     pub output_type: Option<String>,
 
     pub last_modified: DateTime<Utc>,
+
+    /// The code run's inputs changed while calculation was paused, so its
+    /// result is out of date until it's recalculated.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+
+    /// How long the last run took and how much it read and returned.  Runs
+    /// saved before timing was recorded don't have this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<CodeRunTiming>,
+}
+
+/// Profiling information for the last run of a code cell.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "js", derive(ts_rs::TS))]
+pub struct CodeRunTiming {
+    /// Time from starting the run to receiving its result
+    pub duration_ms: u64,
+
+    /// Number of cells read from the grid during the run
+    pub cells_read: u64,
+
+    pub output_w: u32,
+    pub output_h: u32,
+
+    /// Timing reported by the connection service for connection cells
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionTiming>,
+}
+
+/// Timing reported by quadratic-connection in its `ELAPSED-*` headers.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "js", derive(ts_rs::TS))]
+pub struct ConnectionTiming {
+    pub connect_ms: u64,
+    pub query_ms: u64,
+    pub total_ms: u64,
 }
 
 impl CodeRun {
@@ -263,6 +302,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         assert_eq!(code_run.output_size(), ArraySize::_1X1);
         assert_eq!(
@@ -290,6 +331,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         assert_eq!(code_run.output_size().w.get(), 10);
         assert_eq!(code_run.output_size().h.get(), 11);
@@ -323,6 +366,8 @@ mod test {
             output_type: None,
             spill_error: true,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         assert_eq!(code_run.output_size().w.get(), 10);
         assert_eq!(code_run.output_size().h.get(), 11);
@@ -83,6 +83,9 @@ impl<T: Default + Clone + PartialEq + Debug> Contiguous2D<T> {
                 };
                 c.set_rect(start_col, start_row, end_col, end_row, value.clone());
             }
+            // table references are resolved by the sheet (see
+            // `Sheet::resolve_selection`) before the selection gets here
+            CellRefRange::Table { .. } => (),
         });
         c
     }
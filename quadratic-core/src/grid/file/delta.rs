@@ -0,0 +1,254 @@
+//! Delta checkpoints
+//!
+//! A delta checkpoint is a base snapshot (a regular .grid file) followed by
+//! chunks of operations that were applied after it.  Appending a chunk only
+//! copies bytes, so saving a batch of transactions doesn't need to import and
+//! export the whole grid.  Once the chunks grow too large, the file should be
+//! compacted into a new snapshot by importing and exporting it.
+//!
+//! Layout after the header: a segment for the base snapshot, then a segment
+//! for each chunk.  Each segment is the length of its data and its number of
+//! operations (both u64 little endian), followed by the data.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{import, FileVersion, HEADER_SERIALIZATION_FORMAT};
+use crate::compression::{add_header, deserialize, remove_header, serialize};
+use crate::controller::{
+    operations::operation::Operation, transaction::Transaction, GridController,
+};
+use crate::grid::Grid;
+
+pub static DELTA_VERSION: &str = "delta-1.0";
+
+const SEGMENT_HEADER_LEN: usize = 16;
+
+/// Operations applied to the grid after the base snapshot
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeltaChunk {
+    /// The sequence number of the last transaction in the chunk
+    pub sequence_num: u64,
+    pub operations: Vec<Operation>,
+}
+
+/// The size of the chunks in a delta checkpoint, used to decide when to compact
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DeltaSummary {
+    pub chunks: usize,
+    pub operations: u64,
+    pub bytes: usize,
+}
+
+struct Segment<'a> {
+    operations: u64,
+    data: &'a [u8],
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data
+        .get(offset..offset + 8)
+        .ok_or_else(|| anyhow!("Delta checkpoint is truncated"))?;
+
+    Ok(u64::from_le_bytes(bytes.try_into()?))
+}
+
+fn read_segments(data: &[u8]) -> Result<Vec<Segment<'_>>> {
+    let mut segments = vec![];
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let len = usize::try_from(read_u64(data, offset)?)
+            .map_err(|_| anyhow!("Delta checkpoint segment is too large"))?;
+        let operations = read_u64(data, offset + 8)?;
+        let start = offset + SEGMENT_HEADER_LEN;
+        let end = start
+            .checked_add(len)
+            .ok_or_else(|| anyhow!("Delta checkpoint segment is too large"))?;
+        let data = data
+            .get(start..end)
+            .ok_or_else(|| anyhow!("Delta checkpoint is truncated"))?;
+
+        segments.push(Segment { operations, data });
+        offset = end;
+    }
+
+    Ok(segments)
+}
+
+fn write_segment(output: &mut Vec<u8>, operations: u64, data: &[u8]) {
+    output.extend((data.len() as u64).to_le_bytes());
+    output.extend(operations.to_le_bytes());
+    output.extend(data);
+}
+
+/// Returns the data after the header if the file is a delta checkpoint
+fn delta_data(file: &[u8]) -> Option<&[u8]> {
+    let (header, data) = remove_header(file).ok()?;
+    let version = deserialize::<FileVersion>(&HEADER_SERIALIZATION_FORMAT, header).ok()?;
+
+    (version.version == DELTA_VERSION).then_some(data)
+}
+
+pub fn is_delta_file(file: &[u8]) -> bool {
+    delta_data(file).is_some()
+}
+
+/// Summarize the chunks of a delta checkpoint.  A snapshot has no chunks.
+pub fn delta_summary(file: &[u8]) -> Result<DeltaSummary> {
+    let Some(data) = delta_data(file) else {
+        return Ok(DeltaSummary::default());
+    };
+
+    let summary =
+        read_segments(data)?
+            .iter()
+            .skip(1)
+            .fold(DeltaSummary::default(), |summary, segment| DeltaSummary {
+                chunks: summary.chunks + 1,
+                operations: summary.operations + segment.operations,
+                bytes: summary.bytes + segment.data.len(),
+            });
+
+    Ok(summary)
+}
+
+/// Append a chunk of operations to a delta checkpoint.  A snapshot becomes
+/// the base of a new delta checkpoint.
+pub fn append_delta(file: Vec<u8>, chunk: &DeltaChunk) -> Result<Vec<u8>> {
+    let data = Transaction::serialize_and_compress(chunk)?;
+    let operations = chunk.operations.len() as u64;
+
+    let mut output = if is_delta_file(&file) {
+        file
+    } else {
+        let version = FileVersion {
+            version: DELTA_VERSION.into(),
+        };
+        let header = serialize(&HEADER_SERIALIZATION_FORMAT, &version)?;
+        let mut output = add_header(header, vec![])?;
+        write_segment(&mut output, 0, &file);
+        output
+    };
+
+    write_segment(&mut output, operations, &data);
+
+    Ok(output)
+}
+
+/// Import the base snapshot of a delta checkpoint and apply its chunks
+pub(crate) fn import_delta(data: &[u8]) -> Result<Grid> {
+    let segments = read_segments(data)?;
+    let (base, chunks) = segments
+        .split_first()
+        .ok_or_else(|| anyhow!("Delta checkpoint has no base snapshot"))?;
+
+    let grid = import(base.data.to_vec())?;
+    let mut gc = GridController::from_grid(grid, 0);
+
+    for segment in chunks {
+        let chunk = Transaction::decompress_and_deserialize::<DeltaChunk>(segment.data)?;
+        gc.server_apply_transaction(chunk.operations, None);
+    }
+
+    Ok(gc.into_grid())
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::grid::file::export;
+    use crate::{CellValue, Pos, SheetPos};
+
+    fn set_value(gc: &mut GridController, x: i64, value: &str) -> DeltaChunk {
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(SheetPos { x, y: 1, sheet_id }, value.into(), None);
+
+        DeltaChunk {
+            sequence_num: x as u64,
+            operations: gc.last_transaction().unwrap().operations.clone(),
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn imports_a_snapshot_with_deltas() {
+        let mut gc = GridController::test();
+        let snapshot = export(gc.grid().clone()).unwrap();
+        let first = set_value(&mut gc, 1, "a");
+        let second = set_value(&mut gc, 2, "b");
+
+        let file = append_delta(snapshot.clone(), &first).unwrap();
+        let file = append_delta(file, &second).unwrap();
+
+        assert!(!is_delta_file(&snapshot));
+        assert!(is_delta_file(&file));
+
+        let grid = import(file.clone()).unwrap();
+        let sheet = &grid.sheets()[0];
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("a".into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 2, y: 1 }),
+            Some(CellValue::Text("b".into()))
+        );
+
+        // compacting the checkpoint results in the same grid
+        let compacted = export(grid.clone()).unwrap();
+        assert!(!is_delta_file(&compacted));
+        assert_eq!(import(compacted).unwrap(), grid);
+    }
+
+    #[test]
+    #[parallel]
+    fn summarizes_deltas() {
+        let mut gc = GridController::test();
+        let snapshot = export(gc.grid().clone()).unwrap();
+        assert_eq!(delta_summary(&snapshot).unwrap(), DeltaSummary::default());
+
+        let chunk = set_value(&mut gc, 1, "a");
+        let file = append_delta(snapshot.clone(), &chunk).unwrap();
+        let summary = delta_summary(&file).unwrap();
+
+        assert_eq!(summary.chunks, 1);
+        assert_eq!(summary.operations, chunk.operations.len() as u64);
+        assert_eq!(
+            summary.bytes,
+            Transaction::serialize_and_compress(&chunk).unwrap().len()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_a_truncated_delta() {
+        let mut gc = GridController::test();
+        let snapshot = export(gc.grid().clone()).unwrap();
+        let chunk = set_value(&mut gc, 1, "a");
+        let mut file = append_delta(snapshot, &chunk).unwrap();
+        file.truncate(file.len() - 1);
+
+        assert!(import(file.clone()).is_err());
+        assert!(delta_summary(&file).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_an_oversized_segment_length() {
+        let mut gc = GridController::test();
+        let snapshot = export(gc.grid().clone()).unwrap();
+        let chunk = set_value(&mut gc, 1, "a");
+        let mut file = append_delta(snapshot, &chunk).unwrap();
+
+        // overwrite the segment's length so that start + len overflows
+        let bytes = Transaction::serialize_and_compress(&chunk).unwrap().len();
+        let offset = file.len() - bytes - SEGMENT_HEADER_LEN;
+        file[offset..offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(import(file.clone()).is_err());
+        assert!(delta_summary(&file).is_err());
+    }
+}
@@ -15,6 +15,7 @@ use std::fmt::Debug;
 use std::str;
 pub use v1_7_1::GridSchema as current;
 
+pub mod delta;
 mod migrate_code_cell_references;
 pub mod serialize;
 pub mod sheet_schema;
@@ -93,7 +94,7 @@ impl GridFile {
 }
 
 /// Imports a file. We check if the first character is `{` to determine if it is
-/// a JSON file.
+/// a JSON file.  Delta checkpoints are imported with their operations applied.
 pub fn import(file_contents: Vec<u8>) -> Result<Grid> {
     if file_contents.first() == Some(&b'{') {
         import_json(String::from_utf8(file_contents)?)
@@ -107,6 +108,12 @@ fn import_binary(file_contents: Vec<u8>) -> Result<Grid> {
     let (header, data) = remove_header(&file_contents)?;
 
     let file_version = deserialize::<FileVersion>(&HEADER_SERIALIZATION_FORMAT, header)?;
+
+    // a base snapshot with operations appended, see delta.rs
+    if file_version.version == delta::DELTA_VERSION {
+        return delta::import_delta(data);
+    }
+
     let mut check_for_negative_offsets = false;
     let mut grid = match file_version.version.as_str() {
         "1.6" => {
@@ -6,13 +6,14 @@ use indexmap::IndexMap;
 use itertools::Itertools;
 
 use crate::{
-    grid::{CellsAccessed, CodeRun, CodeRunResult, SheetId},
+    grid::{CellsAccessed, CodeRun, CodeRunResult, CodeRunTiming, ConnectionTiming, SheetId},
     CellRefCoord, CellRefRange, CellRefRangeEnd, Pos, RefRangeBounds, Value,
 };
 
 use super::{
     cell_value::{export_cell_value, import_cell_value},
     current,
+    selection::{export_table_ref, import_table_ref},
 };
 
 fn import_cell_ref_coord(coord: current::CellRefCoordSchema) -> CellRefCoord {
@@ -36,6 +37,9 @@ fn import_cell_ref_range(range: current::CellRefRangeSchema) -> CellRefRange {
                 },
             },
         },
+        current::CellRefRangeSchema::Table(table_ref) => CellRefRange::Table {
+            range: import_table_ref(table_ref),
+        },
     }
 }
 
@@ -55,6 +59,20 @@ fn import_cells_accessed(
     Ok(imported_cells)
 }
 
+fn import_code_run_timing(timing: current::CodeRunTimingSchema) -> CodeRunTiming {
+    CodeRunTiming {
+        duration_ms: timing.duration_ms,
+        cells_read: timing.cells_read,
+        output_w: timing.output_w,
+        output_h: timing.output_h,
+        connection: timing.connection.map(|connection| ConnectionTiming {
+            connect_ms: connection.connect_ms,
+            query_ms: connection.query_ms,
+            total_ms: connection.total_ms,
+        }),
+    }
+}
+
 pub(crate) fn import_code_cell_builder(
     code_runs: current::CodeRunsSchema,
 ) -> Result<IndexMap<Pos, CodeRun>> {
@@ -94,6 +112,8 @@ pub(crate) fn import_code_cell_builder(
                 return_type: code_run.return_type,
                 line_number: code_run.line_number,
                 output_type: code_run.output_type,
+                stale: code_run.stale,
+                timing: code_run.timing.map(import_code_run_timing),
             },
         );
     }
@@ -121,6 +141,9 @@ fn export_cell_ref_range(range: CellRefRange) -> current::CellRefRangeSchema {
                 },
             })
         }
+        CellRefRange::Table { range } => {
+            current::CellRefRangeSchema::Table(export_table_ref(range))
+        }
     }
 }
 
@@ -139,6 +162,22 @@ fn export_cells_accessed(
         .collect()
 }
 
+fn export_code_run_timing(timing: CodeRunTiming) -> current::CodeRunTimingSchema {
+    current::CodeRunTimingSchema {
+        duration_ms: timing.duration_ms,
+        cells_read: timing.cells_read,
+        output_w: timing.output_w,
+        output_h: timing.output_h,
+        connection: timing
+            .connection
+            .map(|connection| current::ConnectionTimingSchema {
+                connect_ms: connection.connect_ms,
+                query_ms: connection.query_ms,
+                total_ms: connection.total_ms,
+            }),
+    }
+}
+
 pub(crate) fn export_rows_code_runs(code_runs: IndexMap<Pos, CodeRun>) -> current::CodeRunsSchema {
     code_runs
         .into_iter()
@@ -185,6 +224,8 @@ pub(crate) fn export_rows_code_runs(code_runs: IndexMap<Pos, CodeRun>) -> curren
                     return_type: code_run.return_type,
                     line_number: code_run.line_number,
                     output_type: code_run.output_type,
+                    stale: code_run.stale,
+                    timing: code_run.timing.map(export_code_run_timing),
                 },
             )
         })
@@ -236,6 +277,8 @@ mod tests {
                 return_type: Some("string".to_string()),
                 line_number: Some(1),
                 output_type: Some("text".to_string()),
+                stale: false,
+                timing: None,
             },
         )];
 
@@ -263,6 +306,8 @@ mod tests {
                     return_type: Some("number".to_string()),
                     line_number: Some(1),
                     output_type: Some("number".to_string()),
+                    stale: false,
+                    timing: None,
                 },
             ),
             (
@@ -280,6 +325,8 @@ mod tests {
                     return_type: Some("string".to_string()),
                     line_number: Some(1),
                     output_type: Some("text".to_string()),
+                    stale: false,
+                    timing: None,
                 },
             ),
         ];
@@ -315,6 +362,8 @@ mod tests {
                 return_type: Some("string".to_string()),
                 line_number: Some(1),
                 output_type: Some("text".to_string()),
+                stale: false,
+                timing: None,
             },
         );
 
@@ -341,6 +390,8 @@ mod tests {
                 return_type: Some("number".to_string()),
                 line_number: Some(1),
                 output_type: Some("number".to_string()),
+                stale: false,
+                timing: None,
             },
         );
         code_runs.insert(
@@ -358,6 +409,8 @@ mod tests {
                 return_type: Some("string".to_string()),
                 line_number: Some(1),
                 output_type: Some("text".to_string()),
+                stale: false,
+                timing: None,
             },
         );
 
@@ -387,6 +440,18 @@ mod tests {
                 return_type: Some("number".to_string()),
                 line_number: Some(1),
                 output_type: Some("number".to_string()),
+                stale: false,
+                timing: Some(current::CodeRunTimingSchema {
+                    duration_ms: 120,
+                    cells_read: 4,
+                    output_w: 1,
+                    output_h: 1,
+                    connection: Some(current::ConnectionTimingSchema {
+                        connect_ms: 10,
+                        query_ms: 100,
+                        total_ms: 115,
+                    }),
+                }),
             },
         )];
 
@@ -396,6 +461,7 @@ mod tests {
         assert_eq!(original_code_runs.len(), exported.len());
         assert_eq!(original_code_runs[0].0.x, exported[0].0.x);
         assert_eq!(original_code_runs[0].0.y, exported[0].0.y);
+        assert_eq!(original_code_runs[0].1.timing, exported[0].1.timing);
         // Add more detailed comparisons here for other fields
     }
 }
@@ -4,7 +4,7 @@ use anyhow::Result;
 use sheets::{export_sheet, import_sheet};
 
 pub use crate::grid::file::v1_7_1 as current;
-use crate::grid::Grid;
+use crate::grid::{CalculationMode, Grid};
 
 use super::CURRENT_VERSION;
 
@@ -17,6 +17,7 @@ pub(crate) mod formats;
 pub(crate) mod row_resizes;
 pub(crate) mod selection;
 pub mod sheets;
+pub(crate) mod tables;
 pub(crate) mod validations;
 
 pub fn import(file: current::GridSchema) -> Result<Grid> {
@@ -26,12 +27,34 @@ pub fn import(file: current::GridSchema) -> Result<Grid> {
             .into_iter()
             .map(import_sheet)
             .collect::<Result<_>>()?,
+        calculation_mode: import_calculation_mode(file.calculation_mode),
     })
 }
 
 pub fn export(grid: Grid) -> Result<current::GridSchema> {
     Ok(current::GridSchema {
         version: CURRENT_VERSION.into(),
+        calculation_mode: export_calculation_mode(grid.calculation_mode),
         sheets: grid.sheets.into_iter().map(export_sheet).collect(),
     })
 }
+
+fn import_calculation_mode(calculation_mode: current::CalculationModeSchema) -> CalculationMode {
+    match calculation_mode {
+        current::CalculationModeSchema::Automatic => CalculationMode::Automatic,
+        current::CalculationModeSchema::AutomaticExceptConnections => {
+            CalculationMode::AutomaticExceptConnections
+        }
+        current::CalculationModeSchema::Manual => CalculationMode::Manual,
+    }
+}
+
+fn export_calculation_mode(calculation_mode: CalculationMode) -> current::CalculationModeSchema {
+    match calculation_mode {
+        CalculationMode::Automatic => current::CalculationModeSchema::Automatic,
+        CalculationMode::AutomaticExceptConnections => {
+            current::CalculationModeSchema::AutomaticExceptConnections
+        }
+        CalculationMode::Manual => current::CalculationModeSchema::Manual,
+    }
+}
@@ -2,6 +2,7 @@ use std::str::FromStr;
 
 use crate::{
     grid::SheetId, A1Selection, CellRefCoord, CellRefRange, CellRefRangeEnd, Pos, RefRangeBounds,
+    TableColumns, TableRef, TableRows,
 };
 
 use super::current;
@@ -13,6 +14,26 @@ fn import_cell_ref_coord(coord: current::CellRefCoordSchema) -> CellRefCoord {
     }
 }
 
+pub(crate) fn import_table_ref(table_ref: current::TableRefSchema) -> TableRef {
+    TableRef {
+        table_name: table_ref.table_name,
+        rows: match table_ref.rows {
+            current::TableRowsSchema::Data => TableRows::Data,
+            current::TableRowsSchema::Headers => TableRows::Headers,
+            current::TableRowsSchema::Totals => TableRows::Totals,
+            current::TableRowsSchema::All => TableRows::All,
+            current::TableRowsSchema::ThisRow => TableRows::ThisRow,
+        },
+        columns: match table_ref.columns {
+            current::TableColumnsSchema::All => TableColumns::All,
+            current::TableColumnsSchema::Column(column) => TableColumns::Column(column),
+            current::TableColumnsSchema::ColumnRange { start, end } => {
+                TableColumns::ColumnRange { start, end }
+            }
+        },
+    }
+}
+
 fn import_cell_ref_range(range: current::CellRefRangeSchema) -> CellRefRange {
     match range {
         current::CellRefRangeSchema::Sheet(range) => CellRefRange::Sheet {
@@ -27,6 +48,9 @@ fn import_cell_ref_range(range: current::CellRefRangeSchema) -> CellRefRange {
                 },
             },
         },
+        current::CellRefRangeSchema::Table(table_ref) => CellRefRange::Table {
+            range: import_table_ref(table_ref),
+        },
     }
 }
 
@@ -34,6 +58,9 @@ pub fn import_selection(selection: current::A1SelectionSchema) -> A1Selection {
     A1Selection {
         // todo: handle error more gracefully
         sheet_id: SheetId::from_str(&selection.sheet_id.to_string()).unwrap(),
+        last_sheet_id: selection
+            .last_sheet_id
+            .and_then(|id| SheetId::from_str(&id.to_string()).ok()),
         cursor: Pos {
             x: selection.cursor.x,
             y: selection.cursor.y,
@@ -53,6 +80,26 @@ fn export_cell_ref_coord(coord: CellRefCoord) -> current::CellRefCoordSchema {
     }
 }
 
+pub(crate) fn export_table_ref(table_ref: TableRef) -> current::TableRefSchema {
+    current::TableRefSchema {
+        table_name: table_ref.table_name,
+        rows: match table_ref.rows {
+            TableRows::Data => current::TableRowsSchema::Data,
+            TableRows::Headers => current::TableRowsSchema::Headers,
+            TableRows::Totals => current::TableRowsSchema::Totals,
+            TableRows::All => current::TableRowsSchema::All,
+            TableRows::ThisRow => current::TableRowsSchema::ThisRow,
+        },
+        columns: match table_ref.columns {
+            TableColumns::All => current::TableColumnsSchema::All,
+            TableColumns::Column(column) => current::TableColumnsSchema::Column(column),
+            TableColumns::ColumnRange { start, end } => {
+                current::TableColumnsSchema::ColumnRange { start, end }
+            }
+        },
+    }
+}
+
 fn export_cell_ref_range(range: CellRefRange) -> current::CellRefRangeSchema {
     match range {
         CellRefRange::Sheet { range } => {
@@ -67,12 +114,16 @@ fn export_cell_ref_range(range: CellRefRange) -> current::CellRefRangeSchema {
                 },
             })
         }
+        CellRefRange::Table { range } => {
+            current::CellRefRangeSchema::Table(export_table_ref(range))
+        }
     }
 }
 
 pub fn export_selection(selection: A1Selection) -> current::A1SelectionSchema {
     current::A1SelectionSchema {
         sheet_id: selection.sheet_id.to_string().into(),
+        last_sheet_id: selection.last_sheet_id.map(|id| id.to_string().into()),
         cursor: current::PosSchema {
             x: selection.cursor.x,
             y: selection.cursor.y,
@@ -94,7 +145,8 @@ mod tests {
     #[test]
     #[parallel]
     fn import_export_selection() {
-        let selection = A1Selection::test_a1("A2,C4:E6,G8:I10,1:3,D:E");
+        let selection =
+            A1Selection::test_a1("A2,C4:E6,G8:I10,1:3,D:E,Sales[[#Totals],[Amount]],Sales[@Price]");
         let imported = import_selection(export_selection(selection.clone()));
         assert_eq!(selection, imported);
     }
@@ -14,6 +14,7 @@ use super::{
     current,
     formats::{export_formats, import_formats},
     row_resizes::{export_rows_size, import_rows_resize},
+    tables::{export_tables, import_tables},
     validations::{export_validations, import_validations},
 };
 
@@ -26,14 +27,17 @@ pub fn import_sheet(sheet: current::SheetSchema) -> Result<Sheet> {
         offsets: SheetOffsets::import(sheet.offsets),
         rows_resize: import_rows_resize(sheet.rows_resize),
         validations: import_validations(sheet.validations),
+        tables: import_tables(sheet.tables),
         borders: import_borders(sheet.borders),
         formats: import_formats(sheet.formats),
         code_runs: import_code_cell_builder(sheet.code_runs)?,
         columns: import_column_builder(sheet.columns)?,
         format_bounds: GridBounds::Empty,
         data_bounds: GridBounds::Empty,
+        dependency_index: Default::default(),
     };
     new_sheet.recalculate_bounds();
+    new_sheet.rebuild_dependency_index();
     Ok(new_sheet)
 }
 
@@ -48,6 +52,7 @@ pub(crate) fn export_sheet(sheet: Sheet) -> current::SheetSchema {
         offsets: sheet.offsets.export(),
         rows_resize: export_rows_size(sheet.rows_resize),
         validations: export_validations(sheet.validations),
+        tables: export_tables(sheet.tables),
         borders: export_borders(sheet.borders),
         formats: export_formats(sheet.formats),
         code_runs: export_rows_code_runs(sheet.code_runs),
@@ -0,0 +1,87 @@
+use crate::grid::sheet::tables::{Table, TableColumn, Tables};
+use crate::{Pos, Rect};
+
+use super::current;
+
+pub fn import_tables(tables: current::TablesSchema) -> Tables {
+    Tables {
+        tables: tables
+            .tables
+            .into_iter()
+            .map(|table| Table {
+                id: table.id,
+                name: table.name,
+                bounds: Rect {
+                    min: Pos {
+                        x: table.bounds.min.x,
+                        y: table.bounds.min.y,
+                    },
+                    max: Pos {
+                        x: table.bounds.max.x,
+                        y: table.bounds.max.y,
+                    },
+                },
+                show_header: table.show_header,
+                columns: table
+                    .columns
+                    .into_iter()
+                    .map(|column| TableColumn { name: column.name })
+                    .collect(),
+                show_totals: table.show_totals,
+            })
+            .collect(),
+    }
+}
+
+pub fn export_tables(tables: Tables) -> current::TablesSchema {
+    current::TablesSchema {
+        tables: tables
+            .tables
+            .into_iter()
+            .map(|table| current::TableSchema {
+                id: table.id,
+                name: table.name,
+                bounds: current::RectSchema {
+                    min: current::PosSchema {
+                        x: table.bounds.min.x,
+                        y: table.bounds.min.y,
+                    },
+                    max: current::PosSchema {
+                        x: table.bounds.max.x,
+                        y: table.bounds.max.y,
+                    },
+                },
+                show_header: table.show_header,
+                columns: table
+                    .columns
+                    .into_iter()
+                    .map(|column| current::TableColumnSchema { name: column.name })
+                    .collect(),
+                show_totals: table.show_totals,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+    use crate::grid::SheetId;
+
+    #[test]
+    fn import_export_tables() {
+        let mut tables = Tables::default();
+        let mut table = Table::new(
+            "Sales",
+            Rect::new(2, 3, 4, 10),
+            true,
+            vec!["Region".into(), "Amount".into()],
+        );
+        table.show_totals = true;
+        tables.set(SheetId::TEST, table);
+
+        let imported = import_tables(export_tables(tables.clone()));
+        assert_eq!(imported, tables);
+    }
+}
@@ -100,6 +100,7 @@ pub fn shift_negative_offsets(grid: &mut Grid) -> HashMap<String, (i64, i64)> {
                     .collect();
             }
         }
+        sheet.rebuild_dependency_index();
     }
 
     // remove the import offset from the formats and borders
@@ -186,6 +186,11 @@ impl RunError {
                 crate::RunErrorMsg::IndexOutOfBounds => RunErrorMsg::IndexOutOfBounds,
                 crate::RunErrorMsg::NoMatch => RunErrorMsg::NoMatch,
                 crate::RunErrorMsg::InvalidArgument => RunErrorMsg::InvalidArgument,
+
+                // v1.5 predates connection and timeout errors
+                crate::RunErrorMsg::QueryCancelled
+                | crate::RunErrorMsg::QueryTimeout
+                | crate::RunErrorMsg::CodeRunTimeout => RunErrorMsg::UnknownError,
             },
         }
     }
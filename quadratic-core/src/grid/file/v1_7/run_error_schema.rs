@@ -83,6 +83,11 @@ pub enum RunErrorMsgSchema {
     IndexOutOfBounds,
     NoMatch,
     InvalidArgument,
+
+    // Connection errors
+    QueryCancelled,
+    QueryTimeout,
+    CodeRunTimeout,
 }
 
 // todo: There's probably a better way to do the From/Into between the types.
@@ -186,6 +191,10 @@ impl RunErrorSchema {
                 crate::RunErrorMsg::IndexOutOfBounds => RunErrorMsgSchema::IndexOutOfBounds,
                 crate::RunErrorMsg::NoMatch => RunErrorMsgSchema::NoMatch,
                 crate::RunErrorMsg::InvalidArgument => RunErrorMsgSchema::InvalidArgument,
+
+                crate::RunErrorMsg::QueryCancelled => RunErrorMsgSchema::QueryCancelled,
+                crate::RunErrorMsg::QueryTimeout => RunErrorMsgSchema::QueryTimeout,
+                crate::RunErrorMsg::CodeRunTimeout => RunErrorMsgSchema::CodeRunTimeout,
             },
         }
     }
@@ -295,6 +304,11 @@ impl From<RunErrorSchema> for crate::RunError {
                 RunErrorMsgSchema::IndexOutOfBounds => crate::RunErrorMsg::IndexOutOfBounds,
                 RunErrorMsgSchema::NoMatch => crate::RunErrorMsg::NoMatch,
                 RunErrorMsgSchema::InvalidArgument => crate::RunErrorMsg::InvalidArgument,
+
+                // Connection errors
+                RunErrorMsgSchema::QueryCancelled => crate::RunErrorMsg::QueryCancelled,
+                RunErrorMsgSchema::QueryTimeout => crate::RunErrorMsg::QueryTimeout,
+                RunErrorMsgSchema::CodeRunTimeout => crate::RunErrorMsg::CodeRunTimeout,
             },
         }
     }
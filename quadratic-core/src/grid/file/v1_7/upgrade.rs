@@ -62,6 +62,8 @@ fn upgrade_code_run(code_run: current::CodeRunSchema) -> v1_7_1::CodeRunSchema {
         output_type: code_run.output_type,
         spill_error: code_run.spill_error,
         last_modified: code_run.last_modified,
+        stale: false,
+        timing: None,
     }
 }
 
@@ -218,6 +220,7 @@ fn upgrade_selection(selection: current::SelectionSchema) -> v1_7_1::A1Selection
 
     v1_7_1::A1SelectionSchema {
         sheet_id: v1_7_1::IdSchema::from(selection.sheet_id.to_string()),
+        last_sheet_id: None,
         cursor,
         ranges,
     }
@@ -648,6 +651,7 @@ pub fn upgrade_sheet(sheet: current::SheetSchema) -> v1_7_1::SheetSchema {
         offsets,
         rows_resize,
         validations: upgrade_validations(validations),
+        tables: v1_7_1::TablesSchema::default(),
         borders: upgrade_borders(borders),
         formats,
         code_runs: upgrade_code_runs(code_runs),
@@ -659,6 +663,7 @@ pub fn upgrade(grid: current::GridSchema) -> Result<v1_7_1::GridSchema> {
     let new_grid = v1_7_1::GridSchema {
         version: "1.7.1".to_string(),
         sheets: grid.sheets.into_iter().map(upgrade_sheet).collect(),
+        calculation_mode: v1_7_1::CalculationModeSchema::Automatic,
     };
     Ok(new_grid)
 }
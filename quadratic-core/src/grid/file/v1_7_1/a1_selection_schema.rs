@@ -7,6 +7,8 @@ use super::PosSchema;
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct A1SelectionSchema {
     pub sheet_id: v1_7_1::IdSchema,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sheet_id: Option<v1_7_1::IdSchema>,
     pub cursor: PosSchema,
     pub ranges: Vec<CellRefRangeSchema>,
 }
@@ -14,6 +16,30 @@ pub struct A1SelectionSchema {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CellRefRangeSchema {
     Sheet(RefRangeBoundsSchema),
+    Table(TableRefSchema),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableRefSchema {
+    pub table_name: String,
+    pub rows: TableRowsSchema,
+    pub columns: TableColumnsSchema,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TableRowsSchema {
+    Data,
+    Headers,
+    Totals,
+    All,
+    ThisRow,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TableColumnsSchema {
+    All,
+    Column(String),
+    ColumnRange { start: String, end: String },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -35,4 +35,28 @@ pub struct CodeRunSchema {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_modified: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<CodeRunTimingSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeRunTimingSchema {
+    pub duration_ms: u64,
+    pub cells_read: u64,
+    pub output_w: u32,
+    pub output_h: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionTimingSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionTimingSchema {
+    pub connect_ms: u64,
+    pub query_ms: u64,
+    pub total_ms: u64,
 }
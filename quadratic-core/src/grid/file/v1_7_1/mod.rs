@@ -3,6 +3,7 @@ mod borders_a1_schema;
 mod cells_accessed_schema;
 mod contiguous_2d_schema;
 mod sheet_formatting_schema;
+mod tables_schema;
 mod validations_schema;
 
 pub use a1_selection_schema::*;
@@ -10,6 +11,7 @@ pub use borders_a1_schema::*;
 pub use cells_accessed_schema::*;
 pub use contiguous_2d_schema::*;
 pub use sheet_formatting_schema::*;
+pub use tables_schema::*;
 pub use validations_schema::*;
 
 use crate::grid::file::v1_7::schema as v1_7;
@@ -73,6 +75,8 @@ pub struct SheetSchema {
     pub order: String,
     pub offsets: OffsetsSchema,
     pub validations: ValidationsSchema,
+    #[serde(default)]
+    pub tables: TablesSchema,
     pub rows_resize: RowsResizeSchema,
     pub borders: BordersSchema,
     pub formats: SheetFormattingSchema,
@@ -80,8 +84,19 @@ pub struct SheetSchema {
     pub columns: ColumnsSchema,
 }
 
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub enum CalculationModeSchema {
+    #[default]
+    Automatic,
+    AutomaticExceptConnections,
+    Manual,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct GridSchema {
     pub version: String,
     pub sheets: Vec<SheetSchema>,
+
+    #[serde(default)]
+    pub calculation_mode: CalculationModeSchema,
 }
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::RectSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableColumnSchema {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub id: Uuid,
+    pub name: String,
+    pub bounds: RectSchema,
+    pub show_header: bool,
+    pub columns: Vec<TableColumnSchema>,
+    pub show_totals: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct TablesSchema {
+    pub tables: Vec<TableSchema>,
+}
@@ -8,7 +8,7 @@ use super::cells_accessed::JsCellsAccessed;
 use super::formats::Format;
 use super::formatting::{CellAlign, CellVerticalAlign, CellWrap};
 use super::sheet::validations::validation::ValidationStyle;
-use super::{CodeCellLanguage, NumericFormat};
+use super::{CodeCellLanguage, CodeRunTiming, NumericFormat};
 use crate::Pos;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
@@ -198,6 +198,15 @@ pub struct JsCodeCell {
     pub cells_accessed: Option<Vec<JsCellsAccessed>>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct JsCodeRunTiming {
+    pub sheet_id: String,
+    pub x: i64,
+    pub y: i64,
+    pub language: CodeCellLanguage,
+    pub timing: CodeRunTiming,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 pub struct JsRenderCodeCell {
     pub x: i32,
@@ -207,6 +216,7 @@ pub struct JsRenderCodeCell {
     pub language: CodeCellLanguage,
     pub state: JsRenderCodeCellState,
     pub spill_error: Option<Vec<Pos>>,
+    pub stale: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
@@ -290,6 +300,16 @@ pub struct JsSummarizeSelectionResult {
     pub average: Option<f64>,
 }
 
+/// Result of a goal seek. If no solution was found, `input_value` is the input
+/// that came closest to the target.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct JsGoalSeekResult {
+    pub found: bool,
+    pub input_value: f64,
+    pub result_value: Option<f64>,
+    pub iterations: u32,
+}
+
 #[cfg(test)]
 mod test {
     use serial_test::parallel;
@@ -1,4 +1,5 @@
 pub use bounds::GridBounds;
+pub use calculation_mode::CalculationMode;
 pub use cells_accessed::*;
 pub use code_cell::*;
 pub use code_run::*;
@@ -24,6 +25,7 @@ use crate::{Array, Pos};
 
 mod block;
 mod bounds;
+mod calculation_mode;
 mod cells_accessed;
 mod code_cell;
 mod code_run;
@@ -46,6 +48,9 @@ pub mod sheets;
 #[cfg_attr(feature = "js", wasm_bindgen)]
 pub struct Grid {
     sheets: Vec<Sheet>,
+
+    #[serde(default)]
+    calculation_mode: CalculationMode,
 }
 impl Default for Grid {
     fn default() -> Self {
@@ -59,7 +64,10 @@ impl Grid {
         ret
     }
     pub fn new_blank() -> Self {
-        Grid { sheets: vec![] }
+        Grid {
+            sheets: vec![],
+            calculation_mode: CalculationMode::default(),
+        }
     }
 
     /// Creates a grid for testing.
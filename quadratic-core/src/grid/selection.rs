@@ -1,29 +1,63 @@
-use crate::{controller::GridController, A1Error, A1Selection};
+use crate::{controller::GridController, A1Error, A1Selection, Pos};
 
 use super::SheetId;
 
 impl GridController {
+    /// Parses a selection string. Relative R1C1 references such as
+    /// `R[-1]C[2]` are resolved from `base`.
     pub fn a1_selection_from_string(
         &self,
         a1: &str,
         default_sheet_id: &SheetId,
+        base: Option<Pos>,
     ) -> Result<A1Selection, A1Error> {
         let sheet_map = self.grid().sheet_name_id_map();
-        A1Selection::from_str(a1, default_sheet_id, &sheet_map)
+        let mut selection =
+            A1Selection::from_str_relative_to(a1, default_sheet_id, &sheet_map, base)?;
+
+        // table references don't include a sheet name, so the selection's
+        // sheet is the sheet that contains the tables
+        let mut table_sheet_id = None;
+        for range in selection.ranges.iter() {
+            let Some(table_ref) = range.as_table_ref() else {
+                continue;
+            };
+            let (sheet, _) = self
+                .grid()
+                .try_table_from_name(&table_ref.table_name)
+                .ok_or_else(|| A1Error::TableNotFound(table_ref.table_name.clone()))?;
+            if *table_sheet_id.get_or_insert(sheet.id) != sheet.id {
+                return Err(A1Error::TooManySheets(a1.to_string()));
+            }
+        }
+        if let Some(sheet_id) = table_sheet_id {
+            let has_sheet_ranges = selection
+                .ranges
+                .iter()
+                .any(|range| range.as_table_ref().is_none());
+            if has_sheet_ranges && selection.sheet_id != sheet_id {
+                return Err(A1Error::TooManySheets(a1.to_string()));
+            }
+            selection.sheet_id = sheet_id;
+        }
+
+        Ok(selection)
     }
 }
 
 #[cfg(test)]
 #[serial_test::parallel]
 mod test {
-    use crate::{controller::GridController, CellRefRange};
+    use crate::{
+        controller::GridController, grid::sheet::tables::Table, A1Error, CellRefRange, Rect,
+    };
 
     #[test]
     fn a1_selection_from_string() {
         let gc = GridController::test();
         let sheet_id = gc.sheet_ids()[0];
         let selection = gc
-            .a1_selection_from_string("'Sheet 1'!A1:B2", &sheet_id)
+            .a1_selection_from_string("'Sheet 1'!A1:B2", &sheet_id, None)
             .unwrap();
         assert_eq!(selection.sheet_id, sheet_id);
         assert_eq!(selection.cursor, pos![A1]);
@@ -36,10 +70,43 @@ mod test {
         gc.add_sheet_with_name("Types: sequences, mapping, sets".to_string(), None);
         let sheet_id = gc.sheet_ids()[1];
         let selection = gc
-            .a1_selection_from_string("'Types: sequences, mapping, sets'!A1:B2", &sheet_id)
+            .a1_selection_from_string("'Types: sequences, mapping, sets'!A1:B2", &sheet_id, None)
             .unwrap();
         assert_eq!(selection.sheet_id, sheet_id);
         assert_eq!(selection.cursor, pos![A1]);
         assert_eq!(selection.ranges, vec![CellRefRange::test_a1("A1:B2")]);
     }
+
+    #[test]
+    fn a1_selection_from_string_table() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_id_2 = gc.sheet_ids()[1];
+        gc.sheet_mut(sheet_id_2).tables.set(
+            sheet_id_2,
+            Table::new("Sales", Rect::new(1, 1, 2, 3), true, vec![]),
+        );
+
+        let selection = gc
+            .a1_selection_from_string("Sales[Column1]", &sheet_id, None)
+            .unwrap();
+        assert_eq!(selection.sheet_id, sheet_id_2);
+        assert_eq!(
+            selection.ranges,
+            vec![CellRefRange::test_a1("Sales[Column1]")]
+        );
+
+        assert_eq!(
+            gc.a1_selection_from_string("Other[]", &sheet_id, None),
+            Err(A1Error::TableNotFound("Other".into()))
+        );
+        assert!(matches!(
+            gc.a1_selection_from_string("Sales[],A1", &sheet_id, None),
+            Err(A1Error::TooManySheets(_))
+        ));
+        assert!(gc
+            .a1_selection_from_string("Sales[],A1", &sheet_id_2, None)
+            .is_ok());
+    }
 }
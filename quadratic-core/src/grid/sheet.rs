@@ -3,9 +3,11 @@ use std::str::FromStr;
 
 use bigdecimal::{BigDecimal, RoundingMode};
 use borders::Borders;
+use dependency_index::DependencyIndex;
 use indexmap::IndexMap;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use tables::Tables;
 use validations::Validations;
 
 use super::bounds::GridBounds;
@@ -25,6 +27,7 @@ pub mod cell_values;
 pub mod clipboard;
 pub mod code;
 pub mod col_row;
+pub mod dependency_index;
 pub mod formats;
 pub mod jump_cursor;
 pub mod rendering;
@@ -34,6 +37,7 @@ pub mod search;
 pub mod send_render;
 pub mod sheet_test;
 pub mod summarize;
+pub mod tables;
 pub mod validations;
 
 /// Sheet in a file.
@@ -64,6 +68,9 @@ pub struct Sheet {
     #[serde(default)]
     pub validations: Validations,
 
+    #[serde(default)]
+    pub tables: Tables,
+
     // bounds for the grid with only data
     pub(super) data_bounds: GridBounds,
 
@@ -73,6 +80,10 @@ pub struct Sheet {
     pub(super) rows_resize: ResizeMap,
 
     pub borders: Borders,
+
+    // index of the cells accessed by code_runs, rebuilt on import
+    #[serde(skip)]
+    pub(crate) dependency_index: DependencyIndex,
 }
 impl Sheet {
     /// Constructs a new empty sheet.
@@ -89,8 +100,10 @@ impl Sheet {
             data_bounds: GridBounds::Empty,
             format_bounds: GridBounds::Empty,
             validations: Validations::default(),
+            tables: Tables::default(),
             rows_resize: ResizeMap::default(),
             borders: Borders::default(),
+            dependency_index: DependencyIndex::default(),
         }
     }
 
@@ -167,7 +180,14 @@ impl Sheet {
         }
 
         // remove code_cells where the rect overlaps the anchor cell
-        self.code_runs.retain(|pos, _| !rect.contains(*pos));
+        self.code_runs.retain(|pos, _| {
+            if rect.contains(*pos) {
+                self.dependency_index.remove(*pos);
+                false
+            } else {
+                true
+            }
+        });
 
         old_cell_values_array
     }
@@ -361,6 +381,7 @@ impl Sheet {
     pub fn clear(&mut self) {
         self.columns.clear();
         self.code_runs.clear();
+        self.dependency_index.clear();
         self.recalculate_bounds();
     }
 
@@ -468,7 +489,9 @@ impl Sheet {
     ) -> Vec<i64> {
         let mut rows_set = HashSet::<i64>::new();
         selection.ranges.iter().for_each(|range| {
-            let rect = self.cell_ref_range_to_rect(*range);
+            let Some(rect) = self.cell_ref_range_to_rect(range) else {
+                return;
+            };
             let rows = self.get_rows_with_wrap_in_rect(&rect, include_blanks);
             rows_set.extend(rows);
         });
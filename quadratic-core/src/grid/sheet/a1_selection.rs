@@ -48,7 +48,9 @@ impl Sheet {
             |entry: &CellValue| skip_code_runs || !matches!(entry, &CellValue::Code(_));
 
         for range in selection.ranges.iter() {
-            let rect = self.cell_ref_range_to_rect(*range);
+            let Some(rect) = self.cell_ref_range_to_rect(range) else {
+                continue;
+            };
             for x in rect.x_range() {
                 for y in rect.y_range() {
                     if let Some(entry) = self.cell_value_ref(Pos { x, y }) {
@@ -186,8 +188,9 @@ impl Sheet {
     }
 
     /// Converts a cell reference range to a minimal rectangle covering the data
-    /// on the sheet.
-    pub fn cell_ref_range_to_rect(&self, cell_ref_range: CellRefRange) -> Rect {
+    /// on the sheet. Returns None for a table range that can't be resolved in
+    /// this sheet.
+    pub fn cell_ref_range_to_rect(&self, cell_ref_range: &CellRefRange) -> Option<Rect> {
         match cell_ref_range {
             CellRefRange::Sheet { range } => {
                 let start = range.start;
@@ -242,8 +245,9 @@ impl Sheet {
                     }
                 };
 
-                Rect::new_span(rect_start, rect_end)
+                Some(Rect::new_span(rect_start, rect_end))
             }
+            CellRefRange::Table { range } => self.resolve_table_ref(range, None),
         }
     }
 
@@ -254,7 +258,12 @@ impl Sheet {
         selection
             .ranges
             .iter()
-            .map(|&range| self.cell_ref_range_to_rect(range))
+            .filter_map(|range| match range {
+                CellRefRange::Table { range } => {
+                    self.resolve_table_ref(range, Some(selection.cursor.y))
+                }
+                CellRefRange::Sheet { .. } => self.cell_ref_range_to_rect(range),
+            })
             .collect()
     }
 
@@ -271,9 +280,10 @@ impl Sheet {
 
     /// Converts an unbounded cell reference range to a finite rectangle via
     /// [`Self::cell_ref_range_to_rect()`]. Bounded ranges are returned
-    /// unmodified.
-    pub fn finitize_cell_ref_range(&self, cell_ref_range: CellRefRange) -> CellRefRange {
-        CellRefRange::new_relative_rect(self.cell_ref_range_to_rect(cell_ref_range))
+    /// unmodified, as are table ranges that can't be resolved.
+    pub fn finitize_cell_ref_range(&self, cell_ref_range: &CellRefRange) -> CellRefRange {
+        self.cell_ref_range_to_rect(cell_ref_range)
+            .map_or_else(|| cell_ref_range.clone(), CellRefRange::new_relative_rect)
     }
 
     /// Converts unbounded regions in a selection to finite rectangular regions.
@@ -281,11 +291,12 @@ impl Sheet {
     pub fn finitize_selection(&self, selection: &A1Selection) -> A1Selection {
         A1Selection {
             sheet_id: selection.sheet_id,
+            last_sheet_id: selection.last_sheet_id,
             cursor: selection.cursor,
             ranges: selection
                 .ranges
                 .iter()
-                .map(|&range| self.finitize_cell_ref_range(range))
+                .map(|range| self.finitize_cell_ref_range(range))
                 .collect(),
         }
     }
@@ -299,6 +310,7 @@ mod tests {
     use crate::{
         grid::{
             js_types::{JsCellValuePosAIContext, JsCodeCell, JsReturnInfo},
+            sheet::tables::Table,
             CodeCellLanguage, CodeCellValue, CodeRun, CodeRunResult,
         },
         A1Selection, Array, CellRefRange, CellValue, Pos, Rect, RunError, RunErrorMsg, SheetRect,
@@ -413,6 +425,8 @@ mod tests {
             line_number: None,
             output_type: None,
             spill_error: false,
+            stale: false,
+            timing: None,
         };
         sheet.set_cell_value(
             Pos { x: 1, y: 1 },
@@ -437,6 +451,8 @@ mod tests {
             line_number: None,
             output_type: None,
             spill_error: false,
+            stale: false,
+            timing: None,
         };
         sheet.set_cell_value(
             Pos { x: 9, y: 31 },
@@ -461,6 +477,8 @@ mod tests {
             line_number: None,
             output_type: None,
             spill_error: true,
+            stale: false,
+            timing: None,
         };
         sheet.set_cell_value(
             Pos { x: 19, y: 15 },
@@ -526,13 +544,28 @@ mod tests {
 
         // Test fully specified range
         let range = CellRefRange::test_a1("A1:E5");
-        let rect = sheet.cell_ref_range_to_rect(range);
-        assert_eq!(rect, Rect::new(1, 1, 5, 5));
+        let rect = sheet.cell_ref_range_to_rect(&range);
+        assert_eq!(rect, Some(Rect::new(1, 1, 5, 5)));
 
         // Test unbounded end
         let range = CellRefRange::test_a1("B2:");
-        let rect = sheet.cell_ref_range_to_rect(range);
-        assert_eq!(rect, Rect::new(2, 2, 5, 5)); // Should extend to sheet bounds
+        let rect = sheet.cell_ref_range_to_rect(&range);
+        assert_eq!(rect, Some(Rect::new(2, 2, 5, 5))); // Should extend to sheet bounds
+
+        // Test table range
+        let range = CellRefRange::test_a1("Sales[Amount]");
+        assert_eq!(sheet.cell_ref_range_to_rect(&range), None);
+        sheet.tables.set(
+            sheet.id,
+            Table::new(
+                "Sales",
+                Rect::new(1, 1, 2, 5),
+                true,
+                vec!["Region".into(), "Amount".into()],
+            ),
+        );
+        let rect = sheet.cell_ref_range_to_rect(&range);
+        assert_eq!(rect, Some(Rect::new(2, 2, 2, 5)));
     }
 
     #[test]
@@ -554,17 +587,17 @@ mod tests {
 
         // Test unbounded range
         let range = CellRefRange::test_a1("B2:");
-        let finite_range = sheet.finitize_cell_ref_range(range);
+        let finite_range = sheet.finitize_cell_ref_range(&range);
         assert_eq!(finite_range, CellRefRange::test_a1("B2:J10"));
 
         // Test already bounded range (should remain unchanged)
         let range = CellRefRange::test_a1("C3:E5");
-        let finite_range = sheet.finitize_cell_ref_range(range);
+        let finite_range = sheet.finitize_cell_ref_range(&range);
         assert_eq!(finite_range, CellRefRange::test_a1("C3:E5"));
 
         // Test select all
         let range = CellRefRange::test_a1("*");
-        let finite_range = sheet.finitize_cell_ref_range(range);
+        let finite_range = sheet.finitize_cell_ref_range(&range);
         assert_eq!(finite_range, CellRefRange::test_a1("A1:J10"));
     }
 
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::ops::Range;
 
 use super::Sheet;
@@ -7,7 +8,7 @@ use crate::{
         js_types::{JsCodeCell, JsReturnInfo},
         CodeCellLanguage, CodeRun, RenderSize,
     },
-    CellValue, Pos, Rect,
+    CellValue, Pos, Rect, SheetRect,
 };
 
 impl Sheet {
@@ -15,13 +16,38 @@ impl Sheet {
     ///
     /// Returns the old value if it was set.
     pub fn set_code_run(&mut self, pos: Pos, code_run: Option<CodeRun>) -> Option<CodeRun> {
-        if let Some(code_run) = code_run {
+        let old_code_run = if let Some(code_run) = code_run {
             self.code_runs.insert_sorted(pos, code_run).1
         } else {
             self.code_runs.shift_remove(&pos)
+        };
+        self.update_dependency_index(pos);
+        old_code_run
+    }
+
+    /// Updates the dependency index for the code run at pos.  This must be
+    /// called whenever a code run is set or removed.
+    pub(crate) fn update_dependency_index(&mut self, pos: Pos) {
+        match self.code_runs.get(&pos) {
+            Some(code_run) => self.dependency_index.insert(pos, &code_run.cells_accessed),
+            None => self.dependency_index.remove(pos),
+        }
+    }
+
+    /// Rebuilds the dependency index from all code runs.
+    pub(crate) fn rebuild_dependency_index(&mut self) {
+        self.dependency_index.clear();
+        for (pos, code_run) in self.code_runs.iter() {
+            self.dependency_index.insert(*pos, &code_run.cells_accessed);
         }
     }
 
+    /// Returns the positions of code runs in this sheet that access cells in
+    /// the sheet_rect (which may be in another sheet).
+    pub fn dependent_code_runs(&self, sheet_rect: &SheetRect) -> HashSet<Pos> {
+        self.dependency_index.dependents(sheet_rect)
+    }
+
     /// Returns a CodeCell at a Pos
     pub fn code_run(&self, pos: Pos) -> Option<&CodeRun> {
         self.code_runs.get(&pos)
@@ -265,6 +291,8 @@ mod test {
             line_number: None,
             output_type: None,
             spill_error: false,
+            stale: false,
+            timing: None,
         };
         let old = sheet.set_code_run(Pos { x: 0, y: 0 }, Some(code_run.clone()));
         assert_eq!(old, None);
@@ -290,6 +318,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(Pos { x: 0, y: 0 }, Some(code_run.clone()));
         assert_eq!(
@@ -324,6 +354,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(Pos { x: 0, y: 0 }, Some(code_run.clone()));
         assert_eq!(
@@ -415,6 +447,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(Pos { x: 0, y: 0 }, Some(code_run.clone()));
         sheet.set_code_run(Pos { x: 1, y: 1 }, Some(code_run.clone()));
@@ -446,6 +480,8 @@ mod test {
             output_type: None,
             spill_error: false,
             last_modified: Utc::now(),
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(Pos { x: 0, y: 0 }, Some(code_run.clone()));
         sheet.set_code_run(Pos { x: 1, y: 1 }, Some(code_run.clone()));
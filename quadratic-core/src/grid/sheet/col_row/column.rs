@@ -151,6 +151,7 @@ impl Sheet {
                 } else if code_run.is_image() {
                     transaction.add_image_cell(self.id, *pos);
                 }
+                self.dependency_index.remove(*pos);
                 false
             } else {
                 true
@@ -196,6 +197,7 @@ impl Sheet {
                 }
 
                 self.code_runs.insert_sorted(new_pos, code_run);
+                self.dependency_index.move_code_run(old_pos, new_pos);
 
                 // signal client to update the code runs
                 transaction.add_code_cell(self.id, old_pos);
@@ -206,6 +208,8 @@ impl Sheet {
         // mark hashes of new columns dirty
         transaction.add_dirty_hashes_from_sheet_columns(self, column, None);
 
+        self.tables.remove_column(transaction, self.id, column);
+
         let changed_selections = self.validations.remove_column(transaction, self.id, column);
         transaction.add_dirty_hashes_from_selections(self, changed_selections);
 
@@ -274,6 +278,7 @@ impl Sheet {
                 }
 
                 self.code_runs.insert_sorted(new_pos, code_run);
+                self.dependency_index.move_code_run(old_pos, new_pos);
 
                 // signal the client to updates to the code cells (to draw the code arrays)
                 if send_client {
@@ -300,6 +305,8 @@ impl Sheet {
             transaction.add_dirty_hashes_from_sheet_columns(self, column, None);
         }
 
+        self.tables.insert_column(transaction, self.id, column);
+
         let changed_selections = self.validations.insert_column(transaction, self.id, column);
         if send_client {
             transaction.add_dirty_hashes_from_selections(self, changed_selections);
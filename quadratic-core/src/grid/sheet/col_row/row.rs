@@ -181,6 +181,7 @@ impl Sheet {
                 } else if code_run.is_image() {
                     transaction.add_image_cell(self.id, *pos);
                 }
+                self.dependency_index.remove(*pos);
                 false
             } else {
                 true
@@ -211,6 +212,7 @@ impl Sheet {
                 }
 
                 self.code_runs.insert_sorted(new_pos, code_run);
+                self.dependency_index.move_code_run(old_pos, new_pos);
 
                 // signal client to update the code runs
                 transaction.add_code_cell(self.id, old_pos);
@@ -221,6 +223,8 @@ impl Sheet {
         // mark hashes of new rows dirty
         transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
 
+        self.tables.remove_row(transaction, self.id, row);
+
         let changed_selections = self.validations.remove_row(transaction, self.id, row);
         transaction.add_dirty_hashes_from_selections(self, changed_selections);
 
@@ -312,6 +316,7 @@ impl Sheet {
                 }
 
                 self.code_runs.insert_sorted(new_pos, code_run);
+                self.dependency_index.move_code_run(old_pos, new_pos);
 
                 // signal the client to updates to the code cells (to draw the code arrays)
                 if send_client {
@@ -326,6 +331,8 @@ impl Sheet {
             transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
         }
 
+        self.tables.insert_row(transaction, self.id, row);
+
         let changed_selections = self.validations.insert_row(transaction, self.id, row);
         if send_client {
             transaction.add_dirty_hashes_from_selections(self, changed_selections);
@@ -0,0 +1,382 @@
+//! Spatial index of the cells accessed by a sheet's code runs.
+//!
+//! Each range accessed by a code run is stored in the buckets it overlaps:
+//! small ranges in fixed-size tiles, tall ranges (including unbounded
+//! columns) in column buckets, wide ranges (including unbounded rows) in row
+//! buckets, and anything larger in a single bucket that's always checked.
+//! Finding the code runs that depend on a rect only looks at the buckets the
+//! rect overlaps rather than every code run.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::grid::{CellsAccessed, SheetId};
+use crate::{CellRefRange, Pos, Rect, SheetRect};
+
+/// Width and height of a tile
+const TILE_SIZE: i64 = 16;
+
+/// Ranges that overlap more tiles than this are stored in column or row
+/// buckets.  Queries that overlap more tiles than this check every range.
+const MAX_TILES: i64 = 64;
+
+/// Inclusive bounds of an accessed range.  Unbounded ranges use `UNBOUNDED`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    min: Pos,
+    max: Pos,
+}
+
+impl Bounds {
+    fn intersects(&self, rect: &Rect) -> bool {
+        self.min.x <= rect.max.x
+            && self.max.x >= rect.min.x
+            && self.min.y <= rect.max.y
+            && self.max.y >= rect.min.y
+    }
+}
+
+impl TryFrom<&CellRefRange> for Bounds {
+    type Error = ();
+
+    /// Table ranges have no bounds. (Code runs record the sheet ranges that
+    /// their table references resolved to.)
+    fn try_from(range: &CellRefRange) -> Result<Self, Self::Error> {
+        let CellRefRange::Sheet { range } = range else {
+            return Err(());
+        };
+        let (x1, x2) = (range.start.col(), range.end.col());
+        let (y1, y2) = (range.start.row(), range.end.row());
+
+        Ok(Bounds {
+            min: Pos {
+                x: x1.min(x2),
+                y: y1.min(y2),
+            },
+            max: Pos {
+                x: x1.max(x2),
+                y: y1.max(y2),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Bucket {
+    Tile(i64, i64),
+    Column(i64),
+    Row(i64),
+    Large,
+}
+
+fn tile(coord: i64) -> i64 {
+    coord.div_euclid(TILE_SIZE)
+}
+
+/// Number of tiles between two coordinates, saturating for unbounded ranges.
+fn tile_count(min: i64, max: i64) -> i64 {
+    tile(max).saturating_sub(tile(min)).saturating_add(1)
+}
+
+impl Bucket {
+    /// The buckets that a range is stored in.
+    fn for_range(bounds: &Bounds) -> Vec<Bucket> {
+        let (x1, x2) = (tile(bounds.min.x), tile(bounds.max.x));
+        let (y1, y2) = (tile(bounds.min.y), tile(bounds.max.y));
+        let columns = tile_count(bounds.min.x, bounds.max.x);
+        let rows = tile_count(bounds.min.y, bounds.max.y);
+
+        if columns.saturating_mul(rows) <= MAX_TILES {
+            (x1..=x2)
+                .flat_map(|x| (y1..=y2).map(move |y| Bucket::Tile(x, y)))
+                .collect()
+        } else if columns <= MAX_TILES {
+            (x1..=x2).map(Bucket::Column).collect()
+        } else if rows <= MAX_TILES {
+            (y1..=y2).map(Bucket::Row).collect()
+        } else {
+            vec![Bucket::Large]
+        }
+    }
+
+    /// The buckets that may contain ranges that intersect the rect, or None
+    /// if the rect is too large to look up by bucket.
+    fn for_query(rect: &Rect) -> Option<Vec<Bucket>> {
+        let (x1, x2) = (tile(rect.min.x), tile(rect.max.x));
+        let (y1, y2) = (tile(rect.min.y), tile(rect.max.y));
+        let columns = tile_count(rect.min.x, rect.max.x);
+        let rows = tile_count(rect.min.y, rect.max.y);
+
+        if columns.saturating_mul(rows) > MAX_TILES {
+            return None;
+        }
+
+        let tiles = (x1..=x2).flat_map(|x| (y1..=y2).map(move |y| Bucket::Tile(x, y)));
+        let buckets = tiles
+            .chain((x1..=x2).map(Bucket::Column))
+            .chain((y1..=y2).map(Bucket::Row))
+            .chain([Bucket::Large])
+            .collect();
+
+        Some(buckets)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DependencyIndex {
+    /// Ranges accessed by each code run, by the sheet they're on
+    ranges: HashMap<Pos, Vec<(SheetId, Bounds)>>,
+
+    /// Code runs that access a range in each bucket
+    buckets: HashMap<(SheetId, Bucket), HashSet<Pos>>,
+}
+
+// The index is derived from the sheet's code runs, so it's not compared.
+impl PartialEq for DependencyIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl DependencyIndex {
+    /// Adds the cells accessed by the code run at pos, replacing any that
+    /// were previously added.
+    pub fn insert(&mut self, pos: Pos, cells_accessed: &CellsAccessed) {
+        self.remove(pos);
+
+        let ranges = cells_accessed
+            .cells
+            .iter()
+            .flat_map(|(sheet_id, ranges)| {
+                ranges
+                    .iter()
+                    .filter_map(|range| Some((*sheet_id, range.try_into().ok()?)))
+            })
+            .collect::<Vec<(SheetId, Bounds)>>();
+
+        if ranges.is_empty() {
+            return;
+        }
+
+        self.add_to_buckets(pos, &ranges);
+        self.ranges.insert(pos, ranges);
+    }
+
+    /// Removes the code run at pos from the index.
+    pub fn remove(&mut self, pos: Pos) {
+        if let Some(ranges) = self.ranges.remove(&pos) {
+            self.remove_from_buckets(pos, &ranges);
+        }
+    }
+
+    /// Moves the code run at old_pos to new_pos, keeping the ranges it
+    /// accesses. Only the buckets of that code run are touched.
+    pub fn move_code_run(&mut self, old_pos: Pos, new_pos: Pos) {
+        let Some(ranges) = self.ranges.remove(&old_pos) else {
+            return;
+        };
+
+        self.remove(new_pos);
+        self.remove_from_buckets(old_pos, &ranges);
+        self.add_to_buckets(new_pos, &ranges);
+        self.ranges.insert(new_pos, ranges);
+    }
+
+    fn add_to_buckets(&mut self, pos: Pos, ranges: &[(SheetId, Bounds)]) {
+        for (sheet_id, bounds) in ranges.iter() {
+            for bucket in Bucket::for_range(bounds) {
+                self.buckets
+                    .entry((*sheet_id, bucket))
+                    .or_default()
+                    .insert(pos);
+            }
+        }
+    }
+
+    fn remove_from_buckets(&mut self, pos: Pos, ranges: &[(SheetId, Bounds)]) {
+        for (sheet_id, bounds) in ranges.iter() {
+            for bucket in Bucket::for_range(bounds) {
+                if let Some(positions) = self.buckets.get_mut(&(*sheet_id, bucket)) {
+                    positions.remove(&pos);
+
+                    if positions.is_empty() {
+                        self.buckets.remove(&(*sheet_id, bucket));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes all code runs from the index.
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+        self.buckets.clear();
+    }
+
+    /// Returns the positions of code runs that access cells in the sheet_rect.
+    pub fn dependents(&self, sheet_rect: &SheetRect) -> HashSet<Pos> {
+        let rect: Rect = (*sheet_rect).into();
+        let intersects = |pos: &Pos| {
+            self.ranges.get(pos).is_some_and(|ranges| {
+                ranges.iter().any(|(sheet_id, bounds)| {
+                    *sheet_id == sheet_rect.sheet_id && bounds.intersects(&rect)
+                })
+            })
+        };
+
+        match Bucket::for_query(&rect) {
+            Some(buckets) => buckets
+                .into_iter()
+                .filter_map(|bucket| self.buckets.get(&(sheet_rect.sheet_id, bucket)))
+                .flatten()
+                .filter(|pos| intersects(pos))
+                .copied()
+                .collect(),
+            None => self
+                .ranges
+                .keys()
+                .filter(|pos| intersects(pos))
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Number of code runs in the index.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use super::*;
+
+    fn cells_accessed(sheet_id: SheetId, a1: &str) -> CellsAccessed {
+        let mut cells_accessed = CellsAccessed::default();
+        cells_accessed.add(sheet_id, a1.parse().unwrap());
+        cells_accessed
+    }
+
+    fn dependents(index: &DependencyIndex, rect: Rect, sheet_id: SheetId) -> Vec<Pos> {
+        let mut dependents = index
+            .dependents(&rect.to_sheet_rect(sheet_id))
+            .into_iter()
+            .collect::<Vec<_>>();
+        dependents.sort();
+        dependents
+    }
+
+    #[test]
+    fn test_dependents() {
+        let sheet_id = SheetId::new();
+        let mut index = DependencyIndex::default();
+        index.insert(Pos { x: 10, y: 1 }, &cells_accessed(sheet_id, "A1:B2"));
+        index.insert(Pos { x: 10, y: 2 }, &cells_accessed(sheet_id, "C"));
+        index.insert(Pos { x: 10, y: 3 }, &cells_accessed(sheet_id, "5:5"));
+        index.insert(Pos { x: 10, y: 4 }, &cells_accessed(sheet_id, "A1:ZZ10000"));
+        index.insert(Pos { x: 10, y: 5 }, &cells_accessed(sheet_id, "*"));
+
+        assert_eq!(
+            dependents(&index, Rect::new(1, 1, 1, 1), sheet_id),
+            vec![
+                Pos { x: 10, y: 1 },
+                Pos { x: 10, y: 4 },
+                Pos { x: 10, y: 5 }
+            ]
+        );
+        assert_eq!(
+            dependents(&index, Rect::new(3, 1000, 3, 1000), sheet_id),
+            vec![
+                Pos { x: 10, y: 2 },
+                Pos { x: 10, y: 4 },
+                Pos { x: 10, y: 5 }
+            ]
+        );
+        assert_eq!(
+            dependents(&index, Rect::new(500, 5, 500, 5), sheet_id),
+            vec![
+                Pos { x: 10, y: 3 },
+                Pos { x: 10, y: 4 },
+                Pos { x: 10, y: 5 }
+            ]
+        );
+        assert_eq!(
+            dependents(
+                &index,
+                Rect::new(100_000, 100_000, 100_000, 100_000),
+                sheet_id
+            ),
+            vec![Pos { x: 10, y: 5 }]
+        );
+
+        // a query too large to look up by bucket
+        assert_eq!(
+            dependents(&index, Rect::new(2, 2, 1000, 1000), sheet_id).len(),
+            5
+        );
+
+        // ranges on other sheets are not dependents
+        assert!(dependents(&index, Rect::new(1, 1, 1, 1), SheetId::new()).is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let sheet_id = SheetId::new();
+        let pos = Pos { x: 10, y: 1 };
+        let mut index = DependencyIndex::default();
+
+        index.insert(pos, &cells_accessed(sheet_id, "A1"));
+        assert_eq!(
+            dependents(&index, Rect::new(1, 1, 1, 1), sheet_id),
+            vec![pos]
+        );
+
+        // inserting again replaces the previous ranges
+        index.insert(pos, &cells_accessed(sheet_id, "B2"));
+        assert!(dependents(&index, Rect::new(1, 1, 1, 1), sheet_id).is_empty());
+        assert_eq!(
+            dependents(&index, Rect::new(2, 2, 2, 2), sheet_id),
+            vec![pos]
+        );
+        assert_eq!(index.len(), 1);
+
+        index.remove(pos);
+        assert!(dependents(&index, Rect::new(2, 2, 2, 2), sheet_id).is_empty());
+        assert!(index.is_empty());
+        assert!(index.buckets.is_empty());
+
+        // code runs that don't access any cells aren't indexed
+        index.insert(pos, &CellsAccessed::default());
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_move_code_run() {
+        let sheet_id = SheetId::new();
+        let old_pos = Pos { x: 10, y: 1 };
+        let new_pos = Pos { x: 11, y: 1 };
+        let mut index = DependencyIndex::default();
+
+        index.insert(old_pos, &cells_accessed(sheet_id, "A1:B2"));
+        index.move_code_run(old_pos, new_pos);
+        assert_eq!(
+            dependents(&index, Rect::new(1, 1, 1, 1), sheet_id),
+            vec![new_pos]
+        );
+        assert_eq!(index.len(), 1);
+
+        // moving a code run that isn't indexed does nothing
+        index.move_code_run(old_pos, Pos { x: 12, y: 1 });
+        assert_eq!(
+            dependents(&index, Rect::new(1, 1, 1, 1), sheet_id),
+            vec![new_pos]
+        );
+
+        index.remove(new_pos);
+        assert!(index.buckets.is_empty());
+    }
+}
@@ -369,6 +369,7 @@ impl Sheet {
             },
             state,
             spill_error,
+            stale: run.stale,
         })
     }
 
@@ -411,6 +412,7 @@ impl Sheet {
                                 language: code.language.to_owned(),
                                 state,
                                 spill_error,
+                                stale: run.stale,
                             })
                         }
                         _ => None, // this should not happen. A CodeRun should always have a CellValue::Code.
@@ -457,6 +459,13 @@ impl Sheet {
         }
     }
 
+    /// Sends all tables for this sheet to the client.
+    pub fn send_all_tables(&self) {
+        if let Ok(tables) = serde_json::to_string(&self.tables.tables) {
+            crate::wasm_bindings::js::jsSheetTables(self.id.to_string(), tables);
+        }
+    }
+
     // Sends an update to a code cell. Sends a message regardless of whether the
     // code cell is still present.
     pub fn send_code_cell(&self, pos: Pos) {
@@ -621,6 +630,8 @@ mod tests {
                 line_number: None,
                 output_type: None,
                 last_modified: Utc::now(),
+                stale: false,
+                timing: None,
             }),
         );
         assert!(sheet.has_render_cells(rect));
@@ -837,6 +848,8 @@ mod tests {
             spill_error: false,
             line_number: None,
             output_type: None,
+            stale: false,
+            timing: None,
         };
 
         // render rect is larger than code rect
@@ -886,6 +899,8 @@ mod tests {
             spill_error: false,
             line_number: None,
             output_type: None,
+            stale: false,
+            timing: None,
         };
         let code_cells = sheet.get_code_cells(
             &code_cell,
@@ -1006,6 +1021,8 @@ mod tests {
             spill_error: false,
             line_number: None,
             output_type: None,
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(pos, Some(run));
         sheet.set_cell_value(pos, code);
@@ -1020,6 +1037,7 @@ mod tests {
                 language: CodeCellLanguage::Python,
                 state: crate::grid::js_types::JsRenderCodeCellState::Success,
                 spill_error: None,
+                stale: false,
             })
         );
     }
@@ -1052,6 +1070,8 @@ mod tests {
             spill_error: false,
             line_number: None,
             output_type: None,
+            stale: false,
+            timing: None,
         };
         sheet.set_code_run(pos, Some(run));
         sheet.set_cell_value(pos, code);
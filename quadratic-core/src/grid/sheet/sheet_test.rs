@@ -65,6 +65,8 @@ impl Sheet {
                 output_type: None,
                 spill_error: false,
                 last_modified: chrono::Utc::now(),
+                stale: false,
+                timing: None,
             }),
         );
     }
@@ -132,6 +134,8 @@ impl Sheet {
                 output_type: None,
                 spill_error: false,
                 last_modified: Utc::now(),
+                stale: false,
+                timing: None,
             }),
         );
         self.recalculate_bounds();
@@ -181,6 +185,8 @@ impl Sheet {
                 output_type: None,
                 spill_error: false,
                 last_modified: Utc::now(),
+                stale: false,
+                timing: None,
             }),
         );
     }
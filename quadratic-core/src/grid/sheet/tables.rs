@@ -0,0 +1,551 @@
+//! Named tables in a Sheet. A table covers a rectangle of cells with an
+//! optional header row (which holds the column names) and an optional totals
+//! row. Tables are referenced by name using structured references (see
+//! [`TableRef`]).
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    controller::{
+        active_transactions::pending_transaction::PendingTransaction,
+        operations::operation::Operation,
+    },
+    grid::SheetId,
+    A1Selection, CellRefRange, Pos, Rect, TableColumns, TableRef, TableRows,
+};
+
+use super::Sheet;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+pub struct TableColumn {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+pub struct Table {
+    pub id: Uuid,
+    pub name: String,
+
+    /// Bounds of the table, including the header and totals rows.
+    pub bounds: Rect,
+
+    pub show_header: bool,
+    pub columns: Vec<TableColumn>,
+    pub show_totals: bool,
+}
+
+impl Table {
+    /// Creates a table. Missing or duplicate column names are replaced with
+    /// `Column1`, `Column2`, etc.
+    pub fn new(name: &str, bounds: Rect, show_header: bool, column_names: Vec<String>) -> Self {
+        let mut table = Table {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            bounds,
+            show_header,
+            columns: vec![],
+            show_totals: false,
+        };
+        for index in 0..bounds.width() as usize {
+            let name = column_names
+                .get(index)
+                .map(|name| name.trim().to_string())
+                .filter(|name| {
+                    crate::is_valid_table_column_name(name) && table.column_index(name).is_none()
+                })
+                .unwrap_or_else(|| table.default_column_name());
+            table.columns.push(TableColumn { name });
+        }
+        table
+    }
+
+    /// Returns the first `ColumnN` name that is not used by the table.
+    pub fn default_column_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Column{n}"))
+            .find(|name| self.column_index(name).is_none())
+            .unwrap_or_default()
+    }
+
+    /// Returns the index of a column (column names are case-insensitive).
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the first and last row of the data (ie, excluding the header
+    /// and totals rows), or None if the table has no data rows.
+    pub fn data_rows(&self) -> Option<(i64, i64)> {
+        let top = self.bounds.min.y + self.show_header as i64;
+        let bottom = self.bounds.max.y - self.show_totals as i64;
+        (top <= bottom).then_some((top, bottom))
+    }
+
+    /// Returns the row of the header, if shown.
+    pub fn header_row(&self) -> Option<i64> {
+        self.show_header.then_some(self.bounds.min.y)
+    }
+
+    /// Returns the row of the totals, if shown.
+    pub fn totals_row(&self) -> Option<i64> {
+        self.show_totals.then_some(self.bounds.max.y)
+    }
+
+    /// Resolves a structured reference to a rect on the sheet. `row` is the
+    /// row of the cell containing the reference, which is needed for
+    /// `#This Row` (`@`) references.
+    pub fn resolve(&self, table_ref: &TableRef, row: Option<i64>) -> Option<Rect> {
+        let (min_x, max_x) = match &table_ref.columns {
+            TableColumns::All => (self.bounds.min.x, self.bounds.max.x),
+            TableColumns::Column(name) => {
+                let x = self.bounds.min.x + self.column_index(name)? as i64;
+                (x, x)
+            }
+            TableColumns::ColumnRange { start, end } => {
+                let start = self.bounds.min.x + self.column_index(start)? as i64;
+                let end = self.bounds.min.x + self.column_index(end)? as i64;
+                (start.min(end), start.max(end))
+            }
+        };
+        let (min_y, max_y) = match table_ref.rows {
+            TableRows::Data => self.data_rows()?,
+            TableRows::Headers => {
+                let y = self.header_row()?;
+                (y, y)
+            }
+            TableRows::Totals => {
+                let y = self.totals_row()?;
+                (y, y)
+            }
+            TableRows::All => (self.bounds.min.y, self.bounds.max.y),
+            TableRows::ThisRow => {
+                let (top, bottom) = self.data_rows()?;
+                let y = row.filter(|y| (top..=bottom).contains(y))?;
+                (y, y)
+            }
+        };
+        Some(Rect::new(min_x, min_y, max_x, max_y))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tables {
+    #[serde(default)]
+    pub tables: Vec<Table>,
+}
+
+impl Tables {
+    /// Updates or adds a table to the sheet. Returns the reverse operations.
+    pub fn set(&mut self, sheet_id: SheetId, table: Table) -> Vec<Operation> {
+        if let Some(t) = self.tables.iter_mut().find(|t| t.id == table.id) {
+            let reverse = vec![Operation::SetTable {
+                sheet_id,
+                table: t.clone(),
+            }];
+            *t = table;
+            return reverse;
+        }
+        let reverse = vec![Operation::RemoveTable {
+            sheet_id,
+            table_id: table.id,
+        }];
+        self.tables.push(table);
+        reverse
+    }
+
+    /// Removes a table from the sheet. Returns the reverse operations.
+    pub fn remove(&mut self, sheet_id: SheetId, table_id: Uuid) -> Vec<Operation> {
+        let Some(index) = self.tables.iter().position(|t| t.id == table_id) else {
+            return vec![];
+        };
+        let table = self.tables.remove(index);
+        vec![Operation::SetTable { sheet_id, table }]
+    }
+
+    /// Gets a table based on a table_id.
+    pub fn table(&self, table_id: Uuid) -> Option<&Table> {
+        self.tables.iter().find(|t| t.id == table_id)
+    }
+
+    /// Gets a table by name (table names are case-insensitive).
+    pub fn table_by_name(&self, name: &str) -> Option<&Table> {
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Gets the table that contains a position.
+    pub fn table_at(&self, pos: Pos) -> Option<&Table> {
+        self.tables.iter().find(|t| t.bounds.contains(pos))
+    }
+
+    /// Returns the tables that intersect a rect.
+    pub fn tables_in_rect(&self, rect: Rect) -> impl Iterator<Item = &Table> {
+        self.tables
+            .iter()
+            .filter(move |t| t.bounds.intersects(rect))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Table> {
+        self.tables.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Applies a change to every table and adds undo operations. A table is
+    /// deleted if `change` returns false.
+    fn update_all(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        change: impl Fn(&mut Table) -> bool,
+    ) {
+        let mut reverse_operations = Vec::new();
+        self.tables.retain_mut(|table| {
+            let original = table.clone();
+            let keep = change(table);
+            if *table != original || !keep {
+                reverse_operations.push(Operation::SetTable {
+                    sheet_id,
+                    table: original,
+                });
+                transaction.tables.insert(sheet_id);
+            }
+            keep
+        });
+        transaction.reverse_operations.extend(reverse_operations);
+    }
+
+    /// Removes a column from all tables and adds undo operations. A table
+    /// loses the column's name, and is deleted if it was its only column.
+    pub(crate) fn remove_column(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        column: i64,
+    ) {
+        self.update_all(transaction, sheet_id, |table| {
+            if column < table.bounds.min.x {
+                table.bounds.min.x -= 1;
+                table.bounds.max.x -= 1;
+            } else if column <= table.bounds.max.x {
+                if table.bounds.width() == 1 {
+                    return false;
+                }
+                table.columns.remove((column - table.bounds.min.x) as usize);
+                table.bounds.max.x -= 1;
+            }
+            true
+        });
+    }
+
+    /// Inserts a column in all tables and adds undo operations. A column
+    /// inserted inside a table adds a column to the table.
+    pub(crate) fn insert_column(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        column: i64,
+    ) {
+        self.update_all(transaction, sheet_id, |table| {
+            if column <= table.bounds.min.x {
+                table.bounds.min.x += 1;
+                table.bounds.max.x += 1;
+            } else if column <= table.bounds.max.x {
+                let name = table.default_column_name();
+                table
+                    .columns
+                    .insert((column - table.bounds.min.x) as usize, TableColumn { name });
+                table.bounds.max.x += 1;
+            }
+            true
+        });
+    }
+
+    /// Removes a row from all tables and adds undo operations. Removing the
+    /// header or totals row hides it, and a table is deleted if it was its
+    /// only row.
+    pub(crate) fn remove_row(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        row: i64,
+    ) {
+        self.update_all(transaction, sheet_id, |table| {
+            if row < table.bounds.min.y {
+                table.bounds.min.y -= 1;
+                table.bounds.max.y -= 1;
+            } else if row <= table.bounds.max.y {
+                if table.bounds.height() == 1 {
+                    return false;
+                }
+                if table.header_row() == Some(row) {
+                    table.show_header = false;
+                } else if table.totals_row() == Some(row) {
+                    table.show_totals = false;
+                }
+                table.bounds.max.y -= 1;
+            }
+            true
+        });
+    }
+
+    /// Inserts a row in all tables and adds undo operations. A row inserted
+    /// inside a table adds a data row to the table.
+    pub(crate) fn insert_row(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        row: i64,
+    ) {
+        self.update_all(transaction, sheet_id, |table| {
+            if row <= table.bounds.min.y {
+                table.bounds.min.y += 1;
+                table.bounds.max.y += 1;
+            } else if row <= table.bounds.max.y {
+                table.bounds.max.y += 1;
+            }
+            true
+        });
+    }
+}
+
+impl Sheet {
+    /// Resolves a table reference to a rect, if the table is in this sheet.
+    pub fn resolve_table_ref(&self, table_ref: &TableRef, row: Option<i64>) -> Option<Rect> {
+        self.tables
+            .table_by_name(&table_ref.table_name)?
+            .resolve(table_ref, row)
+    }
+
+    /// Resolves a table range to a sheet range. Sheet ranges are returned
+    /// unchanged. Returns None if the table is not in this sheet or the
+    /// referenced part of the table is empty.
+    pub fn resolve_cell_ref_range(
+        &self,
+        range: &CellRefRange,
+        row: Option<i64>,
+    ) -> Option<CellRefRange> {
+        match range {
+            CellRefRange::Sheet { .. } => Some(range.clone()),
+            CellRefRange::Table { range } => self
+                .resolve_table_ref(range, row)
+                .map(CellRefRange::new_relative_rect),
+        }
+    }
+
+    /// Replaces the table ranges in a selection with the sheet ranges they
+    /// refer to. `row` is used for `#This Row` references. Table ranges that
+    /// can't be resolved are left unchanged.
+    pub fn resolve_selection(&self, selection: &A1Selection, row: Option<i64>) -> A1Selection {
+        A1Selection {
+            sheet_id: selection.sheet_id,
+            last_sheet_id: selection.last_sheet_id,
+            cursor: selection.cursor,
+            ranges: selection
+                .ranges
+                .iter()
+                .map(|range| {
+                    self.resolve_cell_ref_range(range, row)
+                        .unwrap_or_else(|| range.clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[serial_test::parallel]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn test_table() -> Table {
+        Table::new(
+            "Sales",
+            Rect::new(2, 2, 4, 6),
+            true,
+            vec!["Region".into(), "Amount".into(), "".into()],
+        )
+    }
+
+    fn resolve(table: &Table, s: &str, row: Option<i64>) -> Option<Rect> {
+        table.resolve(&TableRef::from_str(s).unwrap(), row)
+    }
+
+    #[test]
+    fn test_new() {
+        let table = test_table();
+        assert_eq!(
+            table
+                .columns
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Region", "Amount", "Column1"]
+        );
+
+        // duplicate and missing names
+        let table = Table::new(
+            "T",
+            Rect::new(1, 1, 4, 1),
+            true,
+            vec!["Column1".into(), "column1".into()],
+        );
+        assert_eq!(
+            table
+                .columns
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Column1", "Column2", "Column3", "Column4"]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut table = test_table();
+        assert_eq!(
+            resolve(&table, "Sales[]", None),
+            Some(Rect::new(2, 3, 4, 6))
+        );
+        assert_eq!(
+            resolve(&table, "Sales[Amount]", None),
+            Some(Rect::new(3, 3, 3, 6))
+        );
+        assert_eq!(
+            resolve(&table, "Sales[[Region]:[Amount]]", None),
+            Some(Rect::new(2, 3, 3, 6))
+        );
+        assert_eq!(
+            resolve(&table, "Sales[#Headers]", None),
+            Some(Rect::new(2, 2, 4, 2))
+        );
+        assert_eq!(
+            resolve(&table, "Sales[#All]", None),
+            Some(Rect::new(2, 2, 4, 6))
+        );
+        assert_eq!(resolve(&table, "Sales[#Totals]", None), None);
+        assert_eq!(resolve(&table, "Sales[Missing]", None), None);
+
+        assert_eq!(
+            resolve(&table, "Sales[@Amount]", Some(4)),
+            Some(Rect::new(3, 4, 3, 4))
+        );
+        assert_eq!(resolve(&table, "Sales[@Amount]", Some(2)), None);
+        assert_eq!(resolve(&table, "Sales[@Amount]", None), None);
+
+        table.show_totals = true;
+        assert_eq!(
+            resolve(&table, "Sales[]", None),
+            Some(Rect::new(2, 3, 4, 5))
+        );
+        assert_eq!(
+            resolve(&table, "Sales[[#Totals],[Amount]]", None),
+            Some(Rect::new(3, 6, 3, 6))
+        );
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let sheet_id = SheetId::TEST;
+        let mut tables = Tables::default();
+        let table = test_table();
+
+        let reverse = tables.set(sheet_id, table.clone());
+        assert_eq!(
+            reverse,
+            vec![Operation::RemoveTable {
+                sheet_id,
+                table_id: table.id
+            }]
+        );
+        assert_eq!(tables.table_by_name("sales"), Some(&table));
+        assert_eq!(tables.table_at(Pos { x: 3, y: 3 }), Some(&table));
+        assert_eq!(tables.table_at(Pos { x: 1, y: 1 }), None);
+
+        let mut renamed = table.clone();
+        renamed.name = "Revenue".into();
+        let reverse = tables.set(sheet_id, renamed);
+        assert_eq!(
+            reverse,
+            vec![Operation::SetTable {
+                sheet_id,
+                table: table.clone()
+            }]
+        );
+        assert!(tables.table_by_name("Sales").is_none());
+
+        let reverse = tables.remove(sheet_id, table.id);
+        assert_eq!(reverse.len(), 1);
+        assert!(tables.is_empty());
+        assert!(tables.remove(sheet_id, table.id).is_empty());
+    }
+
+    #[test]
+    fn test_insert_remove_column_row() {
+        let sheet_id = SheetId::TEST;
+        let mut transaction = PendingTransaction::default();
+        let mut tables = Tables::default();
+        let table = test_table();
+        tables.set(sheet_id, table.clone());
+
+        // inserting before the table moves it
+        tables.insert_column(&mut transaction, sheet_id, 1);
+        tables.insert_row(&mut transaction, sheet_id, 2);
+        assert_eq!(tables.tables[0].bounds, Rect::new(3, 3, 5, 7));
+        assert_eq!(transaction.reverse_operations.len(), 2);
+
+        // inserting and removing inside the table changes its columns
+        tables.insert_column(&mut transaction, sheet_id, 4);
+        assert_eq!(tables.tables[0].bounds, Rect::new(3, 3, 6, 7));
+        assert_eq!(tables.tables[0].columns[1].name, "Column2");
+        tables.remove_column(&mut transaction, sheet_id, 3);
+        assert_eq!(tables.tables[0].bounds, Rect::new(3, 3, 5, 7));
+        assert_eq!(tables.tables[0].columns[0].name, "Column2");
+
+        // removing the header row hides it
+        tables.remove_row(&mut transaction, sheet_id, 3);
+        assert!(!tables.tables[0].show_header);
+        assert_eq!(tables.tables[0].bounds, Rect::new(3, 3, 5, 6));
+
+        // changes after the table don't affect it
+        let count = transaction.reverse_operations.len();
+        tables.insert_row(&mut transaction, sheet_id, 100);
+        tables.remove_column(&mut transaction, sheet_id, 100);
+        assert_eq!(transaction.reverse_operations.len(), count);
+
+        // removing the only column deletes the table
+        let mut tables = Tables::default();
+        tables.set(
+            sheet_id,
+            Table::new("T", Rect::new(1, 1, 1, 3), true, vec![]),
+        );
+        tables.remove_column(&mut transaction, sheet_id, 1);
+        assert!(tables.is_empty());
+        assert!(transaction.tables.contains(&sheet_id));
+    }
+
+    #[test]
+    fn test_resolve_selection() {
+        let mut sheet = Sheet::test();
+        sheet.tables.set(sheet.id, test_table());
+
+        let selection = A1Selection::test_a1("Sales[Amount],A1");
+        let resolved = sheet.resolve_selection(&selection, None);
+        assert_eq!(
+            resolved.ranges,
+            vec![CellRefRange::test_a1("C3:C6"), CellRefRange::test_a1("A1")]
+        );
+
+        // unknown tables are left unchanged
+        let selection = A1Selection::test_a1("Other[]");
+        assert_eq!(sheet.resolve_selection(&selection, None), selection);
+    }
+}
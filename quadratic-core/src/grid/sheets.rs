@@ -1,5 +1,6 @@
 use crate::SheetNameIdMap;
 
+use super::sheet::tables::Table;
 use super::{Grid, Sheet, SheetId};
 use lexicon_fractional_index::key_between;
 use std::str::FromStr;
@@ -37,6 +38,36 @@ impl Grid {
         self.sheets.iter().find(|sheet| sheet.name == name)
     }
 
+    /// Finds the first and last sheets of a 3D reference such as `Jan:Dec`.
+    /// Sheet names may contain `:`, so every split is tried in order.
+    pub fn try_sheet_span_endpoints(&self, name: &str) -> Option<(SheetId, SheetId)> {
+        name.match_indices(':').find_map(|(i, _)| {
+            let first = self.try_sheet_from_name(name[..i].to_string())?;
+            let last = self.try_sheet_from_name(name[i + 1..].to_string())?;
+            Some((first.id, last.id))
+        })
+    }
+
+    /// Returns the IDs of the sheets covered by a sheet name or a 3D reference
+    /// such as `Jan:Dec`, which covers both sheets and every sheet between
+    /// them in sheet order. An exact sheet name takes precedence.
+    pub fn try_sheet_span_from_name(&self, name: &str) -> Option<Vec<SheetId>> {
+        if let Some(sheet) = self.try_sheet_from_name(name.to_string()) {
+            return Some(vec![sheet.id]);
+        }
+        let (first, last) = self.try_sheet_span_endpoints(name)?;
+        self.sheet_ids_between(first, last)
+    }
+
+    /// Returns the IDs of `first`, `last` and every sheet between them in
+    /// sheet order.
+    pub fn sheet_ids_between(&self, first: SheetId, last: SheetId) -> Option<Vec<SheetId>> {
+        let first = self.sheet_id_to_index(first)?;
+        let last = self.sheet_id_to_index(last)?;
+        let range = first.min(last)..=first.max(last);
+        Some(self.sheets[range].iter().map(|sheet| sheet.id).collect())
+    }
+
     pub fn try_sheet_mut_from_name(&mut self, name: String) -> Option<&mut Sheet> {
         self.sheets.iter_mut().find(|sheet| sheet.name == name)
     }
@@ -168,6 +199,22 @@ impl Grid {
     pub fn sheet_name_id_map(&self) -> SheetNameIdMap {
         self.sheets.iter().map(|s| (s.name.clone(), s.id)).collect()
     }
+
+    /// Finds a table by name. Table names are unique across the file (and
+    /// case-insensitive).
+    pub fn try_table_from_name(&self, name: &str) -> Option<(&Sheet, &Table)> {
+        self.sheets
+            .iter()
+            .find_map(|sheet| Some((sheet, sheet.tables.table_by_name(name)?)))
+    }
+
+    /// Returns the names of all tables in the file.
+    pub fn table_names(&self) -> Vec<String> {
+        self.sheets
+            .iter()
+            .flat_map(|sheet| sheet.tables.iter().map(|table| table.name.clone()))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -293,6 +340,28 @@ mod test {
         assert_eq!(grid.next_sheet(grid.sheets[2].id), None);
     }
 
+    #[test]
+    #[parallel]
+    fn test_try_sheet_span_from_name() {
+        let mut grid = create_three_sheets();
+        let ids = grid.sheet_ids();
+        assert_eq!(grid.try_sheet_span_from_name("1"), Some(vec![ids[1]]));
+        assert_eq!(grid.try_sheet_span_from_name("0:2"), Some(ids.clone()));
+        assert_eq!(
+            grid.try_sheet_span_from_name("2:1"),
+            Some(ids[1..].to_vec())
+        );
+        assert_eq!(grid.try_sheet_span_from_name("0:3"), None);
+
+        // sheet names containing `:` take precedence
+        grid.sheets[2].name = String::from("0:1");
+        assert_eq!(grid.try_sheet_span_from_name("0:1"), Some(vec![ids[2]]));
+        assert_eq!(
+            grid.try_sheet_span_endpoints("1:0:1"),
+            Some((ids[1], ids[2]))
+        );
+    }
+
     #[test]
     #[parallel]
     fn test_sort_sheets() {
@@ -1,18 +1,19 @@
-use std::sync::Arc;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use arrow_array::{
     cast::AsArray,
-    types::{Date32Type, Date64Type},
-    Array, ArrayRef,
+    types::{Date32Type, Date64Type, Decimal128Type},
+    Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array, StringArray,
+    Time32MillisecondArray, TimestampMillisecondArray, UnionArray,
 };
-use arrow_buffer::ArrowNativeType;
+use arrow_buffer::{ArrowNativeType, Buffer};
 use arrow_data::ArrayData;
-use arrow_schema::{DataType, TimeUnit};
-use bigdecimal::BigDecimal;
-use chrono::{NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use arrow_schema::{DataType, Field, TimeUnit, DECIMAL128_MAX_PRECISION, DECIMAL128_MAX_SCALE};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{NaiveDate, NaiveTime, TimeDelta, TimeZone, Timelike, Utc};
 
-use crate::{cell_values::CellValues, CellValue};
+use crate::{cell_values::CellValues, CellValue, IsBlank};
 
 use super::time::map_local_result;
 
@@ -24,7 +25,22 @@ fn i64_naive_date(value: i64) -> NaiveDate {
     Date64Type::to_naive_date(value)
 }
 
+/// Convert an Arrow array into cell values.  Nulls become blank cells.
 pub fn arrow_col_to_cell_value_vec(array: &ArrayRef) -> Result<Vec<CellValue>> {
+    let mut values = arrow_values_to_cell_values(array)?;
+
+    if array.null_count() > 0 {
+        values
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| array.is_null(*index))
+            .for_each(|(_, value)| *value = CellValue::Blank);
+    }
+
+    Ok(values)
+}
+
+fn arrow_values_to_cell_values(array: &ArrayRef) -> Result<Vec<CellValue>> {
     let data_type = array.data_type();
     let array_data = array.to_data();
 
@@ -43,6 +59,7 @@ pub fn arrow_col_to_cell_value_vec(array: &ArrayRef) -> Result<Vec<CellValue>> {
         DataType::Boolean => Ok(arrow_bool_to_cell_values(array)),
         DataType::Binary => Ok(arrow_binary_to_cell_values(array)),
         DataType::Utf8 => Ok(arrow_utf8_to_cell_values(array)),
+        DataType::LargeUtf8 => Ok(arrow_large_utf8_to_cell_values(array)),
         DataType::Date32 => Ok(arrow_date_to_cell_values::<i32>(
             array_data,
             &i32_naive_date,
@@ -54,6 +71,8 @@ pub fn arrow_col_to_cell_value_vec(array: &ArrayRef) -> Result<Vec<CellValue>> {
         DataType::Time32(unit) => arrow_time_unit_to_cell_values::<i32>(array_data, unit),
         DataType::Time64(unit) => arrow_time_unit_to_cell_values::<i64>(array_data, unit),
         DataType::Timestamp(unit, extra) => arrow_timestamp_to_cell_value(array_data, unit, extra),
+        DataType::Decimal128(_, scale) => Ok(arrow_decimal_to_cell_values(array, *scale)),
+        DataType::Union(_, _) => arrow_union_to_cell_values(array),
         // unsupported data type
         _ => {
             dbgjs!(format!(
@@ -65,6 +84,293 @@ pub fn arrow_col_to_cell_value_vec(array: &ArrayRef) -> Result<Vec<CellValue>> {
     }
 }
 
+/// The Arrow type a cell value is stored as.  Mixed columns are dense unions
+/// with one child per kind, so the kind's position is its union type id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ArrowKind {
+    Number,
+    /// Numbers that don't fit in the column's Decimal128 are kept as strings
+    NumberText,
+    /// Text and any other value without an Arrow type
+    Text,
+    Logical,
+    Date,
+    DateTime,
+    Time,
+}
+
+impl ArrowKind {
+    const ALL: [ArrowKind; 7] = [
+        ArrowKind::Number,
+        ArrowKind::NumberText,
+        ArrowKind::Text,
+        ArrowKind::Logical,
+        ArrowKind::Date,
+        ArrowKind::DateTime,
+        ArrowKind::Time,
+    ];
+
+    fn new(value: &CellValue, scale: i8) -> Option<Self> {
+        match value {
+            CellValue::Blank => None,
+            CellValue::Number(number) => match number_to_decimal(number, scale) {
+                Some(_) => Some(ArrowKind::Number),
+                None => Some(ArrowKind::NumberText),
+            },
+            CellValue::Logical(_) => Some(ArrowKind::Logical),
+            CellValue::Date(_) => Some(ArrowKind::Date),
+            CellValue::DateTime(_) => Some(ArrowKind::DateTime),
+            CellValue::Time(_) => Some(ArrowKind::Time),
+            _ => Some(ArrowKind::Text),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        ArrowKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ArrowKind::Number => "number",
+            ArrowKind::NumberText => "number_text",
+            ArrowKind::Text => "text",
+            ArrowKind::Logical => "logical",
+            ArrowKind::Date => "date",
+            ArrowKind::DateTime => "date_time",
+            ArrowKind::Time => "time",
+        }
+    }
+
+    fn type_id(self) -> i8 {
+        self as i8
+    }
+
+    /// Build an array of this kind.  Values of other kinds become nulls.
+    fn to_array<'a>(
+        self,
+        values: impl Iterator<Item = &'a CellValue>,
+        scale: i8,
+    ) -> Result<ArrayRef> {
+        Ok(match self {
+            ArrowKind::Number => Arc::new(
+                Decimal128Array::from_iter(values.map(|value| match value {
+                    CellValue::Number(number) => number_to_decimal(number, scale),
+                    _ => None,
+                }))
+                .with_precision_and_scale(DECIMAL128_MAX_PRECISION, scale)?,
+            ),
+            ArrowKind::NumberText => {
+                Arc::new(StringArray::from_iter(values.map(|value| match value {
+                    CellValue::Number(number) => Some(number.to_string()),
+                    _ => None,
+                })))
+            }
+            ArrowKind::Text => {
+                Arc::new(StringArray::from_iter(values.map(
+                    |value| match value.is_blank() {
+                        true => None,
+                        false => Some(value.to_string()),
+                    },
+                )))
+            }
+            ArrowKind::Logical => {
+                Arc::new(BooleanArray::from_iter(values.map(|value| match value {
+                    CellValue::Logical(logical) => Some(*logical),
+                    _ => None,
+                })))
+            }
+            ArrowKind::Date => Arc::new(Date32Array::from_iter(values.map(|value| match value {
+                CellValue::Date(date) => Some(Date32Type::from_naive_date(*date)),
+                _ => None,
+            }))),
+            ArrowKind::DateTime => Arc::new(TimestampMillisecondArray::from_iter(values.map(
+                |value| match value {
+                    CellValue::DateTime(date_time) => Some(date_time.and_utc().timestamp_millis()),
+                    _ => None,
+                },
+            ))),
+            ArrowKind::Time => {
+                Arc::new(Time32MillisecondArray::from_iter(values.map(
+                    |value| match value {
+                        CellValue::Time(time) => Some(
+                            (time.num_seconds_from_midnight() * 1000
+                                + time.nanosecond() / 1_000_000) as i32,
+                        ),
+                        _ => None,
+                    },
+                )))
+            }
+        })
+    }
+}
+
+/// Digits after the decimal point, ignoring trailing zeros.
+fn number_scale(number: &BigDecimal) -> i64 {
+    number.normalized().as_bigint_and_exponent().1.max(0)
+}
+
+/// The Decimal128 scale for a column: enough for every number that fits.
+fn decimal_scale(values: &[CellValue]) -> i8 {
+    values
+        .iter()
+        .filter_map(|value| match value {
+            CellValue::Number(number) => Some(number_scale(number)),
+            _ => None,
+        })
+        .filter(|scale| *scale <= DECIMAL128_MAX_SCALE as i64)
+        .max()
+        .unwrap_or(0) as i8
+}
+
+/// The Decimal128 value of a number at the scale, if it fits exactly.
+fn number_to_decimal(number: &BigDecimal, scale: i8) -> Option<i128> {
+    if number_scale(number) > scale as i64 {
+        return None;
+    }
+
+    let (digits, _) = number.with_scale(scale as i64).into_bigint_and_exponent();
+    let decimal = digits.to_i128()?;
+    let max = 10_i128.pow(DECIMAL128_MAX_PRECISION as u32);
+
+    (decimal.abs() < max).then_some(decimal)
+}
+
+/// The number for a Decimal128 value, without trailing zeros after the
+/// decimal point.
+fn decimal_to_number(decimal: i128, scale: i8) -> BigDecimal {
+    let number = BigDecimal::new(decimal.into(), scale as i64).normalized();
+
+    match number.as_bigint_and_exponent().1 < 0 {
+        true => number.with_scale(0),
+        false => number,
+    }
+}
+
+/// Convert a column of cell values into an Arrow array.  A column of one type
+/// becomes an array of that type (numbers are Decimal128 so they keep their
+/// precision), and mixed columns become a dense union that keeps each cell's
+/// type.  Blank cells become nulls.
+pub fn cell_values_to_arrow_col(values: &[CellValue]) -> Result<ArrayRef> {
+    let scale = decimal_scale(values);
+    let kinds = values
+        .iter()
+        .map(|value| ArrowKind::new(value, scale))
+        .collect::<Vec<_>>();
+    let mut present = kinds.iter().flatten().copied().collect::<Vec<_>>();
+    present.sort();
+    present.dedup();
+
+    match present.as_slice() {
+        [] => ArrowKind::Text.to_array(values.iter(), scale),
+        [kind] if *kind != ArrowKind::NumberText => kind.to_array(values.iter(), scale),
+        _ => cell_values_to_arrow_union(values, &kinds, &present, scale),
+    }
+}
+
+/// Convert a column of cell values into an Arrow array for formats without
+/// union types (eg, Parquet).  Mixed columns are stored as strings.
+pub fn cell_values_to_arrow_col_without_union(values: &[CellValue]) -> Result<ArrayRef> {
+    let array = cell_values_to_arrow_col(values)?;
+
+    match array.data_type() {
+        DataType::Union(_, _) => ArrowKind::Text.to_array(values.iter(), 0),
+        _ => Ok(array),
+    }
+}
+
+fn cell_values_to_arrow_union(
+    values: &[CellValue],
+    kinds: &[Option<ArrowKind>],
+    present: &[ArrowKind],
+    scale: i8,
+) -> Result<ArrayRef> {
+    // blank cells are stored as nulls in the first child
+    let mut children = present
+        .iter()
+        .map(|kind| (*kind, vec![]))
+        .collect::<Vec<(ArrowKind, Vec<&CellValue>)>>();
+    let mut type_ids = Vec::with_capacity(values.len());
+    let mut offsets = Vec::with_capacity(values.len());
+
+    for (value, kind) in values.iter().zip(kinds.iter()) {
+        let kind = kind.unwrap_or(present[0]);
+        let (_, child) = children
+            .iter_mut()
+            .find(|(child_kind, _)| *child_kind == kind)
+            .ok_or_else(|| anyhow!("Missing union child for {}", kind.name()))?;
+
+        type_ids.push(kind.type_id());
+        offsets.push(child.len() as i32);
+        child.push(value);
+    }
+
+    let field_type_ids = present
+        .iter()
+        .map(|kind| kind.type_id())
+        .collect::<Vec<_>>();
+    let child_arrays = children
+        .into_iter()
+        .map(|(kind, child)| {
+            let array = kind.to_array(child.into_iter(), scale)?;
+            let field = Field::new(kind.name(), array.data_type().to_owned(), true);
+
+            Ok((field, array))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let union = UnionArray::try_new(
+        &field_type_ids,
+        Buffer::from_vec(type_ids),
+        Some(Buffer::from_vec(offsets)),
+        child_arrays,
+    )?;
+
+    Ok(Arc::new(union))
+}
+
+/// Convert a union array into cell values, taking each cell from its child.
+fn arrow_union_to_cell_values(array: &ArrayRef) -> Result<Vec<CellValue>> {
+    let DataType::Union(fields, _) = array.data_type() else {
+        bail!("Expected a union array");
+    };
+    let union = array.as_union();
+    let mut children = HashMap::new();
+
+    for (type_id, field) in fields.iter() {
+        let mut values = arrow_col_to_cell_value_vec(union.child(type_id))?;
+
+        // numbers that don't fit in a Decimal128 are stored as strings
+        if ArrowKind::from_name(field.name()) == Some(ArrowKind::NumberText) {
+            values.iter_mut().for_each(|value| {
+                if let CellValue::Text(text) = value {
+                    if let Ok(number) = BigDecimal::from_str(text) {
+                        *value = CellValue::Number(number);
+                    }
+                }
+            });
+        }
+
+        children.insert(type_id, values);
+    }
+
+    Ok((0..union.len())
+        .map(|index| {
+            children
+                .get(&union.type_id(index))
+                .and_then(|values| values.get(union.value_offset(index)))
+                .cloned()
+                .unwrap_or(CellValue::Blank)
+        })
+        .collect())
+}
+
+fn arrow_decimal_to_cell_values(col: &ArrayRef, scale: i8) -> Vec<CellValue> {
+    col.as_primitive::<Decimal128Type>()
+        .values()
+        .iter()
+        .map(|decimal| CellValue::Number(decimal_to_number(*decimal, scale)))
+        .collect()
+}
+
 impl TryFrom<&ArrayRef> for CellValues {
     type Error = anyhow::Error;
 
@@ -155,6 +461,12 @@ fn arrow_utf8_to_cell_values(col: &ArrayRef) -> Vec<CellValue> {
     values
 }
 
+fn arrow_large_utf8_to_cell_values(col: &ArrayRef) -> Vec<CellValue> {
+    (0..col.len())
+        .map(|index| CellValue::Text(col.as_string::<i64>().value(index).into()))
+        .collect()
+}
+
 fn arrow_date_to_cell_values<T>(
     array_data: ArrayData,
     conversion_fn: &dyn Fn(T) -> NaiveDate,
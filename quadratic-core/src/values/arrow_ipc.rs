@@ -0,0 +1,299 @@
+//! Arrow IPC (streaming format) transfer of cell values.  Code results and
+//! the cells that code reads are sent in this format so that values keep
+//! their types instead of being converted to and from strings per cell.
+
+use std::{collections::HashMap, io::Cursor, sync::Arc};
+
+use anyhow::Result;
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use arrow_ipc::{reader::StreamReader, writer::StreamWriter};
+use arrow_schema::{Field, Schema};
+
+use crate::{
+    arrow::{arrow_col_to_cell_value_vec, cell_values_to_arrow_col},
+    CellValue,
+};
+
+/// Schema metadata key that, when set to `false`, skips the header row.
+pub const HEADERS_METADATA_KEY: &str = "headers";
+
+/// Convert an Arrow IPC stream into rows of cell values.  The field names are
+/// the first row unless the schema's `headers` metadata is `false`.
+pub fn arrow_ipc_to_vec(bytes: &[u8]) -> Result<Vec<Vec<CellValue>>> {
+    Ok(arrow_ipc_to_vec_with_metadata(bytes)?.0)
+}
+
+/// Convert an Arrow IPC stream into rows of cell values (see
+/// `arrow_ipc_to_vec`), and also return the schema's metadata.
+pub fn arrow_ipc_to_vec_with_metadata(
+    bytes: &[u8],
+) -> Result<(Vec<Vec<CellValue>>, HashMap<String, String>)> {
+    if bytes.is_empty() {
+        return Ok((vec![], HashMap::new()));
+    }
+
+    let reader = StreamReader::try_new(Cursor::new(bytes), None)?;
+    let schema = reader.schema();
+    let width = schema.fields().len();
+    let mut output = vec![];
+
+    if schema
+        .metadata()
+        .get(HEADERS_METADATA_KEY)
+        .map_or(true, |headers| headers != "false")
+    {
+        output.push(
+            schema
+                .fields()
+                .iter()
+                .map(|field| field.name().into())
+                .collect(),
+        );
+    }
+
+    for batch in reader {
+        let batch = batch?;
+        let start = output.len();
+        output.extend((0..batch.num_rows()).map(|_| vec![CellValue::Blank; width]));
+
+        for (col_index, col) in batch.columns().iter().enumerate() {
+            let values = arrow_col_to_cell_value_vec(col)?;
+
+            for (index, value) in values.into_iter().enumerate() {
+                output[start + index][col_index] = value;
+            }
+        }
+    }
+
+    Ok((output, schema.metadata().to_owned()))
+}
+
+/// Convert a code result's output_array (rows of stringified values and
+/// types) into an Arrow IPC stream without headers, so the language workers
+/// can send typed results to core.  Returns `None` if a cell needs something
+/// Arrow can't carry (eg, a currency format or an image); the output_array is
+/// sent as-is in that case.
+pub fn js_output_to_arrow_ipc(output: &[Vec<Vec<String>>]) -> Result<Option<Vec<u8>>> {
+    let width = output.first().map_or(0, |row| row.len());
+    let mut columns = vec![Vec::with_capacity(output.len()); width];
+
+    for row in output {
+        for (index, column) in columns.iter_mut().enumerate() {
+            // short rows are padded with blanks
+            let Some(cell) = row.get(index) else {
+                column.push(CellValue::Blank);
+                continue;
+            };
+            let (value, js_type) = match cell.as_slice() {
+                [value, js_type, ..] => (value, js_type.as_str()),
+                _ => return Ok(None),
+            };
+            let value = match CellValue::from_js_value(value, js_type) {
+                Ok((_, Some(_))) => return Ok(None),
+                Ok((value, None)) => value,
+                Err(_) => CellValue::Blank,
+            };
+            match value {
+                CellValue::Blank
+                | CellValue::Text(_)
+                | CellValue::Number(_)
+                | CellValue::Logical(_)
+                | CellValue::Date(_)
+                | CellValue::DateTime(_)
+                | CellValue::Time(_) => column.push(value),
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    let headers = (0..width).map(|index| index.to_string()).collect();
+    let metadata = HashMap::from([(HEADERS_METADATA_KEY.to_string(), "false".to_string())]);
+
+    Ok(Some(vec_to_arrow_ipc(headers, columns, metadata)?))
+}
+
+/// Convert columns of cell values into an Arrow IPC stream, the reverse of
+/// `arrow_ipc_to_vec`.  Each column is typed from its values (mixed columns
+/// keep each cell's type), and the metadata is added to the schema.
+pub fn vec_to_arrow_ipc(
+    headers: Vec<String>,
+    columns: Vec<Vec<CellValue>>,
+    metadata: HashMap<String, String>,
+) -> Result<Vec<u8>> {
+    let num_rows = columns.first().map_or(0, |column| column.len());
+    let arrays = columns
+        .iter()
+        .map(|column| cell_values_to_arrow_col(column))
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    let fields = headers
+        .into_iter()
+        .zip(arrays.iter())
+        .map(|(header, array)| Field::new(header, array.data_type().to_owned(), true))
+        .collect::<Vec<Field>>();
+
+    let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    let record_batch = RecordBatch::try_new_with_options(schema.clone(), arrays, &options)?;
+    let mut writer = StreamWriter::try_new(vec![], &schema)?;
+    writer.write(&record_batch)?;
+    writer.finish()?;
+
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_arrow_ipc_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        let columns = vec![
+            vec![CellValue::Number(1.into()), CellValue::Blank],
+            vec![CellValue::Text("a".into()), CellValue::Number(3.into())],
+            vec![CellValue::Logical(true), CellValue::Logical(false)],
+            vec![CellValue::Date(date), CellValue::Date(date)],
+        ];
+        let headers = vec![
+            "number".into(),
+            "mixed".into(),
+            "bool".into(),
+            "date".into(),
+        ];
+        let ipc = vec_to_arrow_ipc(headers, columns, HashMap::new()).unwrap();
+
+        assert_eq!(
+            arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![
+                vec![
+                    CellValue::Text("number".into()),
+                    CellValue::Text("mixed".into()),
+                    CellValue::Text("bool".into()),
+                    CellValue::Text("date".into()),
+                ],
+                vec![
+                    CellValue::Number(1.into()),
+                    CellValue::Text("a".into()),
+                    CellValue::Logical(true),
+                    CellValue::Date(date),
+                ],
+                vec![
+                    CellValue::Blank,
+                    CellValue::Number(3.into()),
+                    CellValue::Logical(false),
+                    CellValue::Date(date),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrow_ipc_keeps_types_and_precision() {
+        let number = |value: &str| CellValue::Number(BigDecimal::from_str(value).unwrap());
+        let date = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        let columns = vec![
+            vec![
+                number("12345678901234567890.123456789"),
+                number("0.1"),
+                CellValue::Blank,
+            ],
+            vec![
+                number("1.5"),
+                CellValue::Text("a".into()),
+                CellValue::Logical(true),
+            ],
+            vec![CellValue::Date(date), CellValue::Blank, number("2")],
+            vec![number("1e-50"), number("1"), CellValue::Blank],
+        ];
+        let metadata = HashMap::from([(HEADERS_METADATA_KEY.to_string(), "false".to_string())]);
+        let ipc = vec_to_arrow_ipc(
+            vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            columns,
+            metadata,
+        )
+        .unwrap();
+
+        assert_eq!(
+            arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![
+                vec![
+                    number("12345678901234567890.123456789"),
+                    number("1.5"),
+                    CellValue::Date(date),
+                    number("1e-50"),
+                ],
+                vec![
+                    number("0.1"),
+                    CellValue::Text("a".into()),
+                    CellValue::Blank,
+                    number("1"),
+                ],
+                vec![
+                    CellValue::Blank,
+                    CellValue::Logical(true),
+                    number("2"),
+                    CellValue::Blank,
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrow_ipc_without_headers() {
+        let metadata = HashMap::from([(HEADERS_METADATA_KEY.to_string(), "false".to_string())]);
+        let ipc = vec_to_arrow_ipc(
+            vec!["a".into()],
+            vec![vec![CellValue::Number(1.into())]],
+            metadata,
+        )
+        .unwrap();
+
+        assert_eq!(
+            arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![vec![CellValue::Number(1.into())]]
+        );
+    }
+
+    #[test]
+    fn test_arrow_ipc_empty() {
+        assert!(arrow_ipc_to_vec(&[]).unwrap().is_empty());
+
+        let ipc = vec_to_arrow_ipc(vec![], vec![], HashMap::new()).unwrap();
+        assert_eq!(
+            arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![Vec::<CellValue>::new()]
+        );
+    }
+
+    #[test]
+    fn test_js_output_to_arrow_ipc() {
+        let cell = |value: &str, js_type: &str| vec![value.to_string(), js_type.to_string()];
+        let output = vec![
+            vec![cell("1.25", "number"), cell("a", "text")],
+            vec![cell("true", "logical")],
+        ];
+        let ipc = js_output_to_arrow_ipc(&output).unwrap().unwrap();
+
+        assert_eq!(
+            arrow_ipc_to_vec(&ipc).unwrap(),
+            vec![
+                vec![
+                    CellValue::Number(BigDecimal::from_str("1.25").unwrap()),
+                    CellValue::Text("a".into()),
+                ],
+                vec![CellValue::Logical(true), CellValue::Blank],
+            ]
+        );
+
+        // formats and images aren't sent as Arrow
+        let output = vec![vec![cell("$1.25", "number")]];
+        assert!(js_output_to_arrow_ipc(&output).unwrap().is_none());
+        let output = vec![vec![cell("data:image/png;base64,", "image")]];
+        assert!(js_output_to_arrow_ipc(&output).unwrap().is_none());
+    }
+}
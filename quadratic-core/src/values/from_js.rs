@@ -51,6 +51,37 @@ impl CellValue {
         sheet: &mut Sheet,
     ) -> Result<(CellValue, Vec<Operation>)> {
         let mut ops = vec![];
+        let (cell_value, numeric_format) = Self::from_js_value(value, js_type)?;
+
+        if let Some(numeric_format) = numeric_format {
+            sheet
+                .formats
+                .numeric_format
+                .set(pos, Some(numeric_format.clone()));
+
+            ops.push(Operation::SetCellFormatsA1 {
+                sheet_id: sheet.id,
+                formats: SheetFormatUpdates::from_selection(
+                    &A1Selection::from_single_cell(pos.to_sheet_pos(sheet.id)),
+                    FormatUpdate {
+                        numeric_format: Some(Some(numeric_format)),
+                        ..Default::default()
+                    },
+                ),
+            });
+        }
+
+        Ok((cell_value, ops))
+    }
+
+    /// Convert stringified values and types from JS to CellValue, without a
+    /// sheet.  Currency and percentage numbers also return the numeric
+    /// format that the cell needs to display them.
+    pub fn from_js_value(
+        value: &String,
+        js_type: &str,
+    ) -> Result<(CellValue, Option<NumericFormat>)> {
+        let mut numeric_format = None;
 
         let cell_value = match js_type {
             "text" => {
@@ -66,24 +97,9 @@ impl CellValue {
             }
             "number" => {
                 if let Some((currency, number)) = CellValue::unpack_currency(value) {
-                    let numeric_format = NumericFormat {
+                    numeric_format = Some(NumericFormat {
                         kind: NumericFormatKind::Currency,
                         symbol: Some(currency),
-                    };
-                    sheet
-                        .formats
-                        .numeric_format
-                        .set(pos, Some(numeric_format.clone()));
-
-                    ops.push(Operation::SetCellFormatsA1 {
-                        sheet_id: sheet.id,
-                        formats: SheetFormatUpdates::from_selection(
-                            &A1Selection::from_single_cell(pos.to_sheet_pos(sheet.id)),
-                            FormatUpdate {
-                                numeric_format: Some(Some(numeric_format.clone())),
-                                ..Default::default()
-                            },
-                        ),
                     });
 
                     // We no longer automatically set numeric decimals for
@@ -94,25 +110,10 @@ impl CellValue {
                 } else if let Ok(number) = BigDecimal::from_str(value) {
                     CellValue::Number(number)
                 } else if let Some(number) = CellValue::unpack_percentage(value) {
-                    let numeric_format = NumericFormat {
+                    numeric_format = Some(NumericFormat {
                         kind: NumericFormatKind::Percentage,
                         symbol: None,
-                    };
-                    sheet
-                        .formats
-                        .numeric_format
-                        .set(pos, Some(numeric_format.clone()));
-                    ops.push(Operation::SetCellFormatsA1 {
-                        sheet_id: sheet.id,
-                        formats: SheetFormatUpdates::from_selection(
-                            &A1Selection::from_single_cell(pos.to_sheet_pos(sheet.id)),
-                            FormatUpdate {
-                                numeric_format: Some(Some(numeric_format.clone())),
-                                ..Default::default()
-                            },
-                        ),
                     });
-
                     CellValue::Number(number)
                 } else {
                     bail!("Could not parse number: {}", value);
@@ -134,7 +135,7 @@ impl CellValue {
                 .unwrap_or_else(|| CellValue::Text(value.clone())),
         };
 
-        Ok((cell_value, ops))
+        Ok((cell_value, numeric_format))
     }
 }
 
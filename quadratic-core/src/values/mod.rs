@@ -7,6 +7,7 @@ use smallvec::{smallvec, SmallVec};
 mod array;
 mod array_size;
 pub mod arrow;
+pub mod arrow_ipc;
 pub mod cell_values;
 pub mod cellvalue;
 mod convert;
@@ -1,8 +1,15 @@
+use std::sync::Arc;
+
 use anyhow::Result;
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{Field, Schema};
 use bytes::Bytes;
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter};
 
-use crate::{arrow::arrow_col_to_cell_value_vec, CellValue};
+use crate::{
+    arrow::{arrow_col_to_cell_value_vec, cell_values_to_arrow_col_without_union},
+    CellValue,
+};
 
 pub fn parquet_to_vec(file: Vec<u8>) -> Result<Vec<Vec<CellValue>>> {
     if file.is_empty() {
@@ -43,6 +50,29 @@ pub fn parquet_to_vec(file: Vec<u8>) -> Result<Vec<Vec<CellValue>>> {
 
     Ok(output)
 }
+
+/// Convert columns of cell values into a Parquet file, the reverse of
+/// `parquet_to_vec`.  Each column is typed from its values, and mixed
+/// columns are stored as strings since Parquet has no union type.
+pub fn vec_to_parquet(headers: Vec<String>, columns: Vec<Vec<CellValue>>) -> Result<Vec<u8>> {
+    let arrays = columns
+        .iter()
+        .map(|column| cell_values_to_arrow_col_without_union(column))
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    let fields = headers
+        .into_iter()
+        .zip(arrays.iter())
+        .map(|(header, array)| Field::new(header, array.data_type().to_owned(), true))
+        .collect::<Vec<Field>>();
+
+    let schema = Arc::new(Schema::new(fields));
+    let record_batch = RecordBatch::try_new(schema.clone(), arrays)?;
+    let mut writer = ArrowWriter::try_new(vec![], schema, None)?;
+    writer.write(&record_batch)?;
+
+    Ok(writer.into_inner()?)
+}
+
 #[cfg(test)]
 mod test {
     use std::fs::File;
@@ -62,4 +92,46 @@ mod test {
         let _results = parquet_to_vec(buffer);
         // println!("{:?}", results);
     }
+
+    #[test]
+    fn test_vec_to_parquet() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        let columns = vec![
+            vec![CellValue::Number(1.into()), CellValue::Number(2.into())],
+            vec![CellValue::Text("a".into()), CellValue::Number(3.into())],
+            vec![CellValue::Logical(true), CellValue::Logical(false)],
+            vec![CellValue::Date(date), CellValue::Date(date)],
+        ];
+        let headers = vec![
+            "number".into(),
+            "mixed".into(),
+            "bool".into(),
+            "date".into(),
+        ];
+        let parquet = vec_to_parquet(headers, columns).unwrap();
+
+        assert_eq!(
+            parquet_to_vec(parquet).unwrap(),
+            vec![
+                vec![
+                    CellValue::Text("number".into()),
+                    CellValue::Text("mixed".into()),
+                    CellValue::Text("bool".into()),
+                    CellValue::Text("date".into()),
+                ],
+                vec![
+                    CellValue::Number(1.into()),
+                    CellValue::Text("a".into()),
+                    CellValue::Logical(true),
+                    CellValue::Date(date),
+                ],
+                vec![
+                    CellValue::Number(2.into()),
+                    CellValue::Text("3".into()),
+                    CellValue::Logical(false),
+                    CellValue::Date(date),
+                ],
+            ]
+        );
+    }
 }
@@ -12,6 +12,20 @@ impl GridController {
         }
     }
 
+    /// Called after a external calculation is complete, with the output as
+    /// an Arrow IPC stream instead of the JSON result's output_array.
+    #[wasm_bindgen(js_name = "calculationCompleteArrow")]
+    pub fn js_calculation_complete_arrow(&mut self, result: String, output: Vec<u8>) {
+        if let Ok(mut result) =
+            serde_json::from_str::<crate::controller::transaction_types::JsCodeResult>(&result)
+        {
+            result.output_arrow = Some(output);
+            let _ = self.calculation_complete(result);
+        } else {
+            dbgjs!("calculationCompleteArrow: Failed to parse calculation result");
+        }
+    }
+
     #[wasm_bindgen(js_name = "calculationGetCellsA1")]
     pub fn js_calculation_get_cells_a1(
         &mut self,
@@ -31,6 +45,18 @@ impl GridController {
         }
     }
 
+    /// Returns the cells for a getCells call as an Arrow IPC stream.
+    #[wasm_bindgen(js_name = "calculationGetCellsA1Arrow")]
+    pub fn js_calculation_get_cells_a1_arrow(
+        &mut self,
+        transaction_id: String,
+        a1: String,
+        line_number: Option<u32>,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.calculation_get_cells_a1_arrow(transaction_id, a1, line_number)
+            .map_err(|_| JsValue::UNDEFINED)
+    }
+
     /// Returns the code cell (which is a combination of CellValue::Code and CodeRun).
     /// If the cell is part of a code run, it returns the code run that caused the output.
     ///
@@ -48,6 +74,33 @@ impl GridController {
         }
     }
 
+    /// Returns the code cells in a circular reference through the code cell
+    /// at pos, as a stringified Vec<SheetPos>, or null if there isn't one.
+    #[wasm_bindgen(js_name = "getCircularReference")]
+    pub fn js_get_circular_reference(
+        &self,
+        sheet_id: String,
+        pos: String,
+    ) -> Result<JsValue, JsValue> {
+        let pos: Pos = serde_json::from_str(&pos).map_err(|_| JsValue::UNDEFINED)?;
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|_| JsValue::UNDEFINED)?;
+        match self.get_circular_reference(pos.to_sheet_pos(sheet_id)) {
+            Some(cycle) => match serde_json::to_string(&cycle) {
+                Ok(json) => Ok(JsValue::from_str(&json)),
+                Err(_) => Err(JsValue::UNDEFINED),
+            },
+            None => Ok(JsValue::null()),
+        }
+    }
+
+    /// Returns the code cells whose last run took the longest, slowest first,
+    /// as a stringified Vec<JsCodeRunTiming>.
+    #[wasm_bindgen(js_name = "getSlowestCodeCells")]
+    pub fn js_get_slowest_code_cells(&self, limit: u32) -> Result<String, JsValue> {
+        serde_json::to_string(&self.slowest_code_cells(limit as usize))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     /// Sets the code on a cell
     #[wasm_bindgen(js_name = "setCellCode")]
     pub fn js_set_cell_code(
@@ -90,6 +143,53 @@ impl GridController {
         }
     }
 
+    /// Returns the calculation mode of the file.
+    #[wasm_bindgen(js_name = "getCalculationMode")]
+    pub fn js_get_calculation_mode(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.grid().calculation_mode())
+            .map_err(|_| JsValue::UNDEFINED)
+    }
+
+    /// Sets the calculation mode of the file.
+    #[wasm_bindgen(js_name = "setCalculationMode")]
+    pub fn js_set_calculation_mode(
+        &mut self,
+        calculation_mode: JsValue,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let calculation_mode = serde_wasm_bindgen::from_value::<CalculationMode>(calculation_mode)
+            .map_err(|_| JsValue::from_str("Unable to parse CalculationMode"))?;
+        self.set_calculation_mode(calculation_mode, cursor);
+        Ok(())
+    }
+
+    /// Recalculates all stale code cells.
+    #[wasm_bindgen(js_name = "recalculateAll")]
+    pub fn js_recalculate_all(&mut self, cursor: Option<String>) {
+        self.recalculate_all(cursor);
+    }
+
+    /// Recalculates the stale code cells in a sheet.
+    #[wasm_bindgen(js_name = "recalculateSheet")]
+    pub fn js_recalculate_sheet(&mut self, sheet_id: String, cursor: Option<String>) {
+        if let Ok(sheet_id) = SheetId::from_str(&sheet_id) {
+            self.recalculate_sheet(sheet_id, cursor);
+        }
+    }
+
+    /// Recalculates the stale code cells in a selection.
+    #[wasm_bindgen(js_name = "recalculateSelection")]
+    pub fn js_recalculate_selection(
+        &mut self,
+        selection: String,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let selection = serde_json::from_str::<A1Selection>(&selection)
+            .map_err(|_| JsValue::from_str("Unable to parse A1Selection"))?;
+        self.recalculate_selection(&selection, cursor);
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "connectionComplete")]
     pub fn js_connection_complete(
         &mut self,
@@ -98,10 +198,98 @@ impl GridController {
         std_out: Option<String>,
         std_err: Option<String>,
         extra: Option<String>,
+        connection_timing: Option<String>,
     ) -> Result<(), JsValue> {
-        self.connection_complete(transaction_id, data, std_out, std_err, extra)
+        let connection_timing = connection_timing
+            .and_then(|connection_timing| serde_json::from_str(&connection_timing).ok());
+        self.connection_complete(
+            transaction_id,
+            data,
+            std_out,
+            std_err,
+            extra,
+            connection_timing,
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = "connectionCancelled")]
+    pub fn js_connection_cancelled(
+        &mut self,
+        transaction_id: String,
+        timed_out: bool,
+    ) -> Result<(), JsValue> {
+        self.connection_cancelled(transaction_id, timed_out)
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Cancels the code run that an async transaction is waiting on. Returns
+    /// the CodeCellLanguage of the cancelled code run, if any.
+    #[wasm_bindgen(js_name = "cancelTransaction")]
+    pub fn js_cancel_transaction(&mut self, transaction_id: String) -> Result<JsValue, JsValue> {
+        let language = self
+            .cancel_async_transaction(transaction_id)
             .map_err(|e| e.to_string())?;
+        Ok(serde_wasm_bindgen::to_value(&language)?)
+    }
 
+    /// Cancels the code run at pos. Returns the CodeCellLanguage of the
+    /// cancelled code run, if one was waiting there.
+    #[wasm_bindgen(js_name = "cancelCodeRun")]
+    pub fn js_cancel_code_run(
+        &mut self,
+        sheet_id: String,
+        pos: String,
+    ) -> Result<JsValue, JsValue> {
+        let pos: Pos = serde_json::from_str(&pos).map_err(|_| JsValue::UNDEFINED)?;
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|_| JsValue::UNDEFINED)?;
+        let language = self.cancel_code_run(pos.to_sheet_pos(sheet_id));
+        Ok(serde_wasm_bindgen::to_value(&language)?)
+    }
+
+    /// Cancels every code run that an async transaction is waiting on.
+    /// Returns the languages of the cancelled code runs.
+    #[wasm_bindgen(js_name = "cancelAllCodeRuns")]
+    pub fn js_cancel_all_code_runs(&mut self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.cancel_all_code_runs())?)
+    }
+
+    /// Times out code runs that have waited longer than their language's
+    /// timeout. Returns the languages of the code runs that timed out.
+    #[wasm_bindgen(js_name = "checkAsyncTimeouts")]
+    pub fn js_check_async_timeouts(&mut self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.check_async_timeouts())?)
+    }
+
+    /// Sets the timeouts from a stringified AsyncTimeouts.
+    #[wasm_bindgen(js_name = "setAsyncTimeouts")]
+    pub fn js_set_async_timeouts(&mut self, async_timeouts: String) -> Result<(), JsValue> {
+        let async_timeouts = serde_json::from_str(&async_timeouts).map_err(|e| e.to_string())?;
+        self.set_async_timeouts(async_timeouts);
         Ok(())
     }
+
+    /// Varies the input cell until the formula cell evaluates to `target`,
+    /// setting the input cell if a solution is found. Both positions are
+    /// stringified SheetPos. Returns a JsGoalSeekResult.
+    #[wasm_bindgen(js_name = "goalSeek")]
+    pub fn js_goal_seek(
+        &mut self,
+        formula_pos: String,
+        target: f64,
+        input_pos: String,
+        cursor: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let formula_pos: SheetPos =
+            serde_json::from_str(&formula_pos).map_err(|e| e.to_string())?;
+        let input_pos: SheetPos = serde_json::from_str(&input_pos).map_err(|e| e.to_string())?;
+        let result = self
+            .goal_seek(formula_pos, target, input_pos, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
 }
@@ -14,4 +14,16 @@ impl GridController {
             .map_err(|e| e.to_string())?;
         Ok(output)
     }
+
+    /// Returns the Parquet bytes of a selection, for writing back to a
+    /// database connection
+    #[wasm_bindgen(js_name = "exportParquetSelection")]
+    pub fn js_export_parquet_selection(&self, selection: String) -> Result<Vec<u8>, JsValue> {
+        let selection = serde_json::from_str::<A1Selection>(&selection)
+            .map_err(|_| "Unable to parse A1Selection")?;
+        let output = self
+            .export_parquet_selection(&selection)
+            .map_err(|e| e.to_string())?;
+        Ok(output)
+    }
 }
@@ -20,6 +20,7 @@ pub mod sheet_info;
 pub mod sheet_offsets;
 pub mod sheets;
 pub mod summarize;
+pub mod tables;
 pub mod transactions;
 pub mod validation;
 pub mod worker;
@@ -80,6 +81,9 @@ impl GridController {
                             // sends all validations to the client
                             sheet.send_all_validations();
 
+                            // sends all tables to the client
+                            sheet.send_all_tables();
+
                             // sends all validation warnings to the client
                             sheet.send_all_validation_warnings();
 
@@ -0,0 +1,112 @@
+//! WASM functions for Tables
+
+use std::str::FromStr;
+
+use uuid::Uuid;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{controller::GridController, grid::SheetId, A1Selection, Rect};
+
+#[wasm_bindgen]
+impl GridController {
+    /// Returns a stringified version of Vec<Table>
+    #[wasm_bindgen(js_name = "getTables")]
+    pub fn js_tables(&self, sheet_id: String) -> Result<JsValue, JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        Ok(serde_wasm_bindgen::to_value(&self.tables(sheet_id))?)
+    }
+
+    /// Creates a table over a rect. Returns an error if the name is invalid or
+    /// the table overlaps another table.
+    #[wasm_bindgen(js_name = "addTable")]
+    pub fn js_add_table(
+        &mut self,
+        sheet_id: String,
+        rect: String,
+        name: Option<String>,
+        show_header: bool,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        let rect = serde_json::from_str::<Rect>(&rect).map_err(|e| e.to_string())?;
+        self.add_table(rect.to_sheet_rect(sheet_id), name, show_header, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Removes a table
+    #[wasm_bindgen(js_name = "removeTable")]
+    pub fn js_remove_table(
+        &mut self,
+        sheet_id: String,
+        table_id: String,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        let table_id = Uuid::from_str(&table_id).map_err(|e| e.to_string())?;
+        self.remove_table(sheet_id, table_id, cursor);
+        Ok(())
+    }
+
+    /// Renames a table
+    #[wasm_bindgen(js_name = "renameTable")]
+    pub fn js_rename_table(
+        &mut self,
+        sheet_id: String,
+        table_id: String,
+        name: String,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        let table_id = Uuid::from_str(&table_id).map_err(|e| e.to_string())?;
+        self.rename_table(sheet_id, table_id, name, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Renames a column of a table
+    #[wasm_bindgen(js_name = "renameTableColumn")]
+    pub fn js_rename_table_column(
+        &mut self,
+        sheet_id: String,
+        table_id: String,
+        column: u32,
+        name: String,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        let table_id = Uuid::from_str(&table_id).map_err(|e| e.to_string())?;
+        self.rename_table_column(sheet_id, table_id, column as usize, name, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Shows or hides the totals row of a table
+    #[wasm_bindgen(js_name = "setTableTotals")]
+    pub fn js_set_table_totals(
+        &mut self,
+        sheet_id: String,
+        table_id: String,
+        show_totals: bool,
+        cursor: Option<String>,
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(&sheet_id).map_err(|e| e.to_string())?;
+        let table_id = Uuid::from_str(&table_id).map_err(|e| e.to_string())?;
+        self.set_table_totals(sheet_id, table_id, show_totals, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Replaces table references in a selection (stringified A1Selection)
+    /// with the sheet ranges they refer to. Returns a stringified A1Selection.
+    #[wasm_bindgen(js_name = "resolveSelection")]
+    pub fn js_resolve_selection(&self, selection: String) -> Result<String, JsValue> {
+        let selection = serde_json::from_str::<A1Selection>(&selection)
+            .map_err(|_| JsValue::from_str("Unable to parse A1Selection"))?;
+        let Some(sheet) = self.try_sheet(selection.sheet_id) else {
+            return Err(JsValue::from_str("Sheet not found"));
+        };
+        let selection = sheet.resolve_selection(&selection, None);
+        serde_json::to_string(&selection).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
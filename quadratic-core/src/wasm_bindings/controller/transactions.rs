@@ -60,6 +60,16 @@ impl GridController {
         }
     }
 
+    /// Undo a transaction that the server rejected (eg, because it touched a
+    /// range locked by another user).
+    #[wasm_bindgen(js_name = "rejectTransaction")]
+    pub fn js_reject_transaction(&mut self, transaction_id: String) -> Result<(), JsValue> {
+        let transaction_id = Uuid::parse_str(&transaction_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid transaction id: {}", e)))?;
+        self.reject_transaction(transaction_id);
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "applyOfflineUnsavedTransaction")]
     pub fn js_apply_offline_unsaved_transaction(
         &mut self,
@@ -118,6 +118,7 @@ extern "C" {
     pub fn jsRequestRowHeights(transaction_id: String, sheet_id: String, rows: String);
 
     pub fn jsSheetValidations(sheet_id: String, validations: String /* Vec<Validation> */);
+    pub fn jsSheetTables(sheet_id: String, tables: String /* Vec<Table> */);
     pub fn jsValidationWarning(
         sheet_id: String,
         validations: String, /* Vec<(x, y, validation_id, failed) */
@@ -601,6 +602,15 @@ pub fn jsSheetValidations(sheet_id: String, validations: String /* JsValidation
     ));
 }
 
+#[cfg(test)]
+#[allow(non_snake_case)]
+pub fn jsSheetTables(sheet_id: String, tables: String /* Vec<Table> */) {
+    TEST_ARRAY.lock().unwrap().push(TestFunction::new(
+        "jsSheetTables",
+        format!("{},{}", sheet_id, tables),
+    ));
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 pub fn jsRequestRowHeights(
@@ -13,10 +13,16 @@ use axum_extra::{
     TypedHeader,
 };
 use jsonwebtoken::jwk::JwkSet;
-use quadratic_rust_shared::auth::jwt::authorize;
+use quadratic_rust_shared::{
+    auth::jwt::authorize,
+    quadratic_api::{can_edit, can_view, get_file_perms},
+};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::error::{FilesError, Result};
+use crate::state::State;
 
 /// The claims from the Quadratic/Auth0 JWT token.
 /// We need our own implementation of this because we need to impl on it.
@@ -67,5 +73,57 @@ where
     }
 }
 
+/// A request authorized with the M2M token, used by admin endpoints.
+/// Adding this parameter to a handler rejects any other caller.
+#[derive(Debug)]
+pub struct M2mAuth;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for M2mAuth
+where
+    S: Send + Sync,
+{
+    type Rejection = FilesError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|e| FilesError::Authentication(e.to_string()))?;
+
+        let state = parts
+            .extensions
+            .get::<Arc<State>>()
+            .ok_or_else(|| FilesError::InternalServer("State not found".into()))?;
+
+        if bearer.token() != state.settings.m2m_auth_token {
+            return Err(FilesError::Authentication("Invalid M2M token".into()));
+        }
+
+        Ok(M2mAuth)
+    }
+}
+
+/// Ask the API whether the user making the request can view or edit the file.
+/// A valid JWT alone doesn't grant access to any particular file.
+pub(crate) async fn validate_user_can_view_file(
+    state: &State,
+    jwt: &str,
+    file_id: Uuid,
+) -> Result<()> {
+    let (permissions, _) =
+        get_file_perms(&state.settings.quadratic_api_uri, jwt.to_owned(), file_id)
+            .await
+            .map_err(|e| FilesError::FilePermissions(e.to_string()))?;
+
+    if !(can_view(&permissions) || can_edit(&permissions)) {
+        return Err(FilesError::FilePermissions(
+            "You do not have permission to access this file".into(),
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 pub(crate) mod tests {}
@@ -16,6 +16,8 @@ use strum_macros::Display;
 pub(crate) enum StorageType {
     S3,
     FileSystem,
+    AzureBlob,
+    Gcs,
 }
 
 #[allow(dead_code)]
@@ -27,6 +29,8 @@ pub(crate) struct Config {
     pub(crate) files_per_check: i64,
     pub(crate) truncate_file_check_s: i64,
     pub(crate) truncate_transaction_age_days: i64,
+    pub(crate) checkpoint_compact_operations: u64,
+    pub(crate) checkpoint_compact_bytes: u64,
     pub(crate) environment: Environment,
 
     pub(crate) pubsub_host: String,
@@ -34,12 +38,17 @@ pub(crate) struct Config {
     pub(crate) pubsub_password: String,
     pub(crate) pubsub_active_channels: String,
     pub(crate) pubsub_processed_transactions_channel: String,
+    pub(crate) pubsub_dead_letter_channel: String,
+
+    // Ack transactions that can't be decoded rather than stopping at them
+    #[serde(default)]
+    pub(crate) skip_unprocessable_transactions: bool,
 
     pub(crate) auth0_jwks_uri: String,
     pub(crate) quadratic_api_uri: String,
     pub(crate) m2m_auth_token: String,
 
-    // Storage Type: s3 or file-system
+    // Storage Type: s3, file-system, azure-blob or gcs
     pub(crate) storage_type: StorageType,
 
     // StorageType::S3
@@ -51,6 +60,18 @@ pub(crate) struct Config {
     // StorageType::FileSystem
     pub(crate) storage_dir: Option<String>,
     pub(crate) storage_encryption_keys: Option<Vec<String>>,
+
+    // StorageType::AzureBlob
+    pub(crate) azure_storage_account: Option<String>,
+    pub(crate) azure_storage_access_key: Option<String>,
+    pub(crate) azure_storage_container: Option<String>,
+    pub(crate) azure_storage_endpoint: Option<String>,
+
+    // StorageType::Gcs
+    pub(crate) gcs_bucket_name: Option<String>,
+    pub(crate) gcs_endpoint: Option<String>,
+    // The json of a service account key
+    pub(crate) gcs_credentials: Option<String>,
 }
 
 /// Load the global configuration from the environment into Config.
@@ -0,0 +1,384 @@
+//! Dead Letters
+//!
+//! Transactions that can't be decoded are copied to a dead-letter stream with
+//! their file id, sequence number and error.  By default, a file isn't
+//! processed past a dead-lettered transaction until it's replayed, which
+//! retries the file from its last checkpoint.
+//!
+//! Replaying doesn't change the transaction, so it's meant for after a fix to
+//! decoding has been deployed.  A transaction that still can't be decoded
+//! isn't replayed.
+
+use axum::{extract::Path, Extension, Json};
+use chrono::{DateTime, Utc};
+use quadratic_rust_shared::{
+    pubsub::{redis_streams::RedisConnection, PubSub as PubSubTrait},
+    quadratic_api::get_file_checkpoint,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::M2mAuth,
+    error::{FilesError, Result},
+    file::decode_transaction,
+    state::State,
+};
+
+/// The set of dead-lettered transactions, used to add each only once
+fn dead_letter_set(channel: &str) -> String {
+    format!("{channel}-transactions")
+}
+
+fn dead_letter_member(dead_letter: &DeadLetter) -> String {
+    format!("{}-{}", dead_letter.file_id, dead_letter.sequence_num)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DeadLetter {
+    pub(crate) file_id: Uuid,
+    pub(crate) sequence_num: u64,
+    pub(crate) error: String,
+    pub(crate) created_at: DateTime<Utc>,
+    /// The message as it was read from the file's stream
+    pub(crate) message: Vec<u8>,
+}
+
+impl DeadLetter {
+    pub(crate) fn new(
+        file_id: Uuid,
+        sequence_num: u64,
+        error: &FilesError,
+        message: Vec<u8>,
+    ) -> Self {
+        DeadLetter {
+            file_id,
+            sequence_num,
+            error: error.to_string(),
+            created_at: Utc::now(),
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DeadLetterResponse {
+    pub(crate) id: String,
+    pub(crate) file_id: Uuid,
+    pub(crate) sequence_num: u64,
+    pub(crate) error: String,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+impl From<(String, DeadLetter)> for DeadLetterResponse {
+    fn from((id, dead_letter): (String, DeadLetter)) -> Self {
+        DeadLetterResponse {
+            id,
+            file_id: dead_letter.file_id,
+            sequence_num: dead_letter.sequence_num,
+            error: dead_letter.error,
+            created_at: dead_letter.created_at,
+        }
+    }
+}
+
+/// Get all dead letters in the channel, keyed by their stream id
+pub(crate) async fn get_dead_letters(
+    connection: &mut RedisConnection,
+    channel: &str,
+) -> Result<Vec<(String, DeadLetter)>> {
+    let dead_letters = connection
+        .get_messages_from(channel, "-", true)
+        .await?
+        .into_iter()
+        .filter_map(|(id, message)| match serde_json::from_slice(&message) {
+            Ok(dead_letter) => Some((id, dead_letter)),
+            Err(error) => {
+                tracing::warn!("Unable to parse dead letter {id}: {error}");
+                None
+            }
+        })
+        .collect();
+
+    Ok(dead_letters)
+}
+
+/// Add a transaction to the dead-letter channel.  A transaction that is
+/// already dead-lettered isn't added again, since a blocked file re-reads it
+/// each time new transactions arrive.  Returns true if it was added.
+pub(crate) async fn add_dead_letter(
+    connection: &mut RedisConnection,
+    channel: &str,
+    dead_letter: &DeadLetter,
+) -> Result<bool> {
+    let added = connection
+        .add_to_set(&dead_letter_set(channel), &dead_letter_member(dead_letter))
+        .await?;
+
+    if !added {
+        return Ok(false);
+    }
+
+    let message = serde_json::to_vec(dead_letter)?;
+    connection.publish(channel, "*", &message, None).await?;
+
+    tracing::warn!(
+        "Dead-lettered transaction {} for file {}: {}",
+        dead_letter.sequence_num,
+        dead_letter.file_id,
+        dead_letter.error
+    );
+
+    Ok(true)
+}
+
+/// Remove a dead letter and mark its file as active so that it's processed
+/// again from its last checkpoint.  Transactions that were skipped are
+/// already behind the checkpoint, so they can't be replayed, and transactions
+/// that still can't be decoded return the decode error instead of being
+/// replayed.
+pub(crate) async fn replay_dead_letter(
+    state: &Arc<State>,
+    channel: &str,
+    id: &str,
+) -> Result<(String, DeadLetter)> {
+    let settings = &state.settings;
+    let mut pubsub = state.pubsub.lock().await;
+
+    let (id, dead_letter) = get_dead_letters(&mut pubsub.connection, channel)
+        .await?
+        .into_iter()
+        .find(|(key, _)| key == id)
+        .ok_or_else(|| FilesError::NotFound(format!("Dead letter {id} not found")))?;
+
+    let checkpoint_sequence_num = match get_file_checkpoint(
+        &settings.quadratic_api_uri,
+        &settings.m2m_auth_token,
+        &dead_letter.file_id,
+    )
+    .await
+    {
+        Ok(last_checkpoint) => last_checkpoint.sequence_number,
+        Err(_) => 0,
+    };
+
+    if dead_letter.sequence_num <= checkpoint_sequence_num {
+        return Err(FilesError::DeadLetter(format!(
+            "Transaction {} for file {} was skipped, the file is at checkpoint {checkpoint_sequence_num}",
+            dead_letter.sequence_num, dead_letter.file_id
+        )));
+    }
+
+    if let Err(error) = decode_transaction(&dead_letter.message) {
+        return Err(FilesError::DeadLetter(format!(
+            "Transaction {} for file {} still can't be decoded: {error}",
+            dead_letter.sequence_num, dead_letter.file_id
+        )));
+    }
+
+    pubsub
+        .connection
+        .delete(channel, vec![id.as_str()], true)
+        .await?;
+    pubsub
+        .connection
+        .remove_from_set(&dead_letter_set(channel), &dead_letter_member(&dead_letter))
+        .await?;
+    pubsub
+        .connection
+        .upsert_active_channel(
+            &settings.pubsub_active_channels,
+            &dead_letter.file_id.to_string(),
+        )
+        .await?;
+
+    tracing::info!(
+        "Replaying transaction {} for file {}",
+        dead_letter.sequence_num,
+        dead_letter.file_id
+    );
+
+    Ok((id, dead_letter))
+}
+
+/// List dead-lettered transactions
+pub(crate) async fn list_dead_letters(
+    _auth: M2mAuth,
+    state: Extension<Arc<State>>,
+) -> Result<Json<Vec<DeadLetterResponse>>> {
+    let channel = &state.settings.pubsub_dead_letter_channel;
+    let dead_letters = get_dead_letters(&mut state.pubsub.lock().await.connection, channel)
+        .await?
+        .into_iter()
+        .map(DeadLetterResponse::from)
+        .collect();
+
+    Ok(Json(dead_letters))
+}
+
+/// Replay a dead-lettered transaction
+pub(crate) async fn replay(
+    _auth: M2mAuth,
+    Path(id): Path<String>,
+    state: Extension<Arc<State>>,
+) -> Result<Json<DeadLetterResponse>> {
+    let channel = &state.settings.pubsub_dead_letter_channel;
+    let dead_letter = replay_dead_letter(&state, channel, &id).await?;
+
+    Ok(Json(dead_letter.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::new_arc_state;
+    use quadratic_core::controller::{
+        operations::operation::Operation,
+        transaction::{Transaction, TransactionServer},
+    };
+
+    fn dead_letter(file_id: Uuid, sequence_num: u64) -> DeadLetter {
+        let error = FilesError::Serialization("invalid header".into());
+        DeadLetter::new(file_id, sequence_num, &error, vec![1, 2, 3])
+    }
+
+    /// A dead letter whose transaction decodes, as if decoding was fixed
+    fn fixed_dead_letter(file_id: Uuid, sequence_num: u64) -> DeadLetter {
+        let transaction = TransactionServer {
+            id: Uuid::new_v4(),
+            file_id,
+            operations: Transaction::serialize_and_compress(Vec::<Operation>::new()).unwrap(),
+            sequence_num,
+        };
+        let message = Transaction::serialize_and_compress(transaction).unwrap();
+
+        DeadLetter {
+            message,
+            ..dead_letter(file_id, sequence_num)
+        }
+    }
+
+    #[tokio::test]
+    async fn adds_a_dead_letter_once() {
+        let state = new_arc_state().await;
+        let channel = Uuid::new_v4().to_string();
+        let file_id = Uuid::new_v4();
+        let mut pubsub = state.pubsub.lock().await;
+        let connection = &mut pubsub.connection;
+
+        assert!(
+            add_dead_letter(connection, &channel, &dead_letter(file_id, 1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !add_dead_letter(connection, &channel, &dead_letter(file_id, 1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            add_dead_letter(connection, &channel, &dead_letter(file_id, 2))
+                .await
+                .unwrap()
+        );
+
+        let dead_letters = get_dead_letters(connection, &channel).await.unwrap();
+        let sequence_nums = dead_letters
+            .iter()
+            .map(|(_, dead_letter)| dead_letter.sequence_num)
+            .collect::<Vec<_>>();
+
+        assert_eq!(sequence_nums, vec![1, 2]);
+        assert_eq!(dead_letters[0].1.message, vec![1, 2, 3]);
+        assert_eq!(
+            dead_letters[0].1.error,
+            "Error serializing or deserializing: invalid header"
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_a_dead_letter() {
+        let state = new_arc_state().await;
+        let channel = Uuid::new_v4().to_string();
+        let file_id = Uuid::new_v4();
+        let active_channels = &state.settings.pubsub_active_channels;
+
+        add_dead_letter(
+            &mut state.pubsub.lock().await.connection,
+            &channel,
+            &fixed_dead_letter(file_id, 1),
+        )
+        .await
+        .unwrap();
+
+        let (id, _) = get_dead_letters(&mut state.pubsub.lock().await.connection, &channel)
+            .await
+            .unwrap()
+            .remove(0);
+
+        let (_, replayed) = replay_dead_letter(&state, &channel, &id).await.unwrap();
+        assert_eq!(replayed.file_id, file_id);
+
+        let mut pubsub = state.pubsub.lock().await;
+        let dead_letters = get_dead_letters(&mut pubsub.connection, &channel)
+            .await
+            .unwrap();
+        let active = pubsub
+            .connection
+            .active_channels(active_channels)
+            .await
+            .unwrap();
+
+        assert!(dead_letters.is_empty());
+        assert!(active.contains(&file_id.to_string()));
+
+        pubsub
+            .connection
+            .remove_active_channel(active_channels, &file_id.to_string())
+            .await
+            .unwrap();
+        drop(pubsub);
+
+        // a dead letter that is no longer in the channel can't be replayed
+        let replayed = replay_dead_letter(&state, &channel, &id).await;
+        assert!(matches!(replayed, Err(FilesError::NotFound(_))));
+
+        // the transaction can be dead-lettered again if it fails again
+        assert!(add_dead_letter(
+            &mut state.pubsub.lock().await.connection,
+            &channel,
+            &fixed_dead_letter(file_id, 1),
+        )
+        .await
+        .unwrap());
+    }
+
+    #[tokio::test]
+    async fn does_not_replay_a_transaction_that_still_fails() {
+        let state = new_arc_state().await;
+        let channel = Uuid::new_v4().to_string();
+        let file_id = Uuid::new_v4();
+        let mut pubsub = state.pubsub.lock().await;
+
+        add_dead_letter(&mut pubsub.connection, &channel, &dead_letter(file_id, 1))
+            .await
+            .unwrap();
+
+        let (id, _) = get_dead_letters(&mut pubsub.connection, &channel)
+            .await
+            .unwrap()
+            .remove(0);
+        drop(pubsub);
+
+        let replayed = replay_dead_letter(&state, &channel, &id).await;
+        assert!(
+            matches!(replayed, Err(FilesError::DeadLetter(error)) if error.contains("still can't be decoded"))
+        );
+
+        let dead_letters = get_dead_letters(&mut state.pubsub.lock().await.connection, &channel)
+            .await
+            .unwrap();
+        assert_eq!(dead_letters.len(), 1);
+    }
+}
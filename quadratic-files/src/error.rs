@@ -29,9 +29,15 @@ pub enum FilesError {
     #[error("Connection error: {0}")]
     Connection(String),
 
+    #[error("Dead letter error: {0}")]
+    DeadLetter(String),
+
     #[error("Unable to export file {0}: {1}")]
     ExportFile(String, String),
 
+    #[error("File permissions error: {0}")]
+    FilePermissions(String),
+
     #[error("Unable to import file {0}: {1}")]
     ImportFile(String, String),
 
@@ -78,6 +84,8 @@ impl IntoResponse for FilesError {
     fn into_response(self) -> Response {
         let (status, error) = match &self {
             FilesError::Authentication(error) => (StatusCode::UNAUTHORIZED, clean_errors(error)),
+            FilesError::DeadLetter(error) => (StatusCode::BAD_REQUEST, clean_errors(error)),
+            FilesError::FilePermissions(error) => (StatusCode::FORBIDDEN, clean_errors(error)),
             FilesError::InternalServer(error) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, clean_errors(error))
             }
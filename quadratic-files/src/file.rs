@@ -1,6 +1,7 @@
 use chrono::Utc;
 use serde::de::DeserializeOwned;
 use std::sync::Arc;
+use std::time::Instant;
 use uuid::Uuid;
 
 use quadratic_core::{
@@ -10,17 +11,21 @@ use quadratic_core::{
         GridController,
     },
     grid::{
-        file::{export, import, CURRENT_VERSION},
+        file::{
+            delta::{append_delta, delta_summary, DeltaChunk, DeltaSummary},
+            export, import, CURRENT_VERSION,
+        },
         Grid,
     },
 };
 use quadratic_rust_shared::{
     pubsub::PubSub as PubSubTrait,
     quadratic_api::{get_file_checkpoint, set_file_checkpoint},
-    storage::{Storage, StorageContainer},
+    storage::Storage,
 };
 
 use crate::{
+    dead_letter::{add_dead_letter, DeadLetter},
     error::{FilesError, Result},
     state::{settings::Settings, State},
     truncate::{add_processed_transaction, processed_transaction_key},
@@ -43,43 +48,65 @@ pub(crate) fn apply_transaction(grid: &mut GridController, operations: Vec<Opera
     grid.server_apply_transaction(operations, None)
 }
 
-/// Exports a .grid file
-pub(crate) async fn get_and_load_object(
-    storage: &StorageContainer,
-    key: &str,
-    sequence_num: u64,
-) -> Result<GridController> {
-    let body = storage
-        .read(key)
-        .await
-        .map_err(|e| FilesError::LoadFile(key.into(), e.to_string()))?;
-    let grid = load_file(key, body.to_vec())?;
-
-    Ok(GridController::from_grid(grid, sequence_num))
-}
-
 pub(crate) fn key(file_id: Uuid, sequence: u64) -> String {
     format!("{file_id}-{sequence}.grid")
 }
 
-/// Load a file from S3, add it to memory, process transactions and upload it back to S3
+/// Compact a delta checkpoint into a new snapshot once its chunks, including
+/// the operations about to be added, reach either limit
+pub(crate) fn should_compact(
+    summary: DeltaSummary,
+    operations: usize,
+    max_operations: u64,
+    max_bytes: u64,
+) -> bool {
+    summary.operations + operations as u64 >= max_operations || summary.bytes as u64 >= max_bytes
+}
+
+/// Load the checkpoint from storage, add the operations and upload it back to
+/// storage.  The operations are appended to the checkpoint as a delta, unless
+/// it's time to compact, in which case the grid is loaded, the operations are
+/// applied and the full file is written.
 pub(crate) async fn process_transactions(
-    storage: &StorageContainer,
+    settings: &Settings,
     file_id: Uuid,
     checkpoint_sequence_num: u64,
     final_sequence_num: u64,
     operations: Vec<Operation>,
 ) -> Result<u64> {
-    let mut grid = get_and_load_object(
-        storage,
-        &key(file_id, checkpoint_sequence_num),
-        checkpoint_sequence_num,
-    )
-    .await?;
+    let storage = &settings.storage;
+    let checkpoint_key = key(file_id, checkpoint_sequence_num);
+    let checkpoint = storage
+        .read(&checkpoint_key)
+        .await
+        .map_err(|e| FilesError::LoadFile(checkpoint_key.to_owned(), e.to_string()))?
+        .to_vec();
+    let summary = delta_summary(&checkpoint)
+        .map_err(|e| FilesError::ImportFile(checkpoint_key.to_owned(), e.to_string()))?;
     let key = key(file_id, final_sequence_num);
 
-    apply_transaction(&mut grid, operations);
-    let body = export_file(&key, grid.into_grid())?;
+    let compact = should_compact(
+        summary,
+        operations.len(),
+        settings.checkpoint_compact_operations,
+        settings.checkpoint_compact_bytes,
+    );
+
+    let body = if compact {
+        let grid = load_file(&checkpoint_key, checkpoint)?;
+        let mut grid = GridController::from_grid(grid, checkpoint_sequence_num);
+
+        apply_transaction(&mut grid, operations);
+        export_file(&key, grid.into_grid())?
+    } else {
+        let chunk = DeltaChunk {
+            sequence_num: final_sequence_num,
+            operations,
+        };
+
+        append_delta(checkpoint, &chunk)
+            .map_err(|e| FilesError::ExportFile(key.to_owned(), e.to_string()))?
+    };
 
     storage.write(&key, &body.into()).await?;
 
@@ -95,6 +122,7 @@ pub(crate) async fn process_queue_for_room(
     let start = Utc::now();
     let channel = &file_id.to_string();
 
+    let settings = &state.settings;
     let Settings {
         storage,
         quadratic_api_uri,
@@ -115,27 +143,71 @@ pub(crate) async fn process_queue_for_room(
     pubsub.connection.subscribe(channel, GROUP_NAME).await?;
 
     // get all transactions for the room in the queue
-    let transactions = pubsub
+    let messages = pubsub
         .connection
         .get_messages_from(channel, &(checkpoint_sequence_num + 1).to_string(), false)
-        .await?
-        .into_iter()
-        .flat_map(|(_, message)| decompress_and_deserialize::<TransactionServer>(message))
-        .collect::<Vec<TransactionServer>>();
+        .await?;
 
-    tracing::trace!(
-        "Found {} transaction(s) for room {file_id}",
-        transactions.len()
-    );
+    tracing::trace!("Found {} transaction(s) for room {file_id}", messages.len());
 
-    if transactions.is_empty() {
+    if messages.is_empty() {
         return Ok(None);
     }
 
-    let sequence_numbers = transactions
-        .iter()
-        .map(|transaction| transaction.sequence_num)
-        .collect::<Vec<u64>>();
+    let mut sequence_numbers = vec![];
+    let mut operations = vec![];
+
+    for (id, message) in messages {
+        match decode_transaction(&message) {
+            Ok((sequence_num, transaction_operations)) => {
+                sequence_numbers.push(sequence_num);
+                operations.extend(transaction_operations);
+            }
+            Err(error) => {
+                let sequence_num = id.parse::<u64>().map_err(|_| {
+                    FilesError::TransactionQueue(format!("Invalid sequence number {id}"))
+                })?;
+                let dead_letter = DeadLetter::new(file_id, sequence_num, &error, message);
+
+                if add_dead_letter(
+                    &mut pubsub.connection,
+                    &state.settings.pubsub_dead_letter_channel,
+                    &dead_letter,
+                )
+                .await?
+                {
+                    state.metrics.inc_counter(
+                        "quadratic_files_dead_letters_total",
+                        "Transactions moved to the dead-letter stream",
+                        &[],
+                        1.0,
+                    );
+                }
+
+                // don't advance the checkpoint past the gap unless configured
+                // to skip it, the file is processed again after a replay
+                if !state.settings.skip_unprocessable_transactions {
+                    tracing::warn!(
+                        "Stopped processing file {file_id} at transaction {sequence_num}"
+                    );
+                    break;
+                }
+
+                sequence_numbers.push(sequence_num);
+            }
+        }
+    }
+
+    if sequence_numbers.is_empty() {
+        // the first transaction is dead-lettered, so wait for new transactions
+        // or a replay to process the file again
+        pubsub
+            .connection
+            .remove_active_channel(active_channels, channel)
+            .await?;
+
+        return Ok(None);
+    }
 
     let first_sequence_num = sequence_numbers
         .first()
@@ -147,23 +219,9 @@ pub(crate) async fn process_queue_for_room(
         .cloned()
         .ok_or_else(|| FilesError::Unknown("No transactions to process".into()))?;
 
-    // combine all operations into a single vec
-    let operations = transactions
-        .into_iter()
-        .flat_map(|transaction| {
-            // tracing::info!(
-            //     "Processing transaction {}, sequence number {} for room {file_id}",
-            //     transaction.id,
-            //     transaction.sequence_num
-            // );
-            decompress_and_deserialize::<Vec<Operation>>(transaction.operations)
-        })
-        .flatten()
-        .collect::<Vec<Operation>>();
-
     // process the transactions and save the file to S3
     let last_sequence_num = process_transactions(
-        storage,
+        settings,
         file_id,
         checkpoint_sequence_num,
         last_sequence_num,
@@ -254,21 +312,52 @@ pub(crate) async fn process(state: &Arc<State>, active_channels: &str) -> Result
 
         // process file in a separate thread
         tokio::spawn(async move {
-            // TODO(ddimaria): instead of logging the error, move the file to a dead letter queue
-            if let Err(error) = process_queue_for_room(&state, file_id, &active_channels).await {
-                tracing::error!("Error processing file {file_id}: {error}");
-            };
+            let start = Instant::now();
+            let processed = process_queue_for_room(&state, file_id, &active_channels).await;
+
+            state.metrics.observe_duration(
+                "quadratic_files_process_file_seconds",
+                "Time to apply a file's queued transactions and save it",
+                &[],
+                start.elapsed(),
+            );
+
+            match processed {
+                Ok(_) => state.metrics.inc_counter(
+                    "quadratic_files_processed_files_total",
+                    "Files processed from the queue",
+                    &[],
+                    1.0,
+                ),
+                Err(error) => {
+                    tracing::error!("Error processing file {file_id}: {error}");
+                    state.metrics.inc_counter(
+                        "quadratic_files_failures_total",
+                        "Failures processing or truncating the queue",
+                        &[("task", "process")],
+                        1.0,
+                    );
+                }
+            }
         });
     }
 
     Ok(())
 }
 
-fn decompress_and_deserialize<T: DeserializeOwned>(data: Vec<u8>) -> Result<T> {
-    Transaction::decompress_and_deserialize::<T>(&data)
+fn decompress_and_deserialize<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    Transaction::decompress_and_deserialize::<T>(data)
         .map_err(|e| FilesError::Serialization(e.to_string()))
 }
 
+/// Decode a message from the queue into its sequence number and operations
+pub(crate) fn decode_transaction(message: &[u8]) -> Result<(u64, Vec<Operation>)> {
+    let transaction = decompress_and_deserialize::<TransactionServer>(message)?;
+    let operations = decompress_and_deserialize::<Vec<Operation>>(&transaction.operations)?;
+
+    Ok((transaction.sequence_num, operations))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -318,6 +407,22 @@ mod tests {
         assert!(grid.is_ok());
     }
 
+    #[test]
+    fn compacts_after_either_limit() {
+        let summary = DeltaSummary {
+            chunks: 2,
+            operations: 8,
+            bytes: 100,
+        };
+
+        assert!(!should_compact(summary, 1, 10, 1000));
+        assert!(should_compact(summary, 2, 10, 1000));
+        assert!(should_compact(summary, 1, 10, 100));
+
+        // a snapshot is compacted when a single batch is over the limit
+        assert!(should_compact(DeltaSummary::default(), 10, 10, 1000));
+    }
+
     #[tokio::test]
     async fn processes_a_file() {
         // let state = new_arc_state().await;
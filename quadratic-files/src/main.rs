@@ -5,6 +5,7 @@
 
 mod auth;
 mod config;
+mod dead_letter;
 mod error;
 mod file;
 mod server;
@@ -13,6 +14,7 @@ mod storage;
 #[cfg(test)]
 mod test_util;
 mod truncate;
+mod version;
 
 use error::Result;
 
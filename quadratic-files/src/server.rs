@@ -3,11 +3,15 @@
 //! Handle bootstrapping and starting the HTTP server.  Adds global state
 //! to be shared across all requests and threads.  Adds tracing/logging.
 
-use axum::http::{Method, StatusCode};
+use axum::http::{header::CONTENT_TYPE, Method, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
-use axum::{routing::get, Extension, Router};
+use axum::{
+    routing::{get, post},
+    Extension, Router,
+};
 use quadratic_rust_shared::auth::jwt::get_jwks;
+use quadratic_rust_shared::metrics::METRICS_CONTENT_TYPE;
 use quadratic_rust_shared::storage::Storage;
 use std::time::Duration;
 use std::{net::SocketAddr, sync::Arc};
@@ -16,10 +20,12 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use crate::dead_letter::{list_dead_letters, replay};
 use crate::file::get_files_to_process;
 use crate::state::stats::StatsResponse;
 use crate::storage::{get_presigned_storage, get_storage};
 use crate::truncate::truncate_processed_transactions;
+use crate::version::{get_checkpoints, get_diff, get_version};
 use crate::{
     auth::get_middleware,
     config::config,
@@ -64,6 +70,15 @@ pub(crate) fn app(state: Arc<State>) -> Router {
                 .post(upload_storage),
         )
         //
+        // list the checkpoints of a file
+        .route("/files/:file_id/checkpoints", get(get_checkpoints))
+        //
+        // get a file at a sequence number
+        .route("/files/:file_id/versions/:sequence_num", get(get_version))
+        //
+        // diff two versions of a file
+        .route("/files/:file_id/diff", get(get_diff))
+        //
         // auth middleware
         .route_layer(auth)
         //
@@ -75,9 +90,20 @@ pub(crate) fn app(state: Arc<State>) -> Router {
         // stats
         .route("/stats", get(stats))
         //
+        // prometheus metrics
+        .route("/metrics", get(metrics))
+        //
         // presigned urls
         .route("/storage/presigned/:key", get(get_presigned_storage))
         //
+        // ADMIN ROUTES (via M2M token)
+        //
+        // list dead-lettered transactions
+        .route("/admin/dead-letters", get(list_dead_letters))
+        //
+        // replay a dead-lettered transaction
+        .route("/admin/dead-letters/:id/replay", post(replay))
+        //
         // state
         .layer(Extension(state))
         //
@@ -217,10 +243,33 @@ pub(crate) async fn stats(state: Extension<Arc<State>>) -> impl IntoResponse {
     Json(response)
 }
 
+pub(crate) async fn metrics(state: Extension<Arc<State>>) -> impl IntoResponse {
+    let stats = state.stats.lock().await.to_owned();
+
+    state.metrics.set_gauge(
+        "quadratic_files_files_to_process",
+        "Files with transactions waiting in the queue",
+        &[],
+        stats.files_to_process_in_pubsub as f64,
+    );
+    state.metrics.set_gauge(
+        "quadratic_files_channels_to_truncate",
+        "Processed transaction channels waiting to be truncated",
+        &[],
+        stats.channels_to_truncate_in_pubsub as f64,
+    );
+
+    (
+        [(CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        state.metrics.render(),
+    )
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::test_util::{new_arc_state, response};
     use axum::http::Method;
+    use tower::util::ServiceExt;
 
     use super::*;
 
@@ -232,6 +281,49 @@ pub(crate) mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn responds_with_metrics() {
+        let state = new_arc_state().await;
+        let app = app(state);
+        let response = response(app, Method::GET, "/metrics").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("quadratic_files_files_to_process 0"));
+    }
+
+    #[tokio::test]
+    async fn requires_the_m2m_token_for_dead_letters() {
+        let state = new_arc_state().await;
+        let response = response(app(Arc::clone(&state)), Method::GET, "/admin/dead-letters").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = |token: &str| {
+            axum::http::Request::builder()
+                .method(Method::GET)
+                .uri("/admin/dead-letters")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = app(Arc::clone(&state))
+            .oneshot(request("not the token"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let token = &state.settings.m2m_auth_token;
+        let response = app(Arc::clone(&state))
+            .oneshot(request(token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn responds_with_a_200_ok_for_stats() {
         let state = new_arc_state().await;
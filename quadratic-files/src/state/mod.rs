@@ -8,6 +8,7 @@ pub mod settings;
 pub mod stats;
 
 use jsonwebtoken::jwk::JwkSet;
+use quadratic_rust_shared::metrics::Metrics;
 use quadratic_rust_shared::pubsub::redis_streams::RedisStreamsConfig;
 use quadratic_rust_shared::pubsub::Config as PubSubConfig;
 use tokio::sync::Mutex;
@@ -24,6 +25,7 @@ pub(crate) struct State {
     pub(crate) pubsub: Mutex<PubSub>,
     pub(crate) settings: Settings,
     pub(crate) stats: Mutex<Stats>,
+    pub(crate) metrics: Metrics,
 }
 
 impl State {
@@ -39,6 +41,7 @@ impl State {
             pubsub: Mutex::new(PubSub::new(pubsub_config).await?),
             settings: Settings::new(config, jwks).await,
             stats: Mutex::new(Stats::new()),
+            metrics: Metrics::new(),
         })
     }
 }
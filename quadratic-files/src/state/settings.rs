@@ -1,7 +1,9 @@
 use jsonwebtoken::jwk::JwkSet;
 use quadratic_rust_shared::aws::client;
 use quadratic_rust_shared::environment::Environment;
+use quadratic_rust_shared::storage::azure_blob::{AzureBlob, AzureBlobConfig};
 use quadratic_rust_shared::storage::file_system::{FileSystem, FileSystemConfig};
+use quadratic_rust_shared::storage::gcs::{Gcs, GcsConfig};
 use quadratic_rust_shared::storage::s3::{S3Config, S3};
 use quadratic_rust_shared::storage::StorageContainer;
 
@@ -13,7 +15,14 @@ pub(crate) struct Settings {
     pub(crate) quadratic_api_uri: String,
     pub(crate) m2m_auth_token: String,
     pub(crate) storage: StorageContainer,
+    // keys shared with the API to decrypt the file names in presigned URLs
+    pub(crate) storage_encryption_keys: Vec<String>,
+    pub(crate) checkpoint_compact_operations: u64,
+    pub(crate) checkpoint_compact_bytes: u64,
+    pub(crate) pubsub_active_channels: String,
     pub(crate) pubsub_processed_transactions_channel: String,
+    pub(crate) pubsub_dead_letter_channel: String,
+    pub(crate) skip_unprocessable_transactions: bool,
 }
 
 impl Settings {
@@ -26,6 +35,7 @@ impl Settings {
             val.to_owned()
                 .unwrap_or_else(|| panic!("Expected {} to have a value", var))
         };
+        let optional = |val: &Option<String>| val.to_owned().filter(|val| !val.is_empty());
 
         let storage = match config.storage_type {
             StorageType::S3 => StorageContainer::S3(S3::new(S3Config {
@@ -48,6 +58,25 @@ impl Settings {
                         .expect("Expected STORAGE_ENCRYPTION_KEYS to have a value"),
                 }))
             }
+            StorageType::AzureBlob => {
+                StorageContainer::AzureBlob(AzureBlob::new(AzureBlobConfig {
+                    account: expected(&config.azure_storage_account, "AZURE_STORAGE_ACCOUNT"),
+                    access_key: expected(
+                        &config.azure_storage_access_key,
+                        "AZURE_STORAGE_ACCESS_KEY",
+                    ),
+                    container: expected(&config.azure_storage_container, "AZURE_STORAGE_CONTAINER"),
+                    endpoint: optional(&config.azure_storage_endpoint),
+                }))
+            }
+            StorageType::Gcs => StorageContainer::Gcs(Gcs::new(GcsConfig {
+                bucket: expected(&config.gcs_bucket_name, "GCS_BUCKET_NAME"),
+                endpoint: optional(&config.gcs_endpoint),
+                credentials: optional(&config.gcs_credentials).map(|credentials| {
+                    serde_json::from_str(&credentials)
+                        .expect("Expected GCS_CREDENTIALS to be a service account key")
+                }),
+            })),
         };
 
         Settings {
@@ -55,9 +84,18 @@ impl Settings {
             quadratic_api_uri: config.quadratic_api_uri.to_owned(),
             m2m_auth_token: config.m2m_auth_token.to_owned(),
             storage,
+            storage_encryption_keys: config
+                .storage_encryption_keys
+                .to_owned()
+                .unwrap_or_default(),
+            checkpoint_compact_operations: config.checkpoint_compact_operations,
+            checkpoint_compact_bytes: config.checkpoint_compact_bytes,
+            pubsub_active_channels: config.pubsub_active_channels.to_owned(),
             pubsub_processed_transactions_channel: config
                 .pubsub_processed_transactions_channel
                 .to_owned(),
+            pubsub_dead_letter_channel: config.pubsub_dead_letter_channel.to_owned(),
+            skip_unprocessable_transactions: config.skip_unprocessable_transactions,
         }
     }
 }
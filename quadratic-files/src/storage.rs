@@ -1,7 +1,7 @@
 use axum::{
     body::to_bytes,
     extract::{Path, Request},
-    response::IntoResponse,
+    response::{IntoResponse, Redirect},
     Extension, Json,
 };
 use quadratic_rust_shared::{
@@ -9,11 +9,14 @@ use quadratic_rust_shared::{
     storage::{Storage, StorageConfig},
 };
 use serde::Serialize;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::error::{FilesError, Result};
 use crate::state::State;
 
+// how long the cloud storage URLs we redirect to are valid for
+const PRESIGNED_URL_EXPIRATION: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Serialize)]
 pub(crate) struct UploadStorageResponse {
     bucket: String,
@@ -38,23 +41,38 @@ pub(crate) async fn get_presigned_storage(
 ) -> Result<impl IntoResponse> {
     tracing::trace!("Get presigned file {}", encrypted_file_name);
 
-    match state.settings.storage.config() {
-        StorageConfig::FileSystem(config) => {
-            // For now, we only support one encryption key.
-            // In the future, implement key traversal on decryption failures.
-            let key = config
-                .encryption_keys
-                .first()
-                .ok_or(FilesError::Storage("No encryption keys found".to_string()))?;
-            let file_name = decrypt_from_api(key, &encrypted_file_name)?;
-            let file = state.settings.storage.read(&file_name).await?;
-
-            Ok(file.into_response())
-        }
-        _ => Err(FilesError::Storage(
-            "Presigned URLs only supported in FileSystem storage options".to_string(),
-        )),
+    let config = state.settings.storage.config();
+
+    // S3 URLs are presigned by the API
+    if let StorageConfig::S3(_) = config {
+        return Err(FilesError::Storage(
+            "Presigned URLs are not supported for S3 storage".to_string(),
+        ));
+    }
+
+    // For now, we only support one encryption key.
+    // In the future, implement key traversal on decryption failures.
+    let key = state
+        .settings
+        .storage_encryption_keys
+        .first()
+        .ok_or(FilesError::Storage("No encryption keys found".to_string()))?;
+    let file_name = decrypt_from_api(key, &encrypted_file_name)?;
+
+    if let StorageConfig::FileSystem(_) = config {
+        let file = state.settings.storage.read(&file_name).await?;
+
+        return Ok(file.into_response());
     }
+
+    // Azure Blob Storage and GCS serve the file from a signed URL
+    let url = state
+        .settings
+        .storage
+        .presigned_url(&file_name, PRESIGNED_URL_EXPIRATION)
+        .await?;
+
+    Ok(Redirect::temporary(&url).into_response())
 }
 
 /// Upload a file to storage
@@ -140,6 +140,12 @@ pub(crate) async fn truncate_processed_transactions(
             truncate_processed_transaction(state, channel, key, &file_id, &sequence_num).await
         {
             tracing::error!("Error truncating channel {file_id}.{sequence_num}: {error}");
+            state.metrics.inc_counter(
+                "quadratic_files_failures_total",
+                "Failures processing or truncating the queue",
+                &[("task", "truncate")],
+                1.0,
+            );
         };
     }
 
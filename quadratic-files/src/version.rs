@@ -0,0 +1,468 @@
+//! Version History
+//!
+//! Every processed batch of transactions is saved as a checkpoint under
+//! `{file_id}-{sequence_num}.grid`.  A version at any sequence number can be
+//! materialized by loading the nearest checkpoint at or before it and
+//! replaying the transactions that are still retained in the file's stream
+//! (see `truncate.rs` for how long they are kept).
+
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+    Extension, Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use quadratic_core::{
+    controller::GridController,
+    grid::{Grid, Sheet},
+    Pos, Rect,
+};
+use quadratic_rust_shared::{pubsub::PubSub as PubSubTrait, storage::Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::validate_user_can_view_file,
+    error::{FilesError, Result},
+    file::{apply_transaction, decode_transaction, export_file, key, load_file},
+    state::State,
+};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Checkpoint {
+    pub(crate) sequence_num: u64,
+    pub(crate) key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SheetChange {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct SheetDiff {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) change: SheetChange,
+    pub(crate) cells_from: usize,
+    pub(crate) cells_to: usize,
+    /// Ranges of cells whose values changed, e.g. "A1:B3"
+    pub(crate) changed_ranges: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct VersionDiff {
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+    pub(crate) sheets: Vec<SheetDiff>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DiffQuery {
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+}
+
+/// Parse the sequence number from a checkpoint key
+fn checkpoint_sequence_num(file_id: Uuid, key: &str) -> Option<u64> {
+    key.strip_prefix(&format!("{file_id}-"))?
+        .strip_suffix(".grid")?
+        .parse()
+        .ok()
+}
+
+/// List the checkpoints in storage for a file, oldest first
+pub(crate) async fn list_checkpoints(state: &Arc<State>, file_id: Uuid) -> Result<Vec<Checkpoint>> {
+    let mut checkpoints = state
+        .settings
+        .storage
+        .list(&format!("{file_id}-"))
+        .await?
+        .into_iter()
+        .filter_map(|key| {
+            checkpoint_sequence_num(file_id, &key)
+                .map(|sequence_num| Checkpoint { sequence_num, key })
+        })
+        .collect::<Vec<_>>();
+
+    checkpoints.sort_by_key(|checkpoint| checkpoint.sequence_num);
+
+    Ok(checkpoints)
+}
+
+/// Find the latest checkpoint at or before the sequence number
+fn nearest_checkpoint(checkpoints: &[Checkpoint], sequence_num: u64) -> Option<&Checkpoint> {
+    checkpoints
+        .iter()
+        .filter(|checkpoint| checkpoint.sequence_num <= sequence_num)
+        .max_by_key(|checkpoint| checkpoint.sequence_num)
+}
+
+/// Load the grid as it was at the sequence number
+pub(crate) async fn materialize(
+    state: &Arc<State>,
+    file_id: Uuid,
+    sequence_num: u64,
+) -> Result<Grid> {
+    let checkpoints = list_checkpoints(state, file_id).await?;
+    let checkpoint = nearest_checkpoint(&checkpoints, sequence_num).ok_or_else(|| {
+        FilesError::NotFound(format!(
+            "No checkpoint for file {file_id} at or before sequence number {sequence_num}"
+        ))
+    })?;
+
+    let body = state.settings.storage.read(&checkpoint.key).await?;
+
+    if checkpoint.sequence_num == sequence_num {
+        let key = checkpoint.key.to_owned();
+        return blocking(move || load_file(&key, body.to_vec())).await;
+    }
+
+    // replay the retained transactions after the checkpoint
+    let messages = state
+        .pubsub
+        .lock()
+        .await
+        .connection
+        .get_messages_from(
+            &file_id.to_string(),
+            &(checkpoint.sequence_num + 1).to_string(),
+            false,
+        )
+        .await?;
+
+    let key = checkpoint.key.to_owned();
+    let checkpoint_sequence_num = checkpoint.sequence_num;
+
+    // loading and replaying are CPU bound, so keep them off the executor
+    blocking(move || {
+        let grid = load_file(&key, body.to_vec())?;
+        let mut grid = GridController::from_grid(grid, checkpoint_sequence_num);
+        let mut expected = checkpoint_sequence_num + 1;
+
+        for (_, message) in messages {
+            let (transaction_sequence_num, operations) = decode_transaction(&message)?;
+
+            // stop at the target or at a gap in the retained transactions
+            if transaction_sequence_num > sequence_num || transaction_sequence_num != expected {
+                break;
+            }
+
+            apply_transaction(&mut grid, operations);
+            expected += 1;
+        }
+
+        if expected <= sequence_num {
+            return Err(FilesError::NotFound(format!(
+                "Transactions {expected} - {sequence_num} for file {file_id} are no longer retained"
+            )));
+        }
+
+        Ok(grid.into_grid())
+    })
+    .await
+}
+
+/// Run CPU bound work on the blocking thread pool
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| FilesError::InternalServer(e.to_string()))?
+}
+
+/// Positions of cells with values, including the output of code runs
+fn cell_positions(sheet: &Sheet) -> BTreeSet<Pos> {
+    let values = sheet.iter_columns().flat_map(|(x, column)| {
+        column
+            .values
+            .iter()
+            .filter(|(_, value)| !value.is_blank_or_empty_string())
+            .map(|(y, _)| Pos { x: *x, y: *y })
+    });
+    let code_runs = sheet
+        .code_runs
+        .iter()
+        .flat_map(|(pos, code_run)| code_run.output_rect(*pos, false).iter());
+
+    values
+        .chain(code_runs)
+        .filter(|pos| {
+            sheet
+                .display_value(*pos)
+                .is_some_and(|value| !value.is_blank_or_empty_string())
+        })
+        .collect()
+}
+
+/// Group positions into rectangles: consecutive cells in a row are joined,
+/// then runs with the same columns in consecutive rows are joined.
+fn group_into_rects(positions: &BTreeSet<Pos>) -> Vec<Rect> {
+    let by_row = positions
+        .iter()
+        .map(|pos| (pos.y, pos.x))
+        .collect::<BTreeSet<_>>();
+    let mut runs: Vec<Rect> = vec![];
+
+    for (y, x) in by_row {
+        match runs.last_mut() {
+            Some(run) if run.max.y == y && run.max.x + 1 == x => run.max.x = x,
+            _ => runs.push(Rect::single_pos(Pos { x, y })),
+        }
+    }
+
+    let mut rects: Vec<Rect> = vec![];
+
+    // rects that end on the previous row, keyed by their columns
+    let mut open: HashMap<(i64, i64), usize> = HashMap::new();
+    let mut current: HashMap<(i64, i64), usize> = HashMap::new();
+    let mut row = None;
+
+    for run in runs {
+        if row != Some(run.min.y) {
+            open = if row.is_some_and(|y| y + 1 == run.min.y) {
+                std::mem::take(&mut current)
+            } else {
+                current.clear();
+                HashMap::new()
+            };
+            row = Some(run.min.y);
+        }
+
+        let columns = (run.min.x, run.max.x);
+        let index = match open.get(&columns) {
+            Some(&index) => {
+                rects[index].max.y = run.max.y;
+                index
+            }
+            None => {
+                rects.push(run);
+                rects.len() - 1
+            }
+        };
+
+        current.insert(columns, index);
+    }
+
+    rects
+}
+
+/// A1 notation for a range, or a single cell
+fn range_a1_string(rect: &Rect) -> String {
+    if rect.min == rect.max {
+        rect.min.a1_string()
+    } else {
+        rect.a1_string()
+    }
+}
+
+fn diff_sheet(sheet: &Sheet, from: Option<&Sheet>, to: Option<&Sheet>) -> SheetDiff {
+    let from_positions = from.map(cell_positions).unwrap_or_default();
+    let to_positions = to.map(cell_positions).unwrap_or_default();
+
+    let changed = from_positions
+        .union(&to_positions)
+        .filter(|pos| {
+            from.and_then(|sheet| sheet.display_value(**pos))
+                != to.and_then(|sheet| sheet.display_value(**pos))
+        })
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let change = match (from, to) {
+        (None, _) => SheetChange::Added,
+        (_, None) => SheetChange::Removed,
+        _ if changed.is_empty() && from.map(|s| &s.name) == to.map(|s| &s.name) => {
+            SheetChange::Unchanged
+        }
+        _ => SheetChange::Modified,
+    };
+
+    SheetDiff {
+        id: sheet.id.to_string(),
+        name: sheet.name.to_owned(),
+        change,
+        cells_from: from_positions.len(),
+        cells_to: to_positions.len(),
+        changed_ranges: group_into_rects(&changed)
+            .iter()
+            .map(range_a1_string)
+            .collect(),
+    }
+}
+
+/// Summarize the differences between two versions of a grid
+pub(crate) fn diff_grids(from: &Grid, to: &Grid) -> Vec<SheetDiff> {
+    let removed = from
+        .sheets()
+        .iter()
+        .filter(|sheet| to.try_sheet(sheet.id).is_none())
+        .map(|sheet| diff_sheet(sheet, Some(sheet), None));
+
+    let current = to
+        .sheets()
+        .iter()
+        .map(|sheet| diff_sheet(sheet, from.try_sheet(sheet.id), Some(sheet)));
+
+    current.chain(removed).collect()
+}
+
+/// List the checkpoints of a file
+pub(crate) async fn get_checkpoints(
+    Path(file_id): Path<Uuid>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    state: Extension<Arc<State>>,
+) -> Result<Json<Vec<Checkpoint>>> {
+    validate_user_can_view_file(&state, bearer.token(), file_id).await?;
+
+    Ok(Json(list_checkpoints(&state, file_id).await?))
+}
+
+/// Get the file as it was at a sequence number, e.g. to restore it as a copy
+pub(crate) async fn get_version(
+    Path((file_id, sequence_num)): Path<(Uuid, u64)>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    state: Extension<Arc<State>>,
+) -> Result<impl IntoResponse> {
+    validate_user_can_view_file(&state, bearer.token(), file_id).await?;
+
+    let grid = materialize(&state, file_id, sequence_num).await?;
+    let file = blocking(move || export_file(&key(file_id, sequence_num), grid)).await?;
+
+    Ok(file.into_response())
+}
+
+/// Summarize the differences between two versions of a file
+pub(crate) async fn get_diff(
+    Path(file_id): Path<Uuid>,
+    Query(DiffQuery { from, to }): Query<DiffQuery>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    state: Extension<Arc<State>>,
+) -> Result<Json<VersionDiff>> {
+    validate_user_can_view_file(&state, bearer.token(), file_id).await?;
+
+    let from_grid = materialize(&state, file_id, from).await?;
+    let to_grid = materialize(&state, file_id, to).await?;
+    let sheets = blocking(move || Ok(diff_grids(&from_grid, &to_grid))).await?;
+
+    Ok(Json(VersionDiff { from, to, sheets }))
+}
+
+#[cfg(test)]
+mod tests {
+    use quadratic_core::SheetPos;
+
+    use super::*;
+
+    fn set_value(gc: &mut GridController, x: i64, y: i64, value: &str) {
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(SheetPos { x, y, sheet_id }, value.into(), None);
+    }
+
+    #[test]
+    fn parses_checkpoint_keys() {
+        let file_id = Uuid::new_v4();
+
+        assert_eq!(
+            checkpoint_sequence_num(file_id, &key(file_id, 12)),
+            Some(12)
+        );
+        assert_eq!(
+            checkpoint_sequence_num(file_id, &key(Uuid::new_v4(), 12)),
+            None
+        );
+        assert_eq!(
+            checkpoint_sequence_num(file_id, &format!("{file_id}-12.txt")),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_the_nearest_checkpoint() {
+        let checkpoints = [0, 5, 10]
+            .into_iter()
+            .map(|sequence_num| Checkpoint {
+                sequence_num,
+                key: sequence_num.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let nearest =
+            |sequence_num| nearest_checkpoint(&checkpoints, sequence_num).map(|c| c.sequence_num);
+
+        assert_eq!(nearest(0), Some(0));
+        assert_eq!(nearest(7), Some(5));
+        assert_eq!(nearest(10), Some(10));
+        assert_eq!(nearest(100), Some(10));
+        assert_eq!(nearest_checkpoint(&checkpoints[1..], 2), None);
+    }
+
+    #[test]
+    fn groups_positions_into_rects() {
+        let positions = [(1, 1), (2, 1), (1, 2), (2, 2), (5, 1), (1, 4)]
+            .into_iter()
+            .map(Pos::from)
+            .collect::<BTreeSet<_>>();
+
+        let rects = group_into_rects(&positions)
+            .iter()
+            .map(range_a1_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(rects, vec!["A1:B2", "E1", "A4"]);
+
+        let positions = [(1, 1), (1, 2), (3, 2), (1, 3), (3, 3), (1, 5)]
+            .into_iter()
+            .map(Pos::from)
+            .collect::<BTreeSet<_>>();
+
+        let rects = group_into_rects(&positions)
+            .iter()
+            .map(range_a1_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(rects, vec!["A1:A3", "C2:C3", "A5"]);
+    }
+
+    #[test]
+    fn diffs_grids() {
+        let mut gc = GridController::test();
+        set_value(&mut gc, 1, 1, "a");
+        set_value(&mut gc, 2, 1, "b");
+        let from = gc.grid().clone();
+
+        set_value(&mut gc, 2, 1, "changed");
+        set_value(&mut gc, 1, 2, "new");
+        set_value(&mut gc, 2, 2, "new");
+        gc.add_sheet(None);
+        let to = gc.grid().clone();
+
+        let diff = diff_grids(&from, &to);
+
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].change, SheetChange::Modified);
+        assert_eq!(diff[0].cells_from, 2);
+        assert_eq!(diff[0].cells_to, 4);
+        assert_eq!(diff[0].changed_ranges, vec!["B1", "A2:B2"]);
+        assert_eq!(diff[1].change, SheetChange::Added);
+
+        let diff = diff_grids(&to, &from);
+        assert_eq!(diff[1].change, SheetChange::Removed);
+
+        let diff = diff_grids(&from, &from);
+        assert_eq!(diff[0].change, SheetChange::Unchanged);
+        assert!(diff[0].changed_ranges.is_empty());
+    }
+}
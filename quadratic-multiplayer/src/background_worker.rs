@@ -5,7 +5,6 @@ use uuid::Uuid;
 
 use crate::{
     error::Result,
-    get_room,
     message::{broadcast, response::MessageResponse},
     state::State,
 };
@@ -109,8 +108,8 @@ async fn remove_stale_users_in_room(
         return Ok(None);
     }
 
-    let users = get_room!(state, file_id)?.users.to_owned();
-    let message = MessageResponse::from((users, &state.settings.min_version));
+    let room = state.get_room(file_id).await?;
+    let message = MessageResponse::from((room, &state.settings.min_version));
 
     Ok(Some(broadcast(
         vec![],
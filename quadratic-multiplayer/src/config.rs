@@ -18,6 +18,14 @@ pub(crate) struct Config {
     pub(crate) authenticate_jwt: bool,
     pub(crate) heartbeat_timeout_s: i64,
     pub(crate) environment: Environment,
+    pub(crate) max_transaction_bytes: usize,
+    pub(crate) max_operations_per_transaction: usize,
+    pub(crate) max_viewer_presence_updates_per_s: u32,
+    pub(crate) session_messages_per_s: u64,
+    pub(crate) session_bytes_per_s: u64,
+    pub(crate) room_messages_per_s: u64,
+    pub(crate) room_bytes_per_s: u64,
+    pub(crate) slow_consumer_timeout_ms: u64,
 
     pub(crate) pubsub_host: String,
     pub(crate) pubsub_port: String,
@@ -51,6 +51,9 @@ pub(crate) enum MpError {
     #[error("Connection error: {0}")]
     Connection(String),
 
+    #[error("Transaction {0} decompresses to over the limit of {1} bytes")]
+    DecompressedTransactionTooLarge(Uuid, usize),
+
     #[error("File permissions error: {0}")]
     FilePermissions(String),
 
@@ -60,15 +63,36 @@ pub(crate) enum MpError {
     #[error("Internal server error: {0}")]
     InternalServer(String),
 
+    #[error("Invalid operation in transaction {0}: {1}")]
+    InvalidOperation(Uuid, String),
+
+    #[error("Could not decode transaction {0}: {1}")]
+    InvalidTransaction(Uuid, String),
+
+    #[error("Range is already locked by user {0} in room {1}")]
+    LockConflict(Uuid, Uuid),
+
     #[error("Error reading MinVersion file: {0}")]
     MinVersion(String),
 
     #[error("Requested {0} transactions but only found {1}")]
     MissingTransactions(String, String),
 
+    #[error("User {0} is not presenting in room {1}")]
+    NotPresenting(Uuid, Uuid),
+
+    #[error("User {0} is already presenting in room {1}")]
+    PresentationActive(Uuid, Uuid),
+
     #[error("PubSub error: {0}")]
     PubSub(String),
 
+    #[error("Transaction {0} changes cells locked by user {1}")]
+    RangeLocked(Uuid, Uuid),
+
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
     #[error("Error requesting data: {0}")]
     Request(String),
 
@@ -90,9 +114,18 @@ pub(crate) enum MpError {
     #[error("Error serializing or deserializing: {0}")]
     Serialization(String),
 
+    #[error("Transaction {0} has {1} operations, over the limit of {2}")]
+    TooManyOperations(Uuid, usize, usize),
+
+    #[error("Transaction {0} is {1} bytes, over the limit of {2}")]
+    TransactionTooLarge(Uuid, usize, usize),
+
     #[error("Transaction queue error: {0}")]
     TransactionQueue(String),
 
+    #[error("Transaction {0} references unknown sheet {1}")]
+    UnknownSheet(Uuid, String),
+
     #[error("unknown error: {0}")]
     Unknown(String),
 
@@ -0,0 +1,326 @@
+//! Binary Websocket Protocol
+//!
+//! JSON messages carry operations as base64 strings, which inflates them by a
+//! third and costs an encode on every broadcast.  Clients that offer the
+//! `quadratic-binary-v1` websocket subprotocol can send and receive
+//! transactions as binary frames instead:
+//!
+//! ```text
+//! [version: u8][header length: u32 big-endian][JSON header][operations]
+//! ```
+//!
+//! The header is the JSON message without its operations.  A `Transactions`
+//! header lists each transaction's `operations_len`, and their operations are
+//! concatenated in the same order.  All other messages remain JSON text frames,
+//! and clients that don't negotiate the subprotocol only ever see JSON.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+use crate::error::{MpError, Result};
+use crate::message::{request::MessageRequest, response::MessageResponse};
+
+pub(crate) const BINARY_PROTOCOL: &str = "quadratic-binary-v1";
+pub(crate) const BINARY_VERSION: u8 = 1;
+
+/// Compressed operations, which are base64 encoded in JSON messages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Operations(pub(crate) Vec<u8>);
+
+impl Serialize for Operations {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Operations {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let decoded = STANDARD.decode(encoded).map_err(|e| {
+            serde::de::Error::custom(format!("invalid base64 encoded operations: {e}"))
+        })?;
+
+        Ok(Operations(decoded))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+enum RequestHeader {
+    Transaction {
+        id: Uuid,
+        session_id: Uuid,
+        file_id: Uuid,
+    },
+    GetTransactions {
+        file_id: Uuid,
+        session_id: Uuid,
+        min_sequence_num: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct TransactionHeader {
+    id: Uuid,
+    file_id: Uuid,
+    sequence_num: u64,
+    operations_len: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+enum ResponseHeader {
+    Transaction {
+        id: Uuid,
+        file_id: Uuid,
+        sequence_num: u64,
+    },
+    Transactions {
+        transactions: Vec<TransactionHeader>,
+    },
+}
+
+fn frame_error(message: impl ToString) -> MpError {
+    MpError::Serialization(format!("Invalid binary frame: {}", message.to_string()))
+}
+
+fn frame<'a>(header: &impl Serialize, payloads: impl Iterator<Item = &'a [u8]>) -> Result<Vec<u8>> {
+    let header = serde_json::to_vec(header)?;
+    let header_len = u32::try_from(header.len()).map_err(frame_error)?;
+
+    let mut frame = Vec::with_capacity(5 + header.len());
+    frame.push(BINARY_VERSION);
+    frame.extend_from_slice(&header_len.to_be_bytes());
+    frame.extend_from_slice(&header);
+    payloads.for_each(|payload| frame.extend_from_slice(payload));
+
+    Ok(frame)
+}
+
+fn unframe<T: DeserializeOwned>(frame: &[u8]) -> Result<(T, &[u8])> {
+    let (&version, rest) = frame.split_first().ok_or_else(|| frame_error("empty"))?;
+
+    if version != BINARY_VERSION {
+        return Err(frame_error(format!("unsupported version {version}")));
+    }
+
+    let (header_len, rest) = rest
+        .split_first_chunk::<4>()
+        .ok_or_else(|| frame_error("missing header length"))?;
+    let header_len = u32::from_be_bytes(*header_len) as usize;
+
+    if rest.len() < header_len {
+        return Err(frame_error("header is longer than the frame"));
+    }
+
+    let (header, payload) = rest.split_at(header_len);
+
+    Ok((serde_json::from_slice(header)?, payload))
+}
+
+/// Decode a binary frame sent by a client
+pub(crate) fn decode_request(frame: &[u8]) -> Result<MessageRequest> {
+    let (header, payload) = unframe::<RequestHeader>(frame)?;
+
+    let request = match header {
+        RequestHeader::Transaction {
+            id,
+            session_id,
+            file_id,
+        } => MessageRequest::Transaction {
+            id,
+            session_id,
+            file_id,
+            operations: Operations(payload.to_vec()),
+        },
+        RequestHeader::GetTransactions {
+            file_id,
+            session_id,
+            min_sequence_num,
+        } => MessageRequest::GetTransactions {
+            file_id,
+            session_id,
+            min_sequence_num,
+        },
+    };
+
+    Ok(request)
+}
+
+/// Encode a response as a binary frame.  Returns None for responses that are
+/// always sent as JSON.
+pub(crate) fn encode_response(response: &MessageResponse) -> Result<Option<Vec<u8>>> {
+    let frame = match response {
+        MessageResponse::Transaction {
+            id,
+            file_id,
+            sequence_num,
+            operations,
+        } => {
+            let header = ResponseHeader::Transaction {
+                id: *id,
+                file_id: *file_id,
+                sequence_num: *sequence_num,
+            };
+
+            frame(&header, std::iter::once(operations.0.as_slice()))?
+        }
+        MessageResponse::Transactions { transactions } => {
+            let header = ResponseHeader::Transactions {
+                transactions: transactions
+                    .iter()
+                    .map(|transaction| TransactionHeader {
+                        id: transaction.id,
+                        file_id: transaction.file_id,
+                        sequence_num: transaction.sequence_num,
+                        operations_len: transaction.operations.0.len(),
+                    })
+                    .collect(),
+            };
+            let payloads = transactions
+                .iter()
+                .map(|transaction| transaction.operations.0.as_slice());
+
+            frame(&header, payloads)?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(frame))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::message::response::Transaction;
+
+    /// Decode a binary frame sent by the server, as a client would
+    pub(crate) fn decode_response(frame: &[u8]) -> MessageResponse {
+        let (header, mut payload) = unframe::<ResponseHeader>(frame).unwrap();
+
+        match header {
+            ResponseHeader::Transaction {
+                id,
+                file_id,
+                sequence_num,
+            } => MessageResponse::Transaction {
+                id,
+                file_id,
+                sequence_num,
+                operations: Operations(payload.to_vec()),
+            },
+            ResponseHeader::Transactions { transactions } => {
+                let transactions = transactions
+                    .into_iter()
+                    .map(|header| {
+                        let (operations, rest) = payload.split_at(header.operations_len);
+                        payload = rest;
+
+                        Transaction {
+                            id: header.id,
+                            file_id: header.file_id,
+                            sequence_num: header.sequence_num,
+                            operations: Operations(operations.to_vec()),
+                        }
+                    })
+                    .collect();
+
+                MessageResponse::Transactions { transactions }
+            }
+        }
+    }
+
+    /// Encode a request as a binary frame, as a client would
+    pub(crate) fn encode_request(request: &MessageRequest) -> Vec<u8> {
+        match request {
+            MessageRequest::Transaction {
+                id,
+                session_id,
+                file_id,
+                operations,
+            } => {
+                let header = RequestHeader::Transaction {
+                    id: *id,
+                    session_id: *session_id,
+                    file_id: *file_id,
+                };
+
+                frame(&header, std::iter::once(operations.0.as_slice())).unwrap()
+            }
+            MessageRequest::GetTransactions {
+                file_id,
+                session_id,
+                min_sequence_num,
+            } => {
+                let header = RequestHeader::GetTransactions {
+                    file_id: *file_id,
+                    session_id: *session_id,
+                    min_sequence_num: *min_sequence_num,
+                };
+
+                frame(&header, std::iter::empty()).unwrap()
+            }
+            _ => panic!("only transaction requests can be sent as binary"),
+        }
+    }
+
+    #[test]
+    fn round_trips_requests() {
+        let request = MessageRequest::Transaction {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            file_id: Uuid::new_v4(),
+            operations: Operations(vec![0, 1, 2, 255]),
+        };
+        assert_eq!(decode_request(&encode_request(&request)).unwrap(), request);
+
+        let request = MessageRequest::GetTransactions {
+            file_id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            min_sequence_num: 3,
+        };
+        assert_eq!(decode_request(&encode_request(&request)).unwrap(), request);
+    }
+
+    #[test]
+    fn round_trips_responses() {
+        let transaction = |operations: Vec<u8>| Transaction {
+            id: Uuid::new_v4(),
+            file_id: Uuid::new_v4(),
+            sequence_num: 1,
+            operations: Operations(operations),
+        };
+        let response = MessageResponse::Transactions {
+            transactions: vec![
+                transaction(vec![1, 2, 3]),
+                transaction(vec![]),
+                transaction(vec![4]),
+            ],
+        };
+        let frame = encode_response(&response).unwrap().unwrap();
+        assert_eq!(decode_response(&frame), response);
+
+        let response = MessageResponse::CurrentTransaction { sequence_num: 1 };
+        assert_eq!(encode_response(&response).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_frames() {
+        assert!(decode_request(&[]).is_err());
+        assert!(decode_request(&[2, 0, 0, 0, 0]).is_err());
+        assert!(decode_request(&[BINARY_VERSION, 0, 0, 1, 0, b'{']).is_err());
+    }
+
+    #[test]
+    fn operations_are_base64_in_json() {
+        let operations = Operations(vec![1, 2, 3]);
+        let json = serde_json::to_string(&operations).unwrap();
+        assert_eq!(json, "\"AQID\"");
+        assert_eq!(
+            serde_json::from_str::<Operations>(&json).unwrap(),
+            operations
+        );
+        assert!(serde_json::from_str::<Operations>("\"not base64!\"").is_err());
+    }
+}
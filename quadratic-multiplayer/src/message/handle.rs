@@ -6,9 +6,8 @@
 //! to all users in a room.
 
 use axum::extract::ws::{Message, WebSocket};
-use base64::{engine::general_purpose::STANDARD, Engine};
 use futures_util::stream::SplitSink;
-use quadratic_rust_shared::quadratic_api::{get_file_perms, FilePermRole};
+use quadratic_rust_shared::quadratic_api::{can_edit, get_file_perms, FilePermRole};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
@@ -16,6 +15,7 @@ use uuid::Uuid;
 use crate::error::{ErrorLevel, MpError, Result};
 use crate::get_mut_room;
 use crate::message::response::Transaction;
+use crate::message::validation::decode_operations;
 use crate::message::{
     broadcast, request::MessageRequest, response::MessageResponse, send_user_message,
 };
@@ -26,7 +26,7 @@ use crate::permissions::{
 use crate::state::{
     connection::PreConnection,
     pubsub::GROUP_NAME,
-    user::{User, UserState},
+    user::{LockUpdate, User, UserState, UserStateUpdate},
     State,
 };
 
@@ -109,6 +109,8 @@ pub(crate) async fn handle_message(
                 state: user_state,
                 socket: Some(Arc::clone(&sender)),
                 last_heartbeat: chrono::Utc::now(),
+                binary: pre_connection.binary,
+                presence_window: (0, 0),
 
                 // this will be properly set in the enter_room function
                 index: 0,
@@ -139,7 +141,7 @@ pub(crate) async fn handle_message(
             // only broadcast if the user is new to the room
             if is_new {
                 let room = state.get_room(&file_id).await?;
-                let response = MessageResponse::from((room.users, &state.settings.min_version));
+                let response = MessageResponse::from((room, &state.settings.min_version));
 
                 broadcast(vec![], file_id, Arc::clone(&state), response);
             }
@@ -158,7 +160,7 @@ pub(crate) async fn handle_message(
             let room = state.get_room(&file_id).await?;
 
             if is_not_empty {
-                let response = MessageResponse::from((room.users, &state.settings.min_version));
+                let response = MessageResponse::from((room, &state.settings.min_version));
                 broadcast(vec![session_id], file_id, Arc::clone(&state), response);
             }
 
@@ -184,18 +186,19 @@ pub(crate) async fn handle_message(
                 &operations
             );
 
+            // reject malformed transactions before they reach other users
+            let core_operations = decode_operations(id, &operations.0, &state.settings)?;
+
             // get and increment the room's sequence_num
-            let room_sequence_num = get_mut_room!(state, file_id)?.increment_sequence_num();
-            let decoded_operations = STANDARD.decode(&operations).map_err(|e| {
-                MpError::Serialization(format!(
-                    "Could not decode base64 encoded operations in transaction {id}: {:?}",
-                    e
-                ))
-            })?;
+            let room_sequence_num = get_mut_room!(state, file_id)?.accept_transaction(
+                id,
+                session_id,
+                &core_operations,
+            )?;
 
             // add the transaction to the transaction queue
             let sequence_num = state
-                .push_pubsub(id, file_id, decoded_operations, room_sequence_num)
+                .push_pubsub(id, file_id, operations.0.to_owned(), room_sequence_num)
                 .await?;
 
             // broadcast the transaction to all users in the room
@@ -271,6 +274,32 @@ pub(crate) async fn handle_message(
             // update the heartbeat
             state.update_user_heartbeat(file_id, &session_id).await?;
 
+            // viewers can't change the file, so drop their presence updates
+            // over the limit rather than flood large rooms
+            let user = state.get_room(&file_id).await?.get_user(&session_id)?;
+            let max_per_s = state.settings.max_viewer_presence_updates_per_s;
+
+            if !can_edit(&user.permissions)
+                && !state
+                    .allow_presence_update(&file_id, &session_id, max_per_s)
+                    .await?
+            {
+                tracing::trace!("Dropping presence update from viewer {session_id}");
+                return Ok(None);
+            }
+
+            // locks are held on the room so that transactions can be checked
+            match &update.lock {
+                Some(LockUpdate::Claim { selection }) => {
+                    validate_user_can_edit_file(Arc::clone(&state), file_id, session_id).await?;
+                    get_mut_room!(state, file_id)?.claim_lock(session_id, selection.to_owned())?;
+                }
+                Some(LockUpdate::Release) => {
+                    get_mut_room!(state, file_id)?.release_lock(&session_id);
+                }
+                None => {}
+            }
+
             // update user state
             state
                 .update_user_state(&file_id, &session_id, &update)
@@ -298,6 +327,68 @@ pub(crate) async fn handle_message(
             state.update_user_heartbeat(file_id, &session_id).await?;
             Ok(None)
         }
+
+        // User starts presenting to the room
+        MessageRequest::StartPresenting {
+            session_id,
+            file_id,
+        } => {
+            validate_user_can_edit_file(Arc::clone(&state), file_id, session_id).await?;
+
+            state.update_user_heartbeat(file_id, &session_id).await?;
+            get_mut_room!(state, file_id)?.start_presenting(session_id)?;
+
+            let room = state.get_room(&file_id).await?;
+            let response = MessageResponse::from((room, &state.settings.min_version));
+            broadcast(vec![], file_id, Arc::clone(&state), response);
+
+            Ok(None)
+        }
+
+        // Presenter ends their presentation
+        MessageRequest::StopPresenting {
+            session_id,
+            file_id,
+        } => {
+            validate_user_can_edit_or_view_file(Arc::clone(&state), file_id, session_id).await?;
+
+            state.update_user_heartbeat(file_id, &session_id).await?;
+            get_mut_room!(state, file_id)?.stop_presenting(session_id)?;
+
+            let room = state.get_room(&file_id).await?;
+            let response = MessageResponse::from((room, &state.settings.min_version));
+            broadcast(vec![], file_id, Arc::clone(&state), response);
+
+            Ok(None)
+        }
+
+        // Presenter pushes their sheet, selection and viewport to followers
+        MessageRequest::Present {
+            session_id,
+            file_id,
+            update,
+        } => {
+            validate_user_can_edit_file(Arc::clone(&state), file_id, session_id).await?;
+
+            if state.get_room(&file_id).await?.presenter != Some(session_id) {
+                return Err(MpError::NotPresenting(session_id, file_id));
+            }
+
+            // keep the presenter's state current for users who join later
+            state
+                .update_user_state(&file_id, &session_id, &UserStateUpdate::from(&update))
+                .await?;
+
+            let response = MessageResponse::Presentation {
+                session_id,
+                file_id,
+                update,
+            };
+
+            broadcast(vec![session_id], file_id, Arc::clone(&state), response);
+
+            Ok(None)
+        }
     }
 }
 
@@ -306,13 +397,16 @@ pub(crate) mod tests {
     use quadratic_core::controller::operations::operation::Operation;
     use quadratic_core::controller::transaction::Transaction as CoreTransaction;
     use quadratic_core::grid::SheetId;
+    use quadratic_core::{A1Selection, CellValues, SheetPos};
+    use std::collections::HashMap;
     use tokio::net::TcpStream;
     use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
     use uuid::Uuid;
 
     use super::*;
+    use crate::message::binary::Operations;
     use crate::state::settings::MinVersion;
-    use crate::state::user::{CellEdit, UserStateUpdate};
+    use crate::state::user::{CellEdit, PresentationUpdate, UserStateUpdate};
     use crate::test_util::{integration_test_receive, new_user, setup};
 
     async fn test_handle(
@@ -331,9 +425,14 @@ pub(crate) mod tests {
             .socket
             .unwrap();
 
-        let handled = handle_message(request, state.clone(), stream, PreConnection::new(None))
-            .await
-            .unwrap();
+        let handled = handle_message(
+            request,
+            state.clone(),
+            stream,
+            PreConnection::new(None, false),
+        )
+        .await
+        .unwrap();
         assert_eq!(handled, response);
 
         if let Some(broadcast_response) = broadcast_response {
@@ -357,6 +456,7 @@ pub(crate) mod tests {
             viewport: Some("viewport".to_string()),
             code_running: Some("code_running".to_string()),
             follow: Some(Uuid::new_v4().to_string()),
+            lock: None,
         };
 
         let request = MessageRequest::UserUpdate {
@@ -439,6 +539,8 @@ pub(crate) mod tests {
         let response = MessageResponse::UsersInRoom {
             users: vec![user_2.clone()],
             min_version: MinVersion::new().unwrap(),
+            presenter: None,
+            locks: HashMap::new(),
         };
 
         let users_in_room = state.get_room(&file_id).await.unwrap().users;
@@ -469,7 +571,7 @@ pub(crate) mod tests {
             color: Some("red".to_string()),
         }];
         let compressed_ops = CoreTransaction::serialize_and_compress(&operations).unwrap();
-        let encoded_ops = STANDARD.encode(&compressed_ops);
+        let encoded_ops = Operations(compressed_ops);
 
         let request = MessageRequest::Transaction {
             id,
@@ -524,6 +626,54 @@ pub(crate) mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn handle_invalid_transactions() {
+        let (_, state, _, file_id, user_1, _) = setup().await;
+        let session_id = user_1.session_id;
+        let stream = state
+            ._get_user_in_room(&file_id, &session_id)
+            .await
+            .unwrap()
+            .socket
+            .unwrap();
+
+        let out_of_bounds = vec![Operation::DeleteRow {
+            sheet_id: SheetId::new(),
+            row: 0,
+        }];
+        let compressed_ops = CoreTransaction::serialize_and_compress(&out_of_bounds).unwrap();
+
+        for operations in [
+            Operations(b"not a transaction".to_vec()),
+            Operations(compressed_ops),
+        ] {
+            let id = Uuid::new_v4();
+            let request = MessageRequest::Transaction {
+                id,
+                file_id,
+                session_id,
+                operations,
+            };
+
+            let handled = handle_message(
+                request,
+                state.clone(),
+                stream.clone(),
+                PreConnection::new(None, false),
+            )
+            .await;
+
+            assert!(matches!(
+                handled,
+                Err(MpError::InvalidTransaction(error_id, _) | MpError::InvalidOperation(error_id, _))
+                    if error_id == id
+            ));
+        }
+
+        // rejected transactions don't take a sequence_num
+        assert_eq!(state.get_sequence_num(&file_id).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn handle_missing_transactions() {
         let (socket, state, _, file_id, user_1, _) = setup().await;
@@ -557,4 +707,125 @@ pub(crate) mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn handle_presentation() {
+        let (_, state, _, file_id, user_1, user_2) = setup().await;
+        let stream = state
+            ._get_user_in_room(&file_id, &user_1.session_id)
+            .await
+            .unwrap()
+            .socket
+            .unwrap();
+        let handle = |request: MessageRequest| {
+            handle_message(
+                request,
+                state.clone(),
+                stream.clone(),
+                PreConnection::new(None, false),
+            )
+        };
+        let present = |session_id: Uuid| MessageRequest::Present {
+            session_id,
+            file_id,
+            update: PresentationUpdate {
+                viewport: Some("presented viewport".to_string()),
+                ..Default::default()
+            },
+        };
+
+        let start = |session_id: Uuid| MessageRequest::StartPresenting {
+            session_id,
+            file_id,
+        };
+        handle(start(user_1.session_id)).await.unwrap();
+        let room = state.get_room(&file_id).await.unwrap();
+        assert_eq!(room.presenter, Some(user_1.session_id));
+
+        // only one user presents at a time, and only the presenter can present
+        let result = handle(start(user_2.session_id)).await;
+        assert_eq!(
+            result,
+            Err(MpError::PresentationActive(user_1.session_id, file_id))
+        );
+        let result = handle(present(user_2.session_id)).await;
+        assert_eq!(
+            result,
+            Err(MpError::NotPresenting(user_2.session_id, file_id))
+        );
+
+        handle(present(user_1.session_id)).await.unwrap();
+        let presenter = state
+            ._get_user_in_room(&file_id, &user_1.session_id)
+            .await
+            .unwrap();
+        assert_eq!(presenter.state.viewport, "presented viewport");
+
+        // the presentation ends when the presenter leaves
+        state.leave_room(file_id, &user_1.session_id).await.unwrap();
+        let room = state.get_room(&file_id).await.unwrap();
+        assert_eq!(room.presenter, None);
+    }
+
+    #[tokio::test]
+    async fn handle_locks() {
+        let (_, state, _, file_id, user_1, user_2) = setup().await;
+        let stream = state
+            ._get_user_in_room(&file_id, &user_1.session_id)
+            .await
+            .unwrap()
+            .socket
+            .unwrap();
+        let handle = |request: MessageRequest| {
+            handle_message(
+                request,
+                state.clone(),
+                stream.clone(),
+                PreConnection::new(None, false),
+            )
+        };
+        let sheet_id = SheetId::new();
+        let claim = |session_id: Uuid, x: i64, y: i64| MessageRequest::UserUpdate {
+            session_id,
+            file_id,
+            update: UserStateUpdate {
+                lock: Some(LockUpdate::Claim {
+                    selection: A1Selection::from_xy(x, y, sheet_id),
+                }),
+                ..Default::default()
+            },
+        };
+
+        handle(claim(user_1.session_id, 1, 1)).await.unwrap();
+        let result = handle(claim(user_2.session_id, 1, 1)).await;
+        assert_eq!(
+            result,
+            Err(MpError::LockConflict(user_1.session_id, file_id))
+        );
+
+        // other users can't change locked cells
+        let operations = vec![Operation::SetCellValues {
+            sheet_pos: SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            values: CellValues::new(1, 1),
+        }];
+        let id = Uuid::new_v4();
+        let request = MessageRequest::Transaction {
+            id,
+            session_id: user_2.session_id,
+            file_id,
+            operations: Operations(CoreTransaction::serialize_and_compress(&operations).unwrap()),
+        };
+        let result = handle(request).await;
+        assert_eq!(result, Err(MpError::RangeLocked(id, user_1.session_id)));
+
+        // locks are released when the user leaves
+        state.leave_room(file_id, &user_1.session_id).await.unwrap();
+        handle(claim(user_2.session_id, 1, 1)).await.unwrap();
+        let locks = state.get_room(&file_id).await.unwrap().locks;
+        assert_eq!(locks.keys().collect::<Vec<_>>(), vec![&user_2.session_id]);
+    }
 }
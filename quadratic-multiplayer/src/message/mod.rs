@@ -1,17 +1,23 @@
 use axum::extract::ws::Message;
+use futures::future::join_all;
 use futures_util::SinkExt;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
-use crate::error::MpError;
+use crate::error::{MpError, Result};
+use crate::message::binary::encode_response;
 use crate::message::response::MessageResponse;
+use crate::state::user::UserSocket;
 use crate::state::State;
 
+pub mod binary;
 pub mod handle;
 pub mod request;
 pub mod response;
+pub mod validation;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub(crate) struct CellEdit {
@@ -48,6 +54,50 @@ pub(crate) struct UserStateUpdate {
     pub viewport: Option<String>,
 }
 
+/// Serialize a response for a connection, as a binary frame if it negotiated
+/// the binary protocol and the response has one.
+pub(crate) fn to_message(response: &MessageResponse, binary: bool) -> Result<Message> {
+    if binary {
+        if let Some(frame) = encode_response(response)? {
+            return Ok(Message::Binary(frame));
+        }
+    }
+
+    Ok(Message::Text(serde_json::to_string(response)?))
+}
+
+/// Send a message to a user's socket.  If the socket is backed up for longer
+/// than the slow consumer timeout, the message is dropped and false is
+/// returned.  Clients that miss transactions catch up using the sequence_num
+/// broadcast by the background worker.
+async fn send_message(socket: &UserSocket, message: Message, timeout: Duration) -> Result<bool> {
+    let send = async { socket.lock().await.send(message).await };
+
+    match tokio::time::timeout(timeout, send).await {
+        Ok(sent) => sent
+            .map(|_| true)
+            .map_err(|e| MpError::SendingMessage(e.to_string())),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Count messages sent to and dropped for slow clients
+fn record_sent(state: &State, sent: &Result<bool>) {
+    let (name, help) = match sent {
+        Ok(true) => (
+            "quadratic_multiplayer_messages_sent_total",
+            "Messages sent to clients",
+        ),
+        Ok(false) => (
+            "quadratic_multiplayer_messages_dropped_total",
+            "Messages dropped for slow clients",
+        ),
+        Err(_) => return,
+    };
+
+    state.metrics.inc_counter(name, help, &[], 1.0);
+}
+
 /// Broadcast a message to all users in a room except the sender.
 /// All messages are sent in a separate thread.
 #[tracing::instrument(level = "trace")]
@@ -76,27 +126,52 @@ pub(crate) fn broadcast(
                     return Ok::<_, MpError>(());
                 }
 
-                let serialized_message = serde_json::to_string(&message)?;
-
-                for user in included_users {
-                    if let Some(sender) = &user.socket {
-                        let sent = sender
-                            .lock()
-                            .await
-                            .send(Message::Text(serialized_message.clone()))
-                            .await
-                            .map_err(|e| MpError::SendingMessage(e.to_string()));
-
-                        if let Err(error) = sent {
+                // serialize once for each protocol in use
+                let text_message = to_message(&message, false)?;
+                let binary_message = match included_users.clone().any(|user| user.binary) {
+                    true => to_message(&message, true)?,
+                    false => text_message.clone(),
+                };
+
+                // send concurrently so that a slow consumer doesn't hold up
+                // the rest of the room
+                let timeout = Duration::from_millis(state.settings.slow_consumer_timeout_ms);
+                let sends = included_users.filter_map(|user| {
+                    let socket = user.socket.to_owned()?;
+                    let session_id = user.session_id;
+                    let serialized_message = match user.binary {
+                        true => binary_message.clone(),
+                        false => text_message.clone(),
+                    };
+
+                    Some(async move {
+                        let sent = send_message(&socket, serialized_message, timeout).await;
+                        (session_id, sent)
+                    })
+                });
+
+                for (session_id, sent) in join_all(sends).await {
+                    record_sent(&state, &sent);
+
+                    match sent {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            tracing::warn!(
+                                "Dropped message to slow user {} in room {}",
+                                session_id,
+                                file_id,
+                            );
+                        }
+                        Err(error) => {
                             tracing::warn!(
                                 "Error broadcasting to user {} in room {}: {:?}",
-                                user.session_id,
+                                session_id,
                                 file_id,
                                 error,
                             );
 
                             // the user's socket is stale, so remove them from the room
-                            state.leave_room(file_id, &user.session_id).await?;
+                            state.leave_room(file_id, &session_id).await?;
                         }
                     }
                 }
@@ -123,13 +198,18 @@ pub(crate) fn send_user_message(
     tokio::spawn(async move {
         let result = async {
             if let Ok(user) = state.get_room(&file_id).await?.get_user(&session_id) {
-                if let Some(sender) = &user.socket {
-                    sender
-                        .lock()
-                        .await
-                        .send(Message::Text(serde_json::to_string(&message)?))
-                        .await
-                        .map_err(|e| MpError::SendingMessage(e.to_string()))?;
+                if let Some(socket) = &user.socket {
+                    let timeout = Duration::from_millis(state.settings.slow_consumer_timeout_ms);
+                    let message = to_message(&message, user.binary)?;
+
+                    let sent = send_message(socket, message, timeout).await;
+                    record_sent(&state, &sent);
+
+                    if !sent? {
+                        tracing::warn!(
+                            "Dropped message to slow user {session_id} in room {file_id}"
+                        );
+                    }
                 }
                 Ok::<_, MpError>(())
             } else {
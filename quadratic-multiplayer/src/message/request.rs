@@ -3,12 +3,14 @@
 //! A central place for websocket messages requests.
 
 use serde::{Deserialize, Serialize};
+use strum_macros::IntoStaticStr;
 use uuid::Uuid;
 
-use crate::state::user::{CellEdit, UserStateUpdate};
+use crate::message::binary::Operations;
+use crate::state::user::{CellEdit, PresentationUpdate, UserStateUpdate};
 
 // NOTE: needs to be kept in sync with multiplayerTypes.ts
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, IntoStaticStr)]
 #[serde(tag = "type")]
 pub(crate) enum MessageRequest {
     EnterRoom {
@@ -38,7 +40,7 @@ pub(crate) enum MessageRequest {
         id: Uuid,
         session_id: Uuid,
         file_id: Uuid,
-        operations: String,
+        operations: Operations,
     },
     GetTransactions {
         file_id: Uuid,
@@ -49,4 +51,76 @@ pub(crate) enum MessageRequest {
         session_id: Uuid,
         file_id: Uuid,
     },
+    StartPresenting {
+        session_id: Uuid,
+        file_id: Uuid,
+    },
+    StopPresenting {
+        session_id: Uuid,
+        file_id: Uuid,
+    },
+    Present {
+        session_id: Uuid,
+        file_id: Uuid,
+        update: PresentationUpdate,
+    },
+}
+
+impl MessageRequest {
+    /// The file the request is for
+    pub(crate) fn file_id(&self) -> Uuid {
+        match self {
+            MessageRequest::EnterRoom { file_id, .. }
+            | MessageRequest::LeaveRoom { file_id, .. }
+            | MessageRequest::UserUpdate { file_id, .. }
+            | MessageRequest::Transaction { file_id, .. }
+            | MessageRequest::GetTransactions { file_id, .. }
+            | MessageRequest::Heartbeat { file_id, .. }
+            | MessageRequest::StartPresenting { file_id, .. }
+            | MessageRequest::StopPresenting { file_id, .. }
+            | MessageRequest::Present { file_id, .. } => *file_id,
+        }
+    }
+
+    /// Whether the request counts toward the session and room rate limits.
+    /// Entering and leaving rooms and heartbeats are exempt, so that a busy
+    /// room can't get users dropped for missing heartbeats.
+    pub(crate) fn is_rate_limited(&self) -> bool {
+        !matches!(
+            self,
+            MessageRequest::EnterRoom { .. }
+                | MessageRequest::LeaveRoom { .. }
+                | MessageRequest::Heartbeat { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeats_are_not_rate_limited() {
+        let session_id = Uuid::new_v4();
+        let file_id = Uuid::new_v4();
+
+        let heartbeat = MessageRequest::Heartbeat {
+            session_id,
+            file_id,
+        };
+        assert!(!heartbeat.is_rate_limited());
+
+        let leave_room = MessageRequest::LeaveRoom {
+            session_id,
+            file_id,
+        };
+        assert!(!leave_room.is_rate_limited());
+
+        let get_transactions = MessageRequest::GetTransactions {
+            session_id,
+            file_id,
+            min_sequence_num: 0,
+        };
+        assert!(get_transactions.is_rate_limited());
+    }
 }
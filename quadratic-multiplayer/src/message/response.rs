@@ -3,12 +3,14 @@
 //! A central place for websocket messages responses.
 
 use crate::error::{ErrorLevel, MpError};
+use crate::message::binary::Operations;
+use crate::state::room::Room;
 use crate::state::settings::MinVersion;
-use crate::state::user::{User, UserStateUpdate};
-use base64::{engine::general_purpose::STANDARD, Engine};
-use dashmap::DashMap;
+use crate::state::user::{PresentationUpdate, User, UserStateUpdate};
 use quadratic_core::controller::transaction::TransactionServer;
+use quadratic_core::A1Selection;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -16,7 +18,7 @@ pub(crate) struct Transaction {
     pub(crate) id: Uuid,
     pub(crate) file_id: Uuid,
     pub(crate) sequence_num: u64,
-    pub(crate) operations: String,
+    pub(crate) operations: Operations,
 }
 
 // NOTE: needs to be kept in sync with multiplayerTypes.ts
@@ -26,6 +28,10 @@ pub(crate) enum MessageResponse {
     UsersInRoom {
         users: Vec<User>,
         min_version: MinVersion,
+        /// The user presenting to the room, if a presentation is active
+        presenter: Option<Uuid>,
+        /// Ranges locked by users, by session_id
+        locks: HashMap<Uuid, A1Selection>,
     },
     UserUpdate {
         session_id: Uuid,
@@ -36,7 +42,7 @@ pub(crate) enum MessageResponse {
         id: Uuid,
         file_id: Uuid,
         sequence_num: u64,
-        operations: String,
+        operations: Operations,
     },
     Transactions {
         transactions: Vec<Transaction>,
@@ -48,6 +54,11 @@ pub(crate) enum MessageResponse {
     CurrentTransaction {
         sequence_num: u64,
     },
+    Presentation {
+        session_id: Uuid,
+        file_id: Uuid,
+        update: PresentationUpdate,
+    },
     Error {
         error: MpError,
         error_level: ErrorLevel,
@@ -60,16 +71,18 @@ impl From<TransactionServer> for Transaction {
             id: transaction_server.id,
             file_id: transaction_server.file_id,
             sequence_num: transaction_server.sequence_num,
-            operations: STANDARD.encode(&transaction_server.operations),
+            operations: Operations(transaction_server.operations),
         }
     }
 }
 
-impl From<(DashMap<Uuid, User>, &MinVersion)> for MessageResponse {
-    fn from((users, min_version): (DashMap<Uuid, User>, &MinVersion)) -> Self {
+impl From<(Room, &MinVersion)> for MessageResponse {
+    fn from((room, min_version): (Room, &MinVersion)) -> Self {
         MessageResponse::UsersInRoom {
-            users: users.into_iter().map(|user| (user.1)).collect(),
+            users: room.users.into_iter().map(|user| (user.1)).collect(),
             min_version: min_version.to_owned(),
+            presenter: room.presenter,
+            locks: room.locks,
         }
     }
 }
@@ -0,0 +1,499 @@
+//! Transaction Validation
+//!
+//! Transactions are decoded and checked before they're queued or broadcast,
+//! so that a buggy or malicious client can't poison a room with operations
+//! that other clients fail to apply.  The server doesn't hold the grid, so
+//! sheets are only known to be missing once the room has seen them deleted.
+//!
+//! Transactions are also rejected if they change cells that another user has
+//! locked.
+
+use std::collections::{HashMap, HashSet};
+
+use quadratic_core::controller::operations::operation::Operation;
+use quadratic_core::controller::transaction::Transaction as CoreTransaction;
+use quadratic_core::grid::SheetId;
+use quadratic_core::{A1Selection, CellRefRange, CellValues, Pos, SheetPos, SheetRect};
+use uuid::Uuid;
+
+use crate::error::{MpError, Result};
+use crate::state::settings::Settings;
+
+/// Largest column or row an operation may reference.  This is well past
+/// anything a user can reach, and small enough that adding a region's size
+/// can't overflow.
+pub(crate) const MAX_COORDINATE: i64 = i32::MAX as i64;
+
+/// Decompress and deserialize a transaction's operations, enforcing the
+/// configured size limits.  `max_transaction_bytes` bounds both the
+/// compressed and the decompressed operations.
+pub(crate) fn decode_operations(
+    id: Uuid,
+    operations: &[u8],
+    settings: &Settings,
+) -> Result<Vec<Operation>> {
+    if operations.len() > settings.max_transaction_bytes {
+        return Err(MpError::TransactionTooLarge(
+            id,
+            operations.len(),
+            settings.max_transaction_bytes,
+        ));
+    }
+
+    let operations = CoreTransaction::decompress_and_deserialize_with_limit::<Vec<Operation>>(
+        operations,
+        settings.max_transaction_bytes,
+    )
+    .map_err(|e| MpError::InvalidTransaction(id, e.to_string()))?
+    .ok_or(MpError::DecompressedTransactionTooLarge(
+        id,
+        settings.max_transaction_bytes,
+    ))?;
+
+    if operations.len() > settings.max_operations_per_transaction {
+        return Err(MpError::TooManyOperations(
+            id,
+            operations.len(),
+            settings.max_operations_per_transaction,
+        ));
+    }
+
+    Ok(operations)
+}
+
+/// Check that every operation references a sheet that hasn't been deleted
+/// and stays within the grid.
+///
+/// Returns the room's deleted sheets after applying the operations.
+pub(crate) fn validate_operations(
+    id: Uuid,
+    operations: &[Operation],
+    deleted_sheet_ids: &HashSet<SheetId>,
+) -> Result<HashSet<SheetId>> {
+    let mut deleted_sheet_ids = deleted_sheet_ids.to_owned();
+
+    for operation in operations {
+        let validator = Validator {
+            id,
+            deleted_sheet_ids: &deleted_sheet_ids,
+        };
+        validator.operation(operation)?;
+
+        match operation {
+            Operation::DeleteSheet { sheet_id } => {
+                deleted_sheet_ids.insert(*sheet_id);
+            }
+            Operation::DuplicateSheet { new_sheet_id, .. } => {
+                deleted_sheet_ids.remove(new_sheet_id);
+            }
+            // undoing a delete adds the sheet back with the same id
+            Operation::AddSheet { sheet } => {
+                deleted_sheet_ids.remove(&sheet.id);
+            }
+            Operation::AddSheetSchema { schema } => {
+                if let Ok(sheet) = schema.to_owned().into_latest() {
+                    deleted_sheet_ids.remove(&sheet.id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(deleted_sheet_ids)
+}
+
+/// The cells an operation changes values or code in.  Inserting or deleting
+/// a column or row shifts every cell after it, so it conflicts with any lock
+/// at or after it.  Formatting and sheet operations don't conflict with locks.
+fn locked_ranges(operation: &Operation) -> Vec<A1Selection> {
+    match operation {
+        Operation::SetCellValues { sheet_pos, values } => {
+            let (w, h) = (values.w.max(1) as i64, values.h.max(1) as i64);
+            let sheet_rect =
+                SheetRect::from_numbers(sheet_pos.x, sheet_pos.y, w, h, sheet_pos.sheet_id);
+
+            vec![A1Selection::from_rect(sheet_rect)]
+        }
+        Operation::SetCodeRun { sheet_pos, .. }
+        | Operation::SetCodeRunVersion { sheet_pos, .. }
+        | Operation::ComputeCode { sheet_pos } => vec![A1Selection::from_single_cell(*sheet_pos)],
+        Operation::MoveCells { source, dest } => {
+            let dest = SheetRect::from_numbers(
+                dest.x,
+                dest.y,
+                source.width() as i64,
+                source.height() as i64,
+                dest.sheet_id,
+            );
+
+            vec![
+                A1Selection::from_rect(*source),
+                A1Selection::from_rect(dest),
+            ]
+        }
+        Operation::DeleteColumn { sheet_id, column }
+        | Operation::InsertColumn {
+            sheet_id, column, ..
+        } => {
+            vec![A1Selection::from_range(
+                CellRefRange::new_relative_all_from(Pos { x: *column, y: 1 }),
+                *sheet_id,
+            )]
+        }
+        Operation::DeleteRow { sheet_id, row } | Operation::InsertRow { sheet_id, row, .. } => {
+            vec![A1Selection::from_range(
+                CellRefRange::new_relative_all_from(Pos { x: 1, y: *row }),
+                *sheet_id,
+            )]
+        }
+        _ => vec![],
+    }
+}
+
+/// Check that no operation changes cells locked by another session.
+pub(crate) fn validate_locks(
+    id: Uuid,
+    session_id: Uuid,
+    operations: &[Operation],
+    locks: &HashMap<Uuid, A1Selection>,
+) -> Result<()> {
+    let other_locks = locks
+        .iter()
+        .filter(|(lock_session_id, _)| **lock_session_id != session_id)
+        .collect::<Vec<_>>();
+
+    if other_locks.is_empty() {
+        return Ok(());
+    }
+
+    for range in operations.iter().flat_map(locked_ranges) {
+        for (lock_session_id, lock) in other_locks.iter() {
+            if range.overlaps_a1_selection(lock) {
+                return Err(MpError::RangeLocked(id, **lock_session_id));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Validator<'a> {
+    id: Uuid,
+    deleted_sheet_ids: &'a HashSet<SheetId>,
+}
+
+impl Validator<'_> {
+    fn invalid(&self, message: impl ToString) -> MpError {
+        MpError::InvalidOperation(self.id, message.to_string())
+    }
+
+    /// Reject sheets that were deleted while the room was open.  The room
+    /// doesn't know the file's sheets, so an id that was never in the file,
+    /// or a sheet deleted before the room opened, isn't caught here; clients
+    /// skip operations on sheets they don't have.
+    fn sheet(&self, sheet_id: &SheetId) -> Result<()> {
+        match self.deleted_sheet_ids.contains(sheet_id) {
+            true => Err(MpError::UnknownSheet(self.id, sheet_id.to_string())),
+            false => Ok(()),
+        }
+    }
+
+    fn coordinate(&self, name: &str, value: i64) -> Result<()> {
+        match (1..=MAX_COORDINATE).contains(&value) {
+            true => Ok(()),
+            false => Err(self.invalid(format!("{name} {value} is out of bounds"))),
+        }
+    }
+
+    fn sheet_pos(&self, sheet_pos: &SheetPos) -> Result<()> {
+        self.sheet(&sheet_pos.sheet_id)?;
+        self.coordinate("column", sheet_pos.x)?;
+        self.coordinate("row", sheet_pos.y)
+    }
+
+    fn sheet_rect(&self, sheet_rect: &SheetRect) -> Result<()> {
+        self.sheet(&sheet_rect.sheet_id)?;
+
+        for pos in [sheet_rect.min, sheet_rect.max] {
+            self.coordinate("column", pos.x)?;
+            self.coordinate("row", pos.y)?;
+        }
+
+        Ok(())
+    }
+
+    fn size(&self, name: &str, value: f64) -> Result<()> {
+        match value.is_finite() && value >= 0.0 {
+            true => Ok(()),
+            false => Err(self.invalid(format!("{name} {value} is not a valid size"))),
+        }
+    }
+
+    /// Clients assume that values fit within their width and height
+    fn cell_values(&self, sheet_pos: &SheetPos, values: &CellValues) -> Result<()> {
+        if values.columns.len() != values.w as usize
+            || values
+                .columns
+                .iter()
+                .any(|column| column.keys().any(|&y| y >= values.h as u64))
+        {
+            return Err(self.invalid("cell values don't match their size"));
+        }
+
+        if values.w > 0 && values.h > 0 {
+            self.coordinate("column", sheet_pos.x + values.w as i64 - 1)?;
+            self.coordinate("row", sheet_pos.y + values.h as i64 - 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn operation(&self, operation: &Operation) -> Result<()> {
+        match operation {
+            Operation::SetCellValues { sheet_pos, values } => {
+                self.sheet_pos(sheet_pos)?;
+                self.cell_values(sheet_pos, values)
+            }
+            Operation::SetCodeRunVersion { sheet_pos, .. }
+            | Operation::ComputeCode { sheet_pos }
+            | Operation::SetValidationWarning { sheet_pos, .. } => self.sheet_pos(sheet_pos),
+            Operation::MoveCells { source, dest } => {
+                self.sheet_rect(source)?;
+                self.sheet_pos(dest)
+            }
+
+            // deprecated operations may predate 1-based coordinates, so only
+            // their sheets are checked
+            Operation::SetCodeRun { sheet_pos, .. } => self.sheet(&sheet_pos.sheet_id),
+            Operation::SetCellFormats { sheet_rect, .. }
+            | Operation::SetBorders { sheet_rect, .. }
+            | Operation::SetCursor { sheet_rect } => self.sheet(&sheet_rect.sheet_id),
+            Operation::SetCellFormatsSelection { selection, .. }
+            | Operation::SetBordersSelection { selection, .. }
+            | Operation::SetCursorSelection { selection } => self.sheet(&selection.sheet_id),
+
+            Operation::SetCursorA1 { selection } => self.sheet(&selection.sheet_id),
+            Operation::SetValidation { validation } => self.sheet(&validation.selection.sheet_id),
+            Operation::SetCellFormatsA1 { sheet_id, .. }
+            | Operation::SetBordersA1 { sheet_id, .. }
+            | Operation::DuplicateSheet { sheet_id, .. }
+            | Operation::DeleteSheet { sheet_id }
+            | Operation::SetSheetName { sheet_id, .. }
+            | Operation::SetSheetColor { sheet_id, .. }
+            | Operation::RemoveValidation { sheet_id, .. }
+            | Operation::ReorderSheet {
+                target: sheet_id, ..
+            } => self.sheet(sheet_id),
+
+            Operation::ResizeColumn {
+                sheet_id,
+                column,
+                new_size,
+                ..
+            } => {
+                self.sheet(sheet_id)?;
+                self.coordinate("column", *column)?;
+                self.size("width", *new_size)
+            }
+            Operation::ResizeRow {
+                sheet_id,
+                row,
+                new_size,
+                ..
+            } => {
+                self.sheet(sheet_id)?;
+                self.coordinate("row", *row)?;
+                self.size("height", *new_size)
+            }
+            Operation::ResizeRows {
+                sheet_id,
+                row_heights,
+            } => {
+                self.sheet(sheet_id)?;
+
+                for row_height in row_heights {
+                    self.coordinate("row", row_height.row)?;
+                    self.size("height", row_height.height)?;
+                }
+
+                Ok(())
+            }
+            Operation::DeleteColumn { sheet_id, column }
+            | Operation::InsertColumn {
+                sheet_id, column, ..
+            } => {
+                self.sheet(sheet_id)?;
+                self.coordinate("column", *column)
+            }
+            Operation::DeleteRow { sheet_id, row } | Operation::InsertRow { sheet_id, row, .. } => {
+                self.sheet(sheet_id)?;
+                self.coordinate("row", *row)
+            }
+
+            Operation::SetTable { sheet_id, table } => {
+                self.sheet_rect(&table.bounds.to_sheet_rect(*sheet_id))
+            }
+            Operation::RemoveTable { sheet_id, .. } => self.sheet(sheet_id),
+
+            Operation::AddSheet { .. }
+            | Operation::AddSheetSchema { .. }
+            | Operation::SetCalculationMode { .. } => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quadratic_core::{CellValue, CopyFormats};
+
+    use super::*;
+
+    fn set_value(sheet_id: SheetId, x: i64, y: i64) -> Operation {
+        let mut values = CellValues::new(1, 1);
+        values.set(0, 0, CellValue::Text("a".into()));
+
+        Operation::SetCellValues {
+            sheet_pos: SheetPos { x, y, sheet_id },
+            values,
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_decompressed_size() {
+        let id = Uuid::new_v4();
+        let sheet_id = SheetId::new();
+        let operations = (1..=1000)
+            .map(|y| set_value(sheet_id, 1, y))
+            .collect::<Vec<_>>();
+        let compressed = CoreTransaction::serialize_and_compress(&operations).unwrap();
+        let decompressed_len = serde_json::to_vec(&operations).unwrap().len();
+        let mut settings = crate::test_util::new_state().await.settings;
+
+        // the compressed operations are well within the limit
+        settings.max_transaction_bytes = decompressed_len - 1;
+        assert!(compressed.len() < settings.max_transaction_bytes);
+        assert!(matches!(
+            decode_operations(id, &compressed, &settings),
+            Err(MpError::DecompressedTransactionTooLarge(error_id, _)) if error_id == id
+        ));
+
+        settings.max_transaction_bytes = decompressed_len;
+        assert_eq!(
+            decode_operations(id, &compressed, &settings).unwrap(),
+            operations
+        );
+    }
+
+    #[test]
+    fn validates_bounds() {
+        let id = Uuid::new_v4();
+        let sheet_id = SheetId::new();
+        let deleted = HashSet::new();
+
+        assert!(validate_operations(id, &[set_value(sheet_id, 1, 1)], &deleted).is_ok());
+
+        for (x, y) in [(0, 1), (1, -5), (MAX_COORDINATE + 1, 1)] {
+            let result = validate_operations(id, &[set_value(sheet_id, x, y)], &deleted);
+            assert!(matches!(result, Err(MpError::InvalidOperation(..))));
+        }
+
+        let resize = Operation::ResizeColumn {
+            sheet_id,
+            column: 1,
+            new_size: f64::NAN,
+            client_resized: false,
+        };
+        let result = validate_operations(id, &[resize], &deleted);
+        assert!(matches!(result, Err(MpError::InvalidOperation(..))));
+
+        let mut values = CellValues::new(1, 1);
+        values.columns.push(Default::default());
+        let mismatched = Operation::SetCellValues {
+            sheet_pos: SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            values,
+        };
+        let result = validate_operations(id, &[mismatched], &deleted);
+        assert!(matches!(result, Err(MpError::InvalidOperation(..))));
+    }
+
+    #[test]
+    fn tracks_deleted_sheets() {
+        let id = Uuid::new_v4();
+        let sheet_id = SheetId::new();
+        let new_sheet_id = SheetId::new();
+
+        let deleted =
+            validate_operations(id, &[Operation::DeleteSheet { sheet_id }], &HashSet::new())
+                .unwrap();
+        assert_eq!(deleted, HashSet::from([sheet_id]));
+
+        let result = validate_operations(id, &[set_value(sheet_id, 1, 1)], &deleted);
+        assert_eq!(result, Err(MpError::UnknownSheet(id, sheet_id.to_string())));
+
+        let duplicate = Operation::DuplicateSheet {
+            sheet_id,
+            new_sheet_id,
+        };
+        let result = validate_operations(id, &[duplicate], &deleted);
+        assert!(matches!(result, Err(MpError::UnknownSheet(..))));
+    }
+
+    #[test]
+    fn rejects_changes_to_locked_ranges() {
+        let id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let lock_session_id = Uuid::new_v4();
+        let sheet_id = SheetId::new();
+        let lock = A1Selection::from_rect(SheetRect::new(1, 1, 2, 2, sheet_id));
+        let locks = HashMap::from([(lock_session_id, lock)]);
+
+        let locked = [set_value(sheet_id, 2, 2)];
+        assert!(validate_locks(id, lock_session_id, &locked, &locks).is_ok());
+        assert_eq!(
+            validate_locks(id, session_id, &locked, &locks),
+            Err(MpError::RangeLocked(id, lock_session_id))
+        );
+
+        let unlocked = [set_value(sheet_id, 3, 3), set_value(SheetId::new(), 1, 1)];
+        assert!(validate_locks(id, session_id, &unlocked, &locks).is_ok());
+
+        let delete_row = [Operation::DeleteRow { sheet_id, row: 2 }];
+        assert!(validate_locks(id, session_id, &delete_row, &locks).is_err());
+
+        // inserts and deletes before a lock shift it, and after it don't
+        let insert_column = |column| Operation::InsertColumn {
+            sheet_id,
+            column,
+            copy_formats: CopyFormats::None,
+        };
+        let insert_row = |row| Operation::InsertRow {
+            sheet_id,
+            row,
+            copy_formats: CopyFormats::None,
+        };
+        for operation in [
+            insert_column(1),
+            insert_row(2),
+            Operation::DeleteRow { sheet_id, row: 1 },
+        ] {
+            assert_eq!(
+                validate_locks(id, session_id, &[operation], &locks),
+                Err(MpError::RangeLocked(id, lock_session_id))
+            );
+        }
+        for operation in [
+            insert_column(3),
+            insert_row(3),
+            Operation::DeleteColumn {
+                sheet_id,
+                column: 3,
+            },
+        ] {
+            assert!(validate_locks(id, session_id, &[operation], &locks).is_ok());
+        }
+    }
+}
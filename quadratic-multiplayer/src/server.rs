@@ -8,7 +8,10 @@ use axum::{
         connect_info::ConnectInfo,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
-    http::StatusCode,
+    http::{
+        header::{CONTENT_TYPE, SEC_WEBSOCKET_PROTOCOL},
+        HeaderMap, StatusCode,
+    },
     response::IntoResponse,
     routing::get,
     Extension, Router,
@@ -18,6 +21,7 @@ use futures::stream::StreamExt;
 use futures_util::stream::SplitSink;
 use futures_util::SinkExt;
 use quadratic_rust_shared::auth::jwt::{authorize, get_jwks};
+use quadratic_rust_shared::metrics::METRICS_CONTENT_TYPE;
 use serde::{Deserialize, Serialize};
 use std::ops::ControlFlow;
 use std::{net::SocketAddr, sync::Arc};
@@ -30,9 +34,14 @@ use crate::{
     config::config,
     error::{ErrorLevel, MpError, Result},
     message::{
-        broadcast, handle::handle_message, request::MessageRequest, response::MessageResponse,
+        binary::{decode_request, BINARY_PROTOCOL},
+        broadcast,
+        handle::handle_message,
+        request::MessageRequest,
+        response::MessageResponse,
+        to_message,
     },
-    state::{connection::PreConnection, State},
+    state::{connection::PreConnection, rate_limit::RateLimiter, State},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,6 +58,8 @@ pub(crate) fn app(state: Arc<State>) -> Router {
         .route("/ws", get(ws_handler))
         // healthchecks
         .route("/health", get(healthcheck))
+        // prometheus metrics
+        .route("/metrics", get(metrics))
         // state
         .layer(Extension(state))
         // logger
@@ -122,6 +133,7 @@ async fn ws_handler(
     addr: Option<ConnectInfo<SocketAddr>>,
     Extension(state): Extension<Arc<State>>,
     cookie: Option<TypedHeader<headers::Cookie>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let user_agent = user_agent.map_or("Unknown user agent".into(), |user_agent| {
         user_agent.to_string()
@@ -167,15 +179,25 @@ async fn ws_handler(
         }
     }
 
-    let pre_connection = PreConnection::new(jwt);
+    // clients opt into binary transactions by offering the subprotocol
+    let binary = headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim() == BINARY_PROTOCOL);
+
+    let pre_connection = PreConnection::new(jwt, binary);
 
     tracing::info!(
-        "New connection {}, `{user_agent}` at {addr}",
+        "New connection {}, `{user_agent}` at {addr}, binary={binary}",
         pre_connection.id
     );
 
-    // upgrade the connection
-    let ws = ws.max_message_size(1024 * 1024 * 1000); // 1GB
+    // upgrade the connection, echoing the subprotocol if it was offered
+    let ws = ws
+        .protocols([BINARY_PROTOCOL])
+        .max_message_size(state.settings.max_message_bytes());
     ws.on_upgrade(move |socket| handle_socket(socket, state, addr, pre_connection))
 }
 
@@ -190,6 +212,7 @@ async fn handle_socket(
     let (sender, mut receiver) = socket.split();
     let sender = Arc::new(Mutex::new(sender));
     let connection_id = pre_connection.id;
+    let mut rate_limiter = RateLimiter::default();
 
     while let Some(Ok(msg)) = receiver.next().await {
         let response = process_message(
@@ -197,6 +220,7 @@ async fn handle_socket(
             Arc::clone(&sender),
             Arc::clone(&state),
             pre_connection.to_owned(),
+            &mut rate_limiter,
         )
         .await;
 
@@ -206,6 +230,12 @@ async fn handle_socket(
             Err(error) => {
                 let error_level = ErrorLevel::from(&error);
                 error_level.log(&format!("Error processing message: {:?}", &error));
+                state.metrics.inc_counter(
+                    "quadratic_multiplayer_errors_total",
+                    "Errors sent to clients",
+                    &[("level", &error_level.to_string())],
+                    1.0,
+                );
 
                 if let Ok(message) = serde_json::to_string(&MessageResponse::Error {
                     error: error.to_owned(),
@@ -246,7 +276,7 @@ async fn handle_socket(
                 if let Ok(room) = state.get_room(&file_id).await {
                     tracing::info!("Broadcasting room {file_id} after connection close");
 
-                    let message = MessageResponse::from((room.users, &state.settings.min_version));
+                    let message = MessageResponse::from((room, &state.settings.min_version));
 
                     if let Err(error) = broadcast(
                         vec![connection.session_id],
@@ -281,30 +311,17 @@ async fn process_message(
     sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
     state: Arc<State>,
     pre_connection: PreConnection,
+    rate_limiter: &mut RateLimiter,
 ) -> Result<ControlFlow<Option<MessageResponse>, ()>> {
-    match msg {
-        Message::Text(text) => {
-            let messsage_request = serde_json::from_str::<MessageRequest>(&text)?;
-            let message_response =
-                handle_message(messsage_request, state, Arc::clone(&sender), pre_connection)
-                    .await?;
-
-            if let Some(message_response) = message_response {
-                let response = Message::Text(serde_json::to_string(&message_response)?);
-
-                (*sender.lock().await)
-                    .send(response)
-                    .await
-                    .map_err(|e| MpError::SendingMessage(e.to_string()))?;
-            }
-        }
-        Message::Binary(d) => {
-            tracing::info!(
-                "Binary messages are not yet supported.  {} bytes: {:?}",
-                d.len(),
-                d
-            );
-        }
+    let bytes = match &msg {
+        Message::Text(text) => text.len(),
+        Message::Binary(frame) => frame.len(),
+        _ => 0,
+    };
+
+    let messsage_request = match msg {
+        Message::Text(text) => serde_json::from_str::<MessageRequest>(&text)?,
+        Message::Binary(frame) => decode_request(&frame)?,
         Message::Close(c) => {
             if let Some(cf) = c {
                 tracing::info!("Close with code {} and reason `{}`", cf.code, cf.reason);
@@ -315,7 +332,45 @@ async fn process_message(
         }
         _ => {
             tracing::info!("Unhandled message type");
+            return Ok(ControlFlow::Continue(()));
         }
+    };
+
+    // messages are parsed before limiting so that room membership and
+    // heartbeats are never rejected (message size is capped by the socket)
+    if messsage_request.is_rate_limited() {
+        let now = chrono::Utc::now().timestamp();
+        rate_limiter.check("session", now, bytes, &state.settings.session_rate_limit)?;
+        state
+            .check_room_rate_limit(messsage_request.file_id(), bytes)
+            .await?;
+    }
+
+    let request_type: &'static str = (&messsage_request).into();
+    state.metrics.inc_counter(
+        "quadratic_multiplayer_messages_received_total",
+        "Messages received from clients",
+        &[("type", request_type)],
+        1.0,
+    );
+    state.metrics.inc_counter(
+        "quadratic_multiplayer_bytes_received_total",
+        "Bytes received from clients",
+        &[("type", request_type)],
+        bytes as f64,
+    );
+
+    let binary = pre_connection.binary;
+    let message_response =
+        handle_message(messsage_request, state, Arc::clone(&sender), pre_connection).await?;
+
+    if let Some(message_response) = message_response {
+        let response = to_message(&message_response, binary)?;
+
+        (*sender.lock().await)
+            .send(response)
+            .await
+            .map_err(|e| MpError::SendingMessage(e.to_string()))?;
     }
 
     Ok(ControlFlow::Continue(()))
@@ -325,23 +380,58 @@ pub(crate) async fn healthcheck() -> impl IntoResponse {
     StatusCode::OK
 }
 
+pub(crate) async fn metrics(Extension(state): Extension<Arc<State>>) -> impl IntoResponse {
+    let (num_rooms, num_users) = {
+        let rooms = state.rooms.lock().await;
+        let num_users = rooms.iter().map(|room| room.users.len()).sum::<usize>();
+        (rooms.len(), num_users)
+    };
+    let num_connections = state.connections.lock().await.len();
+
+    let metrics = &state.metrics;
+    metrics.set_gauge(
+        "quadratic_multiplayer_rooms",
+        "Open rooms",
+        &[],
+        num_rooms as f64,
+    );
+    metrics.set_gauge(
+        "quadratic_multiplayer_users",
+        "Users in rooms",
+        &[],
+        num_users as f64,
+    );
+    metrics.set_gauge(
+        "quadratic_multiplayer_connections",
+        "Open websocket connections",
+        &[],
+        num_connections as f64,
+    );
+
+    ([(CONTENT_TYPE, METRICS_CONTENT_TYPE)], metrics.render())
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
 
     use super::*;
+    use crate::message::binary::tests::{decode_response, encode_request};
+    use crate::message::binary::Operations;
     use crate::state::settings::MinVersion;
     use crate::state::user::{User, UserStateUpdate};
     use crate::test_util::{
-        add_user_via_ws, integration_test_send_and_receive, new_arc_state, setup,
+        add_user_via_ws, integration_test_send_and_receive, integration_test_setup, new_arc_state,
+        new_user, setup,
     };
     use axum::{
         body::Body,
         http::{self, Request},
     };
-    use base64::{engine::general_purpose::STANDARD, Engine};
     use quadratic_core::controller::operations::operation::Operation;
     use quadratic_core::controller::transaction::Transaction;
     use quadratic_core::grid::SheetId;
+    use std::collections::HashMap;
+    use tokio_tungstenite::tungstenite;
 
     use tower::ServiceExt;
     use uuid::Uuid;
@@ -378,6 +468,7 @@ pub(crate) mod tests {
                 code_running: None,
                 viewport: Some("new_viewport".to_string()),
                 follow: None,
+                lock: None,
             },
         }
     }
@@ -401,6 +492,35 @@ pub(crate) mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn responds_with_metrics() {
+        let (_, state, _, _, _, _) = setup().await;
+        let app = app(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains("quadratic_multiplayer_users 2"));
+        assert!(
+            body.contains(r#"quadratic_multiplayer_messages_received_total{type="EnterRoom"} 2"#)
+        );
+    }
+
     #[tokio::test]
     async fn test_user_enters_a_room() {
         // user_2 is created using the MessageRequest::EnterRoom message
@@ -424,6 +544,8 @@ pub(crate) mod tests {
         let expected = MessageResponse::UsersInRoom {
             users: vec![user_1.clone()],
             min_version: MinVersion::new().unwrap(),
+            presenter: None,
+            locks: HashMap::new(),
         };
 
         let response = integration_test_send_and_receive(&socket, request, true, 2).await;
@@ -536,7 +658,7 @@ pub(crate) mod tests {
         }];
         let id = Uuid::new_v4();
         let compressed_ops = Transaction::serialize_and_compress(&operations).unwrap();
-        let encoded_ops = STANDARD.encode(&compressed_ops);
+        let encoded_ops = Operations(compressed_ops);
         let request = MessageRequest::Transaction {
             id,
             session_id,
@@ -554,4 +676,47 @@ pub(crate) mod tests {
 
         assert_eq!(response, Some(expected));
     }
+
+    #[tokio::test]
+    async fn binary_user_shares_operations() {
+        let state = new_arc_state().await;
+        let socket = integration_test_setup(state.clone(), true).await;
+        let socket = Arc::new(Mutex::new(socket));
+        let file_id = Uuid::new_v4();
+        let user = add_user_via_ws(file_id, socket.clone(), new_user()).await;
+
+        let operations = vec![Operation::SetSheetName {
+            sheet_id: SheetId::new(),
+            name: "test".to_string(),
+        }];
+        let id = Uuid::new_v4();
+        let compressed_ops = Transaction::serialize_and_compress(&operations).unwrap();
+        let request = MessageRequest::Transaction {
+            id,
+            session_id: user.session_id,
+            file_id,
+            operations: Operations(compressed_ops.clone()),
+        };
+
+        // the transaction is sent and broadcast as binary frames
+        socket
+            .lock()
+            .await
+            .send(tungstenite::Message::binary(encode_request(&request)))
+            .await
+            .unwrap();
+        let expected = MessageResponse::Transaction {
+            id,
+            file_id,
+            operations: Operations(compressed_ops),
+            sequence_num: 1,
+        };
+
+        let response = match socket.lock().await.next().await {
+            Some(Ok(tungstenite::Message::Binary(frame))) => decode_response(&frame),
+            other => panic!("expected a binary message but got {other:?}"),
+        };
+
+        assert_eq!(response, expected);
+    }
 }
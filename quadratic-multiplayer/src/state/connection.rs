@@ -7,13 +7,16 @@ use crate::state::State;
 pub(crate) struct PreConnection {
     pub(crate) id: Uuid,
     pub(crate) jwt: Option<String>,
+    /// Transactions are sent as binary frames rather than JSON
+    pub(crate) binary: bool,
 }
 
 impl PreConnection {
-    pub(crate) fn new(jwt: Option<String>) -> Self {
+    pub(crate) fn new(jwt: Option<String>, binary: bool) -> Self {
         Self {
             id: Uuid::new_v4(),
             jwt,
+            binary,
         }
     }
 }
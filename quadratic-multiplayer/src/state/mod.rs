@@ -5,12 +5,14 @@
 
 pub mod connection;
 pub mod pubsub;
+pub mod rate_limit;
 pub mod room;
 pub mod settings;
 pub mod user;
 
 use dashmap::DashMap;
 use jsonwebtoken::jwk::JwkSet;
+use quadratic_rust_shared::metrics::Metrics;
 use quadratic_rust_shared::pubsub::redis_streams::RedisStreamsConfig;
 use quadratic_rust_shared::pubsub::Config as PubSubConfig;
 use std::collections::HashMap;
@@ -31,6 +33,7 @@ pub(crate) struct State {
     pub(crate) connections: Mutex<HashMap<Uuid, Connection>>,
     pub(crate) pubsub: Mutex<PubSub>,
     pub(crate) settings: Settings,
+    pub(crate) metrics: Metrics,
 }
 
 impl State {
@@ -47,6 +50,7 @@ impl State {
             connections: Mutex::new(HashMap::new()),
             pubsub: Mutex::new(PubSub::new(pubsub_config).await?),
             settings: Settings::new(config, jwks).await,
+            metrics: Metrics::new(),
         })
     }
 }
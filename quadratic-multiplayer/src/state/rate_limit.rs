@@ -0,0 +1,87 @@
+//! Rate Limiting
+//!
+//! Each session and room counts the messages and bytes it receives in
+//! one-second windows.  Messages over a limit are rejected with
+//! `MpError::RateLimited`, which is sent to the client as an `Error`.
+
+use crate::error::{MpError, Result};
+
+/// Messages and bytes allowed per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RateLimit {
+    pub(crate) messages_per_s: u64,
+    pub(crate) bytes_per_s: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimiter {
+    second: i64,
+    messages: u64,
+    bytes: u64,
+}
+
+impl RateLimiter {
+    /// Count a message of `bytes` received at `now` (a unix timestamp in
+    /// seconds), returning an error if the `scope` is over its limit.
+    pub(crate) fn check(
+        &mut self,
+        scope: &str,
+        now: i64,
+        bytes: usize,
+        limit: &RateLimit,
+    ) -> Result<()> {
+        if self.second != now {
+            *self = RateLimiter {
+                second: now,
+                ..Default::default()
+            };
+        }
+
+        self.messages += 1;
+        self.bytes += bytes as u64;
+
+        if self.messages > limit.messages_per_s {
+            return Err(MpError::RateLimited(format!(
+                "{scope} is over the limit of {} messages per second",
+                limit.messages_per_s
+            )));
+        }
+
+        if self.bytes > limit.bytes_per_s {
+            return Err(MpError::RateLimited(format!(
+                "{scope} is over the limit of {} bytes per second",
+                limit.bytes_per_s
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_messages_and_bytes_per_second() {
+        let limit = RateLimit {
+            messages_per_s: 2,
+            bytes_per_s: 100,
+        };
+        let mut limiter = RateLimiter::default();
+
+        assert!(limiter.check("session", 1, 10, &limit).is_ok());
+        assert!(limiter.check("session", 1, 10, &limit).is_ok());
+        assert!(matches!(
+            limiter.check("session", 1, 10, &limit),
+            Err(MpError::RateLimited(_))
+        ));
+
+        // the window resets each second
+        assert!(limiter.check("session", 2, 100, &limit).is_ok());
+        assert!(matches!(
+            limiter.check("session", 2, 1, &limit),
+            Err(MpError::RateLimited(_))
+        ));
+    }
+}
@@ -1,9 +1,14 @@
 use dashmap::DashMap;
+use quadratic_core::controller::operations::operation::Operation;
+use quadratic_core::grid::SheetId;
+use quadratic_core::A1Selection;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::error::{MpError, Result};
-use crate::state::{user::User, State};
+use crate::message::validation::{validate_locks, validate_operations};
+use crate::state::{rate_limit::RateLimiter, user::User, State};
 use crate::{get_mut_room, get_or_create_room, get_room};
 
 use super::connection::{Connection, PreConnection};
@@ -15,6 +20,17 @@ pub(crate) struct Room {
     pub(crate) sequence_num: u64,
     pub(crate) checkpoint_sequence_num: u64,
     pub(crate) user_index: usize,
+    /// Sheets deleted while the room was open, which later operations can't
+    /// reference
+    pub(crate) deleted_sheet_ids: HashSet<SheetId>,
+    /// The user presenting to the room, whose sheet, selection and viewport
+    /// are pushed to everyone else
+    pub(crate) presenter: Option<Uuid>,
+    /// Ranges that users have locked, by session_id.  Transactions from other
+    /// sessions can't change locked cells.
+    pub(crate) locks: HashMap<Uuid, A1Selection>,
+    #[serde(skip)]
+    pub(crate) rate_limiter: RateLimiter,
 }
 
 #[cfg(test)]
@@ -34,6 +50,10 @@ impl Room {
             sequence_num,
             checkpoint_sequence_num: sequence_num,
             user_index: 0,
+            deleted_sheet_ids: HashSet::new(),
+            presenter: None,
+            locks: HashMap::new(),
+            rate_limiter: RateLimiter::default(),
         }
     }
 
@@ -42,6 +62,63 @@ impl Room {
         self.sequence_num
     }
 
+    /// Validate a transaction's operations against the room, then take the
+    /// next sequence_num for it.
+    pub fn accept_transaction(
+        &mut self,
+        id: Uuid,
+        session_id: Uuid,
+        operations: &[Operation],
+    ) -> Result<u64> {
+        validate_locks(id, session_id, operations, &self.locks)?;
+        self.deleted_sheet_ids = validate_operations(id, operations, &self.deleted_sheet_ids)?;
+
+        Ok(self.increment_sequence_num())
+    }
+
+    /// Make a user the room's presenter.  Only one user can present at a time.
+    pub fn start_presenting(&mut self, session_id: Uuid) -> Result<()> {
+        match self.presenter {
+            Some(presenter) if presenter != session_id => {
+                Err(MpError::PresentationActive(presenter, self.file_id))
+            }
+            _ => {
+                self.presenter = Some(session_id);
+                Ok(())
+            }
+        }
+    }
+
+    /// End the presentation if the user is presenting.
+    pub fn stop_presenting(&mut self, session_id: Uuid) -> Result<()> {
+        if self.presenter != Some(session_id) {
+            return Err(MpError::NotPresenting(session_id, self.file_id));
+        }
+
+        self.presenter = None;
+        Ok(())
+    }
+
+    /// Lock a range for a user, replacing any lock they already hold.  Fails
+    /// if the range overlaps another user's lock.
+    pub fn claim_lock(&mut self, session_id: Uuid, selection: A1Selection) -> Result<()> {
+        let locked_by = self.locks.iter().find(|(lock_session_id, lock)| {
+            **lock_session_id != session_id && lock.overlaps_a1_selection(&selection)
+        });
+
+        if let Some((lock_session_id, _)) = locked_by {
+            return Err(MpError::LockConflict(*lock_session_id, self.file_id));
+        }
+
+        self.locks.insert(session_id, selection);
+        Ok(())
+    }
+
+    /// Release a user's lock, if they hold one.
+    pub fn release_lock(&mut self, session_id: &Uuid) {
+        self.locks.remove(session_id);
+    }
+
     pub fn get_user(&self, session_id: &Uuid) -> Result<User> {
         let user = self
             .users
@@ -99,11 +176,20 @@ impl State {
         Ok(is_new)
     }
 
-    /// Removes a user from a room. If the room is empty, it deletes the room.
+    /// Removes a user from a room, ending their presentation if they were
+    /// presenting and releasing their lock. If the room is empty, it deletes
+    /// the room.
     /// Returns true if the room still exists after the user leaves.
     #[tracing::instrument(level = "trace")]
     pub(crate) async fn leave_room(&self, file_id: Uuid, session_id: &Uuid) -> Result<bool> {
         get_mut_room!(self, file_id)?.users.remove(session_id);
+
+        get_mut_room!(self, file_id)?.release_lock(session_id);
+
+        if get_room!(self, file_id)?.presenter == Some(*session_id) {
+            get_mut_room!(self, file_id)?.presenter = None;
+        }
+
         let num_in_room = get_room!(self, file_id)?.users.len();
 
         tracing::info!(
@@ -128,6 +214,18 @@ impl State {
         tracing::info!("Room {file_id} removed");
     }
 
+    /// Count a message against a room's rate limit.  Rooms that don't exist
+    /// yet aren't limited.
+    pub(crate) async fn check_room_rate_limit(&self, file_id: Uuid, bytes: usize) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let limit = &self.settings.room_rate_limit;
+
+        match get_mut_room!(self, file_id) {
+            Ok(mut room) => room.rate_limiter.check("room", now, bytes, limit),
+            Err(_) => Ok(()),
+        }
+    }
+
     /// Get a room's current sequence number.
     pub(crate) async fn get_sequence_num(&self, file_id: &Uuid) -> Result<u64> {
         Ok(get_room!(self, file_id)?.sequence_num)
@@ -209,8 +307,8 @@ mod tests {
         let file_id = Uuid::new_v4();
         let mut user = new_user();
         let mut user2 = new_user();
-        let connection = PreConnection::new(None);
-        let connection2 = PreConnection::new(None);
+        let connection = PreConnection::new(None, false);
+        let connection2 = PreConnection::new(None, false);
 
         let is_new = state
             .enter_room(file_id, &mut user, connection, 0)
@@ -258,10 +356,10 @@ mod tests {
         let mut user = new_user();
         let mut user2 = new_user();
         let mut user3 = new_user();
-        let connection = PreConnection::new(None);
-        let connection2 = PreConnection::new(None);
-        let connection3 = PreConnection::new(None);
-        let connection4 = PreConnection::new(None);
+        let connection = PreConnection::new(None, false);
+        let connection2 = PreConnection::new(None, false);
+        let connection3 = PreConnection::new(None, false);
+        let connection4 = PreConnection::new(None, false);
 
         state
             .enter_room(file_id, &mut user, connection, 0)
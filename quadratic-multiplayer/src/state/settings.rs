@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
 use crate::error::{MpError, Result};
+use crate::state::rate_limit::RateLimit;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +26,12 @@ pub(crate) struct Settings {
     pub(crate) quadratic_api_uri: String,
     pub(crate) m2m_auth_token: String,
     pub(crate) min_version: MinVersion,
+    pub(crate) max_transaction_bytes: usize,
+    pub(crate) max_operations_per_transaction: usize,
+    pub(crate) max_viewer_presence_updates_per_s: u32,
+    pub(crate) session_rate_limit: RateLimit,
+    pub(crate) room_rate_limit: RateLimit,
+    pub(crate) slow_consumer_timeout_ms: u64,
 }
 
 impl Settings {
@@ -35,6 +42,24 @@ impl Settings {
             quadratic_api_uri: config.quadratic_api_uri.to_owned(),
             m2m_auth_token: config.m2m_auth_token.to_owned(),
             min_version: MinVersion::new().expect("Unable to load min version file"),
+            max_transaction_bytes: config.max_transaction_bytes,
+            max_operations_per_transaction: config.max_operations_per_transaction,
+            max_viewer_presence_updates_per_s: config.max_viewer_presence_updates_per_s,
+            session_rate_limit: RateLimit {
+                messages_per_s: config.session_messages_per_s,
+                bytes_per_s: config.session_bytes_per_s,
+            },
+            room_rate_limit: RateLimit {
+                messages_per_s: config.room_messages_per_s,
+                bytes_per_s: config.room_bytes_per_s,
+            },
+            slow_consumer_timeout_ms: config.slow_consumer_timeout_ms,
         }
     }
+
+    /// The largest websocket message accepted: a maximum size transaction,
+    /// base64 encoded, plus room for the rest of the JSON message
+    pub(crate) fn max_message_bytes(&self) -> usize {
+        self.max_transaction_bytes.div_ceil(3) * 4 + 64 * 1024
+    }
 }
@@ -6,6 +6,8 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use quadratic_core::A1Selection;
+
 use crate::error::{MpError, Result};
 use crate::state::State;
 use crate::{get_mut_room, get_room};
@@ -30,6 +32,13 @@ pub(crate) struct User {
     pub socket: Option<UserSocket>,
     #[serde(skip)]
     pub last_heartbeat: DateTime<Utc>,
+    /// The user's connection negotiated the binary protocol
+    #[serde(skip)]
+    pub binary: bool,
+    /// The second (unix timestamp) and number of presence updates sent in it,
+    /// for rate limiting viewers
+    #[serde(skip)]
+    pub presence_window: (i64, u32),
 }
 
 impl PartialEq for User {
@@ -79,6 +88,52 @@ pub(crate) struct UserStateUpdate {
 
     // empty string signifies removing follow; otherwise we'll parse the string for the Uuid
     pub follow: Option<String>,
+
+    // claims or releases the user's lock, which is held on the room
+    #[serde(default)]
+    pub lock: Option<LockUpdate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub(crate) enum LockUpdate {
+    Claim { selection: A1Selection },
+    Release,
+}
+
+impl User {
+    /// Counts a presence update sent at `now` (a unix timestamp in seconds).
+    /// Returns false if the user has already sent `max_per_s` in that second.
+    pub(crate) fn count_presence_update(&mut self, now: i64, max_per_s: u32) -> bool {
+        let (second, count) = &mut self.presence_window;
+
+        if *second != now {
+            *second = now;
+            *count = 0;
+        }
+
+        *count += 1;
+        *count <= max_per_s
+    }
+}
+
+/// Changes a presenter pushes to their followers
+#[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct PresentationUpdate {
+    pub sheet_id: Option<Uuid>,
+    pub selection: Option<String>,
+    pub viewport: Option<String>,
+}
+
+impl From<&PresentationUpdate> for UserStateUpdate {
+    fn from(update: &PresentationUpdate) -> Self {
+        UserStateUpdate {
+            sheet_id: update.sheet_id,
+            selection: update.selection.to_owned(),
+            viewport: update.viewport.to_owned(),
+            ..Default::default()
+        }
+    }
 }
 
 impl State {
@@ -171,6 +226,23 @@ impl State {
         Ok(())
     }
 
+    /// Counts a presence update against a user's per-second limit.  Returns
+    /// false if the user is over the limit and the update should be dropped.
+    pub(crate) async fn allow_presence_update(
+        &self,
+        file_id: &Uuid,
+        session_id: &Uuid,
+        max_per_s: u32,
+    ) -> Result<bool> {
+        let now = Utc::now().timestamp();
+
+        get_mut_room!(self, file_id)?
+            .users
+            .get_mut(session_id)
+            .map(|mut user| user.count_presence_update(now, max_per_s))
+            .ok_or(MpError::UserNotFound(*session_id, *file_id))
+    }
+
     /// updates a user's state in a room
     pub(crate) async fn update_user_state(
         &self,
@@ -224,7 +296,10 @@ impl State {
 
 #[cfg(test)]
 mod tests {
-    use crate::{error::MpError, test_util::setup};
+    use crate::{
+        error::MpError,
+        test_util::{new_user, setup},
+    };
 
     use super::*;
     #[tokio::test]
@@ -486,4 +561,15 @@ mod tests {
 
         assert_eq!(user.state.cell_edit, cell_edit);
     }
+
+    #[test]
+    fn limits_presence_updates_per_second() {
+        let mut user = new_user();
+
+        assert!((0..3).all(|_| user.count_presence_update(100, 3)));
+        assert!(!user.count_presence_update(100, 3));
+
+        // the count resets each second
+        assert!(user.count_presence_update(101, 3));
+    }
 }
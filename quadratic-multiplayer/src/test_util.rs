@@ -16,10 +16,13 @@ use std::{
 };
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
 use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 
 use crate::config::config;
+use crate::message::binary::{tests::decode_response, BINARY_PROTOCOL};
 use crate::message::request::MessageRequest;
 use crate::message::response::MessageResponse;
 use crate::state::connection::PreConnection;
@@ -45,7 +48,7 @@ pub(crate) async fn setup() -> (
     User,
 ) {
     let state = new_arc_state().await;
-    let socket = integration_test_setup(state.clone()).await;
+    let socket = integration_test_setup(state.clone(), false).await;
     let socket = Arc::new(Mutex::new(socket));
     let file_id = Uuid::new_v4();
 
@@ -111,6 +114,8 @@ pub(crate) fn new_user() -> User {
         permissions: vec![FilePermRole::FileView, FilePermRole::FileEdit],
         socket: None,
         last_heartbeat: chrono::Utc::now(),
+        binary: false,
+        presence_window: (0, 0),
         index: 0,
     }
 }
@@ -154,7 +159,7 @@ pub(crate) async fn add_new_user_to_room(file_id: Uuid, state: Arc<State>) -> Us
 /// Add an existing user to a room via global state directly.
 /// Returns the user.
 pub(crate) async fn add_user_to_room(file_id: Uuid, user: User, state: Arc<State>) -> User {
-    let connection = PreConnection::new(None);
+    let connection = PreConnection::new(None, false);
     let mut user = user.clone();
     state
         .enter_room(file_id, &mut user, connection, 0)
@@ -192,10 +197,11 @@ pub(crate) fn operation(grid: &mut GridController, x: i64, y: i64, value: &str)
 
 /// Setup integration testing, which:
 /// - Runs the app in a separate thread
-/// - Connects to the app via WebSocket
+/// - Connects to the app via WebSocket, offering the binary protocol if `binary`
 /// - Returns a reference to the user's `receiver` WebSocket
 pub(crate) async fn integration_test_setup(
     state: Arc<State>,
+    binary: bool,
 ) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
     let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
         .await
@@ -205,9 +211,16 @@ pub(crate) async fn integration_test_setup(
     // run the server in a separate thread
     tokio::spawn(axum::serve(listener, crate::server::app(state)).into_future());
 
-    let (socket, _response) = tokio_tungstenite::connect_async(format!("ws://{addr}/ws"))
-        .await
-        .unwrap();
+    let mut request = format!("ws://{addr}/ws").into_client_request().unwrap();
+
+    if binary {
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static(BINARY_PROTOCOL),
+        );
+    }
+
+    let (socket, _response) = tokio_tungstenite::connect_async(request).await.unwrap();
 
     socket
 }
@@ -265,7 +278,8 @@ pub(crate) async fn integration_test_receive(
             tungstenite::Message::Text(msg) => {
                 Some(serde_json::from_str::<MessageResponse>(&msg).unwrap())
             }
-            other => panic!("expected a text message but got {other:?}"),
+            tungstenite::Message::Binary(frame) => Some(decode_response(&frame)),
+            other => panic!("expected a text or binary message but got {other:?}"),
         };
 
         if count >= response_num {
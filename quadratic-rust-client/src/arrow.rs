@@ -0,0 +1,27 @@
+//! WASM functions for the Arrow IPC streams that the language workers send to
+//! and receive from core.
+
+use quadratic_core::{
+    arrow_ipc::js_output_to_arrow_ipc, controller::execution::run_code::get_cells::CellA1Response,
+};
+use wasm_bindgen::prelude::*;
+
+/// Decodes the Arrow IPC stream from calculationGetCellsA1Arrow into a
+/// stringified CellA1Response.
+#[wasm_bindgen(js_name = "arrowToCellsA1")]
+pub fn js_arrow_to_cells_a1(bytes: &[u8]) -> Result<String, JsValue> {
+    let response =
+        CellA1Response::from_arrow_ipc(bytes).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_json::to_string(&response).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Encodes a stringified output_array as an Arrow IPC stream for
+/// calculationCompleteArrow. Returns undefined if the output can't be sent
+/// as Arrow (eg, it needs a currency format), in which case the output_array
+/// should be sent with calculationComplete.
+#[wasm_bindgen(js_name = "outputArrayToArrow")]
+pub fn js_output_array_to_arrow(output_array: &str) -> Result<Option<Vec<u8>>, JsValue> {
+    let output_array = serde_json::from_str::<Vec<Vec<Vec<String>>>>(output_array)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    js_output_to_arrow_ipc(&output_array).map_err(|e| JsValue::from_str(&e.to_string()))
+}
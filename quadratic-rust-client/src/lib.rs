@@ -3,6 +3,7 @@ use wasm_bindgen::prelude::*;
 
 pub use quadratic_core::a1::JsSelection;
 
+pub mod arrow;
 pub mod date_time;
 pub mod jsexpr;
 pub mod lsp;
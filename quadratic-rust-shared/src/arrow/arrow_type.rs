@@ -1,7 +1,14 @@
 use arrow::array::{
-    ArrayRef, BooleanArray, Date32Array, Date64Array, Float32Array, Float64Array, Int16Array,
-    Int32Array, Int64Array, Int8Array, StringArray, Time32SecondArray, TimestampMillisecondArray,
-    UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+    Array, ArrayRef, AsArray, BooleanArray, Date32Array, Date64Array, Float32Array, Float64Array,
+    Int16Array, Int32Array, Int64Array, Int8Array, StringArray, Time32SecondArray,
+    TimestampMillisecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{
+    DataType, Date32Type, Date64Type, Decimal128Type, Float16Type, Float32Type, Float64Type,
+    Int16Type, Int32Type, Int64Type, Int8Type, Time32MillisecondType, Time32SecondType,
+    Time64MicrosecondType, Time64NanosecondType, TimeUnit, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
 };
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, Timelike};
@@ -130,6 +137,94 @@ impl ArrowType {
             }
         }
     }
+
+    /// Read a single value from an Arrow array, the reverse of `to_array_ref`.
+    /// Nulls become `Void`.
+    pub fn from_array(array: &ArrayRef, index: usize) -> ArrowType {
+        if array.is_null(index) {
+            return ArrowType::Void;
+        }
+
+        let time = |time: Option<NaiveTime>| time.map_or(ArrowType::Unsupported, ArrowType::Time32);
+        let timestamp = |date_time: Option<NaiveDateTime>, has_timezone: bool| match date_time {
+            Some(date_time) if has_timezone => {
+                ArrowType::TimestampTz(date_time.and_utc().with_timezone(&Local))
+            }
+            Some(date_time) => ArrowType::Timestamp(date_time),
+            None => ArrowType::Unsupported,
+        };
+
+        match array.data_type() {
+            DataType::Int8 => ArrowType::Int8(array.as_primitive::<Int8Type>().value(index)),
+            DataType::Int16 => ArrowType::Int16(array.as_primitive::<Int16Type>().value(index)),
+            DataType::Int32 => ArrowType::Int32(array.as_primitive::<Int32Type>().value(index)),
+            DataType::Int64 => ArrowType::Int64(array.as_primitive::<Int64Type>().value(index)),
+            DataType::UInt8 => ArrowType::UInt8(array.as_primitive::<UInt8Type>().value(index)),
+            DataType::UInt16 => ArrowType::UInt16(array.as_primitive::<UInt16Type>().value(index)),
+            DataType::UInt32 => ArrowType::UInt32(array.as_primitive::<UInt32Type>().value(index)),
+            DataType::UInt64 => ArrowType::UInt64(array.as_primitive::<UInt64Type>().value(index)),
+            DataType::Float16 => {
+                ArrowType::Float32(array.as_primitive::<Float16Type>().value(index).to_f32())
+            }
+            DataType::Float32 => {
+                ArrowType::Float32(array.as_primitive::<Float32Type>().value(index))
+            }
+            DataType::Float64 => {
+                ArrowType::Float64(array.as_primitive::<Float64Type>().value(index))
+            }
+            DataType::Decimal128(_, scale) => {
+                let value = array.as_primitive::<Decimal128Type>().value(index);
+                ArrowType::BigDecimal(BigDecimal::new(value.into(), *scale as i64))
+            }
+            DataType::Boolean => ArrowType::Boolean(array.as_boolean().value(index)),
+            DataType::Utf8 => ArrowType::Utf8(array.as_string::<i32>().value(index).to_owned()),
+            DataType::LargeUtf8 => {
+                ArrowType::Utf8(array.as_string::<i64>().value(index).to_owned())
+            }
+            DataType::Date32 => ArrowType::Date32(array.as_primitive::<Date32Type>().value(index)),
+            DataType::Date64 => ArrowType::Date64(array.as_primitive::<Date64Type>().value(index)),
+            DataType::Time32(TimeUnit::Second) => time(
+                array
+                    .as_primitive::<Time32SecondType>()
+                    .value_as_time(index),
+            ),
+            DataType::Time32(TimeUnit::Millisecond) => time(
+                array
+                    .as_primitive::<Time32MillisecondType>()
+                    .value_as_time(index),
+            ),
+            DataType::Time64(TimeUnit::Microsecond) => time(
+                array
+                    .as_primitive::<Time64MicrosecondType>()
+                    .value_as_time(index),
+            ),
+            DataType::Time64(TimeUnit::Nanosecond) => time(
+                array
+                    .as_primitive::<Time64NanosecondType>()
+                    .value_as_time(index),
+            ),
+            DataType::Timestamp(unit, timezone) => {
+                let date_time = match unit {
+                    TimeUnit::Second => array
+                        .as_primitive::<TimestampSecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Millisecond => array
+                        .as_primitive::<TimestampMillisecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Microsecond => array
+                        .as_primitive::<TimestampMicrosecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Nanosecond => array
+                        .as_primitive::<TimestampNanosecondType>()
+                        .value_as_datetime(index),
+                };
+
+                timestamp(date_time, timezone.is_some())
+            }
+            DataType::Null => ArrowType::Void,
+            _ => ArrowType::Unsupported,
+        }
+    }
 }
 
 #[macro_export]
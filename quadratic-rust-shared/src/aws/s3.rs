@@ -1,8 +1,10 @@
 use aws_sdk_s3::{
     operation::{get_object::GetObjectOutput, put_object::PutObjectOutput},
+    presigning::PresigningConfig,
     primitives::{ByteStream, SdkBody},
     Client,
 };
+use std::time::Duration;
 
 use crate::aws::error::Aws as AwsError;
 use crate::error::{Result, SharedError};
@@ -45,6 +47,85 @@ pub async fn upload_object(
         })
 }
 
+pub async fn delete_object(client: &Client, bucket: &str, key: &str) -> Result<()> {
+    client
+        .delete_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|error| {
+            SharedError::Aws(AwsError::S3(format!(
+                "Error deleting file {key} from bucket {bucket}: {:?}.",
+                error
+            )))
+        })?;
+
+    Ok(())
+}
+
+pub async fn object_exists(client: &Client, bucket: &str, key: &str) -> Result<bool> {
+    match client.head_object().bucket(bucket).key(key).send().await {
+        Ok(_) => Ok(true),
+        Err(error) if error.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+        Err(error) => Err(SharedError::Aws(AwsError::S3(format!(
+            "Error checking file {key} in bucket {bucket}: {:?}.",
+            error
+        )))),
+    }
+}
+
+pub async fn presigned_object_url(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    expires_in: Duration,
+) -> Result<String> {
+    let presigned_error = |error| {
+        SharedError::Aws(AwsError::S3(format!(
+            "Error presigning file {key} in bucket {bucket}: {error}."
+        )))
+    };
+    let config = PresigningConfig::expires_in(expires_in)
+        .map_err(|error| presigned_error(format!("{:?}", error)))?;
+    let request = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .presigned(config)
+        .await
+        .map_err(|error| presigned_error(format!("{:?}", error)))?;
+
+    Ok(request.uri().to_string())
+}
+
+pub async fn list_objects(client: &Client, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+    let mut keys = vec![];
+    let mut pages = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .prefix(prefix)
+        .into_paginator()
+        .send();
+
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|error| {
+            SharedError::Aws(AwsError::S3(format!(
+                "Error listing files with prefix {prefix} in bucket {bucket}: {:?}.",
+                error
+            )))
+        })?;
+
+        keys.extend(
+            page.contents()
+                .iter()
+                .filter_map(|object| object.key().map(ToOwned::to_owned)),
+        );
+    }
+
+    Ok(keys)
+}
+
 #[cfg(test)]
 pub mod tests {
     // use aws_config::{imds::Client as ImdsClient, provider_config::ProviderConfig};
@@ -6,6 +6,7 @@ use aes::{
 };
 use bytes::Bytes;
 use cbc::{Decryptor, Encryptor};
+use rand::{rngs::OsRng, RngCore};
 
 use crate::{crypto::error::Crypto as CryptoError, error::Result, SharedError};
 
@@ -20,6 +21,12 @@ pub fn encrypt(key: &[u8; 32], iv: &[u8; 16], data: &[u8]) -> Result<Bytes> {
     Ok(encrypted.into())
 }
 
+/// Convenience function to handle errors when encrypting data.
+fn encrypt_error(e: impl Debug) -> SharedError {
+    let error = CryptoError::AesCbcEncode(format!("Error encoding data: {:?}", e));
+    SharedError::Crypto(error)
+}
+
 /// Convenience function to handle errors when decrypting data.
 fn decrypt_error(e: impl Debug) -> SharedError {
     let error = CryptoError::AesCbcDecode(format!("Error decoding data: {:?}", e));
@@ -52,6 +59,18 @@ pub fn decrypt_from_api(key: &str, data: &str) -> Result<String> {
     Ok(decrypted_string)
 }
 
+/// Encrypt data in the same format as the Quadratic API: a random IV is
+/// prepended to the data and both are hex encoded.
+pub fn encrypt_for_api(key: &str, data: &str) -> Result<String> {
+    let key = hex::decode(key).map_err(encrypt_error)?;
+    let key = key.try_into().map_err(encrypt_error)?;
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut iv);
+    let encrypted = encrypt(&key, &iv, data.as_bytes())?;
+
+    Ok(format!("{}:{}", hex::encode(iv), hex::encode(encrypted)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,4 +91,16 @@ mod tests {
 
         assert_eq!(text, decrypted.as_bytes());
     }
+
+    #[test]
+    fn encrypt_for_and_decrypt_from_api() {
+        let key = hex::encode([0x42; 32]);
+        let text = "Hello, world!";
+
+        let encrypted = encrypt_for_api(&key, text).unwrap();
+        let decrypted = decrypt_from_api(&key, &encrypted).unwrap();
+
+        assert_eq!(text, decrypted);
+        assert_ne!(encrypted, encrypt_for_api(&key, text).unwrap());
+    }
 }
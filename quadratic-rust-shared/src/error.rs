@@ -78,6 +78,12 @@ impl From<reqwest::Error> for SharedError {
     }
 }
 
+impl From<reqwest::header::InvalidHeaderValue> for SharedError {
+    fn from(error: reqwest::header::InvalidHeaderValue) -> Self {
+        SharedError::Request(error.to_string())
+    }
+}
+
 impl From<serde_json::Error> for SharedError {
     fn from(error: serde_json::Error) -> Self {
         SharedError::Serialization(error.to_string())
@@ -0,0 +1,291 @@
+//! Prometheus Metrics
+//!
+//! A small registry of counters, gauges and histograms that renders the
+//! Prometheus text exposition format, so that each service can serve it from a
+//! `/metrics` endpoint.  Metrics are created the first time they're recorded.
+//! Recording a metric as a different type than it was created with is logged
+//! and ignored, since metrics should never fail a request.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The content type of `Metrics::render()`
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Default histogram buckets, in seconds
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Labels of a single series, e.g. `&[("kind", "postgres")]`
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Counter(f64),
+    Gauge(f64),
+    Histogram {
+        buckets: Vec<f64>,
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Counter(_) => "counter",
+            Value::Gauge(_) => "gauge",
+            Value::Histogram { .. } => "histogram",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Family {
+    help: String,
+    type_name: &'static str,
+    /// Series by their rendered labels
+    series: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(name, value)| format!(r#"{name}="{}""#, escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Join rendered labels with an extra label, wrapped in braces
+fn with_label(labels: &str, extra: Option<String>) -> String {
+    match (labels.is_empty(), extra) {
+        (true, None) => String::new(),
+        (true, Some(extra)) => format!("{{{extra}}}"),
+        (false, None) => format!("{{{labels}}}"),
+        (false, Some(extra)) => format!("{{{labels},{extra}}}"),
+    }
+}
+
+fn format_float(value: f64) -> String {
+    match value {
+        v if v == f64::INFINITY => "+Inf".into(),
+        v if v == f64::NEG_INFINITY => "-Inf".into(),
+        v => v.to_string(),
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(
+        &self,
+        name: &str,
+        help: &str,
+        labels: Labels,
+        new: impl FnOnce() -> Value,
+        update: impl FnOnce(&mut Value),
+    ) {
+        let Ok(mut families) = self.families.lock() else {
+            return;
+        };
+
+        let value = new();
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            type_name: value.type_name(),
+            series: BTreeMap::new(),
+        });
+
+        if family.type_name != value.type_name() {
+            tracing::warn!(
+                "Metric {name} is a {}, not a {}",
+                family.type_name,
+                value.type_name()
+            );
+            return;
+        }
+
+        update(family.series.entry(render_labels(labels)).or_insert(value));
+    }
+
+    /// Add to a counter
+    pub fn inc_counter(&self, name: &str, help: &str, labels: Labels, amount: f64) {
+        self.record(
+            name,
+            help,
+            labels,
+            || Value::Counter(0.0),
+            |value| {
+                if let Value::Counter(total) = value {
+                    *total += amount;
+                }
+            },
+        );
+    }
+
+    /// Set a gauge
+    pub fn set_gauge(&self, name: &str, help: &str, labels: Labels, gauge: f64) {
+        self.record(
+            name,
+            help,
+            labels,
+            || Value::Gauge(0.0),
+            |value| {
+                if let Value::Gauge(current) = value {
+                    *current = gauge;
+                }
+            },
+        );
+    }
+
+    /// Record an observation in a histogram with the given bucket bounds
+    pub fn observe_with_buckets(
+        &self,
+        name: &str,
+        help: &str,
+        labels: Labels,
+        buckets: &[f64],
+        observation: f64,
+    ) {
+        self.record(
+            name,
+            help,
+            labels,
+            || Value::Histogram {
+                buckets: buckets.to_vec(),
+                counts: vec![0; buckets.len()],
+                sum: 0.0,
+                count: 0,
+            },
+            |value| {
+                if let Value::Histogram {
+                    buckets,
+                    counts,
+                    sum,
+                    count,
+                } = value
+                {
+                    for (bound, bucket_count) in buckets.iter().zip(counts.iter_mut()) {
+                        if observation <= *bound {
+                            *bucket_count += 1;
+                        }
+                    }
+
+                    *sum += observation;
+                    *count += 1;
+                }
+            },
+        );
+    }
+
+    /// Record a duration in a histogram with `LATENCY_BUCKETS`, in seconds
+    pub fn observe_duration(&self, name: &str, help: &str, labels: Labels, duration: Duration) {
+        self.observe_with_buckets(name, help, labels, LATENCY_BUCKETS, duration.as_secs_f64());
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let Ok(families) = self.families.lock() else {
+            return output;
+        };
+
+        for (name, family) in families.iter() {
+            let _ = writeln!(output, "# HELP {name} {}", family.help.replace('\n', " "));
+            let _ = writeln!(output, "# TYPE {name} {}", family.type_name);
+
+            for (labels, value) in family.series.iter() {
+                match value {
+                    Value::Counter(value) | Value::Gauge(value) => {
+                        let labels = with_label(labels, None);
+                        let _ = writeln!(output, "{name}{labels} {}", format_float(*value));
+                    }
+                    Value::Histogram {
+                        buckets,
+                        counts,
+                        sum,
+                        count,
+                    } => {
+                        for (bound, bucket_count) in buckets.iter().zip(counts.iter()) {
+                            let le = Some(format!(r#"le="{}""#, format_float(*bound)));
+                            let labels = with_label(labels, le);
+                            let _ = writeln!(output, "{name}_bucket{labels} {bucket_count}");
+                        }
+
+                        let inf = with_label(labels, Some(r#"le="+Inf""#.into()));
+                        let labels = with_label(labels, None);
+                        let _ = writeln!(output, "{name}_bucket{inf} {count}");
+                        let _ = writeln!(output, "{name}_sum{labels} {}", format_float(*sum));
+                        let _ = writeln!(output, "{name}_count{labels} {count}");
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_and_gauges() {
+        let metrics = Metrics::new();
+        metrics.inc_counter("requests_total", "Requests", &[("kind", "a\"b")], 1.0);
+        metrics.inc_counter("requests_total", "Requests", &[("kind", "a\"b")], 2.0);
+        metrics.set_gauge("rooms", "Open rooms", &[], 4.0);
+
+        // a metric can't change its type
+        metrics.set_gauge("requests_total", "Requests", &[], 1.0);
+
+        let expected = "\
+# HELP requests_total Requests
+# TYPE requests_total counter
+requests_total{kind=\"a\\\"b\"} 3
+# HELP rooms Open rooms
+# TYPE rooms gauge
+rooms 4
+";
+        assert_eq!(metrics.render(), expected);
+    }
+
+    #[test]
+    fn renders_histograms() {
+        let metrics = Metrics::new();
+        let buckets = &[0.1, 1.0];
+        metrics.observe_with_buckets("latency", "Latency", &[("kind", "pg")], buckets, 0.05);
+        metrics.observe_with_buckets("latency", "Latency", &[("kind", "pg")], buckets, 0.5);
+        metrics.observe_with_buckets("latency", "Latency", &[("kind", "pg")], buckets, 5.0);
+
+        let expected = "\
+# HELP latency Latency
+# TYPE latency histogram
+latency_bucket{kind=\"pg\",le=\"0.1\"} 1
+latency_bucket{kind=\"pg\",le=\"1\"} 2
+latency_bucket{kind=\"pg\",le=\"+Inf\"} 3
+latency_sum{kind=\"pg\"} 5.55
+latency_count{kind=\"pg\"} 3
+";
+        assert_eq!(metrics.render(), expected);
+    }
+}
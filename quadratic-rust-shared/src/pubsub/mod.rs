@@ -62,6 +62,13 @@ pub trait PubSub {
 
     fn trim(&mut self, channel: &str, key: &str) -> impl Future<Output = Result<i64>> + Send;
 
+    fn delete(
+        &mut self,
+        channel: &str,
+        keys: Vec<&str>,
+        preserve_sequence: bool,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
     fn messages(
         &mut self,
         channel: &str,
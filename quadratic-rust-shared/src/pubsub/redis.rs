@@ -117,6 +117,15 @@ impl super::PubSub for RedisConnection {
         unimplemented!()
     }
 
+    async fn delete(
+        &mut self,
+        _channel: &str,
+        _keys: Vec<&str>,
+        _preserve_sequence: bool,
+    ) -> Result<i64> {
+        unimplemented!()
+    }
+
     async fn messages(
         &mut self,
         _channel: &str,
@@ -103,6 +103,20 @@ fn stream_ids_to_messages(ids: Vec<StreamId>, preserve_sequence: bool) -> Vec<Me
         .collect::<Vec<_>>()
 }
 
+impl RedisConnection {
+    /// Add a member to a set.  Returns false if it was already in the set.
+    pub async fn add_to_set(&mut self, set_key: &str, member: &str) -> Result<bool> {
+        let added: i64 = self.multiplex.sadd(set_key, member).await?;
+        Ok(added > 0)
+    }
+
+    /// Remove a member from a set
+    pub async fn remove_from_set(&mut self, set_key: &str, member: &str) -> Result<()> {
+        let _: i64 = self.multiplex.srem(set_key, member).await?;
+        Ok(())
+    }
+}
+
 impl super::PubSub for RedisConnection {
     type Connection = RedisConnection;
 
@@ -247,6 +261,23 @@ impl super::PubSub for RedisConnection {
         }
     }
 
+    /// Delete messages from a channel
+    async fn delete(
+        &mut self,
+        channel: &str,
+        keys: Vec<&str>,
+        preserve_sequence: bool,
+    ) -> Result<i64> {
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let ids = to_keys(keys, preserve_sequence);
+        let deleted = self.multiplex.xdel(channel, &ids).await?;
+
+        Ok(deleted)
+    }
+
     /// Get unread messages from a channel.  Specify the keys to get messages for,
     /// or None to get all new messages.
     ///
@@ -478,6 +509,19 @@ pub mod tests {
         assert!(results.contains(&channel));
     }
 
+    #[tokio::test]
+    async fn stream_sets() {
+        let (config, _) = setup();
+        let set_key = Uuid::new_v4().to_string();
+        let mut connection = RedisConnection::new(config).await.unwrap();
+
+        assert!(connection.add_to_set(&set_key, "a").await.unwrap());
+        assert!(!connection.add_to_set(&set_key, "a").await.unwrap());
+
+        connection.remove_from_set(&set_key, "a").await.unwrap();
+        assert!(connection.add_to_set(&set_key, "a").await.unwrap());
+    }
+
     #[tokio::test]
     async fn stream_active_channels() {
         let (config, channel) = setup();
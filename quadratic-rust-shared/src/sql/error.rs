@@ -3,6 +3,9 @@ use thiserror::Error;
 
 #[derive(Error, Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Sql {
+    #[error("Error cancelling query: {0}")]
+    Cancel(String),
+
     #[error("Error connecting to database: {0}")]
     Connect(String),
 
@@ -12,6 +15,12 @@ pub enum Sql {
     #[error("Error executing query: {0}")]
     Query(String),
 
+    #[error("Query rejected on a read-only connection: {0}")]
+    ReadOnly(String),
+
     #[error("Error creating schema: {0}")]
     Schema(String),
+
+    #[error("Error writing to database: {0}")]
+    Write(String),
 }
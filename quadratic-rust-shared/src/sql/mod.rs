@@ -5,11 +5,16 @@ use arrow::{
 use async_trait::async_trait;
 use bytes::Bytes;
 use parquet::arrow::ArrowWriter;
-use schema::DatabaseSchema;
+use schema::{DatabaseSchema, SchemaFilter};
 use snowflake_connection::SnowflakeConnection;
 use std::sync::Arc;
+use write::{WriteData, WriteOptions};
 
-use crate::{arrow::arrow_type::ArrowType, error::Result};
+use crate::{
+    arrow::arrow_type::ArrowType,
+    error::{Result, SharedError},
+    sql::error::Sql as SqlError,
+};
 
 use self::{
     mssql_connection::MsSqlConnection, mysql_connection::MySqlConnection,
@@ -20,8 +25,10 @@ pub mod error;
 pub mod mssql_connection;
 pub mod mysql_connection;
 pub mod postgres_connection;
+pub mod read_only;
 pub mod schema;
 pub mod snowflake_connection;
+pub mod write;
 
 pub enum SqlConnection {
     Postgres(PostgresConnection),
@@ -49,6 +56,55 @@ pub trait Connection {
         max_bytes: Option<u64>,
     ) -> Result<(Bytes, bool, usize)>;
 
+    /// Check that a pooled connection is still usable before reusing it.
+    ///
+    /// Defaults to Ok(()) for databases without a persistent connection.
+    async fn ping(&self, _pool: &mut Self::Conn) -> Result<()> {
+        Ok(())
+    }
+
+    /// Clear the state a query may have left in the session, such as
+    /// settings, temporary tables or an open transaction, before it's reused.
+    ///
+    /// Returns an error if the session can't be reset, in which case it must
+    /// not be reused.  This is the default, since not every driver exposes a
+    /// way to reset a session.
+    async fn reset(&self, _pool: &mut Self::Conn) -> Result<()> {
+        Err(SharedError::Sql(SqlError::Connect(format!(
+            "{} sessions can't be reset",
+            self.kind()
+        ))))
+    }
+
+    /// Get the database-native identifier of the session behind `pool`, used
+    /// to cancel a running query from a separate connection.
+    ///
+    /// Returns None if the database does not support cancellation.
+    async fn session_id(&self, _pool: &mut Self::Conn) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Cancel the query currently running in the session `session_id`.
+    ///
+    /// This opens a new connection, since the original one is busy running
+    /// the query.
+    async fn cancel(&self, _session_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the connection only allows statements that read data
+    fn read_only(&self) -> bool {
+        false
+    }
+
+    /// Whether data may be written into the connection's tables
+    fn allow_write(&self) -> bool {
+        false
+    }
+
+    /// The kind of database, e.g. "postgres", used to label metrics
+    fn kind(&self) -> &'static str;
+
     /// Get the number of columns in a row
     fn row_len(row: &Self::Row) -> usize;
 
@@ -58,8 +114,24 @@ pub trait Connection {
     /// Get the name of a column
     fn column_name(col: &Self::Column) -> &str;
 
-    /// Generically query a database
-    async fn schema(&self, pool: &mut Self::Conn) -> Result<DatabaseSchema>;
+    /// Introspect the tables and views of a database, optionally limited to
+    /// a single schema and/or table
+    async fn schema(&self, pool: &mut Self::Conn, filter: &SchemaFilter) -> Result<DatabaseSchema>;
+
+    /// Insert or upsert rows into a table, writing all batches in a single
+    /// transaction.  The data should already be validated against the table.
+    ///
+    /// Returns the number of rows written.
+    async fn write(
+        &self,
+        _pool: &mut Self::Conn,
+        _data: &WriteData,
+        _options: &WriteOptions,
+    ) -> Result<usize> {
+        Err(SharedError::Sql(SqlError::Write(
+            "Writing is not supported for this connection".into(),
+        )))
+    }
 
     /// Convert a database-specific column to an Arrow type
     fn to_arrow(row: &Self::Row, col: &Self::Column, col_index: usize) -> ArrowType;
@@ -1,17 +1,17 @@
 use std::collections::BTreeMap;
 use std::str::FromStr;
 
-use arrow::datatypes::Date32Type;
+use arrow::datatypes::{Date32Type, Date64Type};
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
 use bytes::Bytes;
 use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use futures_util::{StreamExt, TryStreamExt};
+use futures_util::StreamExt;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tiberius::xml::XmlData;
 use tiberius::ColumnData;
-use tiberius::{AuthMethod, Client, Column, Config, FromSql, FromSqlOwned, Row};
+use tiberius::{AuthMethod, Client, Column, Config, FromSql, FromSqlOwned, Query, Row};
 use tokio::net::TcpStream;
 use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
 use uuid::Uuid;
@@ -19,7 +19,10 @@ use uuid::Uuid;
 use crate::arrow::arrow_type::ArrowType;
 use crate::error::{Result, SharedError};
 use crate::sql::error::Sql as SqlError;
-use crate::sql::schema::{DatabaseSchema, SchemaColumn, SchemaTable};
+use crate::sql::schema::{
+    non_empty, DatabaseSchema, SchemaColumn, SchemaFilter, SchemaTable, TableKind,
+};
+use crate::sql::write::{write_error, write_sql, Dialect, WriteData, WriteOptions};
 use crate::sql::Connection;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +32,15 @@ pub struct MsSqlConnection {
     pub host: String,
     pub port: Option<String>,
     pub database: String,
+    /// Only allow statements that read data.  SQL Server has no read-only
+    /// session, so use a login without write permissions to enforce it in
+    /// the database too.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Allow writing Parquet or Arrow data into tables.  Writes must be
+    /// explicitly enabled, even on connections that aren't read only.
+    #[serde(default)]
+    pub allow_write: bool,
 }
 
 impl MsSqlConnection {
@@ -38,6 +50,8 @@ impl MsSqlConnection {
         host: String,
         port: Option<String>,
         database: String,
+        read_only: bool,
+        allow_write: bool,
     ) -> MsSqlConnection {
         MsSqlConnection {
             username,
@@ -45,6 +59,8 @@ impl MsSqlConnection {
             host,
             port,
             database,
+            read_only,
+            allow_write,
         }
     }
 
@@ -65,8 +81,27 @@ impl MsSqlConnection {
 
         Ok(rows)
     }
+
+    /// Run a schema query, binding the filter's schema and table to @P1 and @P2
+    async fn query_filtered(
+        client: &mut Client<Compat<TcpStream>>,
+        sql: &str,
+        filter: &SchemaFilter,
+    ) -> Result<Vec<Row>> {
+        client
+            .query(sql, &[&filter.schema.as_deref(), &filter.table.as_deref()])
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Schema(e.to_string())))?
+            .into_first_result()
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Schema(e.to_string())))
+    }
 }
 
+/// Queries are cancelled on the client rather than with `KILL`, which would
+/// end another session: tiberius can't send an attention while a query is
+/// running, so the cancelled query's connection is closed instead, and SQL
+/// Server aborts the batch of a closed connection.
 #[async_trait]
 impl Connection for MsSqlConnection {
     type Conn = Client<Compat<TcpStream>>;
@@ -85,6 +120,18 @@ impl Connection for MsSqlConnection {
         col.name()
     }
 
+    fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn allow_write(&self) -> bool {
+        self.allow_write
+    }
+
+    fn kind(&self) -> &'static str {
+        "mssql"
+    }
+
     async fn connect(&self) -> Result<Client<Compat<TcpStream>>> {
         let mut config = Config::new();
         config.host(&self.host);
@@ -107,6 +154,13 @@ impl Connection for MsSqlConnection {
 
         config.trust_cert();
 
+        // ApplicationIntent=ReadOnly, which routes to a readable secondary if
+        // there is one.  It's only a routing hint: on a primary or a server
+        // outside an availability group, writes still succeed, so read-only
+        // connections rely on the statement check unless they use a login
+        // without write permissions.
+        config.readonly(self.read_only);
+
         let tcp = TcpStream::connect(config.get_addr()).await.map_err(|e| {
             SharedError::Sql(SqlError::Connect(format!("Failed to connect: {}", e)))
         })?;
@@ -165,37 +219,54 @@ impl Connection for MsSqlConnection {
         Ok((bytes, over_the_limit, num_records))
     }
 
-    async fn schema(&self, client: &mut Self::Conn) -> Result<DatabaseSchema> {
+    async fn ping(&self, client: &mut Self::Conn) -> Result<()> {
+        client
+            .simple_query("SELECT 1")
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))?
+            .into_results()
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))?;
+
+        Ok(())
+    }
+
+    async fn schema(
+        &self,
+        client: &mut Self::Conn,
+        filter: &SchemaFilter,
+    ) -> Result<DatabaseSchema> {
         let database = self.database.to_owned();
+        let filter_sql = "
+    AND (@P1 IS NULL OR s.name = @P1)
+    AND (@P2 IS NULL OR o.name = @P2)";
         let sql = format!(
             "
 SELECT
     DB_NAME() AS 'database',
     s.name AS 'schema',
-    t.name AS 'table',
+    o.name AS 'table',
     c.name AS 'column_name',
     TYPE_NAME(c.user_type_id) AS 'column_type',
-    CASE WHEN c.is_nullable = 1 THEN 'YES' ELSE 'NO' END AS 'is_nullable'
+    CASE WHEN c.is_nullable = 1 THEN 'YES' ELSE 'NO' END AS 'is_nullable',
+    CASE WHEN o.type = 'V' THEN 'VIEW' ELSE 'BASE TABLE' END AS 'table_type',
+    CAST(ep.value AS NVARCHAR(MAX)) AS 'column_comment'
 FROM
     {database}.sys.columns c
 INNER JOIN
-    {database}.sys.tables t ON c.object_id = t.object_id
+    {database}.sys.objects o ON c.object_id = o.object_id
 INNER JOIN
-    {database}.sys.schemas s ON t.schema_id = s.schema_id
+    {database}.sys.schemas s ON o.schema_id = s.schema_id
+LEFT JOIN
+    {database}.sys.extended_properties ep ON ep.class = 1 AND ep.major_id = c.object_id AND ep.minor_id = c.column_id AND ep.name = 'MS_Description'
+WHERE
+    o.type IN ('U', 'V')
+    AND o.is_ms_shipped = 0{filter_sql}
 ORDER BY
-    t.name, c.column_id, c.name"
+    o.name, c.column_id, c.name"
         );
 
-        let row_stream = client
-            .query(sql, &[])
-            .await
-            .map_err(|e| SharedError::Sql(SqlError::Schema(e.to_string())))?
-            .into_row_stream();
-
-        let rows: Vec<Row> = row_stream
-            .try_collect()
-            .await
-            .map_err(|e| SharedError::Sql(SqlError::Schema(e.to_string())))?;
+        let rows = Self::query_filtered(client, &sql, filter).await?;
 
         let mut schema = DatabaseSchema {
             database: self.database.to_owned(),
@@ -212,22 +283,213 @@ ORDER BY
             schema
                 .tables
                 .entry(table_name.to_owned())
-                .or_insert_with(|| SchemaTable {
-                    name: table_name,
-                    schema: safe_get(row.get(1), "Schema"),
-                    columns: vec![],
+                .or_insert_with(|| {
+                    let kind = match row.get(6) {
+                        Some("VIEW") => TableKind::View,
+                        _ => TableKind::Table,
+                    };
+                    SchemaTable::new(table_name, safe_get(row.get(1), "Schema"), kind)
                 })
                 .columns
                 .push(SchemaColumn {
                     name: safe_get(row.get(3), "Column"),
                     r#type: safe_get(row.get(4), "Type"),
                     is_nullable: row.get(5).map_or("NO", |v| v).to_uppercase() == "YES",
+                    comment: non_empty(row.get::<&str, usize>(7).map(str::to_string)),
                 });
         }
 
+        // all remaining queries return strings, with the schema and table
+        // names first
+        let get_opt = |row: &Row, index: usize| row.get::<&str, usize>(index).map(str::to_string);
+        let get = |row: &Row, index: usize| get_opt(row, index).unwrap_or_default();
+
+        // table comments and row counts from the partition metadata
+        let sql = format!(
+            "
+SELECT
+    s.name,
+    o.name,
+    CAST(ep.value AS NVARCHAR(MAX)),
+    CAST((
+        SELECT SUM(p.rows) FROM {database}.sys.partitions p
+        WHERE p.object_id = o.object_id AND p.index_id IN (0, 1)
+    ) AS NVARCHAR(20))
+FROM
+    {database}.sys.objects o
+INNER JOIN
+    {database}.sys.schemas s ON o.schema_id = s.schema_id
+LEFT JOIN
+    {database}.sys.extended_properties ep ON ep.class = 1 AND ep.major_id = o.object_id AND ep.minor_id = 0 AND ep.name = 'MS_Description'
+WHERE
+    o.type IN ('U', 'V'){filter_sql}"
+        );
+
+        for row in Self::query_filtered(client, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&get(&row, 0), &get(&row, 1)) {
+                table.comment = non_empty(get_opt(&row, 2));
+                table.row_count = match table.kind {
+                    TableKind::Table => get_opt(&row, 3).and_then(|rows| rows.parse().ok()),
+                    TableKind::View => None,
+                };
+            }
+        }
+
+        // primary keys, with columns in key order
+        let sql = format!(
+            "
+SELECT
+    s.name,
+    o.name,
+    c.name
+FROM
+    {database}.sys.key_constraints kc
+INNER JOIN
+    {database}.sys.index_columns ic ON ic.object_id = kc.parent_object_id AND ic.index_id = kc.unique_index_id
+INNER JOIN
+    {database}.sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id
+INNER JOIN
+    {database}.sys.objects o ON o.object_id = kc.parent_object_id
+INNER JOIN
+    {database}.sys.schemas s ON o.schema_id = s.schema_id
+WHERE
+    kc.type = 'PK'{filter_sql}
+ORDER BY
+    o.name, ic.key_ordinal"
+        );
+
+        for row in Self::query_filtered(client, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&get(&row, 0), &get(&row, 1)) {
+                table.primary_key.push(get(&row, 2));
+            }
+        }
+
+        // foreign keys, with columns in key order
+        let sql = format!(
+            "
+SELECT
+    s.name,
+    o.name,
+    fk.name,
+    c.name,
+    rs.name,
+    ro.name,
+    rc.name
+FROM
+    {database}.sys.foreign_keys fk
+INNER JOIN
+    {database}.sys.foreign_key_columns fkc ON fkc.constraint_object_id = fk.object_id
+INNER JOIN
+    {database}.sys.columns c ON c.object_id = fkc.parent_object_id AND c.column_id = fkc.parent_column_id
+INNER JOIN
+    {database}.sys.columns rc ON rc.object_id = fkc.referenced_object_id AND rc.column_id = fkc.referenced_column_id
+INNER JOIN
+    {database}.sys.objects o ON o.object_id = fk.parent_object_id
+INNER JOIN
+    {database}.sys.schemas s ON o.schema_id = s.schema_id
+INNER JOIN
+    {database}.sys.objects ro ON ro.object_id = fk.referenced_object_id
+INNER JOIN
+    {database}.sys.schemas rs ON ro.schema_id = rs.schema_id
+WHERE
+    1 = 1{filter_sql}
+ORDER BY
+    o.name, fk.name, fkc.constraint_column_id"
+        );
+
+        for row in Self::query_filtered(client, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&get(&row, 0), &get(&row, 1)) {
+                table.push_foreign_key_column(
+                    get(&row, 2),
+                    get(&row, 3),
+                    get(&row, 4),
+                    get(&row, 5),
+                    get(&row, 6),
+                );
+            }
+        }
+
+        // secondary indexes, excluding heaps and included columns
+        let sql = format!(
+            "
+SELECT
+    s.name,
+    o.name,
+    i.name,
+    c.name,
+    CASE WHEN i.is_unique = 1 THEN 'YES' ELSE 'NO' END
+FROM
+    {database}.sys.indexes i
+INNER JOIN
+    {database}.sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id
+INNER JOIN
+    {database}.sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id
+INNER JOIN
+    {database}.sys.objects o ON o.object_id = i.object_id
+INNER JOIN
+    {database}.sys.schemas s ON o.schema_id = s.schema_id
+WHERE
+    i.is_primary_key = 0
+    AND i.type > 0
+    AND ic.is_included_column = 0{filter_sql}
+ORDER BY
+    o.name, i.name, ic.key_ordinal"
+        );
+
+        for row in Self::query_filtered(client, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&get(&row, 0), &get(&row, 1)) {
+                table.push_index_column(get(&row, 2), get(&row, 3), get(&row, 4) == "YES");
+            }
+        }
+
         Ok(schema)
     }
 
+    async fn write(
+        &self,
+        client: &mut Self::Conn,
+        data: &WriteData,
+        options: &WriteOptions,
+    ) -> Result<usize> {
+        // all batches are committed together
+        client
+            .simple_query("BEGIN TRANSACTION")
+            .await
+            .map_err(write_error)?
+            .into_results()
+            .await
+            .map_err(write_error)?;
+
+        for rows in data.batches(Dialect::MsSql, options.batch_size) {
+            let (sql, values) = write_sql(Dialect::MsSql, options, &data.columns, rows);
+            let mut query = Query::new(sql);
+
+            for value in values {
+                bind_arrow_type(&mut query, value);
+            }
+
+            if let Err(e) = query.execute(&mut *client).await {
+                // an error may have already aborted the transaction
+                let rollback = "IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION";
+                if let Ok(stream) = client.simple_query(rollback).await {
+                    let _ = stream.into_results().await;
+                }
+
+                return Err(write_error(e));
+            }
+        }
+
+        client
+            .simple_query("COMMIT TRANSACTION")
+            .await
+            .map_err(write_error)?
+            .into_results()
+            .await
+            .map_err(write_error)?;
+
+        Ok(data.rows.len())
+    }
+
     fn to_arrow(row: &tiberius::Row, _: &tiberius::Column, index: usize) -> ArrowType {
         if let Some((_, column_data)) = row.cells().nth(index) {
             match column_data {
@@ -314,6 +576,34 @@ where
         .unwrap_or(ArrowType::Void)
 }
 
+/// Bind a value, converting to the types that tiberius can send
+fn bind_arrow_type(query: &mut Query<'_>, value: &ArrowType) {
+    match value {
+        ArrowType::Int8(value) => query.bind(i16::from(*value)),
+        ArrowType::Int16(value) => query.bind(*value),
+        ArrowType::Int32(value) => query.bind(*value),
+        ArrowType::Int64(value) => query.bind(*value),
+        ArrowType::UInt8(value) => query.bind(*value),
+        ArrowType::UInt16(value) => query.bind(i32::from(*value)),
+        ArrowType::UInt32(value) => query.bind(i64::from(*value)),
+        ArrowType::UInt64(value) => query.bind(Decimal::from(*value)),
+        ArrowType::Float32(value) => query.bind(*value),
+        ArrowType::Float64(value) => query.bind(*value),
+        ArrowType::BigDecimal(value) => query.bind(Decimal::from_str(&value.to_string()).ok()),
+        ArrowType::Utf8(value) => query.bind(value.to_owned()),
+        ArrowType::Boolean(value) => query.bind(*value),
+        ArrowType::Date32(value) => query.bind(Date32Type::to_naive_date(*value)),
+        ArrowType::Date64(value) => query.bind(Date64Type::to_naive_date(*value)),
+        ArrowType::Time32(value) | ArrowType::TimeTz(value) => query.bind(*value),
+        ArrowType::Time64(value) => query.bind(*value),
+        ArrowType::Timestamp(value) => query.bind(*value),
+        ArrowType::TimestampTz(value) => query.bind(value.fixed_offset()),
+        ArrowType::Uuid(value) => query.bind(*value),
+        ArrowType::Json(value) | ArrowType::Jsonb(value) => query.bind(value.to_string()),
+        ArrowType::Void | ArrowType::Unsupported => query.bind(None::<String>),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -330,6 +620,8 @@ mod tests {
             "0.0.0.0".into(),
             Some("1433".into()),
             "AllTypes".into(),
+            false,
+            false,
         )
     }
 
@@ -446,7 +738,10 @@ mod tests {
     async fn test_mssql_schema() {
         let connection = new_mssql_connection();
         let mut client = connection.connect().await.unwrap();
-        let schema = connection.schema(&mut client).await.unwrap();
+        let schema = connection
+            .schema(&mut client, &SchemaFilter::default())
+            .await
+            .unwrap();
 
         // for (table_name, table) in &schema.tables {
         //     println!("Table: {}", table_name);
@@ -460,171 +755,208 @@ mod tests {
                 name: "id".into(),
                 r#type: "int".into(),
                 is_nullable: false,
+                comment: None,
             },
             SchemaColumn {
                 name: "tinyint_col".into(),
                 r#type: "tinyint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "smallint_col".into(),
                 r#type: "smallint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "int_col".into(),
                 r#type: "int".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "bigint_col".into(),
                 r#type: "bigint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "bit_col".into(),
                 r#type: "bit".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "decimal_col".into(),
                 r#type: "decimal".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "numeric_col".into(),
                 r#type: "numeric".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "money_col".into(),
                 r#type: "money".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "smallmoney_col".into(),
                 r#type: "smallmoney".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "float_col".into(),
                 r#type: "float".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "real_col".into(),
                 r#type: "real".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "date_col".into(),
                 r#type: "date".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "time_col".into(),
                 r#type: "time".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "datetime2_col".into(),
                 r#type: "datetime2".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "datetimeoffset_col".into(),
                 r#type: "datetimeoffset".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "datetime_col".into(),
                 r#type: "datetime".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "smalldatetime_col".into(),
                 r#type: "smalldatetime".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "char_col".into(),
                 r#type: "char".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varchar_col".into(),
                 r#type: "varchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "text_col".into(),
                 r#type: "text".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "nchar_col".into(),
                 r#type: "nchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "nvarchar_col".into(),
                 r#type: "nvarchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "ntext_col".into(),
                 r#type: "ntext".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "binary_col".into(),
                 r#type: "binary".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varbinary_col".into(),
                 r#type: "varbinary".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "image_col".into(),
                 r#type: "image".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "json_col".into(),
                 r#type: "nvarchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "uniqueidentifier_col".into(),
                 r#type: "uniqueidentifier".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "xml_col".into(),
                 r#type: "xml".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varchar_max_col".into(),
                 r#type: "varchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "nvarchar_max_col".into(),
                 r#type: "nvarchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varbinary_max_col".into(),
                 r#type: "varbinary".into(),
                 is_nullable: true,
+                comment: None,
             },
         ];
 
-        let columns = &schema.tables.get("all_native_data_types").unwrap().columns;
+        let table = schema.tables.get("all_native_data_types").unwrap();
 
-        assert_eq!(columns, &expected);
+        assert_eq!(table.columns, expected);
+        assert_eq!(table.kind, TableKind::Table);
+        assert_eq!(table.primary_key, vec!["id".to_string()]);
+        assert!(table.foreign_keys.is_empty());
+        assert!(table.indexes.is_empty());
     }
 }
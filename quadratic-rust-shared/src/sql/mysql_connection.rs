@@ -1,24 +1,28 @@
 use std::collections::BTreeMap;
 
-use arrow::datatypes::Date32Type;
+use arrow::datatypes::{Date32Type, Date64Type};
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
 use bytes::Bytes;
-use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
 use sqlx::{
-    mysql::{MySqlColumn, MySqlConnectOptions, MySqlRow /* , MySqlTypeInfo*/},
-    Column, ConnectOptions, MySqlConnection as SqlxMySqlConnection, Row, TypeInfo,
+    mysql::{MySqlArguments, MySqlColumn, MySqlConnectOptions, MySqlRow /* , MySqlTypeInfo*/},
+    query::Query,
+    Column, ConnectOptions, MySql, MySqlConnection as SqlxMySqlConnection, Row, TypeInfo,
 };
 
 use crate::convert_mysql_type;
 use crate::error::{Result, SharedError};
 use crate::sql::error::Sql as SqlError;
-use crate::sql::schema::{DatabaseSchema, SchemaColumn, SchemaTable};
+use crate::sql::schema::{
+    non_empty, DatabaseSchema, SchemaColumn, SchemaFilter, SchemaTable, TableKind,
+};
+use crate::sql::write::{write_error, write_sql, Dialect, WriteData, WriteOptions};
 use crate::sql::{ArrowType, Connection};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,6 +32,13 @@ pub struct MySqlConnection {
     pub host: String,
     pub port: Option<String>,
     pub database: String,
+    /// Only allow statements that read data
+    #[serde(default)]
+    pub read_only: bool,
+    /// Allow writing Parquet or Arrow data into tables.  Writes must be
+    /// explicitly enabled, even on connections that aren't read only.
+    #[serde(default)]
+    pub allow_write: bool,
 }
 
 impl MySqlConnection {
@@ -37,6 +48,8 @@ impl MySqlConnection {
         host: String,
         port: Option<String>,
         database: String,
+        read_only: bool,
+        allow_write: bool,
     ) -> MySqlConnection {
         MySqlConnection {
             username,
@@ -44,6 +57,8 @@ impl MySqlConnection {
             host,
             port,
             database,
+            read_only,
+            allow_write,
         }
     }
 
@@ -55,6 +70,33 @@ impl MySqlConnection {
 
         Ok(rows)
     }
+
+    /// Run a schema query, binding the filter's schema and table to the
+    /// `(? is null or ... = ?)` placeholder pairs
+    async fn query_filtered(
+        pool: &mut SqlxMySqlConnection,
+        sql: &str,
+        filter: &SchemaFilter,
+    ) -> Result<Vec<MySqlRow>> {
+        sqlx::query(sql)
+            .bind(&filter.schema)
+            .bind(&filter.schema)
+            .bind(&filter.table)
+            .bind(&filter.table)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Query(e.to_string())))
+    }
+}
+
+/// INFORMATION_SCHEMA returns strings as binary, so decode them manually
+fn row_get_opt(row: &MySqlRow, index: usize) -> Option<String> {
+    row.get::<Option<Vec<u8>>, usize>(index)
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn row_get(row: &MySqlRow, index: usize) -> String {
+    row_get_opt(row, index).unwrap_or_default()
 }
 
 #[async_trait]
@@ -75,6 +117,18 @@ impl Connection for MySqlConnection {
         col.name()
     }
 
+    fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn allow_write(&self) -> bool {
+        self.allow_write
+    }
+
+    fn kind(&self) -> &'static str {
+        "mysql"
+    }
+
     async fn connect(&self) -> Result<Self::Conn> {
         let mut options = MySqlConnectOptions::new();
         options = options.host(&self.host);
@@ -96,10 +150,18 @@ impl Connection for MySqlConnection {
             })?);
         }
 
-        let pool = options.connect().await.map_err(|e| {
+        let mut pool = options.connect().await.map_err(|e| {
             SharedError::Sql(SqlError::Connect(format!("{:?}: {e}", self.database)))
         })?;
 
+        // every transaction in the session, including implicit ones, is read-only
+        if self.read_only {
+            sqlx::query("SET SESSION TRANSACTION READ ONLY")
+                .execute(&mut pool)
+                .await
+                .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))?;
+        }
+
         Ok(pool)
     }
 
@@ -136,16 +198,53 @@ impl Connection for MySqlConnection {
         Ok((bytes, over_the_limit, num_records))
     }
 
-    async fn schema(&self, pool: &mut Self::Conn) -> Result<DatabaseSchema> {
+    async fn ping(&self, pool: &mut Self::Conn) -> Result<()> {
+        sqlx::Connection::ping(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))
+    }
+
+    async fn session_id(&self, pool: &mut Self::Conn) -> Result<Option<String>> {
+        let row = sqlx::query("select connection_id()")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Query(e.to_string())))?;
+
+        Ok(Some(row.get::<u64, usize>(0).to_string()))
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<()> {
+        let cancel_error = |e: String| SharedError::Sql(SqlError::Cancel(e));
+
+        // parse into a number so that nothing but an id is interpolated
+        let id = session_id
+            .parse::<u64>()
+            .map_err(|e| cancel_error(e.to_string()))?;
+        let mut pool = self.connect().await?;
+        let sql = format!("KILL QUERY {id}");
+
+        sqlx::raw_sql(&sql)
+            .execute(&mut pool)
+            .await
+            .map_err(|e| cancel_error(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn schema(&self, pool: &mut Self::Conn, filter: &SchemaFilter) -> Result<DatabaseSchema> {
         let database = self.database.to_owned();
         let sql = format!("
             select c.TABLE_SCHEMA as 'database', c.TABLE_SCHEMA as 'schema', c.TABLE_NAME as 'table', 
-                c.COLUMN_NAME as 'column_name', c.DATA_TYPE as 'column_type', c.IS_NULLABLE as 'is_nullable'
+                c.COLUMN_NAME as 'column_name', c.DATA_TYPE as 'column_type', c.IS_NULLABLE as 'is_nullable',
+                t.TABLE_TYPE as 'table_type', c.COLUMN_COMMENT as 'column_comment'
             from INFORMATION_SCHEMA.COLUMNS as c
-            where table_schema = '{database}'
+            inner join INFORMATION_SCHEMA.TABLES as t on t.TABLE_SCHEMA = c.TABLE_SCHEMA and t.TABLE_NAME = c.TABLE_NAME
+            where c.TABLE_SCHEMA = '{database}'
+                and (? is null or c.TABLE_SCHEMA = ?)
+                and (? is null or c.TABLE_NAME = ?)
             order by c.TABLE_NAME, c.ORDINAL_POSITION, c.COLUMN_NAME");
 
-        let rows = MySqlConnection::query_all(pool, &sql).await?;
+        let rows = MySqlConnection::query_filtered(pool, &sql, filter).await?;
 
         let mut schema = DatabaseSchema {
             database: self.database.to_owned(),
@@ -153,32 +252,129 @@ impl Connection for MySqlConnection {
         };
 
         for row in rows.into_iter() {
-            let row_get = |row: &MySqlRow, index: usize| {
-                let bytes: Vec<u8> = row.get::<Vec<u8>, usize>(index);
-                String::from_utf8_lossy(&bytes).into_owned()
-            };
-
             let table_name = row_get(&row, 2);
 
             schema
                 .tables
                 .entry(table_name.to_owned())
-                .or_insert_with(|| SchemaTable {
-                    name: table_name,
-                    schema: row_get(&row, 1),
-                    columns: vec![],
+                .or_insert_with(|| {
+                    let kind = match row_get(&row, 6).as_str() {
+                        "VIEW" => TableKind::View,
+                        _ => TableKind::Table,
+                    };
+                    SchemaTable::new(table_name, row_get(&row, 1), kind)
                 })
                 .columns
                 .push(SchemaColumn {
                     name: row_get(&row, 3),
                     r#type: row_get(&row, 4),
                     is_nullable: matches!(row_get(&row, 5).to_lowercase().as_str(), "yes"),
+                    comment: non_empty(row_get_opt(&row, 7)),
                 });
         }
 
+        // table comments and row estimates, views always have a comment of
+        // 'VIEW' and no row count
+        let sql = format!(
+            "
+            select TABLE_SCHEMA, TABLE_NAME, TABLE_COMMENT, cast(TABLE_ROWS as char)
+            from INFORMATION_SCHEMA.TABLES
+            where TABLE_SCHEMA = '{database}'
+                and (? is null or TABLE_SCHEMA = ?)
+                and (? is null or TABLE_NAME = ?)"
+        );
+
+        for row in MySqlConnection::query_filtered(pool, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&row_get(&row, 0), &row_get(&row, 1)) {
+                if table.kind == TableKind::Table {
+                    table.comment = non_empty(row_get_opt(&row, 2));
+                    table.row_count = row_get_opt(&row, 3).and_then(|rows| rows.parse().ok());
+                }
+            }
+        }
+
+        // primary and foreign keys, with columns in key order
+        let sql = format!(
+            "
+            select TABLE_SCHEMA, TABLE_NAME, CONSTRAINT_NAME, COLUMN_NAME,
+                REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME
+            from INFORMATION_SCHEMA.KEY_COLUMN_USAGE
+            where TABLE_SCHEMA = '{database}'
+                and (CONSTRAINT_NAME = 'PRIMARY' or REFERENCED_TABLE_NAME is not null)
+                and (? is null or TABLE_SCHEMA = ?)
+                and (? is null or TABLE_NAME = ?)
+            order by TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION"
+        );
+
+        for row in MySqlConnection::query_filtered(pool, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&row_get(&row, 0), &row_get(&row, 1)) {
+                let name = row_get(&row, 2);
+                let column = row_get(&row, 3);
+
+                match name.as_str() {
+                    "PRIMARY" => table.primary_key.push(column),
+                    _ => table.push_foreign_key_column(
+                        name,
+                        column,
+                        row_get(&row, 4),
+                        row_get(&row, 5),
+                        row_get(&row, 6),
+                    ),
+                }
+            }
+        }
+
+        // secondary indexes
+        let sql = format!(
+            "
+            select TABLE_SCHEMA, TABLE_NAME, INDEX_NAME, COLUMN_NAME, cast(NON_UNIQUE as char)
+            from INFORMATION_SCHEMA.STATISTICS
+            where TABLE_SCHEMA = '{database}'
+                and INDEX_NAME <> 'PRIMARY'
+                and (? is null or TABLE_SCHEMA = ?)
+                and (? is null or TABLE_NAME = ?)
+            order by TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX"
+        );
+
+        for row in MySqlConnection::query_filtered(pool, &sql, filter).await? {
+            if let Some(table) = schema.table_mut(&row_get(&row, 0), &row_get(&row, 1)) {
+                table.push_index_column(
+                    row_get(&row, 2),
+                    row_get(&row, 3),
+                    row_get(&row, 4) == "0",
+                );
+            }
+        }
+
         Ok(schema)
     }
 
+    async fn write(
+        &self,
+        pool: &mut Self::Conn,
+        data: &WriteData,
+        options: &WriteOptions,
+    ) -> Result<usize> {
+        // all batches are committed together, dropping the transaction on an
+        // error rolls back the batches that were already written
+        let mut transaction = sqlx::Connection::begin(pool).await.map_err(write_error)?;
+
+        for rows in data.batches(Dialect::MySql, options.batch_size) {
+            let (sql, values) = write_sql(Dialect::MySql, options, &data.columns, rows);
+
+            values
+                .into_iter()
+                .fold(sqlx::query(&sql), bind_arrow_type)
+                .execute(&mut *transaction)
+                .await
+                .map_err(write_error)?;
+        }
+
+        transaction.commit().await.map_err(write_error)?;
+
+        Ok(data.rows.len())
+    }
+
     fn to_arrow(row: &Self::Row, column: &Self::Column, index: usize) -> ArrowType {
         // println!("Column: {} ({})", column.name(), column.type_info().name());
         match column.type_info().name() {
@@ -216,6 +412,36 @@ impl Connection for MySqlConnection {
     }
 }
 
+fn bind_arrow_type<'q>(
+    query: Query<'q, MySql, MySqlArguments>,
+    value: &ArrowType,
+) -> Query<'q, MySql, MySqlArguments> {
+    match value {
+        ArrowType::Int8(value) => query.bind(*value),
+        ArrowType::Int16(value) => query.bind(*value),
+        ArrowType::Int32(value) => query.bind(*value),
+        ArrowType::Int64(value) => query.bind(*value),
+        ArrowType::UInt8(value) => query.bind(*value),
+        ArrowType::UInt16(value) => query.bind(*value),
+        ArrowType::UInt32(value) => query.bind(*value),
+        ArrowType::UInt64(value) => query.bind(*value),
+        ArrowType::Float32(value) => query.bind(*value),
+        ArrowType::Float64(value) => query.bind(*value),
+        ArrowType::BigDecimal(value) => query.bind(value.to_owned()),
+        ArrowType::Utf8(value) => query.bind(value.to_owned()),
+        ArrowType::Boolean(value) => query.bind(*value),
+        ArrowType::Date32(value) => query.bind(Date32Type::to_naive_date(*value)),
+        ArrowType::Date64(value) => query.bind(Date64Type::to_naive_date(*value)),
+        ArrowType::Time32(value) | ArrowType::TimeTz(value) => query.bind(*value),
+        ArrowType::Time64(value) => query.bind(*value),
+        ArrowType::Timestamp(value) => query.bind(*value),
+        ArrowType::TimestampTz(value) => query.bind(value.with_timezone(&Utc)),
+        ArrowType::Uuid(value) => query.bind(value.to_string()),
+        ArrowType::Json(value) | ArrowType::Jsonb(value) => query.bind(value.to_owned()),
+        ArrowType::Void | ArrowType::Unsupported => query.bind(None::<String>),
+    }
+}
+
 #[macro_export]
 macro_rules! convert_mysql_type {
     ( $kind:ty, $row:ident, $index:ident ) => {{
@@ -242,6 +468,8 @@ mod tests {
             "0.0.0.0".into(),
             Some("3306".into()),
             "mysql-connection".into(),
+            false,
+            false,
         )
     }
 
@@ -327,7 +555,10 @@ mod tests {
     async fn test_mysql_schema() {
         let connection = new_mysql_connection();
         let mut pool = connection.connect().await.unwrap();
-        let schema = connection.schema(&mut pool).await.unwrap();
+        let schema = connection
+            .schema(&mut pool, &SchemaFilter::default())
+            .await
+            .unwrap();
 
         // for (table_name, table) in &_schema.tables {
         //     println!("Table: {}", table_name);
@@ -341,156 +572,190 @@ mod tests {
                 name: "id".into(),
                 r#type: "int".into(),
                 is_nullable: false,
+                comment: None,
             },
             SchemaColumn {
                 name: "tinyint_col".into(),
                 r#type: "tinyint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "smallint_col".into(),
                 r#type: "smallint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "mediumint_col".into(),
                 r#type: "mediumint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "int_col".into(),
                 r#type: "int".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "bigint_col".into(),
                 r#type: "bigint".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "decimal_col".into(),
                 r#type: "decimal".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "float_col".into(),
                 r#type: "float".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "double_col".into(),
                 r#type: "double".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "bit_col".into(),
                 r#type: "bit".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "char_col".into(),
                 r#type: "char".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varchar_col".into(),
                 r#type: "varchar".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "binary_col".into(),
                 r#type: "binary".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "varbinary_col".into(),
                 r#type: "varbinary".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "tinyblob_col".into(),
                 r#type: "tinyblob".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "blob_col".into(),
                 r#type: "blob".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "mediumblob_col".into(),
                 r#type: "mediumblob".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "longblob_col".into(),
                 r#type: "longblob".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "tinytext_col".into(),
                 r#type: "tinytext".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "text_col".into(),
                 r#type: "text".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "mediumtext_col".into(),
                 r#type: "mediumtext".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "longtext_col".into(),
                 r#type: "longtext".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "enum_col".into(),
                 r#type: "enum".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "set_col".into(),
                 r#type: "set".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "date_col".into(),
                 r#type: "date".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "datetime_col".into(),
                 r#type: "datetime".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "timestamp_col".into(),
                 r#type: "timestamp".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "time_col".into(),
                 r#type: "time".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "year_col".into(),
                 r#type: "year".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "json_col".into(),
                 r#type: "json".into(),
                 is_nullable: true,
+                comment: None,
             },
         ];
 
-        let columns = &schema.tables.get("all_native_data_types").unwrap().columns;
+        let table = schema.tables.get("all_native_data_types").unwrap();
 
-        assert_eq!(columns, &expected);
+        assert_eq!(table.columns, expected);
+        assert_eq!(table.kind, TableKind::Table);
+        assert_eq!(table.primary_key, vec!["id".to_string()]);
+        assert!(table.foreign_keys.is_empty());
+        assert!(table.indexes.is_empty());
     }
 }
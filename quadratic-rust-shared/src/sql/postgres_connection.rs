@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use arrow::datatypes::Date32Type;
+use arrow::datatypes::{Date32Type, Date64Type};
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
 use bytes::Bytes;
@@ -11,14 +11,19 @@ use serde_json::Value;
 use uuid::Uuid;
 
 use sqlx::{
-    postgres::{types::PgTimeTz, PgColumn, PgConnectOptions, PgRow, PgTypeKind},
-    Column, ConnectOptions, PgConnection, Row, TypeInfo,
+    postgres::{types::PgTimeTz, PgArguments, PgColumn, PgConnectOptions, PgRow, PgTypeKind},
+    query::Query,
+    Column, ConnectOptions, PgConnection, Postgres, Row, TypeInfo,
 };
 
 use crate::convert_pg_type;
 use crate::error::{Result, SharedError};
 use crate::sql::error::Sql as SqlError;
-use crate::sql::schema::{DatabaseSchema, SchemaColumn, SchemaTable};
+use crate::sql::schema::{
+    non_empty, DatabaseSchema, SchemaColumn, SchemaFilter, SchemaForeignKey, SchemaIndex,
+    SchemaTable, TableKind,
+};
+use crate::sql::write::{write_error, write_sql, Dialect, WriteData, WriteOptions};
 use crate::sql::{ArrowType, Connection};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,6 +33,13 @@ pub struct PostgresConnection {
     pub host: String,
     pub port: Option<String>,
     pub database: String,
+    /// Only allow statements that read data
+    #[serde(default)]
+    pub read_only: bool,
+    /// Allow writing Parquet or Arrow data into tables.  Writes must be
+    /// explicitly enabled, even on connections that aren't read only.
+    #[serde(default)]
+    pub allow_write: bool,
 }
 
 impl PostgresConnection {
@@ -37,6 +49,8 @@ impl PostgresConnection {
         host: String,
         port: Option<String>,
         database: String,
+        read_only: bool,
+        allow_write: bool,
     ) -> PostgresConnection {
         PostgresConnection {
             username,
@@ -44,6 +58,8 @@ impl PostgresConnection {
             host,
             port,
             database,
+            read_only,
+            allow_write,
         }
     }
 
@@ -55,6 +71,20 @@ impl PostgresConnection {
 
         Ok(rows)
     }
+
+    /// Run a schema query, binding the filter's schema and table to $1 and $2
+    async fn query_filtered(
+        pool: &mut PgConnection,
+        sql: &str,
+        filter: &SchemaFilter,
+    ) -> Result<Vec<PgRow>> {
+        sqlx::query(sql)
+            .bind(&filter.schema)
+            .bind(&filter.table)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Query(e.to_string())))
+    }
 }
 
 #[async_trait]
@@ -75,6 +105,18 @@ impl Connection for PostgresConnection {
         col.name()
     }
 
+    fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn allow_write(&self) -> bool {
+        self.allow_write
+    }
+
+    fn kind(&self) -> &'static str {
+        "postgres"
+    }
+
     async fn connect(&self) -> Result<Self::Conn> {
         let mut options = PgConnectOptions::new();
         options = options.host(&self.host);
@@ -96,10 +138,18 @@ impl Connection for PostgresConnection {
             })?);
         }
 
-        let pool = options.connect().await.map_err(|e| {
+        let mut pool = options.connect().await.map_err(|e| {
             SharedError::Sql(SqlError::Connect(format!("{:?}: {e}", self.database)))
         })?;
 
+        // every transaction in the session, including implicit ones, is read-only
+        if self.read_only {
+            sqlx::query("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")
+                .execute(&mut pool)
+                .await
+                .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))?;
+        }
+
         Ok(pool)
     }
 
@@ -140,18 +190,76 @@ impl Connection for PostgresConnection {
         Ok((bytes, over_the_limit, num_records))
     }
 
-    async fn schema(&self, pool: &mut Self::Conn) -> Result<DatabaseSchema> {
+    async fn ping(&self, pool: &mut Self::Conn) -> Result<()> {
+        sqlx::Connection::ping(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Connect(e.to_string())))
+    }
+
+    async fn reset(&self, pool: &mut Self::Conn) -> Result<()> {
+        let reset_error = |e: sqlx::Error| SharedError::Sql(SqlError::Connect(e.to_string()));
+
+        // DISCARD ALL also deallocates prepared statements, so drop sqlx's
+        // cache of them, and run the reset without preparing it
+        sqlx::Connection::clear_cached_statements(&mut *pool)
+            .await
+            .map_err(reset_error)?;
+        sqlx::raw_sql("DISCARD ALL")
+            .execute(&mut *pool)
+            .await
+            .map_err(reset_error)?;
+
+        // DISCARD ALL resets the session characteristics set in connect()
+        if self.read_only {
+            sqlx::raw_sql("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")
+                .execute(&mut *pool)
+                .await
+                .map_err(reset_error)?;
+        }
+
+        Ok(())
+    }
+
+    async fn session_id(&self, pool: &mut Self::Conn) -> Result<Option<String>> {
+        let row = sqlx::query("select pg_backend_pid()")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Query(e.to_string())))?;
+
+        Ok(Some(row.get::<i32, usize>(0).to_string()))
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<()> {
+        let cancel_error = |e: String| SharedError::Sql(SqlError::Cancel(e));
+        let pid = session_id
+            .parse::<i32>()
+            .map_err(|e| cancel_error(e.to_string()))?;
+        let mut pool = self.connect().await?;
+
+        sqlx::query("select pg_cancel_backend($1)")
+            .bind(pid)
+            .execute(&mut pool)
+            .await
+            .map_err(|e| cancel_error(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn schema(&self, pool: &mut Self::Conn, filter: &SchemaFilter) -> Result<DatabaseSchema> {
         let database = self.database.to_owned();
         let sql = format!("
-            select c.table_catalog as database, c.table_schema as schema, c.table_name as table, c.column_name, c.udt_name as column_type, c.is_nullable
-            from information_schema.tables as t inner join information_schema.columns as c on t.table_name = c.table_name
-            where t.table_type = 'BASE TABLE' 
+            select c.table_catalog as database, c.table_schema as schema, c.table_name as table, c.column_name, c.udt_name as column_type, c.is_nullable,
+                t.table_type, col_description(format('%I.%I', c.table_schema, c.table_name)::regclass, c.ordinal_position::int) as column_comment
+            from information_schema.tables as t inner join information_schema.columns as c on t.table_schema = c.table_schema and t.table_name = c.table_name
+            where t.table_type in ('BASE TABLE', 'VIEW')
                 and c.table_schema not in 
                     ('pg_catalog', 'information_schema')
                     and c.table_catalog = '{database}'
+                    and ($1::text is null or c.table_schema = $1)
+                    and ($2::text is null or c.table_name = $2)
             order by c.table_name, c.ordinal_position, c.column_name");
 
-        let rows = Self::query_all(pool, &sql).await?;
+        let rows = Self::query_filtered(pool, &sql, filter).await?;
 
         let mut schema = DatabaseSchema {
             database,
@@ -165,10 +273,12 @@ impl Connection for PostgresConnection {
                 .tables
                 // get or insert the table
                 .entry(table_name.to_owned())
-                .or_insert_with(|| SchemaTable {
-                    name: table_name,
-                    schema: row.get::<String, usize>(1),
-                    columns: vec![],
+                .or_insert_with(|| {
+                    let kind = match row.get::<String, usize>(6).as_str() {
+                        "VIEW" => TableKind::View,
+                        _ => TableKind::Table,
+                    };
+                    SchemaTable::new(table_name, row.get::<String, usize>(1), kind)
                 })
                 .columns
                 // add the column to the table
@@ -179,12 +289,135 @@ impl Connection for PostgresConnection {
                         row.get::<String, usize>(5).to_lowercase().as_str(),
                         "yes"
                     ),
+                    comment: non_empty(row.get::<Option<String>, usize>(7)),
+                });
+        }
+
+        // table comments and row estimates, reltuples is -1 for tables that
+        // have never been analyzed
+        let sql = "
+            select n.nspname::text, c.relname::text, obj_description(c.oid, 'pg_class'), c.reltuples::bigint
+            from pg_class as c inner join pg_namespace as n on n.oid = c.relnamespace
+            where c.relkind in ('r', 'p', 'v')
+                and n.nspname not in ('pg_catalog', 'information_schema')
+                and ($1::text is null or n.nspname = $1)
+                and ($2::text is null or c.relname = $2)";
+
+        for row in Self::query_filtered(pool, sql, filter).await? {
+            if let Some(table) =
+                schema.table_mut(&row.get::<String, usize>(0), &row.get::<String, usize>(1))
+            {
+                table.comment = non_empty(row.get::<Option<String>, usize>(2));
+                table.row_count = match table.kind {
+                    TableKind::Table => Some(row.get::<i64, usize>(3)).filter(|count| *count >= 0),
+                    TableKind::View => None,
+                };
+            }
+        }
+
+        // primary and foreign keys, with columns in key order
+        let sql = "
+            select n.nspname::text, cl.relname::text, con.conname::text, con.contype::text,
+                array(
+                    select a.attname::text from unnest(con.conkey) with ordinality as k(attnum, ord)
+                    inner join pg_attribute as a on a.attrelid = con.conrelid and a.attnum = k.attnum
+                    order by k.ord
+                ) as columns,
+                fn.nspname::text as referenced_schema, fcl.relname::text as referenced_table,
+                array(
+                    select a.attname::text from unnest(con.confkey) with ordinality as k(attnum, ord)
+                    inner join pg_attribute as a on a.attrelid = con.confrelid and a.attnum = k.attnum
+                    order by k.ord
+                ) as referenced_columns
+            from pg_constraint as con
+                inner join pg_class as cl on cl.oid = con.conrelid
+                inner join pg_namespace as n on n.oid = cl.relnamespace
+                left join pg_class as fcl on fcl.oid = con.confrelid
+                left join pg_namespace as fn on fn.oid = fcl.relnamespace
+            where con.contype in ('p', 'f')
+                and n.nspname not in ('pg_catalog', 'information_schema')
+                and ($1::text is null or n.nspname = $1)
+                and ($2::text is null or cl.relname = $2)
+            order by con.conname";
+
+        for row in Self::query_filtered(pool, sql, filter).await? {
+            if let Some(table) =
+                schema.table_mut(&row.get::<String, usize>(0), &row.get::<String, usize>(1))
+            {
+                let columns = row.get::<Vec<String>, usize>(4);
+
+                match row.get::<String, usize>(3).as_str() {
+                    "p" => table.primary_key = columns,
+                    _ => table.foreign_keys.push(SchemaForeignKey {
+                        name: row.get::<String, usize>(2),
+                        columns,
+                        referenced_schema: row.get::<Option<String>, usize>(5).unwrap_or_default(),
+                        referenced_table: row.get::<Option<String>, usize>(6).unwrap_or_default(),
+                        referenced_columns: row.get::<Vec<String>, usize>(7),
+                    }),
+                }
+            }
+        }
+
+        // secondary indexes
+        let sql = "
+            select n.nspname::text, t.relname::text, i.relname::text, ix.indisunique,
+                array(
+                    select a.attname::text from unnest(ix.indkey::int2[]) with ordinality as k(attnum, ord)
+                    inner join pg_attribute as a on a.attrelid = t.oid and a.attnum = k.attnum
+                    order by k.ord
+                ) as columns
+            from pg_index as ix
+                inner join pg_class as t on t.oid = ix.indrelid
+                inner join pg_class as i on i.oid = ix.indexrelid
+                inner join pg_namespace as n on n.oid = t.relnamespace
+            where not ix.indisprimary
+                and n.nspname not in ('pg_catalog', 'information_schema')
+                and ($1::text is null or n.nspname = $1)
+                and ($2::text is null or t.relname = $2)
+            order by i.relname";
+
+        for row in Self::query_filtered(pool, sql, filter).await? {
+            if let Some(table) =
+                schema.table_mut(&row.get::<String, usize>(0), &row.get::<String, usize>(1))
+            {
+                table.indexes.push(SchemaIndex {
+                    name: row.get::<String, usize>(2),
+                    columns: row.get::<Vec<String>, usize>(4),
+                    is_unique: row.get::<bool, usize>(3),
                 });
+            }
         }
 
         Ok(schema)
     }
 
+    async fn write(
+        &self,
+        pool: &mut Self::Conn,
+        data: &WriteData,
+        options: &WriteOptions,
+    ) -> Result<usize> {
+        // all batches are committed together, dropping the transaction on an
+        // error rolls back the batches that were already written
+        let mut transaction = sqlx::Connection::begin(pool).await.map_err(write_error)?;
+
+        for rows in data.batches(Dialect::Postgres, options.batch_size) {
+            let (sql, values) = write_sql(Dialect::Postgres, options, &data.columns, rows);
+
+            values
+                .into_iter()
+                .fold(sqlx::query(&sql), bind_arrow_type)
+                .execute(&mut *transaction)
+                .await
+                .map_err(write_error)?;
+        }
+
+        transaction.commit().await.map_err(write_error)?;
+
+        Ok(data.rows.len())
+    }
+
     fn to_arrow(row: &Self::Row, column: &Self::Column, index: usize) -> ArrowType {
         // println!("Column: {} ({})", column.name(), column.type_info().name());
         match column.type_info().name() {
@@ -254,6 +487,38 @@ impl Connection for PostgresConnection {
     }
 }
 
+/// Bind a value, using the closest type that Postgres can assign to the
+/// target column
+fn bind_arrow_type<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    value: &ArrowType,
+) -> Query<'q, Postgres, PgArguments> {
+    match value {
+        ArrowType::Int8(value) => query.bind(i16::from(*value)),
+        ArrowType::Int16(value) => query.bind(*value),
+        ArrowType::Int32(value) => query.bind(*value),
+        ArrowType::Int64(value) => query.bind(*value),
+        ArrowType::UInt8(value) => query.bind(i16::from(*value)),
+        ArrowType::UInt16(value) => query.bind(i32::from(*value)),
+        ArrowType::UInt32(value) => query.bind(i64::from(*value)),
+        ArrowType::UInt64(value) => query.bind(BigDecimal::from(*value)),
+        ArrowType::Float32(value) => query.bind(*value),
+        ArrowType::Float64(value) => query.bind(*value),
+        ArrowType::BigDecimal(value) => query.bind(value.to_owned()),
+        ArrowType::Utf8(value) => query.bind(value.to_owned()),
+        ArrowType::Boolean(value) => query.bind(*value),
+        ArrowType::Date32(value) => query.bind(Date32Type::to_naive_date(*value)),
+        ArrowType::Date64(value) => query.bind(Date64Type::to_naive_date(*value)),
+        ArrowType::Time32(value) | ArrowType::TimeTz(value) => query.bind(*value),
+        ArrowType::Time64(value) => query.bind(*value),
+        ArrowType::Timestamp(value) => query.bind(*value),
+        ArrowType::TimestampTz(value) => query.bind(*value),
+        ArrowType::Uuid(value) => query.bind(*value),
+        ArrowType::Json(value) | ArrowType::Jsonb(value) => query.bind(value.to_owned()),
+        ArrowType::Void | ArrowType::Unsupported => query.bind(None::<String>),
+    }
+}
+
 #[macro_export]
 macro_rules! convert_pg_type {
     ( $kind:ty, $row:ident, $index:ident ) => {{
@@ -276,6 +541,8 @@ mod tests {
             "127.0.0.1".into(),
             Some("5433".into()),
             "postgres-connection".into(),
+            false,
+            false,
         )
     }
 
@@ -298,12 +565,66 @@ mod tests {
         // println!("{:?}", _data);
     }
 
+    #[tokio::test]
+    async fn test_postgres_reset() {
+        let connection = new_postgres_connection();
+        let mut pool = connection.connect().await.unwrap();
+        let setting = "select current_setting('application_name')";
+
+        PostgresConnection::query_all(&mut pool, "set application_name = 'leaked'")
+            .await
+            .unwrap();
+        PostgresConnection::query_all(&mut pool, "create temp table leaked (id int)")
+            .await
+            .unwrap();
+
+        connection.reset(&mut pool).await.unwrap();
+
+        let rows = PostgresConnection::query_all(&mut pool, setting)
+            .await
+            .unwrap();
+        assert_ne!(rows[0].get::<String, usize>(0), "leaked");
+        assert!(
+            PostgresConnection::query_all(&mut pool, "select * from leaked")
+                .await
+                .is_err()
+        );
+
+        // statements prepared before the reset can be run again
+        PostgresConnection::query_all(&mut pool, setting)
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_postgres_schema() {
         let connection = new_postgres_connection();
         let mut pool = connection.connect().await.unwrap();
-        let _schema = connection.schema(&mut pool).await.unwrap();
+        let schema = connection
+            .schema(&mut pool, &SchemaFilter::default())
+            .await
+            .unwrap();
+        let table = &schema.tables["all_native_data_types"];
+
+        assert_eq!(table.kind, TableKind::Table);
+        assert_eq!(table.primary_key, vec!["id".to_string()]);
+        assert!(table.foreign_keys.is_empty());
+        assert!(table.indexes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_postgres_schema_filter() {
+        let connection = new_postgres_connection();
+        let mut pool = connection.connect().await.unwrap();
+        let filter = SchemaFilter {
+            schema: Some("public".into()),
+            table: Some("all_native_data_types".into()),
+        };
+        let schema = connection.schema(&mut pool, &filter).await.unwrap();
 
-        // println!("{:?}", schema);
+        assert_eq!(
+            schema.tables.keys().collect::<Vec<_>>(),
+            vec!["all_native_data_types"]
+        );
     }
 }
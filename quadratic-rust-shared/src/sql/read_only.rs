@@ -0,0 +1,277 @@
+//! Read-only SQL enforcement
+//!
+//! Read-only connections only accept statements that read data.  Statements
+//! are classified by their keywords after removing comments, string literals
+//! and quoted identifiers, so a value like `'drop table'` is not rejected.
+//!
+//! Databases disagree on how to lex SQL (backslash escapes, `$$` strings,
+//! `[identifiers]`, nested comments), and a lexer that guesses wrong could be
+//! tricked into treating a statement as a string.  Instead of guessing, the
+//! SQL is checked under every combination of those rules and must pass all of
+//! them.  This only guards the connection's read-only session, which is
+//! enforced by the database where supported.
+
+use crate::error::{Result, SharedError};
+use crate::sql::error::Sql as SqlError;
+
+/// Keywords that a statement may start with on a read-only connection
+pub const ALLOWED_STATEMENTS: &[&str] = &[
+    "DESC", "DESCRIBE", "EXPLAIN", "SELECT", "SHOW", "TABLE", "VALUES", "WITH",
+];
+
+/// Keywords that write data, change the schema or permissions, or run
+/// arbitrary code.  These are rejected anywhere in a statement, since CTEs can
+/// contain DML and SQL Server doesn't require a `;` between statements.
+pub const DENIED_KEYWORDS: &[&str] = &[
+    "ALTER",
+    "BACKUP",
+    "CALL",
+    "COPY",
+    "CREATE",
+    "DBCC",
+    "DELETE",
+    "DROP",
+    "EXEC",
+    "EXECUTE",
+    "GRANT",
+    "INSERT",
+    "INTO",
+    "KILL",
+    "LOCK",
+    "MERGE",
+    "RECONFIGURE",
+    "RENAME",
+    "RESTORE",
+    "REVOKE",
+    "SHUTDOWN",
+    "TRUNCATE",
+    "UPDATE",
+    "UPSERT",
+];
+
+/// Lexing rules that differ between databases
+#[derive(Debug, Clone, Copy)]
+struct Dialect {
+    /// `\'` escapes a quote within a string (MySQL)
+    backslash_escapes: bool,
+    /// `$tag$ ... $tag$` is a string (Postgres)
+    dollar_quotes: bool,
+    /// `[ ... ]` is a quoted identifier (SQL Server)
+    bracket_identifiers: bool,
+    /// `/* /* */ */` is a single comment (Postgres)
+    nested_comments: bool,
+}
+
+impl Dialect {
+    fn all() -> impl Iterator<Item = Dialect> {
+        (0..16).map(|bits| Dialect {
+            backslash_escapes: bits & 1 != 0,
+            dollar_quotes: bits & 2 != 0,
+            bracket_identifiers: bits & 4 != 0,
+            nested_comments: bits & 8 != 0,
+        })
+    }
+}
+
+/// Split SQL into statements of uppercased keywords and identifiers, skipping
+/// comments, literals and quoted identifiers.
+fn statements(sql: &str, dialect: Dialect) -> Vec<Vec<String>> {
+    let chars = sql.chars().collect::<Vec<char>>();
+    let mut statements = vec![vec![]];
+    let mut word = String::new();
+    let mut i = 0;
+
+    // the character after the closing quote, or the end of the input
+    let skip_quoted = |start: usize, close: char, escapes: bool| -> usize {
+        let mut i = start + 1;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if escapes => i += 2,
+                c if c == close => match chars.get(i + 1) {
+                    // a doubled quote is an escaped quote
+                    Some(&next) if next == close => i += 2,
+                    _ => return i + 1,
+                },
+                _ => i += 1,
+            }
+        }
+
+        i
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+            i += 1;
+            continue;
+        }
+
+        if !word.is_empty() {
+            if let Some(statement) = statements.last_mut() {
+                statement.push(std::mem::take(&mut word));
+            }
+        }
+
+        let next = chars.get(i + 1).copied();
+
+        i = match (c, next) {
+            (';', _) => {
+                statements.push(vec![]);
+                i + 1
+            }
+            ('-', Some('-')) => chars[i..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |end| i + end + 1),
+            // MySQL runs the contents of /*! ... */ comments, so read them
+            ('/', Some('*')) if chars.get(i + 2) == Some(&'!') => i + 3,
+            ('/', Some('*')) => {
+                let mut depth = 1;
+                let mut j = i + 2;
+
+                while j < chars.len() && depth > 0 {
+                    match (chars[j], chars.get(j + 1)) {
+                        ('*', Some('/')) => {
+                            depth -= 1;
+                            j += 2;
+                        }
+                        ('/', Some('*')) if dialect.nested_comments => {
+                            depth += 1;
+                            j += 2;
+                        }
+                        _ => j += 1,
+                    }
+                }
+
+                j
+            }
+            ('\'', _) | ('"', _) => skip_quoted(i, c, dialect.backslash_escapes),
+            ('`', _) => skip_quoted(i, '`', false),
+            ('[', _) if dialect.bracket_identifiers => skip_quoted(i, ']', false),
+            ('$', _) if dialect.dollar_quotes => {
+                let tag_end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .map(|end| i + 1 + end);
+
+                match tag_end {
+                    Some(tag_end) if chars[tag_end] == '$' => {
+                        let tag = &chars[i..=tag_end];
+                        let body = tag_end + 1;
+
+                        (body..chars.len())
+                            .find(|&j| chars[j..].starts_with(tag))
+                            .map_or(chars.len(), |j| j + tag.len())
+                    }
+                    _ => i + 1,
+                }
+            }
+            _ => i + 1,
+        };
+    }
+
+    if !word.is_empty() {
+        if let Some(statement) = statements.last_mut() {
+            statement.push(word);
+        }
+    }
+
+    statements.retain(|statement| !statement.is_empty());
+    statements
+}
+
+fn rejected(message: String) -> SharedError {
+    SharedError::Sql(SqlError::ReadOnly(message))
+}
+
+/// Check that `sql` only reads data, returning a `Sql::ReadOnly` error naming
+/// the offending keyword otherwise.
+pub fn validate_read_only(sql: &str) -> Result<()> {
+    for dialect in Dialect::all() {
+        let statements = statements(sql, dialect);
+
+        if statements.is_empty() {
+            return Err(rejected("no statement to run".into()));
+        }
+
+        for statement in statements {
+            let first = &statement[0];
+
+            if !ALLOWED_STATEMENTS.contains(&first.as_str()) {
+                return Err(rejected(format!("{first} statements are not allowed")));
+            }
+
+            if let Some(denied) = statement
+                .iter()
+                .find(|word| DENIED_KEYWORDS.contains(&word.as_str()))
+            {
+                return Err(rejected(format!(
+                    "{denied} is not allowed; quote identifiers that use this name"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_allowed(sql: &str) -> bool {
+        validate_read_only(sql).is_ok()
+    }
+
+    #[test]
+    fn allows_reads() {
+        assert!(is_allowed("select * from users"));
+        assert!(is_allowed("SELECT 1;"));
+        assert!(is_allowed(
+            "  -- comment\n select updated_at from t; select 2"
+        ));
+        assert!(is_allowed("with t as (select 1) select * from t"));
+        assert!(is_allowed("show tables"));
+        assert!(is_allowed("explain select 1"));
+        assert!(is_allowed("select 'drop table users', \"delete\" from t"));
+        assert!(is_allowed("select [first name] from t"));
+        assert!(is_allowed("select $$it's$$"));
+        assert!(is_allowed("/* drop table */ select 1"));
+    }
+
+    #[test]
+    fn rejects_writes() {
+        assert!(!is_allowed(""));
+        assert!(!is_allowed("-- select 1"));
+        assert!(!is_allowed("insert into t values (1)"));
+        assert!(!is_allowed("select 1; drop table t"));
+        assert!(!is_allowed("DROP TABLE t"));
+        assert!(!is_allowed("set role admin"));
+        assert!(!is_allowed(
+            "with d as (delete from t returning *) select * from d"
+        ));
+        assert!(!is_allowed("select * into backup from t"));
+        assert!(!is_allowed("select * from t for update"));
+        assert!(!is_allowed("select 1 delete from t"));
+        assert!(!is_allowed("/*! delete from t */ select 1"));
+        assert!(!is_allowed("select update from t"));
+    }
+
+    #[test]
+    fn rejects_statements_hidden_by_lexing_differences() {
+        // MySQL reads 'a\'' as one string, Postgres ends it at the backslash
+        assert!(!is_allowed(r"select 'a\'; delete from t; select '"));
+        assert!(!is_allowed(r"select 'it\'s', 1; delete from t; select 'x'"));
+
+        // Postgres reads $$'$$ as a string
+        assert!(!is_allowed("select $$'$$; delete from t; select '"));
+
+        // Postgres nests comments, MySQL and SQL Server do not
+        assert!(!is_allowed(
+            "select 1 /* /* */ ' */; delete from t; select '"
+        ));
+    }
+}
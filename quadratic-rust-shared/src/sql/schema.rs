@@ -1,22 +1,200 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    #[default]
+    Table,
+    View,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct SchemaColumn {
     pub name: String,
     pub r#type: String,
     pub is_nullable: bool,
+    pub comment: Option<String>,
 }
 
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SchemaForeignKey {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+/// A secondary index.  Indexes backing the primary key are reported through
+/// `SchemaTable::primary_key` instead.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SchemaIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct SchemaTable {
     pub name: String,
     pub schema: String,
+    pub kind: TableKind,
     pub columns: Vec<SchemaColumn>,
+    pub primary_key: Vec<String>,
+    pub foreign_keys: Vec<SchemaForeignKey>,
+    pub indexes: Vec<SchemaIndex>,
+    pub comment: Option<String>,
+    /// Approximate number of rows, from the database's statistics
+    pub row_count: Option<i64>,
+}
+
+impl SchemaTable {
+    pub fn new(name: String, schema: String, kind: TableKind) -> Self {
+        SchemaTable {
+            name,
+            schema,
+            kind,
+            columns: vec![],
+            primary_key: vec![],
+            foreign_keys: vec![],
+            indexes: vec![],
+            comment: None,
+            row_count: None,
+        }
+    }
+
+    /// Add a column to a foreign key, creating the key for its first column.
+    /// Rows must be visited in key order.
+    pub fn push_foreign_key_column(
+        &mut self,
+        name: String,
+        column: String,
+        referenced_schema: String,
+        referenced_table: String,
+        referenced_column: String,
+    ) {
+        match self.foreign_keys.iter_mut().find(|key| key.name == name) {
+            Some(key) => {
+                key.columns.push(column);
+                key.referenced_columns.push(referenced_column);
+            }
+            None => self.foreign_keys.push(SchemaForeignKey {
+                name,
+                columns: vec![column],
+                referenced_schema,
+                referenced_table,
+                referenced_columns: vec![referenced_column],
+            }),
+        }
+    }
+
+    /// Add a column to an index, creating the index for its first column.
+    /// Rows must be visited in index order.
+    pub fn push_index_column(&mut self, name: String, column: String, is_unique: bool) {
+        match self.indexes.iter_mut().find(|index| index.name == name) {
+            Some(index) => index.columns.push(column),
+            None => self.indexes.push(SchemaIndex {
+                name,
+                columns: vec![column],
+                is_unique,
+            }),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct DatabaseSchema {
     pub database: String,
     pub tables: BTreeMap<String, SchemaTable>,
 }
+
+impl DatabaseSchema {
+    /// Find a table that was returned by the columns query
+    pub fn table_mut(&mut self, schema: &str, table: &str) -> Option<&mut SchemaTable> {
+        self.tables
+            .get_mut(table)
+            .filter(|found| found.schema == schema)
+    }
+}
+
+/// Limit introspection to a single schema and/or table, which keeps large
+/// warehouses responsive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct SchemaFilter {
+    pub schema: Option<String>,
+    pub table: Option<String>,
+}
+
+impl SchemaFilter {
+    pub fn is_empty(&self) -> bool {
+        self.schema.is_none() && self.table.is_none()
+    }
+}
+
+/// Treat empty strings as missing, since databases return them for unset
+/// comments.
+pub(crate) fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_key_and_index_columns() {
+        let mut table = SchemaTable::new("orders".into(), "public".into(), TableKind::Table);
+
+        table.push_foreign_key_column(
+            "fk_customer".into(),
+            "customer_id".into(),
+            "public".into(),
+            "customers".into(),
+            "id".into(),
+        );
+        table.push_foreign_key_column(
+            "fk_customer".into(),
+            "region_id".into(),
+            "public".into(),
+            "customers".into(),
+            "region_id".into(),
+        );
+        table.push_index_column("idx_created".into(), "created_at".into(), false);
+        table.push_index_column("idx_number".into(), "number".into(), true);
+        table.push_index_column("idx_created".into(), "status".into(), false);
+
+        assert_eq!(
+            table.foreign_keys,
+            vec![SchemaForeignKey {
+                name: "fk_customer".into(),
+                columns: vec!["customer_id".into(), "region_id".into()],
+                referenced_schema: "public".into(),
+                referenced_table: "customers".into(),
+                referenced_columns: vec!["id".into(), "region_id".into()],
+            }]
+        );
+        assert_eq!(
+            table.indexes,
+            vec![
+                SchemaIndex {
+                    name: "idx_created".into(),
+                    columns: vec!["created_at".into(), "status".into()],
+                    is_unique: false,
+                },
+                SchemaIndex {
+                    name: "idx_number".into(),
+                    columns: vec!["number".into()],
+                    is_unique: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_empty_strings() {
+        assert_eq!(non_empty(Some("".into())), None);
+        assert_eq!(non_empty(Some("comment".into())), Some("comment".into()));
+        assert_eq!(non_empty(None), None);
+    }
+}
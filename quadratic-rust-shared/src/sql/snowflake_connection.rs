@@ -1,4 +1,5 @@
 use arrow::array::ArrayRef;
+use arrow::datatypes::DataType;
 use arrow_array::array::Array;
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -13,7 +14,9 @@ use std::sync::Arc;
 use crate::arrow::arrow_type::ArrowType;
 use crate::error::{Result, SharedError};
 use crate::sql::error::Sql as SqlError;
-use crate::sql::schema::{DatabaseSchema, SchemaColumn, SchemaTable};
+use crate::sql::schema::{
+    non_empty, DatabaseSchema, SchemaColumn, SchemaFilter, SchemaTable, TableKind,
+};
 use crate::sql::Connection;
 use crate::utils::array::transpose;
 
@@ -26,9 +29,13 @@ pub struct SnowflakeConnection {
     pub database: String,
     pub schema: Option<String>,
     pub role: Option<String>,
+    /// Only allow statements that read data
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 impl SnowflakeConnection {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         account_identifier: String,
         username: String,
@@ -37,6 +44,7 @@ impl SnowflakeConnection {
         database: String,
         schema: Option<String>,
         role: Option<String>,
+        read_only: bool,
     ) -> SnowflakeConnection {
         SnowflakeConnection {
             account_identifier,
@@ -46,8 +54,108 @@ impl SnowflakeConnection {
             database,
             schema,
             role,
+            read_only,
         }
     }
+
+    /// Add table kinds, comments, row counts and keys to a schema.  Snowflake
+    /// doesn't have secondary indexes on standard tables, so those are left
+    /// empty.
+    async fn schema_details(
+        &self,
+        client: &mut SnowflakeApi,
+        filter: &SchemaFilter,
+        schema: &mut DatabaseSchema,
+    ) -> Result<()> {
+        let database = &self.database;
+
+        let filter_sql = filter_sql(filter, "table_schema", "table_name");
+        let sql = format!(
+            "
+            SELECT table_schema, table_name, table_type, row_count, comment
+            FROM {database}.information_schema.tables
+            WHERE table_schema != 'INFORMATION_SCHEMA'{filter_sql}"
+        );
+
+        for row in Self::query_strings(client, &sql).await? {
+            if let Some(table) = schema.table_mut(&row[0], &row[1]) {
+                if row[2] == "VIEW" {
+                    table.kind = TableKind::View;
+                }
+
+                table.row_count = row[3].parse().ok();
+                table.comment = non_empty(Some(row[4].to_owned()));
+            }
+        }
+
+        let sql = format!(
+            "
+            SELECT table_schema, table_name, column_name, comment
+            FROM {database}.information_schema.columns
+            WHERE comment IS NOT NULL{filter_sql}"
+        );
+
+        for row in Self::query_strings(client, &sql).await? {
+            if let Some(table) = schema.table_mut(&row[0], &row[1]) {
+                if let Some(column) = table.columns.iter_mut().find(|c| c.name == row[2]) {
+                    column.comment = non_empty(Some(row[3].to_owned()));
+                }
+            }
+        }
+
+        // SHOW results can only be filtered and sorted through RESULT_SCAN,
+        // which must run in the same session
+        let scope = show_scope(database, filter);
+        let result_filter_sql = filter_sql(filter, r#""schema_name""#, r#""table_name""#);
+        Self::query_strings(client, &format!("SHOW PRIMARY KEYS IN {scope}")).await?;
+        let sql = format!(
+            r#"
+            SELECT "schema_name", "table_name", "column_name"
+            FROM TABLE(RESULT_SCAN(LAST_QUERY_ID()))
+            WHERE TRUE{result_filter_sql}
+            ORDER BY "table_name", "key_sequence""#
+        );
+
+        for row in Self::query_strings(client, &sql).await? {
+            if let Some(table) = schema.table_mut(&row[0], &row[1]) {
+                table.primary_key.push(row[2].to_owned());
+            }
+        }
+
+        Self::query_strings(client, &format!("SHOW IMPORTED KEYS IN {scope}")).await?;
+        let result_filter_sql = filter_sql(filter, r#""fk_schema_name""#, r#""fk_table_name""#);
+        let sql = format!(
+            r#"
+            SELECT "fk_schema_name", "fk_table_name", "fk_name", "fk_column_name",
+                "pk_schema_name", "pk_table_name", "pk_column_name"
+            FROM TABLE(RESULT_SCAN(LAST_QUERY_ID()))
+            WHERE TRUE{result_filter_sql}
+            ORDER BY "fk_table_name", "fk_name", "key_sequence""#
+        );
+
+        for row in Self::query_strings(client, &sql).await? {
+            if let Some(table) = schema.table_mut(&row[0], &row[1]) {
+                table.push_foreign_key_column(
+                    row[2].to_owned(),
+                    row[3].to_owned(),
+                    row[4].to_owned(),
+                    row[5].to_owned(),
+                    row[6].to_owned(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn query_strings(client: &mut SnowflakeApi, sql: &str) -> Result<Vec<Vec<String>>> {
+        let result = client
+            .exec(sql)
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Schema(e.to_string())))?;
+
+        Ok(string_rows(result))
+    }
 }
 
 /// Implement the Connection trait for Snowflake
@@ -72,6 +180,14 @@ impl Connection for SnowflakeConnection {
         unimplemented!();
     }
 
+    fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn kind(&self) -> &'static str {
+        "snowflake"
+    }
+
     fn to_arrow(_row: &Self::Row, _: &ArrayRef, _index: usize) -> ArrowType {
         unimplemented!();
     }
@@ -160,8 +276,61 @@ impl Connection for SnowflakeConnection {
         )))
     }
 
-    async fn schema(&self, _client: &mut Self::Conn) -> Result<DatabaseSchema> {
+    async fn session_id(&self, _client: &mut Self::Conn) -> Result<Option<String>> {
+        #[cfg(any(test, feature = "test"))]
+        let (mut _client, _recording) = tests::get_mocked(&self, "snowflake-connection").await;
+
+        let result = _client
+            .exec("SELECT CURRENT_SESSION()")
+            .await
+            .map_err(|e| SharedError::Sql(SqlError::Query(e.to_string())))?;
+
+        let session_id = match result {
+            QueryResult::Arrow(batches) => batches.first().and_then(|batch| {
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<arrow::array::StringArray>()
+                    .filter(|col| !col.is_empty())
+                    .map(|col| col.value(0).to_owned())
+            }),
+            QueryResult::Json(json) => json
+                .value
+                .get(0)
+                .and_then(|row| row.get(0))
+                .and_then(|value| value.as_str())
+                .map(str::to_owned),
+            QueryResult::Empty => None,
+        };
+
+        Ok(session_id)
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<()> {
+        let cancel_error = |e: String| SharedError::Sql(SqlError::Cancel(e));
+
+        // parse into a number so that nothing but an id is interpolated
+        let session_id = session_id
+            .parse::<u64>()
+            .map_err(|e| cancel_error(e.to_string()))?;
+        let client = self.connect().await?;
+        let sql = format!("SELECT SYSTEM$CANCEL_ALL_QUERIES({session_id})");
+
+        client
+            .exec(&sql)
+            .await
+            .map_err(|e| cancel_error(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn schema(
+        &self,
+        _client: &mut Self::Conn,
+        filter: &SchemaFilter,
+    ) -> Result<DatabaseSchema> {
         let database = self.database.to_owned();
+        let filter_sql = filter_sql(filter, "sch.schema_name", "tbl.table_name");
         let sql = format!(
             "
             SELECT
@@ -184,7 +353,7 @@ impl Connection for SnowflakeConnection {
             JOIN
                 {database}.information_schema.databases db
                 ON sch.catalog_name = db.database_name
-            where sch.schema_name != 'INFORMATION_SCHEMA'
+            where sch.schema_name != 'INFORMATION_SCHEMA'{filter_sql}
             ORDER BY
                 db.database_name,
                 sch.schema_name,
@@ -204,35 +373,7 @@ impl Connection for SnowflakeConnection {
         #[cfg(all(any(test, feature = "test"), feature = "record-request-mock"))]
         record_stop(scenario, _recording).await;
 
-        let mut data = vec![vec![]; 6];
-
-        match row_stream {
-            QueryResult::Arrow(a) => {
-                for batch in a {
-                    let num_cols = batch.num_columns();
-
-                    for col_index in 0..num_cols {
-                        let col = batch.column(col_index);
-
-                        // convert columns into a vec of strings
-                        let col_values = col
-                            .as_any()
-                            .downcast_ref::<arrow::array::StringArray>()
-                            .unwrap()
-                            .iter()
-                            .map(|s| s.unwrap_or_default().to_owned())
-                            .collect::<Vec<String>>();
-
-                        // data in coming in as batches, so we need to combine them
-                        data[col_index].extend(col_values);
-                    }
-                }
-            }
-            QueryResult::Json(j) => unimplemented!("{j}"),
-            QueryResult::Empty => { /* noop */ }
-        }
-
-        let rows = transpose(data);
+        let rows = string_rows(row_stream);
         let mut schema = DatabaseSchema {
             database: self.database.to_owned(),
             tables: BTreeMap::new(),
@@ -248,23 +389,111 @@ impl Connection for SnowflakeConnection {
             schema
                 .tables
                 .entry(table_name.to_owned())
-                .or_insert_with(|| SchemaTable {
-                    name: table_name,
-                    schema: safe_get(row.get(1), "Schema"),
-                    columns: vec![],
+                .or_insert_with(|| {
+                    SchemaTable::new(table_name, safe_get(row.get(1), "Schema"), TableKind::Table)
                 })
                 .columns
                 .push(SchemaColumn {
                     name: safe_get(row.get(3), "Column"),
                     r#type: safe_get(row.get(4), "Type"),
                     is_nullable: row.get(5).map_or("NO", |v| v).to_uppercase() == "YES",
+                    comment: None,
                 });
         }
 
+        // there are no recorded requests for these statements, so skip them when mocked
+        if !cfg!(any(test, feature = "test")) {
+            self.schema_details(_client, filter, &mut schema).await?;
+        }
+
         Ok(schema)
     }
 }
 
+/// Convert a query result into rows of strings, casting non-string columns.
+/// Nulls become empty strings.
+fn string_rows(result: QueryResult) -> Vec<Vec<String>> {
+    let mut data: Vec<Vec<String>> = vec![];
+
+    match result {
+        QueryResult::Arrow(a) => {
+            for batch in a {
+                let num_cols = batch.num_columns();
+                data.resize(num_cols, vec![]);
+
+                for col_index in 0..num_cols {
+                    let col = batch.column(col_index);
+                    let col = arrow::compute::cast(col, &DataType::Utf8).unwrap_or_else(|_| {
+                        arrow::array::new_null_array(&DataType::Utf8, col.len())
+                    });
+
+                    // convert columns into a vec of strings
+                    let col_values = col
+                        .as_any()
+                        .downcast_ref::<arrow::array::StringArray>()
+                        .map(|col| {
+                            col.iter()
+                                .map(|s| s.unwrap_or_default().to_owned())
+                                .collect::<Vec<String>>()
+                        })
+                        .unwrap_or_default();
+
+                    // data in coming in as batches, so we need to combine them
+                    data[col_index].extend(col_values);
+                }
+            }
+        }
+        QueryResult::Json(j) => unimplemented!("{j}"),
+        QueryResult::Empty => { /* noop */ }
+    }
+
+    transpose(data)
+}
+
+/// Escape user input that is interpolated into a string literal
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "''")
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// The object a SHOW command lists keys in: the filtered table or schema, or
+/// the whole database. SHOW can't scope to a table without its schema, so a
+/// table-only filter is applied to the results instead.
+fn show_scope(database: &str, filter: &SchemaFilter) -> String {
+    match (&filter.schema, &filter.table) {
+        (Some(schema), Some(table)) => format!(
+            "TABLE {database}.{}.{}",
+            quote_identifier(schema),
+            quote_identifier(table)
+        ),
+        (Some(schema), None) => format!("SCHEMA {database}.{}", quote_identifier(schema)),
+        (None, _) => format!("DATABASE {database}"),
+    }
+}
+
+fn filter_sql(filter: &SchemaFilter, schema_column: &str, table_column: &str) -> String {
+    let mut sql = String::new();
+
+    if let Some(schema) = &filter.schema {
+        sql.push_str(&format!(
+            "\n            AND {schema_column} = '{}'",
+            escape(schema)
+        ));
+    }
+
+    if let Some(table) = &filter.table {
+        sql.push_str(&format!(
+            "\n            AND {table_column} = '{}'",
+            escape(table)
+        ));
+    }
+
+    sql
+}
+
 #[cfg(any(test, feature = "test"))]
 pub mod tests {
 
@@ -287,6 +516,7 @@ pub mod tests {
             "ALL_NATIVE_DATA_TYPES".into(),
             None,
             None,
+            false,
         )
     }
 
@@ -310,9 +540,49 @@ pub mod tests {
         #[cfg(all(any(test, feature = "test"), feature = "record-request-mock"))]
         return (Some(record_start(&server)), server);
 
+        mock_session_id(&server);
+
         (None, server)
     }
 
+    pub const MOCK_SESSION_ID: &str = "221925255290930";
+
+    /// `SELECT CURRENT_SESSION()` isn't in the recorded scenarios, so answer it
+    /// with a fixed session id.
+    pub fn mock_session_id(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path("/queries/v1/query-request")
+                .body_contains("CURRENT_SESSION()");
+            then.status(200).json_body(serde_json::json!({
+                "data": {
+                    "parameters": [],
+                    "rowtype": [{
+                        "name": "CURRENT_SESSION()",
+                        "database": "",
+                        "schema": "",
+                        "table": "",
+                        "byteLength": 16777216,
+                        "length": 16777216,
+                        "type": "text",
+                        "scale": null,
+                        "precision": null,
+                        "nullable": true,
+                        "collation": null
+                    }],
+                    "rowset": [[MOCK_SESSION_ID]],
+                    "total": 1,
+                    "returned": 1,
+                    "queryId": "01b7301e-0000-0000-0000-000000000000",
+                    "queryResultFormat": "json"
+                },
+                "code": null,
+                "message": null,
+                "success": true
+            }));
+        });
+    }
+
     pub async fn get_mocked_client(
         connection: &SnowflakeConnection,
         server: &MockServer,
@@ -431,103 +701,152 @@ pub mod tests {
         assert_eq!(num_records, 0);
     }
 
+    #[tokio::test]
+    async fn test_snowflake_session_id() {
+        let connection = new_snowflake_connection();
+        let mut client = connection.connect().await.unwrap();
+        let session_id = connection.session_id(&mut client).await.unwrap();
+
+        assert_eq!(session_id.as_deref(), Some(MOCK_SESSION_ID));
+    }
+
+    #[test]
+    fn test_snowflake_show_scope() {
+        let filter = |schema: Option<&str>, table: Option<&str>| SchemaFilter {
+            schema: schema.map(Into::into),
+            table: table.map(Into::into),
+        };
+
+        assert_eq!(show_scope("DB", &filter(None, None)), "DATABASE DB");
+        assert_eq!(show_scope("DB", &filter(None, Some("T"))), "DATABASE DB");
+        assert_eq!(
+            show_scope("DB", &filter(Some("PUBLIC"), None)),
+            r#"SCHEMA DB."PUBLIC""#
+        );
+        assert_eq!(
+            show_scope("DB", &filter(Some("PUBLIC"), Some(r#"MY "T""#))),
+            r#"TABLE DB."PUBLIC"."MY ""T""""#
+        );
+    }
+
     // to record: cargo test test_snowflake_schema --features record-request-mock
     #[tokio::test]
     async fn test_snowflake_schema() {
         let connection = new_snowflake_connection();
         let mut client = connection.connect().await.unwrap();
-        let schema = connection.schema(&mut client).await.unwrap();
+        let schema = connection
+            .schema(&mut client, &SchemaFilter::default())
+            .await
+            .unwrap();
 
         let expected = vec![
             SchemaColumn {
                 name: "INTEGER_COL".into(),
                 r#type: "NUMBER".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "FLOAT_COL".into(),
                 r#type: "FLOAT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "NUMBER_COL".into(),
                 r#type: "NUMBER".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "DECIMAL_COL".into(),
                 r#type: "NUMBER".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "BOOLEAN_COL".into(),
                 r#type: "BOOLEAN".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "VARCHAR_COL".into(),
                 r#type: "TEXT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "CHAR_COL".into(),
                 r#type: "TEXT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "STRING_COL".into(),
                 r#type: "TEXT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "BINARY_COL".into(),
                 r#type: "BINARY".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "DATE_COL".into(),
                 r#type: "DATE".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "TIME_COL".into(),
                 r#type: "TIME".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "TIMESTAMP_NTZ_COL".into(),
                 r#type: "TIMESTAMP_NTZ".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "TIMESTAMP_LTZ_COL".into(),
                 r#type: "TIMESTAMP_LTZ".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "TIMESTAMP_TZ_COL".into(),
                 r#type: "TIMESTAMP_TZ".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "VARIANT_COL".into(),
                 r#type: "VARIANT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "OBJECT_COL".into(),
                 r#type: "OBJECT".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "ARRAY_COL".into(),
                 r#type: "ARRAY".into(),
                 is_nullable: true,
+                comment: None,
             },
             SchemaColumn {
                 name: "GEOGRAPHY_COL".into(),
                 r#type: "GEOGRAPHY".into(),
                 is_nullable: true,
+                comment: None,
             },
         ];
 
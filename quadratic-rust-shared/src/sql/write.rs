@@ -0,0 +1,611 @@
+//! Write-back
+//!
+//! Insert or upsert rows from a Parquet file or Arrow IPC stream into a
+//! database table.  Values are converted to `ArrowType`s and bound as
+//! parameters, so only quoted identifiers are interpolated into the SQL.
+
+use arrow::array::RecordBatch;
+use arrow::ipc::reader::StreamReader;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::arrow::arrow_type::ArrowType;
+use crate::error::{Result, SharedError};
+use crate::sql::error::Sql as SqlError;
+use crate::sql::schema::SchemaTable;
+
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    #[default]
+    Insert,
+    /// Update rows that match on `key_columns`, and insert the rest
+    Upsert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WriteOptions {
+    pub schema: Option<String>,
+    pub table: String,
+    pub mode: WriteMode,
+    pub key_columns: Vec<String>,
+    /// The maximum number of rows written per statement
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    MySql,
+    MsSql,
+}
+
+impl Dialect {
+    pub fn quote(&self, identifier: &str) -> String {
+        match self {
+            Dialect::Postgres => format!("\"{}\"", identifier.replace('"', "\"\"")),
+            Dialect::MySql => format!("`{}`", identifier.replace('`', "``")),
+            Dialect::MsSql => format!("[{}]", identifier.replace(']', "]]")),
+        }
+    }
+
+    /// The placeholder for the 1-based parameter `index`
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${index}"),
+            Dialect::MySql => "?".into(),
+            Dialect::MsSql => format!("@P{index}"),
+        }
+    }
+
+    /// The maximum number of parameters in a single statement
+    fn max_parameters(&self) -> usize {
+        match self {
+            Dialect::Postgres | Dialect::MySql => 65_535,
+            Dialect::MsSql => 2_099,
+        }
+    }
+
+    fn table(&self, options: &WriteOptions) -> String {
+        match &options.schema {
+            Some(schema) => format!("{}.{}", self.quote(schema), self.quote(&options.table)),
+            None => self.quote(&options.table),
+        }
+    }
+}
+
+/// The broad kind of a database column type, used to catch values that can't
+/// be written to a column before anything is written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Integer,
+    Number,
+    Boolean,
+    Text,
+    Date,
+    Time,
+    Timestamp,
+    Json,
+    Uuid,
+    /// Types we don't check, the database rejects any mismatches
+    Other,
+}
+
+impl ColumnKind {
+    /// Classify a column type as reported by the schema queries, e.g. `int4`,
+    /// `varchar(255)` or `NUMBER`
+    fn from_type(column_type: &str) -> Self {
+        let column_type = column_type.to_lowercase();
+        let name = column_type.split('(').next().unwrap_or_default().trim();
+
+        match name {
+            "smallint" | "int" | "integer" | "bigint" | "tinyint" | "mediumint" | "int2"
+            | "int4" | "int8" | "smallserial" | "serial" | "bigserial" => ColumnKind::Integer,
+            "real" | "float" | "float4" | "float8" | "double" | "double precision" | "numeric"
+            | "decimal" | "number" | "money" | "smallmoney" => ColumnKind::Number,
+            "bool" | "boolean" | "bit" => ColumnKind::Boolean,
+            "text" | "varchar" | "char" | "bpchar" | "name" | "citext" | "nchar" | "nvarchar"
+            | "ntext" | "tinytext" | "mediumtext" | "longtext" | "character varying"
+            | "character" | "string" | "enum" => ColumnKind::Text,
+            "date" => ColumnKind::Date,
+            "time" | "timetz" => ColumnKind::Time,
+            "timestamp" | "timestamptz" | "datetime" | "datetime2" | "smalldatetime"
+            | "datetimeoffset" | "timestamp_ntz" | "timestamp_ltz" | "timestamp_tz" => {
+                ColumnKind::Timestamp
+            }
+            "json" | "jsonb" => ColumnKind::Json,
+            "uuid" | "uniqueidentifier" => ColumnKind::Uuid,
+            _ => ColumnKind::Other,
+        }
+    }
+
+    /// Whether `value` can be written to a column of this kind.  Numbers
+    /// must be whole for integer columns, since spreadsheets store all
+    /// numbers as floats or decimals.
+    fn accepts(&self, value: &ArrowType) -> bool {
+        use ArrowType::*;
+
+        match (self, value) {
+            (_, Void) | (ColumnKind::Other, _) | (ColumnKind::Text, _) => true,
+            (
+                ColumnKind::Integer | ColumnKind::Number,
+                Int8(_) | Int16(_) | Int32(_) | Int64(_) | UInt8(_) | UInt16(_) | UInt32(_)
+                | UInt64(_),
+            ) => true,
+            (ColumnKind::Integer, Float32(n)) => n.fract() == 0.0,
+            (ColumnKind::Integer, Float64(n)) => n.fract() == 0.0,
+            (ColumnKind::Integer, BigDecimal(n)) => n.is_integer(),
+            (ColumnKind::Integer | ColumnKind::Boolean, Boolean(_)) => true,
+            (ColumnKind::Number, Float32(_) | Float64(_) | BigDecimal(_)) => true,
+            (ColumnKind::Boolean, Int8(_) | Int16(_) | Int32(_) | Int64(_) | UInt8(_)) => true,
+            (ColumnKind::Date | ColumnKind::Timestamp, Date32(_) | Date64(_)) => true,
+            (ColumnKind::Timestamp, Timestamp(_) | TimestampTz(_)) => true,
+            (ColumnKind::Time, Time32(_) | Time64(_) | TimeTz(_)) => true,
+            (ColumnKind::Json, Json(_) | Jsonb(_) | Utf8(_)) => true,
+            (ColumnKind::Uuid, Uuid(_) | Utf8(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Rows decoded from a write-back payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<ArrowType>>,
+}
+
+impl WriteData {
+    /// Decode a Parquet file or an Arrow IPC stream
+    pub fn from_bytes(bytes: Bytes) -> Result<Self> {
+        let write_error = |e: String| SharedError::Sql(SqlError::Write(e));
+
+        let batches = if bytes.starts_with(PARQUET_MAGIC) {
+            ParquetRecordBatchReaderBuilder::try_new(bytes)?
+                .build()?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| write_error(e.to_string()))?
+        } else {
+            StreamReader::try_new(bytes.as_ref(), None)
+                .map_err(|e| write_error(e.to_string()))?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| write_error(e.to_string()))?
+        };
+
+        Ok(Self::from_record_batches(&batches))
+    }
+
+    pub fn from_record_batches(batches: &[RecordBatch]) -> Self {
+        let columns = batches
+            .first()
+            .map(|batch| {
+                batch
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut rows = vec![];
+
+        for batch in batches {
+            for row_index in 0..batch.num_rows() {
+                rows.push(
+                    batch
+                        .columns()
+                        .iter()
+                        .map(|column| ArrowType::from_array(column, row_index))
+                        .collect(),
+                );
+            }
+        }
+
+        WriteData { columns, rows }
+    }
+
+    /// Check the data against the target table before writing anything
+    pub fn validate(&self, table: &SchemaTable, options: &WriteOptions) -> Result<()> {
+        let write_error = |e: String| Err(SharedError::Sql(SqlError::Write(e)));
+
+        if self.columns.is_empty() {
+            return write_error("No columns to write".into());
+        }
+
+        if let Some(column) = self
+            .columns
+            .iter()
+            .find(|column| !table.columns.iter().any(|c| &c.name == *column))
+        {
+            return write_error(format!("Column {column} is not in table {}", table.name));
+        }
+
+        if options.mode == WriteMode::Upsert {
+            if options.key_columns.is_empty() {
+                return write_error("Upserts require key columns".into());
+            }
+
+            if let Some(key) = options
+                .key_columns
+                .iter()
+                .find(|key| !self.columns.contains(key))
+            {
+                return write_error(format!("Key column {key} is not in the data"));
+            }
+        }
+
+        let columns = self
+            .columns
+            .iter()
+            .filter_map(|name| table.columns.iter().find(|c| &c.name == name))
+            .collect::<Vec<_>>();
+
+        for (row_index, row) in self.rows.iter().enumerate() {
+            for (value, column) in row.iter().zip(columns.iter()) {
+                if *value == ArrowType::Unsupported {
+                    return write_error(format!("Column {} has an unsupported type", column.name));
+                }
+
+                if *value == ArrowType::Void && !column.is_nullable {
+                    return write_error(format!(
+                        "Column {} can't be empty (row {})",
+                        column.name,
+                        row_index + 1
+                    ));
+                }
+
+                if !ColumnKind::from_type(&column.r#type).accepts(value) {
+                    return write_error(format!(
+                        "Column {} has type {}, which can't hold {value:?} (row {})",
+                        column.name,
+                        column.r#type,
+                        row_index + 1
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split the rows into batches that fit within both the requested batch
+    /// size and the dialect's parameter limit
+    pub fn batches(
+        &self,
+        dialect: Dialect,
+        batch_size: usize,
+    ) -> std::slice::Chunks<'_, Vec<ArrowType>> {
+        let max_rows = dialect.max_parameters() / self.columns.len().max(1);
+
+        self.rows.chunks(batch_size.min(max_rows).max(1))
+    }
+}
+
+/// Build the insert or upsert statement for a batch of rows.  Nulls are
+/// written inline, all other values are returned in placeholder order.
+pub fn write_sql<'a>(
+    dialect: Dialect,
+    options: &WriteOptions,
+    columns: &[String],
+    rows: &'a [Vec<ArrowType>],
+) -> (String, Vec<&'a ArrowType>) {
+    let mut values = vec![];
+    let table = dialect.table(options);
+    let quoted = columns
+        .iter()
+        .map(|column| dialect.quote(column))
+        .collect::<Vec<_>>();
+    let column_list = quoted.join(", ");
+    let rows_sql = rows
+        .iter()
+        .map(|row| {
+            let row_sql = row
+                .iter()
+                .map(|value| match value {
+                    ArrowType::Void => "NULL".to_string(),
+                    _ => {
+                        values.push(value);
+                        dialect.placeholder(values.len())
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!("({row_sql})")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let keys = options
+        .key_columns
+        .iter()
+        .map(|key| dialect.quote(key))
+        .collect::<Vec<_>>();
+    let updates = quoted
+        .iter()
+        .filter(|column| !keys.contains(column))
+        .collect::<Vec<_>>();
+
+    let sql = match (options.mode, dialect) {
+        (WriteMode::Insert, _) => {
+            format!("INSERT INTO {table} ({column_list}) VALUES {rows_sql}")
+        }
+        (WriteMode::Upsert, Dialect::Postgres) => {
+            let action = match updates.is_empty() {
+                true => "NOTHING".to_string(),
+                false => {
+                    let set = updates
+                        .iter()
+                        .map(|column| format!("{column} = EXCLUDED.{column}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("UPDATE SET {set}")
+                }
+            };
+
+            format!(
+                "INSERT INTO {table} ({column_list}) VALUES {rows_sql} ON CONFLICT ({}) DO {action}",
+                keys.join(", ")
+            )
+        }
+        (WriteMode::Upsert, Dialect::MySql) => {
+            // MySQL matches on any unique key, updating a key to itself is a
+            // no-op when there is nothing else to update
+            let set = match updates.is_empty() {
+                true => format!("{} = {}", keys[0], keys[0]),
+                false => updates
+                    .iter()
+                    .map(|column| format!("{column} = VALUES({column})"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+
+            format!(
+                "INSERT INTO {table} ({column_list}) VALUES {rows_sql} ON DUPLICATE KEY UPDATE {set}"
+            )
+        }
+        (WriteMode::Upsert, Dialect::MsSql) => {
+            let on = keys
+                .iter()
+                .map(|key| format!("target.{key} = source.{key}"))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            let matched = match updates.is_empty() {
+                true => "".to_string(),
+                false => {
+                    let set = updates
+                        .iter()
+                        .map(|column| format!("target.{column} = source.{column}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!(" WHEN MATCHED THEN UPDATE SET {set}")
+                }
+            };
+            let source_columns = quoted
+                .iter()
+                .map(|column| format!("source.{column}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!(
+                "MERGE INTO {table} AS target USING (VALUES {rows_sql}) AS source ({column_list}) ON {on}{matched} WHEN NOT MATCHED THEN INSERT ({column_list}) VALUES ({source_columns});"
+            )
+        }
+    };
+
+    (sql, values)
+}
+
+/// Report a failed write.  All batches are written in one transaction, so
+/// nothing was committed.
+pub(crate) fn write_error(error: impl ToString) -> SharedError {
+    SharedError::Sql(SqlError::Write(format!(
+        "{} (no rows were written)",
+        error.to_string()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+
+    use super::*;
+    use crate::sql::schema::{SchemaColumn, TableKind};
+
+    fn record_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec![Some("a"), None])),
+        ];
+
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    fn options(mode: WriteMode) -> WriteOptions {
+        WriteOptions {
+            schema: Some("public".into()),
+            table: "users".into(),
+            mode,
+            key_columns: vec!["id".into()],
+            batch_size: 1000,
+        }
+    }
+
+    fn table() -> SchemaTable {
+        let mut table = SchemaTable::new("users".into(), "public".into(), TableKind::Table);
+
+        for name in ["id", "name"] {
+            table.columns.push(SchemaColumn {
+                name: name.into(),
+                r#type: "text".into(),
+                is_nullable: true,
+                comment: None,
+            });
+        }
+
+        table
+    }
+
+    #[test]
+    fn decodes_parquet() {
+        let batch = record_batch();
+        let mut writer = ArrowWriter::try_new(vec![], batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        let bytes = Bytes::from(writer.into_inner().unwrap());
+
+        let data = WriteData::from_bytes(bytes).unwrap();
+
+        assert_eq!(data.columns, vec!["id".to_string(), "name".to_string()]);
+        assert_eq!(
+            data.rows,
+            vec![
+                vec![ArrowType::Int32(1), ArrowType::Utf8("a".into())],
+                vec![ArrowType::Int32(2), ArrowType::Void],
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_arrow_ipc() {
+        let batch = record_batch();
+        let mut writer =
+            arrow::ipc::writer::StreamWriter::try_new(vec![], &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        let bytes = Bytes::from(writer.into_inner().unwrap());
+
+        let data = WriteData::from_bytes(bytes).unwrap();
+
+        assert_eq!(data, WriteData::from_record_batches(&[batch]));
+    }
+
+    #[test]
+    fn validates_against_the_table() {
+        let data = WriteData::from_record_batches(&[record_batch()]);
+        let mut upsert = options(WriteMode::Upsert);
+
+        assert!(data.validate(&table(), &upsert).is_ok());
+
+        upsert.key_columns = vec![];
+        assert!(data.validate(&table(), &upsert).is_err());
+
+        let mut missing_column = table();
+        missing_column.columns.pop();
+        assert!(data
+            .validate(&missing_column, &options(WriteMode::Insert))
+            .is_err());
+    }
+
+    #[test]
+    fn validates_column_types() {
+        let mut typed = table();
+        typed.columns[0].r#type = "int4".into();
+        typed.columns[0].is_nullable = false;
+        let insert = options(WriteMode::Insert);
+        let data = |id: ArrowType| WriteData {
+            columns: vec!["id".into(), "name".into()],
+            rows: vec![vec![id, ArrowType::Utf8("a".into())]],
+        };
+
+        assert!(data(ArrowType::Int32(1)).validate(&typed, &insert).is_ok());
+        assert!(data(ArrowType::Float64(1.0))
+            .validate(&typed, &insert)
+            .is_ok());
+        assert!(data(ArrowType::Float64(1.5))
+            .validate(&typed, &insert)
+            .is_err());
+        assert!(data(ArrowType::Utf8("1".into()))
+            .validate(&typed, &insert)
+            .is_err());
+        assert!(data(ArrowType::Void).validate(&typed, &insert).is_err());
+
+        // text and unknown types accept anything
+        typed.columns[0].r#type = "varchar(255)".into();
+        assert!(data(ArrowType::Float64(1.5))
+            .validate(&typed, &insert)
+            .is_ok());
+        typed.columns[0].r#type = "geometry".into();
+        assert!(data(ArrowType::Utf8("a".into()))
+            .validate(&typed, &insert)
+            .is_ok());
+    }
+
+    #[test]
+    fn batches_within_the_parameter_limit() {
+        let data = WriteData {
+            columns: (0..1000).map(|i| i.to_string()).collect(),
+            rows: vec![vec![ArrowType::Int32(1); 1000]; 10],
+        };
+
+        assert_eq!(data.batches(Dialect::Postgres, 4).count(), 3);
+        assert_eq!(data.batches(Dialect::MsSql, 1000).count(), 5);
+    }
+
+    #[test]
+    fn builds_inserts() {
+        let data = WriteData::from_record_batches(&[record_batch()]);
+        let (sql, values) = write_sql(
+            Dialect::Postgres,
+            &options(WriteMode::Insert),
+            &data.columns,
+            &data.rows,
+        );
+
+        assert_eq!(
+            sql,
+            r#"INSERT INTO "public"."users" ("id", "name") VALUES ($1, $2), ($3, NULL)"#
+        );
+        assert_eq!(
+            values,
+            vec![
+                &ArrowType::Int32(1),
+                &ArrowType::Utf8("a".into()),
+                &ArrowType::Int32(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_upserts() {
+        let data = WriteData::from_record_batches(&[record_batch()]);
+        let upsert = |dialect| {
+            write_sql(
+                dialect,
+                &options(WriteMode::Upsert),
+                &data.columns,
+                &data.rows[..1],
+            )
+            .0
+        };
+
+        assert_eq!(
+            upsert(Dialect::Postgres),
+            r#"INSERT INTO "public"."users" ("id", "name") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "name" = EXCLUDED."name""#
+        );
+        assert_eq!(
+            upsert(Dialect::MySql),
+            "INSERT INTO `public`.`users` (`id`, `name`) VALUES (?, ?) ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+        assert_eq!(
+            upsert(Dialect::MsSql),
+            "MERGE INTO [public].[users] AS target USING (VALUES (@P1, @P2)) AS source ([id], [name]) ON target.[id] = source.[id] WHEN MATCHED THEN UPDATE SET target.[name] = source.[name] WHEN NOT MATCHED THEN INSERT ([id], [name]) VALUES (source.[id], source.[name]);"
+        );
+    }
+
+    #[test]
+    fn quotes_identifiers() {
+        assert_eq!(Dialect::Postgres.quote(r#"a"b"#), r#""a""b""#);
+        assert_eq!(Dialect::MySql.quote("a`b"), "`a``b`");
+        assert_eq!(Dialect::MsSql.quote("a]b"), "[a]]b]");
+    }
+}
@@ -0,0 +1,416 @@
+//! Azure Blob Storage
+//!
+//! Talks to the Blob service REST API directly, authenticating each request
+//! with a Shared Key signature.  Presigned urls are read-only service SAS
+//! urls.  Works against Azurite for local development and tests.
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{header::HeaderMap, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use sha2::Sha256;
+use std::time::Duration;
+
+use super::Storage;
+use crate::error::Result;
+
+const API_VERSION: &str = "2021-08-06";
+const SAS_VERSION: &str = "2020-12-06";
+
+#[derive(Debug, Clone)]
+pub struct AzureBlobConfig {
+    pub account: String,
+    pub access_key: String,
+    pub container: String,
+    /// Defaults to `https://{account}.blob.core.windows.net`, set to
+    /// `http://127.0.0.1:10000/devstoreaccount1` for Azurite
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AzureBlob {
+    pub config: AzureBlobConfig,
+    client: Client,
+}
+
+#[async_trait]
+impl Storage for AzureBlob {
+    type Config = AzureBlobConfig;
+
+    /// Read the blob from the container and return the bytes.
+    async fn read(&self, key: &str) -> Result<Bytes> {
+        let response = self
+            .send(Method::GET, self.blob_url(key)?, HeaderMap::new(), None)
+            .await
+            .map_err(|e| Self::read_error(key, e))?;
+
+        response.bytes().await.map_err(|e| Self::read_error(key, e))
+    }
+
+    /// Write the bytes to a block blob in the container.
+    async fn write<'a>(&self, key: &'a str, data: &'a Bytes) -> Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ms-blob-type", "BlockBlob".parse()?);
+
+        self.send(
+            Method::PUT,
+            self.blob_url(key)?,
+            headers,
+            Some(data.clone()),
+        )
+        .await
+        .map_err(|e| Self::write_error(key, e))?;
+
+        Ok(())
+    }
+
+    /// Delete the blob from the container.
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.send(Method::DELETE, self.blob_url(key)?, HeaderMap::new(), None)
+            .await
+            .map_err(|e| Self::delete_error(key, e))?;
+
+        Ok(())
+    }
+
+    /// Check if the blob exists in the container.
+    async fn exists(&self, key: &str) -> Result<bool> {
+        let url = self.blob_url(key)?;
+        let response = self
+            .request(Method::HEAD, url, HeaderMap::new(), None)?
+            .send()
+            .await
+            .map_err(|e| Self::read_error(key, e))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(Self::read_error(key, status)),
+        }
+    }
+
+    /// List the blobs in the container that start with the prefix.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        let mut marker = String::new();
+
+        loop {
+            let mut url = self.container_url()?;
+            url.query_pairs_mut()
+                .append_pair("restype", "container")
+                .append_pair("comp", "list")
+                .append_pair("prefix", prefix);
+
+            if !marker.is_empty() {
+                url.query_pairs_mut().append_pair("marker", &marker);
+            }
+
+            let body = self
+                .send(Method::GET, url, HeaderMap::new(), None)
+                .await
+                .map_err(|e| Self::list_error(prefix, e))?
+                .text()
+                .await
+                .map_err(|e| Self::list_error(prefix, e))?;
+
+            keys.extend(xml_values(&body, "Name"));
+            marker = xml_values(&body, "NextMarker").pop().unwrap_or_default();
+
+            if marker.is_empty() {
+                break;
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Generate a read-only service SAS url for the blob.
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let AzureBlobConfig {
+            account, container, ..
+        } = &self.config;
+        let expires_in =
+            chrono::Duration::from_std(expires_in).map_err(|e| Self::presigned_error(key, e))?;
+        let expiry = (Utc::now() + expires_in)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let resource = format!("/blob/{account}/{container}/{key}");
+
+        // permissions, start, expiry, resource, identifier, ip, protocol,
+        // version, resource type, snapshot time, encryption scope and the
+        // five response header overrides
+        let string_to_sign = [
+            "r",
+            "",
+            &expiry,
+            &resource,
+            "",
+            "",
+            "",
+            SAS_VERSION,
+            "b",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+        ]
+        .join("\n");
+        let signature = self
+            .sign(&string_to_sign)
+            .map_err(|e| Self::presigned_error(key, e))?;
+
+        let mut url = self.blob_url(key)?;
+        url.query_pairs_mut()
+            .append_pair("sv", SAS_VERSION)
+            .append_pair("sr", "b")
+            .append_pair("sp", "r")
+            .append_pair("se", &expiry)
+            .append_pair("sig", &signature);
+
+        Ok(url.to_string())
+    }
+
+    /// Return the name of the container.
+    fn path(&self) -> &str {
+        &self.config.container
+    }
+
+    /// Return the configuration
+    fn config(&self) -> Self::Config {
+        self.config.clone()
+    }
+}
+
+impl AzureBlob {
+    pub fn new(config: AzureBlobConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Create the container if it doesn't already exist.
+    pub async fn create_container(&self) -> Result<()> {
+        let mut url = self.container_url()?;
+        url.query_pairs_mut().append_pair("restype", "container");
+
+        let response = self
+            .request(Method::PUT, url, HeaderMap::new(), None)?
+            .send()
+            .await
+            .map_err(|e| Self::write_error(&self.config.container, e))?;
+
+        match response.status() {
+            StatusCode::CONFLICT => Ok(()),
+            status if status.is_success() => Ok(()),
+            status => Err(Self::write_error(&self.config.container, status)),
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        self.config
+            .endpoint
+            .to_owned()
+            .unwrap_or_else(|| format!("https://{}.blob.core.windows.net", self.config.account))
+    }
+
+    fn container_url(&self) -> Result<Url> {
+        let container = &self.config.container;
+        let mut url = Url::parse(&self.endpoint()).map_err(|e| Self::read_error(container, e))?;
+        url.path_segments_mut()
+            .map_err(|_| Self::read_error(container, "invalid endpoint"))?
+            .pop_if_empty()
+            .push(container);
+
+        Ok(url)
+    }
+
+    fn blob_url(&self, key: &str) -> Result<Url> {
+        let mut url = self.container_url()?;
+        url.path_segments_mut()
+            .map_err(|_| Self::read_error(key, "invalid endpoint"))?
+            .push(key);
+
+        Ok(url)
+    }
+
+    /// Sign the string with the account's access key (HMAC-SHA256).
+    fn sign(&self, string_to_sign: &str) -> std::result::Result<String, String> {
+        let key = STANDARD
+            .decode(&self.config.access_key)
+            .map_err(|e| e.to_string())?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key).map_err(|e| e.to_string())?;
+        mac.update(string_to_sign.as_bytes());
+
+        Ok(STANDARD.encode(mac.finalize().into_bytes()))
+    }
+
+    /// Build a request that is signed with the Shared Key scheme.
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+        mut headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<RequestBuilder> {
+        let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        headers.insert("x-ms-date", date.parse()?);
+        headers.insert("x-ms-version", API_VERSION.parse()?);
+
+        let content_length = body
+            .as_ref()
+            .map(|body| body.len())
+            .filter(|len| *len > 0)
+            .map(|len| len.to_string())
+            .unwrap_or_default();
+
+        let mut canonicalized_headers = headers
+            .iter()
+            .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+            .map(|(name, value)| format!("{}:{}\n", name, value.to_str().unwrap_or_default()))
+            .collect::<Vec<_>>();
+        canonicalized_headers.sort();
+
+        let mut query = url
+            .query_pairs()
+            .map(|(name, value)| format!("\n{}:{}", name.to_lowercase(), value))
+            .collect::<Vec<_>>();
+        query.sort();
+
+        let canonicalized_resource =
+            format!("/{}{}{}", self.config.account, url.path(), query.join(""));
+
+        // verb, content encoding, language, length, md5, type, date,
+        // if-modified-since, if-match, if-none-match, if-unmodified-since
+        // and range, followed by the canonicalized headers and resource
+        let string_to_sign = format!(
+            "{}\n\n\n{}\n\n\n\n\n\n\n\n\n{}{}",
+            method,
+            content_length,
+            canonicalized_headers.join(""),
+            canonicalized_resource
+        );
+        let signature = self
+            .sign(&string_to_sign)
+            .map_err(|e| Self::read_error(url.path(), e))?;
+        let authorization = format!("SharedKey {}:{}", self.config.account, signature);
+        headers.insert("authorization", authorization.parse()?);
+
+        let request = self.client.request(method, url).headers(headers);
+
+        Ok(match body {
+            Some(body) => request.body(body),
+            None => request,
+        })
+    }
+
+    /// Send a signed request, returning an error for unsuccessful responses.
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> std::result::Result<Response, String> {
+        self.request(method, url, headers, body)
+            .map_err(|e| e.to_string())?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Extract the text of each element with the tag from an xml document.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+
+    xml.split(&open)
+        .skip(1)
+        .filter_map(|part| part.split(&close).next())
+        .map(|value| {
+            value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    // well-known Azurite development account
+    fn storage() -> AzureBlob {
+        AzureBlob::new(AzureBlobConfig {
+            account: "devstoreaccount1".into(),
+            access_key: "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==".into(),
+            container: "quadratic".into(),
+            endpoint: Some("http://127.0.0.1:10000/devstoreaccount1".into()),
+        })
+    }
+
+    #[test]
+    fn parses_xml_values() {
+        let xml = "<Blobs><Blob><Name>a&amp;b</Name></Blob><Blob><Name>c</Name></Blob></Blobs><NextMarker />";
+
+        assert_eq!(xml_values(xml, "Name"), vec!["a&b", "c"]);
+        assert!(xml_values(xml, "NextMarker").is_empty());
+    }
+
+    #[tokio::test]
+    async fn azure_blob_write_read_and_delete() {
+        let storage = storage();
+        storage.create_container().await.unwrap();
+
+        let file_name = Uuid::new_v4().to_string();
+        let key = &format!("{file_name}-0.grid");
+        let data = &Bytes::from("Hello, world!");
+
+        assert!(!storage.exists(key).await.unwrap());
+        storage.write(key, data).await.unwrap();
+        assert!(storage.exists(key).await.unwrap());
+        assert_eq!(&storage.read(key).await.unwrap(), data);
+        assert_eq!(storage.list(&file_name).await.unwrap(), vec![key.clone()]);
+
+        storage.delete(key).await.unwrap();
+        assert!(!storage.exists(key).await.unwrap());
+        assert!(storage.read(key).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn azure_blob_presigned_url() {
+        let storage = storage();
+        storage.create_container().await.unwrap();
+
+        let key = &format!("{}-0.grid", Uuid::new_v4());
+        let data = &Bytes::from("Hello, world!");
+        storage.write(key, data).await.unwrap();
+
+        let url = storage
+            .presigned_url(key, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let response = reqwest::get(&url).await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(&response.bytes().await.unwrap(), data);
+
+        // the url can't be used to read another blob
+        let other = url.replace(key, &format!("{}-0.grid", Uuid::new_v4()));
+        let response = reqwest::get(&other).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        storage.delete(key).await.unwrap();
+    }
+}
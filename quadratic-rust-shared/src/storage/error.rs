@@ -6,9 +6,18 @@ pub enum Storage {
     #[error("Error creating directory {0}: {1}")]
     CreateDirectory(String, String),
 
+    #[error("Error deleting key {0}: {1}")]
+    Delete(String, String),
+
     #[error("Invalid key: {0}")]
     InvalidKey(String),
 
+    #[error("Error listing keys with prefix {0}: {1}")]
+    List(String, String),
+
+    #[error("Error presigning key {0}: {1}")]
+    Presigned(String, String),
+
     #[error("Error reading key {0}: {1}")]
     Read(String, String),
 
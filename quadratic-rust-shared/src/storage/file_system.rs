@@ -1,10 +1,13 @@
 use async_trait::async_trait;
 use bytes::Bytes;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-use tokio::fs::{create_dir_all, File};
+use std::time::Duration;
+use tokio::fs::{create_dir_all, read_dir, remove_file, try_exists, File};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use super::Storage;
+use crate::crypto::aes_cbc::encrypt_for_api;
 use crate::error::Result;
 use crate::storage::error::Storage as StorageError;
 use crate::SharedError;
@@ -52,6 +55,85 @@ impl Storage for FileSystem {
         Ok(())
     }
 
+    /// Delete the file from the file system.
+    async fn delete(&self, key: &str) -> Result<()> {
+        let file_path = self.full_path(key, false).await?.0;
+        remove_file(file_path)
+            .await
+            .map_err(|e| Self::delete_error(key, &e))?;
+
+        Ok(())
+    }
+
+    /// Check if the file exists in the file system.
+    async fn exists(&self, key: &str) -> Result<bool> {
+        let file_path = self.full_path(key, false).await?.0;
+        try_exists(file_path)
+            .await
+            .map_err(|e| Self::read_error(key, &e))
+    }
+
+    /// List the keys in the file system that start with the prefix.  Keys are
+    /// stored as `uuid/file_name`, so only matching directories are read.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        let mut dirs = match read_dir(self.path()).await {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(keys),
+            Err(e) => return Err(Self::list_error(prefix, &e)),
+        };
+
+        while let Some(dir) = dirs
+            .next_entry()
+            .await
+            .map_err(|e| Self::list_error(prefix, &e))?
+        {
+            let dir_name = dir.file_name().to_string_lossy().into_owned();
+            let is_dir = dir
+                .file_type()
+                .await
+                .is_ok_and(|file_type| file_type.is_dir());
+
+            if !is_dir || !(prefix.starts_with(&dir_name) || dir_name.starts_with(prefix)) {
+                continue;
+            }
+
+            let mut files = read_dir(dir.path())
+                .await
+                .map_err(|e| Self::list_error(prefix, &e))?;
+
+            while let Some(file) = files
+                .next_entry()
+                .await
+                .map_err(|e| Self::list_error(prefix, &e))?
+            {
+                let key = format!("{dir_name}-{}", file.file_name().to_string_lossy());
+
+                if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        keys.sort();
+
+        Ok(keys)
+    }
+
+    /// Return a url, relative to the files service, that serves the file
+    /// without authentication.  The key is encrypted with the first
+    /// encryption key.  The file system can't enforce an expiration.
+    async fn presigned_url(&self, key: &str, _expires_in: Duration) -> Result<String> {
+        let encryption_key = self
+            .config
+            .encryption_keys
+            .first()
+            .ok_or_else(|| Self::presigned_error(key, "No encryption keys found"))?;
+        let encrypted = encrypt_for_api(encryption_key, key)?;
+
+        Ok(format!("/storage/presigned/{encrypted}"))
+    }
+
     /// Return the path to the file system.
     fn path(&self) -> &str {
         &self.config.path
@@ -100,10 +182,11 @@ impl FileSystem {
 
 #[cfg(test)]
 mod tests {
-    use tokio::fs::{remove_dir, remove_file};
+    use tokio::fs::remove_dir;
     use uuid::Uuid;
 
     use super::*;
+    use crate::crypto::aes_cbc::decrypt_from_api;
     use std::env;
 
     fn config() -> FileSystemConfig {
@@ -132,4 +215,77 @@ mod tests {
 
         assert_eq!(data, &read_data);
     }
+
+    #[tokio::test]
+    async fn file_system_list() {
+        let config = config();
+        let storage = FileSystem { config };
+        let file_name = Uuid::new_v4().to_string();
+        let keys = (0..3)
+            .map(|sequence_number| format!("{}-{}.grid", file_name, sequence_number))
+            .collect::<Vec<_>>();
+
+        for key in keys.iter() {
+            storage.write(key, &Bytes::from("data")).await.unwrap();
+        }
+
+        let listed = storage.list(&format!("{file_name}-")).await.unwrap();
+        let listed_one = storage.list(&keys[1]).await.unwrap();
+        let listed_none = storage.list(&Uuid::new_v4().to_string()).await.unwrap();
+
+        // cleanup
+        for key in keys.iter() {
+            let (full_path, _) = storage.full_path(key, false).await.unwrap();
+            remove_file(full_path).await.unwrap();
+        }
+        let (_, dir) = storage.full_path(&keys[0], false).await.unwrap();
+        remove_dir(dir).await.unwrap();
+
+        assert_eq!(listed, keys);
+        assert_eq!(listed_one, vec![keys[1].to_owned()]);
+        assert!(listed_none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn file_system_exists_and_delete() {
+        let config = config();
+        let storage = FileSystem { config };
+        let key = &format!("{}-0.grid", Uuid::new_v4());
+
+        assert!(!storage.exists(key).await.unwrap());
+        storage.write(key, &Bytes::from("data")).await.unwrap();
+        assert!(storage.exists(key).await.unwrap());
+
+        storage.delete(key).await.unwrap();
+        assert!(!storage.exists(key).await.unwrap());
+        assert!(storage.delete(key).await.is_err());
+
+        // cleanup
+        let (_, dir) = storage.full_path(key, false).await.unwrap();
+        remove_dir(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_system_presigned_url() {
+        let encryption_key = hex::encode([0x42; 32]);
+        let storage = FileSystem {
+            config: FileSystemConfig {
+                encryption_keys: vec![encryption_key.to_owned()],
+                ..config()
+            },
+        };
+        let key = &format!("{}-0.grid", Uuid::new_v4());
+        let url = storage
+            .presigned_url(key, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let encrypted = url.strip_prefix("/storage/presigned/").unwrap();
+
+        assert_eq!(decrypt_from_api(&encryption_key, encrypted).unwrap(), *key);
+
+        // without an encryption key, the url can't be presigned
+        let storage = FileSystem { config: config() };
+        let url = storage.presigned_url(key, Duration::from_secs(60)).await;
+        assert!(url.is_err());
+    }
 }
@@ -1,24 +1,33 @@
 use async_trait::async_trait;
+use azure_blob::AzureBlobConfig;
 use bytes::Bytes;
 use file_system::FileSystemConfig;
+use gcs::GcsConfig;
 use s3::S3Config;
+use std::time::Duration;
 
 use crate::{error::Result, storage::error::Storage as StorageError, SharedError};
 
+pub mod azure_blob;
 pub mod error;
 pub mod file_system;
+pub mod gcs;
 pub mod s3;
 
 #[derive(Debug)]
 pub enum StorageConfig {
     S3(S3Config),
     FileSystem(FileSystemConfig),
+    AzureBlob(AzureBlobConfig),
+    Gcs(GcsConfig),
 }
 
 #[derive(Debug)]
 pub enum StorageContainer {
     S3(s3::S3),
     FileSystem(file_system::FileSystem),
+    AzureBlob(azure_blob::AzureBlob),
+    Gcs(gcs::Gcs),
 }
 
 #[async_trait]
@@ -27,6 +36,10 @@ pub trait Storage {
 
     async fn read(&self, key: &str) -> Result<Bytes>;
     async fn write<'a>(&self, key: &'a str, data: &'a Bytes) -> Result<()>;
+    async fn delete(&self, key: &str) -> Result<()>;
+    async fn exists(&self, key: &str) -> Result<bool>;
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String>;
     fn path(&self) -> &str;
     fn config(&self) -> Self::Config;
 
@@ -37,6 +50,30 @@ pub trait Storage {
     fn write_error(key: &str, e: impl ToString) -> SharedError {
         SharedError::Storage(StorageError::Write(key.into(), e.to_string()))
     }
+
+    fn delete_error(key: &str, e: impl ToString) -> SharedError {
+        SharedError::Storage(StorageError::Delete(key.into(), e.to_string()))
+    }
+
+    fn list_error(prefix: &str, e: impl ToString) -> SharedError {
+        SharedError::Storage(StorageError::List(prefix.into(), e.to_string()))
+    }
+
+    fn presigned_error(key: &str, e: impl ToString) -> SharedError {
+        SharedError::Storage(StorageError::Presigned(key.into(), e.to_string()))
+    }
+}
+
+/// Call the same method on whichever storage is in the container
+macro_rules! dispatch {
+    ($self:ident, $storage:ident => $call:expr) => {
+        match $self {
+            Self::S3($storage) => $call,
+            Self::FileSystem($storage) => $call,
+            Self::AzureBlob($storage) => $call,
+            Self::Gcs($storage) => $call,
+        }
+    };
 }
 
 // TODO(ddimaria): this is a temp hack to get around some trait issues, do something better
@@ -45,30 +82,39 @@ impl Storage for StorageContainer {
     type Config = StorageConfig;
 
     async fn read(&self, key: &str) -> Result<Bytes> {
-        match self {
-            Self::S3(s3) => s3.read(key).await,
-            Self::FileSystem(fs) => fs.read(key).await,
-        }
+        dispatch!(self, storage => storage.read(key).await)
     }
 
     async fn write<'a>(&self, key: &'a str, data: &'a Bytes) -> Result<()> {
-        match self {
-            Self::S3(s3) => s3.write(key, data).await,
-            Self::FileSystem(fs) => fs.write(key, data).await,
-        }
+        dispatch!(self, storage => storage.write(key, data).await)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        dispatch!(self, storage => storage.delete(key).await)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        dispatch!(self, storage => storage.exists(key).await)
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        dispatch!(self, storage => storage.list(prefix).await)
+    }
+
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        dispatch!(self, storage => storage.presigned_url(key, expires_in).await)
     }
 
     fn path(&self) -> &str {
-        match self {
-            Self::S3(s3) => s3.path(),
-            Self::FileSystem(fs) => fs.path(),
-        }
+        dispatch!(self, storage => storage.path())
     }
 
     fn config(&self) -> StorageConfig {
         match self {
             Self::S3(s3) => StorageConfig::S3(s3.config()),
             Self::FileSystem(fs) => StorageConfig::FileSystem(fs.config()),
+            Self::AzureBlob(azure) => StorageConfig::AzureBlob(azure.config()),
+            Self::Gcs(gcs) => StorageConfig::Gcs(gcs.config()),
         }
     }
 }
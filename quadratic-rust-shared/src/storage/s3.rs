@@ -1,10 +1,14 @@
 use async_trait::async_trait;
 use aws_sdk_s3::Client;
 use bytes::Bytes;
+use std::time::Duration;
 
 use super::Storage;
 use crate::{
-    aws::s3::{download_object, upload_object},
+    aws::s3::{
+        delete_object, download_object, list_objects, object_exists, presigned_object_url,
+        upload_object,
+    },
     error::Result,
 };
 
@@ -52,6 +56,45 @@ impl Storage for S3 {
         Ok(())
     }
 
+    /// Delete the file from the S3 bucket.
+    async fn delete(&self, key: &str) -> Result<()> {
+        let S3Config { client, bucket } = &self.config;
+
+        delete_object(client, bucket, key)
+            .await
+            .map_err(|e| Self::delete_error(key, &e))
+    }
+
+    /// Check if the file exists in the S3 bucket.
+    async fn exists(&self, key: &str) -> Result<bool> {
+        let S3Config { client, bucket } = &self.config;
+
+        object_exists(client, bucket, key)
+            .await
+            .map_err(|e| Self::read_error(key, &e))
+    }
+
+    /// Generate a presigned url to read the file from the S3 bucket.
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let S3Config { client, bucket } = &self.config;
+
+        presigned_object_url(client, bucket, key, expires_in)
+            .await
+            .map_err(|e| Self::presigned_error(key, &e))
+    }
+
+    /// List the keys in the S3 bucket that start with the prefix.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let S3Config { client, bucket } = &self.config;
+
+        let mut keys = list_objects(client, bucket, prefix)
+            .await
+            .map_err(|e| Self::list_error(prefix, &e))?;
+        keys.sort();
+
+        Ok(keys)
+    }
+
     /// Return the S3 bucket.
     fn path(&self) -> &str {
         &self.config.bucket